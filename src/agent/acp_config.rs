@@ -0,0 +1,74 @@
+//! Agent defaults from the `[acp]` section of `$CODEX_HOME/config.toml`.
+//!
+//! ```toml
+//! [acp]
+//! max_session_tokens = 2_000_000
+//!
+//! [acp.shell]
+//! login_shell = true
+//! env_file = "/home/me/.config/dev.env"
+//! ```
+//!
+//! Each key has an environment variable that takes precedence over it (see
+//! [`budget`](super::budget) and [`shell_env`](super::shell_env)); session
+//! meta overrides both. The file is read again for every new or resumed
+//! session. `[acp.logging]` is read by [`crate::logging`].
+
+use std::path::{Path, PathBuf};
+
+use toml_edit::{DocumentMut, Item};
+use tracing::warn;
+
+/// The settings from `[acp]`; `None` where a key is not set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AcpConfig {
+    pub max_session_tokens: Option<u64>,
+    pub login_shell: Option<bool>,
+    pub env_file: Option<PathBuf>,
+}
+
+impl AcpConfig {
+    /// Parse `[acp]` out of a whole `config.toml`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let doc: DocumentMut = text.parse().map_err(|err| format!("invalid TOML: {err}"))?;
+        let Some(section) = doc.get("acp").and_then(Item::as_table_like) else {
+            return Ok(Self::default());
+        };
+        let mut config = Self::default();
+        if let Some(item) = section.get("max_session_tokens") {
+            config.max_session_tokens = Some(
+                item.as_integer()
+                    .and_then(|cap| u64::try_from(cap).ok())
+                    .filter(|cap| *cap > 0)
+                    .ok_or("acp.max_session_tokens must be a positive integer")?,
+            );
+        }
+        if let Some(shell) = section.get("shell") {
+            let shell = shell.as_table_like().ok_or("acp.shell must be a table")?;
+            if let Some(item) = shell.get("login_shell") {
+                config.login_shell = Some(
+                    item.as_bool()
+                        .ok_or("acp.shell.login_shell must be a boolean")?,
+                );
+            }
+            if let Some(item) = shell.get("env_file") {
+                let path = item.as_str().ok_or("acp.shell.env_file must be a string")?;
+                config.env_file = (!path.trim().is_empty()).then(|| PathBuf::from(path));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Read `$CODEX_HOME/config.toml`; a missing file or an invalid section
+    /// gives the defaults.
+    pub async fn load(codex_home: &Path) -> Self {
+        let path = codex_home.join("config.toml");
+        let Ok(text) = tokio::fs::read_to_string(&path).await else {
+            return Self::default();
+        };
+        Self::parse(&text).unwrap_or_else(|err| {
+            warn!(path = %path.display(), error = %err, "ignoring [acp] settings");
+            Self::default()
+        })
+    }
+}
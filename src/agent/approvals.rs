@@ -0,0 +1,306 @@
+//! Batched approval of low-risk commands.
+//!
+//! With parallel tool calls the model often asks to run several similar,
+//! harmless commands at once (`cargo check`, `git diff`, `rg ...`). Instead of
+//! one modal per command, consecutive approval requests for low-risk commands
+//! are collected for a short window and shown as a single digest with
+//! approve-all / review-individually / reject-all options. A lone request is
+//! shown as usual. Set `CODEX_ACP_APPROVAL_DIGEST=0` to disable batching.
+
+use std::{path::PathBuf, time::Duration};
+
+use agent_client_protocol::{
+    Error, PermissionOption, PermissionOptionId, PermissionOptionKind, RequestPermissionOutcome,
+    RequestPermissionRequest, RequestPermissionResponse, SessionId,
+};
+use codex_core::{
+    CodexConversation,
+    protocol::{ExecApprovalRequestEvent, Op, ReviewDecision},
+};
+use codex_protocol::parse_command::ParsedCommand;
+use tokio::sync::oneshot;
+
+use super::{core::CodexAgent, events, interactive, terminal};
+use crate::fs::ClientOp;
+
+/// How long to wait for further approval requests before showing a digest.
+pub const DIGEST_WINDOW: Duration = Duration::from_millis(300);
+
+/// Environment variable disabling approval digests (`0`/`false`).
+pub const APPROVAL_DIGEST_ENV: &str = "CODEX_ACP_APPROVAL_DIGEST";
+
+/// Whether approval requests may be batched into digests.
+pub fn digest_enabled() -> bool {
+    std::env::var(APPROVAL_DIGEST_ENV)
+        .map(|v| !matches!(v.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no"))
+        .unwrap_or(true)
+}
+
+/// Whether `command` only inspects or builds the workspace and may be batched.
+///
+/// Shell scripts with pipes, redirections, or command chaining never qualify.
+pub fn is_low_risk(command: &[String]) -> bool {
+    let words = interactive::command_words(command);
+    if words
+        .iter()
+        .any(|w| w.contains(['|', '&', ';', '>', '<', '`', '$']))
+    {
+        return false;
+    }
+    let Some(program) = words.first().and_then(|p| p.rsplit('/').next()) else {
+        return false;
+    };
+    let sub = words.get(1).map(String::as_str);
+    match program {
+        "ls" | "pwd" | "cat" | "head" | "tail" | "wc" | "rg" | "grep" | "tree" | "which" => true,
+        "find" => !words.iter().any(|w| {
+            matches!(
+                w.as_str(),
+                "-delete" | "-exec" | "-execdir" | "-ok" | "-okdir"
+            )
+        }),
+        "cargo" => matches!(
+            sub,
+            Some("check" | "build" | "test" | "clippy" | "metadata" | "tree")
+        ),
+        "git" => matches!(
+            sub,
+            Some("status" | "diff" | "log" | "show" | "blame" | "rev-parse")
+        ),
+        "go" => matches!(sub, Some("build" | "test" | "vet")),
+        "npm" | "pnpm" | "yarn" => matches!(sub, Some("test" | "run")),
+        _ => false,
+    }
+}
+
+/// An exec approval request waiting to be shown to the user.
+#[derive(Debug, Clone)]
+pub struct PendingExecApproval {
+    /// Codex event id the decision is submitted for.
+    pub event_id: String,
+    pub call_id: String,
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    pub parsed_cmd: Vec<ParsedCommand>,
+}
+
+impl PendingExecApproval {
+    pub fn new(event_id: String, req: ExecApprovalRequestEvent) -> Self {
+        Self {
+            event_id,
+            call_id: req.call_id,
+            command: req.command,
+            cwd: req.cwd,
+            parsed_cmd: req.parsed_cmd,
+        }
+    }
+
+    /// The command as shown in a digest.
+    pub fn display(&self) -> String {
+        interactive::command_words(&self.command).join(" ")
+    }
+}
+
+/// The user's answer to a digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestOutcome {
+    ApproveAll,
+    ApproveAllForSession,
+    Individually,
+    RejectAll,
+}
+
+/// Options offered for a digest.
+pub fn digest_options() -> Vec<PermissionOption> {
+    let option = |id: &str, name: &str, kind| PermissionOption {
+        id: PermissionOptionId(id.into()),
+        name: name.into(),
+        kind,
+        meta: None,
+    };
+    vec![
+        option(
+            "approve-all",
+            "Approve all",
+            PermissionOptionKind::AllowOnce,
+        ),
+        option(
+            "approve-all-for-session",
+            "Approve all always",
+            PermissionOptionKind::AllowAlways,
+        ),
+        // Picking individually approves nothing by itself, so it is not an "allow" kind.
+        option(
+            "review-individually",
+            "Review individually",
+            PermissionOptionKind::RejectOnce,
+        ),
+        option("reject-all", "Reject all", PermissionOptionKind::RejectOnce),
+    ]
+}
+
+/// Map the client's answer to a digest outcome; cancellation rejects everything.
+pub fn digest_outcome(resp: RequestPermissionResponse) -> DigestOutcome {
+    match resp.outcome {
+        RequestPermissionOutcome::Selected { option_id } => match option_id.0.as_ref() {
+            "approve-all" => DigestOutcome::ApproveAll,
+            "approve-all-for-session" => DigestOutcome::ApproveAllForSession,
+            "review-individually" => DigestOutcome::Individually,
+            _ => DigestOutcome::RejectAll,
+        },
+        RequestPermissionOutcome::Cancelled => DigestOutcome::RejectAll,
+    }
+}
+
+impl CodexAgent {
+    /// Ask the client to approve one command and forward the decision to Codex.
+    pub(super) async fn request_exec_approval(
+        &self,
+        session_id: &SessionId,
+        conversation: &CodexConversation,
+        event_handler: &events::EventHandler,
+        pending: &PendingExecApproval,
+    ) -> Result<(), Error> {
+        let permission_req = event_handler.on_exec_approval_request(
+            session_id,
+            &pending.call_id,
+            &pending.command,
+            &pending.cwd,
+            &pending.parsed_cmd,
+        );
+        let outcome = self
+            .request_turn_permission(session_id, permission_req)
+            .await?;
+        if let Ok(resp) = outcome {
+            let decision = events::handle_response_outcome(resp);
+            self.submit_exec_decision(session_id, conversation, pending, decision)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Ask the client for a permission on behalf of the running turn.
+    ///
+    /// A `session/cancel` arriving while the request is pending resolves it as
+    /// `Cancelled`, so the turn can decline and end without waiting for a
+    /// client that never answers.
+    pub(super) async fn request_turn_permission(
+        &self,
+        session_id: &SessionId,
+        request: RequestPermissionRequest,
+    ) -> Result<Result<RequestPermissionResponse, Error>, Error> {
+        let cancel = self
+            .with_session_state_mut(session_id, |state| state.turn_cancel.clone())
+            .unwrap_or_default();
+        let (txp, rxp) = oneshot::channel();
+        let _ = self.client_tx.send(ClientOp::RequestPermission {
+            session_id: session_id.clone(),
+            request,
+            response_tx: txp,
+        });
+        tokio::select! {
+            outcome = rxp => outcome.map_err(|_| Error::internal_error()),
+            () = cancel.cancelled() => Ok(Ok(RequestPermissionResponse {
+                outcome: RequestPermissionOutcome::Cancelled,
+                meta: None,
+            })),
+        }
+    }
+
+    /// Whether `command` was approved for the session and runs in client terminals.
+    ///
+    /// Codex never sees those approvals, so the agent remembers them itself.
+    pub(super) fn is_terminal_approved(&self, session_id: &SessionId, command: &[String]) -> bool {
+        self.with_session_state_mut(session_id, |state| {
+            state.terminal_approved.contains(command)
+        })
+        .unwrap_or(false)
+    }
+
+    /// Resolve collected approval requests, as a digest when there are several.
+    pub(super) async fn resolve_exec_approvals(
+        &self,
+        session_id: &SessionId,
+        conversation: &CodexConversation,
+        event_handler: &events::EventHandler,
+        batch: Vec<PendingExecApproval>,
+    ) -> Result<(), Error> {
+        if let [single] = batch.as_slice() {
+            return self
+                .request_exec_approval(session_id, conversation, event_handler, single)
+                .await;
+        }
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let permission_req = event_handler.on_exec_approval_digest(session_id, &batch);
+        let outcome = match self
+            .request_turn_permission(session_id, permission_req)
+            .await?
+        {
+            Ok(resp) => digest_outcome(resp),
+            Err(_) => DigestOutcome::RejectAll,
+        };
+
+        for pending in &batch {
+            let decision = match outcome {
+                DigestOutcome::ApproveAll => ReviewDecision::Approved,
+                DigestOutcome::ApproveAllForSession => ReviewDecision::ApprovedForSession,
+                DigestOutcome::RejectAll => ReviewDecision::Abort,
+                DigestOutcome::Individually => {
+                    self.request_exec_approval(session_id, conversation, event_handler, pending)
+                        .await?;
+                    continue;
+                }
+            };
+            self.submit_exec_decision(session_id, conversation, pending, decision)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Forward a decision to Codex, running approved commands in a client
+    /// terminal when the client supports it.
+    pub(super) async fn submit_exec_decision(
+        &self,
+        session_id: &SessionId,
+        conversation: &CodexConversation,
+        pending: &PendingExecApproval,
+        decision: ReviewDecision,
+    ) -> Result<(), Error> {
+        self.audit_exec_decision(session_id, &pending.command, &pending.cwd, &decision);
+        let approved = matches!(
+            decision,
+            ReviewDecision::Approved | ReviewDecision::ApprovedForSession
+        );
+        if approved && let Some(why) = interactive::interactive_reason(&pending.command) {
+            if matches!(decision, ReviewDecision::ApprovedForSession) && self.support_terminal() {
+                self.with_session_state_mut(session_id, |state| {
+                    state.terminal_approved.insert(pending.command.clone());
+                });
+            }
+            return self
+                .run_interactive_exec(session_id, conversation, pending, why)
+                .await;
+        }
+        if approved && self.support_terminal() && terminal::terminal_exec_enabled() {
+            if matches!(decision, ReviewDecision::ApprovedForSession) {
+                self.with_session_state_mut(session_id, |state| {
+                    state.terminal_approved.insert(pending.command.clone());
+                });
+            }
+            return self
+                .run_approved_in_terminal(session_id, conversation, pending, decision)
+                .await;
+        }
+        conversation
+            .submit(Op::ExecApproval {
+                id: pending.event_id.clone(),
+                decision,
+            })
+            .await
+            .map_err(Error::into_internal_error)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+//! Recording the agent's own actions in the audit log.
+//!
+//! The log itself, which the `acp_fs` bridge writes to as well, is
+//! [`crate::fs::tools::audit`].
+
+use std::path::{Path, PathBuf};
+
+use agent_client_protocol::{Error, SessionId};
+use codex_core::protocol::ReviewDecision;
+use serde_json::{Value, json};
+
+use super::{core::CodexAgent, redact};
+use crate::fs::tools::audit::{AuditEntry, AuditEvent, verify_chain};
+
+/// An entry for `command` run in `cwd`, with secrets redacted.
+pub fn command_entry(
+    session_id: impl Into<String>,
+    event: AuditEvent,
+    command: &[String],
+    cwd: &Path,
+) -> AuditEntry {
+    let mut entry = AuditEntry::new(session_id, event);
+    entry.command = Some(redact::redact_command(command).join(" "));
+    entry.cwd = Some(cwd.to_path_buf());
+    entry
+}
+
+/// Label of a review decision, as recorded in `decision`.
+pub fn decision_label(decision: &ReviewDecision) -> &'static str {
+    match decision {
+        ReviewDecision::Approved => "approved",
+        ReviewDecision::ApprovedForSession => "approved_for_session",
+        ReviewDecision::Denied => "denied",
+        ReviewDecision::Abort => "aborted",
+    }
+}
+
+impl CodexAgent {
+    /// Record the answer to a command approval.
+    pub(super) fn audit_exec_decision(
+        &self,
+        session_id: &SessionId,
+        command: &[String],
+        cwd: &Path,
+        decision: &ReviewDecision,
+    ) {
+        self.audit.record(
+            command_entry(
+                session_id.0.to_string(),
+                AuditEvent::ExecDecision,
+                command,
+                cwd,
+            )
+            .with_decision(decision_label(decision)),
+        );
+    }
+
+    /// The `codex/audit` response: the session's entries, plus whether the
+    /// chain of the whole log verifies.
+    pub(super) async fn audit_trail(&self, session_id: &SessionId) -> Result<Value, Error> {
+        let entries = self
+            .audit
+            .entries()
+            .await
+            .map_err(Error::into_internal_error)?;
+        let broken_at = verify_chain(&entries);
+        let session: Vec<&AuditEntry> = entries
+            .iter()
+            .filter(|entry| entry.session_id == session_id.0.as_ref())
+            .collect();
+        Ok(json!({
+            "sessionId": session_id,
+            "enabled": self.audit.is_enabled(),
+            "intact": broken_at.is_none(),
+            "brokenAt": broken_at,
+            "entries": session,
+        }))
+    }
+
+    /// Record a patch approval (`PatchDecision`) or application (`Patch`).
+    pub(super) fn audit_patch(
+        &self,
+        session_id: &SessionId,
+        event: AuditEvent,
+        paths: Vec<PathBuf>,
+        decision: &str,
+    ) {
+        let mut entry = AuditEntry::new(session_id.0.to_string(), event).with_decision(decision);
+        entry.paths = paths;
+        self.audit.record(entry);
+    }
+}
@@ -0,0 +1,51 @@
+//! Session updates the client missed.
+//!
+//! When a session notification cannot be delivered, for example while the
+//! editor reloads, it is kept in the session's [`Backlog`] instead of being
+//! lost. The next `session/load` of that session replays the backlog after
+//! the transcript, each update marked with `_meta.backfill: true` so clients
+//! can tell it from live output. Only the newest [`MAX_BACKFILL`] updates of a
+//! session are kept.
+
+use std::collections::VecDeque;
+
+use agent_client_protocol::SessionNotification;
+use serde_json::{Value, json};
+
+/// Undelivered updates kept per session.
+pub const MAX_BACKFILL: usize = 500;
+
+#[derive(Debug, Clone, Default)]
+pub struct Backlog {
+    pending: VecDeque<SessionNotification>,
+    /// Updates dropped because the backlog was full.
+    dropped: usize,
+}
+
+impl Backlog {
+    pub fn push(&mut self, notification: SessionNotification) {
+        if self.pending.len() == MAX_BACKFILL {
+            self.pending.pop_front();
+            self.dropped += 1;
+        }
+        self.pending.push_back(notification);
+    }
+
+    /// Take the kept updates, oldest first, and how many were dropped.
+    pub fn take(&mut self) -> (Vec<SessionNotification>, usize) {
+        let dropped = std::mem::take(&mut self.dropped);
+        (self.pending.drain(..).collect(), dropped)
+    }
+}
+
+/// `notification` with `_meta.backfill` set, keeping any other meta.
+pub fn mark_backfill(mut notification: SessionNotification) -> SessionNotification {
+    notification.meta = Some(match notification.meta.take() {
+        Some(Value::Object(mut meta)) => {
+            meta.insert("backfill".to_string(), Value::Bool(true));
+            Value::Object(meta)
+        }
+        _ => json!({ "backfill": true }),
+    });
+    notification
+}
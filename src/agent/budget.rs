@@ -0,0 +1,126 @@
+//! Hard cap on the tokens a session may consume.
+//!
+//! A forgotten editor session can quietly drain a shared API key. When a cap
+//! is configured and the session's cumulative token usage reaches it, further
+//! prompts fail with [`TOKEN_CAP_ERROR_CODE`] until the user lifts or raises
+//! the cap with `/override`.
+//!
+//! Sources, highest priority first:
+//! - `NewSessionRequest.meta.maxSessionTokens`;
+//! - the `CODEX_ACP_MAX_SESSION_TOKENS` environment variable;
+//! - `max_session_tokens` in the `[acp]` section of `config.toml`.
+
+use agent_client_protocol::{Error, SessionId};
+use serde_json::{Value, json};
+
+use super::{acp_config::AcpConfig, core::CodexAgent};
+
+/// JSON-RPC error code returned when a session has used up its token cap.
+pub const TOKEN_CAP_ERROR_CODE: i32 = -32052;
+
+/// Environment variable with the default per-session token cap.
+pub const MAX_SESSION_TOKENS_ENV: &str = "CODEX_ACP_MAX_SESSION_TOKENS";
+
+/// The cap for new sessions from `CODEX_ACP_MAX_SESSION_TOKENS`, or else
+/// from `[acp] max_session_tokens`.
+pub fn default_max_session_tokens(config: &AcpConfig) -> Option<u64> {
+    std::env::var(MAX_SESSION_TOKENS_ENV)
+        .ok()
+        .and_then(|v| v.trim().replace('_', "").parse().ok())
+        .filter(|cap| *cap > 0)
+        .or(config.max_session_tokens)
+}
+
+/// Read `NewSessionRequest.meta.maxSessionTokens`, if present.
+pub fn max_session_tokens_from_meta(meta: Option<&Value>) -> Result<Option<u64>, Error> {
+    match meta.and_then(|m| m.get("maxSessionTokens")) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .filter(|cap| *cap > 0)
+            .map(Some)
+            .ok_or_else(|| {
+                Error::invalid_params().with_data("maxSessionTokens must be a positive integer")
+            }),
+    }
+}
+
+/// The error returned for prompts beyond the cap.
+pub fn token_cap_error(used: u64, limit: u64) -> Error {
+    Error {
+        code: TOKEN_CAP_ERROR_CODE,
+        message: "Session token limit reached".to_string(),
+        data: Some(json!({
+            "used": used,
+            "limit": limit,
+            "guidance": "Run /override to lift the limit for this session, /override <tokens> to allow more, or start a new session.",
+        })),
+    }
+}
+
+/// Parsed `/override` argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapOverride {
+    /// Remove the cap for this session.
+    Lift,
+    /// Allow this many tokens beyond current usage.
+    Extend(u64),
+}
+
+/// Parse `/override` arguments: empty, `off`, or a token count such as `50000`.
+pub fn parse_override(args: &str) -> Result<CapOverride, String> {
+    match args.trim() {
+        "" | "off" => Ok(CapOverride::Lift),
+        n => n
+            .replace('_', "")
+            .parse::<u64>()
+            .ok()
+            .filter(|n| *n > 0)
+            .map(CapOverride::Extend)
+            .ok_or_else(|| "Usage: /override [off|<tokens>]\n".to_string()),
+    }
+}
+
+impl CodexAgent {
+    /// Fail if the session has reached its token cap.
+    pub(super) fn check_token_cap(&self, session_id: &SessionId) -> Result<(), Error> {
+        let exceeded = self
+            .with_session_state_mut(session_id, |state| {
+                let used = state.token_usage.as_ref().map_or(0, |u| u.total_tokens);
+                state
+                    .max_session_tokens
+                    .filter(|limit| used >= *limit)
+                    .map(|limit| (used, limit))
+            })
+            .flatten();
+        match exceeded {
+            Some((used, limit)) => Err(token_cap_error(used, limit)),
+            None => Ok(()),
+        }
+    }
+
+    /// Apply `/override` to the session's token cap and describe the result.
+    pub(super) fn override_token_cap(&self, session_id: &SessionId, args: &str) -> String {
+        let parsed = match parse_override(args) {
+            Ok(parsed) => parsed,
+            Err(usage) => return usage,
+        };
+        let result = self.with_session_state_mut(session_id, |state| {
+            let used = state.token_usage.as_ref().map_or(0, |u| u.total_tokens);
+            state.max_session_tokens = match parsed {
+                CapOverride::Lift => None,
+                CapOverride::Extend(more) => Some(used.saturating_add(more)),
+            };
+            (used, state.max_session_tokens)
+        });
+        match result {
+            Some((used, None)) => {
+                format!("🔓 Token limit lifted for this session ({used} tokens used).\n\n")
+            }
+            Some((used, Some(limit))) => {
+                format!("🔓 Token limit raised to {limit} ({used} tokens used).\n\n")
+            }
+            None => "Session not found.\n".to_string(),
+        }
+    }
+}
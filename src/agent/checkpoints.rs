@@ -0,0 +1,401 @@
+//! Git checkpoints of the workspace, per session.
+//!
+//! With `CODEX_ACP_CHECKPOINTS=1`, the workspace is committed to the shadow
+//! ref `refs/codex/checkpoints/<session>` when a session's first turn starts
+//! and after every turn that changed files. Checkpoints are written through a
+//! private index, so the user's index, `HEAD` and branches are never touched;
+//! untracked files are included unless ignored. `/checkpoint [note]` takes
+//! one by hand in any git workspace, `/rollback` lists them, and
+//! `/rollback <n>` puts the workspace back to checkpoint `n` after
+//! checkpointing the current state, so a rollback can be rolled back too.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use agent_client_protocol::SessionId;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::{context::ContextItem, core::CodexAgent, turn_changes::ChangeSummary};
+
+/// Environment variable enabling automatic checkpoints (`1`, `true`, `yes`).
+pub const CHECKPOINTS_ENV: &str = "CODEX_ACP_CHECKPOINTS";
+
+/// Namespace of the shadow refs.
+pub const CHECKPOINT_REF_PREFIX: &str = "refs/codex/checkpoints/";
+
+pub fn enabled_from_env() -> bool {
+    std::env::var(CHECKPOINTS_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// The shadow ref of a session; characters git does not allow in ref names
+/// become `-`.
+pub fn checkpoint_ref(session_id: &str) -> String {
+    let name: String = session_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("{CHECKPOINT_REF_PREFIX}{name}")
+}
+
+/// One checkpoint of a session, numbered from 1 (the oldest).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub number: usize,
+    pub commit: String,
+    /// When it was taken, as git puts it (`5 minutes ago`).
+    pub age: String,
+    pub message: String,
+}
+
+/// Checkpoints from `git log --format=%H%x09%cr%x09%s` output (newest first).
+pub fn parse_log(text: &str) -> Vec<Checkpoint> {
+    let mut checkpoints: Vec<Checkpoint> = text
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            Some(Checkpoint {
+                number: 0,
+                commit: fields.next()?.to_string(),
+                age: fields.next()?.to_string(),
+                message: fields.next().unwrap_or_default().to_string(),
+            })
+        })
+        .collect();
+    checkpoints.reverse();
+    for (index, checkpoint) in checkpoints.iter_mut().enumerate() {
+        checkpoint.number = index + 1;
+    }
+    checkpoints
+}
+
+/// Files to check out and to delete, from `git diff --name-status -z`
+/// between the current state and the target.
+pub fn parse_name_status(text: &str) -> (Vec<String>, Vec<String>) {
+    let mut checkout = Vec::new();
+    let mut delete = Vec::new();
+    let mut fields = text.split('\0').filter(|field| !field.is_empty());
+    while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+        if status.starts_with('D') {
+            delete.push(path.to_string());
+        } else {
+            checkout.push(path.to_string());
+        }
+    }
+    (checkout, delete)
+}
+
+/// `/rollback` lists checkpoints; `/rollback <n>` restores checkpoint `n`.
+pub fn parse_rollback_args(args: &str) -> Result<Option<usize>, String> {
+    let args = args.trim();
+    if args.is_empty() {
+        return Ok(None);
+    }
+    match args.trim_start_matches('#').parse::<usize>() {
+        Ok(number) if number > 0 => Ok(Some(number)),
+        _ => Err("Usage: /rollback [<n>]\n".to_string()),
+    }
+}
+
+/// What a rollback changed, relative to the repository root.
+#[derive(Debug, Default)]
+pub struct Rollback {
+    pub restored: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
+/// A git repository with a private index for checkpointing.
+struct Repo {
+    root: PathBuf,
+    index: PathBuf,
+}
+
+impl Drop for Repo {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.index);
+    }
+}
+
+impl Repo {
+    async fn open(cwd: &Path) -> Result<Self, String> {
+        let root = PathBuf::from(
+            git(cwd, None, &["rev-parse", "--show-toplevel"])
+                .await?
+                .trim(),
+        );
+        let git_dir = git(&root, None, &["rev-parse", "--absolute-git-dir"]).await?;
+        let index =
+            Path::new(git_dir.trim()).join(format!("codex-checkpoint-{}.index", Uuid::new_v4()));
+        Ok(Self { root, index })
+    }
+
+    async fn git(&self, args: &[&str]) -> Result<String, String> {
+        git(&self.root, Some(&self.index), args).await
+    }
+
+    /// The tree of the workspace as it is now.
+    async fn snapshot(&self) -> Result<String, String> {
+        // Starting from HEAD spares rehashing unchanged files.
+        if self.git(&["read-tree", "HEAD"]).await.is_err() {
+            self.git(&["read-tree", "--empty"]).await?;
+        }
+        self.git(&["add", "-A"]).await?;
+        Ok(self.git(&["write-tree"]).await?.trim().to_string())
+    }
+
+    async fn head_of(&self, refname: &str) -> Option<String> {
+        let spec = format!("{refname}^{{commit}}");
+        let commit = self
+            .git(&["rev-parse", "--verify", "-q", &spec])
+            .await
+            .ok()?;
+        Some(commit.trim().to_string())
+    }
+
+    /// Commit `tree` onto `refname`. `None` when it matches the newest
+    /// checkpoint.
+    async fn commit(
+        &self,
+        refname: &str,
+        tree: &str,
+        message: &str,
+    ) -> Result<Option<String>, String> {
+        let parent = self.head_of(refname).await;
+        let mut args = vec!["commit-tree", tree, "-m", message];
+        if let Some(parent) = &parent {
+            let parent_tree = self
+                .git(&["rev-parse", &format!("{parent}^{{tree}}")])
+                .await?;
+            if parent_tree.trim() == tree {
+                return Ok(None);
+            }
+            args.extend(["-p", parent.as_str()]);
+        }
+        let commit = self.git(&args).await?.trim().to_string();
+        let mut update = vec!["update-ref", "-m", message, refname, commit.as_str()];
+        // Fails if another checkpoint moved the ref meanwhile.
+        update.push(parent.as_deref().unwrap_or(""));
+        self.git(&update).await?;
+        Ok(Some(commit))
+    }
+
+    async fn list(&self, refname: &str) -> Vec<Checkpoint> {
+        match self
+            .git(&["log", "--format=%H%x09%cr%x09%s", refname, "--"])
+            .await
+        {
+            Ok(text) => parse_log(&text),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Make the workspace match `tree` of `commit`, given that it is at
+    /// `current` now.
+    async fn restore(&self, current: &str, commit: &str) -> Result<Rollback, String> {
+        let diff = self
+            .git(&[
+                "diff",
+                "--name-status",
+                "--no-renames",
+                "-z",
+                current,
+                commit,
+            ])
+            .await?;
+        let (restored, deleted) = parse_name_status(&diff);
+        if !restored.is_empty() {
+            self.git(&["read-tree", commit]).await?;
+            let mut args = vec!["checkout-index", "-f", "--"];
+            args.extend(restored.iter().map(String::as_str));
+            self.git(&args).await?;
+        }
+        for path in &deleted {
+            match tokio::fs::remove_file(self.root.join(path)).await {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                    return Err(format!("{path}: {err}"));
+                }
+                _ => {}
+            }
+        }
+        Ok(Rollback { restored, deleted })
+    }
+}
+
+/// Run git in `dir`, with `index` as the index file if given.
+async fn git(dir: &Path, index: Option<&Path>, args: &[&str]) -> Result<String, String> {
+    let mut command = tokio::process::Command::new("git");
+    command
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        // Checkpoints must not depend on the user's identity being set up.
+        .env("GIT_AUTHOR_NAME", "codex-acp")
+        .env("GIT_AUTHOR_EMAIL", "codex-acp@localhost")
+        .env("GIT_COMMITTER_NAME", "codex-acp")
+        .env("GIT_COMMITTER_EMAIL", "codex-acp@localhost");
+    if let Some(index) = index {
+        command.env("GIT_INDEX_FILE", index);
+    }
+    let output = command.output().await.map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+impl CodexAgent {
+    /// Commit the workspace to the session's shadow ref. The number of the
+    /// new checkpoint, or `None` when nothing changed since the last one.
+    async fn checkpoint(
+        &self,
+        session_id: &SessionId,
+        message: &str,
+    ) -> Result<Option<usize>, String> {
+        let refname = checkpoint_ref(&session_id.0);
+        let repo = Repo::open(&self.config.cwd).await?;
+        let tree = repo.snapshot().await?;
+        Ok(match repo.commit(&refname, &tree, message).await? {
+            Some(_) => Some(repo.list(&refname).await.len()),
+            None => None,
+        })
+    }
+
+    /// Checkpoint the workspace before a session's first turn, if enabled.
+    pub(super) async fn checkpoint_session_start(&self, session_id: &SessionId) {
+        if !enabled_from_env() {
+            return;
+        }
+        let refname = checkpoint_ref(&session_id.0);
+        let result = match Repo::open(&self.config.cwd).await {
+            Ok(repo) if repo.head_of(&refname).await.is_some() => return,
+            Ok(repo) => match repo.snapshot().await {
+                Ok(tree) => repo.commit(&refname, &tree, "Session start").await,
+                Err(err) => Err(err),
+            },
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            warn!(session_id = %session_id.0, error = %err, "failed to checkpoint the workspace");
+        }
+    }
+
+    /// Checkpoint the workspace after a turn that changed files, if enabled.
+    pub(super) async fn checkpoint_turn(&self, session_id: &SessionId, summary: &ChangeSummary) {
+        let changed = summary.added.len() + summary.modified.len() + summary.deleted.len();
+        if !enabled_from_env() || changed == 0 {
+            return;
+        }
+        let message = format!(
+            "After turn: {} added, {} modified, {} deleted",
+            summary.added.len(),
+            summary.modified.len(),
+            summary.deleted.len()
+        );
+        if let Err(err) = self.checkpoint(session_id, &message).await {
+            warn!(session_id = %session_id.0, error = %err, "failed to checkpoint the workspace");
+        }
+    }
+
+    /// `/checkpoint [note]`: checkpoint the workspace now.
+    pub(super) async fn checkpoint_command(&self, session_id: &SessionId, args: &str) -> String {
+        let note = args.trim();
+        let message = if note.is_empty() {
+            "Manual checkpoint"
+        } else {
+            note
+        };
+        match self.checkpoint(session_id, message).await {
+            Ok(Some(number)) => {
+                format!("📍 Saved checkpoint #{number}. Send /rollback {number} to return to it.\n")
+            }
+            Ok(None) => "📍 Nothing changed since the last checkpoint.\n".to_string(),
+            Err(err) => format!("⚠️ Cannot checkpoint the workspace: {err}\n"),
+        }
+    }
+
+    /// `/rollback [<n>]`: list checkpoints, or restore one.
+    pub(super) async fn rollback_command(&self, session_id: &SessionId, args: &str) -> String {
+        let number = match parse_rollback_args(args) {
+            Ok(number) => number,
+            Err(usage) => return usage,
+        };
+        let refname = checkpoint_ref(&session_id.0);
+        let repo = match Repo::open(&self.config.cwd).await {
+            Ok(repo) => repo,
+            Err(err) => return format!("⚠️ Checkpoints need a git workspace: {err}\n"),
+        };
+        let checkpoints = repo.list(&refname).await;
+        let Some(number) = number else {
+            if checkpoints.is_empty() {
+                return "No checkpoints yet. Send /checkpoint to take one.\n".to_string();
+            }
+            let mut text = String::from("Checkpoints:\n");
+            for checkpoint in checkpoints.iter().rev() {
+                text.push_str(&format!(
+                    "  #{} {} ({})\n",
+                    checkpoint.number, checkpoint.message, checkpoint.age
+                ));
+            }
+            text.push_str("Send /rollback <n> to return to one.\n");
+            return text;
+        };
+        let Some(target) = checkpoints.get(number - 1) else {
+            return format!("⚠️ There is no checkpoint #{number}.\n");
+        };
+
+        let result = async {
+            let current = repo.snapshot().await?;
+            let saved = repo
+                .commit(&refname, &current, &format!("Before rollback to #{number}"))
+                .await?;
+            let rollback = repo.restore(&current, &target.commit).await?;
+            Ok::<_, String>((saved, rollback))
+        }
+        .await;
+        let (saved, rollback) = match result {
+            Ok(done) => done,
+            Err(err) => return format!("⚠️ Rollback failed: {err}\n"),
+        };
+
+        let changed: Vec<String> = rollback
+            .restored
+            .iter()
+            .chain(&rollback.deleted)
+            .cloned()
+            .collect();
+        if !changed.is_empty() {
+            let note = ContextItem::Text {
+                text: format!(
+                    "The user rolled the workspace back to an earlier checkpoint; these files \
+                     changed: {}",
+                    changed.join(", ")
+                ),
+                label: Some("rollback".to_string()),
+            };
+            self.with_session_state_mut(session_id, |state| state.pending_context.push(note));
+        }
+        let mut text = format!(
+            "⏪ Rolled back to checkpoint #{number} ({}): {} file(s) restored, {} deleted.\n",
+            target.message,
+            rollback.restored.len(),
+            rollback.deleted.len()
+        );
+        if saved.is_some() {
+            text.push_str(&format!(
+                "The state before the rollback was saved as checkpoint #{}.\n",
+                checkpoints.len() + 1
+            ));
+        }
+        text
+    }
+}
@@ -0,0 +1,74 @@
+//! Commands executed in a session, for the model to look back at.
+//!
+//! Every finished command (Codex exec calls and approved commands run in
+//! client terminals) is recorded in session state with its exit code,
+//! duration, and the tail of its output, and journaled with the turn that ran
+//! it, with secrets redacted. `session/load` restores the history from the
+//! journal. The `acp_fs` `command_history` tool lists it so the model can
+//! avoid repeating failed commands and refer to earlier output without
+//! re-running them.
+
+use std::path::PathBuf;
+
+use agent_client_protocol::SessionId;
+
+use super::{core::CodexAgent, interactive, redact, store::JournalEntry};
+use crate::fs::{text, tools::command_history::CommandRecord};
+
+/// Commands kept in memory per session.
+pub const MAX_COMMAND_HISTORY: usize = 200;
+
+/// Bytes of output kept per recorded command.
+pub const MAX_RECORDED_OUTPUT: usize = 2 * 1024;
+
+/// A record of a finished command, with secrets redacted and the output
+/// cut to its tail.
+pub fn new_record(
+    command: &[String],
+    cwd: PathBuf,
+    exit_code: i32,
+    duration_ms: u64,
+    output: &str,
+) -> CommandRecord {
+    let output = text::elide_head(&redact::redact(output.trim_end()), MAX_RECORDED_OUTPUT);
+    CommandRecord {
+        command: redact::redact(&interactive::command_words(command).join(" ")),
+        cwd,
+        exit_code,
+        duration_ms,
+        output,
+    }
+}
+
+/// Commands recorded in a session journal, oldest first.
+pub fn history_from_journal(entries: Vec<JournalEntry>) -> Vec<CommandRecord> {
+    let mut records: Vec<CommandRecord> = entries
+        .into_iter()
+        .flat_map(|entry| entry.commands)
+        .collect();
+    let excess = records.len().saturating_sub(MAX_COMMAND_HISTORY);
+    records.drain(..excess);
+    records
+}
+
+impl CodexAgent {
+    /// Remember a finished command in the session's history.
+    pub(super) fn record_command(&self, session_id: &SessionId, record: CommandRecord) {
+        self.with_session_state_mut(session_id, |state| {
+            state.turn_changes.record_command(&record);
+            state.commands.push(record);
+            state.unjournaled_commands += 1;
+            let excess = state.commands.len().saturating_sub(MAX_COMMAND_HISTORY);
+            state.commands.drain(..excess);
+        });
+    }
+
+    /// Take the commands recorded since the last journaled turn.
+    pub(super) fn take_unjournaled_commands(&self, session_id: &SessionId) -> Vec<CommandRecord> {
+        self.with_session_state_mut(session_id, |state| {
+            let count = std::mem::take(&mut state.unjournaled_commands).min(state.commands.len());
+            state.commands[state.commands.len() - count..].to_vec()
+        })
+        .unwrap_or_default()
+    }
+}
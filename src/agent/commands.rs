@@ -1,14 +1,37 @@
 use std::{path::Path, sync::LazyLock};
 
+use super::{
+    acp_config::AcpConfig,
+    budget, continuation,
+    custom_prompts::{self, CustomPrompt},
+    instructions::{self, InstructionsCommand},
+    language,
+    mcp_commands::{self, McpServerCommand},
+    sampling::SamplingOverrides,
+    session::{self, is_custom_provider},
+    shell_env::ShellParity,
+    status::{self, AccountStatus, ModelStatus, StatusReport, TokenStatus, WorkspaceStatus},
+    usage::UsageTracker,
+    workspace_mcp::{self, McpCommand, WORKSPACE_MCP_FILE},
+};
 use crate::CodexAgent;
-use agent_client_protocol::{AvailableCommand, SessionId};
+use agent_client_protocol::{
+    AvailableCommand, AvailableCommandInput, ContentChunk, SessionId, SessionUpdate,
+};
 use codex_core::protocol::{AskForApproval, Op, ReviewRequest, SandboxPolicy};
 use codex_protocol::user_input::UserInput;
+use serde::Serialize;
+use serde_json::json;
 
 pub static AVAILABLE_COMMANDS: LazyLock<Vec<AvailableCommand>> = LazyLock::new(built_in_commands);
 
 impl CodexAgent {
-    pub async fn handle_slash_command(&self, session_id: &SessionId, name: &str) -> Option<Op> {
+    pub async fn handle_slash_command(
+        &self,
+        session_id: &SessionId,
+        name: &str,
+        args: &str,
+    ) -> Option<Op> {
         let mut msg = String::default();
         let op = match name {
             "init" => {
@@ -22,11 +45,21 @@ impl CodexAgent {
                 })
             }
             "status" => {
-                let status_text = self.render_status(session_id).await;
-                drop(
-                    self.send_message_chunk(session_id, status_text.into())
-                        .await,
-                );
+                let report = self.status_report(session_id).await;
+                let chunk = SessionUpdate::AgentMessageChunk(ContentChunk {
+                    content: status::render_status(&report, self.report_format.get()).into(),
+                    meta: Some(json!({ "codexStatus": report })),
+                });
+                drop(self.send_session_update(session_id, chunk).await);
+                None
+            }
+            "stats" => {
+                let report = self.status_report(session_id).await;
+                let chunk = SessionUpdate::AgentMessageChunk(ContentChunk {
+                    content: status::render_stats(&report, self.report_format.get()).into(),
+                    meta: Some(json!({ "codexStatus": report })),
+                });
+                drop(self.send_session_update(session_id, chunk).await);
                 None
             }
             "compact" => {
@@ -45,6 +78,228 @@ impl CodexAgent {
                     },
                 })
             }
+            "attach" => {
+                let text = self.attach_paths(session_id, args);
+                drop(self.send_message_chunk(session_id, text.into()).await);
+                None
+            }
+            "detach" => {
+                let text = self.detach_paths(session_id, args);
+                drop(self.send_message_chunk(session_id, text.into()).await);
+                None
+            }
+            "summaries" => {
+                let text = match session::parse_reasoning_summary(args) {
+                    Some(summary) => match self.set_reasoning_summary(session_id, summary).await {
+                        Ok(()) => format!(
+                            "🧠 Reasoning summaries set to {}\n\n",
+                            status::title_case(&summary.to_string())
+                        ),
+                        Err(err) => format!("⚠️ Failed to update reasoning summaries: {err}\n\n"),
+                    },
+                    None => "Usage: /summaries auto|concise|detailed|none\n".to_string(),
+                };
+                drop(self.send_message_chunk(session_id, text.into()).await);
+                None
+            }
+            "verbosity" => {
+                let text = if args.is_empty() {
+                    let current = self
+                        .with_session_state_mut(session_id, |state| state.verbosity)
+                        .flatten();
+                    format!(
+                        "Verbosity: {}\nUsage: /verbosity low|medium|high\n",
+                        current.map_or("default", session::verbosity_label)
+                    )
+                } else {
+                    match session::parse_verbosity(args) {
+                        Some(verbosity) => {
+                            self.with_session_state_mut(session_id, |state| {
+                                state.verbosity = Some(verbosity);
+                            });
+                            format!(
+                                "📏 Verbosity set to {}\n\n",
+                                session::verbosity_label(verbosity)
+                            )
+                        }
+                        None => "Usage: /verbosity low|medium|high\n".to_string(),
+                    }
+                };
+                drop(self.send_message_chunk(session_id, text.into()).await);
+                None
+            }
+            "lang" => {
+                let text = if args.is_empty() {
+                    let current = self
+                        .with_session_state_mut(session_id, |state| state.response_language.clone())
+                        .flatten();
+                    format!(
+                        "Response language: {}\nUsage: /lang <language>|off\n",
+                        current.as_deref().unwrap_or("same as the prompt")
+                    )
+                } else {
+                    match language::parse_language(args) {
+                        Ok(language) => {
+                            let text = match &language {
+                                Some(language) => {
+                                    format!("🌐 Answers will be in {language}\n\n")
+                                }
+                                None => {
+                                    "🌐 Answers will follow the prompt's language\n\n".to_string()
+                                }
+                            };
+                            self.with_session_state_mut(session_id, |state| {
+                                state.response_language = language;
+                            });
+                            self.save_session_settings(session_id).await;
+                            text
+                        }
+                        Err(_) => "Usage: /lang <language>|off\n".to_string(),
+                    }
+                };
+                drop(self.send_message_chunk(session_id, text.into()).await);
+                None
+            }
+            "instructions" => {
+                let text = self.instructions_command(args).await;
+                drop(self.send_message_chunk(session_id, text.into()).await);
+                None
+            }
+            "mcp" => {
+                let text = self.mcp_command(args).await;
+                drop(self.send_message_chunk(session_id, text.into()).await);
+                None
+            }
+            "sampling" => {
+                let text = self.sampling_command(session_id, args);
+                self.save_session_settings(session_id).await;
+                drop(self.send_message_chunk(session_id, text.into()).await);
+                None
+            }
+            "retry" => {
+                let last = self
+                    .with_session_state_mut(session_id, |state| state.last_prompt.clone())
+                    .flatten();
+                match (last, parse_retry_modifier(args)) {
+                    (Some(mut items), Ok(modifier)) => {
+                        msg = match &modifier {
+                            Some(modifier) => {
+                                format!("🔁 Retrying the last prompt with: {modifier}\n\n")
+                            }
+                            None => "🔁 Retrying the last prompt...\n\n".into(),
+                        };
+                        if let Some(modifier) = modifier {
+                            items.push(UserInput::Text { text: modifier });
+                        }
+                        Some(Op::UserInput { items })
+                    }
+                    (None, _) => {
+                        drop(
+                            self.send_message_chunk(
+                                session_id,
+                                "Nothing to retry: no prompt has been sent in this session yet.\n"
+                                    .into(),
+                            )
+                            .await,
+                        );
+                        None
+                    }
+                    (Some(_), Err(usage)) => {
+                        drop(self.send_message_chunk(session_id, usage.into()).await);
+                        None
+                    }
+                }
+            }
+            "continue" => {
+                let tail = self
+                    .with_session_state_mut(session_id, |state| state.truncated_output.take())
+                    .flatten();
+                match tail {
+                    Some(tail) => {
+                        msg = "⏩ Continuing the cut-off response...\n\n".into();
+                        Some(Op::UserInput {
+                            items: vec![UserInput::Text {
+                                text: continuation::continuation_prompt(&tail),
+                            }],
+                        })
+                    }
+                    None => {
+                        drop(
+                            self.send_message_chunk(
+                                session_id,
+                                "Nothing to continue: the last response was not cut off.\n".into(),
+                            )
+                            .await,
+                        );
+                        None
+                    }
+                }
+            }
+            "undo" => {
+                self.undo_command(session_id, args).await;
+                None
+            }
+            "diff" => {
+                self.diff_command(session_id, args).await;
+                None
+            }
+            "checkpoint" => {
+                let text = self.checkpoint_command(session_id, args).await;
+                drop(self.send_message_chunk(session_id, text.into()).await);
+                None
+            }
+            "rollback" => {
+                let text = self.rollback_command(session_id, args).await;
+                drop(self.send_message_chunk(session_id, text.into()).await);
+                None
+            }
+            "override" => {
+                let text = self.override_token_cap(session_id, args);
+                self.save_session_settings(session_id).await;
+                drop(self.send_message_chunk(session_id, text.into()).await);
+                None
+            }
+            "help" => {
+                let topic = args.split_whitespace().next();
+                let prompts = self.custom_prompts().await;
+                let mcp_commands = self.mcp_commands(session_id.0.as_ref());
+                drop(
+                    self.send_message_chunk(
+                        session_id,
+                        render_help(topic, &prompts, &mcp_commands).into(),
+                    )
+                    .await,
+                );
+                None
+            }
+            name if name.starts_with(custom_prompts::PROMPT_COMMAND_PREFIX) => {
+                let prompts = self.custom_prompts().await;
+                match custom_prompts::find_prompt(&prompts, name) {
+                    Some(prompt) => Some(Op::UserInput {
+                        items: vec![UserInput::Text {
+                            text: prompt.expand(args),
+                        }],
+                    }),
+                    None => {
+                        msg = render_help(Some(name), &prompts, &[]);
+                        None
+                    }
+                }
+            }
+            name if name.starts_with(mcp_commands::MCP_COMMAND_PREFIX) => {
+                let commands = self.mcp_commands(session_id.0.as_ref());
+                match mcp_commands::find_server_command(&commands, name) {
+                    Some(command) => Some(Op::UserInput {
+                        items: vec![UserInput::Text {
+                            text: command.expand(args),
+                        }],
+                    }),
+                    None => {
+                        msg = render_help(Some(name), &[], &commands);
+                        None
+                    }
+                }
+            }
             _ => None,
         };
 
@@ -54,119 +309,277 @@ impl CodexAgent {
         op
     }
 
-    async fn render_status(&self, session_id: &SessionId) -> String {
+    /// Pin workspace files so they are embedded in every subsequent turn.
+    ///
+    /// With no arguments, lists the currently attached files.
+    fn attach_paths(&self, session_id: &SessionId, args: &str) -> String {
+        let paths: Vec<String> = args.split_whitespace().map(str::to_string).collect();
+        let attachments = self.with_session_state_mut(session_id, |state| {
+            for path in &paths {
+                if !state.attachments.contains(path) {
+                    state.attachments.push(path.clone());
+                }
+            }
+            state.attachments.clone()
+        });
+        let Some(attachments) = attachments else {
+            return "Session not found.\n".to_string();
+        };
+
+        if paths.is_empty() {
+            return format_attachments(&attachments);
+        }
+        format!(
+            "📎 Attached {} (embedded in every turn until `/detach`)\n\n",
+            paths.join(", ")
+        )
+    }
+
+    /// Remove pinned files; with no arguments, removes all of them.
+    fn detach_paths(&self, session_id: &SessionId, args: &str) -> String {
+        let paths: Vec<&str> = args.split_whitespace().collect();
+        let removed = self.with_session_state_mut(session_id, |state| {
+            let before = state.attachments.len();
+            if paths.is_empty() {
+                state.attachments.clear();
+            } else {
+                state
+                    .attachments
+                    .retain(|path| !paths.contains(&path.as_str()));
+            }
+            before - state.attachments.len()
+        });
+        match removed {
+            Some(0) => "No matching attachments.\n".to_string(),
+            Some(n) => format!("📎 Detached {n} file(s).\n\n"),
+            None => "Session not found.\n".to_string(),
+        }
+    }
+
+    /// Show or update sampling overrides of this session and of sessions
+    /// created afterwards.
+    fn sampling_command(&self, session_id: &SessionId, args: &str) -> String {
+        if !is_custom_provider(&self.config.model_provider_id) {
+            return "Sampling overrides are only supported for custom providers.\n".to_string();
+        }
+        let (current, fs_session_id) = self
+            .with_session_state_mut(session_id, |state| {
+                (state.sampling, state.fs_session_id.clone())
+            })
+            .unwrap_or_default();
+        if args.is_empty() {
+            return format!(
+                "🎛️ This session: {}\nNew sessions: {}\nUsage: /sampling temperature=<0-2> top_p=<0-1> seed=<n> | reset\n",
+                current.describe(),
+                self.sampling_defaults.borrow().describe()
+            );
+        }
+        let updated = if args.eq_ignore_ascii_case("reset") {
+            Ok(SamplingOverrides::default())
+        } else {
+            current.parse_args(args)
+        };
+        match updated {
+            Ok(updated) => {
+                *self.sampling_defaults.borrow_mut() = updated;
+                if self.update_route(&fs_session_id, updated) {
+                    self.with_session_state_mut(session_id, |state| state.sampling = updated);
+                    format!(
+                        "🎛️ Sampling: {} (this session from its next request, and new sessions)\n\n",
+                        updated.describe()
+                    )
+                } else {
+                    format!(
+                        "🎛️ Sampling for new sessions: {}\nThis session keeps {} (its provider requests do not pass the sampling proxy).\n\n",
+                        updated.describe(),
+                        current.describe()
+                    )
+                }
+            }
+            Err(err) => format!("⚠️ {err}\n"),
+        }
+    }
+
+    /// Handle `/instructions`: manage the workspace's instruction snippets.
+    async fn instructions_command(&self, args: &str) -> String {
+        let store = &self.instruction_store;
+        let workspace = &self.config.cwd;
+        match instructions::parse_instructions_command(args) {
+            Ok(InstructionsCommand::List) => {
+                instructions::format_snippets(&store.list(workspace).await)
+            }
+            Ok(InstructionsCommand::Add(snippet)) => match store.add(workspace, snippet).await {
+                Ok(snippets) => format!(
+                    "📌 Saved instruction #{}; it applies to new sessions in this workspace.\n\n",
+                    snippets.len()
+                ),
+                Err(err) => format!("⚠️ Failed to save instruction: {err}\n\n"),
+            },
+            Ok(InstructionsCommand::Remove(index)) => match store.remove(workspace, index).await {
+                Ok(Some(removed)) => format!(
+                    "🗑️ Removed instruction #{index}: {removed}\nNew sessions in this workspace no longer get it.\n\n"
+                ),
+                Ok(None) => format!("⚠️ No instruction #{index}; see /instructions list\n\n"),
+                Err(err) => format!("⚠️ Failed to remove instruction: {err}\n\n"),
+            },
+            Err(usage) => usage,
+        }
+    }
+
+    /// Handle `/mcp`: show and trust the workspace's `.codex/mcp.toml`.
+    async fn mcp_command(&self, args: &str) -> String {
+        let command = match workspace_mcp::parse_mcp_command(args) {
+            Ok(command) => command,
+            Err(usage) => return usage,
+        };
+        let store = &self.mcp_trust_store;
+        let workspace = &self.config.cwd;
+        if command == McpCommand::Untrust {
+            return match store.untrust(workspace).await {
+                Ok(true) => format!(
+                    "🔌 {WORKSPACE_MCP_FILE} is no longer trusted; new sessions in this workspace will not start its servers.\n\n"
+                ),
+                Ok(false) => {
+                    format!("{WORKSPACE_MCP_FILE} was not trusted for this workspace.\n\n")
+                }
+                Err(err) => format!("⚠️ Failed to update MCP trust: {err}\n\n"),
+            };
+        }
+
+        let Some(text) = workspace_mcp::read_workspace_mcp(workspace).await else {
+            return format!("No {WORKSPACE_MCP_FILE} in this workspace.\n\n");
+        };
+        let servers = match workspace_mcp::parse_workspace_mcp(&text, workspace) {
+            Ok(servers) => servers,
+            Err(err) => return format!("⚠️ {WORKSPACE_MCP_FILE} is invalid: {err}\n\n"),
+        };
+        let mut msg = format!("MCP servers in {WORKSPACE_MCP_FILE}:\n");
+        for server in &servers {
+            msg.push_str(&format!("- {}\n", workspace_mcp::describe_server(server)));
+        }
+        if command == McpCommand::Trust {
+            return match store.trust(workspace, text).await {
+                Ok(()) => format!(
+                    "{msg}🔌 Trusted; new sessions in this workspace start these servers. Changes to the file need /mcp trust again.\n\n"
+                ),
+                Err(err) => format!("⚠️ Failed to save MCP trust: {err}\n\n"),
+            };
+        }
+        if store.is_trusted(workspace, &text).await {
+            msg.push_str("Trusted: new sessions start these servers.\n\n");
+        } else {
+            msg.push_str("Not trusted (or changed since trusted); review the file and run /mcp trust to start them.\n\n");
+        }
+        msg
+    }
+
+    /// Gather the session status shown by `/status` and `codex/status`.
+    pub(super) async fn status_report(&self, session_id: &SessionId) -> StatusReport {
         let sid_str = session_id.0.as_ref();
+        let acp_config = AcpConfig::load(&self.config.codex_home).await;
         // Session snapshot
-        let (approval_mode, sandbox_mode, token_usage) = {
-            if let Some(state) = self.sessions.borrow().get(sid_str) {
+        let (
+            approval_mode,
+            sandbox_mode,
+            summary_mode,
+            verbosity,
+            response_language,
+            token_usage,
+            model_usage,
+            attachments,
+            shell,
+            token_limit,
+        ) = self
+            .sessions
+            .with(sid_str, |state| {
                 (
                     state.current_approval,
                     state.current_sandbox.clone(),
+                    state.current_summary,
+                    state.verbosity,
+                    state.response_language.clone(),
                     state.token_usage.clone(),
+                    state.model_usage.clone(),
+                    state.attachments.clone(),
+                    state.shell.describe(),
+                    state.max_session_tokens,
                 )
-            } else {
+            })
+            .unwrap_or_else(|| {
                 (
                     AskForApproval::OnRequest,
                     SandboxPolicy::new_workspace_write_policy(),
+                    self.config.model_reasoning_summary,
+                    self.config.model_verbosity,
+                    language::default_response_language(),
                     None,
+                    UsageTracker::default(),
+                    Vec::new(),
+                    ShellParity::defaults(&acp_config).describe(),
+                    budget::default_max_session_tokens(&acp_config),
                 )
-            }
-        };
-
-        // Workspace
-        let cwd = self.shorten_home(&self.config.cwd);
+            });
 
         // Account
-        let (auth_mode, email, plan): (String, String, String) =
-            match self.auth_manager.read().ok().and_then(|am| am.auth()) {
-                Some(auth) => match auth.get_token_data().await {
-                    Ok(td) => {
-                        let email = td
-                            .id_token
-                            .email
-                            .clone()
-                            .unwrap_or_else(|| "(none)".to_string());
-                        let plan = td
-                            .id_token
-                            .get_chatgpt_plan_type()
-                            .unwrap_or_else(|| "(unknown)".to_string());
-                        ("ChatGPT".to_string(), email, plan)
-                    }
-                    Err(_) => (
-                        "API key".to_string(),
-                        "(none)".to_string(),
-                        "(unknown)".to_string(),
-                    ),
-                },
-                None => (
-                    "Not signed in".to_string(),
-                    "(none)".to_string(),
-                    "(unknown)".to_string(),
+        let (auth_mode, email, plan) = match self.auth_manager.read().ok().and_then(|am| am.auth())
+        {
+            Some(auth) => match auth.get_token_data().await {
+                Ok(td) => (
+                    "ChatGPT".to_string(),
+                    td.id_token.email.clone(),
+                    td.id_token.get_chatgpt_plan_type(),
                 ),
-            };
-
-        // Model
-        let model = &self.config.model;
-        let provider = self.title_case(&self.config.model_provider_id);
-        let effort = self.title_case(
-            format!("{}", self.config.model_reasoning_effort.unwrap_or_default()).as_str(),
-        );
-        let summary = self.title_case(format!("{}", self.config.model_reasoning_summary).as_str());
-
-        // Tokens
-        let (input, output, total) = match token_usage {
-            Some(u) => (
-                u.input_tokens.to_string(),
-                u.output_tokens.to_string(),
-                u.total_tokens.to_string(),
-            ),
-            None => ("0".to_string(), "0".to_string(), "0".to_string()),
+                Err(_) => ("API key".to_string(), None, None),
+            },
+            None => ("Not signed in".to_string(), None, None),
         };
 
-        let status = format!(
-            r#"
-📂 Workspace
-
-    Path:          {cwd}
-    Approval Mode: {approval}
-    Sandbox:       {sandbox}
-
-👤 Account
-
-    Signed in with: {auth_mode}
-    Login:          {email}
-    Plan:           {plan}
-
-🧠 Model
-
-    Name:                {model}
-    Provider:            {provider}
-    Reasoning Effort:    {effort}
-    Reasoning Summaries: {summary}
-
-📊 Token Usage
-
-    Session ID:     {sid}
-    Input:          {input}
-    Output:         {output}
-    Total:          {total}
-"#,
-            cwd = cwd,
-            approval = approval_mode,
-            sandbox = sandbox_mode,
-            auth_mode = auth_mode,
-            email = email,
-            plan = plan,
-            model = model,
-            provider = provider,
-            effort = effort,
-            summary = summary,
-            sid = sid_str,
-            input = input,
-            output = output,
-            total = total,
-        );
-        status
+        StatusReport {
+            session_id: sid_str.to_string(),
+            workspace: WorkspaceStatus {
+                path: self.shorten_home(&self.config.cwd),
+                approval_mode: approval_mode.to_string(),
+                sandbox: sandbox_mode.to_string(),
+                shell,
+            },
+            account: AccountStatus {
+                auth_mode,
+                email,
+                plan,
+            },
+            model: ModelStatus {
+                name: self.config.model.clone(),
+                provider: self.config.model_provider_id.clone(),
+                reasoning_effort: self
+                    .config
+                    .model_reasoning_effort
+                    .unwrap_or_default()
+                    .to_string(),
+                reasoning_summaries: summary_mode.to_string(),
+                verbosity: verbosity.map(|v| session::verbosity_label(v).to_string()),
+                response_language,
+            },
+            tokens: TokenStatus {
+                usage: token_usage.unwrap_or_default(),
+                limit: token_limit,
+                by_model: model_usage.by_model().clone(),
+            },
+            spend: self.spend_status().await,
+            attachments,
+            process: self.process_info(),
+        }
+    }
+
+    /// Custom prompts from `$CODEX_HOME/prompts`.
+    pub(super) async fn custom_prompts(&self) -> Vec<CustomPrompt> {
+        custom_prompts::discover(&self.config.codex_home.join("prompts")).await
+    }
+
+    /// The `/mcp:<server>` commands of a session.
+    pub(super) fn mcp_commands(&self, session_id: &str) -> Vec<McpServerCommand> {
+        self.sessions
+            .with(session_id, |state| state.mcp_commands.clone())
+            .unwrap_or_default()
     }
 
     fn shorten_home(&self, p: &Path) -> String {
@@ -178,43 +591,602 @@ impl CodexAgent {
         }
         s
     }
+}
 
-    fn title_case(&self, s: &str) -> String {
-        if s.is_empty() {
-            return s.to_string();
-        }
-        let mut chars = s.chars();
-        let first = chars.next().unwrap().to_uppercase().to_string();
-        let rest = chars.as_str();
-        format!("{}{}", first, rest)
+/// Render the list of attached files for `/attach` and `/status`.
+pub fn format_attachments(attachments: &[String]) -> String {
+    if attachments.is_empty() {
+        return "No files attached. Use `/attach <path>` to pin a file.\n".to_string();
+    }
+    let mut out = String::from("📎 Attachments\n\n");
+    for path in attachments {
+        out.push_str(&format!("    {path}\n"));
     }
+    out
 }
 
-fn built_in_commands() -> Vec<AvailableCommand> {
-    vec![
+/// Palette group a command belongs to, used by clients to build grouped menus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandCategory {
+    Session,
+    Files,
+    Git,
+    Diagnostics,
+}
+
+/// Typed metadata attached to every advertised `AvailableCommand` under `meta`.
+///
+/// Serialized shape:
+/// `{"category": "session", "icon": "🧠", "expects_followup": true, "keybinding": "..."}`
+/// where `keybinding` is omitted when no hint is suggested.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandMeta {
+    /// Palette group for the command.
+    pub category: CommandCategory,
+    /// Icon hint (emoji) clients may render next to the command.
+    pub icon: &'static str,
+    /// Whether running the command starts a model turn the user should wait on.
+    pub expects_followup: bool,
+    /// Suggested keybinding; clients are free to ignore or remap it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keybinding: Option<&'static str>,
+}
+
+/// A single argument accepted by a slash command.
+pub struct CommandArg {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub required: bool,
+}
+
+/// Static description of a built-in slash command.
+///
+/// This is the structured registry behind both `AvailableCommandsUpdate` and `/help`.
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub usage: &'static str,
+    pub args: &'static [CommandArg],
+    pub examples: &'static [&'static str],
+    pub meta: CommandMeta,
+}
+
+impl CommandSpec {
+    fn to_available_command(&self) -> AvailableCommand {
         AvailableCommand {
-            name: "init".into(),
-            description: "create an AGENTS.md file with instructions for Codex".into(),
-            input: None,
-            meta: None,
+            name: self.name.into(),
+            description: self.description.into(),
+            input: self
+                .input_hint()
+                .map(|hint| AvailableCommandInput::Unstructured { hint }),
+            meta: serde_json::to_value(&self.meta).ok(),
+        }
+    }
+
+    /// Input hint shown by clients after the command name, e.g. `[command]`.
+    fn input_hint(&self) -> Option<String> {
+        if self.args.is_empty() {
+            return None;
+        }
+        let parts: Vec<String> = self
+            .args
+            .iter()
+            .map(|arg| {
+                if arg.required {
+                    format!("<{}>", arg.name)
+                } else {
+                    format!("[{}]", arg.name)
+                }
+            })
+            .collect();
+        Some(parts.join(" "))
+    }
+}
+
+/// Look up a built-in command by name (without the leading `/`).
+pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+    let name = name.trim_start_matches('/');
+    BUILTIN_COMMANDS.iter().find(|spec| spec.name == name)
+}
+
+/// The commands advertised to a session: the built-in ones, then the custom
+/// prompts, then the MCP server commands.
+pub fn session_commands(
+    prompts: &[CustomPrompt],
+    mcp_commands: &[McpServerCommand],
+) -> Vec<AvailableCommand> {
+    AVAILABLE_COMMANDS
+        .iter()
+        .cloned()
+        .chain(prompts.iter().map(CustomPrompt::to_available_command))
+        .chain(
+            mcp_commands
+                .iter()
+                .map(McpServerCommand::to_available_command),
+        )
+        .collect()
+}
+
+/// Render `/help` output: an overview of every advertised command, or
+/// details for one.
+pub fn render_help(
+    topic: Option<&str>,
+    prompts: &[CustomPrompt],
+    mcp_commands: &[McpServerCommand],
+) -> String {
+    match topic {
+        Some(name) => {
+            let name = name.trim_start_matches('/');
+            if let Some(spec) = find_command(name) {
+                return render_command_help(spec);
+            }
+            if let Some(prompt) = custom_prompts::find_prompt(prompts, name) {
+                return render_prompt_help(prompt);
+            }
+            match mcp_commands::find_server_command(mcp_commands, name) {
+                Some(command) => render_mcp_command_help(command),
+                None => {
+                    format!("Unknown command `/{name}`. Run `/help` to list available commands.\n")
+                }
+            }
+        }
+        None => {
+            let mut out = String::from("❓ Available commands\n\n");
+            for spec in BUILTIN_COMMANDS {
+                out.push_str(&format!(
+                    "    {} {:<10} {}\n",
+                    spec.meta.icon, spec.name, spec.description
+                ));
+            }
+            if !prompts.is_empty() {
+                out.push_str("\nCustom prompts\n\n");
+                for prompt in prompts {
+                    out.push_str(&format!(
+                        "    📜 {:<10} {}\n",
+                        prompt.command_name(),
+                        prompt.description()
+                    ));
+                }
+            }
+            if !mcp_commands.is_empty() {
+                out.push_str("\nMCP servers\n\n");
+                for command in mcp_commands {
+                    out.push_str(&format!(
+                        "    🔌 {:<10} {}\n",
+                        command.command_name(),
+                        command.description()
+                    ));
+                }
+            }
+            out.push_str("\nRun `/help <command>` for arguments and examples.\n");
+            out
+        }
+    }
+}
+
+fn render_prompt_help(prompt: &CustomPrompt) -> String {
+    let mut usage = format!("/{}", prompt.command_name());
+    if let Some(hint) = &prompt.argument_hint {
+        usage.push(' ');
+        usage.push_str(hint);
+    }
+    format!(
+        "📜 /{}\n\n    {}\n\nUsage\n\n    {usage}\n",
+        prompt.command_name(),
+        prompt.description()
+    )
+}
+
+fn render_mcp_command_help(command: &McpServerCommand) -> String {
+    let name = command.command_name();
+    format!(
+        "🔌 /{name}\n\n    {}\n\nUsage\n\n    /{name} [task]\n\nExamples\n\n    /{name}\n    /{name} summarize the open issues\n",
+        command.description()
+    )
+}
+
+fn render_command_help(spec: &CommandSpec) -> String {
+    let mut out = format!(
+        "{} /{}\n\n    {}\n\nUsage\n\n    {}\n",
+        spec.meta.icon, spec.name, spec.description, spec.usage
+    );
+    if !spec.args.is_empty() {
+        out.push_str("\nArguments\n\n");
+        for arg in spec.args {
+            let required = if arg.required { "required" } else { "optional" };
+            out.push_str(&format!(
+                "    {:<10} {} ({required})\n",
+                arg.name, arg.description
+            ));
+        }
+    }
+    if !spec.examples.is_empty() {
+        out.push_str("\nExamples\n\n");
+        for example in spec.examples {
+            out.push_str(&format!("    {example}\n"));
+        }
+    }
+    out
+}
+
+/// Parse `/retry` arguments: empty, or `with <modifier>`.
+pub fn parse_retry_modifier(args: &str) -> Result<Option<String>, String> {
+    let args = args.trim();
+    if args.is_empty() {
+        return Ok(None);
+    }
+    match args.split_once(char::is_whitespace) {
+        Some((kw, rest)) if kw.eq_ignore_ascii_case("with") && !rest.trim().is_empty() => {
+            Ok(Some(rest.trim().to_string()))
+        }
+        _ => Err("Usage: /retry [with <modifier>]\n".to_string()),
+    }
+}
+
+/// Registry of built-in slash commands; the single source for advertised commands.
+pub static BUILTIN_COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "init",
+        description: "create an AGENTS.md file with instructions for Codex",
+        usage: "/init",
+        args: &[],
+        examples: &["/init"],
+        meta: CommandMeta {
+            category: CommandCategory::Files,
+            icon: "📝",
+            expects_followup: true,
+            keybinding: None,
         },
-        AvailableCommand {
-            name: "compact".into(),
-            description: "summarize conversation to prevent hitting the context limit".into(),
-            input: None,
-            meta: None,
+    },
+    CommandSpec {
+        name: "compact",
+        description: "summarize conversation to prevent hitting the context limit",
+        usage: "/compact",
+        args: &[],
+        examples: &["/compact"],
+        meta: CommandMeta {
+            category: CommandCategory::Session,
+            icon: "🧠",
+            expects_followup: true,
+            keybinding: None,
         },
-        AvailableCommand {
-            name: "review".into(),
-            description: "review my current changes and find issues".to_string(),
-            input: None,
-            meta: None,
+    },
+    CommandSpec {
+        name: "review",
+        description: "review my current changes and find issues",
+        usage: "/review",
+        args: &[],
+        examples: &["/review"],
+        meta: CommandMeta {
+            category: CommandCategory::Git,
+            icon: "🔍",
+            expects_followup: true,
+            keybinding: Some("ctrl-shift-r"),
         },
-        AvailableCommand {
-            name: "status".into(),
-            description: "show current session configuration and token usage".into(),
-            input: None,
-            meta: None,
+    },
+    CommandSpec {
+        name: "status",
+        description: "show current session configuration and token usage",
+        usage: "/status",
+        args: &[],
+        examples: &["/status"],
+        meta: CommandMeta {
+            category: CommandCategory::Diagnostics,
+            icon: "📊",
+            expects_followup: false,
+            keybinding: Some("ctrl-shift-s"),
+        },
+    },
+    CommandSpec {
+        name: "stats",
+        description: "show token usage per model and spend as a table",
+        usage: "/stats",
+        args: &[],
+        examples: &["/stats"],
+        meta: CommandMeta {
+            category: CommandCategory::Diagnostics,
+            icon: "📈",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "attach",
+        description: "pin files as context for every subsequent turn",
+        usage: "/attach [path...]",
+        args: &[CommandArg {
+            name: "path",
+            description: "workspace-relative or absolute file path; omit to list attachments",
+            required: false,
+        }],
+        examples: &[
+            "/attach src/main.rs",
+            "/attach README.md Cargo.toml",
+            "/attach",
+        ],
+        meta: CommandMeta {
+            category: CommandCategory::Files,
+            icon: "📎",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "detach",
+        description: "remove pinned files (all files when no path is given)",
+        usage: "/detach [path...]",
+        args: &[CommandArg {
+            name: "path",
+            description: "attached path to remove; omit to remove all",
+            required: false,
+        }],
+        examples: &["/detach src/main.rs", "/detach"],
+        meta: CommandMeta {
+            category: CommandCategory::Files,
+            icon: "📎",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "summaries",
+        description: "set reasoning summary detail for this session",
+        usage: "/summaries auto|concise|detailed|none",
+        args: &[CommandArg {
+            name: "level",
+            description: "one of auto, concise, detailed, none",
+            required: true,
+        }],
+        examples: &["/summaries detailed", "/summaries none"],
+        meta: CommandMeta {
+            category: CommandCategory::Session,
+            icon: "🧠",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "verbosity",
+        description: "set how long final answers should be",
+        usage: "/verbosity [low|medium|high]",
+        args: &[CommandArg {
+            name: "level",
+            description: "one of low, medium, high; omit to show the current setting",
+            required: false,
+        }],
+        examples: &["/verbosity low", "/verbosity"],
+        meta: CommandMeta {
+            category: CommandCategory::Session,
+            icon: "📏",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "lang",
+        description: "set the language answers are written in",
+        usage: "/lang [<language>|off]",
+        args: &[CommandArg {
+            name: "language",
+            description: "a language name such as German or 日本語, or off to follow the prompt; omit to show the current setting",
+            required: false,
+        }],
+        examples: &["/lang German", "/lang off", "/lang"],
+        meta: CommandMeta {
+            category: CommandCategory::Session,
+            icon: "🌐",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "instructions",
+        description: "manage standing instructions for new sessions in this workspace",
+        usage: "/instructions [list] | add <text> | remove <n>",
+        args: &[
+            CommandArg {
+                name: "action",
+                description: "list (default), add, or remove",
+                required: false,
+            },
+            CommandArg {
+                name: "argument",
+                description: "the instruction to add, or the number of the one to remove",
+                required: false,
+            },
+        ],
+        examples: &[
+            "/instructions add Prefer pnpm over npm",
+            "/instructions list",
+            "/instructions remove 2",
+        ],
+        meta: CommandMeta {
+            category: CommandCategory::Session,
+            icon: "📌",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "mcp",
+        description: "list or trust the MCP servers in this workspace's .codex/mcp.toml",
+        usage: "/mcp [list] | trust | untrust",
+        args: &[CommandArg {
+            name: "action",
+            description: "list (default), trust, or untrust",
+            required: false,
+        }],
+        examples: &["/mcp", "/mcp trust"],
+        meta: CommandMeta {
+            category: CommandCategory::Session,
+            icon: "🔌",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "sampling",
+        description: "set temperature/top_p/seed for custom providers",
+        usage: "/sampling [temperature=<0-2>] [top_p=<0-1>] [seed=<n>] | reset",
+        args: &[
+            CommandArg {
+                name: "temperature",
+                description: "sampling temperature between 0 and 2, or `default`",
+                required: false,
+            },
+            CommandArg {
+                name: "top_p",
+                description: "nucleus sampling cutoff in (0, 1], or `default`",
+                required: false,
+            },
+            CommandArg {
+                name: "seed",
+                description: "fixed seed for reproducible generations, or `default`",
+                required: false,
+            },
+        ],
+        examples: &["/sampling temperature=0.2 top_p=0.9", "/sampling reset"],
+        meta: CommandMeta {
+            category: CommandCategory::Session,
+            icon: "🎛️",
+            expects_followup: false,
+            keybinding: None,
         },
-    ]
+    },
+    CommandSpec {
+        name: "retry",
+        description: "resubmit the last prompt, optionally with extra instructions",
+        usage: "/retry [with <modifier>]",
+        args: &[CommandArg {
+            name: "modifier",
+            description: "text appended to the resubmitted prompt, introduced by `with`",
+            required: false,
+        }],
+        examples: &["/retry", "/retry with shorter answer, no code"],
+        meta: CommandMeta {
+            category: CommandCategory::Session,
+            icon: "🔁",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "continue",
+        description: "resume a response cut off by the output token limit",
+        usage: "/continue",
+        args: &[],
+        examples: &["/continue"],
+        meta: CommandMeta {
+            category: CommandCategory::Session,
+            icon: "⏩",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "undo",
+        description: "restore files changed by the agent: the last change, or all of the last turn",
+        usage: "/undo [all]",
+        args: &[CommandArg {
+            name: "all",
+            description: "`all` restores every change of the last turn that changed files",
+            required: false,
+        }],
+        examples: &["/undo", "/undo all"],
+        meta: CommandMeta {
+            category: CommandCategory::Files,
+            icon: "↩️",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "diff",
+        description: "show every file the agent changed in this session as diffs",
+        usage: "/diff",
+        args: &[],
+        examples: &["/diff"],
+        meta: CommandMeta {
+            category: CommandCategory::Files,
+            icon: "🗂️",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "checkpoint",
+        description: "save the workspace as a git checkpoint of this session",
+        usage: "/checkpoint [note]",
+        args: &[CommandArg {
+            name: "note",
+            description: "what the checkpoint is for",
+            required: false,
+        }],
+        examples: &["/checkpoint", "/checkpoint before refactor"],
+        meta: CommandMeta {
+            category: CommandCategory::Files,
+            icon: "📍",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "rollback",
+        description: "list this session's checkpoints, or return the workspace to one",
+        usage: "/rollback [<n>]",
+        args: &[CommandArg {
+            name: "n",
+            description: "number of the checkpoint to return to",
+            required: false,
+        }],
+        examples: &["/rollback", "/rollback 2"],
+        meta: CommandMeta {
+            category: CommandCategory::Files,
+            icon: "⏪",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "override",
+        description: "lift or raise this session's token limit",
+        usage: "/override [off|<tokens>]",
+        args: &[CommandArg {
+            name: "tokens",
+            description: "tokens allowed beyond current usage; omit or `off` to remove the limit",
+            required: false,
+        }],
+        examples: &["/override", "/override 50000"],
+        meta: CommandMeta {
+            category: CommandCategory::Session,
+            icon: "🔓",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+    CommandSpec {
+        name: "help",
+        description: "list available commands or show details for one",
+        usage: "/help [command]",
+        args: &[CommandArg {
+            name: "command",
+            description: "command to describe, e.g. `status`",
+            required: false,
+        }],
+        examples: &["/help", "/help review"],
+        meta: CommandMeta {
+            category: CommandCategory::Session,
+            icon: "❓",
+            expects_followup: false,
+            keybinding: None,
+        },
+    },
+];
+
+fn built_in_commands() -> Vec<AvailableCommand> {
+    BUILTIN_COMMANDS
+        .iter()
+        .map(CommandSpec::to_available_command)
+        .collect()
 }
@@ -0,0 +1,150 @@
+//! Older ACP client compatibility (`acp-compat` feature).
+//!
+//! Clients pinned to agent-client-protocol 0.4 reject fields and update kinds
+//! added in later releases: the `_meta` maps on chunks, tool calls, plans and
+//! commands, and `current_mode_update`. A client that sends
+//! `_meta.acpCompat = "0.4"` in `initialize` (on the request or its client
+//! capabilities) gets every update and permission request in that shape:
+//! `_meta` is removed throughout and mode updates are not sent. The response
+//! echoes `_meta.acpCompat` when the shape was accepted.
+
+use std::{cell::Cell, rc::Rc};
+
+use agent_client_protocol::{
+    ContentBlock, RequestPermissionRequest, SessionNotification, SessionUpdate, ToolCallContent,
+};
+use serde_json::{Value, json};
+
+use super::core::CodexAgent;
+
+/// Meta key carrying the requested protocol shape.
+pub const COMPAT_META_KEY: &str = "acpCompat";
+
+/// The older shape this layer can produce.
+pub const LEGACY_SHAPE: &str = "0.4";
+
+/// Whether `initialize` asked for the legacy shape.
+pub fn requests_legacy(request_meta: Option<&Value>, capabilities_meta: Option<&Value>) -> bool {
+    [request_meta, capabilities_meta]
+        .into_iter()
+        .flatten()
+        .filter_map(|meta| meta.get(COMPAT_META_KEY).and_then(Value::as_str))
+        .any(|shape| shape.trim() == LEGACY_SHAPE)
+}
+
+impl CodexAgent {
+    /// Pick the message shape for this client; the `initialize` response
+    /// meta confirming it, if the legacy shape was chosen.
+    pub(super) fn negotiate_shape(
+        &self,
+        request_meta: Option<&Value>,
+        capabilities_meta: Option<&Value>,
+    ) -> Option<Value> {
+        let legacy = requests_legacy(request_meta, capabilities_meta);
+        self.legacy_shape.set(legacy);
+        legacy.then(|| json!({ COMPAT_META_KEY: LEGACY_SHAPE }))
+    }
+}
+
+/// Rewrites outgoing messages for the negotiated shape. Shares the setting
+/// with the agent, so it follows `initialize`.
+#[derive(Debug, Clone, Default)]
+pub struct ProtocolCompat {
+    legacy: Rc<Cell<bool>>,
+}
+
+impl From<&CodexAgent> for ProtocolCompat {
+    fn from(agent: &CodexAgent) -> Self {
+        Self {
+            legacy: agent.legacy_shape.clone(),
+        }
+    }
+}
+
+impl ProtocolCompat {
+    /// A fixed shape, for embedders that negotiate it themselves.
+    pub fn new(legacy: bool) -> Self {
+        Self {
+            legacy: Rc::new(Cell::new(legacy)),
+        }
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.legacy.get()
+    }
+
+    /// Rewrite `notification`; `false` when it must not be sent at all.
+    pub fn notification(&self, notification: &mut SessionNotification) -> bool {
+        if !self.is_legacy() {
+            return true;
+        }
+        notification.meta = None;
+        match &mut notification.update {
+            SessionUpdate::UserMessageChunk(chunk)
+            | SessionUpdate::AgentMessageChunk(chunk)
+            | SessionUpdate::AgentThoughtChunk(chunk) => {
+                chunk.meta = None;
+                strip_block(&mut chunk.content);
+            }
+            SessionUpdate::ToolCall(call) => {
+                call.meta = None;
+                call.locations.iter_mut().for_each(|loc| loc.meta = None);
+                call.content.iter_mut().for_each(strip_tool_content);
+            }
+            SessionUpdate::ToolCallUpdate(update) => strip_tool_update(update),
+            SessionUpdate::Plan(plan) => {
+                plan.meta = None;
+                plan.entries.iter_mut().for_each(|entry| entry.meta = None);
+            }
+            SessionUpdate::AvailableCommandsUpdate(update) => {
+                update.meta = None;
+                update
+                    .available_commands
+                    .iter_mut()
+                    .for_each(|command| command.meta = None);
+            }
+            SessionUpdate::CurrentModeUpdate(_) => return false,
+        }
+        true
+    }
+
+    pub fn permission_request(&self, request: &mut RequestPermissionRequest) {
+        if !self.is_legacy() {
+            return;
+        }
+        request.meta = None;
+        strip_tool_update(&mut request.tool_call);
+        request
+            .options
+            .iter_mut()
+            .for_each(|option| option.meta = None);
+    }
+}
+
+fn strip_tool_update(update: &mut agent_client_protocol::ToolCallUpdate) {
+    update.meta = None;
+    if let Some(locations) = &mut update.fields.locations {
+        locations.iter_mut().for_each(|loc| loc.meta = None);
+    }
+    if let Some(content) = &mut update.fields.content {
+        content.iter_mut().for_each(strip_tool_content);
+    }
+}
+
+fn strip_tool_content(content: &mut ToolCallContent) {
+    match content {
+        ToolCallContent::Content { content } => strip_block(content),
+        ToolCallContent::Diff { diff } => diff.meta = None,
+        _ => {}
+    }
+}
+
+fn strip_block(block: &mut ContentBlock) {
+    match block {
+        ContentBlock::Text(text) => text.meta = None,
+        ContentBlock::Image(image) => image.meta = None,
+        ContentBlock::Audio(audio) => audio.meta = None,
+        ContentBlock::ResourceLink(link) => link.meta = None,
+        ContentBlock::Resource(resource) => resource.meta = None,
+    }
+}
@@ -6,19 +6,33 @@ use codex_core::{
     config_types::{McpServerConfig, McpServerTransportConfig},
 };
 
-use crate::fs::FsBridge;
+use tracing::warn;
 
-use super::core::CodexAgent;
+use crate::fs::{
+    FsBridge,
+    tools::{bridge_timeouts::BridgeTimeouts, command_cache::RUN_COMMAND_TOOL},
+};
+
+use super::{
+    core::CodexAgent,
+    instructions, language,
+    mcp_servers::{FS_SERVER_NAME, ServerRename, unique_server_name},
+    sampling::SamplingOverrides,
+    session::is_custom_provider,
+    shell_env::ShellParity,
+};
 
 impl CodexAgent {
     /// Prepare the filesystem MCP server configuration for a session.
     ///
     /// This creates a stdio-based MCP server that communicates with the
     /// filesystem bridge, enabling file operations within the session.
+    /// `run_cached_command` is only offered with the command cache enabled.
     pub(super) fn prepare_fs_mcp_server_config(
         &self,
         session_id: &str,
         bridge: &FsBridge,
+        command_cache: bool,
     ) -> Result<McpServerConfig, Error> {
         let exe_path = env::current_exe().map_err(|err| {
             Error::internal_error().with_data(format!("failed to locate agent binary: {err}"))
@@ -27,9 +41,15 @@ impl CodexAgent {
         let mut env = HashMap::new();
         env.insert(
             "ACP_FS_BRIDGE_ADDR".to_string(),
-            bridge.address().to_string(),
+            bridge.session_address(session_id).to_string(),
         );
         env.insert("ACP_FS_SESSION_ID".to_string(), session_id.to_string());
+        env.insert(
+            "ACP_FS_BRIDGE_TOKEN".to_string(),
+            bridge.session_token(session_id),
+        );
+        let timeouts = BridgeTimeouts::from_env();
+        env.extend(timeouts.to_env());
 
         Ok(McpServerConfig {
             transport: McpServerTransportConfig::Stdio {
@@ -41,7 +61,8 @@ impl CodexAgent {
             },
             enabled: true,
             startup_timeout_sec: Some(Duration::from_secs(5)),
-            tool_timeout_sec: Some(Duration::from_secs(30)),
+            // The helper times out each bridge request itself; see `BridgeTimeouts`.
+            tool_timeout_sec: Some(timeouts.tool_timeout()),
             enabled_tools: None,
             disabled_tools: {
                 let caps = self.client_capabilities.borrow();
@@ -53,6 +74,10 @@ impl CodexAgent {
                     v.push("write_text_file".to_string());
                     v.push("edit_text_file".to_string());
                     v.push("multi_edit_text_file".to_string());
+                    v.push("apply_unified_diff".to_string());
+                }
+                if !command_cache {
+                    v.push(RUN_COMMAND_TOOL.to_string());
                 }
                 if v.is_empty() { None } else { Some(v) }
             },
@@ -142,18 +167,41 @@ impl CodexAgent {
         }
     }
 
+    /// Instructions added to a new session: the response language and the
+    /// workspace's `/instructions` snippets.
+    pub(super) async fn extra_instructions(&self, response_language: Option<&str>) -> Vec<String> {
+        let snippets = self.instruction_store.list(&self.config.cwd).await;
+        response_language
+            .map(language::language_instruction)
+            .into_iter()
+            .chain(instructions::render_snippets(&snippets))
+            .collect()
+    }
+
     /// Build a session-specific Codex configuration.
     ///
     /// This clones the base config and adds:
     /// - Filesystem guidance instructions
-    /// - Session-specific MCP servers
-    /// - The acp_fs MCP server if filesystem bridge is available
+    /// - Extra session instructions (see [`Self::extra_instructions`])
+    /// - Session-specific MCP servers, renamed where their names clash
+    /// - The acp_fs MCP server if filesystem bridge is available, with
+    ///   `run_cached_command` when `command_cache` is set
+    /// - Custom providers reached through the sampling proxy
+    /// - Login-shell / env-file settings on the shell environment policy
     pub(super) fn build_session_config(
         &self,
         session_id: &str,
         mcp_servers: Vec<McpServer>,
-    ) -> Result<CodexConfig, Error> {
-        let mut session_config = self.config.clone();
+        sampling: &SamplingOverrides,
+        shell: &mut ShellParity,
+        extra_instructions: &[String],
+        command_cache: bool,
+    ) -> Result<(CodexConfig, Vec<ServerRename>), Error> {
+        let mut session_config = self.model_config().into_owned();
+        if is_custom_provider(&session_config.model_provider_id) {
+            self.route_provider(session_id, sampling, &mut session_config.model_provider);
+        }
+        shell.apply_to(&mut session_config.shell_environment_policy)?;
         let fs_guidance = include_str!("prompt_fs_guidance.md");
 
         // Inject filesystem guidance into instructions
@@ -180,24 +228,52 @@ impl CodexAgent {
             };
         }
 
+        // Session-specific instructions go next to the guidance above.
+        for extra in extra_instructions {
+            let instructions = if session_config.base_instructions.is_some() {
+                &mut session_config.base_instructions
+            } else {
+                &mut session_config.user_instructions
+            };
+            let text = instructions.get_or_insert_with(String::new);
+            if !text.trim_end().is_empty() {
+                text.push_str("\n\n");
+            }
+            text.push_str(extra);
+        }
+
         let startup_timeout = Some(Duration::from_secs(5));
         let tool_timeout = Some(Duration::from_secs(30));
 
-        // Add requested MCP servers
-        session_config.mcp_servers.extend(
-            mcp_servers
-                .into_iter()
-                .filter_map(|srv| self.build_mcp_server(srv, startup_timeout, tool_timeout)),
-        );
+        // Add requested MCP servers; a clashing name would shadow another
+        // server's tools, so such servers get a suffix.
+        let mut renames = Vec::new();
+        for (name, server_config) in mcp_servers
+            .into_iter()
+            .filter_map(|srv| self.build_mcp_server(srv, startup_timeout, tool_timeout))
+        {
+            let assigned = unique_server_name(&name, |candidate| {
+                candidate == FS_SERVER_NAME || session_config.mcp_servers.contains_key(candidate)
+            });
+            if assigned != name {
+                warn!(requested = %name, %assigned, "renamed clashing MCP server");
+                renames.push(ServerRename {
+                    requested: name,
+                    assigned: assigned.clone(),
+                });
+            }
+            session_config.mcp_servers.insert(assigned, server_config);
+        }
 
         // Add acp_fs MCP server if bridge is available
         if let Some(bridge) = &self.fs_bridge {
-            let server_config = self.prepare_fs_mcp_server_config(session_id, bridge.as_ref())?;
+            let server_config =
+                self.prepare_fs_mcp_server_config(session_id, bridge.as_ref(), command_cache)?;
             session_config
                 .mcp_servers
-                .insert("acp_fs".to_string(), server_config);
+                .insert(FS_SERVER_NAME.to_string(), server_config);
         }
 
-        Ok(session_config)
+        Ok((session_config, renames))
     }
 }
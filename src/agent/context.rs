@@ -0,0 +1,232 @@
+//! Extra context embedded into user turns.
+//!
+//! Two sources feed this module:
+//! - files pinned with `/attach`, re-read through the FS bridge every turn so
+//!   edits are always reflected;
+//! - items pushed by the client via the `codex/addContext` extension method,
+//!   which are consumed by the next turn only.
+//!
+//! Both are prepended to the next `Op::UserInput`, followed by a response
+//! language changed with `/lang` and the session's `/verbosity` preference
+//! when one is set.
+
+use agent_client_protocol::SessionId;
+use codex_core::protocol_config_types::Verbosity;
+use codex_protocol::user_input::UserInput;
+use serde::Deserialize;
+use tracing::warn;
+
+use super::{core::CodexAgent, language, session};
+use crate::fs::text;
+
+/// Maximum bytes embedded per attached or referenced file.
+pub const MAX_ATTACHMENT_BYTES: usize = 64 * 1024;
+/// Maximum bytes embedded across all attached files in a single turn.
+pub const MAX_TOTAL_ATTACHMENT_BYTES: usize = 256 * 1024;
+
+/// A context item pushed by the client between prompts.
+///
+/// Wire format (`type` tagged):
+/// - `{"type": "text", "text": "...", "label": "pytest"}`
+/// - `{"type": "file", "path": "src/lib.rs", "line": 10, "limit": 40}`
+/// - `{"type": "diagnostic", "path": "src/lib.rs", "line": 12, "severity": "error", "message": "...", "source": "rustc"}`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContextItem {
+    Text {
+        text: String,
+        #[serde(default)]
+        label: Option<String>,
+    },
+    File {
+        path: String,
+        #[serde(default)]
+        line: Option<u32>,
+        #[serde(default)]
+        limit: Option<u32>,
+    },
+    Diagnostic {
+        path: String,
+        message: String,
+        #[serde(default)]
+        line: Option<u32>,
+        #[serde(default)]
+        severity: Option<String>,
+        #[serde(default)]
+        source: Option<String>,
+    },
+}
+
+impl CodexAgent {
+    /// Collect all context for the next turn: attached files first, then any
+    /// pushed items (which are drained from the session).
+    pub(super) async fn context_items(&self, session_id: &SessionId) -> Vec<UserInput> {
+        let mut items = self.attachment_items(session_id).await;
+        let pending = self
+            .with_session_state_mut(session_id, |state| {
+                std::mem::take(&mut state.pending_context)
+            })
+            .unwrap_or_default();
+        for item in pending {
+            if let Some(input) = self.render_context_item(session_id, item).await {
+                items.push(input);
+            }
+        }
+        if let Some(language) = self
+            .with_session_state_mut(session_id, |state| {
+                (state.response_language != state.instructed_language)
+                    .then(|| state.response_language.clone())
+            })
+            .flatten()
+        {
+            items.push(language::language_item(language.as_deref()));
+        }
+        if let Some(verbosity) = self
+            .with_session_state_mut(session_id, |state| state.verbosity)
+            .flatten()
+        {
+            items.push(verbosity_item(verbosity));
+        }
+        items
+    }
+
+    /// Read every attached file for the session and render them as user input items.
+    ///
+    /// Files that fail to read are skipped with a warning chunk; content beyond
+    /// the per-file or per-turn byte caps is truncated with a marker.
+    async fn attachment_items(&self, session_id: &SessionId) -> Vec<UserInput> {
+        let attachments = self
+            .with_session_state_mut(session_id, |state| state.attachments.clone())
+            .unwrap_or_default();
+
+        let mut items = Vec::new();
+        let mut budget = MAX_TOTAL_ATTACHMENT_BYTES;
+        for path in attachments {
+            if budget == 0 {
+                let msg = format!("⚠️ Skipped attachment {path}: per-turn size cap reached\n\n");
+                drop(self.send_message_chunk(session_id, msg.into()).await);
+                continue;
+            }
+
+            let content = match self.read_context_file(session_id, &path, None, None).await {
+                Ok(content) => content,
+                Err(err) => {
+                    warn!(path = %path, error = %err, "failed to read attachment");
+                    let msg = format!("⚠️ Could not read attachment {path}: {err}\n\n");
+                    drop(self.send_message_chunk(session_id, msg.into()).await);
+                    continue;
+                }
+            };
+
+            let (text, truncated) = text::truncate_head(&content, MAX_ATTACHMENT_BYTES.min(budget));
+            budget = budget.saturating_sub(text.len());
+            items.push(UserInput::Text {
+                text: wrap_file("attached-file", &path, text, truncated),
+            });
+        }
+        items
+    }
+
+    async fn render_context_item(
+        &self,
+        session_id: &SessionId,
+        item: ContextItem,
+    ) -> Option<UserInput> {
+        let text = match item {
+            ContextItem::Text { text, label } => match label {
+                Some(label) => format!("<context label=\"{label}\">\n{text}\n</context>"),
+                None => format!("<context>\n{text}\n</context>"),
+            },
+            ContextItem::File { path, line, limit } => {
+                match self.read_context_file(session_id, &path, line, limit).await {
+                    Ok(content) => {
+                        let (text, truncated) = text::truncate_head(&content, MAX_ATTACHMENT_BYTES);
+                        wrap_file("context-file", &path, text, truncated)
+                    }
+                    Err(err) => {
+                        warn!(path = %path, error = %err, "failed to read pushed context file");
+                        return None;
+                    }
+                }
+            }
+            ContextItem::Diagnostic {
+                path,
+                message,
+                line,
+                severity,
+                source,
+            } => format_diagnostic(
+                &path,
+                line,
+                severity.as_deref(),
+                source.as_deref(),
+                &message,
+            ),
+        };
+        Some(UserInput::Text { text })
+    }
+
+    async fn read_context_file(
+        &self,
+        session_id: &SessionId,
+        path: &str,
+        line: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<String, String> {
+        match &self.fs_bridge {
+            Some(bridge) => bridge.read_text_file(session_id, path, line, limit).await,
+            None => tokio::fs::read_to_string(self.config.cwd.join(path))
+                .await
+                .map_err(|err| err.to_string()),
+        }
+    }
+}
+
+/// Render a single diagnostic as a compact tagged line for the model.
+pub fn format_diagnostic(
+    path: &str,
+    line: Option<u32>,
+    severity: Option<&str>,
+    source: Option<&str>,
+    message: &str,
+) -> String {
+    let location = match line {
+        Some(line) => format!("{path}:{line}"),
+        None => path.to_string(),
+    };
+    let severity = severity.unwrap_or("error");
+    match source {
+        Some(source) => format!(
+            "<diagnostic severity=\"{severity}\" source=\"{source}\">{location}: {message}</diagnostic>"
+        ),
+        None => format!("<diagnostic severity=\"{severity}\">{location}: {message}</diagnostic>"),
+    }
+}
+
+fn wrap_file(tag: &str, path: &str, text: &str, truncated: bool) -> String {
+    let marker = if truncated {
+        format!("\n[truncated at {} bytes]", text.len())
+    } else {
+        String::new()
+    };
+    format!("<{tag} path=\"{path}\">\n{text}{marker}\n</{tag}>")
+}
+
+/// Turn-level instruction for the session's verbosity preference.
+///
+/// `OverrideTurnContext` has no verbosity field, so the preference is sent as
+/// text; models with native verbosity support still get `model_verbosity`
+/// from config at session start.
+fn verbosity_item(verbosity: Verbosity) -> UserInput {
+    let guidance = match verbosity {
+        Verbosity::Low => "Keep the final answer terse: a few sentences or a short list, no recap.",
+        Verbosity::Medium => "Keep the final answer moderately detailed.",
+        Verbosity::High => "Give a thorough final answer with full explanations.",
+    };
+    UserInput::Text {
+        text: format!(
+            "<verbosity level=\"{}\">{guidance}</verbosity>",
+            session::verbosity_label(verbosity)
+        ),
+    }
+}
@@ -0,0 +1,100 @@
+//! Recovery from context-window overflows.
+//!
+//! When Codex reports that the conversation no longer fits the model's context
+//! window, the turn either fails with [`CONTEXT_WINDOW_ERROR_CODE`] (so clients
+//! can offer a "conversation too long" affordance) or, when
+//! `CODEX_ACP_AUTO_COMPACT` is enabled, the conversation is compacted and the
+//! turn is retried once before giving up.
+
+use agent_client_protocol::{Error, SessionId};
+use codex_core::{CodexConversation, protocol::Op};
+use codex_protocol::user_input::UserInput;
+use serde_json::json;
+use tracing::info;
+
+use super::{core::CodexAgent, events, prompt::TurnOutcome};
+
+/// JSON-RPC error code returned when the conversation exceeds the context window.
+pub const CONTEXT_WINDOW_ERROR_CODE: i32 = -32051;
+
+/// Environment variable enabling automatic compaction on overflow (`1`/`true`).
+pub const AUTO_COMPACT_ENV: &str = "CODEX_ACP_AUTO_COMPACT";
+
+/// Whether a Codex error message reports a context-window overflow.
+pub fn is_context_window_error(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    message.contains("context window")
+        || message.contains("context_length_exceeded")
+        || message.contains("maximum context length")
+}
+
+/// Whether automatic compaction is enabled for overflowing turns.
+pub fn auto_compact_enabled() -> bool {
+    std::env::var(AUTO_COMPACT_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// The error returned to the client for an overflowing conversation.
+pub fn context_window_error(message: &str) -> Error {
+    Error {
+        code: CONTEXT_WINDOW_ERROR_CODE,
+        message: "Conversation exceeds the model's context window".to_string(),
+        data: Some(json!({
+            "reason": message,
+            "guidance": "Run /compact to summarize earlier history, then retry, or start a new session.",
+        })),
+    }
+}
+
+impl CodexAgent {
+    /// Compact the conversation, then resubmit the turn's input once.
+    pub(super) async fn compact_and_retry(
+        &self,
+        session_id: &SessionId,
+        conversation: &CodexConversation,
+        items: Vec<UserInput>,
+        event_handler: &events::EventHandler,
+        reason: &mut events::ReasoningAggregator,
+        output: &mut String,
+    ) -> Result<TurnOutcome, Error> {
+        info!(%session_id, "context window exceeded; compacting and retrying");
+        self.send_message_chunk(
+            session_id,
+            "📦 Conversation too long; compacting and retrying…\n\n".into(),
+        )
+        .await?;
+
+        let compact_id = conversation
+            .submit(Op::Compact)
+            .await
+            .map_err(Error::into_internal_error)?;
+        let compacted = self
+            .stream_turn(
+                session_id,
+                conversation,
+                &compact_id,
+                event_handler,
+                reason,
+                output,
+            )
+            .await?;
+        if compacted.error.is_some() {
+            return Ok(compacted);
+        }
+
+        let submit_id = conversation
+            .submit(Op::UserInput { items })
+            .await
+            .map_err(Error::into_internal_error)?;
+        self.stream_turn(
+            session_id,
+            conversation,
+            &submit_id,
+            event_handler,
+            reason,
+            output,
+        )
+        .await
+    }
+}
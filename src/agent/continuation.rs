@@ -0,0 +1,72 @@
+//! Resuming responses cut off by the output token limit.
+//!
+//! When a turn stops with `max_tokens`, the end of its output is kept in the
+//! session. `/continue` then submits a prompt asking the model to pick up
+//! exactly where it stopped, in the same conversation, so earlier tool calls,
+//! approvals and partially written files stay in place. With
+//! `CODEX_ACP_AUTO_CONTINUE=<n>` the agent does this on its own up to `n`
+//! times per prompt.
+
+use agent_client_protocol::{PromptRequest, SessionId};
+
+/// Environment variable with the number of automatic continuations per prompt.
+pub const AUTO_CONTINUE_ENV: &str = "CODEX_ACP_AUTO_CONTINUE";
+
+/// Upper bound for `CODEX_ACP_AUTO_CONTINUE`, so a runaway generation ends.
+pub const MAX_AUTO_CONTINUE: u32 = 10;
+
+/// Characters of the cut-off output quoted in the continuation prompt.
+const TAIL_CHARS: usize = 400;
+
+/// Parse a `CODEX_ACP_AUTO_CONTINUE` value: a count, or `true`/`false`.
+pub fn parse_auto_continue(value: &str) -> Option<u32> {
+    let value = value.trim().to_ascii_lowercase();
+    match value.as_str() {
+        "true" | "yes" | "on" => Some(1),
+        "false" | "no" | "off" => Some(0),
+        _ => value.parse::<u32>().ok().map(|n| n.min(MAX_AUTO_CONTINUE)),
+    }
+}
+
+/// Automatic continuations per prompt from `CODEX_ACP_AUTO_CONTINUE` (default 0).
+pub fn auto_continue_limit() -> u32 {
+    std::env::var(AUTO_CONTINUE_ENV)
+        .ok()
+        .and_then(|v| parse_auto_continue(&v))
+        .unwrap_or(0)
+}
+
+/// The last [`TAIL_CHARS`] characters of a cut-off output.
+pub fn output_tail(output: &str) -> String {
+    let start = output
+        .char_indices()
+        .rev()
+        .nth(TAIL_CHARS - 1)
+        .map(|(index, _)| index)
+        .unwrap_or(0);
+    output[start..].to_string()
+}
+
+/// The prompt resuming a response that ended with `tail`.
+pub fn continuation_prompt(tail: &str) -> String {
+    let mut prompt = String::from(
+        "Your previous response was cut off by the output token limit. Continue exactly where \
+         it stopped, without repeating what you already wrote. Earlier tool calls and file \
+         changes are still in place; if you were writing a file, finish it rather than \
+         starting over.",
+    );
+    if !tail.trim().is_empty() {
+        prompt.push_str("\n\nYour previous response ended with:\n\n");
+        prompt.push_str(tail);
+    }
+    prompt
+}
+
+/// The `/continue` prompt the agent sends itself to continue automatically.
+pub fn continue_request(session_id: SessionId) -> PromptRequest {
+    PromptRequest {
+        session_id,
+        prompt: vec!["/continue".into()],
+        meta: None,
+    }
+}
@@ -1,8 +1,11 @@
 use std::{
-    cell::RefCell,
-    collections::HashMap,
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    ops::Deref,
     rc::Rc,
     sync::{Arc, RwLock},
+    time::Instant,
 };
 
 use agent_client_protocol::{
@@ -10,7 +13,7 @@ use agent_client_protocol::{
     SessionUpdate,
 };
 use codex_core::{
-    AuthManager, CodexConversation, ConversationManager,
+    AuthManager, CodexConversation, ConversationManager, ModelProviderInfo,
     config::Config as CodexConfig,
     config_profile::ConfigProfile,
     protocol::{Op, SessionSource},
@@ -21,51 +24,164 @@ use tokio::sync::{
     oneshot::{self, Sender},
 };
 
-use crate::fs::FsBridge;
+use crate::fs::{
+    ClientOp, FsBridge,
+    tools::audit::{self, AuditLog},
+};
 
-use super::session::{ClientOp, SessionContext, SessionState};
+use super::{
+    instances::InstanceGuard,
+    instructions::InstructionStore,
+    process_info::{self, ProcessInfo},
+    sampling::SamplingOverrides,
+    sampling_proxy::SamplingProxy,
+    session::{self, SessionContext, SessionState},
+    session_events::SessionEvent,
+    session_store::SessionStore,
+    status::ReportFormat,
+    store::RecordStore,
+    thoughts::ThoughtDelivery,
+    workspace_mcp::McpTrustStore,
+};
 
 /// The main ACP agent implementation.
 ///
 /// This struct manages sessions, conversations, and coordinates between
 /// the client, Codex conversation engine, and filesystem bridge.
+///
+/// It is a cheap handle to shared state, so work such as a prompt turn can
+/// run on its own `spawn_local` task.
+#[derive(Clone)]
 pub struct CodexAgent {
+    inner: Rc<AgentState>,
+}
+
+impl Deref for CodexAgent {
+    type Target = AgentState;
+
+    fn deref(&self) -> &AgentState {
+        &self.inner
+    }
+}
+
+/// State shared by all handles of a [`CodexAgent`].
+pub struct AgentState {
     pub(super) session_update_tx: UnboundedSender<(SessionNotification, Sender<()>)>,
-    pub(super) sessions: Rc<RefCell<HashMap<String, SessionState>>>,
+    pub(super) sessions: SessionStore,
     pub(super) config: CodexConfig,
-    pub(super) profiles: HashMap<String, ConfigProfile>,
+    pub(super) profiles: RefCell<HashMap<String, ConfigProfile>>,
+    /// Providers registered at runtime via `codex/connectLocalProvider`.
+    pub(super) connected_providers: RefCell<HashMap<String, ModelProviderInfo>>,
     pub(super) conversation_manager: ConversationManager,
     pub(super) auth_manager: Arc<RwLock<Arc<AuthManager>>>,
     pub(super) client_tx: UnboundedSender<ClientOp>,
     pub(super) client_capabilities: RefCell<ClientCapabilities>,
+    /// How `/status` and `/stats` are written for this client.
+    pub(super) report_format: Cell<ReportFormat>,
+    /// How reasoning is sent, chosen at initialize.
+    pub(super) thought_delivery: Cell<ThoughtDelivery>,
     pub(super) fs_bridge: Option<Arc<FsBridge>>,
+    pub(super) record_store: RecordStore,
+    /// Instruction snippets saved with `/instructions`.
+    pub(super) instruction_store: InstructionStore,
+    /// Workspace `.codex/mcp.toml` files trusted with `/mcp trust`.
+    pub(super) mcp_trust_store: McpTrustStore,
+    /// Sampling overrides set via `/sampling`, applied to sessions created afterwards.
+    pub(super) sampling_defaults: RefCell<SamplingOverrides>,
+    /// Carries custom-provider requests, started with the first such session.
+    pub(super) sampling_proxy: RefCell<Option<Rc<SamplingProxy>>>,
+    /// When the agent was created, for the uptime in `/status`.
+    pub(super) started_at: Instant,
+    /// This agent's entry in the instance registry, once registered.
+    pub(super) instance_guard: RefCell<Option<InstanceGuard>>,
+    /// Where commands, patches and bridge writes are recorded.
+    pub(super) audit: AuditLog,
+    /// Turn ends for the [`SessionEvents`](super::SessionEvents) stream, if any.
+    pub(super) turn_events: RefCell<Option<UnboundedSender<SessionEvent>>>,
+    /// Sessions with a running idle watcher.
+    pub(super) idle_watchers: Rc<RefCell<HashSet<String>>>,
+    /// Whether `initialize` negotiated the ACP 0.4 message shape.
+    #[cfg(feature = "acp-compat")]
+    pub(super) legacy_shape: Rc<Cell<bool>>,
 }
 
 impl CodexAgent {
     /// Create a new CodexAgent with the provided configuration.
     pub fn with_config(
         session_update_tx: UnboundedSender<(SessionNotification, Sender<()>)>,
-        client_tx: UnboundedSender<super::session::ClientOp>,
+        client_tx: UnboundedSender<ClientOp>,
         config: CodexConfig,
         profiles: HashMap<String, ConfigProfile>,
         fs_bridge: Option<Arc<FsBridge>>,
     ) -> Self {
         let auth = AuthManager::shared(config.codex_home.clone(), false);
         let conversation_manager = ConversationManager::new(auth.clone(), SessionSource::Unknown);
+        let record_store = RecordStore::new(&config.codex_home);
+        let instruction_store = InstructionStore::new(&config.codex_home);
+        let mcp_trust_store = McpTrustStore::new(&config.codex_home);
+        let audit = AuditLog::new(&config.codex_home, audit::enabled_from_env());
 
-        Self {
+        let state = AgentState {
             session_update_tx,
-            sessions: Rc::new(RefCell::new(HashMap::new())),
+            sessions: SessionStore::default(),
             config,
-            profiles,
+            profiles: RefCell::new(profiles),
+            connected_providers: RefCell::new(HashMap::new()),
             conversation_manager,
             auth_manager: Arc::new(RwLock::new(auth)),
             client_tx,
             client_capabilities: RefCell::new(Default::default()),
+            report_format: Cell::new(ReportFormat::default()),
+            thought_delivery: Cell::new(ThoughtDelivery::default()),
             fs_bridge,
+            record_store,
+            instruction_store,
+            mcp_trust_store,
+            sampling_defaults: RefCell::new(SamplingOverrides::default()),
+            sampling_proxy: RefCell::new(None),
+            started_at: Instant::now(),
+            instance_guard: RefCell::new(None),
+            audit,
+            turn_events: RefCell::new(None),
+            idle_watchers: Rc::default(),
+            #[cfg(feature = "acp-compat")]
+            legacy_shape: Rc::default(),
+        };
+        Self {
+            inner: Rc::new(state),
         }
     }
 
+    /// PID, uptime and resource usage of this agent process.
+    pub(super) fn process_info(&self) -> ProcessInfo {
+        ProcessInfo {
+            pid: std::process::id(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            uptime_secs: self.started_at.elapsed().as_secs(),
+            rss_bytes: process_info::rss_bytes(),
+            open_sessions: self.sessions.count(),
+            bridge_connections: self
+                .fs_bridge
+                .as_ref()
+                .map_or(0, |bridge| bridge.connection_count()),
+        }
+    }
+
+    /// The base config plus any providers connected at runtime.
+    pub(super) fn model_config(&self) -> Cow<'_, CodexConfig> {
+        let connected = self.connected_providers.borrow();
+        if connected.is_empty() {
+            return Cow::Borrowed(&self.config);
+        }
+        let mut config = self.config.clone();
+        config.model_providers.extend(
+            connected
+                .iter()
+                .map(|(id, provider)| (id.clone(), provider.clone())),
+        );
+        Cow::Owned(config)
+    }
+
     /// Get or load the conversation for a session.
     ///
     /// This will reuse a cached conversation if available, otherwise load it
@@ -74,13 +190,9 @@ impl CodexAgent {
         &self,
         session_id: &SessionId,
     ) -> Result<Arc<CodexConversation>, Error> {
-        let conversation_opt = {
-            let sessions = self.sessions.borrow();
-            let state = sessions
-                .get(session_id.0.as_ref())
-                .ok_or_else(|| Error::invalid_params().with_data("session not found"))?;
-            state.conversation.clone()
-        };
+        let conversation_opt = self
+            .sessions
+            .try_with(session_id.0.as_ref(), |state| state.conversation.clone())?;
 
         if let Some(conversation) = conversation_opt {
             return Ok(conversation);
@@ -107,12 +219,28 @@ impl CodexAgent {
         session_id: &SessionId,
         update: SessionUpdate,
     ) -> Result<(), Error> {
-        let (tx, rx) = oneshot::channel();
-        let notification = SessionNotification {
+        self.send_notification(SessionNotification {
             session_id: session_id.clone(),
             update,
             meta: None,
-        };
+        })
+        .await
+    }
+
+    /// Send a prepared session notification to the client.
+    pub(super) async fn send_notification(
+        &self,
+        notification: SessionNotification,
+    ) -> Result<(), Error> {
+        if let SessionUpdate::ToolCall(call) = &notification.update {
+            let id = call.id.0.to_string();
+            self.with_session_state_mut(&notification.session_id, |state| {
+                state.turn_changes.record_tool_call(id)
+            });
+        }
+        #[cfg(feature = "transcripts")]
+        self.record_transcript_update(&notification);
+        let (tx, rx) = oneshot::channel();
         self.session_update_tx
             .send((notification, tx))
             .map_err(Error::into_internal_error)?;
@@ -132,7 +260,8 @@ impl CodexAgent {
         self.send_session_update(session_id, chunk).await
     }
 
-    /// Send a thought content chunk to the client.
+    /// Send a thought content chunk to the client, hidden or folded into
+    /// the message stream if the client asked for that.
     pub async fn send_thought_chunk(
         &self,
         session_id: &SessionId,
@@ -142,19 +271,53 @@ impl CodexAgent {
             content,
             meta: None,
         });
-        self.send_session_update(session_id, chunk).await
+        match self.thought_delivery.get().apply(chunk) {
+            Some(update) => self.send_session_update(session_id, update).await,
+            None => Ok(()),
+        }
     }
 
     /// Mutate session state with a function.
     ///
-    /// Returns `None` if the session is not found.
+    /// Returns `None` if the session is not found or its state is in use.
     pub(super) fn with_session_state_mut<R, F>(&self, session_id: &SessionId, f: F) -> Option<R>
     where
         F: FnOnce(&mut SessionState) -> R,
     {
-        let mut sessions = self.sessions.borrow_mut();
-        let key: &str = session_id.0.as_ref();
-        sessions.get_mut(key).map(f)
+        self.sessions.with_mut(session_id.0.as_ref(), f)
+    }
+
+    /// Like [`Self::with_session_state_mut`], with an error telling a
+    /// missing session from one whose state is in use.
+    pub(super) fn try_with_session_state_mut<R, F>(
+        &self,
+        session_id: &SessionId,
+        f: F,
+    ) -> Result<R, Error>
+    where
+        F: FnOnce(&mut SessionState) -> R,
+    {
+        self.sessions.try_with_mut(session_id.0.as_ref(), f)
+    }
+
+    /// Snapshot the turn context (approval, sandbox, model, effort) stored for a session.
+    pub(super) fn session_context(&self, session_id: &SessionId) -> Result<SessionContext, Error> {
+        self.sessions
+            .try_with(session_id.0.as_ref(), |state| SessionContext {
+                approval: state.current_approval,
+                sandbox: state.current_sandbox.clone(),
+                model: state.current_model.clone(),
+                effort: state.current_effort,
+            })
+    }
+
+    /// Whether the session is in the read-only mode.
+    pub(super) fn is_read_only(&self, session_id: &SessionId) -> bool {
+        self.sessions
+            .with(session_id.0.as_ref(), |state| {
+                session::is_read_only_mode(state.effective_mode())
+            })
+            .unwrap_or(false)
     }
 
     /// Helper to apply turn context overrides while preserving session state.
@@ -175,18 +338,7 @@ impl CodexAgent {
         F: FnOnce(&SessionContext) -> Op,
     {
         // Read current session state to build context
-        let ctx = {
-            let sessions = self.sessions.borrow();
-            let state = sessions
-                .get(session_id.0.as_ref())
-                .ok_or_else(|| Error::invalid_params().with_data("session not found"))?;
-            SessionContext {
-                approval: state.current_approval,
-                sandbox: state.current_sandbox.clone(),
-                model: state.current_model.clone(),
-                effort: state.current_effort,
-            }
-        };
+        let ctx = self.session_context(session_id)?;
 
         // Build and submit the override operation
         let op = build_override(&ctx);
@@ -0,0 +1,132 @@
+//! Custom prompts from `$CODEX_HOME/prompts`.
+//!
+//! As in the Codex CLI, every `*.md` file in the prompts directory is a slash
+//! command `/prompts:<name>`, named after the file. Optional front matter
+//! (`description:` and `argument-hint:` between `---` lines) describes it.
+//! Running the command sends the file's body as the prompt, with
+//! `$ARGUMENTS` replaced by the command's arguments and `$1`..`$9` by single
+//! ones. Custom prompts are advertised with the built-in commands and listed
+//! by `/help`.
+
+use std::path::Path;
+
+use agent_client_protocol::{AvailableCommand, AvailableCommandInput};
+
+use super::commands::{CommandCategory, CommandMeta};
+
+/// Prefix of custom prompt command names.
+pub const PROMPT_COMMAND_PREFIX: &str = "prompts:";
+
+/// A prompt file from the prompts directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomPrompt {
+    /// File name without `.md`.
+    pub name: String,
+    pub description: Option<String>,
+    pub argument_hint: Option<String>,
+    /// Prompt text after the front matter.
+    pub body: String,
+}
+
+impl CustomPrompt {
+    /// Parse a prompt file's text.
+    pub fn parse(name: &str, text: &str) -> Self {
+        let mut prompt = Self {
+            name: name.to_string(),
+            description: None,
+            argument_hint: None,
+            body: text.to_string(),
+        };
+        let Some(rest) = text.strip_prefix("---\n") else {
+            return prompt;
+        };
+        let Some((front, body)) = rest
+            .split_once("\n---\n")
+            .or_else(|| rest.strip_suffix("\n---").map(|front| (front, "")))
+        else {
+            return prompt;
+        };
+        for line in front.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"').to_string();
+            match key.trim() {
+                "description" => prompt.description = Some(value),
+                "argument-hint" | "argument_hint" => prompt.argument_hint = Some(value),
+                _ => {}
+            }
+        }
+        prompt.body = body.to_string();
+        prompt
+    }
+
+    /// The slash command name, without `/`.
+    pub fn command_name(&self) -> String {
+        format!("{PROMPT_COMMAND_PREFIX}{}", self.name)
+    }
+
+    pub fn description(&self) -> &str {
+        self.description.as_deref().unwrap_or("custom prompt")
+    }
+
+    /// The prompt with the command's arguments filled in.
+    pub fn expand(&self, args: &str) -> String {
+        let args = args.trim();
+        let words: Vec<&str> = args.split_whitespace().collect();
+        let mut out = self.body.replace("$ARGUMENTS", args);
+        for n in (1..=9).rev() {
+            let value = words.get(n - 1).copied().unwrap_or("");
+            out = out.replace(&format!("${n}"), value);
+        }
+        out
+    }
+
+    pub fn to_available_command(&self) -> AvailableCommand {
+        let meta = CommandMeta {
+            category: CommandCategory::Session,
+            icon: "📜",
+            expects_followup: true,
+            keybinding: None,
+        };
+        AvailableCommand {
+            name: self.command_name(),
+            description: self.description().to_string(),
+            input: self
+                .argument_hint
+                .clone()
+                .map(|hint| AvailableCommandInput::Unstructured { hint }),
+            meta: serde_json::to_value(&meta).ok(),
+        }
+    }
+}
+
+/// Prompt files in `dir`, sorted by name; a missing directory has none.
+pub async fn discover(dir: &Path) -> Vec<CustomPrompt> {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return Vec::new();
+    };
+    let mut prompts = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if let Ok(text) = tokio::fs::read_to_string(&path).await {
+            prompts.push(CustomPrompt::parse(name, &text));
+        }
+    }
+    prompts.sort_by(|a, b| a.name.cmp(&b.name));
+    prompts
+}
+
+/// The prompt a command name (without `/`, any case) runs.
+pub fn find_prompt<'a>(prompts: &'a [CustomPrompt], command: &str) -> Option<&'a CustomPrompt> {
+    let name = command.strip_prefix(PROMPT_COMMAND_PREFIX)?;
+    prompts
+        .iter()
+        .find(|prompt| prompt.name.eq_ignore_ascii_case(name))
+}
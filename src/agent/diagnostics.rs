@@ -0,0 +1,176 @@
+//! Editor diagnostics pushed by the client.
+//!
+//! Clients publish LSP-style diagnostics with the `codex/publishDiagnostics`
+//! extension notification. The agent keeps the latest set per file in the
+//! session state, serves them to the model through the `acp_fs`
+//! `get_diagnostics` tool, and queues newly appearing errors as context for
+//! the next turn so the model notices breakage caused by its edits.
+//!
+//! Files are keyed by absolute path: `file://` URIs, relative paths (against
+//! the session's working directory, or a named workspace root) and `./` or
+//! `..` components are resolved both when diagnostics are published and when
+//! the tool asks for one file.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use agent_client_protocol::{Error, SessionId};
+use serde::{Deserialize, Serialize};
+
+use super::{context::ContextItem, core::CodexAgent, ext::MAX_PENDING_CONTEXT_ITEMS, utils};
+
+/// LSP `DiagnosticSeverity::Error`.
+pub const SEVERITY_ERROR: u8 = 1;
+
+/// Zero-based LSP position.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+/// Zero-based LSP range.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// A single LSP-style diagnostic.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Diagnostic {
+    pub range: Range,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<u8>,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<serde_json::Value>,
+}
+
+impl Diagnostic {
+    /// Human-readable severity name; missing severities are treated as errors.
+    pub fn severity_label(&self) -> &'static str {
+        match self.severity.unwrap_or(SEVERITY_ERROR) {
+            1 => "error",
+            2 => "warning",
+            3 => "information",
+            _ => "hint",
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity.unwrap_or(SEVERITY_ERROR) == SEVERITY_ERROR
+    }
+}
+
+/// Diagnostics for a single file, as returned to the `get_diagnostics` tool.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDiagnostics {
+    pub path: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Params of the `codex/publishDiagnostics` notification.
+///
+/// Either `uri` (a `file://` URI, as in LSP) or `path` identifies the file.
+/// Publishing an empty list clears the file's diagnostics.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishDiagnosticsParams {
+    pub session_id: SessionId,
+    #[serde(default)]
+    pub uri: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl PublishDiagnosticsParams {
+    fn file_path(&self) -> Option<&str> {
+        self.path.as_deref().or(self.uri.as_deref())
+    }
+}
+
+/// The absolute path a published or requested file (a path or a `file://`
+/// URI) names, as the key diagnostics are stored under. `roots` are the
+/// session's extra workspace roots.
+pub fn diagnostic_path(cwd: &Path, roots: &[PathBuf], raw: &str) -> String {
+    let path = match utils::file_uri_path(raw) {
+        Some(path) => utils::resolve_path(cwd, &path),
+        None => {
+            let all_roots: Vec<PathBuf> = std::iter::once(cwd.to_path_buf())
+                .chain(roots.iter().cloned())
+                .collect();
+            utils::resolve_fs_path(cwd, &all_roots, raw)
+        }
+    };
+    path.display().to_string()
+}
+
+/// Collect diagnostics from a session store, optionally filtered by path.
+pub fn collect_diagnostics(
+    store: &HashMap<String, Vec<Diagnostic>>,
+    path: Option<&str>,
+    cwd: &Path,
+    roots: &[PathBuf],
+) -> Vec<FileDiagnostics> {
+    let path = path.map(|p| diagnostic_path(cwd, roots, p));
+    let mut files: Vec<FileDiagnostics> = store
+        .iter()
+        .filter(|(file, diags)| {
+            !diags.is_empty()
+                && path
+                    .as_deref()
+                    .is_none_or(|p| p == diagnostic_path(cwd, roots, file))
+        })
+        .map(|(file, diags)| FileDiagnostics {
+            path: file.clone(),
+            diagnostics: diags.clone(),
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+impl CodexAgent {
+    /// Store published diagnostics and queue errors that were not present before.
+    pub(super) fn publish_diagnostics(
+        &self,
+        params: PublishDiagnosticsParams,
+    ) -> Result<(), Error> {
+        let raw_path = params
+            .file_path()
+            .ok_or_else(|| Error::invalid_params().with_data("uri or path is required"))?
+            .to_string();
+
+        self.try_with_session_state_mut(&params.session_id, |state| {
+            let path = diagnostic_path(&self.config.cwd, &state.workspace_roots, &raw_path);
+            let previous = state.diagnostics.remove(&path).unwrap_or_default();
+            let new_errors: Vec<ContextItem> = params
+                .diagnostics
+                .iter()
+                .filter(|diag| diag.is_error() && !previous.contains(diag))
+                .map(|diag| ContextItem::Diagnostic {
+                    path: path.clone(),
+                    message: diag.message.clone(),
+                    line: Some(diag.range.start.line + 1),
+                    severity: Some(diag.severity_label().to_string()),
+                    source: diag.source.clone(),
+                })
+                .collect();
+
+            let room = MAX_PENDING_CONTEXT_ITEMS.saturating_sub(state.pending_context.len());
+            state
+                .pending_context
+                .extend(new_errors.into_iter().take(room));
+
+            if !params.diagnostics.is_empty() {
+                state.diagnostics.insert(path, params.diagnostics);
+            }
+        })
+    }
+}
@@ -0,0 +1,150 @@
+//! Per-turn prompt directives.
+//!
+//! A prompt may start with lightweight directives that tweak the turn context
+//! for that single turn only, e.g. `!ro fix the typo` or `!m gpt-5 explain`.
+//! The agent applies an `Op::OverrideTurnContext` before submitting the turn
+//! and reverts to the stored session context afterwards.
+
+use agent_client_protocol::{Error, ModelId, SessionId, SessionModeId};
+use codex_core::protocol::Op;
+
+use super::{core::CodexAgent, session};
+
+/// Directives parsed from the start of a prompt.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TurnDirectives {
+    /// Model to use for this turn (`!m <model>` / `!model <model>`).
+    pub model: Option<String>,
+    /// Session mode to use for this turn (`!ro`, `!auto`, `!full`, `!mode <id>`).
+    pub mode: Option<SessionModeId>,
+}
+
+impl TurnDirectives {
+    pub fn is_empty(&self) -> bool {
+        self.model.is_none() && self.mode.is_none()
+    }
+}
+
+/// Parse leading `!` directives from `text`.
+///
+/// Returns the parsed directives and the remaining prompt text. Parsing stops
+/// at the first token that is not a recognized directive, so prose such as
+/// `!important` is left untouched.
+pub fn parse_directives(text: &str) -> (TurnDirectives, String) {
+    let mut directives = TurnDirectives::default();
+    let mut rest = text.trim_start();
+
+    loop {
+        let (token, after) = split_token(rest);
+        let mode = match token {
+            "!ro" | "!read-only" => Some("read-only"),
+            "!auto" => Some("auto"),
+            "!full" | "!full-access" => Some("full-access"),
+            _ => None,
+        };
+        if let Some(mode) = mode {
+            directives.mode = Some(SessionModeId(mode.into()));
+            rest = after;
+            continue;
+        }
+
+        match token {
+            "!m" | "!model" | "!mode" => {
+                let (value, after_value) = split_token(after);
+                if value.is_empty() {
+                    break;
+                }
+                if token == "!mode" {
+                    directives.mode = Some(SessionModeId(value.to_string().into()));
+                } else {
+                    directives.model = Some(value.to_string());
+                }
+                rest = after_value;
+            }
+            _ => break,
+        }
+    }
+
+    (directives, rest.to_string())
+}
+
+/// Split off the first whitespace-delimited token, returning it and the trimmed remainder.
+fn split_token(text: &str) -> (&str, &str) {
+    match text.split_once(char::is_whitespace) {
+        Some((token, rest)) => (token, rest.trim_start()),
+        None => (text, ""),
+    }
+}
+
+/// The `provider@model` id a `!model` directive runs as; a bare model name
+/// keeps the provider of `session_model`.
+pub fn directive_model_id(session_model: Option<&str>, model: &str) -> String {
+    if model.contains('@') {
+        return model.to_string();
+    }
+    match session_model.and_then(|id| id.split_once('@')) {
+        Some((provider, _)) => format!("{provider}@{model}"),
+        None => model.to_string(),
+    }
+}
+
+impl CodexAgent {
+    /// Build the `(apply, revert)` turn context overrides for a set of directives.
+    ///
+    /// The revert op restores the session's stored context so directives never
+    /// leak into later turns.
+    pub(super) fn directive_overrides(
+        &self,
+        session_id: &SessionId,
+        directives: &TurnDirectives,
+    ) -> Result<(Op, Op), Error> {
+        let ctx = self.session_context(session_id)?;
+
+        let (approval, sandbox) = match &directives.mode {
+            Some(mode_id) => {
+                let preset = session::find_preset_by_mode_id(mode_id).ok_or_else(|| {
+                    Error::invalid_params()
+                        .with_data(format!("unknown mode in directive: {}", mode_id.0))
+                })?;
+                (preset.approval, preset.sandbox.clone())
+            }
+            None => (ctx.approval, ctx.sandbox.clone()),
+        };
+
+        let (model, effort) = match &directives.model {
+            // "provider@model" ids are validated against the configured profiles.
+            Some(model) if model.contains('@') => {
+                let model_ctx = session::parse_and_validate_model(
+                    &self.model_config(),
+                    &self.profiles.borrow(),
+                    &ModelId(model.clone().into()),
+                )
+                .ok_or_else(|| {
+                    Error::invalid_params()
+                        .with_data(format!("unknown model in directive: {model}"))
+                })?;
+                (Some(model_ctx.to_model_id()), model_ctx.effort)
+            }
+            Some(model) => (Some(model.clone()), ctx.effort),
+            None => (ctx.model.clone(), ctx.effort),
+        };
+
+        let apply = Op::OverrideTurnContext {
+            cwd: None,
+            approval_policy: Some(approval),
+            sandbox_policy: Some(sandbox),
+            model,
+            effort: Some(effort),
+            summary: None,
+        };
+        let revert = Op::OverrideTurnContext {
+            cwd: None,
+            approval_policy: Some(ctx.approval),
+            sandbox_policy: Some(ctx.sandbox),
+            model: ctx.model,
+            effort: Some(ctx.effort),
+            summary: None,
+        };
+        Ok((apply, revert))
+    }
+}
@@ -1,19 +1,24 @@
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use agent_client_protocol::{
-    Diff, PermissionOption, PermissionOptionId, PermissionOptionKind, RequestPermissionOutcome,
-    RequestPermissionRequest, RequestPermissionResponse, SessionId, SessionUpdate, TerminalId,
-    ToolCall, ToolCallContent, ToolCallId, ToolCallStatus, ToolCallUpdate, ToolCallUpdateFields,
-    ToolKind,
+    Diff, Error, PermissionOption, PermissionOptionId, PermissionOptionKind,
+    RequestPermissionOutcome, RequestPermissionRequest, RequestPermissionResponse, SessionId,
+    SessionUpdate, TerminalId, ToolCall, ToolCallContent, ToolCallId, ToolCallStatus,
+    ToolCallUpdate, ToolCallUpdateFields, ToolKind,
 };
 use codex_core::protocol::{FileChange, McpInvocation, ReviewDecision};
 use codex_protocol::parse_command::ParsedCommand;
 use serde_json::json;
 
-use super::utils;
+use super::{
+    approvals::{self, PendingExecApproval},
+    fs_diff, redact, risk, utils,
+};
 
 /// Arguments for "Exec Command End" update generation.
 pub struct ExecEndArgs {
@@ -34,6 +39,8 @@ pub struct ExecEndArgs {
 /// the formatting logic and to keep the agent's event loop focused.
 pub struct EventHandler {
     cwd: PathBuf,
+    /// Workspace roots that tool call paths are displayed relative to.
+    roots: Vec<PathBuf>,
     support_terminal: bool,
     permission_options: Arc<Vec<PermissionOption>>,
 }
@@ -42,11 +49,18 @@ impl EventHandler {
     /// Create a new handler with the workspace `cwd` and whether the client supports terminals.
     pub fn new(cwd: PathBuf, support_terminal: bool) -> Self {
         Self {
+            roots: vec![cwd.clone()],
             cwd,
             support_terminal,
             permission_options: default_permission_options(),
         }
     }
+
+    /// Add workspace roots besides `cwd`.
+    pub fn with_roots(mut self, roots: &[PathBuf]) -> Self {
+        self.roots.extend(roots.iter().cloned());
+        self
+    }
     // ---- MCP tool calls ----
 
     /// Build a ToolCall update for "MCP Tool Call Begin".
@@ -55,11 +69,11 @@ impl EventHandler {
         call_id: &str,
         invocation: &McpInvocation,
     ) -> SessionUpdate {
-        let (title, locations) = utils::describe_mcp_tool(invocation, &self.cwd);
+        let (title, locations) = utils::describe_mcp_tool(invocation, &self.cwd, &self.roots);
         let tool = ToolCall {
             id: ToolCallId(call_id.into()),
             title,
-            kind: ToolKind::Fetch,
+            kind: utils::mcp_tool_kind(invocation),
             status: ToolCallStatus::InProgress,
             content: Vec::new(),
             locations,
@@ -84,12 +98,33 @@ impl EventHandler {
             ToolCallStatus::Failed
         };
         let raw_output = Some(result.clone());
-        let (title, locations) = utils::describe_mcp_tool(invocation, &self.cwd);
+        let (title, mut locations) = utils::describe_mcp_tool(invocation, &self.cwd, &self.roots);
+        if invocation.server == "acp_fs" && invocation.tool == "search_text" {
+            locations.extend(utils::search_result_locations(
+                result,
+                &self.cwd,
+                &self.roots,
+            ));
+        }
+        // Previews carry a diff too, but nothing was written.
+        let preview = invocation
+            .arguments
+            .as_ref()
+            .and_then(|args| args.get("preview"))
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false);
+        let diffs = if invocation.server == "acp_fs" && success && !preview {
+            fs_diff::tool_call_diffs(result, &self.cwd)
+        } else {
+            Vec::new()
+        };
         let update = ToolCallUpdate {
             id: ToolCallId(call_id.into()),
             fields: ToolCallUpdateFields {
                 status: Some(status),
+                kind: Some(utils::mcp_tool_kind(invocation)),
                 title: Some(title),
+                content: if diffs.is_empty() { None } else { Some(diffs) },
                 locations: if locations.is_empty() {
                     None
                 } else {
@@ -118,7 +153,7 @@ impl EventHandler {
             locations,
             terminal_output,
             kind,
-        } = utils::format_command_call(cwd, parsed_cmd);
+        } = utils::format_command_call(cwd, &self.roots, parsed_cmd);
 
         let (content, meta) = if self.support_terminal && terminal_output {
             let content = vec![ToolCallContent::Terminal {
@@ -143,8 +178,8 @@ impl EventHandler {
             content,
             locations,
             raw_input: Some(json!({
-                "command": command,
-                "command_string": command.join(" "),
+                "command": redact::redact_command(command),
+                "command_string": redact::redact(&command.join(" ")),
                 "cwd": cwd
             })),
             raw_output: None,
@@ -188,7 +223,7 @@ impl EventHandler {
                 raw_output: Some(json!({
                     "exit_code": end.exit_code,
                     "duration_ms": end.duration_ms,
-                    "formatted_output": end.formatted_output,
+                    "formatted_output": redact::redact(&end.formatted_output),
                 })),
                 ..Default::default()
             },
@@ -198,11 +233,59 @@ impl EventHandler {
         SessionUpdate::ToolCallUpdate(update)
     }
 
-    /// Build a permission request for an exec approval.
+    /// Build a single permission request covering several pending exec approvals.
+    pub fn on_exec_approval_digest(
+        &self,
+        session_id: &SessionId,
+        batch: &[PendingExecApproval],
+    ) -> RequestPermissionRequest {
+        let listing = batch
+            .iter()
+            .map(|pending| format!("- `{}`", redact::redact(&pending.display())))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let first = batch.first().map_or("", |pending| pending.call_id.as_str());
+        let risk = batch
+            .iter()
+            .map(|pending| risk::assess(&pending.command))
+            .max_by_key(|assessment| assessment.level);
+
+        let update = ToolCallUpdate {
+            id: ToolCallId(first.into()),
+            fields: ToolCallUpdateFields {
+                kind: Some(ToolKind::Execute),
+                status: Some(ToolCallStatus::Pending),
+                title: Some(format!("Run {} commands", batch.len())),
+                content: Some(vec![ToolCallContent::from(listing)]),
+                ..Default::default()
+            },
+            meta: Some(json!({
+                "risk": risk,
+                "approval_digest": {
+                    "call_ids": batch.iter().map(|p| &p.call_id).collect::<Vec<_>>(),
+                    "commands": batch
+                        .iter()
+                        .map(|p| redact::redact_command(&p.command))
+                        .collect::<Vec<_>>(),
+                }
+            })),
+        };
+
+        RequestPermissionRequest {
+            session_id: session_id.clone(),
+            tool_call: update,
+            options: approvals::digest_options(),
+            meta: None,
+        }
+    }
+
+    /// Build a permission request for an exec approval, annotated with the
+    /// command's risk assessment in `meta.risk`.
     pub fn on_exec_approval_request(
         &self,
         session_id: &SessionId,
         call_id: &str,
+        command: &[String],
         cwd: &Path,
         parsed_cmd: &[ParsedCommand],
     ) -> RequestPermissionRequest {
@@ -211,7 +294,7 @@ impl EventHandler {
             locations,
             terminal_output: _,
             kind,
-        } = utils::format_command_call(cwd, parsed_cmd);
+        } = utils::format_command_call(cwd, &self.roots, parsed_cmd);
 
         let update = ToolCallUpdate {
             id: ToolCallId(call_id.into()),
@@ -226,7 +309,7 @@ impl EventHandler {
                 },
                 ..Default::default()
             },
-            meta: None,
+            meta: Some(json!({ "risk": risk::assess(command) })),
         };
 
         RequestPermissionRequest {
@@ -329,6 +412,69 @@ impl EventHandler {
 
         SessionUpdate::ToolCallUpdate(update)
     }
+
+    // ---- Dry run ----
+
+    /// Build a completed ToolCall for a command skipped in dry-run mode.
+    pub fn on_dry_run_exec(
+        &self,
+        call_id: &str,
+        cwd: &Path,
+        command: &[String],
+        parsed_cmd: &[ParsedCommand],
+    ) -> SessionUpdate {
+        let utils::FormatCommandCall {
+            title,
+            locations,
+            kind,
+            ..
+        } = utils::format_command_call(cwd, &self.roots, parsed_cmd);
+        SessionUpdate::ToolCall(ToolCall {
+            id: ToolCallId(call_id.into()),
+            title,
+            kind,
+            status: ToolCallStatus::Completed,
+            content: vec![ToolCallContent::from("skipped (dry-run)")],
+            locations,
+            raw_input: Some(json!({
+                "command": redact::redact_command(command),
+                "command_string": redact::redact(&command.join(" ")),
+                "cwd": cwd
+            })),
+            raw_output: None,
+            meta: Some(json!({ "dry_run": true })),
+        })
+    }
+
+    /// Build a completed ToolCall showing the diffs of a patch not applied in dry-run mode.
+    pub fn on_dry_run_patch(
+        &self,
+        session_id: &SessionId,
+        call_id: &str,
+        changes: &[(String, FileChange)],
+    ) -> SessionUpdate {
+        let fields = self
+            .on_apply_patch_approval_request(session_id, call_id, changes)
+            .tool_call
+            .fields;
+        let title = fields.title.unwrap_or_else(|| "Apply changes".to_string());
+        SessionUpdate::ToolCall(ToolCall {
+            id: ToolCallId(call_id.into()),
+            title: format!("{title} (dry-run, not applied)"),
+            kind: ToolKind::Edit,
+            status: ToolCallStatus::Completed,
+            content: fields.content.unwrap_or_default(),
+            locations: changes
+                .iter()
+                .map(|(path, _)| {
+                    utils::tool_location(&self.cwd, &self.roots, Path::new(path), None)
+                })
+                .collect(),
+            raw_input: None,
+            raw_output: None,
+            meta: Some(json!({ "dry_run": true })),
+        })
+    }
 }
 
 /// Map an approval response to the `ReviewDecision` used by Codex operations.
@@ -367,13 +513,93 @@ pub fn default_permission_options() -> Arc<Vec<PermissionOption>> {
     ])
 }
 
+/// Environment variable enabling live reasoning streaming (`1`/`true`).
+pub const STREAM_REASONING_ENV: &str = "CODEX_ACP_STREAM_REASONING";
+
+/// Whether new sessions stream reasoning deltas, from `CODEX_ACP_STREAM_REASONING`.
+pub fn default_stream_reasoning() -> bool {
+    std::env::var(STREAM_REASONING_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Read `NewSessionRequest.meta.streamReasoning`.
+pub fn stream_reasoning_from_meta(meta: Option<&serde_json::Value>) -> Result<Option<bool>, Error> {
+    match meta.and_then(|m| m.get("streamReasoning")) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => value
+            .as_bool()
+            .map(Some)
+            .ok_or_else(|| Error::invalid_params().with_data("streamReasoning must be a boolean")),
+    }
+}
+
+/// Environment variable choosing where plan explanations go (`thought`, `message`, `plan`).
+pub const PLAN_EXPLANATION_ENV: &str = "CODEX_ACP_PLAN_EXPLANATION";
+
+/// Where the explanation accompanying a plan update is sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlanExplanation {
+    /// As an `AgentThoughtChunk`, keeping the answer clean.
+    #[default]
+    Thought,
+    /// As an `AgentMessageChunk`, interleaved with the answer.
+    Message,
+    /// Only in the plan update's `_meta.explanation`.
+    Plan,
+}
+
+impl PlanExplanation {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "thought" => Some(Self::Thought),
+            "message" => Some(Self::Message),
+            "plan" => Some(Self::Plan),
+            _ => None,
+        }
+    }
+}
+
+/// Plan explanation target for new sessions, from `CODEX_ACP_PLAN_EXPLANATION`.
+pub fn default_plan_explanation() -> PlanExplanation {
+    std::env::var(PLAN_EXPLANATION_ENV)
+        .ok()
+        .and_then(|v| PlanExplanation::parse(&v))
+        .unwrap_or_default()
+}
+
+/// Read `NewSessionRequest.meta.planExplanation`.
+pub fn plan_explanation_from_meta(
+    meta: Option<&serde_json::Value>,
+) -> Result<Option<PlanExplanation>, Error> {
+    match meta.and_then(|m| m.get("planExplanation")) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => value
+            .as_str()
+            .and_then(PlanExplanation::parse)
+            .map(Some)
+            .ok_or_else(|| {
+                Error::invalid_params()
+                    .with_data("planExplanation must be \"thought\", \"message\", or \"plan\"")
+            }),
+    }
+}
+
 /// Aggregates reasoning deltas and sections to produce a compact text output.
 ///
 /// This mirrors the logic used by the agent to collate streaming reasoning.
 /// It can be used to decouple reasoning accumulation from the main event loop.
+///
+/// In streaming mode nothing is buffered: deltas are handed back to be sent
+/// as thought chunks right away, with a blank line between sections.
 pub struct ReasoningAggregator {
     sections: Vec<String>,
     current: String,
+    streaming: bool,
+    /// Deltas of the current reasoning item were already streamed.
+    streamed_item: bool,
+    /// Something was streamed and the next text starts a new section.
+    pending_separator: bool,
 }
 
 impl ReasoningAggregator {
@@ -381,12 +607,60 @@ impl ReasoningAggregator {
         Self {
             sections: Vec::new(),
             current: String::new(),
+            streaming: false,
+            streamed_item: false,
+            pending_separator: false,
+        }
+    }
+
+    /// An aggregator that streams deltas instead of buffering them.
+    pub fn streaming() -> Self {
+        Self {
+            streaming: true,
+            ..Self::new()
         }
     }
 
+    pub fn is_streaming(&self) -> bool {
+        self.streaming
+    }
+
+    /// Take a reasoning delta, returning the text to send now in streaming mode.
+    pub fn stream_delta(&mut self, delta: &str) -> Option<String> {
+        if !self.streaming {
+            self.append_delta(delta);
+            return None;
+        }
+        if delta.is_empty() {
+            return None;
+        }
+        let mut chunk = String::new();
+        if std::mem::take(&mut self.pending_separator) {
+            chunk.push_str("\n\n");
+        }
+        self.streamed_item = true;
+        chunk.push_str(delta);
+        Some(chunk)
+    }
+
+    /// End a reasoning item in streaming mode, returning `final_text` to send
+    /// when none of the item was streamed as deltas.
+    pub fn stream_item_end(&mut self, final_text: Option<String>) -> Option<String> {
+        if std::mem::take(&mut self.streamed_item) {
+            self.pending_separator = true;
+            return None;
+        }
+        let text = final_text.filter(|t| !t.trim().is_empty())?;
+        let separator = if self.pending_separator { "\n\n" } else { "" };
+        self.pending_separator = true;
+        Some(format!("{separator}{}", text.trim_end()))
+    }
+
     pub fn reset(&mut self) {
         self.sections.clear();
         self.current.clear();
+        self.streamed_item = false;
+        self.pending_separator = false;
     }
 
     pub fn append_delta(&mut self, delta: &str) {
@@ -394,6 +668,9 @@ impl ReasoningAggregator {
     }
 
     pub fn section_break(&mut self) {
+        if self.streamed_item {
+            self.pending_separator = true;
+        }
         if !self.current.is_empty() {
             let chunk = std::mem::take(&mut self.current);
             self.sections.push(chunk);
@@ -450,3 +727,77 @@ impl ReasoningAggregator {
         }
     }
 }
+
+/// Minimum time between progressive output updates of a running command.
+pub const EXEC_OUTPUT_FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Bytes of streamed output kept per command; older output scrolls away.
+pub const MAX_STREAMED_OUTPUT: usize = 16 * 1024;
+
+/// Collects `ExecCommandOutputDelta` chunks of running commands and turns
+/// them into periodic `ToolCallUpdate`s carrying the output so far.
+///
+/// ACP replaces tool call content on every update, so each update carries
+/// the whole (tail of the) output. The final output still comes from
+/// `ExecCommandEnd`.
+#[derive(Default)]
+pub struct ExecOutputBuffer {
+    calls: HashMap<String, StreamedOutput>,
+}
+
+#[derive(Default)]
+struct StreamedOutput {
+    bytes: Vec<u8>,
+    truncated: bool,
+    last_flush: Option<Instant>,
+}
+
+impl ExecOutputBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start collecting output for `call_id`; deltas of other calls are ignored.
+    pub fn begin(&mut self, call_id: &str) {
+        self.calls
+            .insert(call_id.to_string(), StreamedOutput::default());
+    }
+
+    /// Append a chunk, returning an update when the flush interval has passed.
+    pub fn push(&mut self, call_id: &str, chunk: &[u8], now: Instant) -> Option<SessionUpdate> {
+        let output = self.calls.get_mut(call_id)?;
+        output.bytes.extend_from_slice(chunk);
+        if output.bytes.len() > MAX_STREAMED_OUTPUT {
+            let excess = output.bytes.len() - MAX_STREAMED_OUTPUT;
+            output.bytes.drain(..excess);
+            output.truncated = true;
+        }
+        if output
+            .last_flush
+            .is_some_and(|last| now.duration_since(last) < EXEC_OUTPUT_FLUSH_INTERVAL)
+        {
+            return None;
+        }
+        output.last_flush = Some(now);
+
+        let text = String::from_utf8_lossy(&output.bytes);
+        let text = if output.truncated {
+            format!("…{}", text.trim_start_matches(char::REPLACEMENT_CHARACTER))
+        } else {
+            text.into_owned()
+        };
+        Some(SessionUpdate::ToolCallUpdate(ToolCallUpdate {
+            id: ToolCallId(call_id.into()),
+            fields: ToolCallUpdateFields {
+                content: Some(vec![ToolCallContent::from(text)]),
+                ..Default::default()
+            },
+            meta: None,
+        }))
+    }
+
+    /// Stop collecting output for a finished command.
+    pub fn finish(&mut self, call_id: &str) {
+        self.calls.remove(call_id);
+    }
+}
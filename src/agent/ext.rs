@@ -0,0 +1,259 @@
+//! ACP extension methods and notifications (`_codex/...`).
+//!
+//! Methods are matched without the leading underscore that ACP reserves for
+//! extensions, so both `codex/addContext` and `_codex/addContext` resolve.
+
+use std::path::PathBuf;
+
+use agent_client_protocol::{Error, ExtNotification, ExtRequest, ExtResponse, SessionId};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use super::{
+    context::ContextItem, core::CodexAgent, diagnostics::PublishDiagnosticsParams, history,
+    local_providers::ConnectLocalProviderParams, session, workspace_roots,
+};
+
+/// Push context items into a session; they are prepended to the next user turn.
+pub const ADD_CONTEXT_METHOD: &str = "codex/addContext";
+
+/// Return token usage for a session, broken down per model.
+pub const STATS_METHOD: &str = "codex/stats";
+
+/// Return the `/status` report of a session as structured data.
+pub const STATUS_METHOD: &str = "codex/status";
+
+/// Return PID, uptime and resource usage of the agent process.
+pub const AGENT_INFO_METHOD: &str = "codex/agentInfo";
+
+/// Probe local model servers and optionally write them to config.
+pub const CONNECT_LOCAL_PROVIDER_METHOD: &str = "codex/connectLocalProvider";
+
+/// Return the turn journal of a session.
+pub const JOURNAL_METHOD: &str = "codex/journal";
+
+/// Return a session's entries in the audit log and whether the log is intact.
+pub const AUDIT_METHOD: &str = "codex/audit";
+
+/// List persisted Codex conversations, newest first.
+pub const LIST_SESSIONS_METHOD: &str = "codex/listSessions";
+
+/// Let a session keep prompting past the shared spend budget.
+pub const OVERRIDE_BUDGET_METHOD: &str = "codex/overrideBudget";
+
+/// Change the reasoning summary setting for a session.
+pub const SET_REASONING_SUMMARY_METHOD: &str = "codex/setReasoningSummary";
+
+/// Replace the extra workspace roots of a session.
+pub const SET_WORKSPACE_ROOTS_METHOD: &str = "codex/setWorkspaceRoots";
+
+/// Publish LSP-style diagnostics for a file (notification).
+pub const PUBLISH_DIAGNOSTICS_METHOD: &str = "codex/publishDiagnostics";
+
+/// Upper bound on queued context items per session to keep turns bounded.
+pub const MAX_PENDING_CONTEXT_ITEMS: usize = 64;
+
+/// Conversations returned by `codex/listSessions` unless a limit is given.
+pub const DEFAULT_SESSION_LIST_LIMIT: usize = 50;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionParams {
+    session_id: SessionId,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListSessionsParams {
+    #[serde(default)]
+    limit: Option<usize>,
+    /// Only list conversations started in this directory.
+    #[serde(default)]
+    cwd: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetReasoningSummaryParams {
+    session_id: SessionId,
+    summary: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetWorkspaceRootsParams {
+    session_id: SessionId,
+    roots: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddContextParams {
+    session_id: SessionId,
+    items: Vec<ContextItem>,
+}
+
+/// Normalize an extension method name by stripping the ACP `_` prefix.
+fn normalize_method(method: &str) -> &str {
+    method.strip_prefix('_').unwrap_or(method)
+}
+
+/// Deserialize extension params, mapping failures to `invalid_params`.
+fn parse_params<T: for<'de> Deserialize<'de>>(
+    params: &serde_json::value::RawValue,
+) -> Result<T, Error> {
+    serde_json::from_str(params.get())
+        .map_err(|err| Error::invalid_params().with_data(err.to_string()))
+}
+
+impl CodexAgent {
+    /// Handle extension method calls.
+    pub(super) async fn ext_method(&self, args: ExtRequest) -> Result<ExtResponse, Error> {
+        info!(method = %args.method, params = ?args.params, "Received extension method call");
+        let response = match normalize_method(&args.method) {
+            ADD_CONTEXT_METHOD => {
+                let params: AddContextParams = parse_params(&args.params)?;
+                let queued = self.queue_context(&params.session_id, params.items)?;
+                json!({ "queued": queued })
+            }
+            STATS_METHOD => {
+                let params: SessionParams = parse_params(&args.params)?;
+                self.session_stats(&params.session_id)?
+            }
+            STATUS_METHOD => {
+                let params: SessionParams = parse_params(&args.params)?;
+                self.try_with_session_state_mut(&params.session_id, |_| ())?;
+                json!(self.status_report(&params.session_id).await)
+            }
+            AGENT_INFO_METHOD => json!(self.process_info()),
+            SET_REASONING_SUMMARY_METHOD => {
+                let params: SetReasoningSummaryParams = parse_params(&args.params)?;
+                let summary =
+                    session::parse_reasoning_summary(&params.summary).ok_or_else(|| {
+                        Error::invalid_params()
+                            .with_data("summary must be one of auto, concise, detailed, none")
+                    })?;
+                self.set_reasoning_summary(&params.session_id, summary)
+                    .await?;
+                json!({ "summary": summary.to_string() })
+            }
+            JOURNAL_METHOD => {
+                let params: SessionParams = parse_params(&args.params)?;
+                let entries = self
+                    .record_store
+                    .load_journal(params.session_id.0.as_ref())
+                    .await;
+                json!({ "sessionId": params.session_id, "turns": entries })
+            }
+            AUDIT_METHOD => {
+                let params: SessionParams = parse_params(&args.params)?;
+                self.audit_trail(&params.session_id).await?
+            }
+            LIST_SESSIONS_METHOD => {
+                let params: Option<ListSessionsParams> = parse_params(&args.params)?;
+                self.list_sessions(params.unwrap_or_default()).await
+            }
+            OVERRIDE_BUDGET_METHOD => {
+                let params: SessionParams = parse_params(&args.params)?;
+                self.override_budget(&params.session_id).await?
+            }
+            SET_WORKSPACE_ROOTS_METHOD => {
+                let params: SetWorkspaceRootsParams = parse_params(&args.params)?;
+                let roots = workspace_roots::parse_roots(&params.roots)?;
+                let roots = self.set_workspace_roots(&params.session_id, roots)?;
+                json!({ "sessionId": params.session_id, "roots": roots })
+            }
+            CONNECT_LOCAL_PROVIDER_METHOD => {
+                let params: ConnectLocalProviderParams = parse_params(&args.params)?;
+                self.connect_local_provider(params).await?
+            }
+            _ => return Err(Error::method_not_found()),
+        };
+        Ok(serde_json::value::to_raw_value(&response)?.into())
+    }
+
+    /// Handle extension notifications.
+    ///
+    /// Unknown notifications are logged and ignored.
+    pub(super) async fn ext_notification(&self, args: ExtNotification) -> Result<(), Error> {
+        info!(method = %args.method, params = ?args.params, "Received extension notification call");
+        match normalize_method(&args.method) {
+            PUBLISH_DIAGNOSTICS_METHOD => {
+                let params: PublishDiagnosticsParams = parse_params(&args.params)?;
+                self.publish_diagnostics(params)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Usage totals for a session plus a per-model breakdown.
+    fn session_stats(&self, session_id: &SessionId) -> Result<serde_json::Value, Error> {
+        self.try_with_session_state_mut(session_id, |state| {
+            let by_model: Vec<serde_json::Value> = state
+                .model_usage
+                .by_model()
+                .iter()
+                .map(|(model, usage)| json!({ "model": model, "usage": usage }))
+                .collect();
+            json!({
+                "sessionId": session_id,
+                "total": state.token_usage,
+                "limit": state.max_session_tokens,
+                "byModel": by_model,
+            })
+        })
+    }
+
+    /// Summaries of persisted conversations, newest first.
+    async fn list_sessions(&self, params: ListSessionsParams) -> serde_json::Value {
+        let limit = params.limit.unwrap_or(DEFAULT_SESSION_LIST_LIMIT);
+        let mut sessions = Vec::new();
+        for (path, modified) in history::rollout_files(&self.config.codex_home).await {
+            if sessions.len() >= limit {
+                break;
+            }
+            let mut summary = match history::read_rollout_summary(&path).await {
+                Ok(Some(summary)) => summary,
+                Ok(None) => continue,
+                Err(err) => {
+                    warn!(path = %path.display(), error = %err, "skipping unreadable rollout");
+                    continue;
+                }
+            };
+            if params
+                .cwd
+                .as_ref()
+                .is_some_and(|cwd| summary.cwd.as_ref() != Some(cwd))
+            {
+                continue;
+            }
+            summary.updated_at = Some(modified);
+            summary.token_usage = self
+                .record_store
+                .load(&summary.session_id)
+                .await
+                .and_then(|record| record.token_usage);
+            sessions.push(summary);
+        }
+        json!({ "sessions": sessions })
+    }
+
+    /// Queue pushed context items for the next user turn, returning how many are pending.
+    fn queue_context(
+        &self,
+        session_id: &SessionId,
+        items: Vec<ContextItem>,
+    ) -> Result<usize, Error> {
+        self.try_with_session_state_mut(session_id, |state| {
+            if state.pending_context.len() + items.len() > MAX_PENDING_CONTEXT_ITEMS {
+                return Err(Error::invalid_params().with_data(format!(
+                    "too many pending context items (max {MAX_PENDING_CONTEXT_ITEMS})"
+                )));
+            }
+            state.pending_context.extend(items);
+            Ok(state.pending_context.len())
+        })
+        .and_then(|queued| queued)
+    }
+}
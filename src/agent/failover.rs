@@ -0,0 +1,121 @@
+//! Provider failover for turns that end in an error.
+//!
+//! A session may carry an ordered list of fallback `provider@model` ids, taken
+//! from `NewSessionRequest.meta.fallbackModels` or, when absent, from the
+//! comma-separated `CODEX_ACP_FALLBACK_MODELS` environment variable. When a
+//! turn ends with a Codex error (Codex has already retried stream errors and
+//! rate limits on its own by then), the turn is retried on the next model in
+//! the list. The switch only lasts for that turn.
+//!
+//! Codex records the user's input in the conversation history before it asks
+//! the model, so the failed input is already there: the retry only asks the
+//! fallback model to answer it, instead of sending the input a second time.
+//! A turn that ran, or asked to run, a command, tool or patch before failing
+//! is not retried, since its side effects would happen twice.
+
+use agent_client_protocol::{Error, ModelId, SessionId};
+use codex_core::protocol::Op;
+use codex_protocol::user_input::UserInput;
+use serde_json::Value;
+use tracing::warn;
+
+use super::{
+    context_window,
+    core::CodexAgent,
+    session::{self, ModelContext},
+    stop_reason,
+};
+
+/// Environment variable holding the default fallback chain.
+pub const FALLBACK_MODELS_ENV: &str = "CODEX_ACP_FALLBACK_MODELS";
+
+/// Split a comma-separated `provider@model` list, dropping empty entries.
+pub fn parse_fallback_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Read `fallbackModels` (array of `provider@model` strings) from session meta.
+pub fn fallback_models_from_meta(meta: Option<&Value>) -> Result<Option<Vec<String>>, Error> {
+    match meta.and_then(|m| m.get("fallbackModels")) {
+        None => Ok(None),
+        Some(value) => serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|e| Error::invalid_params().with_data(format!("invalid fallbackModels: {e}"))),
+    }
+}
+
+/// Fallback chain used when a session does not specify one.
+pub fn default_fallback_models() -> Vec<String> {
+    std::env::var(FALLBACK_MODELS_ENV)
+        .map(|v| parse_fallback_list(&v))
+        .unwrap_or_default()
+}
+
+/// Whether a turn that failed with `error` may be retried on a fallback model.
+///
+/// Context-window overflows are compacted instead, and token-limit and refusal
+/// endings are the model's answer.
+pub fn can_fail_over(error: &str, tool_activity: bool) -> bool {
+    !tool_activity
+        && !context_window::is_context_window_error(error)
+        && stop_reason::classify(Some(error), "").is_none()
+}
+
+/// Ask the fallback model to answer the input already in the history.
+pub fn retry_op() -> Op {
+    Op::UserInput {
+        items: vec![UserInput::Text {
+            text: "The previous attempt to answer the last user message failed with a provider \
+                   error. Answer that message now."
+                .to_string(),
+        }],
+    }
+}
+
+/// Switch the active model for the remainder of the current turn.
+///
+/// Approval and sandbox are left untouched so per-turn directives stay in effect.
+pub fn failover_op(model_ctx: &ModelContext) -> Op {
+    Op::OverrideTurnContext {
+        cwd: None,
+        approval_policy: None,
+        sandbox_policy: None,
+        model: Some(model_ctx.to_model_id()),
+        effort: Some(model_ctx.effort),
+        summary: None,
+    }
+}
+
+impl CodexAgent {
+    /// Resolve the session's fallback chain, skipping entries that are not configured
+    /// and the model the session is already using.
+    pub(super) fn fallback_chain(&self, session_id: &SessionId) -> Vec<ModelContext> {
+        let Some((models, current)) = self.with_session_state_mut(session_id, |state| {
+            (state.fallback_models.clone(), state.current_model.clone())
+        }) else {
+            return Vec::new();
+        };
+        let config = self.model_config();
+        let profiles = self.profiles.borrow();
+        models
+            .into_iter()
+            .filter(|id| current.as_deref() != Some(id.as_str()))
+            .filter_map(|id| {
+                let ctx = session::parse_and_validate_model(
+                    &config,
+                    &profiles,
+                    &ModelId(id.clone().into()),
+                );
+                if ctx.is_none() {
+                    warn!(model = %id, "ignoring unknown fallback model");
+                }
+                ctx
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,92 @@
+//! `acp_fs` edits as ACP diffs.
+//!
+//! The `acp_fs` edit tools return a unified diff tagged with
+//! `_meta.codex_fs_diff`. When such a call completes, each hunk of that diff
+//! becomes a [`Diff`] in the tool call content, so clients like Zed show a
+//! diff view for bridge edits just as they do for Codex patches.
+
+use std::path::{Path, PathBuf};
+
+use agent_client_protocol::{Diff, ToolCallContent};
+use serde_json::Value;
+
+use super::utils;
+
+/// The old and new side of each hunk of a unified diff, context included.
+pub fn diff_hunks(unified: &str) -> Vec<(String, String)> {
+    let mut hunks = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    for line in unified.lines() {
+        if line.starts_with("@@") {
+            hunks.extend(current.take());
+            current = Some((String::new(), String::new()));
+            continue;
+        }
+        let Some((old, new)) = current.as_mut() else {
+            // File headers before the first hunk.
+            continue;
+        };
+        if let Some(removed) = line.strip_prefix('-') {
+            old.push_str(removed);
+            old.push('\n');
+        } else if let Some(added) = line.strip_prefix('+') {
+            new.push_str(added);
+            new.push('\n');
+        } else if let Some(context) = line.strip_prefix(' ') {
+            for side in [&mut *old, &mut *new] {
+                side.push_str(context);
+                side.push('\n');
+            }
+        } else if line.is_empty() {
+            // Some writers drop the space of an empty context line.
+            old.push('\n');
+            new.push('\n');
+        }
+        // `\ No newline at end of file` and anything else is ignored.
+    }
+    hunks.extend(current);
+    hunks
+}
+
+/// [`Diff`] content for every `codex_fs_diff` text in an MCP tool result,
+/// one per hunk, with paths resolved against `cwd`.
+pub fn tool_call_diffs(result: &Value, cwd: &Path) -> Vec<ToolCallContent> {
+    let mut found = Vec::new();
+    collect(result, &mut found);
+    found
+        .into_iter()
+        .flat_map(|(path, text)| {
+            let path = utils::resolve_path(cwd, Path::new(path));
+            diff_hunks(text)
+                .into_iter()
+                .map(move |(old, new)| diff(path.clone(), old, new))
+        })
+        .collect()
+}
+
+fn diff(path: PathBuf, old: String, new: String) -> ToolCallContent {
+    ToolCallContent::from(Diff {
+        path,
+        old_text: Some(old),
+        new_text: new,
+        meta: None,
+    })
+}
+
+/// `(path, diff text)` of each text content carrying `codex_fs_diff` meta.
+fn collect<'a>(value: &'a Value, found: &mut Vec<(&'a str, &'a str)>) {
+    match value {
+        Value::Object(map) => {
+            let text = map.get("text").and_then(Value::as_str);
+            let path = map
+                .values()
+                .find_map(|meta| meta.get("codex_fs_diff")?.get("path")?.as_str());
+            match (text, path) {
+                (Some(text), Some(path)) => found.push((path, text)),
+                _ => map.values().for_each(|value| collect(value, found)),
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect(item, found)),
+        _ => {}
+    }
+}
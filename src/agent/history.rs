@@ -0,0 +1,330 @@
+//! Conversation history from Codex rollout files.
+//!
+//! Codex records each conversation as JSONL under `$CODEX_HOME/sessions`.
+//! On `session/load` the transcript is read back and streamed to the client
+//! as `UserMessageChunk` / `AgentMessageChunk` / `AgentThoughtChunk` and
+//! `ToolCall` updates so the IDE can render the whole conversation.
+//!
+//! The same files back the `codex/listSessions` extension method, which
+//! summarizes persisted conversations for a "resume conversation" picker.
+//!
+//! Lines are read as loose JSON (`{"type": ..., "payload": {...}}`) rather
+//! than Codex's internal types; unknown lines are skipped.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use agent_client_protocol::{
+    ContentChunk, SessionUpdate, ToolCall, ToolCallContent, ToolCallId, ToolCallStatus,
+    ToolCallUpdate, ToolCallUpdateFields, ToolKind,
+};
+use codex_core::protocol::TokenUsage;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use super::redact;
+use crate::fs::text;
+
+/// Bytes of tool output replayed per call.
+const MAX_REPLAYED_OUTPUT: usize = 8 * 1024;
+
+/// Characters of the first user message used as a conversation title.
+const TITLE_CHARS: usize = 80;
+
+/// A transcript entry recovered from a rollout line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryItem {
+    User(String),
+    Agent(String),
+    Reasoning(String),
+    ToolCall {
+        call_id: String,
+        title: String,
+        kind: ToolKind,
+        raw_input: Value,
+    },
+    ToolResult {
+        call_id: String,
+        output: String,
+        success: Option<bool>,
+    },
+}
+
+/// Parse one rollout line; lines that are not part of the transcript yield `None`.
+pub fn parse_rollout_line(line: &str) -> Option<HistoryItem> {
+    let value: Value = serde_json::from_str(line).ok()?;
+    let payload = value.get("payload")?;
+    let kind = payload.get("type")?.as_str()?;
+    let text = |key: &str| payload.get(key).and_then(Value::as_str).map(str::to_string);
+
+    match (value.get("type")?.as_str()?, kind) {
+        ("event_msg", "user_message") => text("message").map(HistoryItem::User),
+        ("event_msg", "agent_message") => text("message").map(HistoryItem::Agent),
+        ("event_msg", "agent_reasoning") => text("text").map(HistoryItem::Reasoning),
+        ("response_item", "function_call") => {
+            let name = text("name")?;
+            let raw_input = text("arguments")
+                .and_then(|args| serde_json::from_str(&args).ok())
+                .unwrap_or(Value::Null);
+            Some(tool_call(text("call_id")?, &name, raw_input))
+        }
+        ("response_item", "custom_tool_call") => {
+            let name = text("name")?;
+            let raw_input = payload.get("input").cloned().unwrap_or(Value::Null);
+            Some(tool_call(text("call_id")?, &name, raw_input))
+        }
+        ("response_item", "local_shell_call") => {
+            let raw_input = payload.get("action").cloned().unwrap_or(Value::Null);
+            let call_id = text("call_id").or_else(|| text("id"))?;
+            Some(tool_call(call_id, "shell", raw_input))
+        }
+        ("response_item", "function_call_output" | "custom_tool_call_output") => {
+            let (output, success) = match payload.get("output")? {
+                Value::String(output) => (output.clone(), None),
+                other => (
+                    other
+                        .get("content")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    other.get("success").and_then(Value::as_bool),
+                ),
+            };
+            Some(HistoryItem::ToolResult {
+                call_id: text("call_id")?,
+                output,
+                success,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Title and kind for a replayed tool call.
+fn tool_call(call_id: String, name: &str, mut raw_input: Value) -> HistoryItem {
+    for key in ["command", "cmd"] {
+        if let Some(command) = raw_input.get_mut(key) {
+            redact_value(command);
+        }
+    }
+    let command = raw_input
+        .get("command")
+        .or_else(|| raw_input.get("cmd"))
+        .map(|command| match command {
+            Value::Array(parts) => parts
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(" "),
+            Value::String(command) => command.clone(),
+            other => other.to_string(),
+        });
+    let (title, kind) = match (name, command) {
+        ("shell" | "exec_command" | "local_shell", Some(command)) => {
+            (format!("Run {command}"), ToolKind::Execute)
+        }
+        ("apply_patch", _) => ("Apply patch".to_string(), ToolKind::Edit),
+        (name, _) => match name.split_once("__") {
+            // MCP tools are exposed to the model as `<server>__<tool>`.
+            Some((server, tool)) => (format!("{server}.{tool}"), ToolKind::Fetch),
+            None => (name.to_string(), ToolKind::Other),
+        },
+    };
+    HistoryItem::ToolCall {
+        call_id,
+        title,
+        kind,
+        raw_input,
+    }
+}
+
+/// Redact the strings of a replayed command argument in place.
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::String(text) => *text = redact::redact(text),
+        Value::Array(parts) => parts.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+/// Read the transcript from a rollout file.
+pub async fn read_rollout_history(path: &Path) -> io::Result<Vec<HistoryItem>> {
+    let text = tokio::fs::read_to_string(path).await?;
+    Ok(text.lines().filter_map(parse_rollout_line).collect())
+}
+
+/// Convert transcript entries into the session updates that replay them.
+pub fn history_updates(items: Vec<HistoryItem>) -> Vec<SessionUpdate> {
+    items
+        .into_iter()
+        .map(|item| match item {
+            HistoryItem::User(text) => SessionUpdate::UserMessageChunk(ContentChunk {
+                content: text.into(),
+                meta: None,
+            }),
+            HistoryItem::Agent(text) => SessionUpdate::AgentMessageChunk(ContentChunk {
+                content: text.into(),
+                meta: None,
+            }),
+            HistoryItem::Reasoning(text) => SessionUpdate::AgentThoughtChunk(ContentChunk {
+                content: text.into(),
+                meta: None,
+            }),
+            HistoryItem::ToolCall {
+                call_id,
+                title,
+                kind,
+                raw_input,
+            } => SessionUpdate::ToolCall(ToolCall {
+                id: ToolCallId(call_id.into()),
+                title,
+                kind,
+                status: ToolCallStatus::InProgress,
+                content: Vec::new(),
+                locations: Vec::new(),
+                raw_input: Some(raw_input),
+                raw_output: None,
+                meta: None,
+            }),
+            HistoryItem::ToolResult {
+                call_id,
+                output,
+                success,
+            } => {
+                let output = text::elide_tail(&output, MAX_REPLAYED_OUTPUT);
+                let status = if success == Some(false) {
+                    ToolCallStatus::Failed
+                } else {
+                    ToolCallStatus::Completed
+                };
+                SessionUpdate::ToolCallUpdate(ToolCallUpdate {
+                    id: ToolCallId(call_id.into()),
+                    fields: ToolCallUpdateFields {
+                        status: Some(status),
+                        content: (!output.is_empty()).then(|| vec![ToolCallContent::from(output)]),
+                        ..Default::default()
+                    },
+                    meta: None,
+                })
+            }
+        })
+        .collect()
+}
+
+/// A persisted conversation as listed by `codex/listSessions`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationSummary {
+    pub session_id: String,
+    /// First line of the first user message.
+    pub title: Option<String>,
+    pub cwd: Option<PathBuf>,
+    /// Seconds since the Unix epoch when the rollout was last written.
+    pub updated_at: Option<u64>,
+    pub token_usage: Option<TokenUsage>,
+}
+
+/// Shorten a user message to a one-line title.
+pub fn title_from_message(message: &str) -> Option<String> {
+    let line = message.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let mut title: String = line.chars().take(TITLE_CHARS).collect();
+    if line.chars().count() > TITLE_CHARS {
+        title.push('…');
+    }
+    Some(title)
+}
+
+/// Summarize a rollout from its leading lines.
+///
+/// Consumes lines until the first user message, so callers can stop reading
+/// there. Returns `None` when no session metadata line was seen.
+pub fn summarize_rollout<I, S>(lines: I) -> Option<ConversationSummary>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut summary: Option<ConversationSummary> = None;
+    for line in lines {
+        let Ok(value) = serde_json::from_str::<Value>(line.as_ref()) else {
+            continue;
+        };
+        let payload = value.get("payload");
+        match value.get("type").and_then(Value::as_str) {
+            Some("session_meta") if summary.is_none() => {
+                let payload = payload?;
+                summary = Some(ConversationSummary {
+                    session_id: payload.get("id")?.as_str()?.to_string(),
+                    title: None,
+                    cwd: payload
+                        .get("cwd")
+                        .and_then(Value::as_str)
+                        .map(PathBuf::from),
+                    updated_at: None,
+                    token_usage: None,
+                });
+            }
+            _ => {
+                if let Some(HistoryItem::User(message)) = parse_rollout_line(line.as_ref()) {
+                    if let Some(summary) = summary.as_mut() {
+                        summary.title = title_from_message(&message);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+    summary
+}
+
+/// Rollout files under `$CODEX_HOME/sessions`, newest first.
+pub async fn rollout_files(codex_home: &Path) -> Vec<(PathBuf, u64)> {
+    let mut files = Vec::new();
+    let mut dirs = vec![codex_home.join("sessions")];
+    while let Some(dir) = dirs.pop() {
+        let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let Ok(meta) = entry.metadata().await else {
+                continue;
+            };
+            if meta.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with("rollout-") && name.ends_with(".jsonl") {
+                let modified = meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                files.push((path, modified));
+            }
+        }
+    }
+    files.sort_by(|a, b| b.1.cmp(&a.1));
+    files
+}
+
+/// Read just enough of a rollout to summarize it.
+pub async fn read_rollout_summary(path: &Path) -> io::Result<Option<ConversationSummary>> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut lines = BufReader::new(file).lines();
+    let mut head = Vec::new();
+    while let Some(line) = lines.next_line().await? {
+        let is_user_message = matches!(parse_rollout_line(&line), Some(HistoryItem::User(_)));
+        head.push(line);
+        if is_user_message {
+            break;
+        }
+    }
+    Ok(summarize_rollout(head))
+}
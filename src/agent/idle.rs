@@ -0,0 +1,211 @@
+//! Inactivity fallback to read-only mode.
+//!
+//! A session left unattended in `auto` or `full-access` mode can keep
+//! editing files and running commands. When an idle timeout is configured,
+//! a per-session watcher switches the session to `read-only` once the user
+//! has not interacted with it (prompt, mode change, cancel) for that long,
+//! and notifies the client with a `CurrentModeUpdate`. A running turn counts
+//! as activity, so the mode never changes under it. Each session has at most
+//! one watcher.
+//!
+//! Sources, highest priority first:
+//! - `NewSessionRequest.meta.idleReadOnlyMinutes` (`0` disables);
+//! - the `CODEX_ACP_IDLE_READ_ONLY_MINUTES` environment variable.
+
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use agent_client_protocol::{
+    ContentChunk, CurrentModeUpdate, Error, SessionId, SessionModeId, SessionNotification,
+    SessionUpdate,
+};
+use codex_core::protocol::Op;
+use serde_json::Value;
+use tokio::{sync::oneshot, task};
+use tracing::{info, warn};
+
+use super::{
+    core::CodexAgent,
+    session::{self, SessionState},
+};
+
+/// Environment variable with the default idle timeout in minutes.
+pub const IDLE_READ_ONLY_ENV: &str = "CODEX_ACP_IDLE_READ_ONLY_MINUTES";
+
+/// Longest interval between idle checks.
+const MAX_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+fn minutes(value: u64) -> Option<Duration> {
+    (value > 0).then(|| Duration::from_secs(value * 60))
+}
+
+/// The idle timeout for new sessions from `CODEX_ACP_IDLE_READ_ONLY_MINUTES`, if set.
+pub fn default_idle_timeout() -> Option<Duration> {
+    std::env::var(IDLE_READ_ONLY_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .and_then(minutes)
+}
+
+/// Read `NewSessionRequest.meta.idleReadOnlyMinutes`: `Some(None)` disables the fallback.
+pub fn idle_timeout_from_meta(meta: Option<&Value>) -> Result<Option<Option<Duration>>, Error> {
+    match meta.and_then(|m| m.get("idleReadOnlyMinutes")) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value.as_u64().map(|v| Some(minutes(v))).ok_or_else(|| {
+            Error::invalid_params().with_data("idleReadOnlyMinutes must be a non-negative integer")
+        }),
+    }
+}
+
+/// Whether a session in `mode` idle since `last_activity` should drop to read-only.
+///
+/// Dry-run sessions never change anything, so they are left alone.
+pub fn should_drop_to_read_only(
+    mode: &SessionModeId,
+    last_activity: Instant,
+    now: Instant,
+    timeout: Duration,
+) -> bool {
+    !session::is_read_only_mode(mode)
+        && !session::is_dry_run_mode(mode)
+        && now.saturating_duration_since(last_activity) >= timeout
+}
+
+/// Whether the session should drop to read-only now. A running turn holds
+/// `turn_lock`; it counts as activity, so the timeout starts after it ends.
+pub fn idle_check(state: &mut SessionState, now: Instant, timeout: Duration) -> bool {
+    if state.turn_lock.try_lock().is_err() {
+        state.last_activity = now;
+        return false;
+    }
+    should_drop_to_read_only(&state.current_mode, state.last_activity, now, timeout)
+}
+
+/// Marks a session as watched until dropped.
+struct WatchGuard {
+    watchers: Rc<RefCell<HashSet<String>>>,
+    session_id: String,
+}
+
+impl WatchGuard {
+    /// `None` if the session already has a watcher.
+    fn register(watchers: &Rc<RefCell<HashSet<String>>>, session_id: &SessionId) -> Option<Self> {
+        let session_id = session_id.0.to_string();
+        watchers
+            .borrow_mut()
+            .insert(session_id.clone())
+            .then(|| Self {
+                watchers: watchers.clone(),
+                session_id,
+            })
+    }
+}
+
+impl Drop for WatchGuard {
+    fn drop(&mut self) {
+        self.watchers.borrow_mut().remove(&self.session_id);
+    }
+}
+
+impl CodexAgent {
+    /// Record user interaction with a session, resetting its idle timer.
+    pub(super) fn touch_session(&self, session_id: &SessionId) {
+        self.with_session_state_mut(session_id, |state| {
+            state.last_activity = Instant::now();
+        });
+    }
+
+    /// Watch a session and switch it to read-only after `timeout` without interaction.
+    ///
+    /// Does nothing if the session is already watched. The watcher ends when
+    /// the session is removed.
+    pub(super) fn spawn_idle_watch(&self, session_id: SessionId, timeout: Duration) {
+        let Some(watching) = WatchGuard::register(&self.idle_watchers, &session_id) else {
+            return;
+        };
+        let sessions = self.sessions.clone();
+        let tx_updates = self.session_update_tx.clone();
+        let interval = timeout.min(MAX_CHECK_INTERVAL);
+        let Some(preset) = session::find_preset_by_mode_id(&SessionModeId("read-only".into()))
+        else {
+            return;
+        };
+
+        task::spawn_local(async move {
+            let _watching = watching;
+            loop {
+                tokio::time::sleep(interval).await;
+
+                // Decide and update state before any await.
+                let switched = sessions.with_mut(session_id.0.as_ref(), |state| {
+                    if !idle_check(state, Instant::now(), timeout) {
+                        return None;
+                    }
+                    let op = Op::OverrideTurnContext {
+                        cwd: None,
+                        approval_policy: Some(preset.approval),
+                        sandbox_policy: Some(preset.sandbox.clone()),
+                        model: state.current_model.clone(),
+                        effort: Some(state.current_effort),
+                        summary: None,
+                    };
+                    let previous = state.current_mode.clone();
+                    state.current_approval = preset.approval;
+                    state.current_sandbox = preset.sandbox.clone();
+                    state.current_mode = SessionModeId(preset.id.into());
+                    Some((state.conversation.clone(), op, previous))
+                });
+                let (conversation, op, previous) = match switched {
+                    // The session is gone.
+                    None => return,
+                    Some(None) => continue,
+                    Some(Some(switch)) => switch,
+                };
+
+                info!(session_id = %session_id.0, from = %previous.0, "idle timeout, switching to read-only");
+                if let Some(conversation) = conversation
+                    && let Err(err) = conversation.submit(op).await
+                {
+                    warn!(error = %err, "failed to apply read-only mode after inactivity");
+                }
+
+                let updates = [
+                    SessionUpdate::CurrentModeUpdate(CurrentModeUpdate {
+                        current_mode_id: SessionModeId(preset.id.into()),
+                        meta: None,
+                    }),
+                    SessionUpdate::AgentMessageChunk(ContentChunk {
+                        content: format!(
+                            "🔒 Switched from {} to read-only after {} minute(s) without interaction.\n\n",
+                            previous.0,
+                            timeout.as_secs() / 60
+                        )
+                        .into(),
+                        meta: None,
+                    }),
+                ];
+                for update in updates {
+                    let (tx, rx) = oneshot::channel();
+                    if tx_updates
+                        .send((
+                            SessionNotification {
+                                session_id: session_id.clone(),
+                                update,
+                                meta: None,
+                            },
+                            tx,
+                        ))
+                        .is_err()
+                    {
+                        return;
+                    }
+                    let _ = rx.await;
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,279 @@
+//! Image preprocessing for prompt input.
+//!
+//! Large screenshots can blow up request size, so images are checked against
+//! per-model limits before being turned into `UserInput::Image`. Oversized
+//! images are downscaled and re-encoded as JPEG; images that still do not fit
+//! (or exceed the per-prompt count) are skipped, and either outcome is
+//! reported to the user as a warning chunk.
+//!
+//! Image resource links (`file://` URIs) and embedded image blobs are read and
+//! submitted as image input too, as long as the model accepts images.
+
+use agent_client_protocol::{Error, SessionId};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use codex_protocol::user_input::UserInput;
+use image::{DynamicImage, codecs::jpeg::JpegEncoder, imageops::FilterType};
+
+use super::{core::CodexAgent, utils};
+
+/// JPEG quality used when re-encoding downscaled images.
+const JPEG_QUALITY: u8 = 85;
+
+/// Size limits applied to images in a single prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageLimits {
+    /// Longest edge in pixels; larger images are downscaled.
+    pub max_dimension: u32,
+    /// Maximum encoded size in bytes per image.
+    pub max_bytes: usize,
+    /// Maximum number of images accepted per prompt.
+    pub max_images: usize,
+}
+
+impl ImageLimits {
+    /// Limits for the given model name (bare or `provider@model`).
+    pub fn for_model(model: &str) -> Self {
+        let name = model.rsplit('@').next().unwrap_or(model);
+        if name.starts_with("gpt-5") || name.starts_with("gpt-4o") || name.starts_with("gpt-4.1") {
+            Self {
+                max_dimension: 2048,
+                max_bytes: 10 * 1024 * 1024,
+                max_images: 10,
+            }
+        } else {
+            // Conservative defaults for custom providers with unknown vision limits.
+            Self {
+                max_dimension: 1568,
+                max_bytes: 5 * 1024 * 1024,
+                max_images: 5,
+            }
+        }
+    }
+}
+
+/// Whether a model (bare or `provider@model`) accepts image input.
+///
+/// Only known text-only families are excluded; custom providers are assumed
+/// to accept images and will surface an error from the provider otherwise.
+pub fn model_supports_images(model: &str) -> bool {
+    let name = model.rsplit('@').next().unwrap_or(model);
+    !["gpt-3.5", "o1-mini", "o3-mini"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+/// Return the image MIME type for a resource, from its declared type or its URI extension.
+pub fn image_mime_type(mime_type: Option<&str>, uri: &str) -> Option<String> {
+    if let Some(mime) = mime_type {
+        return mime.starts_with("image/").then(|| mime.to_string());
+    }
+    let ext = uri.rsplit('.').next()?.to_ascii_lowercase();
+    let mime = match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => return None,
+    };
+    Some(mime.to_string())
+}
+
+/// Per-prompt image accounting: limits, vision support, and images seen so far.
+#[derive(Debug, Clone)]
+pub struct ImageBudget {
+    limits: ImageLimits,
+    vision: bool,
+    count: usize,
+}
+
+impl ImageBudget {
+    pub fn for_model(model: &str) -> Self {
+        Self {
+            limits: ImageLimits::for_model(model),
+            vision: model_supports_images(model),
+            count: 0,
+        }
+    }
+
+    pub fn vision(&self) -> bool {
+        self.vision
+    }
+}
+
+/// Result of preprocessing a single image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageOutcome {
+    /// The image fit within limits and is passed through as-is.
+    Unchanged { data_url: String },
+    /// The image was downscaled and re-encoded as JPEG.
+    Downscaled {
+        data_url: String,
+        from: (u32, u32),
+        to: (u32, u32),
+    },
+    /// The image was dropped; the string explains why.
+    Skipped(String),
+}
+
+/// Check an image against `limits`, downscaling and re-encoding when needed.
+///
+/// Decoding and resizing are CPU-bound; async callers run this on the
+/// blocking pool.
+pub fn preprocess_image(mime_type: &str, data: &str, limits: &ImageLimits) -> ImageOutcome {
+    let bytes = match STANDARD.decode(data.trim()) {
+        Ok(bytes) => bytes,
+        Err(err) => return ImageOutcome::Skipped(format!("invalid base64 data: {err}")),
+    };
+
+    let decoded = match image::load_from_memory(&bytes) {
+        Ok(img) => img,
+        // Formats we cannot decode are passed through when they already fit the byte budget.
+        Err(_) if bytes.len() <= limits.max_bytes => {
+            return ImageOutcome::Unchanged {
+                data_url: data_url(mime_type, data.trim()),
+            };
+        }
+        Err(err) => return ImageOutcome::Skipped(format!("could not decode image: {err}")),
+    };
+
+    let from = (decoded.width(), decoded.height());
+    if from.0.max(from.1) <= limits.max_dimension && bytes.len() <= limits.max_bytes {
+        return ImageOutcome::Unchanged {
+            data_url: data_url(mime_type, data.trim()),
+        };
+    }
+
+    let resized = if from.0.max(from.1) > limits.max_dimension {
+        decoded.resize(
+            limits.max_dimension,
+            limits.max_dimension,
+            FilterType::Triangle,
+        )
+    } else {
+        decoded
+    };
+    let to = (resized.width(), resized.height());
+
+    let mut encoded = Vec::new();
+    let rgb = DynamicImage::ImageRgb8(resized.to_rgb8());
+    if let Err(err) =
+        rgb.write_with_encoder(JpegEncoder::new_with_quality(&mut encoded, JPEG_QUALITY))
+    {
+        return ImageOutcome::Skipped(format!("could not re-encode image: {err}"));
+    }
+    if encoded.len() > limits.max_bytes {
+        return ImageOutcome::Skipped(format!(
+            "image is {} bytes after downscaling (limit {})",
+            encoded.len(),
+            limits.max_bytes
+        ));
+    }
+
+    ImageOutcome::Downscaled {
+        data_url: data_url("image/jpeg", &STANDARD.encode(&encoded)),
+        from,
+        to,
+    }
+}
+
+fn data_url(mime_type: &str, data: &str) -> String {
+    format!("data:{mime_type};base64,{data}")
+}
+
+impl CodexAgent {
+    /// Preprocess a base64 image and push it as user input, reporting
+    /// downscaled or skipped images as warning chunks.
+    pub(super) async fn push_image_input(
+        &self,
+        session_id: &SessionId,
+        budget: &mut ImageBudget,
+        mime_type: &str,
+        data: &str,
+        items: &mut Vec<UserInput>,
+    ) -> Result<(), Error> {
+        budget.count += 1;
+        let n = budget.count;
+        let warning = if !budget.vision {
+            Some(format!(
+                "⚠️ Skipped image {n}: the current model does not accept images\n\n"
+            ))
+        } else if n > budget.limits.max_images {
+            Some(format!(
+                "⚠️ Skipped image {n}: at most {} images per prompt\n\n",
+                budget.limits.max_images
+            ))
+        } else {
+            let (mime_type, data, limits) =
+                (mime_type.to_string(), data.to_string(), budget.limits);
+            let outcome =
+                tokio::task::spawn_blocking(move || preprocess_image(&mime_type, &data, &limits))
+                    .await
+                    .unwrap_or_else(|err| {
+                        ImageOutcome::Skipped(format!("image preprocessing failed: {err}"))
+                    });
+            match outcome {
+                ImageOutcome::Unchanged { data_url } => {
+                    items.push(UserInput::Image {
+                        image_url: data_url,
+                    });
+                    None
+                }
+                ImageOutcome::Downscaled { data_url, from, to } => {
+                    items.push(UserInput::Image {
+                        image_url: data_url,
+                    });
+                    Some(format!(
+                        "🖼️ Downscaled image {n} from {}x{} to {}x{}\n\n",
+                        from.0, from.1, to.0, to.1
+                    ))
+                }
+                ImageOutcome::Skipped(reason) => {
+                    Some(format!("⚠️ Skipped image {n}: {reason}\n\n"))
+                }
+            }
+        };
+
+        if let Some(msg) = warning {
+            self.send_message_chunk(session_id, msg.into()).await?;
+        }
+        Ok(())
+    }
+
+    /// Read an image resource link and push it as image input.
+    ///
+    /// Returns `false` when the link is not a readable local file so the
+    /// caller can fall back to a textual reference.
+    pub(super) async fn push_image_link(
+        &self,
+        session_id: &SessionId,
+        budget: &mut ImageBudget,
+        mime_type: &str,
+        uri: &str,
+        items: &mut Vec<UserInput>,
+    ) -> Result<bool, Error> {
+        let Some(path) = utils::file_uri_path(uri) else {
+            return Ok(false);
+        };
+        let bytes = match &self.fs_bridge {
+            Some(bridge) => {
+                bridge
+                    .read_file_bytes(session_id, &path.to_string_lossy())
+                    .await
+            }
+            None => tokio::fs::read(&path).await.map_err(|err| err.to_string()),
+        };
+        match bytes {
+            Ok(bytes) => {
+                let data = STANDARD.encode(bytes);
+                self.push_image_input(session_id, budget, mime_type, &data, items)
+                    .await?;
+                Ok(true)
+            }
+            Err(err) => {
+                let msg = format!("⚠️ Could not read image {}: {err}\n\n", path.display());
+                self.send_message_chunk(session_id, msg.into()).await?;
+                Ok(false)
+            }
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Detecting a second agent for the same workspace.
+//!
+//! Editors sometimes start several agent processes for one workspace, whose
+//! writes then conflict. Each agent registers itself in
+//! `$CODEX_HOME/acp/instances/<workspace key>/<pid>.json` at startup and
+//! refreshes the entry every [`HEARTBEAT_INTERVAL`]. A new session whose
+//! workspace has another live entry gets a warning naming that process, and
+//! so does the log. Entries not refreshed within [`STALE_AFTER`] belong to
+//! agents that exited or hung and are removed.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::task;
+use tracing::{debug, warn};
+
+use super::core::CodexAgent;
+
+/// How often a running agent refreshes its entry.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Age after which an entry no longer counts as a running agent.
+pub const STALE_AFTER: Duration = Duration::from_secs(90);
+
+/// One agent process serving a workspace.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceRecord {
+    pub pid: u32,
+    pub version: String,
+    pub cwd: PathBuf,
+    /// Seconds since the Unix epoch.
+    pub started_at: u64,
+    pub heartbeat_at: u64,
+}
+
+impl InstanceRecord {
+    pub fn is_live(&self, now: u64) -> bool {
+        now.saturating_sub(self.heartbeat_at) < STALE_AFTER.as_secs()
+    }
+}
+
+/// Directory name for a workspace: a stable FNV-1a hash of its path.
+pub fn workspace_key(cwd: &Path) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in cwd.to_string_lossy().bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+/// Warning shown in a session when other agents serve its workspace.
+pub fn duplicate_notice(others: &[InstanceRecord]) -> String {
+    let pids: Vec<String> = others.iter().map(|other| other.pid.to_string()).collect();
+    format!(
+        "⚠️ Another codex-acp agent is already running for this workspace (PID {}). \
+         Edits from both may conflict; close the other editor window or stop that process.\n\n",
+        pids.join(", ")
+    )
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The entries of all agents, under `$CODEX_HOME/acp/instances`.
+#[derive(Debug, Clone)]
+pub struct InstanceRegistry {
+    dir: PathBuf,
+}
+
+impl InstanceRegistry {
+    pub fn new(codex_home: &Path) -> Self {
+        Self {
+            dir: codex_home.join("acp").join("instances"),
+        }
+    }
+
+    fn entry_path(&self, cwd: &Path, pid: u32) -> PathBuf {
+        self.dir
+            .join(workspace_key(cwd))
+            .join(format!("{pid}.json"))
+    }
+
+    /// Write or refresh `record`'s entry.
+    pub async fn save(&self, record: &InstanceRecord) -> io::Result<()> {
+        let path = self.entry_path(&record.cwd, record.pid);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&path, serde_json::to_vec_pretty(record)?).await
+    }
+
+    /// Live agents for `cwd` other than `pid`. Stale entries are removed.
+    pub async fn others(&self, cwd: &Path, pid: u32, now: u64) -> Vec<InstanceRecord> {
+        let mut others = Vec::new();
+        let Ok(mut entries) = tokio::fs::read_dir(self.dir.join(workspace_key(cwd))).await else {
+            return others;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let record = match tokio::fs::read(&path).await {
+                Ok(bytes) => serde_json::from_slice::<InstanceRecord>(&bytes).ok(),
+                Err(_) => continue,
+            };
+            match record {
+                Some(record) if record.pid == pid => {}
+                // A hash collision with another workspace is not a duplicate.
+                Some(record) if record.cwd != cwd => {}
+                Some(record) if record.is_live(now) => others.push(record),
+                _ => {
+                    debug!(path = %path.display(), "removing stale instance entry");
+                    let _ = tokio::fs::remove_file(&path).await;
+                }
+            }
+        }
+        others.sort_by_key(|record| record.pid);
+        others
+    }
+
+    /// Remove `pid`'s entry for `cwd`.
+    pub fn remove(&self, cwd: &Path, pid: u32) {
+        let _ = std::fs::remove_file(self.entry_path(cwd, pid));
+    }
+}
+
+/// Removes this agent's entry when the agent is dropped.
+pub struct InstanceGuard {
+    registry: InstanceRegistry,
+    cwd: PathBuf,
+}
+
+impl Drop for InstanceGuard {
+    fn drop(&mut self) {
+        self.registry.remove(&self.cwd, std::process::id());
+    }
+}
+
+impl CodexAgent {
+    /// Register this agent for its workspace and keep the entry fresh in
+    /// the background, warning in the log if another agent already runs.
+    pub fn start_instance_registry(&self) {
+        let registry = InstanceRegistry::new(&self.config.codex_home);
+        let now = now_secs();
+        let mut record = InstanceRecord {
+            pid: std::process::id(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            cwd: self.config.cwd.clone(),
+            started_at: now,
+            heartbeat_at: now,
+        };
+        *self.instance_guard.borrow_mut() = Some(InstanceGuard {
+            registry: registry.clone(),
+            cwd: record.cwd.clone(),
+        });
+        task::spawn_local(async move {
+            let others = registry.others(&record.cwd, record.pid, now).await;
+            if !others.is_empty() {
+                warn!(
+                    cwd = %record.cwd.display(),
+                    pids = ?others.iter().map(|other| other.pid).collect::<Vec<_>>(),
+                    "another codex-acp agent is running for this workspace"
+                );
+            }
+            loop {
+                if let Err(err) = registry.save(&record).await {
+                    warn!(error = %err, "failed to update instance entry");
+                }
+                tokio::time::sleep(HEARTBEAT_INTERVAL).await;
+                record.heartbeat_at = now_secs();
+            }
+        });
+    }
+
+    /// The warning for a new session if other agents serve this workspace.
+    pub(super) async fn duplicate_instance_notice(&self) -> Option<String> {
+        if self.instance_guard.borrow().is_none() {
+            return None;
+        }
+        let registry = InstanceRegistry::new(&self.config.codex_home);
+        let others = registry
+            .others(&self.config.cwd, std::process::id(), now_secs())
+            .await;
+        (!others.is_empty()).then(|| duplicate_notice(&others))
+    }
+}
@@ -0,0 +1,153 @@
+//! Instruction snippets the user keeps per workspace.
+//!
+//! `/instructions add <text>` saves a short standing instruction for the
+//! current workspace in `$CODEX_HOME/acp/instructions.json`. Every session
+//! created in that workspace afterwards gets the snippets appended to its
+//! instructions, so behavior can be tuned without having the model edit
+//! `AGENTS.md`. Running sessions keep the instructions they started with.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use tracing::warn;
+
+/// Snippets kept per workspace.
+pub const MAX_SNIPPETS: usize = 20;
+
+/// Characters accepted per snippet.
+pub const MAX_SNIPPET_CHARS: usize = 500;
+
+/// A parsed `/instructions` command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstructionsCommand {
+    List,
+    Add(String),
+    /// 1-based index as shown by `list`.
+    Remove(usize),
+}
+
+/// Usage text for `/instructions`.
+pub const INSTRUCTIONS_USAGE: &str = "Usage: /instructions [list] | add <text> | remove <n>\n";
+
+/// Parse `/instructions` arguments.
+pub fn parse_instructions_command(args: &str) -> Result<InstructionsCommand, String> {
+    let args = args.trim();
+    let (verb, rest) = args
+        .split_once(char::is_whitespace)
+        .map_or((args, ""), |(verb, rest)| (verb, rest.trim()));
+    match verb.to_ascii_lowercase().as_str() {
+        "" | "list" if rest.is_empty() => Ok(InstructionsCommand::List),
+        "add" if rest.is_empty() => Err(INSTRUCTIONS_USAGE.to_string()),
+        "add" if rest.chars().count() > MAX_SNIPPET_CHARS => Err(format!(
+            "⚠️ Instructions are limited to {MAX_SNIPPET_CHARS} characters; put longer guidance in AGENTS.md\n"
+        )),
+        "add" => Ok(InstructionsCommand::Add(rest.to_string())),
+        "remove" | "rm" => match rest.parse::<usize>() {
+            Ok(index) if index > 0 => Ok(InstructionsCommand::Remove(index)),
+            _ => Err(INSTRUCTIONS_USAGE.to_string()),
+        },
+        _ => Err(INSTRUCTIONS_USAGE.to_string()),
+    }
+}
+
+/// Section appended to the session instructions, or `None` without snippets.
+pub fn render_snippets(snippets: &[String]) -> Option<String> {
+    if snippets.is_empty() {
+        return None;
+    }
+    let mut text = "Standing instructions from the user for this workspace:".to_string();
+    for snippet in snippets {
+        text.push_str("\n- ");
+        text.push_str(snippet);
+    }
+    Some(text)
+}
+
+/// Numbered listing of a workspace's snippets.
+pub fn format_snippets(snippets: &[String]) -> String {
+    if snippets.is_empty() {
+        return "No instructions saved for this workspace.\nAdd one with /instructions add <text>\n"
+            .to_string();
+    }
+    let mut text = "Instructions for this workspace (applied to new sessions):\n".to_string();
+    for (idx, snippet) in snippets.iter().enumerate() {
+        text.push_str(&format!("{}. {snippet}\n", idx + 1));
+    }
+    text
+}
+
+/// File-backed snippets keyed by workspace path.
+#[derive(Debug, Clone)]
+pub struct InstructionStore {
+    path: PathBuf,
+}
+
+impl InstructionStore {
+    pub fn new(codex_home: &Path) -> Self {
+        Self {
+            path: codex_home.join("acp").join("instructions.json"),
+        }
+    }
+
+    async fn load_all(&self) -> BTreeMap<String, Vec<String>> {
+        let Ok(bytes) = tokio::fs::read(&self.path).await else {
+            return BTreeMap::new();
+        };
+        serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+            warn!(path = %self.path.display(), error = %err, "ignoring malformed instructions file");
+            BTreeMap::new()
+        })
+    }
+
+    async fn save_all(&self, all: &BTreeMap<String, Vec<String>>) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&self.path, serde_json::to_vec_pretty(all)?).await
+    }
+
+    fn key(workspace: &Path) -> String {
+        workspace.to_string_lossy().into_owned()
+    }
+
+    /// Snippets saved for `workspace`, in the order they were added.
+    pub async fn list(&self, workspace: &Path) -> Vec<String> {
+        self.load_all()
+            .await
+            .remove(&Self::key(workspace))
+            .unwrap_or_default()
+    }
+
+    /// Save a snippet, returning the workspace's snippets afterwards.
+    pub async fn add(&self, workspace: &Path, snippet: String) -> io::Result<Vec<String>> {
+        let mut all = self.load_all().await;
+        let snippets = all.entry(Self::key(workspace)).or_default();
+        if snippets.len() >= MAX_SNIPPETS {
+            return Err(io::Error::other(format!(
+                "at most {MAX_SNIPPETS} instructions can be saved per workspace"
+            )));
+        }
+        snippets.push(snippet);
+        let snippets = snippets.clone();
+        self.save_all(&all).await?;
+        Ok(snippets)
+    }
+
+    /// Remove the snippet at the 1-based `index`, returning it if it existed.
+    pub async fn remove(&self, workspace: &Path, index: usize) -> io::Result<Option<String>> {
+        let mut all = self.load_all().await;
+        let key = Self::key(workspace);
+        let Some(snippets) = all.get_mut(&key).filter(|s| (1..=s.len()).contains(&index)) else {
+            return Ok(None);
+        };
+        let removed = snippets.remove(index - 1);
+        if snippets.is_empty() {
+            all.remove(&key);
+        }
+        self.save_all(&all).await?;
+        Ok(Some(removed))
+    }
+}
@@ -0,0 +1,210 @@
+//! Handling of commands that wait for keyboard input.
+//!
+//! Codex runs commands without a usable stdin, so prompts such as `npm init`
+//! or `git commit` (opening an editor) hang until they time out. When such a
+//! command comes up for approval it is detected heuristically and goes through
+//! the usual approval request. Once approved it runs in a client terminal,
+//! where the user can answer it, or is skipped with advice on a
+//! non-interactive alternative when the client has no terminals. Read-only
+//! sessions skip it without asking. The outcome is fed back into the running
+//! turn as user input.
+
+use agent_client_protocol::{Error, SessionId};
+use codex_core::{
+    CodexConversation,
+    protocol::{Op, ReviewDecision},
+};
+use codex_protocol::user_input::UserInput;
+use tracing::info;
+
+use super::{
+    approvals::PendingExecApproval, core::CodexAgent, events, terminal::MAX_REPORTED_OUTPUT,
+};
+
+/// Unwrap `bash -lc "<script>"` style invocations into the script's words.
+pub fn command_words(command: &[String]) -> Vec<String> {
+    match command {
+        [shell, flag, script]
+            if matches!(shell.rsplit('/').next(), Some("bash" | "sh" | "zsh"))
+                && matches!(flag.as_str(), "-c" | "-lc") =>
+        {
+            script.split_whitespace().map(str::to_string).collect()
+        }
+        _ => command.to_vec(),
+    }
+}
+
+/// Return why `command` is likely to wait for keyboard input, if it is.
+pub fn interactive_reason(command: &[String]) -> Option<&'static str> {
+    let words = command_words(command);
+    let program = words.first()?.rsplit('/').next()?.to_string();
+    let args: Vec<&str> = words.iter().skip(1).map(String::as_str).collect();
+    let has = |flags: &[&str]| args.iter().any(|a| flags.contains(a));
+
+    match program.as_str() {
+        "npm" | "pnpm" | "yarn" if args.first() == Some(&"init") && !has(&["-y", "--yes"]) => {
+            Some("`init` asks questions; pass `-y` to accept defaults")
+        }
+        "git"
+            if args.first() == Some(&"commit")
+                && !has(&["-m", "--message", "-F", "--file", "--no-edit", "-C"]) =>
+        {
+            Some("`git commit` opens an editor; pass `-m <message>`")
+        }
+        "git"
+            if matches!(args.first(), Some(&"rebase" | &"add"))
+                && has(&["-i", "--interactive", "-p", "--patch"]) =>
+        {
+            Some("interactive git mode; use the non-interactive form")
+        }
+        "vi" | "vim" | "nvim" | "nano" | "emacs" | "less" | "more" | "top" | "htop" | "man" => {
+            Some("full-screen program; use a non-interactive tool instead")
+        }
+        "ssh" | "telnet" | "ftp" | "sftp" if args.len() <= 1 => {
+            Some("opens an interactive remote session")
+        }
+        "python" | "python3" | "node" | "irb" | "ghci" | "psql" | "mysql" | "sqlite3"
+            if args.iter().all(|a| a.starts_with('-')) && !has(&["-c", "-e", "--version"]) =>
+        {
+            Some("starts an interactive REPL; pass a script or `-c`")
+        }
+        "sudo" if !has(&["-n", "--non-interactive"]) => Some("may prompt for a password"),
+        "passwd" => Some("prompts for a password"),
+        _ => None,
+    }
+}
+
+impl CodexAgent {
+    /// Resolve the approval of an interactive command: ask about it on its
+    /// own, never in a digest, unless the session is read-only or it was
+    /// already approved for the session.
+    pub(super) async fn resolve_interactive_exec(
+        &self,
+        session_id: &SessionId,
+        conversation: &CodexConversation,
+        event_handler: &events::EventHandler,
+        pending: &PendingExecApproval,
+        reason: &str,
+    ) -> Result<(), Error> {
+        if self.is_read_only(session_id) {
+            self.audit_exec_decision(
+                session_id,
+                &pending.command,
+                &pending.cwd,
+                &ReviewDecision::Denied,
+            );
+            return self
+                .skip_interactive_exec(
+                    session_id,
+                    conversation,
+                    pending,
+                    reason,
+                    "the session is read-only",
+                )
+                .await;
+        }
+        if self.is_terminal_approved(session_id, &pending.command) {
+            return self
+                .submit_exec_decision(
+                    session_id,
+                    conversation,
+                    pending,
+                    ReviewDecision::ApprovedForSession,
+                )
+                .await;
+        }
+        self.request_exec_approval(session_id, conversation, event_handler, pending)
+            .await
+    }
+
+    /// Carry out an interactive command the user approved.
+    ///
+    /// With client terminal support the command runs there and its output is
+    /// injected into the turn; otherwise it is skipped with guidance. Codex's
+    /// own execution is always declined so the command does not run twice.
+    pub(super) async fn run_interactive_exec(
+        &self,
+        session_id: &SessionId,
+        conversation: &CodexConversation,
+        pending: &PendingExecApproval,
+        reason: &str,
+    ) -> Result<(), Error> {
+        if !self.support_terminal() {
+            return self
+                .skip_interactive_exec(
+                    session_id,
+                    conversation,
+                    pending,
+                    reason,
+                    "this client cannot provide any",
+                )
+                .await;
+        }
+        let display = pending.display();
+        info!(command = %display, reason, "running interactive command in client terminal");
+        let run = self
+            .run_in_client_terminal(
+                session_id,
+                &pending.call_id,
+                format!("Interactive: {display}"),
+                &pending.command,
+                &pending.cwd,
+            )
+            .await?;
+        let output = run.output_tail(MAX_REPORTED_OUTPUT);
+        let report = format!(
+            "The command `{display}` needs keyboard input, so it was run in the user's terminal instead ({}). Do not run it again. Output:\n{output}",
+            run.describe_exit()
+        );
+        self.decline_with_report(conversation, pending, report)
+            .await
+    }
+
+    /// Decline an interactive command without running it, telling the user
+    /// and the model why (`because` completes "it waits for keyboard input
+    /// and ...").
+    pub(super) async fn skip_interactive_exec(
+        &self,
+        session_id: &SessionId,
+        conversation: &CodexConversation,
+        pending: &PendingExecApproval,
+        reason: &str,
+        because: &str,
+    ) -> Result<(), Error> {
+        let display = pending.display();
+        info!(command = %display, reason, because, "skipping interactive command");
+        self.send_message_chunk(
+            session_id,
+            format!("⚠️ Skipped interactive command `{display}`: {reason}.\n\n").into(),
+        )
+        .await?;
+        let report = format!(
+            "The command `{display}` was not run because it waits for keyboard input ({reason}) and {because}. Use a non-interactive alternative."
+        );
+        self.decline_with_report(conversation, pending, report)
+            .await
+    }
+
+    async fn decline_with_report(
+        &self,
+        conversation: &CodexConversation,
+        pending: &PendingExecApproval,
+        report: String,
+    ) -> Result<(), Error> {
+        // Codex queues input submitted during a running turn for the model's next step.
+        conversation
+            .submit(Op::UserInput {
+                items: vec![UserInput::Text { text: report }],
+            })
+            .await
+            .map_err(Error::into_internal_error)?;
+        conversation
+            .submit(Op::ExecApproval {
+                id: pending.event_id.clone(),
+                decision: ReviewDecision::Denied,
+            })
+            .await
+            .map_err(Error::into_internal_error)?;
+        Ok(())
+    }
+}
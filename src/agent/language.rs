@@ -0,0 +1,81 @@
+//! The language the model answers in.
+//!
+//! A session's response language comes from `CODEX_ACP_RESPONSE_LANGUAGE`,
+//! `NewSessionRequest.meta.responseLanguage`, or `/lang`. The language set at
+//! session start is written into the session instructions; a language chosen
+//! later with `/lang` cannot change those, so it is restated at the start of
+//! every following turn instead.
+
+use agent_client_protocol::Error;
+use codex_protocol::user_input::UserInput;
+
+/// Environment variable setting the default response language (e.g. `German`, `ja`).
+pub const RESPONSE_LANGUAGE_ENV: &str = "CODEX_ACP_RESPONSE_LANGUAGE";
+
+/// Longest accepted language name.
+const MAX_LANGUAGE_LEN: usize = 40;
+
+/// Parse a language name; `off`, `none`, `auto`, and empty values clear it.
+///
+/// Returns `Err` for values that do not look like a language name.
+pub fn parse_language(value: &str) -> Result<Option<String>, String> {
+    let value = value.trim();
+    if value.is_empty() || matches!(value.to_ascii_lowercase().as_str(), "off" | "none" | "auto") {
+        return Ok(None);
+    }
+    let valid = value.chars().count() <= MAX_LANGUAGE_LEN
+        && value
+            .chars()
+            .all(|c| c.is_alphabetic() || matches!(c, ' ' | '-' | '_' | '(' | ')'));
+    if valid {
+        Ok(Some(value.to_string()))
+    } else {
+        Err(format!(
+            "responseLanguage must be a language name of at most {MAX_LANGUAGE_LEN} letters"
+        ))
+    }
+}
+
+/// Response language for new sessions, from `CODEX_ACP_RESPONSE_LANGUAGE`.
+pub fn default_response_language() -> Option<String> {
+    std::env::var(RESPONSE_LANGUAGE_ENV)
+        .ok()
+        .and_then(|v| parse_language(&v).ok().flatten())
+}
+
+/// Read `NewSessionRequest.meta.responseLanguage`.
+///
+/// `Some(None)` means the request explicitly asked for no fixed language.
+pub fn response_language_from_meta(
+    meta: Option<&serde_json::Value>,
+) -> Result<Option<Option<String>>, Error> {
+    match meta.and_then(|m| m.get("responseLanguage")) {
+        None => Ok(None),
+        Some(serde_json::Value::Null) => Ok(Some(None)),
+        Some(serde_json::Value::String(value)) => parse_language(value)
+            .map(Some)
+            .map_err(|msg| Error::invalid_params().with_data(msg)),
+        Some(_) => {
+            Err(Error::invalid_params().with_data("responseLanguage must be a string or null"))
+        }
+    }
+}
+
+/// Session instruction asking for answers in `language`.
+pub fn language_instruction(language: &str) -> String {
+    format!(
+        "Always write your answers, explanations, plans, and questions to the user in {language}, even when the user or the codebase uses another language. Keep code, identifiers, commands, file paths, and quoted tool output unchanged."
+    )
+}
+
+/// Turn-level instruction for a language chosen after the session started.
+pub fn language_item(language: Option<&str>) -> UserInput {
+    let text = match language {
+        Some(language) => format!(
+            "<response-language>{}</response-language>",
+            language_instruction(language)
+        ),
+        None => "<response-language>Answer in the language the user writes in, ignoring any earlier response language instruction.</response-language>".to_string(),
+    };
+    UserInput::Text { text }
+}
@@ -7,7 +7,7 @@ use agent_client_protocol::{
 use codex_app_server_protocol::AuthMode;
 use tracing::info;
 
-use super::{core::CodexAgent, session};
+use super::{core::CodexAgent, session, status::ReportFormat, thoughts::ThoughtDelivery};
 
 impl CodexAgent {
     /// Initialize the agent and return supported capabilities and authentication methods.
@@ -46,10 +46,22 @@ impl CodexAgent {
             });
         }
 
+        self.report_format.set(ReportFormat::for_client(
+            args.meta.as_ref(),
+            args.client_capabilities.meta.as_ref(),
+        ));
+        self.thought_delivery.set(ThoughtDelivery::for_client(
+            args.meta.as_ref(),
+            args.client_capabilities.meta.as_ref(),
+        ));
+        #[cfg(feature = "acp-compat")]
+        let meta = self.negotiate_shape(args.meta.as_ref(), args.client_capabilities.meta.as_ref());
+        #[cfg(not(feature = "acp-compat"))]
+        let meta = None;
         self.client_capabilities.replace(args.client_capabilities);
 
         let agent_capabilities = AgentCapabilities {
-            load_session: false,
+            load_session: true,
             prompt_capabilities: PromptCapabilities {
                 image: true,
                 audio: false,
@@ -73,7 +85,7 @@ impl CodexAgent {
                 title: Some("Codex ACP".into()),
                 version: env!("CARGO_PKG_VERSION").into(),
             }),
-            meta: None,
+            meta,
         })
     }
 
@@ -0,0 +1,347 @@
+//! Quick-connect for local OpenAI-compatible servers (Ollama, LM Studio).
+//!
+//! `codex/connectLocalProvider` probes the well-known local endpoints and
+//! lists the models they serve. With `confirm: true` it also writes a
+//! `[model_providers.<id>]` entry plus one `[profiles.<id>-<model>]` per model
+//! into `$CODEX_HOME/config.toml`, and registers them with the running agent
+//! so they are selectable via `set_session_model` without a restart.
+//!
+//! Provider tables it writes carry a [`MANAGED_COMMENT`]; a provider the user
+//! configured by hand is never overwritten.
+
+use std::{path::Path, time::Duration};
+
+use agent_client_protocol::Error;
+use codex_core::{ModelProviderInfo, config_profile::ConfigProfile};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use toml_edit::{DocumentMut, Item, Table, value};
+use tracing::{info, warn};
+
+use super::core::CodexAgent;
+
+/// How long to wait for a local server to answer `/models`.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Comment above the `[model_providers.<id>]` tables this command wrote.
+pub const MANAGED_COMMENT: &str = "# Written by codex/connectLocalProvider";
+
+/// `wire_api` of new provider tables.
+const DEFAULT_WIRE_API: &str = "chat";
+
+/// A well-known local server.
+#[derive(Debug, Clone, Copy)]
+pub struct LocalEndpoint {
+    /// Provider id written to config (`model_providers.<id>`).
+    pub id: &'static str,
+    pub name: &'static str,
+    pub base_url: &'static str,
+}
+
+pub const LOCAL_ENDPOINTS: &[LocalEndpoint] = &[
+    LocalEndpoint {
+        id: "ollama",
+        name: "Ollama",
+        base_url: "http://localhost:11434/v1",
+    },
+    LocalEndpoint {
+        id: "lmstudio",
+        name: "LM Studio",
+        base_url: "http://localhost:1234/v1",
+    },
+];
+
+/// Params for `codex/connectLocalProvider`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectLocalProviderParams {
+    /// Only probe this provider id (`ollama`, `lmstudio`, or a custom id with `baseUrl`).
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Override the endpoint URL, e.g. a server on another port.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Restrict the written profiles to these models.
+    #[serde(default)]
+    pub models: Option<Vec<String>>,
+    /// Write the discovered provider(s) to config.toml. Without it the call only probes.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+/// Probe result for one endpoint.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbedEndpoint {
+    pub provider: String,
+    pub name: String,
+    pub base_url: String,
+    pub reachable: bool,
+    pub models: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ModelList {
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
+/// List models served at `base_url` via the OpenAI-compatible `GET /models`.
+async fn list_models(client: &reqwest::Client, base_url: &str) -> Option<Vec<String>> {
+    let url = format!("{}/models", base_url.trim_end_matches('/'));
+    let response = client.get(&url).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let list: ModelList = response.json().await.ok()?;
+    Some(list.data.into_iter().map(|m| m.id).collect())
+}
+
+/// Resolve which endpoints to probe for the given params.
+fn endpoints_for(
+    params: &ConnectLocalProviderParams,
+) -> Result<Vec<(String, String, String)>, Error> {
+    let known = |e: &LocalEndpoint| (e.id.to_string(), e.name.to_string(), e.base_url.to_string());
+    match (&params.provider, &params.base_url) {
+        (None, None) => Ok(LOCAL_ENDPOINTS.iter().map(known).collect()),
+        (None, Some(_)) => Err(Error::invalid_params().with_data("baseUrl requires provider")),
+        (Some(id), base_url) => {
+            let endpoint = LOCAL_ENDPOINTS.iter().find(|e| e.id == id.as_str());
+            match (endpoint, base_url) {
+                (Some(e), None) => Ok(vec![known(e)]),
+                (Some(e), Some(url)) => {
+                    Ok(vec![(e.id.to_string(), e.name.to_string(), url.clone())])
+                }
+                (None, Some(_)) if !is_valid_provider_id(id) => Err(Error::invalid_params()
+                    .with_data("provider ids may only contain ASCII letters, digits, `-` and `_`")),
+                (None, Some(url)) => Ok(vec![(id.clone(), id.clone(), url.clone())]),
+                (None, None) => Err(Error::invalid_params()
+                    .with_data(format!("unknown local provider `{id}`; pass baseUrl"))),
+            }
+        }
+    }
+}
+
+/// Whether `id` can name a provider table and prefix profile names.
+pub fn is_valid_provider_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Profile name for a local model; characters TOML bare keys reject become `-`.
+pub fn profile_name(provider_id: &str, model: &str) -> String {
+    let model: String = model
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("{provider_id}-{model}")
+}
+
+/// Insert or update the provider table and per-model profiles in a config
+/// document, returning the provider's `wire_api`. A provider table this
+/// command did not write is refused.
+pub fn write_provider_entries(
+    doc: &mut DocumentMut,
+    endpoint: &ProbedEndpoint,
+    models: &[String],
+) -> Result<String, Error> {
+    let id = &endpoint.provider;
+    let refused = || {
+        Error::invalid_params().with_data(format!(
+            "[model_providers.{id}] in config.toml was not written by connectLocalProvider; \
+             remove it or pick another provider id"
+        ))
+    };
+    let providers = doc
+        .entry("model_providers")
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| {
+            Error::invalid_params().with_data("model_providers in config.toml is not a table")
+        })?;
+    if !providers.contains_key(id) {
+        let mut table = Table::new();
+        table
+            .decor_mut()
+            .set_prefix(format!("\n{MANAGED_COMMENT}\n"));
+        providers.insert(id, Item::Table(table));
+    }
+    let entry = providers
+        .get_mut(id)
+        .and_then(Item::as_table_mut)
+        .filter(|table| is_managed(table))
+        .ok_or_else(refused)?;
+    entry.insert("name", value(endpoint.name.as_str()));
+    entry.insert("base_url", value(endpoint.base_url.as_str()));
+    let wire_api = match entry.get("wire_api").and_then(Item::as_str) {
+        Some(wire_api) => wire_api.to_string(),
+        None => {
+            entry.insert("wire_api", value(DEFAULT_WIRE_API));
+            DEFAULT_WIRE_API.to_string()
+        }
+    };
+
+    let profiles = doc
+        .entry("profiles")
+        .or_insert_with(|| Item::Table(Table::new()));
+    if let Some(profiles) = profiles.as_table_like_mut() {
+        for model in models {
+            let entry = profiles
+                .entry(&profile_name(&endpoint.provider, model))
+                .or_insert(Item::Table(Table::new()));
+            if let Some(entry) = entry.as_table_like_mut() {
+                entry.insert("model", value(model.as_str()));
+                entry.insert("model_provider", value(endpoint.provider.as_str()));
+            }
+        }
+    }
+    Ok(wire_api)
+}
+
+/// Whether a provider table carries the [`MANAGED_COMMENT`].
+fn is_managed(table: &Table) -> bool {
+    table
+        .decor()
+        .prefix()
+        .and_then(|prefix| prefix.as_str())
+        .is_some_and(|prefix| prefix.lines().any(|line| line.trim() == MANAGED_COMMENT))
+}
+
+/// Rewrite `config.toml` atomically with the new entries, preserving
+/// formatting; returns each endpoint's `wire_api`.
+async fn persist_config(
+    path: &Path,
+    endpoints: &[(ProbedEndpoint, Vec<String>)],
+) -> Result<Vec<String>, Error> {
+    let existing = match tokio::fs::read_to_string(path).await {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(Error::into_internal_error(err)),
+    };
+    let mut doc: DocumentMut = existing.parse().map_err(|e| {
+        Error::internal_error().with_data(format!("failed to parse {}: {e}", path.display()))
+    })?;
+    let wire_apis = endpoints
+        .iter()
+        .map(|(endpoint, models)| write_provider_entries(&mut doc, endpoint, models))
+        .collect::<Result<Vec<_>, _>>()?;
+    let tmp = path.with_extension("toml.tmp");
+    tokio::fs::write(&tmp, doc.to_string())
+        .await
+        .map_err(Error::into_internal_error)?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .map_err(Error::into_internal_error)?;
+    Ok(wire_apis)
+}
+
+impl CodexAgent {
+    /// Probe local servers and, when confirmed, persist and register them.
+    pub(super) async fn connect_local_provider(
+        &self,
+        params: ConnectLocalProviderParams,
+    ) -> Result<serde_json::Value, Error> {
+        let client = reqwest::Client::builder()
+            .timeout(PROBE_TIMEOUT)
+            .build()
+            .map_err(Error::into_internal_error)?;
+
+        let mut probed = Vec::new();
+        for (provider, name, base_url) in endpoints_for(&params)? {
+            let models = list_models(&client, &base_url).await;
+            probed.push(ProbedEndpoint {
+                provider,
+                name,
+                base_url,
+                reachable: models.is_some(),
+                models: models.unwrap_or_default(),
+            });
+        }
+
+        // Endpoints (and models) that would be written.
+        let selected: Vec<(ProbedEndpoint, Vec<String>)> = probed
+            .iter()
+            .filter(|e| e.reachable && !e.models.is_empty())
+            .map(|e| {
+                let models = match &params.models {
+                    Some(wanted) => e
+                        .models
+                        .iter()
+                        .filter(|m| wanted.contains(m))
+                        .cloned()
+                        .collect(),
+                    None => e.models.clone(),
+                };
+                (e.clone(), models)
+            })
+            .filter(|(_, models)| !models.is_empty())
+            .collect();
+        let model_ids: Vec<String> = selected
+            .iter()
+            .flat_map(|(e, models)| models.iter().map(move |m| format!("{}@{m}", e.provider)))
+            .collect();
+
+        let config_path = self.config.codex_home.join("config.toml");
+        let written = params.confirm && !selected.is_empty();
+        if written {
+            let wire_apis = persist_config(&config_path, &selected).await?;
+            self.register_local_providers(&selected, &wire_apis);
+            info!(models = ?model_ids, path = %config_path.display(), "connected local providers");
+        }
+
+        Ok(json!({
+            "endpoints": probed,
+            "modelIds": model_ids,
+            "written": written,
+            "configPath": config_path.display().to_string(),
+        }))
+    }
+
+    /// Make newly written providers and profiles visible to `set_session_model`,
+    /// with the `wire_api` each provider was written with.
+    fn register_local_providers(
+        &self,
+        selected: &[(ProbedEndpoint, Vec<String>)],
+        wire_apis: &[String],
+    ) {
+        for ((endpoint, models), wire_api) in selected.iter().zip(wire_apis) {
+            let provider = serde_json::from_value::<ModelProviderInfo>(json!({
+                "name": endpoint.name,
+                "base_url": endpoint.base_url,
+                "wire_api": wire_api,
+            }));
+            match provider {
+                Ok(provider) => {
+                    self.connected_providers
+                        .borrow_mut()
+                        .insert(endpoint.provider.clone(), provider);
+                }
+                Err(err) => {
+                    warn!(error = %err, provider = %endpoint.provider, "failed to register provider");
+                    continue;
+                }
+            }
+            let mut profiles = self.profiles.borrow_mut();
+            for model in models {
+                if let Ok(profile) = serde_json::from_value::<ConfigProfile>(json!({
+                    "model": model,
+                    "model_provider": endpoint.provider,
+                })) {
+                    profiles.insert(profile_name(&endpoint.provider, model), profile);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Slash commands derived from the session's MCP servers.
+//!
+//! Every MCP server a session starts with (from the client, a trusted
+//! `.codex/mcp.toml`, or `config.toml`) except `acp_fs` is a slash command
+//! `/mcp:<server>`. Running it with a task asks the model to do the task
+//! with that server's tools; without one, to describe them. MCP commands are
+//! advertised with the built-in commands and listed by `/help`.
+
+use agent_client_protocol::{AvailableCommand, AvailableCommandInput};
+
+use super::{
+    commands::{CommandCategory, CommandMeta},
+    mcp_servers::FS_SERVER_NAME,
+};
+
+/// Prefix of MCP server command names.
+pub const MCP_COMMAND_PREFIX: &str = "mcp:";
+
+/// The command of one MCP server in a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct McpServerCommand {
+    /// Server name as configured for the session.
+    pub server: String,
+}
+
+impl McpServerCommand {
+    /// The slash command name, without `/`.
+    pub fn command_name(&self) -> String {
+        format!("{MCP_COMMAND_PREFIX}{}", self.server)
+    }
+
+    pub fn description(&self) -> String {
+        format!("use the tools of the {} MCP server", self.server)
+    }
+
+    /// The prompt the command sends for `args`.
+    pub fn expand(&self, args: &str) -> String {
+        let args = args.trim();
+        if args.is_empty() {
+            format!(
+                "List the tools of the `{}` MCP server and what each of them does.",
+                self.server
+            )
+        } else {
+            format!(
+                "Use the tools of the `{}` MCP server to: {args}",
+                self.server
+            )
+        }
+    }
+
+    pub fn to_available_command(&self) -> AvailableCommand {
+        let meta = CommandMeta {
+            category: CommandCategory::Session,
+            icon: "🔌",
+            expects_followup: true,
+            keybinding: None,
+        };
+        AvailableCommand {
+            name: self.command_name(),
+            description: self.description(),
+            input: Some(AvailableCommandInput::Unstructured {
+                hint: "[task]".into(),
+            }),
+            meta: serde_json::to_value(&meta).ok(),
+        }
+    }
+}
+
+/// Commands for the named servers, sorted by name, without `acp_fs`.
+pub fn server_commands<'a>(servers: impl IntoIterator<Item = &'a String>) -> Vec<McpServerCommand> {
+    let mut commands: Vec<McpServerCommand> = servers
+        .into_iter()
+        .filter(|server| server.as_str() != FS_SERVER_NAME)
+        .map(|server| McpServerCommand {
+            server: server.clone(),
+        })
+        .collect();
+    commands.sort_by(|a, b| a.server.cmp(&b.server));
+    commands
+}
+
+/// The server command a command name (without `/`, any case) runs.
+pub fn find_server_command<'a>(
+    commands: &'a [McpServerCommand],
+    command: &str,
+) -> Option<&'a McpServerCommand> {
+    let name = command.strip_prefix(MCP_COMMAND_PREFIX)?;
+    commands
+        .iter()
+        .find(|cmd| cmd.server.eq_ignore_ascii_case(name))
+}
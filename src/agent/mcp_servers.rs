@@ -0,0 +1,152 @@
+//! Client MCP servers remembered with a session.
+//!
+//! The MCP servers passed to `session/new` are saved in the session record so
+//! that `session/load` in a later agent process can start them again. Secrets
+//! are not written to disk: values of secret-named environment variables and
+//! headers (see [`redact::is_secret_name`]) are dropped, and servers whose URL
+//! or arguments embed a credential are not saved at all. Servers passed to
+//! `session/load` replace saved ones of the same name, which is how clients
+//! re-supply the secrets.
+//!
+//! Codex exposes MCP tools to the model as `<server>__<tool>`, so tool names
+//! are unique as long as server names are. Client servers that would clash
+//! with `acp_fs`, a configured server, or each other are registered under a
+//! suffixed name instead (see [`unique_server_name`]).
+
+use agent_client_protocol::McpServer;
+use serde_json::Value;
+use tracing::warn;
+
+use super::redact;
+
+/// Name of the agent's own filesystem MCP server.
+pub const FS_SERVER_NAME: &str = "acp_fs";
+
+/// A client MCP server registered under a different name than it asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerRename {
+    pub requested: String,
+    pub assigned: String,
+}
+
+impl ServerRename {
+    /// Message telling the user about the rename.
+    pub fn notice(&self) -> String {
+        format!(
+            "⚠️ MCP server `{}` was registered as `{}` because its name clashes with another server or is not a valid server name; its tools are named `{}__<tool>`.\n\n",
+            self.requested, self.assigned, self.assigned
+        )
+    }
+}
+
+/// A name for a client server that Codex accepts, that cannot be confused
+/// with the `__` between server and tool name, and for which `taken` is false.
+pub fn unique_server_name(requested: &str, taken: impl Fn(&str) -> bool) -> String {
+    let mut base: String = requested
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    while base.contains("__") {
+        base = base.replace("__", "_");
+    }
+    let base = match base.trim_matches('_') {
+        "" => "mcp".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    if !taken(&base) {
+        return base;
+    }
+    (2..)
+        .map(|n| format!("{base}_{n}"))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or(base)
+}
+
+/// Header names that carry credentials without a secret-looking name.
+const SECRET_HEADERS: &[&str] = &["cookie", "proxy-authorization"];
+
+fn is_secret_entry(key: &str, name: &str) -> bool {
+    redact::is_secret_name(name)
+        || (key == "headers" && SECRET_HEADERS.contains(&name.to_ascii_lowercase().as_str()))
+}
+
+/// The spec to persist for `server`, or `None` when it cannot be saved
+/// without a secret.
+pub fn persistable(server: &McpServer) -> Option<Value> {
+    let mut value = serde_json::to_value(server).ok()?;
+    let name = value
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or("?")
+        .to_string();
+    let url = value.get("url").and_then(Value::as_str).unwrap_or_default();
+    let args: Vec<String> = value
+        .get("args")
+        .and_then(|args| serde_json::from_value(args.clone()).ok())
+        .unwrap_or_default();
+    if redact::redact(url) != url || redact::redact_command(&args) != args {
+        warn!(server = %name, "not saving MCP server whose URL or arguments hold a secret");
+        return None;
+    }
+    for key in ["env", "headers"] {
+        let Some(entries) = value.get_mut(key).and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for entry in entries.iter_mut().filter_map(Value::as_object_mut) {
+            let secret = entry
+                .get("name")
+                .and_then(Value::as_str)
+                .is_some_and(|name| is_secret_entry(key, name));
+            if secret {
+                entry.remove("value");
+            }
+        }
+    }
+    Some(value)
+}
+
+/// Rebuild a saved server; secret environment variables are left for the
+/// server to inherit from the agent, secret headers are omitted.
+pub fn restore(mut value: Value) -> Option<McpServer> {
+    for key in ["env", "headers"] {
+        if let Some(entries) = value.get_mut(key).and_then(Value::as_array_mut) {
+            entries.retain(|entry| entry.get("value").is_some());
+        }
+    }
+    match serde_json::from_value(value) {
+        Ok(server) => Some(server),
+        Err(err) => {
+            warn!(error = %err, "ignoring unreadable saved MCP server");
+            None
+        }
+    }
+}
+
+pub fn server_name(server: &McpServer) -> &str {
+    match server {
+        McpServer::Http { name, .. }
+        | McpServer::Sse { name, .. }
+        | McpServer::Stdio { name, .. } => name,
+    }
+}
+
+/// Servers for a loaded session: the ones passed to `session/load`, plus the
+/// saved ones not among them.
+pub fn merge(saved: Vec<Value>, requested: Vec<McpServer>) -> Vec<McpServer> {
+    let mut servers = requested;
+    for server in saved.into_iter().filter_map(restore) {
+        if !servers
+            .iter()
+            .any(|requested| server_name(requested) == server_name(&server))
+        {
+            servers.push(server);
+        }
+    }
+    servers
+}
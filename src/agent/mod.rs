@@ -1,24 +1,102 @@
 use agent_client_protocol::{self as acp, Agent};
 
 // Submodules
+mod acp_config;
+mod approvals;
+mod audit;
+pub(crate) mod backfill;
+mod budget;
+mod checkpoints;
+mod command_history;
 mod commands;
+#[cfg(feature = "acp-compat")]
+mod compat;
 mod config_builder;
+mod context;
+mod context_window;
+mod continuation;
 mod core;
+mod custom_prompts;
+mod diagnostics;
+mod directives;
 mod events;
+mod ext;
+mod failover;
+mod fs_diff;
+mod history;
+mod idle;
+mod images;
+mod instances;
+mod instructions;
+mod interactive;
+mod language;
 mod lifecycle;
+mod local_providers;
+mod mcp_commands;
+mod mcp_servers;
+mod modified_files;
+mod network;
+mod patch_conflicts;
+mod plain_output;
+mod process_info;
 mod prompt;
+mod redact;
+mod risk;
+mod sampling;
+mod sampling_proxy;
+#[cfg(feature = "strict-schema")]
+mod schema_check;
 mod session;
+mod session_events;
+mod session_store;
 mod sessions;
+mod shell_env;
+mod spend;
+mod status;
+mod steering;
+mod stop_reason;
+mod store;
+mod terminal;
+mod thoughts;
+#[cfg(feature = "transcripts")]
+mod transcript;
+mod turn_changes;
+mod turn_result;
+mod undo;
+pub(crate) mod update_check;
+mod update_clock;
+mod usage;
 mod utils;
+mod workspace_mcp;
+mod workspace_roots;
+
+#[cfg(test)]
+mod tests;
 
 // Public exports
+pub use crate::fs::{ClientOp, TerminalRun};
+#[cfg(feature = "acp-compat")]
+pub use compat::ProtocolCompat;
 pub use core::CodexAgent;
-pub use session::{ClientOp, SessionModeLookup};
+pub use plain_output::PlainOutput;
+#[cfg(feature = "strict-schema")]
+pub use schema_check::{
+    Violation, check_notification, check_permission_request, report_notification,
+    report_permission_request,
+};
+pub use session::SessionModeLookup;
+pub use session_events::{
+    PlanStep, SessionEvent, SessionEvents, StepState, ToolCallEvent, ToolStatus,
+};
+pub use turn_result::{ToolSummary, TurnResult};
+pub use update_clock::UpdateClock;
 
 impl From<&CodexAgent> for SessionModeLookup {
     fn from(agent: &CodexAgent) -> Self {
         Self {
             inner: agent.sessions.clone(),
+            audit: agent.audit.clone(),
+            cwd: agent.config.cwd.clone(),
         }
     }
 }
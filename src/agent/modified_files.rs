@@ -0,0 +1,204 @@
+//! `/diff`: everything the agent changed in a session.
+//!
+//! Every file a Codex patch or an `acp_fs` write touches is remembered per
+//! session, together with the content it had before the session first
+//! changed it (taken from the `/undo` snapshots). `/diff` compares that
+//! baseline with the file as it is now and reports one diff per changed
+//! file. Files whose baseline is not known, or whose baselines would exceed
+//! [`MAX_BASELINE_BYTES`], are compared with their `HEAD` version in git.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use agent_client_protocol::{
+    Diff, SessionId, SessionUpdate, ToolCall, ToolCallContent, ToolCallId, ToolCallLocation,
+    ToolCallStatus, ToolKind,
+};
+use uuid::Uuid;
+
+use super::core::CodexAgent;
+use crate::fs::tools::undo::FileSnapshot;
+
+/// Bytes of baseline content remembered per session.
+pub const MAX_BASELINE_BYTES: usize = 8 * 1024 * 1024;
+
+/// A file's content before the session first changed it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Baseline {
+    Content(String),
+    /// The session created the file.
+    Absent,
+    /// Not recorded; `HEAD` in git is used instead.
+    Unknown,
+}
+
+/// The files a session changed, by path.
+#[derive(Debug, Default)]
+pub struct ModifiedFiles {
+    files: BTreeMap<PathBuf, Baseline>,
+    bytes: usize,
+}
+
+impl ModifiedFiles {
+    /// Remember the files of one change. Only a file's first change sets its
+    /// baseline.
+    pub fn record(&mut self, files: &[FileSnapshot]) {
+        for file in files {
+            let baseline = match &file.before {
+                Some(content) if self.bytes + content.len() <= MAX_BASELINE_BYTES => {
+                    Baseline::Content(content.clone())
+                }
+                Some(_) => Baseline::Unknown,
+                None => Baseline::Absent,
+            };
+            self.insert(&file.path, baseline);
+            if let Some(moved_to) = &file.moved_to {
+                self.insert(moved_to, Baseline::Absent);
+            }
+        }
+    }
+
+    /// Remember a changed file whose earlier content was not captured.
+    pub fn touch(&mut self, path: &Path) {
+        self.insert(path, Baseline::Unknown);
+    }
+
+    fn insert(&mut self, path: &Path, baseline: Baseline) {
+        if self.files.contains_key(path) {
+            return;
+        }
+        if let Baseline::Content(content) = &baseline {
+            self.bytes += content.len();
+        }
+        self.files.insert(path.to_path_buf(), baseline);
+    }
+
+    /// The changed files and their baselines, sorted by path.
+    pub fn files(&self) -> Vec<(PathBuf, Baseline)> {
+        self.files
+            .iter()
+            .map(|(path, baseline)| (path.clone(), baseline.clone()))
+            .collect()
+    }
+}
+
+/// The diff from `before` to `current`, or `None` when nothing changed.
+pub fn file_diff(path: &Path, before: Option<String>, current: Option<String>) -> Option<Diff> {
+    if before == current {
+        return None;
+    }
+    Some(Diff {
+        path: path.to_path_buf(),
+        old_text: before,
+        new_text: current.unwrap_or_default(),
+        meta: None,
+    })
+}
+
+/// The `HEAD` version of `path` in its git repository. `None` when there is
+/// none: not a repository, or the file is not committed.
+async fn git_head_content(path: &Path) -> Option<String> {
+    let dir = path.parent()?;
+    let name = path.file_name()?.to_str()?;
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .arg("show")
+        .arg(format!("HEAD:./{name}"))
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+impl CodexAgent {
+    /// Remember files changed without an `/undo` snapshot.
+    pub(super) fn track_modified<'a>(
+        &self,
+        session_id: &SessionId,
+        paths: impl IntoIterator<Item = &'a Path>,
+    ) {
+        self.with_session_state_mut(session_id, |state| {
+            for path in paths {
+                state.modified.touch(path);
+                state.turn_changes.touch(path);
+            }
+        });
+    }
+
+    /// `/diff`: report every file the session changed as a tool call with
+    /// diffs.
+    pub(super) async fn diff_command(&self, session_id: &SessionId, args: &str) {
+        if !args.trim().is_empty() {
+            drop(
+                self.send_message_chunk(session_id, "Usage: /diff\n".into())
+                    .await,
+            );
+            return;
+        }
+        let files = self
+            .with_session_state_mut(session_id, |state| state.modified.files())
+            .unwrap_or_default();
+
+        let mut diffs = Vec::new();
+        let mut unknown = Vec::new();
+        for (path, baseline) in files {
+            let current = tokio::fs::read_to_string(&path).await.ok();
+            let before = match baseline {
+                Baseline::Content(content) => Some(content),
+                Baseline::Absent => None,
+                Baseline::Unknown => match git_head_content(&path).await {
+                    Some(content) => Some(content),
+                    None => {
+                        unknown.push(format!(
+                            "{}: changed, but its earlier content is not known",
+                            path.display()
+                        ));
+                        continue;
+                    }
+                },
+            };
+            diffs.extend(file_diff(&path, before, current));
+        }
+        if diffs.is_empty() && unknown.is_empty() {
+            drop(
+                self.send_message_chunk(session_id, "No files changed in this session.\n".into())
+                    .await,
+            );
+            return;
+        }
+
+        let title = format!(
+            "Diff: {} file(s) changed in this session",
+            diffs.len() + unknown.len()
+        );
+        let locations = diffs
+            .iter()
+            .map(|diff| ToolCallLocation {
+                path: diff.path.clone(),
+                line: None,
+                meta: None,
+            })
+            .collect();
+        let mut content: Vec<ToolCallContent> =
+            diffs.into_iter().map(ToolCallContent::from).collect();
+        content.extend(unknown.into_iter().map(ToolCallContent::from));
+        let update = SessionUpdate::ToolCall(ToolCall {
+            id: ToolCallId(format!("diff-{}", Uuid::new_v4()).into()),
+            title,
+            kind: ToolKind::Read,
+            status: ToolCallStatus::Completed,
+            content,
+            locations,
+            raw_input: None,
+            raw_output: None,
+            meta: None,
+        });
+        drop(self.send_session_update(session_id, update).await);
+    }
+}
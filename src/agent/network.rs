@@ -0,0 +1,171 @@
+//! Network reachability checks for the model provider.
+//!
+//! Sessions probe the provider endpoint (DNS lookup + TCP connect) in the
+//! background once they are created, and a turn that fails with what looks
+//! like a transport error is re-checked so the client gets
+//! [`OFFLINE_ERROR_CODE`] with guidance instead of an opaque stream error.
+//! Slash commands and the acp_fs tools do not need the network and keep
+//! working while offline.
+//!
+//! A direct connection says nothing about reachability through a proxy, so
+//! nothing is probed when one is configured in the environment.
+
+use std::{fmt, time::Duration};
+
+use agent_client_protocol::{Error, SessionId};
+use serde_json::json;
+use tokio::net::{TcpStream, lookup_host};
+
+use super::core::CodexAgent;
+
+/// JSON-RPC error code returned when the model provider is unreachable.
+pub const OFFLINE_ERROR_CODE: i32 = -32050;
+
+/// Environment variables that route provider requests through a proxy.
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTPS_PROXY",
+    "https_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+];
+
+/// Endpoint assumed for providers without an explicit `base_url`.
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Why the provider endpoint could not be reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkIssue {
+    InvalidUrl(String),
+    Dns { host: String },
+    Connect { host: String, reason: String },
+    Timeout { host: String },
+}
+
+impl fmt::Display for NetworkIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUrl(url) => write!(f, "invalid provider URL `{url}`"),
+            Self::Dns { host } => write!(f, "could not resolve `{host}`"),
+            Self::Connect { host, reason } => write!(f, "could not connect to `{host}`: {reason}"),
+            Self::Timeout { host } => write!(f, "timed out connecting to `{host}`"),
+        }
+    }
+}
+
+/// Split a provider base URL into `(host, port)`.
+pub fn endpoint_host_port(base_url: &str) -> Option<(String, u16)> {
+    let url = reqwest::Url::parse(base_url).ok()?;
+    let host = url.host_str()?.to_string();
+    let port = url.port_or_known_default()?;
+    Some((host, port))
+}
+
+/// Whether `var` (an environment lookup) names a proxy.
+pub fn proxy_configured_with(var: impl Fn(&str) -> Option<String>) -> bool {
+    PROXY_ENV_VARS
+        .iter()
+        .any(|name| var(name).is_some_and(|value| !value.trim().is_empty()))
+}
+
+/// Whether a proxy is configured in the environment.
+pub fn proxy_configured() -> bool {
+    proxy_configured_with(|name| std::env::var(name).ok())
+}
+
+/// Resolve and connect to the provider endpoint.
+pub async fn probe(base_url: &str) -> Result<(), NetworkIssue> {
+    let (host, port) =
+        endpoint_host_port(base_url).ok_or_else(|| NetworkIssue::InvalidUrl(base_url.into()))?;
+    let addrs: Vec<_> =
+        match tokio::time::timeout(PROBE_TIMEOUT, lookup_host((host.as_str(), port))).await {
+            Ok(Ok(addrs)) => addrs.collect(),
+            Ok(Err(_)) => return Err(NetworkIssue::Dns { host }),
+            Err(_) => return Err(NetworkIssue::Timeout { host }),
+        };
+    if addrs.is_empty() {
+        return Err(NetworkIssue::Dns { host });
+    }
+    match tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(&addrs[..])).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(err)) => Err(NetworkIssue::Connect {
+            host,
+            reason: err.to_string(),
+        }),
+        Err(_) => Err(NetworkIssue::Timeout { host }),
+    }
+}
+
+/// Heuristic for Codex error messages caused by the transport rather than the model.
+pub fn looks_like_network_error(message: &str) -> bool {
+    const MARKERS: &[&str] = &[
+        "error sending request",
+        "dns error",
+        "failed to lookup address",
+        "connection refused",
+        "connection reset",
+        "network is unreachable",
+        "timed out",
+        "stream disconnected",
+    ];
+    let message = message.to_ascii_lowercase();
+    MARKERS.iter().any(|m| message.contains(m))
+}
+
+/// Human-readable guidance shown for an unreachable provider.
+pub fn offline_guidance(issue: &NetworkIssue) -> String {
+    format!(
+        "The model provider is unreachable ({issue}). Check your network connection, VPN, or proxy settings \
+         (HTTPS_PROXY), or switch to a local provider. Slash commands such as /status and file tools keep working offline."
+    )
+}
+
+/// The error returned to the client when a turn fails because the provider is unreachable.
+pub fn offline_error(issue: &NetworkIssue, base_url: &str) -> Error {
+    Error {
+        code: OFFLINE_ERROR_CODE,
+        message: "Model provider unreachable".to_string(),
+        data: Some(json!({
+            "endpoint": base_url,
+            "reason": issue.to_string(),
+            "guidance": offline_guidance(issue),
+        })),
+    }
+}
+
+impl CodexAgent {
+    /// Base URL of the provider currently used by the session.
+    pub(super) fn provider_base_url(&self, session_id: Option<&SessionId>) -> String {
+        let config = self.model_config();
+        let provider_id = session_id
+            .and_then(|sid| {
+                self.with_session_state_mut(sid, |state| state.current_model.clone())
+                    .flatten()
+            })
+            .and_then(|model| model.split_once('@').map(|(p, _)| p.to_string()))
+            .unwrap_or_else(|| config.model_provider_id.clone());
+        config
+            .model_providers
+            .get(&provider_id)
+            .and_then(|p| p.base_url.clone())
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+    }
+
+    /// Probe the session's provider, returning the offline error if it is
+    /// unreachable. Behind a proxy the provider is assumed reachable.
+    pub(super) async fn check_provider_reachable(
+        &self,
+        session_id: Option<&SessionId>,
+    ) -> Result<(), Error> {
+        if proxy_configured() {
+            return Ok(());
+        }
+        let base_url = self.provider_base_url(session_id);
+        probe(&base_url)
+            .await
+            .map_err(|issue| offline_error(&issue, &base_url))
+    }
+}
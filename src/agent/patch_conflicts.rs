@@ -0,0 +1,82 @@
+//! Conflict checks for patches Codex applies itself.
+//!
+//! See [`conflicts`](crate::fs::tools::conflicts): before a patch comes up for
+//! approval, every file it changes or deletes is compared with what the
+//! `acp_fs` bridge last saw the agent read or write. For each file the user
+//! edited since, the user decides whether the patch goes on top of their edit.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use agent_client_protocol::{Error, SessionId};
+use codex_core::protocol::FileChange;
+use uuid::Uuid;
+
+use super::core::CodexAgent;
+use crate::fs::tools::conflicts::{self, Resolution};
+
+impl CodexAgent {
+    /// Whether the patch with `changes` may go ahead. Files the user aborted
+    /// are taken as seen as they are now, so a fresh patch is not asked about
+    /// again.
+    pub(super) async fn arbitrate_patch(
+        &self,
+        session_id: &SessionId,
+        changes: &HashMap<PathBuf, FileChange>,
+    ) -> Result<bool, Error> {
+        let Some(bridge) = &self.fs_bridge else {
+            return Ok(true);
+        };
+        let Some(fs_session_id) =
+            self.with_session_state_mut(session_id, |state| state.fs_session_id.clone())
+        else {
+            return Ok(true);
+        };
+        for (path, change) in changes {
+            if matches!(change, FileChange::Add { .. }) {
+                continue;
+            }
+            let Some((base, current)) = bridge.user_edit(&fs_session_id, path).await else {
+                continue;
+            };
+            let call_id = format!("patch_conflict-{}", Uuid::new_v4());
+            let request =
+                conflicts::patch_conflict_request(session_id, &call_id, path, &base, &current);
+            let resolution = match self.request_turn_permission(session_id, request).await? {
+                Ok(response) => conflicts::resolution(&response),
+                Err(_) => Resolution::Abort,
+            };
+            if resolution != Resolution::Rebase {
+                bridge.remember_file(&fs_session_id, path, &current);
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Forget what the bridge saw of files Codex just patched.
+    pub(super) fn forget_patched(&self, session_id: &SessionId, paths: &[PathBuf]) {
+        let Some(bridge) = &self.fs_bridge else {
+            return;
+        };
+        if let Some(fs_session_id) =
+            self.with_session_state_mut(session_id, |state| state.fs_session_id.clone())
+        {
+            for path in paths {
+                bridge.forget_file(&fs_session_id, path);
+            }
+        }
+    }
+
+    /// Drop the command results the bridge cached for the session, since the
+    /// workspace may have changed outside it.
+    pub(super) fn invalidate_commands(&self, session_id: &SessionId) {
+        let Some(bridge) = &self.fs_bridge else {
+            return;
+        };
+        if let Some(fs_session_id) =
+            self.with_session_state_mut(session_id, |state| state.fs_session_id.clone())
+        {
+            bridge.invalidate_commands(&fs_session_id);
+        }
+    }
+}
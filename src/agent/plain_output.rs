@@ -0,0 +1,132 @@
+//! ASCII in place of emoji for clients that cannot show them.
+//!
+//! Status output, command replies and notices start with emoji that some
+//! terminals and editors render as empty boxes. With
+//! `CODEX_ACP_PLAIN_OUTPUT=1`, every outgoing message chunk, tool call title,
+//! permission prompt and command icon has those emoji replaced by bracketed
+//! ASCII tags such as `[!]` or `[usage]`. Other text is left untouched.
+
+use std::borrow::Cow;
+
+use agent_client_protocol::{
+    ContentBlock, RequestPermissionRequest, SessionNotification, SessionUpdate,
+};
+use serde_json::Value;
+
+/// Environment variable enabling plain output (`1`, `true`, `yes`).
+pub const PLAIN_OUTPUT_ENV: &str = "CODEX_ACP_PLAIN_OUTPUT";
+
+/// The agent's emoji and their ASCII replacements. A trailing variation
+/// selector (U+FE0F) is dropped along with the emoji.
+const ASCII_TAGS: &[(char, &str)] = &[
+    ('\u{26A0}', "[!]"),
+    ('\u{2139}', "[i]"),
+    ('\u{1F4A1}', "[tip]"),
+    ('\u{1F4DD}', "[init]"),
+    ('\u{1F9E0}', "[model]"),
+    ('\u{1F50D}', "[review]"),
+    ('\u{1F4CA}', "[usage]"),
+    ('\u{1F4C8}', "[stats]"),
+    ('\u{1F4CE}', "[attach]"),
+    ('\u{1F4CF}', "[verbosity]"),
+    ('\u{1F310}', "[language]"),
+    ('\u{1F4CC}', "[pin]"),
+    ('\u{1F50C}', "[mcp]"),
+    ('\u{1F39B}', "[sampling]"),
+    ('\u{1F501}', "[retry]"),
+    ('\u{23E9}', "[continue]"),
+    ('\u{21A9}', "[undo]"),
+    ('\u{1F5C2}', "[diff]"),
+    ('\u{1F4CD}', "[checkpoint]"),
+    ('\u{23EA}', "[rollback]"),
+    ('\u{1F513}', "[unlocked]"),
+    ('\u{1F512}', "[locked]"),
+    ('\u{2753}', "[help]"),
+    ('\u{1F5D1}', "[removed]"),
+    ('\u{1F4E6}', "[compact]"),
+    ('\u{1F5BC}', "[image]"),
+    ('\u{2702}', "[cut]"),
+    ('\u{1F4C2}', "[workspace]"),
+    ('\u{1F464}', "[account]"),
+    ('\u{1F4B0}', "[spend]"),
+    ('\u{2699}', "[process]"),
+    ('\u{1F4DC}', "[prompt]"),
+];
+
+fn ascii_tag(c: char) -> Option<&'static str> {
+    ASCII_TAGS
+        .iter()
+        .find(|(emoji, _)| *emoji == c)
+        .map(|(_, tag)| *tag)
+}
+
+/// `text` with the agent's emoji replaced by ASCII tags.
+pub fn to_ascii(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|c| ascii_tag(c).is_some()) {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match ascii_tag(c) {
+            Some(tag) => {
+                out.push_str(tag);
+                chars.next_if_eq(&'\u{FE0F}');
+            }
+            None => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+fn rewrite(text: &mut String) {
+    if let Cow::Owned(ascii) = to_ascii(text) {
+        *text = ascii;
+    }
+}
+
+/// Rewrites outgoing messages when `CODEX_ACP_PLAIN_OUTPUT` is set.
+#[derive(Debug, Clone, Copy)]
+pub struct PlainOutput;
+
+impl PlainOutput {
+    /// `Some` when plain output is enabled in the environment.
+    pub fn from_env() -> Option<Self> {
+        std::env::var(PLAIN_OUTPUT_ENV)
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false)
+            .then_some(Self)
+    }
+
+    pub fn notification(&self, notification: &mut SessionNotification) {
+        match &mut notification.update {
+            SessionUpdate::AgentMessageChunk(chunk) => {
+                if let ContentBlock::Text(text) = &mut chunk.content {
+                    rewrite(&mut text.text);
+                }
+            }
+            SessionUpdate::ToolCall(call) => rewrite(&mut call.title),
+            SessionUpdate::ToolCallUpdate(update) => {
+                if let Some(title) = &mut update.fields.title {
+                    rewrite(title);
+                }
+            }
+            SessionUpdate::AvailableCommandsUpdate(update) => {
+                for command in &mut update.available_commands {
+                    if let Some(Value::String(icon)) =
+                        command.meta.as_mut().and_then(|meta| meta.get_mut("icon"))
+                    {
+                        rewrite(icon);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn permission_request(&self, request: &mut RequestPermissionRequest) {
+        if let Some(title) = &mut request.tool_call.fields.title {
+            rewrite(title);
+        }
+    }
+}
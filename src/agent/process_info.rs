@@ -0,0 +1,88 @@
+//! The agent process itself, for `/status` and `codex/agentInfo`.
+//!
+//! Editors sometimes spawn a second agent or keep an old one alive. The PID,
+//! uptime, resident memory and the number of open sessions and bridge
+//! connections make such duplicates and slow leaks easy to spot.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub version: String,
+    pub uptime_secs: u64,
+    /// Resident set size; `None` where it cannot be read (non-Linux).
+    pub rss_bytes: Option<u64>,
+    pub open_sessions: usize,
+    /// `--acp-fs-mcp` helpers currently connected to the FS bridge.
+    pub bridge_connections: usize,
+}
+
+impl ProcessInfo {
+    /// Lines shown under the status section.
+    pub fn describe(&self) -> Vec<String> {
+        vec![
+            format!("PID:                {}", self.pid),
+            format!("Version:            {}", self.version),
+            format!("Uptime:             {}", format_uptime(self.uptime_secs)),
+            format!(
+                "Memory (RSS):       {}",
+                self.rss_bytes
+                    .map(format_bytes)
+                    .unwrap_or_else(|| "(unknown)".to_string())
+            ),
+            format!("Open Sessions:      {}", self.open_sessions),
+            format!("Bridge Connections: {}", self.bridge_connections),
+        ]
+    }
+}
+
+/// Resident memory of this process.
+pub fn rss_bytes() -> Option<u64> {
+    if cfg!(target_os = "linux") {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        parse_vm_rss(&status)
+    } else {
+        None
+    }
+}
+
+/// The `VmRSS` line of `/proc/<pid>/status`, in bytes.
+pub fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let mut fields = line["VmRSS:".len()..].split_whitespace();
+    let value: u64 = fields.next()?.parse().ok()?;
+    match fields.next() {
+        Some("kB") | None => Some(value * 1024),
+        Some(_) => None,
+    }
+}
+
+/// `45s`, `12m 05s`, `3h 07m`, `2d 04h`.
+pub fn format_uptime(secs: u64) -> String {
+    let (days, hours, minutes, seconds) =
+        (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{days}d {hours:02}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// `512 KiB`, `48.3 MiB`, `1.2 GiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    if bytes < KIB * KIB {
+        format!("{:.0} KiB", bytes / KIB)
+    } else if bytes < KIB * KIB * KIB {
+        format!("{:.1} MiB", bytes / (KIB * KIB))
+    } else {
+        format!("{:.1} GiB", bytes / (KIB * KIB * KIB))
+    }
+}
@@ -1,16 +1,123 @@
 use agent_client_protocol as acp;
-use codex_core::protocol::{ErrorEvent, EventMsg, Op, PatchApplyEndEvent, StreamErrorEvent};
+use codex_core::{
+    CodexConversation,
+    protocol::{
+        ErrorEvent, EventMsg, FileChange, Op, PatchApplyEndEvent, ReviewDecision, StreamErrorEvent,
+    },
+};
 use codex_protocol::{
     plan_tool::{StepStatus, UpdatePlanArgs},
     user_input::UserInput,
 };
-use serde_json::json;
-use tokio::sync::oneshot;
-use tracing::info;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
-use super::{core::CodexAgent, events, session::ClientOp};
+use tokio::task;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use super::{
+    approvals::{self, PendingExecApproval},
+    audit, command_history, context_window, continuation,
+    core::CodexAgent,
+    directives, events, failover,
+    images::{self, ImageBudget},
+    interactive, network, session, steering, stop_reason,
+    store::{JournalEntry, TurnStatus},
+    turn_changes::{self, TurnChanges},
+    undo,
+};
+use crate::fs::tools::{
+    audit::AuditEvent,
+    command_cache,
+    protected::{self, ProtectedPaths},
+};
+
+/// How a streamed turn ended.
+pub(super) struct TurnOutcome {
+    pub stop_reason: acp::StopReason,
+    /// Message of the last fatal `EventMsg::Error`, if the turn failed.
+    pub error: Option<String>,
+    /// Whether the turn ran, or asked to run, a command, tool or patch.
+    pub tool_activity: bool,
+}
+
+/// Data journaled for a finished user turn.
+struct TurnRecord {
+    submit_id: String,
+    prompt: String,
+    output: String,
+    status: TurnStatus,
+    error: Option<String>,
+    seed: Option<u64>,
+}
 
 impl CodexAgent {
+    /// Run a prompt turn on its own local task.
+    ///
+    /// Turns of different sessions stream concurrently; a second prompt for a
+    /// session waits until that session's current turn has finished, or
+    /// interrupts it when the session steers (see [`steering`]).
+    pub(super) async fn prompt(
+        &self,
+        args: acp::PromptRequest,
+    ) -> Result<acp::PromptResponse, acp::Error> {
+        let (turn_lock, session_steering) = self
+            .try_with_session_state_mut(&args.session_id, |state| {
+                (state.turn_lock.clone(), state.steering)
+            })?;
+        let steering =
+            steering::steering_from_meta(args.meta.as_ref())?.unwrap_or(session_steering);
+        if steering == steering::Steering::Interrupt && turn_lock.try_lock().is_err() {
+            info!(session_id = %args.session_id.0, "steering: interrupting the running turn");
+            self.steer(&args.session_id).await?;
+        }
+        let agent = self.clone();
+        let session_id = args.session_id.clone();
+        let result = task::spawn_local(async move {
+            let _turn = turn_lock.lock().await;
+            let session_id = args.session_id.clone();
+            agent.with_session_state_mut(&session_id, |state| {
+                state.undo.begin_turn();
+                state.turn_changes = TurnChanges::default();
+            });
+            agent.checkpoint_session_start(&session_id).await;
+            #[cfg(feature = "transcripts")]
+            agent.begin_transcript(&session_id);
+            let mut response = agent.run_prompt(args).await?;
+            // Resume responses cut off by the token limit, as configured.
+            for _ in 0..continuation::auto_continue_limit() {
+                if !matches!(response.stop_reason, acp::StopReason::MaxTokens) {
+                    break;
+                }
+                response = agent
+                    .run_prompt(continuation::continue_request(session_id.clone()))
+                    .await?;
+            }
+            let changes = agent
+                .with_session_state_mut(&session_id, |state| {
+                    std::mem::take(&mut state.turn_changes)
+                })
+                .unwrap_or_default();
+            let summary = changes.summarize().await;
+            agent.checkpoint_turn(&session_id, &summary).await;
+            #[cfg(feature = "transcripts")]
+            agent.finish_transcript(&session_id);
+            response.meta = turn_changes::with_change_summary(response.meta, &summary);
+            Ok(response)
+        })
+        .await
+        .map_err(acp::Error::into_internal_error)?;
+        match &result {
+            Ok(response) => self.emit_turn_ended(&session_id.0, Some(response.stop_reason), None),
+            Err(err) => self.emit_turn_ended(&session_id.0, None, Some(err.message.clone())),
+        }
+        result
+    }
+
     /// Process a user prompt and stream responses back to the client.
     ///
     /// This handles:
@@ -18,14 +125,24 @@ impl CodexAgent {
     /// - Text, image, audio, and resource content blocks
     /// - Streaming agent responses, reasoning, and tool calls
     /// - Approval requests for commands and file operations
-    pub(super) async fn prompt(
+    async fn run_prompt(
         &self,
         args: acp::PromptRequest,
     ) -> Result<acp::PromptResponse, acp::Error> {
         info!(?args, "Received prompt request");
+        self.touch_session(&args.session_id);
         let event_handler =
-            events::EventHandler::new(self.config.cwd.clone(), self.support_terminal());
-        let mut reason = events::ReasoningAggregator::new();
+            events::EventHandler::new(self.config.cwd.clone(), self.support_terminal())
+                .with_roots(&self.workspace_roots(&args.session_id));
+        let mut reason = if self
+            .with_session_state_mut(&args.session_id, |state| state.stream_reasoning)
+            .unwrap_or(false)
+            && self.thought_delivery.get().streams()
+        {
+            events::ReasoningAggregator::streaming()
+        } else {
+            events::ReasoningAggregator::new()
+        };
         let conversation = self.get_conversation(&args.session_id).await?;
 
         let mut op_opt = None;
@@ -33,9 +150,12 @@ impl CodexAgent {
         if let Some(acp::ContentBlock::Text(t)) = args.prompt.first() {
             let line = t.text.trim();
             if let Some(cmd) = line.strip_prefix('/') {
-                let mut parts = cmd.split_whitespace();
-                let name = parts.next().unwrap_or("").to_lowercase();
-                match self.handle_slash_command(&args.session_id, &name).await {
+                let (name, rest) = cmd.split_once(char::is_whitespace).unwrap_or((cmd, ""));
+                let name = name.to_lowercase();
+                match self
+                    .handle_slash_command(&args.session_id, &name, rest.trim())
+                    .await
+                {
                     Some(op) => {
                         op_opt = Some(op);
                     }
@@ -49,79 +169,519 @@ impl CodexAgent {
             }
         }
 
+        // Everything past this point reaches the model, so enforce the token cap and spend budget.
+        self.check_token_cap(&args.session_id)?;
+        self.check_spend_budget(&args.session_id).await?;
+
+        // Per-turn directives (e.g. "!ro", "!m <model>") at the start of the prompt.
+        let mut directives = directives::TurnDirectives::default();
+        let mut first_text: Option<String> = None;
+        if op_opt.is_none()
+            && let Some(acp::ContentBlock::Text(t)) = args.prompt.first()
+            && t.text.trim_start().starts_with('!')
+        {
+            let (parsed, rest) = directives::parse_directives(&t.text);
+            directives = parsed;
+            first_text = Some(rest);
+        }
+        let turn_override = if directives.is_empty() {
+            None
+        } else {
+            Some(self.directive_overrides(&args.session_id, &directives)?)
+        };
+        let session_model = self.session_context(&args.session_id)?.model;
+        let directive_model = directives
+            .model
+            .as_deref()
+            .map(|model| directives::directive_model_id(session_model.as_deref(), model));
+
         reason.reset();
 
         // Build user input submission items from prompt content blocks.
+        let model = session_model.unwrap_or_else(|| self.config.model.clone());
+        let mut image_budget = ImageBudget::for_model(&model);
         let mut items: Vec<UserInput> = Vec::new();
-        for block in &args.prompt {
+        for (idx, block) in args.prompt.iter().enumerate() {
             match block {
                 acp::ContentBlock::Text(t) => {
-                    items.push(UserInput::Text {
-                        text: t.text.clone(),
-                    });
+                    let text = match first_text.take().filter(|_| idx == 0) {
+                        Some(stripped) if stripped.is_empty() => continue,
+                        Some(stripped) => stripped,
+                        None => t.text.clone(),
+                    };
+                    items.push(UserInput::Text { text });
                 }
                 acp::ContentBlock::Image(img) => {
-                    let url = format!("data:{};base64,{}", img.mime_type, img.data);
-                    items.push(UserInput::Image { image_url: url });
+                    self.push_image_input(
+                        &args.session_id,
+                        &mut image_budget,
+                        &img.mime_type,
+                        &img.data,
+                        &mut items,
+                    )
+                    .await?;
                 }
                 acp::ContentBlock::Audio(_a) => {
                     // Not supported by Codex input yet; skip.
                 }
-                acp::ContentBlock::Resource(res) => {
-                    if let acp::EmbeddedResourceResource::TextResourceContents(trc) = &res.resource
-                    {
+                acp::ContentBlock::Resource(res) => match &res.resource {
+                    acp::EmbeddedResourceResource::TextResourceContents(trc) => {
                         items.push(UserInput::Text {
                             text: trc.text.clone(),
                         });
                     }
-                }
+                    acp::EmbeddedResourceResource::BlobResourceContents(blob) => {
+                        if let Some(mime_type) =
+                            images::image_mime_type(blob.mime_type.as_deref(), &blob.uri)
+                        {
+                            self.push_image_input(
+                                &args.session_id,
+                                &mut image_budget,
+                                &mime_type,
+                                &blob.blob,
+                                &mut items,
+                            )
+                            .await?;
+                        }
+                    }
+                },
                 acp::ContentBlock::ResourceLink(link) => {
-                    items.push(UserInput::Text {
-                        text: format!("Resource: {}", link.uri),
-                    });
+                    let image_link = images::image_mime_type(link.mime_type.as_deref(), &link.uri)
+                        .filter(|_| image_budget.vision());
+                    let pushed = match image_link {
+                        Some(mime_type) => {
+                            self.push_image_link(
+                                &args.session_id,
+                                &mut image_budget,
+                                &mime_type,
+                                &link.uri,
+                                &mut items,
+                            )
+                            .await?
+                        }
+                        None => false,
+                    };
+                    if !pushed {
+                        items.push(UserInput::Text {
+                            text: format!("Resource: {}", link.uri),
+                        });
+                    }
                 }
             }
         }
 
-        let op = match op_opt {
-            Some(op) => op,
-            None => Op::UserInput { items },
+        // User input, typed or produced by a command such as /retry, gets the
+        // session context prepended. Typed prompts are remembered for /retry.
+        let (command_op, user_items) = match op_opt {
+            Some(Op::UserInput { items }) => (None, Some(items)),
+            Some(op) => (Some(op), None),
+            None => {
+                self.with_session_state_mut(&args.session_id, |state| {
+                    state.last_prompt = Some(items.clone());
+                });
+                (None, Some(items))
+            }
         };
+        let prompt_summary = user_items
+            .as_deref()
+            .map(summarize_input)
+            .unwrap_or_default();
+        // Keep user input around so a turn can be resubmitted after compacting.
+        let (op, retry_items) = match (command_op, user_items) {
+            (Some(op), _) => (op, None),
+            (None, items) => {
+                let mut with_context = self.context_items(&args.session_id).await;
+                with_context.extend(items.unwrap_or_default());
+                (
+                    Op::UserInput {
+                        items: with_context.clone(),
+                    },
+                    Some(with_context),
+                )
+            }
+        };
+        let is_user_turn = retry_items.is_some();
+        let seed = self.turn_seed(&args)?;
+        let mut output = String::new();
+
+        // Apply temporary turn overrides from directives before submitting.
+        let (apply_override, revert_override) = turn_override.unzip();
+        if let Some(apply) = apply_override {
+            conversation
+                .submit(apply)
+                .await
+                .map_err(acp::Error::into_internal_error)?;
+        }
+
+        // Usage is credited to the model that actually answers, bridge writes
+        // are checked against the directive's mode, and the provider gets the
+        // turn's seed.
+        self.with_session_state_mut(&args.session_id, |state| {
+            state.turn_model = directive_model;
+            state.turn_mode = directives.mode.clone();
+        });
+        let seed_applied = self.apply_turn_seed(&args.session_id, seed);
 
         // Enqueue work and then stream corresponding events back as ACP updates.
-        let submit_id = conversation
-            .submit(op)
-            .await
-            .map_err(acp::Error::into_internal_error)?;
+        let mut fallbacks = match retry_items {
+            Some(_) => self.fallback_chain(&args.session_id),
+            None => Vec::new(),
+        }
+        .into_iter();
+        let mut failed_over = None;
+        let mut op = op;
+        let mut submit_id = String::new();
+        let result = loop {
+            submit_id = match conversation.submit(op).await {
+                Ok(id) => id,
+                Err(err) => break Err(acp::Error::into_internal_error(err)),
+            };
+            let outcome = self
+                .stream_turn(
+                    &args.session_id,
+                    &conversation,
+                    &submit_id,
+                    &event_handler,
+                    &mut reason,
+                    &mut output,
+                )
+                .await;
+            let next = match &outcome {
+                Ok(TurnOutcome {
+                    error: Some(error),
+                    tool_activity,
+                    ..
+                }) if is_user_turn && failover::can_fail_over(error, *tool_activity) => {
+                    fallbacks.next().map(|next| (error.clone(), next))
+                }
+                _ => None,
+            };
+            let Some((error, next)) = next else {
+                break outcome;
+            };
+
+            let next_id = next.to_model_id();
+            warn!(%error, fallback = %next_id, "turn failed; retrying on fallback model");
+            if let Err(err) = self
+                .send_message_chunk(
+                    &args.session_id,
+                    format!("🔁 Retrying this turn with {next_id}…\n\n").into(),
+                )
+                .await
+            {
+                break Err(err);
+            }
+            if let Err(err) = conversation.submit(failover::failover_op(&next)).await {
+                break Err(acp::Error::into_internal_error(err));
+            }
+            self.with_session_state_mut(&args.session_id, |state| {
+                state.turn_model = Some(next_id.clone());
+            });
+            failed_over = Some(next_id);
+            // Only the fallback model's answer belongs to this turn.
+            output.clear();
+            op = failover::retry_op();
+        };
+
+        // A context-window overflow can be recovered by compacting and retrying once.
+        let result = match (result, retry_items) {
+            (Ok(outcome), Some(items))
+                if outcome
+                    .error
+                    .as_deref()
+                    .is_some_and(context_window::is_context_window_error)
+                    && context_window::auto_compact_enabled() =>
+            {
+                self.compact_and_retry(
+                    &args.session_id,
+                    &conversation,
+                    items,
+                    &event_handler,
+                    &mut reason,
+                    &mut output,
+                )
+                .await
+            }
+            (result, _) => result,
+        };
+
+        // Revert directive overrides so the session settings stay untouched.
+        self.with_session_state_mut(&args.session_id, |state| {
+            state.turn_model = None;
+            state.turn_mode = None;
+        });
+        if seed_applied {
+            self.restore_sampling(&args.session_id);
+        }
+        if let Some(revert) = revert_override {
+            if let Err(err) = conversation.submit(revert).await {
+                warn!(error = %err, "failed to revert per-turn directive overrides");
+            }
+        } else if failed_over.is_some()
+            && let Ok(ctx) = self.session_context(&args.session_id)
+            && let Err(err) = conversation
+                .submit(Op::OverrideTurnContext {
+                    cwd: None,
+                    approval_policy: None,
+                    sandbox_policy: None,
+                    model: ctx.model,
+                    effort: Some(ctx.effort),
+                    summary: None,
+                })
+                .await
+        {
+            warn!(error = %err, "failed to restore model after failover");
+        }
 
+        // Journal the turn; failed turns keep their partial output and offer /retry.
+        let failure = match &result {
+            Ok(outcome) => outcome.error.clone(),
+            Err(err) => Some(err.message.clone()),
+        };
+        let steered = self
+            .with_session_state_mut(&args.session_id, |state| std::mem::take(&mut state.steered))
+            .unwrap_or(false);
+        let status = match &result {
+            // The model answered; the stop reason tells the client why it stopped.
+            Ok(TurnOutcome {
+                stop_reason: acp::StopReason::MaxTokens | acp::StopReason::Refusal,
+                ..
+            }) => TurnStatus::Completed,
+            _ if failure.is_some() => TurnStatus::Failed,
+            Ok(TurnOutcome {
+                stop_reason: acp::StopReason::Cancelled,
+                ..
+            }) if steered => TurnStatus::Steered,
+            Ok(TurnOutcome {
+                stop_reason: acp::StopReason::Cancelled,
+                ..
+            }) => TurnStatus::Cancelled,
+            _ => TurnStatus::Completed,
+        };
+        let truncated = matches!(
+            &result,
+            Ok(TurnOutcome {
+                stop_reason: acp::StopReason::MaxTokens,
+                ..
+            })
+        )
+        .then(|| continuation::output_tail(&output));
+        self.with_session_state_mut(&args.session_id, |state| {
+            state.truncated_output = truncated;
+        });
+        if is_user_turn {
+            // The prompt that replaced this turn picks up where it left off.
+            if status == TurnStatus::Steered {
+                let context = steering::steer_context(&prompt_summary, &output);
+                self.with_session_state_mut(&args.session_id, |state| {
+                    state.pending_context.push(context);
+                });
+            }
+            let record = TurnRecord {
+                submit_id,
+                prompt: prompt_summary,
+                output,
+                status,
+                error: failure,
+                seed,
+            };
+            self.record_turn(&args.session_id, record).await;
+            if status == TurnStatus::Failed {
+                drop(
+                    self.send_message_chunk(
+                        &args.session_id,
+                        "\n⚠️ This turn failed; partial output was kept. Send /retry to resubmit your prompt.\n"
+                            .into(),
+                    )
+                    .await,
+                );
+            }
+        }
+        let outcome = result?;
+
+        if let Some(error) = &outcome.error
+            && context_window::is_context_window_error(error)
+        {
+            self.send_message_chunk(
+                &args.session_id,
+                "💡 Run /compact to summarize earlier history, then retry.\n".into(),
+            )
+            .await?;
+            return Err(context_window::context_window_error(error));
+        }
+
+        // Turn failures caused by the transport get a dedicated offline error.
+        if let Some(error) = &outcome.error
+            && network::looks_like_network_error(error)
+        {
+            self.check_provider_reachable(Some(&args.session_id))
+                .await?;
+        }
+        let stop_reason = outcome.stop_reason;
+        if matches!(stop_reason, acp::StopReason::MaxTokens) {
+            self.send_message_chunk(
+                &args.session_id,
+                "\n✂️ The response hit the output token limit; send /continue to resume it.\n"
+                    .into(),
+            )
+            .await?;
+        }
+        if let Some(model) = failed_over {
+            self.send_message_chunk(
+                &args.session_id,
+                format!("\n\nℹ️ Answered by fallback model {model}.\n").into(),
+            )
+            .await?;
+        }
+
+        if let Some(text) = reason.take_text()
+            && !text.trim().is_empty()
+        {
+            self.send_thought_chunk(&args.session_id, text.into())
+                .await?;
+        }
+
+        Ok(acp::PromptResponse {
+            meta: if status == TurnStatus::Steered {
+                Some(steering::steered_meta())
+            } else {
+                stop_reason::stop_meta(&stop_reason, outcome.error.as_deref())
+            },
+            stop_reason,
+        })
+    }
+
+    /// Stream Codex events for the submission `submit_id` back to the client
+    /// until the turn ends, returning the resulting stop reason and any error.
+    ///
+    /// Agent message text is appended to `output` as it streams, so it
+    /// survives even if the turn fails midway.
+    pub(super) async fn stream_turn(
+        &self,
+        session_id: &acp::SessionId,
+        conversation: &CodexConversation,
+        submit_id: &str,
+        event_handler: &events::EventHandler,
+        reason: &mut events::ReasoningAggregator,
+        output: &mut String,
+    ) -> Result<TurnOutcome, acp::Error> {
         let mut saw_message_delta = false;
+        let mut error = None;
+        let mut tool_activity = false;
+        // Low-risk exec approvals collected for a digest.
+        let mut pending_approvals: Vec<PendingExecApproval> = Vec::new();
+        // Output of running commands, streamed as progressive tool call updates.
+        let mut exec_output = events::ExecOutputBuffer::new();
+        // Command and cwd of running commands, for the command history.
+        let mut running_commands: HashMap<String, (Vec<String>, PathBuf)> = HashMap::new();
+        // Changes of patches being applied, for the audit log and `/undo`.
+        let mut patching: HashMap<String, HashMap<PathBuf, FileChange>> = HashMap::new();
+        let dry_run = self
+            .with_session_state_mut(session_id, |state| {
+                session::is_dry_run_mode(state.effective_mode())
+            })
+            .unwrap_or_default();
+        // Cached command results only serve the turn they were made in.
+        self.invalidate_commands(session_id);
+        // Fired by `session/cancel`; see `cancel`.
+        let cancel = CancellationToken::new();
+        self.with_session_state_mut(session_id, |state| state.turn_cancel = cancel.clone());
         let stop_reason = loop {
-            let event = conversation
-                .next_event()
-                .await
-                .map_err(acp::Error::into_internal_error)?;
+            let collecting = !pending_approvals.is_empty();
+            let next_event = async {
+                if !collecting {
+                    Some(conversation.next_event().await)
+                } else {
+                    tokio::time::timeout(approvals::DIGEST_WINDOW, conversation.next_event())
+                        .await
+                        .ok()
+                }
+            };
+            let next_event = tokio::select! {
+                biased;
+                () = cancel.cancelled() => {
+                    // Decline approvals still collected for a digest and end the turn now.
+                    for pending in std::mem::take(&mut pending_approvals) {
+                        conversation
+                            .submit(Op::ExecApproval {
+                                id: pending.event_id,
+                                decision: ReviewDecision::Abort,
+                            })
+                            .await
+                            .map_err(acp::Error::into_internal_error)?;
+                    }
+                    break acp::StopReason::Cancelled;
+                }
+                next_event = next_event => next_event,
+            };
+            let Some(event) = next_event else {
+                let batch = std::mem::take(&mut pending_approvals);
+                self.resolve_exec_approvals(session_id, conversation, event_handler, batch)
+                    .await?;
+                continue;
+            };
+            let event = event.map_err(acp::Error::into_internal_error)?;
             if event.id != submit_id {
                 continue;
             }
+            #[cfg(feature = "transcripts")]
+            self.record_transcript_event(session_id, &event.msg);
+            tool_activity |= matches!(
+                &event.msg,
+                EventMsg::McpToolCallBegin(_)
+                    | EventMsg::ExecCommandBegin(_)
+                    | EventMsg::ExecApprovalRequest(_)
+                    | EventMsg::ApplyPatchApprovalRequest(_)
+                    | EventMsg::PatchApplyBegin(_)
+            );
+
+            // Anything but another batchable approval closes the current digest.
+            let batchable = matches!(
+                &event.msg,
+                EventMsg::ExecApprovalRequest(req)
+                    if !dry_run && approvals::digest_enabled() && approvals::is_low_risk(&req.command)
+            );
+            if !batchable && !pending_approvals.is_empty() {
+                let batch = std::mem::take(&mut pending_approvals);
+                self.resolve_exec_approvals(session_id, conversation, event_handler, batch)
+                    .await?;
+            }
 
             match event.msg {
                 EventMsg::AgentMessageDelta(delta) => {
                     saw_message_delta = true;
-                    self.send_message_chunk(&args.session_id, delta.delta.into())
+                    output.push_str(&delta.delta);
+                    self.send_message_chunk(session_id, delta.delta.into())
                         .await?;
                 }
                 EventMsg::AgentMessage(msg) => {
                     if saw_message_delta {
                         continue;
                     }
-                    self.send_message_chunk(&args.session_id, msg.message.into())
+                    output.push_str(&msg.message);
+                    self.send_message_chunk(session_id, msg.message.into())
                         .await?;
                 }
                 EventMsg::AgentReasoningDelta(delta) => {
-                    reason.append_delta(&delta.delta);
+                    if let Some(chunk) = reason.stream_delta(&delta.delta) {
+                        self.send_thought_chunk(session_id, chunk.into()).await?;
+                    }
                 }
                 EventMsg::AgentReasoningRawContentDelta(delta) => {
-                    reason.append_delta(&delta.delta);
+                    if let Some(chunk) = reason.stream_delta(&delta.delta) {
+                        self.send_thought_chunk(session_id, chunk.into()).await?;
+                    }
+                }
+                // When streaming, final texts only matter if no deltas arrived.
+                EventMsg::AgentReasoning(reason_ev) if reason.is_streaming() => {
+                    if let Some(text) = reason.stream_item_end(Some(reason_ev.text)) {
+                        self.send_thought_chunk(session_id, text.into()).await?;
+                    }
+                }
+                EventMsg::AgentReasoningRawContent(reason_ev) if reason.is_streaming() => {
+                    if let Some(text) = reason.stream_item_end(Some(reason_ev.text)) {
+                        self.send_thought_chunk(session_id, text.into()).await?;
+                    }
                 }
                 EventMsg::AgentReasoning(reason_ev) => {
                     reason.section_break();
@@ -133,7 +693,7 @@ impl CodexAgent {
                     if let Some(text) = reason.choose_final_text(final_text)
                         && !text.trim().is_empty()
                     {
-                        self.send_thought_chunk(&args.session_id, text.clone().into())
+                        self.send_thought_chunk(session_id, text.clone().into())
                             .await?;
                     }
                 }
@@ -150,7 +710,7 @@ impl CodexAgent {
                 EventMsg::McpToolCallBegin(begin) => {
                     let update =
                         event_handler.on_mcp_tool_call_begin(&begin.call_id, &begin.invocation);
-                    self.send_session_update(&args.session_id, update).await?;
+                    self.send_session_update(session_id, update).await?;
                 }
                 EventMsg::McpToolCallEnd(end) => {
                     let result_json =
@@ -161,7 +721,7 @@ impl CodexAgent {
                         &result_json,
                         end.is_success(),
                     );
-                    self.send_session_update(&args.session_id, update).await?;
+                    self.send_session_update(session_id, update).await?;
                 }
                 // Exec command begin/end → ACP ToolCall/ToolCallUpdate
                 EventMsg::ExecCommandBegin(beg) => {
@@ -171,9 +731,51 @@ impl CodexAgent {
                         &beg.command,
                         &beg.parsed_cmd,
                     );
-                    self.send_session_update(&args.session_id, update).await?;
+                    // Calls rendered as a client terminal keep their terminal content.
+                    if matches!(&update, acp::SessionUpdate::ToolCall(tool) if tool.content.is_empty())
+                    {
+                        exec_output.begin(&beg.call_id);
+                    }
+                    running_commands.insert(beg.call_id.clone(), (beg.command, beg.cwd));
+                    self.send_session_update(session_id, update).await?;
+                }
+                EventMsg::ExecCommandOutputDelta(delta) => {
+                    if let Some(update) =
+                        exec_output.push(&delta.call_id, &delta.chunk, Instant::now())
+                    {
+                        self.send_session_update(session_id, update).await?;
+                    }
                 }
                 EventMsg::ExecCommandEnd(end) => {
+                    exec_output.finish(&end.call_id);
+                    if let Some((command, cwd)) = running_commands.remove(&end.call_id) {
+                        if !command_cache::is_cacheable(&interactive::command_words(&command)) {
+                            self.invalidate_commands(session_id);
+                        }
+                        let mut entry = audit::command_entry(
+                            session_id.0.to_string(),
+                            AuditEvent::Exec,
+                            &command,
+                            &cwd,
+                        );
+                        entry.exit_code = Some(end.exit_code);
+                        self.audit.record(entry);
+                        let output = if end.aggregated_output.is_empty() {
+                            format!("{}{}", end.stdout, end.stderr)
+                        } else {
+                            end.aggregated_output.clone()
+                        };
+                        self.record_command(
+                            session_id,
+                            command_history::new_record(
+                                &command,
+                                cwd,
+                                end.exit_code,
+                                end.duration.as_millis() as u64,
+                                &output,
+                            ),
+                        );
+                    }
                     let exec_end_args = events::ExecEndArgs {
                         call_id: end.call_id.clone(),
                         exit_code: end.exit_code,
@@ -184,37 +786,96 @@ impl CodexAgent {
                         formatted_output: end.formatted_output.clone(),
                     };
                     let update = event_handler.on_exec_command_end(exec_end_args);
-                    self.send_session_update(&args.session_id, update).await?;
+                    self.send_session_update(session_id, update).await?;
                 }
-                EventMsg::ExecApprovalRequest(req) => {
-                    let permission_req = event_handler.on_exec_approval_request(
-                        &args.session_id,
+                // Dry run: report commands and patches as simulated and decline them.
+                EventMsg::ExecApprovalRequest(req) if dry_run => {
+                    let update = event_handler.on_dry_run_exec(
                         &req.call_id,
                         &req.cwd,
+                        &req.command,
                         &req.parsed_cmd,
                     );
-
-                    let (txp, rxp) = oneshot::channel();
-                    let _ = self.client_tx.send(ClientOp::RequestPermission {
-                        session_id: args.session_id.clone(),
-                        request: permission_req,
-                        response_tx: txp,
-                    });
-                    let outcome: Result<acp::RequestPermissionResponse, acp::Error> =
-                        rxp.await.map_err(|_| acp::Error::internal_error())?;
-                    if let Ok(resp) = outcome {
-                        let decision = events::handle_response_outcome(resp);
-                        // Send ExecApproval back to Codex; refer to current event.id
+                    self.send_session_update(session_id, update).await?;
+                    conversation
+                        .submit(Op::ExecApproval {
+                            id: event.id.clone(),
+                            decision: ReviewDecision::Denied,
+                        })
+                        .await
+                        .map_err(acp::Error::into_internal_error)?;
+                }
+                EventMsg::ApplyPatchApprovalRequest(req) if dry_run => {
+                    let changes: Vec<(String, _)> = req
+                        .changes
+                        .iter()
+                        .map(|(p, c)| (p.display().to_string(), c.clone()))
+                        .collect();
+                    let update = event_handler.on_dry_run_patch(session_id, &req.call_id, &changes);
+                    self.send_session_update(session_id, update).await?;
+                    conversation
+                        .submit(Op::PatchApproval {
+                            id: event.id.clone(),
+                            decision: ReviewDecision::Denied,
+                        })
+                        .await
+                        .map_err(acp::Error::into_internal_error)?;
+                }
+                EventMsg::ExecApprovalRequest(req) => {
+                    let pending = PendingExecApproval::new(event.id.clone(), req);
+                    // Approved commands may run in a client terminal, out of our sight.
+                    if !command_cache::is_cacheable(&interactive::command_words(&pending.command)) {
+                        self.invalidate_commands(session_id);
+                    }
+                    // Interactive commands are asked about one by one and, once
+                    // approved, run in a client terminal (see `submit_exec_decision`).
+                    if let Some(why) = interactive::interactive_reason(&pending.command) {
+                        self.resolve_interactive_exec(
+                            session_id,
+                            conversation,
+                            event_handler,
+                            &pending,
+                            why,
+                        )
+                        .await?;
+                    } else if self.is_terminal_approved(session_id, &pending.command) {
+                        self.submit_exec_decision(
+                            session_id,
+                            conversation,
+                            &pending,
+                            ReviewDecision::ApprovedForSession,
+                        )
+                        .await?;
+                    } else if batchable {
+                        pending_approvals.push(pending);
+                    } else {
+                        self.request_exec_approval(
+                            session_id,
+                            conversation,
+                            event_handler,
+                            &pending,
+                        )
+                        .await?;
+                    }
+                }
+                EventMsg::ApplyPatchApprovalRequest(req) => {
+                    // Files the user edited since the agent saw them come first.
+                    if !self.arbitrate_patch(session_id, &req.changes).await? {
+                        self.audit_patch(
+                            session_id,
+                            AuditEvent::PatchDecision,
+                            req.changes.keys().cloned().collect(),
+                            "conflict-aborted",
+                        );
                         conversation
-                            .submit(Op::ExecApproval {
+                            .submit(Op::PatchApproval {
                                 id: event.id.clone(),
-                                decision,
+                                decision: ReviewDecision::Denied,
                             })
                             .await
                             .map_err(acp::Error::into_internal_error)?;
+                        continue;
                     }
-                }
-                EventMsg::ApplyPatchApprovalRequest(req) => {
                     // Convert changes to the type expected by EventHandler
                     let changes: Vec<(String, _)> = req
                         .changes
@@ -222,21 +883,41 @@ impl CodexAgent {
                         .map(|(p, c)| (p.display().to_string(), c.clone()))
                         .collect();
 
-                    let permission_req = event_handler.on_apply_patch_approval_request(
-                        &args.session_id,
+                    let mut permission_req = event_handler.on_apply_patch_approval_request(
+                        session_id,
                         &req.call_id,
                         &changes,
                     );
-                    let (txp, rxp) = oneshot::channel();
-                    let _ = self.client_tx.send(ClientOp::RequestPermission {
-                        session_id: args.session_id.clone(),
-                        request: permission_req,
-                        response_tx: txp,
-                    });
-                    let outcome: Result<acp::RequestPermissionResponse, acp::Error> =
-                        rxp.await.map_err(acp::Error::into_internal_error)?;
+                    let protected = ProtectedPaths::from_env();
+                    let hits: Vec<(String, String)> = req
+                        .changes
+                        .keys()
+                        .filter_map(|path| {
+                            protected
+                                .matching(&self.config.cwd, path)
+                                .map(|pattern| (path.display().to_string(), pattern.to_string()))
+                        })
+                        .collect();
+                    if !hits.is_empty() {
+                        protected::escalate(&mut permission_req, &hits);
+                    }
+                    let outcome = self
+                        .request_turn_permission(session_id, permission_req)
+                        .await?;
                     if let Ok(resp) = outcome {
-                        let decision = events::handle_response_outcome(resp);
+                        let decision = if hits.is_empty() {
+                            events::handle_response_outcome(resp)
+                        } else if protected::is_allowed(&resp) {
+                            ReviewDecision::Approved
+                        } else {
+                            ReviewDecision::Abort
+                        };
+                        self.audit_patch(
+                            session_id,
+                            AuditEvent::PatchDecision,
+                            req.changes.keys().cloned().collect(),
+                            audit::decision_label(&decision),
+                        );
                         conversation
                             .submit(Op::PatchApproval {
                                 id: event.id.clone(),
@@ -246,6 +927,9 @@ impl CodexAgent {
                             .map_err(acp::Error::into_internal_error)?;
                     }
                 }
+                EventMsg::PatchApplyBegin(begin) => {
+                    patching.insert(begin.call_id, begin.changes);
+                }
                 EventMsg::PatchApplyEnd(event) => {
                     let raw_output = serde_json::json!(&event);
                     let PatchApplyEndEvent {
@@ -255,21 +939,75 @@ impl CodexAgent {
                         success,
                     } = event;
 
+                    let changes = patching.remove(&call_id).unwrap_or_default();
+                    let patched: Vec<PathBuf> = changes.keys().cloned().collect();
+                    self.forget_patched(session_id, &patched);
+                    self.invalidate_commands(session_id);
+                    if success {
+                        self.record_undo(session_id, undo::patch_snapshots(&changes).await);
+                        // Files whose old content could not be rebuilt.
+                        self.track_modified(session_id, changes.keys().map(PathBuf::as_path));
+                    }
+                    self.audit_patch(
+                        session_id,
+                        AuditEvent::Patch,
+                        changes.into_keys().collect(),
+                        if success { "applied" } else { "failed" },
+                    );
                     let update = event_handler.on_patch_apply_end(&call_id, success, raw_output);
 
-                    self.send_session_update(&args.session_id, update).await?;
+                    self.send_session_update(session_id, update).await?;
                 }
                 EventMsg::TokenCount(tc) => {
                     if let Some(info) = tc.info {
-                        self.with_session_state_mut(&args.session_id, |state| {
-                            state.token_usage = Some(info.total_token_usage.clone());
+                        let usage = info.total_token_usage;
+                        let recorded = self.with_session_state_mut(session_id, |state| {
+                            state.token_usage = Some(usage.clone());
+                            let model = state
+                                .usage_model()
+                                .map(str::to_string)
+                                .unwrap_or_else(|| self.config.model.clone());
+                            let delta = state.model_usage.record(&model, &usage);
+                            (state.model_usage.clone(), model, delta)
                         });
+                        let model_usage = match recorded {
+                            Some((model_usage, model, delta)) => {
+                                self.record_spend(&model, &delta).await;
+                                Some(model_usage)
+                            }
+                            None => None,
+                        };
+                        if let Err(err) = self
+                            .record_store
+                            .update(session_id.0.as_ref(), |record| {
+                                record.token_usage = Some(usage);
+                                if let Some(model_usage) = model_usage {
+                                    record.model_usage = model_usage;
+                                }
+                            })
+                            .await
+                        {
+                            warn!(error = %err, "failed to persist session token usage");
+                        }
                     }
                 }
                 EventMsg::PlanUpdate(UpdatePlanArgs { explanation, plan }) => {
-                    if let Some(content) = explanation {
-                        self.send_message_chunk(&args.session_id, content.into())
-                            .await?;
+                    let target = self
+                        .with_session_state_mut(session_id, |state| state.plan_explanation)
+                        .unwrap_or_default();
+                    let mut plan_meta = None;
+                    if let Some(content) = explanation.filter(|c| !c.trim().is_empty()) {
+                        match target {
+                            events::PlanExplanation::Thought => {
+                                self.send_thought_chunk(session_id, content.into()).await?;
+                            }
+                            events::PlanExplanation::Message => {
+                                self.send_message_chunk(session_id, content.into()).await?;
+                            }
+                            events::PlanExplanation::Plan => {
+                                plan_meta = Some(serde_json::json!({ "explanation": content }));
+                            }
+                        }
                     }
 
                     let entries = plan
@@ -291,10 +1029,10 @@ impl CodexAgent {
                         .collect();
 
                     self.send_session_update(
-                        &args.session_id,
+                        session_id,
                         acp::SessionUpdate::Plan(acp::Plan {
                             entries,
-                            meta: None,
+                            meta: plan_meta,
                         }),
                     )
                     .await?;
@@ -302,12 +1040,15 @@ impl CodexAgent {
                 EventMsg::TaskComplete(_) => {
                     break acp::StopReason::EndTurn;
                 }
-                EventMsg::Error(ErrorEvent { message })
-                | EventMsg::StreamError(StreamErrorEvent { message }) => {
+                EventMsg::Error(ErrorEvent { message }) => {
+                    self.send_message_chunk(session_id, format!("{message}\n\n").into())
+                        .await?;
+                    error = Some(message);
+                }
+                EventMsg::StreamError(StreamErrorEvent { message }) => {
                     let mut msg = String::from(&message);
                     msg.push_str("\n\n");
-                    self.send_message_chunk(&args.session_id, msg.into())
-                        .await?;
+                    self.send_message_chunk(session_id, msg.into()).await?;
                 }
                 EventMsg::ShutdownComplete | EventMsg::TurnAborted(_) => {
                     break acp::StopReason::Cancelled;
@@ -317,22 +1058,90 @@ impl CodexAgent {
             }
         };
 
-        if let Some(text) = reason.take_text()
-            && !text.trim().is_empty()
+        // Token-limit and refusal endings arrive as errors or a bare refusal reply.
+        let stop_reason = match stop_reason {
+            acp::StopReason::EndTurn => {
+                stop_reason::classify(error.as_deref(), output).unwrap_or(stop_reason)
+            }
+            other => other,
+        };
+        Ok(TurnOutcome {
+            stop_reason,
+            error,
+            tool_activity,
+        })
+    }
+
+    /// Seed for this turn: `PromptRequest.meta.seed`, else the session's sampling seed.
+    ///
+    /// Seeds reach the provider through the sampling proxy, so a per-turn seed
+    /// is refused for sessions whose requests do not go through it.
+    pub(super) fn turn_seed(&self, args: &acp::PromptRequest) -> Result<Option<u64>, acp::Error> {
+        let requested = match args.meta.as_ref().and_then(|m| m.get("seed")) {
+            None => None,
+            Some(value) => Some(value.as_u64().ok_or_else(|| {
+                acp::Error::invalid_params().with_data("seed must be a non-negative integer")
+            })?),
+        };
+        let (session_seed, route) = self
+            .with_session_state_mut(&args.session_id, |state| {
+                (state.sampling.seed, state.fs_session_id.clone())
+            })
+            .unwrap_or_default();
+        if let Some(seed) = requested
+            && session_seed != Some(seed)
+            && !self.is_routed(&route)
         {
-            self.send_thought_chunk(&args.session_id, text.into())
-                .await?;
+            return Err(acp::Error::invalid_params().with_data(format!(
+                "seed {seed} cannot be applied: seeds are only supported for sessions on a custom provider"
+            )));
         }
+        Ok(requested.or(session_seed))
+    }
 
-        Ok(acp::PromptResponse {
-            stop_reason,
-            meta: None,
-        })
+    /// Append a finished user turn to the session journal.
+    async fn record_turn(&self, session_id: &acp::SessionId, record: TurnRecord) {
+        let TurnRecord {
+            submit_id,
+            prompt,
+            output,
+            status,
+            error,
+            seed,
+        } = record;
+        let model = self
+            .with_session_state_mut(session_id, |state| state.current_model.clone())
+            .flatten();
+        let commands = self.take_unjournaled_commands(session_id);
+        let entry = JournalEntry {
+            submit_id,
+            finished_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            model,
+            prompt,
+            output,
+            status,
+            error,
+            seed,
+            commands,
+        };
+        if let Err(err) = self
+            .record_store
+            .append_journal(session_id.0.as_ref(), &entry)
+            .await
+        {
+            warn!(error = %err, "failed to append session journal");
+        }
     }
 
     /// Cancel an ongoing prompt operation.
     pub(super) async fn cancel(&self, args: acp::CancelNotification) -> Result<(), acp::Error> {
         info!(?args, "Received cancel request");
+        self.touch_session(&args.session_id);
+        // Release approvals the turn is waiting on; they are declined as cancelled.
+        self.with_session_state_mut(&args.session_id, |state| state.turn_cancel.cancel());
         self.get_conversation(&args.session_id)
             .await?
             .submit(Op::Interrupt)
@@ -340,26 +1149,16 @@ impl CodexAgent {
             .map_err(|e| acp::Error::from(anyhow::anyhow!("failed to send interrupt: {}", e)))?;
         Ok(())
     }
+}
 
-    /// Handle extension method calls.
-    ///
-    /// This is a placeholder for future extensions.
-    pub(super) async fn ext_method(
-        &self,
-        args: acp::ExtRequest,
-    ) -> Result<acp::ExtResponse, acp::Error> {
-        info!(method = %args.method, params = ?args.params, "Received extension method call");
-        Ok(serde_json::value::to_raw_value(&json!({"example": "response"}))?.into())
-    }
-
-    /// Handle extension notifications.
-    ///
-    /// This is a placeholder for future extensions.
-    pub(super) async fn ext_notification(
-        &self,
-        args: acp::ExtNotification,
-    ) -> Result<(), acp::Error> {
-        info!(method = %args.method, params = ?args.params, "Received extension notification call");
-        Ok(())
-    }
+/// Plain-text rendering of user input for the journal.
+fn summarize_input(items: &[UserInput]) -> String {
+    items
+        .iter()
+        .map(|item| match item {
+            UserInput::Text { text } => text.clone(),
+            _ => "[image]".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
@@ -0,0 +1,178 @@
+//! Heuristic risk classification of commands awaiting approval.
+//!
+//! Each exec approval request carries a risk level and the reasons for it in
+//! the `ToolCallUpdate` meta (`{"risk": {"level": "high", "reasons": [...]}}`),
+//! so clients can color-code approval prompts. The analysis is a best-effort
+//! look at the command words; it never blocks anything by itself.
+
+use serde::Serialize;
+
+use super::interactive;
+
+/// How dangerous a command looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+/// Risk level plus human-readable reasons.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RiskAssessment {
+    pub level: RiskLevel,
+    pub reasons: Vec<String>,
+}
+
+impl RiskAssessment {
+    fn flag(&mut self, level: RiskLevel, reason: impl Into<String>) {
+        self.level = self.level.max(level);
+        self.reasons.push(reason.into());
+    }
+}
+
+/// Split command words into the simple commands of a pipeline or list,
+/// remembering whether each one reads from a pipe.
+fn segments(words: &[String]) -> Vec<(bool, Vec<&str>)> {
+    let mut segments = vec![(false, Vec::new())];
+    for word in words {
+        let (token, separator) = match word.strip_suffix(';') {
+            Some(token) => (token, Some(";")),
+            None if matches!(word.as_str(), "|" | "||" | "&&" | ";") => ("", Some(word.as_str())),
+            None => (word.as_str(), None),
+        };
+        if !token.is_empty()
+            && let Some((_, current)) = segments.last_mut()
+        {
+            current.push(token);
+        }
+        if let Some(separator) = separator {
+            segments.push((separator == "|", Vec::new()));
+        }
+    }
+    segments.retain(|(_, words)| !words.is_empty());
+    segments
+}
+
+/// Classify `command` (argv, possibly a `bash -lc` script).
+pub fn assess(command: &[String]) -> RiskAssessment {
+    let words = interactive::command_words(command);
+    let mut risk = RiskAssessment {
+        level: RiskLevel::Low,
+        reasons: Vec::new(),
+    };
+    let mut previous_downloads = false;
+
+    for (piped, segment) in segments(&words) {
+        let mut argv = segment.as_slice();
+        if let ["sudo" | "doas", rest @ ..] = argv {
+            risk.flag(RiskLevel::High, "runs with elevated privileges (sudo)");
+            argv = rest;
+        }
+        let Some((program, args)) = argv.split_first() else {
+            continue;
+        };
+        let program = program.rsplit('/').next().unwrap_or_default();
+        let sub = args.first().copied();
+        let has = |flag: &str| args.iter().any(|a| *a == flag);
+        let short_flags = |c: char| {
+            args.iter()
+                .any(|a| a.starts_with('-') && !a.starts_with("--") && a.contains(c))
+        };
+
+        let downloads = matches!(program, "curl" | "wget");
+        if piped
+            && previous_downloads
+            && matches!(program, "sh" | "bash" | "zsh" | "python" | "python3")
+        {
+            risk.flag(
+                RiskLevel::High,
+                format!("pipes downloaded content into `{program}`"),
+            );
+        }
+        previous_downloads = downloads;
+
+        match program {
+            "rm" if short_flags('r') || short_flags('R') || has("--recursive") => {
+                let force = if short_flags('f') || has("--force") {
+                    " -f"
+                } else {
+                    ""
+                };
+                risk.flag(
+                    RiskLevel::High,
+                    format!("recursively deletes files (rm -r{force})"),
+                );
+            }
+            "rm" | "unlink" | "shred" => risk.flag(RiskLevel::Medium, "deletes files"),
+            "mkfs" | "fdisk" | "parted" => {
+                risk.flag(RiskLevel::High, "modifies disks or partitions")
+            }
+            "dd" if args.iter().any(|a| a.starts_with("of=")) => {
+                risk.flag(RiskLevel::High, "writes raw data with dd")
+            }
+            "chmod" | "chown" if short_flags('R') => risk.flag(
+                RiskLevel::High,
+                "recursively changes permissions or ownership",
+            ),
+            "chmod" | "chown" => risk.flag(RiskLevel::Medium, "changes permissions or ownership"),
+            "git" => match sub {
+                Some("push") if has("--force") || has("-f") || has("--force-with-lease") => risk
+                    .flag(
+                        RiskLevel::High,
+                        "force-pushes and may overwrite remote history",
+                    ),
+                Some("push") => risk.flag(RiskLevel::Medium, "publishes commits to a remote"),
+                Some("reset") if has("--hard") => risk.flag(
+                    RiskLevel::High,
+                    "discards uncommitted changes (git reset --hard)",
+                ),
+                Some("clean") if short_flags('f') => {
+                    risk.flag(RiskLevel::High, "deletes untracked files (git clean)")
+                }
+                Some("clone" | "fetch" | "pull") => {
+                    risk.flag(RiskLevel::Medium, "accesses the network")
+                }
+                _ => {}
+            },
+            "curl" | "wget" | "ssh" | "scp" | "rsync" | "nc" | "ncat" | "ftp" | "sftp" => risk
+                .flag(
+                    RiskLevel::Medium,
+                    format!("accesses the network ({program})"),
+                ),
+            "npm" | "pnpm" | "yarn" | "bun"
+                if matches!(sub, Some("install" | "i" | "add" | "ci")) =>
+            {
+                risk.flag(
+                    RiskLevel::Medium,
+                    "installs packages, which may run install scripts",
+                )
+            }
+            "pip" | "pip3" | "gem" | "brew" | "apt" | "apt-get" | "dnf" | "yum" | "pacman"
+                if matches!(sub, Some("install" | "-S")) =>
+            {
+                risk.flag(RiskLevel::Medium, format!("installs packages ({program})"))
+            }
+            "cargo" | "go" if matches!(sub, Some("install")) => risk.flag(
+                RiskLevel::Medium,
+                format!("installs packages ({program} install)"),
+            ),
+            "cargo" if matches!(sub, Some("add" | "publish")) => risk.flag(
+                RiskLevel::Medium,
+                "changes dependencies or publishes a crate",
+            ),
+            "shutdown" | "reboot" | "halt" | "kill" | "killall" | "pkill" => {
+                risk.flag(RiskLevel::Medium, "stops processes or the system")
+            }
+            _ => {}
+        }
+        if args
+            .iter()
+            .any(|a| a.starts_with("/dev/sd") || a.starts_with("/dev/nvme"))
+        {
+            risk.flag(RiskLevel::High, "touches a raw disk device");
+        }
+    }
+    risk
+}
@@ -0,0 +1,116 @@
+//! Sampling parameter overrides for custom (OSS) providers.
+//!
+//! Local models often need non-default `temperature` / `top_p`, and a fixed
+//! `seed` makes generations reproducible on providers that honour it. Codex has no
+//! sampling settings of its own, so the
+//! [`SamplingProxy`](super::sampling_proxy::SamplingProxy) writes the
+//! overrides into the body of every request a session sends its provider.
+//! `/sampling` changes the overrides of the current session, from its next
+//! request on, and of sessions created afterwards.
+//!
+//! Sources, highest priority first:
+//! - `NewSessionRequest.meta.sampling`, e.g. `{"sampling": {"temperature": 0.2, "topP": 0.9, "seed": 7}}`;
+//! - defaults set with `/sampling` earlier in the agent process.
+
+use agent_client_protocol::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Optional `temperature` / `top_p` / `seed` overrides.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplingOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(default, alias = "top_p", skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl SamplingOverrides {
+    /// Read overrides from `NewSessionRequest.meta.sampling`, if present.
+    pub fn from_meta(meta: Option<&Value>) -> Result<Option<Self>, Error> {
+        let Some(value) = meta.and_then(|m| m.get("sampling")) else {
+            return Ok(None);
+        };
+        let overrides: Self = serde_json::from_value(value.clone())
+            .map_err(|e| Error::invalid_params().with_data(format!("invalid sampling: {e}")))?;
+        overrides
+            .validate()
+            .map_err(|e| Error::invalid_params().with_data(e))?;
+        Ok(Some(overrides))
+    }
+
+    /// Parse `/sampling` arguments such as `temperature=0.2 top_p=0.9 seed=7` on top of `self`.
+    pub fn parse_args(mut self, args: &str) -> Result<Self, String> {
+        fn parse<T: std::str::FromStr>(value: &str) -> Result<Option<T>, String> {
+            if value.eq_ignore_ascii_case("default") {
+                return Ok(None);
+            }
+            value
+                .parse::<T>()
+                .map(Some)
+                .map_err(|_| format!("`{value}` is not a valid number"))
+        }
+
+        for token in args.split_whitespace() {
+            let (key, value) = token
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got `{token}`"))?;
+            match key.to_ascii_lowercase().as_str() {
+                "temperature" | "temp" => self.temperature = parse(value)?,
+                "top_p" | "topp" => self.top_p = parse(value)?,
+                "seed" => self.seed = parse(value)?,
+                _ => return Err(format!("unknown sampling parameter `{key}`")),
+            }
+        }
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Check that values are within the ranges accepted by OpenAI-compatible servers.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(t) = self.temperature
+            && !(0.0..=2.0).contains(&t)
+        {
+            return Err(format!("temperature must be between 0 and 2, got {t}"));
+        }
+        if let Some(p) = self.top_p
+            && !(p > 0.0 && p <= 1.0)
+        {
+            return Err(format!("top_p must be in (0, 1], got {p}"));
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.temperature.is_none() && self.top_p.is_none() && self.seed.is_none()
+    }
+
+    /// Write the overrides into a provider request body.
+    pub fn apply_to(&self, body: &mut Map<String, Value>) {
+        if let Some(t) = self.temperature {
+            body.insert("temperature".to_string(), t.into());
+        }
+        if let Some(p) = self.top_p {
+            body.insert("top_p".to_string(), p.into());
+        }
+        if let Some(seed) = self.seed {
+            body.insert("seed".to_string(), seed.into());
+        }
+    }
+
+    /// Human-readable summary for `/sampling` and `/status`.
+    pub fn describe(&self) -> String {
+        fn fmt<T: ToString>(v: Option<T>) -> String {
+            v.map_or_else(|| "default".to_string(), |v| v.to_string())
+        }
+        format!(
+            "temperature={} top_p={} seed={}",
+            fmt(self.temperature),
+            fmt(self.top_p),
+            fmt(self.seed)
+        )
+    }
+}
@@ -0,0 +1,467 @@
+//! Localhost proxy that puts sampling overrides into provider requests.
+//!
+//! Codex builds the request bodies for a provider itself and has no sampling
+//! settings, so a session on a custom provider talks to its provider through
+//! this proxy: the session's `base_url` becomes
+//! `http://127.0.0.1:<port>/<route>`, and each request is forwarded to the
+//! real base URL with the session's current `temperature` / `top_p` / `seed`
+//! written into its JSON body. The overrides are looked up per request, so a
+//! change made with `/sampling` reaches the running conversation with its next
+//! request. Headers, including the provider's credentials, pass through
+//! unchanged; responses are streamed back as they arrive.
+
+use std::{cell::RefCell, collections::HashMap, io, net::SocketAddr, rc::Rc};
+
+use anyhow::{Context, anyhow, bail};
+use reqwest::{
+    Method, StatusCode,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task,
+};
+use tracing::{error, warn};
+
+use agent_client_protocol::SessionId;
+use codex_core::ModelProviderInfo;
+
+use super::{core::CodexAgent, sampling::SamplingOverrides};
+
+/// Largest request head accepted from Codex.
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+
+/// Largest request body accepted from Codex; larger ones are answered with
+/// `413 Payload Too Large` before anything is allocated for them.
+pub const MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Longest chunk-size line of a chunked body.
+const MAX_CHUNK_LINE_BYTES: u64 = 1024;
+
+/// Headers that describe one hop of the connection rather than the request.
+const HOP_HEADERS: &[&str] = &[
+    "connection",
+    "content-length",
+    "host",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+struct Route {
+    upstream: String,
+    sampling: SamplingOverrides,
+}
+
+type Routes = RefCell<HashMap<String, Route>>;
+
+pub struct SamplingProxy {
+    address: SocketAddr,
+    routes: Rc<Routes>,
+}
+
+impl SamplingProxy {
+    /// Listen on a free localhost port. Must run inside a `LocalSet`.
+    pub fn start() -> io::Result<Rc<Self>> {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        let listener = TcpListener::from_std(listener)?;
+        let routes = Rc::new(Routes::default());
+        let client = reqwest::Client::new();
+        let accept_routes = routes.clone();
+        task::spawn_local(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        let routes = accept_routes.clone();
+                        let client = client.clone();
+                        task::spawn_local(async move {
+                            if let Err(err) = serve(stream, &routes, &client).await {
+                                warn!(error = %err, "sampling proxy request failed");
+                            }
+                        });
+                    }
+                    Err(err) => {
+                        error!(error = %err, "sampling proxy listener failed");
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(Rc::new(Self { address, routes }))
+    }
+
+    /// Send requests for `route` to `upstream` with `sampling`, returning the
+    /// base URL to give Codex instead of `upstream`.
+    pub fn add_route(&self, route: &str, upstream: &str, sampling: SamplingOverrides) -> String {
+        self.routes.borrow_mut().insert(
+            route.to_string(),
+            Route {
+                upstream: upstream.trim_end_matches('/').to_string(),
+                sampling,
+            },
+        );
+        format!("http://{}/{route}", self.address)
+    }
+
+    /// Replace the overrides of `route`; returns whether the route exists.
+    pub fn set_sampling(&self, route: &str, sampling: SamplingOverrides) -> bool {
+        match self.routes.borrow_mut().get_mut(route) {
+            Some(entry) => {
+                entry.sampling = sampling;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn has_route(&self, route: &str) -> bool {
+        self.routes.borrow().contains_key(route)
+    }
+
+    pub fn remove_route(&self, route: &str) {
+        self.routes.borrow_mut().remove(route);
+    }
+}
+
+impl CodexAgent {
+    /// Point `provider` at the sampling proxy, which forwards the requests of
+    /// FS session `route` with `sampling`. Without a base URL or a proxy the
+    /// provider is left alone and sampling overrides are not applied.
+    pub(super) fn route_provider(
+        &self,
+        route: &str,
+        sampling: &SamplingOverrides,
+        provider: &mut ModelProviderInfo,
+    ) {
+        let Some(upstream) = provider.base_url.clone() else {
+            return;
+        };
+        let mut proxy = self.sampling_proxy.borrow_mut();
+        if proxy.is_none() {
+            match SamplingProxy::start() {
+                Ok(started) => *proxy = Some(started),
+                Err(err) => {
+                    warn!(error = %err, "failed to start the sampling proxy; sampling is not applied");
+                    return;
+                }
+            }
+        }
+        if let Some(proxy) = proxy.as_ref() {
+            provider.base_url = Some(proxy.add_route(route, &upstream, *sampling));
+        }
+    }
+
+    /// Apply `sampling` to the running conversation of FS session `route`;
+    /// returns whether its requests go through the proxy.
+    pub(super) fn update_route(&self, route: &str, sampling: SamplingOverrides) -> bool {
+        self.sampling_proxy
+            .borrow()
+            .as_ref()
+            .is_some_and(|proxy| proxy.set_sampling(route, sampling))
+    }
+
+    /// Whether the requests of FS session `route` go through the proxy.
+    pub(super) fn is_routed(&self, route: &str) -> bool {
+        self.sampling_proxy
+            .borrow()
+            .as_ref()
+            .is_some_and(|proxy| proxy.has_route(route))
+    }
+
+    /// Send the session's requests with `seed` until [`Self::restore_sampling`];
+    /// returns whether that differs from the session's own seed.
+    pub(super) fn apply_turn_seed(&self, session_id: &SessionId, seed: Option<u64>) -> bool {
+        let Some((route, sampling)) = self.with_session_state_mut(session_id, |state| {
+            (state.fs_session_id.clone(), state.sampling)
+        }) else {
+            return false;
+        };
+        sampling.seed != seed && self.update_route(&route, SamplingOverrides { seed, ..sampling })
+    }
+
+    /// Send the session's requests with its own sampling overrides again.
+    pub(super) fn restore_sampling(&self, session_id: &SessionId) {
+        if let Some((route, sampling)) = self.with_session_state_mut(session_id, |state| {
+            (state.fs_session_id.clone(), state.sampling)
+        }) {
+            self.update_route(&route, sampling);
+        }
+    }
+
+    pub(super) fn remove_route(&self, route: &str) {
+        if let Some(proxy) = self.sampling_proxy.borrow().as_ref() {
+            proxy.remove_route(route);
+        }
+    }
+}
+
+/// A request body over [`MAX_BODY_BYTES`].
+#[derive(Debug)]
+struct BodyTooLarge;
+
+impl std::fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request body over {MAX_BODY_BYTES} bytes")
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
+/// A request as read from Codex.
+struct Request {
+    method: String,
+    target: String,
+    headers: Vec<(String, Vec<u8>)>,
+    body: Vec<u8>,
+}
+
+/// Answer one request, then close the connection.
+async fn serve(stream: TcpStream, routes: &Routes, client: &reqwest::Client) -> anyhow::Result<()> {
+    let mut stream = BufReader::new(stream);
+    let request = match read_request(&mut stream).await {
+        Ok(request) => request,
+        Err(err) if err.is::<BodyTooLarge>() => {
+            write_error(stream.get_mut(), "413 Payload Too Large", &err).await?;
+            return Err(err);
+        }
+        Err(err) => return Err(err),
+    };
+    let forwarded = forward(request, routes, client).await;
+    let stream = stream.get_mut();
+    match forwarded {
+        Ok(response) => write_response(stream, response).await,
+        Err(err) => {
+            write_error(stream, "502 Bad Gateway", &err).await?;
+            Err(err)
+        }
+    }
+}
+
+/// Answer with `status` and `err` as a plain-text body.
+async fn write_error(
+    stream: &mut TcpStream,
+    status: &str,
+    err: &anyhow::Error,
+) -> anyhow::Result<()> {
+    let message = err.to_string();
+    let head = format!(
+        "HTTP/1.1 {status}\r\ncontent-type: text/plain\r\n\
+         content-length: {}\r\nconnection: close\r\n\r\n",
+        message.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(message.as_bytes()).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+async fn read_request(stream: &mut BufReader<TcpStream>) -> anyhow::Result<Request> {
+    let mut head_bytes = 0;
+    let mut line = Vec::new();
+    read_head_line(stream, &mut line, &mut head_bytes).await?;
+    let request_line = String::from_utf8_lossy(&line);
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        bail!("malformed request line {request_line:?}");
+    };
+    let (method, target) = (method.to_string(), target.to_string());
+
+    // Header values are kept as bytes and forwarded unchanged.
+    let mut headers = Vec::new();
+    loop {
+        read_head_line(stream, &mut line, &mut head_bytes).await?;
+        let header = line.trim_ascii();
+        if header.is_empty() {
+            break;
+        }
+        let colon = header
+            .iter()
+            .position(|&b| b == b':')
+            .ok_or_else(|| anyhow!("malformed header {:?}", String::from_utf8_lossy(header)))?;
+        let name = String::from_utf8_lossy(header[..colon].trim_ascii()).to_ascii_lowercase();
+        headers.push((name, header[colon + 1..].trim_ascii().to_vec()));
+    }
+
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| String::from_utf8_lossy(value))
+    };
+    let mut body = Vec::new();
+    if header("transfer-encoding").is_some_and(|v| v.eq_ignore_ascii_case("chunked")) {
+        loop {
+            read_chunk_line(stream, &mut line).await?;
+            let size = String::from_utf8_lossy(&line);
+            let size = size.trim().split(';').next().unwrap_or_default();
+            let size = usize::from_str_radix(size, 16).context("malformed chunk size")?;
+            if size == 0 {
+                // Skip trailers up to the final empty line; they count as head.
+                loop {
+                    read_head_line(stream, &mut line, &mut head_bytes).await?;
+                    if line.trim_ascii().is_empty() {
+                        break;
+                    }
+                }
+                break;
+            }
+            let start = body.len();
+            if size > MAX_BODY_BYTES - start {
+                return Err(BodyTooLarge.into());
+            }
+            body.resize(start + size, 0);
+            stream.read_exact(&mut body[start..]).await?;
+            read_chunk_line(stream, &mut line).await?;
+        }
+    } else if let Some(length) = header("content-length") {
+        let length: usize = length.trim().parse().context("malformed content-length")?;
+        if length > MAX_BODY_BYTES {
+            return Err(BodyTooLarge.into());
+        }
+        body.resize(length, 0);
+        stream.read_exact(&mut body).await?;
+    }
+
+    Ok(Request {
+        method,
+        target,
+        headers,
+        body,
+    })
+}
+
+/// Read one line of the request head into `line`.
+async fn read_head_line(
+    stream: &mut BufReader<TcpStream>,
+    line: &mut Vec<u8>,
+    head_bytes: &mut usize,
+) -> anyhow::Result<()> {
+    let limit = (MAX_HEAD_BYTES - *head_bytes) as u64;
+    if !read_line_within(stream, line, limit).await? {
+        bail!("request head over {MAX_HEAD_BYTES} bytes");
+    }
+    *head_bytes += line.len();
+    Ok(())
+}
+
+/// Read a chunk-size line, or the line break after a chunk, into `line`.
+async fn read_chunk_line(
+    stream: &mut BufReader<TcpStream>,
+    line: &mut Vec<u8>,
+) -> anyhow::Result<()> {
+    if !read_line_within(stream, line, MAX_CHUNK_LINE_BYTES).await? {
+        bail!("chunk line over {MAX_CHUNK_LINE_BYTES} bytes");
+    }
+    Ok(())
+}
+
+/// Read one line of at most `limit` bytes into `line`; returns `false` when
+/// the line is longer.
+async fn read_line_within(
+    stream: &mut BufReader<TcpStream>,
+    line: &mut Vec<u8>,
+    limit: u64,
+) -> anyhow::Result<bool> {
+    line.clear();
+    let read = (&mut *stream).take(limit).read_until(b'\n', line).await?;
+    if read == 0 && limit > 0 {
+        bail!("connection closed before the request ended");
+    }
+    Ok(line.ends_with(b"\n"))
+}
+
+async fn forward(
+    request: Request,
+    routes: &Routes,
+    client: &reqwest::Client,
+) -> anyhow::Result<reqwest::Response> {
+    let target = request.target.trim_start_matches('/');
+    let (route, rest) = match target.find(['/', '?']) {
+        Some(split) => target.split_at(split),
+        None => (target, ""),
+    };
+    let (upstream, sampling) = routes
+        .borrow()
+        .get(route)
+        .map(|entry| (entry.upstream.clone(), entry.sampling))
+        .ok_or_else(|| anyhow!("unknown sampling proxy route"))?;
+
+    let mut headers = HeaderMap::new();
+    for (name, value) in &request.headers {
+        if HOP_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        headers.append(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_bytes(value)?,
+        );
+    }
+    let body = with_sampling(request.body, &sampling);
+    client
+        .request(
+            Method::from_bytes(request.method.as_bytes())?,
+            format!("{upstream}{rest}"),
+        )
+        .headers(headers)
+        .body(body)
+        .send()
+        .await
+        .context("provider request failed")
+}
+
+/// `body` with the overrides written into it, if it is a JSON object.
+pub fn with_sampling(body: Vec<u8>, sampling: &SamplingOverrides) -> Vec<u8> {
+    if sampling.is_empty() {
+        return body;
+    }
+    let Ok(serde_json::Value::Object(mut object)) = serde_json::from_slice(&body) else {
+        return body;
+    };
+    sampling.apply_to(&mut object);
+    serde_json::to_vec(&object).unwrap_or(body)
+}
+
+async fn write_response(
+    stream: &mut TcpStream,
+    mut response: reqwest::Response,
+) -> anyhow::Result<()> {
+    let status = response.status();
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or("")
+    );
+    for (name, value) in response.headers() {
+        if HOP_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        if let Ok(value) = value.to_str() {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+    }
+    let chunked = status != StatusCode::NO_CONTENT && status != StatusCode::NOT_MODIFIED;
+    if chunked {
+        head.push_str("transfer-encoding: chunked\r\n");
+    }
+    head.push_str("connection: close\r\n\r\n");
+    stream.write_all(head.as_bytes()).await?;
+    if chunked {
+        while let Some(chunk) = response.chunk().await? {
+            stream
+                .write_all(format!("{:x}\r\n", chunk.len()).as_bytes())
+                .await?;
+            stream.write_all(&chunk).await?;
+            stream.write_all(b"\r\n").await?;
+            stream.flush().await?;
+        }
+        stream.write_all(b"0\r\n\r\n").await?;
+    }
+    stream.flush().await?;
+    Ok(())
+}
@@ -0,0 +1,329 @@
+//! Strict checks of outgoing messages (`strict-schema` feature).
+//!
+//! A development aid: every session update and permission request is checked
+//! against what the ACP schema requires right before it is sent, and each
+//! violation is logged as a warning with the session, the JSON path (the
+//! update kind in brackets) and the offending message. Checked are that the
+//! message survives a serde round trip, that every `_meta` is an object,
+//! that required ids, titles and names are present and non-empty, that
+//! content blocks and tool call content have the fields their `type`
+//! requires, and that file paths are absolute. Messages are sent either way.
+
+use std::{fmt, path::Path};
+
+use agent_client_protocol::{RequestPermissionRequest, SessionNotification};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use tracing::warn;
+
+/// One way a message breaks the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// JSON path of the offending field, e.g. `update[tool_call].title`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+#[derive(Default)]
+struct Checker {
+    violations: Vec<Violation>,
+}
+
+impl Checker {
+    fn report(&mut self, path: &str, message: impl Into<String>) {
+        self.violations.push(Violation {
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn round_trip<T: Serialize + DeserializeOwned>(&mut self, value: &Value) {
+        let again = serde_json::from_value::<T>(value.clone())
+            .map_err(|err| err.to_string())
+            .and_then(|parsed| serde_json::to_value(parsed).map_err(|err| err.to_string()));
+        match again {
+            Ok(again) if &again == value => {}
+            Ok(_) => self.report("", "changes when deserialized and serialized again"),
+            Err(err) => self.report("", format!("does not deserialize: {err}")),
+        }
+    }
+
+    /// Every `_meta` below `value` must be an object.
+    fn meta(&mut self, value: &Value, path: &str) {
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    let child_path = join(path, key);
+                    if key == "_meta" && !child.is_object() {
+                        self.report(&child_path, "must be an object");
+                    }
+                    self.meta(child, &child_path);
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.meta(item, &format!("{path}[{index}]"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn string(&mut self, value: &Value, key: &str, path: &str) -> Option<String> {
+        let field = join(path, key);
+        match value.get(key) {
+            Some(Value::String(text)) if text.is_empty() => {
+                self.report(&field, "must not be empty");
+                None
+            }
+            Some(Value::String(text)) => Some(text.clone()),
+            Some(_) => {
+                self.report(&field, "must be a string");
+                None
+            }
+            None => {
+                self.report(&field, "is required");
+                None
+            }
+        }
+    }
+
+    fn array<'a>(&mut self, value: &'a Value, key: &str, path: &str) -> &'a [Value] {
+        match value.get(key) {
+            Some(Value::Array(items)) => items,
+            Some(_) => {
+                self.report(&join(path, key), "must be an array");
+                &[]
+            }
+            None => {
+                self.report(&join(path, key), "is required");
+                &[]
+            }
+        }
+    }
+
+    fn absolute_path(&mut self, value: &Value, key: &str, path: &str) {
+        if let Some(file) = self.string(value, key, path)
+            && !Path::new(&file).is_absolute()
+        {
+            self.report(
+                &join(path, key),
+                format!("`{file}` must be an absolute path"),
+            );
+        }
+    }
+
+    fn update(&mut self, update: &Value) {
+        let Some(kind) = update.get("sessionUpdate").and_then(Value::as_str) else {
+            self.report("update.sessionUpdate", "is required");
+            return;
+        };
+        let path = format!("update[{kind}]");
+        match kind {
+            "user_message_chunk" | "agent_message_chunk" | "agent_thought_chunk" => {
+                match update.get("content") {
+                    Some(content) => self.content_block(content, &join(&path, "content")),
+                    None => self.report(&join(&path, "content"), "is required"),
+                }
+            }
+            "tool_call" => {
+                self.string(update, "toolCallId", &path);
+                self.string(update, "title", &path);
+                self.tool_fields(update, &path);
+            }
+            "tool_call_update" => {
+                self.string(update, "toolCallId", &path);
+                self.tool_fields(update, &path);
+            }
+            "plan" => {
+                for (index, entry) in self.array(update, "entries", &path).iter().enumerate() {
+                    let entry_path = format!("{path}.entries[{index}]");
+                    self.string(entry, "content", &entry_path);
+                    self.string(entry, "priority", &entry_path);
+                    self.string(entry, "status", &entry_path);
+                }
+            }
+            "available_commands_update" => {
+                let commands = self.array(update, "availableCommands", &path);
+                for (index, command) in commands.iter().enumerate() {
+                    let command_path = format!("{path}.availableCommands[{index}]");
+                    if let Some(name) = self.string(command, "name", &command_path)
+                        && name.starts_with('/')
+                    {
+                        self.report(&join(&command_path, "name"), "must not start with `/`");
+                    }
+                    self.string(command, "description", &command_path);
+                }
+            }
+            "current_mode_update" => {
+                self.string(update, "currentModeId", &path);
+            }
+            other => self.report("update.sessionUpdate", format!("unknown kind `{other}`")),
+        }
+    }
+
+    /// `content` and `locations` of a tool call or tool call update.
+    fn tool_fields(&mut self, call: &Value, path: &str) {
+        if let Some(Value::Array(content)) = call.get("content") {
+            for (index, item) in content.iter().enumerate() {
+                self.tool_content(item, &format!("{path}.content[{index}]"));
+            }
+        }
+        if let Some(Value::Array(locations)) = call.get("locations") {
+            for (index, location) in locations.iter().enumerate() {
+                self.absolute_path(location, "path", &format!("{path}.locations[{index}]"));
+            }
+        }
+    }
+
+    fn tool_content(&mut self, item: &Value, path: &str) {
+        match item.get("type").and_then(Value::as_str) {
+            Some("content") => match item.get("content") {
+                Some(block) => self.content_block(block, &join(path, "content")),
+                None => self.report(&join(path, "content"), "is required"),
+            },
+            Some("diff") => {
+                self.absolute_path(item, "path", path);
+                if !item.get("newText").is_some_and(Value::is_string) {
+                    self.report(&join(path, "newText"), "must be a string");
+                }
+            }
+            Some("terminal") => {
+                self.string(item, "terminalId", path);
+            }
+            Some(other) => self.report(&join(path, "type"), format!("unknown type `{other}`")),
+            None => self.report(&join(path, "type"), "is required"),
+        }
+    }
+
+    fn content_block(&mut self, block: &Value, path: &str) {
+        match block.get("type").and_then(Value::as_str) {
+            Some("text") => {
+                if !block.get("text").is_some_and(Value::is_string) {
+                    self.report(&join(path, "text"), "must be a string");
+                }
+            }
+            Some("image") | Some("audio") => {
+                self.string(block, "data", path);
+                self.string(block, "mimeType", path);
+            }
+            Some("resource_link") => {
+                self.string(block, "name", path);
+                self.string(block, "uri", path);
+            }
+            Some("resource") => match block.get("resource") {
+                Some(resource) => {
+                    self.string(resource, "uri", &join(path, "resource"));
+                }
+                None => self.report(&join(path, "resource"), "is required"),
+            },
+            Some(other) => self.report(&join(path, "type"), format!("unknown type `{other}`")),
+            None => self.report(&join(path, "type"), "is required"),
+        }
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Schema violations of a session notification.
+pub fn check_notification(notification: &SessionNotification) -> Vec<Violation> {
+    let mut checker = Checker::default();
+    let value = match serde_json::to_value(notification) {
+        Ok(value) => value,
+        Err(err) => {
+            checker.report("", format!("does not serialize: {err}"));
+            return checker.violations;
+        }
+    };
+    checker.round_trip::<SessionNotification>(&value);
+    checker.meta(&value, "");
+    checker.string(&value, "sessionId", "");
+    match value.get("update") {
+        Some(update) => checker.update(update),
+        None => checker.report("update", "is required"),
+    }
+    checker.violations
+}
+
+/// Schema violations of a permission request.
+pub fn check_permission_request(request: &RequestPermissionRequest) -> Vec<Violation> {
+    let mut checker = Checker::default();
+    let value = match serde_json::to_value(request) {
+        Ok(value) => value,
+        Err(err) => {
+            checker.report("", format!("does not serialize: {err}"));
+            return checker.violations;
+        }
+    };
+    checker.round_trip::<RequestPermissionRequest>(&value);
+    checker.meta(&value, "");
+    checker.string(&value, "sessionId", "");
+    match value.get("toolCall") {
+        Some(call) => {
+            checker.string(call, "toolCallId", "toolCall");
+            checker.tool_fields(call, "toolCall");
+        }
+        None => checker.report("toolCall", "is required"),
+    }
+    let options = checker.array(&value, "options", "");
+    if options.is_empty() && value.get("options").is_some_and(Value::is_array) {
+        checker.report("options", "must offer at least one option");
+    }
+    for (index, option) in options.iter().enumerate() {
+        let path = format!("options[{index}]");
+        checker.string(option, "optionId", &path);
+        checker.string(option, "name", &path);
+        checker.string(option, "kind", &path);
+    }
+    checker.violations
+}
+
+/// Log the violations of an outgoing session notification.
+pub fn report_notification(notification: &SessionNotification) {
+    let violations = check_notification(notification);
+    if violations.is_empty() {
+        return;
+    }
+    let message = serde_json::to_string(notification).unwrap_or_default();
+    for violation in violations {
+        warn!(
+            session_id = %notification.session_id.0,
+            %violation,
+            %message,
+            "outgoing session update violates the ACP schema"
+        );
+    }
+}
+
+/// Log the violations of an outgoing permission request.
+pub fn report_permission_request(request: &RequestPermissionRequest) {
+    let violations = check_permission_request(request);
+    if violations.is_empty() {
+        return;
+    }
+    let message = serde_json::to_string(request).unwrap_or_default();
+    for violation in violations {
+        warn!(
+            session_id = %request.session_id.0,
+            %violation,
+            %message,
+            "outgoing permission request violates the ACP schema"
+        );
+    }
+}
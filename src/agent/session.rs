@@ -1,14 +1,14 @@
 use std::{
-    cell::RefCell,
     collections::{HashMap, HashSet},
+    path::PathBuf,
     rc::Rc,
     sync::{Arc, LazyLock},
+    time::Instant,
 };
 
 use agent_client_protocol::{
-    Error, ModelId, ModelInfo, ReadTextFileRequest, ReadTextFileResponse, RequestPermissionRequest,
-    RequestPermissionResponse, SessionId, SessionMode, SessionModeId, SessionModeState,
-    WriteTextFileRequest, WriteTextFileResponse,
+    ModelId, ModelInfo, SessionId, SessionMode, SessionModeId, SessionModeState,
+    SessionNotification,
 };
 use codex_common::approval_presets::{ApprovalPreset, builtin_approval_presets};
 use codex_core::{
@@ -16,12 +16,50 @@ use codex_core::{
     config::Config as CodexConfig,
     config_profile::ConfigProfile,
     protocol::{AskForApproval, SandboxPolicy, TokenUsage},
-    protocol_config_types::ReasoningEffort,
+    protocol_config_types::{ReasoningEffort, ReasoningSummary, Verbosity},
 };
-use tokio::sync::oneshot::Sender;
+use codex_protocol::user_input::UserInput;
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    backfill::Backlog,
+    context::ContextItem,
+    diagnostics::{self, Diagnostic, FileDiagnostics},
+    events, language,
+    mcp_commands::McpServerCommand,
+    modified_files::ModifiedFiles,
+    sampling::SamplingOverrides,
+    session_store::SessionStore,
+    shell_env::ShellParity,
+    steering::{self, Steering},
+    turn_changes::TurnChanges,
+    usage::UsageTracker,
+};
+use crate::fs::tools::{
+    audit::{AuditEntry, AuditLog},
+    command_history::CommandRecord,
+    undo::{FileSnapshot, UndoHistory},
+};
+
+/// Id of the synthetic dry-run session mode.
+pub const DRY_RUN_MODE_ID: &str = "dry-run";
 
 /// All available approval presets used to derive ACP session modes.
-static APPROVAL_PRESETS: LazyLock<Vec<ApprovalPreset>> = LazyLock::new(builtin_approval_presets);
+///
+/// Codex's built-in presets are followed by the synthetic dry-run mode: a
+/// read-only sandbox where every untrusted command and patch comes to the
+/// agent for approval, which reports it as simulated and declines it.
+static APPROVAL_PRESETS: LazyLock<Vec<ApprovalPreset>> = LazyLock::new(|| {
+    let mut presets = builtin_approval_presets();
+    presets.push(ApprovalPreset {
+        id: DRY_RUN_MODE_ID,
+        label: "Dry run",
+        description: "Simulate edits and commands: patches are shown as diffs but not applied, and commands are skipped.",
+        approval: AskForApproval::UnlessTrusted,
+        sandbox: SandboxPolicy::new_read_only_policy(),
+    });
+    presets
+});
 
 /// Context needed for applying turn context overrides.
 ///
@@ -34,28 +72,6 @@ pub(super) struct SessionContext {
     pub effort: Option<ReasoningEffort>,
 }
 
-/// Operations that require client interaction.
-///
-/// These operations are sent to the client handler to request permissions,
-/// read files, or write files based on client capabilities.
-pub enum ClientOp {
-    RequestPermission {
-        session_id: SessionId,
-        request: RequestPermissionRequest,
-        response_tx: Sender<Result<RequestPermissionResponse, Error>>,
-    },
-    ReadTextFile {
-        session_id: SessionId,
-        request: ReadTextFileRequest,
-        response_tx: Sender<Result<ReadTextFileResponse, Error>>,
-    },
-    WriteTextFile {
-        session_id: SessionId,
-        request: WriteTextFileRequest,
-        response_tx: Sender<Result<WriteTextFileResponse, Error>>,
-    },
-}
-
 /// Compute the ACP `SessionModeState` (current + available) based on the provided Codex config.
 ///
 /// Returns `None` if no matching preset exists for the config's approval and sandbox policies.
@@ -76,7 +92,9 @@ pub fn current_mode_id_for_config(config: &CodexConfig) -> Option<SessionModeId>
     APPROVAL_PRESETS
         .iter()
         .find(|preset| {
-            preset.approval == config.approval_policy && preset.sandbox == config.sandbox_policy
+            preset.id != DRY_RUN_MODE_ID
+                && preset.approval == config.approval_policy
+                && preset.sandbox == config.sandbox_policy
         })
         .map(|preset| SessionModeId(preset.id.into()))
 }
@@ -104,6 +122,40 @@ pub fn is_read_only_mode(mode_id: &SessionModeId) -> bool {
     mode_id.0.as_ref() == "read-only"
 }
 
+pub fn is_dry_run_mode(mode_id: &SessionModeId) -> bool {
+    mode_id.0.as_ref() == DRY_RUN_MODE_ID
+}
+
+/// Parse a reasoning summary setting (`auto`, `concise`, `detailed`, `none`).
+pub fn parse_reasoning_summary(value: &str) -> Option<ReasoningSummary> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "auto" => Some(ReasoningSummary::Auto),
+        "concise" => Some(ReasoningSummary::Concise),
+        "detailed" => Some(ReasoningSummary::Detailed),
+        "none" | "off" => Some(ReasoningSummary::None),
+        _ => None,
+    }
+}
+
+/// Parse a final-answer verbosity setting (`low`, `medium`, `high`).
+pub fn parse_verbosity(value: &str) -> Option<Verbosity> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "low" => Some(Verbosity::Low),
+        "medium" => Some(Verbosity::Medium),
+        "high" => Some(Verbosity::High),
+        _ => None,
+    }
+}
+
+/// Lowercase label for a verbosity setting, as accepted by [`parse_verbosity`].
+pub fn verbosity_label(verbosity: Verbosity) -> &'static str {
+    match verbosity {
+        Verbosity::Low => "low",
+        Verbosity::Medium => "medium",
+        Verbosity::High => "high",
+    }
+}
+
 /// Check if a provider is a custom (non-builtin) provider.
 ///
 /// Builtin providers are: "openai"
@@ -266,6 +318,21 @@ pub fn parse_and_validate_model(
 ///   from the ACP session id (which is the key in the `sessions` map).
 /// - `conversation` is lazily loaded on demand; `None` until first use.
 /// - Reasoning text is aggregated across streaming events.
+/// - `attachments` are workspace paths pinned via `/attach` and embedded in every turn.
+/// - `pending_context` holds items pushed via `codex/addContext`, consumed by the next turn.
+/// - `model_usage` attributes token usage to each model used in the session.
+/// - `turn_model` is the model answering the running turn when a `!model` directive or a
+///   fallback replaced `current_model`; its usage is credited to it.
+/// - `diagnostics` maps file paths to the latest diagnostics published by the client.
+/// - `sampling` holds the overrides the sampling proxy writes into this session's provider requests.
+/// - `fallback_models` is the ordered `provider@model` failover chain for failed turns.
+/// - `last_prompt` is the most recent typed prompt (without injected context), used by `/retry`.
+/// - `verbosity` is the `/verbosity` preference, sent as an instruction with every turn.
+/// - `shell` records how exec commands get their environment (login shell, env file).
+/// - `max_session_tokens` is the cumulative token cap; prompts fail once usage reaches it.
+/// - `budget_override` lets the session keep prompting past the shared spend budget.
+/// - `last_activity` is the time of the last user interaction, used by the idle read-only fallback.
+/// - `mcp_commands` are the `/mcp:<server>` commands of the session's MCP servers.
 #[derive(Clone)]
 pub struct SessionState {
     pub fs_session_id: String,
@@ -273,9 +340,63 @@ pub struct SessionState {
     pub current_approval: AskForApproval,
     pub current_sandbox: SandboxPolicy,
     pub current_mode: SessionModeId,
+    /// Mode a `!ro`/`!mode` directive sets for the running turn only.
+    pub turn_mode: Option<SessionModeId>,
     pub current_model: Option<String>,
     pub current_effort: Option<ReasoningEffort>,
+    pub current_summary: ReasoningSummary,
+    pub verbosity: Option<Verbosity>,
     pub token_usage: Option<TokenUsage>,
+    pub model_usage: UsageTracker,
+    pub turn_model: Option<String>,
+    pub attachments: Vec<String>,
+    pub pending_context: Vec<ContextItem>,
+    pub diagnostics: HashMap<String, Vec<Diagnostic>>,
+    pub sampling: SamplingOverrides,
+    pub fallback_models: Vec<String>,
+    pub last_prompt: Option<Vec<UserInput>>,
+    pub shell: ShellParity,
+    pub max_session_tokens: Option<u64>,
+    pub budget_override: bool,
+    pub last_activity: Instant,
+    pub mcp_commands: Vec<McpServerCommand>,
+    /// Forward reasoning deltas as they arrive instead of one chunk per section.
+    pub stream_reasoning: bool,
+    /// Where plan explanations are sent.
+    pub plan_explanation: events::PlanExplanation,
+    /// Commands approved for the session that run in client terminals.
+    pub terminal_approved: HashSet<Vec<String>>,
+    /// Commands executed in the session, oldest first.
+    pub commands: Vec<CommandRecord>,
+    /// How many of the latest `commands` are not journaled yet.
+    pub unjournaled_commands: usize,
+    /// Cancelled by `session/cancel` to release the running turn's pending approvals.
+    pub turn_cancel: CancellationToken,
+    /// Held while a prompt turn runs, so turns of one session do not overlap.
+    pub turn_lock: Rc<tokio::sync::Mutex<()>>,
+    /// Language the model is asked to answer in.
+    pub response_language: Option<String>,
+    /// Response language written into the session instructions at creation.
+    pub instructed_language: Option<String>,
+    /// What a prompt sent during a running turn does.
+    pub steering: Steering,
+    /// Set when the running turn is being replaced by a steering prompt.
+    pub steered: bool,
+    /// Updates the client did not receive, replayed on the next `session/load`.
+    pub undelivered: Backlog,
+    /// End of the last output cut off by the token limit, resumed by `/continue`.
+    pub truncated_output: Option<String>,
+    /// File changes `/undo` can restore.
+    pub undo: UndoHistory,
+    /// Files changed in the session, for `/diff`.
+    pub modified: ModifiedFiles,
+    /// Files changed and commands run in the running turn.
+    pub turn_changes: TurnChanges,
+    /// Workspace roots besides the agent's working directory.
+    pub workspace_roots: Vec<PathBuf>,
+    /// Capture of the running turn, when transcripts are recorded.
+    #[cfg(feature = "transcripts")]
+    pub transcript: Option<super::transcript::Transcript>,
 }
 
 impl SessionState {
@@ -293,12 +414,58 @@ impl SessionState {
             current_approval: config.approval_policy,
             current_sandbox: config.sandbox_policy.clone(),
             current_mode,
+            turn_mode: None,
             current_model: Some(model_ctx.to_model_id()),
             current_effort: model_ctx.effort,
+            current_summary: config.model_reasoning_summary,
+            verbosity: config.model_verbosity,
             token_usage: None,
+            model_usage: UsageTracker::default(),
+            turn_model: None,
+            attachments: Vec::new(),
+            pending_context: Vec::new(),
+            diagnostics: HashMap::new(),
+            sampling: SamplingOverrides::default(),
+            fallback_models: Vec::new(),
+            last_prompt: None,
+            shell: ShellParity::default(),
+            max_session_tokens: None,
+            budget_override: false,
+            last_activity: Instant::now(),
+            mcp_commands: Vec::new(),
+            stream_reasoning: events::default_stream_reasoning(),
+            plan_explanation: events::default_plan_explanation(),
+            terminal_approved: HashSet::new(),
+            commands: Vec::new(),
+            unjournaled_commands: 0,
+            turn_cancel: CancellationToken::new(),
+            turn_lock: Rc::default(),
+            response_language: language::default_response_language(),
+            instructed_language: language::default_response_language(),
+            steering: steering::default_steering(),
+            steered: false,
+            undelivered: Backlog::default(),
+            truncated_output: None,
+            undo: UndoHistory::default(),
+            modified: ModifiedFiles::default(),
+            turn_changes: TurnChanges::default(),
+            workspace_roots: Vec::new(),
+            #[cfg(feature = "transcripts")]
+            transcript: None,
         }
     }
 
+    /// The mode writes are checked against: a directive's for the running
+    /// turn, otherwise the session's.
+    pub fn effective_mode(&self) -> &SessionModeId {
+        self.turn_mode.as_ref().unwrap_or(&self.current_mode)
+    }
+
+    /// The model the running turn's usage is credited to.
+    pub fn usage_model(&self) -> Option<&str> {
+        self.turn_model.as_deref().or(self.current_model.as_deref())
+    }
+
     /// Update the model context for this session.
     pub fn set_model(&mut self, model_ctx: &ModelContext) {
         self.current_model = Some(model_ctx.to_model_id());
@@ -309,30 +476,27 @@ impl SessionState {
 /// Read-only helper for looking up session-mode related info.
 ///
 /// This type intentionally only exposes query methods to keep mutation
-/// centralized inside the agent. The inner store is shared via `Rc<RefCell<...>>`
-/// because the agent runs on the current-thread runtime.
+/// centralized inside the agent. It shares the agent's [`SessionStore`],
+/// whose accessors never let a borrow escape.
 #[derive(Clone)]
 pub struct SessionModeLookup {
     // crate-visible so the agent can construct directly without extra glue
-    pub(crate) inner: Rc<RefCell<HashMap<String, SessionState>>>,
+    pub(crate) inner: SessionStore,
+    pub(crate) audit: AuditLog,
+    /// The agent's working directory, which relative paths are resolved against.
+    pub(crate) cwd: PathBuf,
 }
 
 impl SessionModeLookup {
-    /// Create a new lookup wrapper from an existing shared session store.
-    /// Return the current mode for the given ACP session id.
+    /// Return the current mode for the given ACP session id, including a
+    /// mode a directive set for the running turn.
     ///
     /// This will also resolve when the provided id matches an FS session id
     /// held inside a `SessionState`.
     pub fn current_mode(&self, session_id: &SessionId) -> Option<SessionModeId> {
-        let sessions = self.inner.borrow();
-        if let Some(state) = sessions.get(session_id.0.as_ref()) {
-            return Some(state.current_mode.clone());
-        }
-
-        sessions
-            .values()
-            .find(|state| state.fs_session_id == session_id.0.as_ref())
-            .map(|state| state.current_mode.clone())
+        let acp_id = self.inner.resolve(session_id.0.as_ref())?;
+        self.inner
+            .with(&acp_id, |state| state.effective_mode().clone())
     }
 
     /// Whether the resolved session is currently read-only.
@@ -342,20 +506,73 @@ impl SessionModeLookup {
             .unwrap_or(false)
     }
 
+    /// Whether the resolved session is in dry-run mode.
+    pub fn is_dry_run(&self, session_id: &SessionId) -> bool {
+        self.current_mode(session_id)
+            .map(|mode| is_dry_run_mode(&mode))
+            .unwrap_or(false)
+    }
+
     /// If the provided `session_id` refers to an FS session id, return the
     /// corresponding ACP session id. Otherwise, return the original ACP id.
     pub fn resolve_acp_session_id(&self, session_id: &SessionId) -> Option<SessionId> {
-        let sessions = self.inner.borrow();
-        if sessions.contains_key(session_id.0.as_ref()) {
-            return Some(session_id.clone());
-        }
+        self.inner
+            .resolve(session_id.0.as_ref())
+            .map(|id| SessionId(id.into()))
+    }
 
-        sessions.iter().find_map(|(key, state)| {
-            if state.fs_session_id == session_id.0.as_ref() {
-                Some(SessionId(key.clone().into()))
-            } else {
-                None
-            }
+    /// Keep a notification the client did not receive for replay on the
+    /// next `session/load`. Updates for unknown sessions are dropped.
+    pub fn buffer_undelivered(&self, notification: SessionNotification) {
+        let Some(acp_id) = self.resolve_acp_session_id(&notification.session_id) else {
+            return;
+        };
+        self.inner.update(acp_id.0.as_ref(), move |state| {
+            state.undelivered.push(notification);
+        });
+    }
+
+    /// Return the diagnostics stored for the resolved session, optionally for one path.
+    pub fn diagnostics(
+        &self,
+        session_id: &SessionId,
+        path: Option<&str>,
+    ) -> Option<Vec<FileDiagnostics>> {
+        let acp_id = self.resolve_acp_session_id(session_id)?;
+        self.inner.with(acp_id.0.as_ref(), |state| {
+            diagnostics::collect_diagnostics(
+                &state.diagnostics,
+                path,
+                &self.cwd,
+                &state.workspace_roots,
+            )
         })
     }
+
+    /// Return the commands executed in the resolved session, oldest first.
+    pub fn command_history(&self, session_id: &SessionId) -> Option<Vec<CommandRecord>> {
+        let acp_id = self.resolve_acp_session_id(session_id)?;
+        self.inner
+            .with(acp_id.0.as_ref(), |state| state.commands.clone())
+    }
+
+    /// Record `entry` in the audit log under the resolved ACP session id.
+    pub fn record_audit(&self, session_id: &SessionId, mut entry: AuditEntry) {
+        if let Some(acp_id) = self.resolve_acp_session_id(session_id) {
+            entry.session_id = acp_id.0.to_string();
+        }
+        self.audit.record(entry);
+    }
+
+    /// Remember a change of the resolved session for `/undo` and `/diff`.
+    pub fn record_undo(&self, session_id: &SessionId, files: Vec<FileSnapshot>) {
+        let Some(acp_id) = self.resolve_acp_session_id(session_id) else {
+            return;
+        };
+        self.inner.update(acp_id.0.as_ref(), move |state| {
+            state.modified.record(&files);
+            state.turn_changes.record_files(&files);
+            state.undo.record(files);
+        });
+    }
 }
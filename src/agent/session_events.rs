@@ -0,0 +1,301 @@
+//! Typed session events for embedders.
+//!
+//! [`CodexAgent::with_config`] hands session updates out as raw
+//! `(SessionNotification, oneshot)` pairs that must be matched on ACP types
+//! and acknowledged one by one. [`CodexAgent::with_event_stream`] instead
+//! returns [`SessionEvents`], a `Stream` of [`SessionEvent`]s that
+//! acknowledges each update as it is yielded and also reports the end of
+//! every prompt turn. Updates without a typed variant (available commands,
+//! echoed user messages) are acknowledged and skipped.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use agent_client_protocol::{
+    ContentBlock, PlanEntryStatus, SessionNotification, SessionUpdate, StopReason, ToolCallContent,
+    ToolCallStatus, ToolKind,
+};
+use codex_core::config::Config as CodexConfig;
+use codex_core::config_profile::ConfigProfile;
+use futures::Stream;
+use tokio::sync::{
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
+    oneshot::Sender,
+};
+
+use super::core::CodexAgent;
+use crate::fs::{ClientOp, FsBridge};
+
+/// One thing that happened in a session.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum SessionEvent {
+    /// Text of the agent's answer, in order.
+    MessageChunk { session_id: String, text: String },
+    /// Text of the model's reasoning summary.
+    Thought { session_id: String, text: String },
+    /// A tool call started or changed.
+    ToolCall {
+        session_id: String,
+        call: ToolCallEvent,
+    },
+    /// The full current plan; replaces any earlier one.
+    Plan {
+        session_id: String,
+        steps: Vec<PlanStep>,
+    },
+    /// The session switched to another mode, e.g. `read-only`.
+    ModeChanged { session_id: String, mode_id: String },
+    /// A prompt turn finished; `error` is set when it failed.
+    TurnEnded {
+        session_id: String,
+        stop_reason: Option<StopReason>,
+        error: Option<String>,
+    },
+}
+
+/// A tool call as first reported or as updated later. Fields an update does
+/// not change are `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolCallEvent {
+    pub id: String,
+    /// `true` for the first report of the call.
+    pub started: bool,
+    pub title: Option<String>,
+    /// `read`, `edit`, `execute`, ... as in ACP.
+    pub kind: Option<String>,
+    pub status: Option<ToolStatus>,
+    /// Text output reported so far in this event.
+    pub output: Vec<String>,
+    /// Files the call shows a diff for.
+    pub edited: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanStep {
+    pub text: String,
+    pub status: StepState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepState {
+    Pending,
+    InProgress,
+    Completed,
+}
+
+impl SessionEvent {
+    /// The typed event for a notification, if it has one.
+    pub fn from_notification(notification: &SessionNotification) -> Option<Self> {
+        let session_id = notification.session_id.0.to_string();
+        Some(match &notification.update {
+            SessionUpdate::AgentMessageChunk(chunk) => Self::MessageChunk {
+                session_id,
+                text: content_text(&chunk.content)?,
+            },
+            SessionUpdate::AgentThoughtChunk(chunk) => Self::Thought {
+                session_id,
+                text: content_text(&chunk.content)?,
+            },
+            SessionUpdate::ToolCall(call) => Self::ToolCall {
+                session_id,
+                call: ToolCallEvent {
+                    id: call.id.0.to_string(),
+                    started: true,
+                    title: Some(call.title.clone()),
+                    kind: Some(kind_name(&call.kind)),
+                    status: Some(tool_status(&call.status)),
+                    output: tool_output(&call.content),
+                    edited: edited_paths(&call.content),
+                },
+            },
+            SessionUpdate::ToolCallUpdate(update) => Self::ToolCall {
+                session_id,
+                call: ToolCallEvent {
+                    id: update.id.0.to_string(),
+                    started: false,
+                    title: update.fields.title.clone(),
+                    kind: update.fields.kind.as_ref().map(kind_name),
+                    status: update.fields.status.as_ref().map(tool_status),
+                    output: update
+                        .fields
+                        .content
+                        .as_deref()
+                        .map(tool_output)
+                        .unwrap_or_default(),
+                    edited: update
+                        .fields
+                        .content
+                        .as_deref()
+                        .map(edited_paths)
+                        .unwrap_or_default(),
+                },
+            },
+            SessionUpdate::Plan(plan) => Self::Plan {
+                session_id,
+                steps: plan
+                    .entries
+                    .iter()
+                    .map(|entry| PlanStep {
+                        text: entry.content.clone(),
+                        status: match entry.status {
+                            PlanEntryStatus::Pending => StepState::Pending,
+                            PlanEntryStatus::InProgress => StepState::InProgress,
+                            PlanEntryStatus::Completed => StepState::Completed,
+                        },
+                    })
+                    .collect(),
+            },
+            SessionUpdate::CurrentModeUpdate(update) => Self::ModeChanged {
+                session_id,
+                mode_id: update.current_mode_id.0.to_string(),
+            },
+            _ => return None,
+        })
+    }
+
+    pub fn session_id(&self) -> &str {
+        match self {
+            Self::MessageChunk { session_id, .. }
+            | Self::Thought { session_id, .. }
+            | Self::ToolCall { session_id, .. }
+            | Self::Plan { session_id, .. }
+            | Self::ModeChanged { session_id, .. }
+            | Self::TurnEnded { session_id, .. } => session_id,
+        }
+    }
+}
+
+fn content_text(content: &ContentBlock) -> Option<String> {
+    match content {
+        ContentBlock::Text(text) => Some(text.text.clone()),
+        ContentBlock::ResourceLink(link) => Some(link.uri.clone()),
+        _ => None,
+    }
+}
+
+fn kind_name(kind: &ToolKind) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_else(|| "other".to_string())
+}
+
+fn tool_status(status: &ToolCallStatus) -> ToolStatus {
+    match status {
+        ToolCallStatus::Pending => ToolStatus::Pending,
+        ToolCallStatus::InProgress => ToolStatus::Running,
+        ToolCallStatus::Completed => ToolStatus::Completed,
+        ToolCallStatus::Failed => ToolStatus::Failed,
+    }
+}
+
+fn tool_output(content: &[ToolCallContent]) -> Vec<String> {
+    content
+        .iter()
+        .filter_map(|item| match item {
+            ToolCallContent::Content { content } => content_text(content),
+            _ => None,
+        })
+        .collect()
+}
+
+fn edited_paths(content: &[ToolCallContent]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for item in content {
+        if let ToolCallContent::Diff { diff } = item
+            && !paths.contains(&diff.path)
+        {
+            paths.push(diff.path.clone());
+        }
+    }
+    paths
+}
+
+/// Stream of [`SessionEvent`]s for an agent built with
+/// [`CodexAgent::with_event_stream`]. Ends when the agent is dropped.
+pub struct SessionEvents {
+    updates: UnboundedReceiver<(SessionNotification, Sender<()>)>,
+    turns: UnboundedReceiver<SessionEvent>,
+}
+
+impl SessionEvents {
+    pub(crate) fn new(
+        updates: UnboundedReceiver<(SessionNotification, Sender<()>)>,
+        turns: UnboundedReceiver<SessionEvent>,
+    ) -> Self {
+        Self { updates, turns }
+    }
+}
+
+impl Stream for SessionEvents {
+    type Item = SessionEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<SessionEvent>> {
+        let this = self.get_mut();
+        // Updates first: a turn only ends once all of its updates were taken.
+        loop {
+            match this.updates.poll_recv(cx) {
+                Poll::Ready(Some((notification, ack))) => {
+                    let _ = ack.send(());
+                    if let Some(event) = SessionEvent::from_notification(&notification) {
+                        return Poll::Ready(Some(event));
+                    }
+                }
+                Poll::Ready(None) => return this.turns.poll_recv(cx),
+                Poll::Pending => break,
+            }
+        }
+        match this.turns.poll_recv(cx) {
+            // Updates are still open; keep waiting for them.
+            Poll::Ready(None) => Poll::Pending,
+            other => other,
+        }
+    }
+}
+
+impl CodexAgent {
+    /// Like [`CodexAgent::with_config`], but session updates arrive as a
+    /// typed [`SessionEvents`] stream that needs no acknowledgements.
+    pub fn with_event_stream(
+        client_tx: UnboundedSender<ClientOp>,
+        config: CodexConfig,
+        profiles: HashMap<String, ConfigProfile>,
+        fs_bridge: Option<Arc<FsBridge>>,
+    ) -> (Self, SessionEvents) {
+        let (update_tx, updates) = mpsc::unbounded_channel();
+        let (turn_tx, turns) = mpsc::unbounded_channel();
+        let agent = Self::with_config(update_tx, client_tx, config, profiles, fs_bridge);
+        *agent.turn_events.borrow_mut() = Some(turn_tx);
+        (agent, SessionEvents::new(updates, turns))
+    }
+
+    /// Report the end of a prompt turn to the event stream, if there is one.
+    pub(super) fn emit_turn_ended(
+        &self,
+        session_id: &str,
+        stop_reason: Option<StopReason>,
+        error: Option<String>,
+    ) {
+        if let Some(tx) = self.turn_events.borrow().as_ref() {
+            let _ = tx.send(SessionEvent::TurnEnded {
+                session_id: session_id.to_string(),
+                stop_reason,
+                error,
+            });
+        }
+    }
+}
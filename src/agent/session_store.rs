@@ -0,0 +1,180 @@
+//! The in-memory table of open sessions.
+//!
+//! Session state is reached from the agent's request handlers, the idle
+//! watchers and the [`SessionModeLookup`](super::SessionModeLookup) the client
+//! connection consults, all on the current thread. With a bare
+//! `Rc<RefCell<HashMap>>`, a borrow held on one of those paths while another
+//! reaches the table panics the whole agent, so [`SessionStore`] never hands
+//! out a borrow: callers pass closures that run while the table is borrowed
+//! and get owned results back.
+//!
+//! Inserts and [`SessionStore::update`]s made while the table is in use,
+//! say from inside such a closure or while it is being searched, are queued
+//! and applied as soon as the outer access ends. Reads nest inside other
+//! reads, but neither reads nor [`SessionStore::with_mut`] can wait for a
+//! mutable access to end. Reaching the table that way is a bug: debug builds
+//! panic on it, and release builds log it and fail with a "session state is
+//! in use" error from [`SessionStore::try_with`] and
+//! [`SessionStore::try_with_mut`], so it is never mistaken for a missing
+//! session.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use agent_client_protocol::Error;
+use tracing::warn;
+
+use super::session::SessionState;
+
+type Mutation = Box<dyn FnOnce(&mut HashMap<String, SessionState>)>;
+
+/// Open sessions keyed by ACP session id, shared by all handles.
+#[derive(Clone, Default)]
+pub struct SessionStore {
+    inner: Rc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    sessions: RefCell<HashMap<String, SessionState>>,
+    /// Mutations made while the table was borrowed, oldest first.
+    queued: RefCell<Vec<Mutation>>,
+}
+
+impl SessionStore {
+    /// Number of open sessions.
+    pub fn count(&self) -> usize {
+        self.read("count", |sessions| sessions.len()).unwrap_or(0)
+    }
+
+    pub fn contains(&self, session_id: &str) -> bool {
+        self.read("contains", |sessions| sessions.contains_key(session_id))
+            .unwrap_or(false)
+    }
+
+    /// Add or replace a session.
+    pub fn insert(&self, session_id: String, state: SessionState) {
+        self.mutate(move |sessions| {
+            sessions.insert(session_id, state);
+        });
+    }
+
+    /// Read a session's state; `None` if the session is not found or the
+    /// table is in use (see [`Self::try_with`]).
+    pub fn with<R>(&self, session_id: &str, f: impl FnOnce(&SessionState) -> R) -> Option<R> {
+        self.try_with(session_id, f).ok()
+    }
+
+    /// Read a session's state, telling a missing session from a table that
+    /// is in use.
+    pub fn try_with<R>(
+        &self,
+        session_id: &str,
+        f: impl FnOnce(&SessionState) -> R,
+    ) -> Result<R, Error> {
+        self.read("with", |sessions| sessions.get(session_id).map(f))?
+            .ok_or_else(not_found)
+    }
+
+    /// Change a session's state and return a result; `None` if the session
+    /// is not found or the table is in use (see [`Self::try_with_mut`]).
+    pub fn with_mut<R>(
+        &self,
+        session_id: &str,
+        f: impl FnOnce(&mut SessionState) -> R,
+    ) -> Option<R> {
+        self.try_with_mut(session_id, f).ok()
+    }
+
+    /// Change a session's state and return a result, telling a missing
+    /// session from a table that is in use.
+    pub fn try_with_mut<R>(
+        &self,
+        session_id: &str,
+        f: impl FnOnce(&mut SessionState) -> R,
+    ) -> Result<R, Error> {
+        let result = match self.inner.sessions.try_borrow_mut() {
+            Ok(mut sessions) => sessions.get_mut(session_id).map(f).ok_or_else(not_found),
+            Err(_) => Err(reentered("with_mut")),
+        };
+        self.flush();
+        result
+    }
+
+    /// Change a session's state, later if the table is in use. Changes of
+    /// unknown sessions are dropped.
+    pub fn update(&self, session_id: &str, f: impl FnOnce(&mut SessionState) + 'static) {
+        let session_id = session_id.to_string();
+        self.mutate(move |sessions| {
+            if let Some(state) = sessions.get_mut(&session_id) {
+                f(state);
+            }
+        });
+    }
+
+    /// The ACP id of the session with ACP or FS session id `id`.
+    pub fn resolve(&self, id: &str) -> Option<String> {
+        self.read("resolve", |sessions| {
+            if sessions.contains_key(id) {
+                return Some(id.to_string());
+            }
+            sessions
+                .iter()
+                .find(|(_, state)| state.fs_session_id == id)
+                .map(|(key, _)| key.clone())
+        })
+        .ok()
+        .flatten()
+    }
+
+    fn read<R>(
+        &self,
+        operation: &'static str,
+        f: impl FnOnce(&HashMap<String, SessionState>) -> R,
+    ) -> Result<R, Error> {
+        let result = match self.inner.sessions.try_borrow() {
+            Ok(sessions) => Ok(f(&sessions)),
+            Err(_) => Err(reentered(operation)),
+        };
+        self.flush();
+        result
+    }
+
+    fn mutate(&self, f: impl FnOnce(&mut HashMap<String, SessionState>) + 'static) {
+        match self.inner.sessions.try_borrow_mut() {
+            Ok(mut sessions) => f(&mut sessions),
+            Err(_) => {
+                self.inner.queued.borrow_mut().push(Box::new(f));
+                return;
+            }
+        }
+        self.flush();
+    }
+
+    /// Apply queued mutations once the table is free.
+    fn flush(&self) {
+        loop {
+            let Ok(mut sessions) = self.inner.sessions.try_borrow_mut() else {
+                return;
+            };
+            let queued = std::mem::take(&mut *self.inner.queued.borrow_mut());
+            if queued.is_empty() {
+                return;
+            }
+            for mutation in queued {
+                mutation(&mut sessions);
+            }
+        }
+    }
+}
+
+fn not_found() -> Error {
+    Error::invalid_params().with_data("session not found")
+}
+
+fn reentered(operation: &str) -> Error {
+    if cfg!(debug_assertions) {
+        panic!("session store reentered by {operation} during a mutable access");
+    }
+    warn!(operation, "session store reentered while in use");
+    Error::internal_error().with_data("session state is in use; retry the request")
+}
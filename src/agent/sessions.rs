@@ -1,14 +1,25 @@
 use agent_client_protocol as acp;
-use codex_core::{NewConversation, protocol::Op};
+use codex_core::{
+    NewConversation, find_conversation_path_by_id_str, protocol::Op,
+    protocol_config_types::ReasoningSummary,
+};
 use tokio::{sync::oneshot, task};
 use tracing::{info, warn};
 use uuid::Uuid;
 
 use super::{
-    commands,
+    acp_config::AcpConfig,
+    backfill, budget, command_history, commands,
     core::CodexAgent,
+    custom_prompts, events, failover, history, idle, language, mcp_commands, mcp_servers,
+    sampling::SamplingOverrides,
     session::{self, is_custom_provider},
+    shell_env::ShellParity,
+    steering,
+    store::SessionSettings,
+    workspace_roots,
 };
+use crate::fs::tools::command_cache;
 
 impl CodexAgent {
     /// Create a new session with the given configuration.
@@ -28,7 +39,57 @@ impl CodexAgent {
             .map(|m| m.current_mode_id.clone())
             .unwrap_or(acp::SessionModeId("auto".into()));
 
-        let session_config = self.build_session_config(&fs_session_id, args.mcp_servers)?;
+        // Sampling overrides only make sense for custom (OSS) providers.
+        let sampling = if is_custom_provider(&self.config.model_provider_id) {
+            SamplingOverrides::from_meta(args.meta.as_ref())?
+                .unwrap_or_else(|| *self.sampling_defaults.borrow())
+        } else {
+            SamplingOverrides::default()
+        };
+
+        let fallback_models = failover::fallback_models_from_meta(args.meta.as_ref())?
+            .unwrap_or_else(failover::default_fallback_models);
+
+        let acp_config = AcpConfig::load(&self.config.codex_home).await;
+        let max_session_tokens = budget::max_session_tokens_from_meta(args.meta.as_ref())?
+            .or_else(|| budget::default_max_session_tokens(&acp_config));
+
+        let idle_timeout = idle::idle_timeout_from_meta(args.meta.as_ref())?
+            .unwrap_or_else(idle::default_idle_timeout);
+
+        let mut shell = ShellParity::from_meta(args.meta.as_ref())?
+            .unwrap_or_else(|| ShellParity::defaults(&acp_config));
+
+        let stream_reasoning = events::stream_reasoning_from_meta(args.meta.as_ref())?;
+        let plan_explanation = events::plan_explanation_from_meta(args.meta.as_ref())?;
+        let steering = steering::steering_from_meta(args.meta.as_ref())?;
+        let response_language = language::response_language_from_meta(args.meta.as_ref())?
+            .unwrap_or_else(language::default_response_language);
+        let workspace_roots = workspace_roots::workspace_roots_from_meta(args.meta.as_ref())?;
+        let command_cache = command_cache::command_cache_from_meta(args.meta.as_ref())?
+            .unwrap_or_else(command_cache::default_command_cache);
+
+        let saved_mcp_servers: Vec<serde_json::Value> = args
+            .mcp_servers
+            .iter()
+            .filter_map(mcp_servers::persistable)
+            .collect();
+
+        // Workspace servers are read again on load, so they are not saved.
+        let (workspace_servers, workspace_notice) =
+            self.workspace_mcp_servers(&args.mcp_servers).await;
+        let mut mcp_servers = args.mcp_servers;
+        mcp_servers.extend(workspace_servers);
+
+        let (session_config, renames) = self.build_session_config(
+            &fs_session_id,
+            mcp_servers,
+            &sampling,
+            &mut shell,
+            &self.extra_instructions(response_language.as_deref()).await,
+            command_cache,
+        )?;
+        let mcp_commands = mcp_commands::server_commands(session_config.mcp_servers.keys());
 
         let new_conv = self
             .conversation_manager
@@ -43,47 +104,73 @@ impl CodexAgent {
             }) => (conversation, conversation_id),
             Err(e) => {
                 warn!(error = %e, "Failed to create Codex conversation");
+                self.forget_fs_session(&fs_session_id);
                 return Err(acp::Error::into_internal_error(e));
             }
         };
 
         let acp_session_id = conversation_id.to_string();
+        let settings = SessionSettings {
+            sampling,
+            fallback_models: fallback_models.clone(),
+            max_session_tokens,
+            response_language: response_language.clone(),
+        };
+        if let Err(err) = self
+            .record_store
+            .update(&acp_session_id, |record| {
+                record.mcp_servers = saved_mcp_servers;
+                record.settings = Some(settings);
+            })
+            .await
+        {
+            warn!(error = %err, "failed to save the session record");
+        }
 
         // Initialize session state from config
-        self.sessions.borrow_mut().insert(
-            acp_session_id.clone(),
-            session::SessionState::new(
-                fs_session_id.clone(),
-                Some(conversation.clone()),
-                &self.config,
-                current_mode.clone(),
-            ),
+        let mut state = session::SessionState::new(
+            fs_session_id.clone(),
+            Some(conversation.clone()),
+            &self.config,
+            current_mode.clone(),
         );
+        state.sampling = sampling;
+        state.fallback_models = fallback_models;
+        state.mcp_commands = mcp_commands;
+        state.shell = shell.clone();
+        state.max_session_tokens = max_session_tokens;
+        if let Some(stream_reasoning) = stream_reasoning {
+            state.stream_reasoning = stream_reasoning;
+        }
+        if let Some(plan_explanation) = plan_explanation {
+            state.plan_explanation = plan_explanation;
+        }
+        if let Some(steering) = steering {
+            state.steering = steering;
+        }
+        state.response_language = response_language.clone();
+        state.instructed_language = response_language;
+        self.sessions.insert(acp_session_id.clone(), state);
+        if let Some(roots) = workspace_roots {
+            self.set_workspace_roots(&acp::SessionId(acp_session_id.clone().into()), roots)?;
+        }
+        if let Some(timeout) = idle_timeout {
+            self.spawn_idle_watch(acp::SessionId(acp_session_id.clone().into()), timeout);
+        }
 
-        // Advertise available slash commands to the client right after
-        // the session is created. Send it asynchronously to avoid racing
-        // with the NewSessionResponse delivery.
-        {
-            let session_id = acp_session_id.clone();
-            let available_commands = commands::AVAILABLE_COMMANDS.to_vec();
-            let tx_updates = self.session_update_tx.clone();
-            task::spawn_local(async move {
-                let (tx, rx) = oneshot::channel();
-                let _ = tx_updates.send((
-                    acp::SessionNotification {
-                        session_id: acp::SessionId(session_id.clone().into()),
-                        update: acp::SessionUpdate::AvailableCommandsUpdate(
-                            acp::AvailableCommandsUpdate {
-                                available_commands,
-                                meta: None,
-                            },
-                        ),
-                        meta: None,
-                    },
-                    tx,
-                ));
-                let _ = rx.await;
-            });
+        self.advertise_commands(&acp_session_id);
+        self.spawn_provider_probe(&acp_session_id);
+        for rename in &renames {
+            self.send_notice(&acp_session_id, rename.notice());
+        }
+        if let Some(notice) = workspace_notice {
+            self.send_notice(&acp_session_id, notice);
+        }
+        if let Some(notice) = self.duplicate_instance_notice().await {
+            self.send_notice(&acp_session_id, notice);
+        }
+        if let Some(notice) = self.take_update_notice().await {
+            self.send_notice(&acp_session_id, notice);
         }
 
         // Build models response only for custom providers
@@ -91,8 +178,8 @@ impl CodexAgent {
             Some(acp::SessionModelState {
                 current_model_id: session::current_model_id_from_config(&self.config),
                 available_models: session::available_models_from_profiles(
-                    &self.config,
-                    &self.profiles,
+                    &self.model_config(),
+                    &self.profiles.borrow(),
                 ),
                 meta: None,
             })
@@ -104,23 +191,150 @@ impl CodexAgent {
             session_id: acp::SessionId(acp_session_id.clone().into()),
             modes,
             models,
-            meta: None,
+            meta: Some(serde_json::json!({ "shell": shell })),
         })
     }
 
+    /// Probe the provider in the background so an offline start is reported
+    /// without delaying the session response; the session works offline for
+    /// slash commands and FS tools.
+    fn spawn_provider_probe(&self, session_id: &str) {
+        let agent = self.clone();
+        let session_id = session_id.to_string();
+        task::spawn_local(async move {
+            let Err(err) = agent.check_provider_reachable(None).await else {
+                return;
+            };
+            warn!(error = ?err, "model provider unreachable at session start");
+            let guidance = err
+                .data
+                .as_ref()
+                .and_then(|d| d.get("guidance"))
+                .and_then(|g| g.as_str())
+                .unwrap_or("The model provider is unreachable.")
+                .to_string();
+            agent.send_notice(&session_id, format!("⚠️ {guidance}\n\n"));
+        });
+    }
+
+    /// Send an agent message once the session response has gone out.
+    fn send_notice(&self, session_id: &str, text: String) {
+        let session_id = session_id.to_string();
+        let tx_updates = self.session_update_tx.clone();
+        task::spawn_local(async move {
+            let (tx, rx) = oneshot::channel();
+            let _ = tx_updates.send((
+                acp::SessionNotification {
+                    session_id: acp::SessionId(session_id.into()),
+                    update: acp::SessionUpdate::AgentMessageChunk(acp::ContentChunk {
+                        content: text.into(),
+                        meta: None,
+                    }),
+                    meta: None,
+                },
+                tx,
+            ));
+            let _ = rx.await;
+        });
+    }
+
+    /// Advertise available slash commands to the client right after a
+    /// session is created or loaded. Sent asynchronously to avoid racing with
+    /// delivery of the session response.
+    fn advertise_commands(&self, session_id: &str) {
+        let session_id = session_id.to_string();
+        let prompts_dir = self.config.codex_home.join("prompts");
+        let mcp_commands = self.mcp_commands(&session_id);
+        let tx_updates = self.session_update_tx.clone();
+        task::spawn_local(async move {
+            let prompts = custom_prompts::discover(&prompts_dir).await;
+            let available_commands = commands::session_commands(&prompts, &mcp_commands);
+            let (tx, rx) = oneshot::channel();
+            let _ = tx_updates.send((
+                acp::SessionNotification {
+                    session_id: acp::SessionId(session_id.into()),
+                    update: acp::SessionUpdate::AvailableCommandsUpdate(
+                        acp::AvailableCommandsUpdate {
+                            available_commands,
+                            meta: None,
+                        },
+                    ),
+                    meta: None,
+                },
+                tx,
+            ));
+            let _ = rx.await;
+        });
+    }
+
+    /// Release the bridge token and provider route of an FS session that
+    /// never got a session.
+    fn forget_fs_session(&self, fs_session_id: &str) {
+        if let Some(bridge) = &self.fs_bridge {
+            bridge.remove_session(fs_session_id);
+        }
+        self.remove_route(fs_session_id);
+    }
+
     /// Load an existing session and return its current state.
+    ///
+    /// Sessions unknown to this process are resumed from their Codex rollout
+    /// file under `codex_home`, so clients can reattach after an agent restart.
     pub(super) async fn load_session(
         &self,
         args: acp::LoadSessionRequest,
     ) -> Result<acp::LoadSessionResponse, acp::Error> {
         info!(?args, "Received load session request");
-        let (current_mode, _current_model) = {
-            let sessions = self.sessions.borrow();
-            let state = sessions
-                .get(args.session_id.0.as_ref())
-                .ok_or_else(|| acp::Error::invalid_params().with_data("session not found"))?;
-            (state.current_mode.clone(), state.current_model.clone())
-        };
+
+        let workspace_roots = workspace_roots::workspace_roots_from_meta(args.meta.as_ref())?;
+        let loaded = self.sessions.contains(args.session_id.0.as_ref());
+        let record = self.record_store.load(args.session_id.0.as_ref()).await;
+        if !loaded {
+            // Restart the session's MCP servers; ones passed now replace saved ones.
+            let saved = record
+                .as_ref()
+                .map(|record| record.mcp_servers.clone())
+                .unwrap_or_default();
+            let servers = mcp_servers::merge(saved, args.mcp_servers.clone());
+            let settings = record.as_ref().and_then(|record| record.settings.clone());
+            self.resume_session(&args.session_id, servers, settings)
+                .await?;
+        }
+
+        // Restore cumulative usage persisted by an earlier agent process.
+        if let Some(record) = record {
+            self.with_session_state_mut(&args.session_id, |state| {
+                if state.token_usage.is_none() {
+                    state.token_usage = record.token_usage;
+                    state.model_usage = record.model_usage;
+                }
+            });
+        }
+
+        // Restore the command history from the journal of an earlier agent process.
+        if !loaded {
+            let journal = self
+                .record_store
+                .load_journal(args.session_id.0.as_ref())
+                .await;
+            let commands = command_history::history_from_journal(journal);
+            self.with_session_state_mut(&args.session_id, |state| {
+                state.commands = commands;
+            });
+        }
+
+        if let Some(roots) = workspace_roots {
+            self.set_workspace_roots(&args.session_id, roots)?;
+        }
+
+        self.replay_history(&args.session_id).await;
+        self.replay_backfill(&args.session_id).await;
+
+        let (current_mode, _current_model) = self
+            .sessions
+            .try_with(args.session_id.0.as_ref(), |state| {
+                (state.current_mode.clone(), state.current_model.clone())
+            })?;
 
         // Use stored model or derive from config
         let current_model_id = if let Some(ref stored_model) = _current_model {
@@ -136,8 +350,8 @@ impl CodexAgent {
             Some(acp::SessionModelState {
                 current_model_id,
                 available_models: session::available_models_from_profiles(
-                    &self.config,
-                    &self.profiles,
+                    &self.model_config(),
+                    &self.profiles.borrow(),
                 ),
                 meta: None,
             })
@@ -156,6 +370,194 @@ impl CodexAgent {
         })
     }
 
+    /// Stream the stored transcript of a session back to the client.
+    ///
+    /// A missing or unreadable rollout only loses the replay, not the session.
+    async fn replay_history(&self, session_id: &acp::SessionId) {
+        let rollout_path =
+            match find_conversation_path_by_id_str(&self.config.codex_home, session_id.0.as_ref())
+                .await
+            {
+                Ok(Some(path)) => path,
+                Ok(None) => return,
+                Err(err) => {
+                    warn!(error = %err, "failed to locate rollout for history replay");
+                    return;
+                }
+            };
+        let items = match history::read_rollout_history(&rollout_path).await {
+            Ok(items) => items,
+            Err(err) => {
+                warn!(path = %rollout_path.display(), error = %err, "failed to read rollout history");
+                return;
+            }
+        };
+        info!(items = items.len(), "Replaying session history");
+        let delivery = self.thought_delivery.get();
+        for update in history::history_updates(items)
+            .into_iter()
+            .filter_map(|update| delivery.apply(update))
+        {
+            if self.send_session_update(session_id, update).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Resend the updates the client missed while it was away, marked as
+    /// backfill, with a note first when some had to be dropped.
+    async fn replay_backfill(&self, session_id: &acp::SessionId) {
+        let Some((missed, dropped)) =
+            self.with_session_state_mut(session_id, |state| state.undelivered.take())
+        else {
+            return;
+        };
+        if missed.is_empty() && dropped == 0 {
+            return;
+        }
+        info!(
+            updates = missed.len(),
+            dropped, "Replaying undelivered session updates"
+        );
+        let lost = (dropped > 0).then(|| acp::SessionNotification {
+            session_id: session_id.clone(),
+            update: acp::SessionUpdate::AgentMessageChunk(acp::ContentChunk {
+                content: format!(
+                    "⚠️ {dropped} earlier updates were lost while the client was disconnected.\n\n"
+                )
+                .into(),
+                meta: None,
+            }),
+            meta: None,
+        });
+        for notification in lost.into_iter().chain(missed) {
+            if self
+                .send_notification(backfill::mark_backfill(notification))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+
+    /// Resume a session from its rollout file and register its state, with
+    /// the settings saved in its record.
+    async fn resume_session(
+        &self,
+        session_id: &acp::SessionId,
+        mut mcp_servers: Vec<acp::McpServer>,
+        settings: Option<SessionSettings>,
+    ) -> Result<(), acp::Error> {
+        let rollout_path =
+            find_conversation_path_by_id_str(&self.config.codex_home, session_id.0.as_ref())
+                .await
+                .map_err(acp::Error::into_internal_error)?
+                .ok_or_else(|| acp::Error::invalid_params().with_data("session not found"))?;
+        info!(path = %rollout_path.display(), "Resuming session from rollout");
+
+        let fs_session_id = Uuid::new_v4().to_string();
+        let acp_config = AcpConfig::load(&self.config.codex_home).await;
+        let settings = self.resumed_settings(settings, &acp_config);
+        let mut shell = ShellParity::defaults(&acp_config);
+        let (workspace_servers, workspace_notice) = self.workspace_mcp_servers(&mcp_servers).await;
+        mcp_servers.extend(workspace_servers);
+        let (session_config, renames) = self.build_session_config(
+            &fs_session_id,
+            mcp_servers,
+            &settings.sampling,
+            &mut shell,
+            &self
+                .extra_instructions(settings.response_language.as_deref())
+                .await,
+            command_cache::default_command_cache(),
+        )?;
+        let mcp_commands = mcp_commands::server_commands(session_config.mcp_servers.keys());
+        let auth_manager = self
+            .auth_manager
+            .read()
+            .map_err(|_| acp::Error::internal_error())?
+            .clone();
+        let NewConversation { conversation, .. } = self
+            .conversation_manager
+            .resume_conversation_from_rollout(session_config, rollout_path, auth_manager)
+            .await
+            .map_err(|e| {
+                warn!(error = %e, "Failed to resume Codex conversation");
+                self.forget_fs_session(&fs_session_id);
+                acp::Error::into_internal_error(e)
+            })?;
+
+        let current_mode = session::current_mode_id_for_config(&self.config)
+            .unwrap_or(acp::SessionModeId("auto".into()));
+        let mut state = session::SessionState::new(
+            fs_session_id,
+            Some(conversation),
+            &self.config,
+            current_mode,
+        );
+        state.sampling = settings.sampling;
+        state.fallback_models = settings.fallback_models;
+        state.mcp_commands = mcp_commands;
+        state.shell = shell;
+        state.max_session_tokens = settings.max_session_tokens;
+        state.response_language = settings.response_language.clone();
+        state.instructed_language = settings.response_language;
+        self.sessions.insert(session_id.0.to_string(), state);
+        if let Some(timeout) = idle::default_idle_timeout() {
+            self.spawn_idle_watch(session_id.clone(), timeout);
+        }
+        self.advertise_commands(session_id.0.as_ref());
+        for rename in &renames {
+            self.send_notice(session_id.0.as_ref(), rename.notice());
+        }
+        if let Some(notice) = workspace_notice {
+            self.send_notice(session_id.0.as_ref(), notice);
+        }
+        Ok(())
+    }
+
+    /// The settings of a resumed session: the saved ones, or what a new
+    /// session without request overrides gets.
+    pub(super) fn resumed_settings(
+        &self,
+        saved: Option<SessionSettings>,
+        acp_config: &AcpConfig,
+    ) -> SessionSettings {
+        saved.unwrap_or_else(|| SessionSettings {
+            sampling: if is_custom_provider(&self.config.model_provider_id) {
+                *self.sampling_defaults.borrow()
+            } else {
+                SamplingOverrides::default()
+            },
+            fallback_models: failover::default_fallback_models(),
+            max_session_tokens: budget::default_max_session_tokens(acp_config),
+            response_language: language::default_response_language(),
+        })
+    }
+
+    /// Save the session's current settings in its record, after a command
+    /// changed them.
+    pub(super) async fn save_session_settings(&self, session_id: &acp::SessionId) {
+        let Some(settings) = self.with_session_state_mut(session_id, |state| SessionSettings {
+            sampling: state.sampling,
+            fallback_models: state.fallback_models.clone(),
+            max_session_tokens: state.max_session_tokens,
+            response_language: state.response_language.clone(),
+        }) else {
+            return;
+        };
+        if let Err(err) = self
+            .record_store
+            .update(session_id.0.as_ref(), |record| {
+                record.settings = Some(settings);
+            })
+            .await
+        {
+            warn!(error = %err, "failed to save session settings");
+        }
+    }
+
     /// Change the approval and sandbox mode for a session.
     ///
     /// This preserves the current model and effort settings while updating
@@ -165,6 +567,7 @@ impl CodexAgent {
         args: acp::SetSessionModeRequest,
     ) -> Result<acp::SetSessionModeResponse, acp::Error> {
         info!(?args, "Received set session mode request");
+        self.touch_session(&args.session_id);
         let preset = session::find_preset_by_mode_id(&args.mode_id)
             .ok_or_else(|| acp::Error::invalid_params().with_data("invalid mode id"))?;
 
@@ -189,6 +592,31 @@ impl CodexAgent {
         Ok(acp::SetSessionModeResponse::default())
     }
 
+    /// Change the reasoning summary setting for a session.
+    ///
+    /// Approval, sandbox, model, and effort are preserved.
+    pub(super) async fn set_reasoning_summary(
+        &self,
+        session_id: &acp::SessionId,
+        summary: ReasoningSummary,
+    ) -> Result<(), acp::Error> {
+        self.apply_context_override(
+            session_id,
+            |ctx| Op::OverrideTurnContext {
+                cwd: None,
+                approval_policy: Some(ctx.approval),
+                sandbox_policy: Some(ctx.sandbox.clone()),
+                model: ctx.model.clone(),
+                effort: Some(ctx.effort),
+                summary: Some(summary),
+            },
+            |state| {
+                state.current_summary = summary;
+            },
+        )
+        .await
+    }
+
     /// Change the model for a session.
     ///
     /// This preserves the current approval and sandbox settings while updating
@@ -209,12 +637,15 @@ impl CodexAgent {
         }
 
         // Parse and validate the model_id, extracting provider, model name, and effort
-        let model_ctx =
-            session::parse_and_validate_model(&self.config, &self.profiles, &args.model_id)
-                .ok_or_else(|| {
-                    acp::Error::invalid_params()
-                        .with_data("invalid model id format or provider/model not found")
-                })?;
+        let model_ctx = session::parse_and_validate_model(
+            &self.model_config(),
+            &self.profiles.borrow(),
+            &args.model_id,
+        )
+        .ok_or_else(|| {
+            acp::Error::invalid_params()
+                .with_data("invalid model id format or provider/model not found")
+        })?;
 
         // Ensure the requested model is also from a custom provider
         if !is_custom_provider(&model_ctx.provider_id) {
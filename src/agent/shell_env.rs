@@ -0,0 +1,127 @@
+//! Environment parity between exec commands and the user's shell.
+//!
+//! Editors often launch the agent with a minimal environment, so commands
+//! fail because `PATH` (or version-manager setup) differs from the user's
+//! terminal. Two opt-in remedies are applied to Codex's
+//! `shell_environment_policy` when a session is created:
+//! - `loginShell`: run commands through the user's login shell profile;
+//! - `envFile`: load `KEY=VALUE` lines (e.g. a `.env` or exported profile) into
+//!   every command's environment.
+//!
+//! Defaults come from `CODEX_ACP_LOGIN_SHELL` / `CODEX_ACP_ENV_FILE`, or else
+//! from `login_shell` / `env_file` in the `[acp.shell]` section of
+//! `config.toml`, and a session can override them via
+//! `NewSessionRequest.meta.shell`.
+
+use std::path::PathBuf;
+
+use agent_client_protocol::Error;
+use codex_core::config_types::ShellEnvironmentPolicy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::acp_config::AcpConfig;
+
+/// Environment variable enabling login-shell execution for all sessions.
+pub const LOGIN_SHELL_ENV: &str = "CODEX_ACP_LOGIN_SHELL";
+/// Environment variable naming an env file loaded for all sessions.
+pub const ENV_FILE_ENV: &str = "CODEX_ACP_ENV_FILE";
+
+/// How exec commands get their environment.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellParity {
+    #[serde(default)]
+    pub login_shell: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_file: Option<PathBuf>,
+    /// Number of variables loaded from `env_file`; filled in when applied.
+    #[serde(default, skip_deserializing)]
+    pub loaded_vars: usize,
+}
+
+impl ShellParity {
+    /// Agent-wide defaults from the environment, or else from `[acp.shell]`.
+    pub fn defaults(config: &AcpConfig) -> Self {
+        Self {
+            login_shell: std::env::var(LOGIN_SHELL_ENV)
+                .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .ok()
+                .or(config.login_shell)
+                .unwrap_or(false),
+            env_file: std::env::var(ENV_FILE_ENV)
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .map(PathBuf::from)
+                .or_else(|| config.env_file.clone()),
+            loaded_vars: 0,
+        }
+    }
+
+    /// Read `NewSessionRequest.meta.shell`, if present.
+    pub fn from_meta(meta: Option<&Value>) -> Result<Option<Self>, Error> {
+        match meta.and_then(|m| m.get("shell")) {
+            None => Ok(None),
+            Some(value) => serde_json::from_value(value.clone())
+                .map(Some)
+                .map_err(|e| Error::invalid_params().with_data(format!("invalid shell: {e}"))),
+        }
+    }
+
+    /// Apply to Codex's shell environment policy, loading the env file if set.
+    pub fn apply_to(&mut self, policy: &mut ShellEnvironmentPolicy) -> Result<(), Error> {
+        if self.login_shell {
+            policy.use_profile = true;
+        }
+        if let Some(path) = &self.env_file {
+            let text = std::fs::read_to_string(path).map_err(|e| {
+                Error::invalid_params()
+                    .with_data(format!("failed to read env file {}: {e}", path.display()))
+            })?;
+            let vars = parse_env_file(&text);
+            self.loaded_vars = vars.len();
+            policy.set.extend(vars);
+        }
+        Ok(())
+    }
+
+    /// Human-readable summary for `/status`.
+    pub fn describe(&self) -> String {
+        let shell = if self.login_shell {
+            "login shell profile"
+        } else {
+            "default"
+        };
+        match &self.env_file {
+            Some(path) => format!(
+                "{shell}, env file {} ({} vars)",
+                path.display(),
+                self.loaded_vars
+            ),
+            None => shell.to_string(),
+        }
+    }
+}
+
+/// Parse `KEY=VALUE` lines, accepting `export` prefixes, comments, and quoted values.
+pub fn parse_env_file(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return None;
+            }
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
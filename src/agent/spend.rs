@@ -0,0 +1,340 @@
+//! Daily and weekly spend budgets shared by all sessions.
+//!
+//! Token usage is priced per model and added to a ledger of per-day totals in
+//! `$CODEX_HOME/acp/spend.json`, so every agent process using the same Codex
+//! home counts against the same budget. When `CODEX_ACP_DAILY_BUDGET_USD` or
+//! `CODEX_ACP_WEEKLY_BUDGET_USD` is set and the spend for the current UTC day
+//! (or the last seven days) reaches it, new turns fail with
+//! [`SPEND_BUDGET_ERROR_CODE`] unless the session was released with the
+//! `codex/overrideBudget` extension method.
+//!
+//! Prices are USD per million tokens. A few OpenAI models are built in;
+//! `CODEX_ACP_MODEL_PRICES` adds or replaces entries, e.g.
+//! `{"gpt-5": {"input": 1.25, "cachedInput": 0.125, "output": 10}}`. Models
+//! without a price (such as local ones) cost nothing.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use agent_client_protocol::{Error, SessionId};
+use codex_core::protocol::TokenUsage;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::warn;
+
+use super::core::CodexAgent;
+
+/// JSON-RPC error code returned when a spend budget is exhausted.
+pub const SPEND_BUDGET_ERROR_CODE: i32 = -32053;
+
+/// Environment variable with the daily budget in USD.
+pub const DAILY_BUDGET_ENV: &str = "CODEX_ACP_DAILY_BUDGET_USD";
+/// Environment variable with the rolling seven-day budget in USD.
+pub const WEEKLY_BUDGET_ENV: &str = "CODEX_ACP_WEEKLY_BUDGET_USD";
+/// Environment variable with extra model prices as JSON.
+pub const MODEL_PRICES_ENV: &str = "CODEX_ACP_MODEL_PRICES";
+
+/// Days of history kept in the ledger.
+const LEDGER_RETENTION_DAYS: u64 = 14;
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPrice {
+    pub input: f64,
+    #[serde(default)]
+    pub cached_input: Option<f64>,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    const fn new(input: f64, cached_input: f64, output: f64) -> Self {
+        Self {
+            input,
+            cached_input: Some(cached_input),
+            output,
+        }
+    }
+
+    /// Cost in USD of `usage`; cached input is billed at the cached rate when known.
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        let cached = usage.cached_input_tokens.min(usage.input_tokens);
+        let uncached = usage.input_tokens - cached;
+        let cached_rate = self.cached_input.unwrap_or(self.input);
+        (uncached as f64 * self.input
+            + cached as f64 * cached_rate
+            + usage.output_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
+}
+
+/// Built-in prices, matched by model name prefix (longest first).
+const BUILTIN_PRICES: &[(&str, ModelPrice)] = &[
+    ("gpt-5-mini", ModelPrice::new(0.25, 0.025, 2.0)),
+    ("gpt-5-nano", ModelPrice::new(0.05, 0.005, 0.4)),
+    ("gpt-5", ModelPrice::new(1.25, 0.125, 10.0)),
+    ("gpt-4.1-mini", ModelPrice::new(0.4, 0.1, 1.6)),
+    ("gpt-4.1", ModelPrice::new(2.0, 0.5, 8.0)),
+    ("o4-mini", ModelPrice::new(1.1, 0.275, 4.4)),
+    ("o3-mini", ModelPrice::new(1.1, 0.55, 4.4)),
+    ("o3", ModelPrice::new(2.0, 0.5, 8.0)),
+];
+
+/// Price for a model id (`provider@model` or a bare model name).
+pub fn price_for(model_id: &str) -> Option<ModelPrice> {
+    let model = model_id
+        .split_once('@')
+        .map_or(model_id, |(_, model)| model);
+    let custom: Option<HashMap<String, ModelPrice>> = std::env::var(MODEL_PRICES_ENV)
+        .ok()
+        .and_then(|raw| match serde_json::from_str(&raw) {
+            Ok(prices) => Some(prices),
+            Err(err) => {
+                warn!(error = %err, "ignoring malformed {MODEL_PRICES_ENV}");
+                None
+            }
+        });
+    if let Some(price) = custom.as_ref().and_then(|prices| prices.get(model)) {
+        return Some(*price);
+    }
+    BUILTIN_PRICES
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, price)| *price)
+}
+
+/// Current day as days since the Unix epoch (UTC).
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or_default()
+}
+
+/// Spend in USD per UTC day, keyed by days since the Unix epoch.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpendLedger {
+    #[serde(default)]
+    days: BTreeMap<u64, f64>,
+}
+
+impl SpendLedger {
+    /// Path of the shared ledger under a Codex home.
+    pub fn path(codex_home: &Path) -> PathBuf {
+        codex_home.join("acp").join("spend.json")
+    }
+
+    /// Load the ledger; a missing or malformed file yields an empty ledger.
+    pub async fn load(path: &Path) -> Self {
+        match tokio::fs::read(path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+                warn!(path = %path.display(), error = %err, "ignoring malformed spend ledger");
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub async fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(path, serde_json::to_vec_pretty(self)?).await
+    }
+
+    /// Add `cost` to `day` and drop days past the retention window.
+    pub fn add(&mut self, day: u64, cost: f64) {
+        *self.days.entry(day).or_default() += cost;
+        let cutoff = day.saturating_sub(LEDGER_RETENTION_DAYS);
+        self.days.retain(|d, _| *d > cutoff);
+    }
+
+    pub fn daily(&self, day: u64) -> f64 {
+        self.days.get(&day).copied().unwrap_or_default()
+    }
+
+    /// Spend over the seven days ending with `day`.
+    pub fn weekly(&self, day: u64) -> f64 {
+        self.days
+            .range(day.saturating_sub(6)..=day)
+            .map(|(_, cost)| cost)
+            .sum()
+    }
+}
+
+/// Budget period that ran out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    Daily,
+    Weekly,
+}
+
+impl BudgetPeriod {
+    pub fn label(self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "daily",
+            BudgetPeriod::Weekly => "weekly",
+        }
+    }
+}
+
+/// Configured spend limits in USD.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SpendBudget {
+    pub daily: Option<f64>,
+    pub weekly: Option<f64>,
+}
+
+impl SpendBudget {
+    pub fn from_env() -> Self {
+        fn limit(name: &str) -> Option<f64> {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().trim_start_matches('$').parse().ok())
+                .filter(|v: &f64| *v > 0.0)
+        }
+        Self {
+            daily: limit(DAILY_BUDGET_ENV),
+            weekly: limit(WEEKLY_BUDGET_ENV),
+        }
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.daily.is_some() || self.weekly.is_some()
+    }
+
+    /// The first exhausted period with its spend and limit, if any.
+    pub fn exceeded(&self, ledger: &SpendLedger, day: u64) -> Option<(BudgetPeriod, f64, f64)> {
+        let daily = self
+            .daily
+            .map(|limit| (BudgetPeriod::Daily, ledger.daily(day), limit));
+        let weekly = self
+            .weekly
+            .map(|limit| (BudgetPeriod::Weekly, ledger.weekly(day), limit));
+        [daily, weekly]
+            .into_iter()
+            .flatten()
+            .find(|(_, spent, limit)| spent >= limit)
+    }
+
+    /// Spend so far against the configured limits.
+    pub fn status(&self, ledger: &SpendLedger, day: u64) -> SpendStatus {
+        SpendStatus {
+            daily_spent_usd: ledger.daily(day),
+            daily_limit_usd: self.daily,
+            weekly_spent_usd: ledger.weekly(day),
+            weekly_limit_usd: self.weekly,
+        }
+    }
+}
+
+/// Spend against the budget, as reported by `/status`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpendStatus {
+    pub daily_spent_usd: f64,
+    pub daily_limit_usd: Option<f64>,
+    pub weekly_spent_usd: f64,
+    pub weekly_limit_usd: Option<f64>,
+}
+
+impl SpendStatus {
+    /// Lines for `/status`, e.g. `Today: $1.20 of $5.00 ($3.80 left)`.
+    pub fn describe(&self) -> Vec<String> {
+        fn line(label: &str, spent: f64, limit: Option<f64>) -> String {
+            match limit {
+                Some(limit) => format!(
+                    "{label}${spent:.2} of ${limit:.2} (${:.2} left)",
+                    (limit - spent).max(0.0)
+                ),
+                None => format!("{label}${spent:.2}"),
+            }
+        }
+        vec![
+            line("Today:     ", self.daily_spent_usd, self.daily_limit_usd),
+            line("This week: ", self.weekly_spent_usd, self.weekly_limit_usd),
+        ]
+    }
+}
+
+/// The error returned for turns beyond the budget.
+pub fn spend_budget_error(period: BudgetPeriod, spent: f64, limit: f64) -> Error {
+    Error {
+        code: SPEND_BUDGET_ERROR_CODE,
+        message: format!("The {} spend budget is exhausted", period.label()),
+        data: Some(json!({
+            "period": period.label(),
+            "spentUsd": spent,
+            "limitUsd": limit,
+            "guidance": "Raise the budget, wait for the next period, or call codex/overrideBudget for this session.",
+        })),
+    }
+}
+
+impl CodexAgent {
+    fn spend_ledger_path(&self) -> PathBuf {
+        SpendLedger::path(&self.config.codex_home)
+    }
+
+    /// Fail if a spend budget is exhausted and the session has not overridden it.
+    pub(super) async fn check_spend_budget(&self, session_id: &SessionId) -> Result<(), Error> {
+        let budget = SpendBudget::from_env();
+        let overridden = self
+            .with_session_state_mut(session_id, |state| state.budget_override)
+            .unwrap_or(false);
+        if !budget.is_set() || overridden {
+            return Ok(());
+        }
+        let ledger = SpendLedger::load(&self.spend_ledger_path()).await;
+        match budget.exceeded(&ledger, today()) {
+            Some((period, spent, limit)) => Err(spend_budget_error(period, spent, limit)),
+            None => Ok(()),
+        }
+    }
+
+    /// Price `usage` for `model_id` and add it to the shared ledger.
+    pub(super) async fn record_spend(&self, model_id: &str, usage: &TokenUsage) {
+        let Some(cost) = price_for(model_id).map(|price| price.cost(usage)) else {
+            return;
+        };
+        if cost <= 0.0 {
+            return;
+        }
+        let path = self.spend_ledger_path();
+        let mut ledger = SpendLedger::load(&path).await;
+        ledger.add(today(), cost);
+        if let Err(err) = ledger.save(&path).await {
+            warn!(error = %err, "failed to persist spend ledger");
+        }
+    }
+
+    /// Budget state for `/status`, or `None` when no budget is configured.
+    pub(super) async fn spend_status(&self) -> Option<SpendStatus> {
+        let budget = SpendBudget::from_env();
+        if !budget.is_set() {
+            return None;
+        }
+        let ledger = SpendLedger::load(&self.spend_ledger_path()).await;
+        Some(budget.status(&ledger, today()))
+    }
+
+    /// Let a session keep prompting past the spend budget.
+    pub(super) async fn override_budget(
+        &self,
+        session_id: &SessionId,
+    ) -> Result<serde_json::Value, Error> {
+        self.try_with_session_state_mut(session_id, |state| state.budget_override = true)?;
+        let day = today();
+        let ledger = SpendLedger::load(&self.spend_ledger_path()).await;
+        Ok(json!({
+            "sessionId": session_id,
+            "overridden": true,
+            "dailySpentUsd": ledger.daily(day),
+            "weeklySpentUsd": ledger.weekly(day),
+        }))
+    }
+}
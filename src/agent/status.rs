@@ -0,0 +1,467 @@
+//! `/status` as data.
+//!
+//! The session status is gathered into a [`StatusReport`], rendered as the
+//! familiar text block for the chat, and attached unchanged to that message's
+//! `_meta.codexStatus`. The `codex/status` extension method returns the same
+//! report, so clients can lay the status out natively instead of showing
+//! preformatted text.
+//!
+//! Clients that render markdown can say so at initialize with
+//! `_meta.clientRendersMarkdown: true` (on the request or its client
+//! capabilities); `/status` and `/stats` then use headings and tables.
+//! `CODEX_ACP_REPORT_FORMAT=plain|markdown` overrides the client's choice.
+//!
+//! The report ends with the agent process itself (see [`ProcessInfo`]),
+//! which `codex/agentInfo` also returns on its own.
+
+use std::collections::BTreeMap;
+
+use codex_core::protocol::TokenUsage;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{
+    process_info::{ProcessInfo, format_bytes, format_uptime},
+    spend::SpendStatus,
+};
+
+/// Environment variable forcing the report format (`plain`, `markdown`).
+pub const REPORT_FORMAT_ENV: &str = "CODEX_ACP_REPORT_FORMAT";
+
+/// How `/status` and `/stats` are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Preformatted text, readable in any client.
+    #[default]
+    Plain,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "plain" | "text" => Some(Self::Plain),
+            "markdown" | "md" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+
+    /// The format for a client, from `CODEX_ACP_REPORT_FORMAT` or else the
+    /// `clientRendersMarkdown` flag in the initialize request's or its client
+    /// capabilities' meta.
+    pub fn for_client(request_meta: Option<&Value>, capabilities_meta: Option<&Value>) -> Self {
+        if let Some(format) = std::env::var(REPORT_FORMAT_ENV)
+            .ok()
+            .and_then(|v| Self::parse(&v))
+        {
+            return format;
+        }
+        Self::from_meta(request_meta, capabilities_meta)
+    }
+
+    /// The format requested by the `clientRendersMarkdown` meta flag.
+    pub fn from_meta(request_meta: Option<&Value>, capabilities_meta: Option<&Value>) -> Self {
+        let renders_markdown = [request_meta, capabilities_meta]
+            .into_iter()
+            .flatten()
+            .find_map(|meta| {
+                meta.get("clientRendersMarkdown")
+                    .or_else(|| meta.get("client_renders_markdown"))
+                    .and_then(Value::as_bool)
+            });
+        match renders_markdown {
+            Some(true) => Self::Markdown,
+            _ => Self::Plain,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusReport {
+    pub session_id: String,
+    pub workspace: WorkspaceStatus,
+    pub account: AccountStatus,
+    pub model: ModelStatus,
+    pub tokens: TokenStatus,
+    /// `None` when no spend budget is configured.
+    pub spend: Option<SpendStatus>,
+    pub attachments: Vec<String>,
+    pub process: ProcessInfo,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceStatus {
+    /// Workspace root, with the home directory shortened to `~`.
+    pub path: String,
+    pub approval_mode: String,
+    pub sandbox: String,
+    pub shell: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountStatus {
+    /// `ChatGPT`, `API key`, or `Not signed in`.
+    pub auth_mode: String,
+    pub email: Option<String>,
+    pub plan: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelStatus {
+    pub name: String,
+    pub provider: String,
+    pub reasoning_effort: String,
+    pub reasoning_summaries: String,
+    /// `None` uses the model's default.
+    pub verbosity: Option<String>,
+    /// `None` answers in the prompt's language.
+    pub response_language: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenStatus {
+    pub usage: TokenUsage,
+    /// The session's token cap, if any.
+    pub limit: Option<u64>,
+    pub by_model: BTreeMap<String, TokenUsage>,
+}
+
+/// `s` with its first character upper-cased.
+pub fn title_case(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}", first.to_uppercase(), chars.as_str()),
+        None => String::new(),
+    }
+}
+
+/// The text shown for `/status`.
+pub fn render_status(report: &StatusReport, format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Plain => format_status(report),
+        ReportFormat::Markdown => format_status_markdown(report),
+    }
+}
+
+/// `/status` as preformatted text.
+pub fn format_status(report: &StatusReport) -> String {
+    let StatusReport {
+        session_id,
+        workspace,
+        account,
+        model,
+        tokens,
+        spend,
+        attachments,
+        process,
+    } = report;
+    let mut status = format!(
+        r#"
+📂 Workspace
+
+    Path:          {cwd}
+    Approval Mode: {approval}
+    Sandbox:       {sandbox}
+    Shell:         {shell}
+
+👤 Account
+
+    Signed in with: {auth_mode}
+    Login:          {email}
+    Plan:           {plan}
+
+🧠 Model
+
+    Name:                {model}
+    Provider:            {provider}
+    Reasoning Effort:    {effort}
+    Reasoning Summaries: {summary}
+    Verbosity:           {verbosity}
+    Response Language:   {response_language}
+
+📊 Token Usage
+
+    Session ID:     {sid}
+    Input:          {input}
+    Output:         {output}
+    Total:          {total}
+"#,
+        cwd = workspace.path,
+        approval = workspace.approval_mode,
+        sandbox = workspace.sandbox,
+        shell = workspace.shell,
+        auth_mode = account.auth_mode,
+        email = account.email.as_deref().unwrap_or("(none)"),
+        plan = account.plan.as_deref().unwrap_or("(unknown)"),
+        model = model.name,
+        provider = title_case(&model.provider),
+        effort = title_case(&model.reasoning_effort),
+        summary = title_case(&model.reasoning_summaries),
+        verbosity = title_case(model.verbosity.as_deref().unwrap_or("default")),
+        response_language = model
+            .response_language
+            .as_deref()
+            .unwrap_or("Same as prompt"),
+        sid = session_id,
+        input = tokens.usage.input_tokens,
+        output = tokens.usage.output_tokens,
+        total = tokens.usage.total_tokens,
+    );
+    if let Some(limit) = tokens.limit {
+        status.push_str(&format!(
+            "    Limit:          {limit} (/override to lift)\n"
+        ));
+    }
+    if let Some(spend) = spend {
+        status.push_str("\n💰 Spend Budget\n\n");
+        for line in spend.describe() {
+            status.push_str(&format!("    {line}\n"));
+        }
+    }
+    if tokens.by_model.len() > 1 {
+        status.push_str("\n    By model:\n");
+        for (model, usage) in &tokens.by_model {
+            status.push_str(&format!(
+                "      {model}: {} in / {} out / {} total\n",
+                usage.input_tokens, usage.output_tokens, usage.total_tokens
+            ));
+        }
+    }
+    if !attachments.is_empty() {
+        status.push('\n');
+        status.push_str(&super::commands::format_attachments(attachments));
+    }
+    status.push_str("\n⚙️ Agent Process\n\n");
+    for line in process.describe() {
+        status.push_str(&format!("    {line}\n"));
+    }
+    status
+}
+
+/// `/status` as markdown headings, lists and tables.
+pub fn format_status_markdown(report: &StatusReport) -> String {
+    let StatusReport {
+        session_id,
+        workspace,
+        account,
+        model,
+        tokens,
+        spend,
+        attachments,
+        process,
+    } = report;
+    let mut out = String::new();
+    let mut section = |title: &str, fields: &[(&str, String)]| {
+        out.push_str(&format!("### {title}\n\n"));
+        for (label, value) in fields {
+            out.push_str(&format!("- **{label}:** {value}\n"));
+        }
+        out.push('\n');
+    };
+    section(
+        "📂 Workspace",
+        &[
+            ("Path", format!("`{}`", workspace.path)),
+            ("Approval Mode", workspace.approval_mode.clone()),
+            ("Sandbox", workspace.sandbox.clone()),
+            ("Shell", workspace.shell.clone()),
+        ],
+    );
+    section(
+        "👤 Account",
+        &[
+            ("Signed in with", account.auth_mode.clone()),
+            (
+                "Login",
+                account.email.clone().unwrap_or_else(|| "(none)".into()),
+            ),
+            (
+                "Plan",
+                account.plan.clone().unwrap_or_else(|| "(unknown)".into()),
+            ),
+        ],
+    );
+    section(
+        "🧠 Model",
+        &[
+            ("Name", model.name.clone()),
+            ("Provider", title_case(&model.provider)),
+            ("Reasoning Effort", title_case(&model.reasoning_effort)),
+            (
+                "Reasoning Summaries",
+                title_case(&model.reasoning_summaries),
+            ),
+            (
+                "Verbosity",
+                title_case(model.verbosity.as_deref().unwrap_or("default")),
+            ),
+            (
+                "Response Language",
+                model
+                    .response_language
+                    .clone()
+                    .unwrap_or_else(|| "Same as prompt".into()),
+            ),
+        ],
+    );
+    out.push_str(&format!("### 📊 Token Usage\n\nSession `{session_id}`\n\n"));
+    out.push_str(&usage_table_markdown(tokens));
+    if let Some(spend) = spend {
+        out.push_str("\n### 💰 Spend Budget\n\n");
+        out.push_str(&spend_table_markdown(spend));
+    }
+    if !attachments.is_empty() {
+        out.push_str("\n### 📎 Attachments\n\n");
+        for path in attachments {
+            out.push_str(&format!("- `{path}`\n"));
+        }
+    }
+    out.push_str("\n### ⚙️ Agent Process\n\n");
+    let memory = process
+        .rss_bytes
+        .map(format_bytes)
+        .unwrap_or_else(|| "(unknown)".to_string());
+    for (label, value) in [
+        ("PID", process.pid.to_string()),
+        ("Version", process.version.clone()),
+        ("Uptime", format_uptime(process.uptime_secs)),
+        ("Memory (RSS)", memory),
+        ("Open Sessions", process.open_sessions.to_string()),
+        ("Bridge Connections", process.bridge_connections.to_string()),
+    ] {
+        out.push_str(&format!("- **{label}:** {value}\n"));
+    }
+    out
+}
+
+/// The text shown for `/stats`: token usage per model and spend.
+pub fn render_stats(report: &StatusReport, format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Plain => {
+            let mut out = String::from("📊 Token Usage\n\n");
+            out.push_str(&usage_table_plain(&report.tokens));
+            if let Some(spend) = &report.spend {
+                out.push_str("\n💰 Spend Budget\n\n");
+                for line in spend.describe() {
+                    out.push_str(&format!("    {line}\n"));
+                }
+            }
+            out
+        }
+        ReportFormat::Markdown => {
+            let mut out = String::from("### 📊 Token Usage\n\n");
+            out.push_str(&usage_table_markdown(&report.tokens));
+            if let Some(spend) = &report.spend {
+                out.push_str("\n### 💰 Spend Budget\n\n");
+                out.push_str(&spend_table_markdown(spend));
+            }
+            out
+        }
+    }
+}
+
+const USAGE_COLUMNS: [&str; 6] = ["Model", "Input", "Cached", "Output", "Reasoning", "Total"];
+
+/// Table rows: one per model, then the session total.
+fn usage_rows(tokens: &TokenStatus) -> Vec<[String; 6]> {
+    fn row(label: String, usage: &TokenUsage) -> [String; 6] {
+        [
+            label,
+            usage.input_tokens.to_string(),
+            usage.cached_input_tokens.to_string(),
+            usage.output_tokens.to_string(),
+            usage.reasoning_output_tokens.to_string(),
+            usage.total_tokens.to_string(),
+        ]
+    }
+    let mut rows: Vec<[String; 6]> = tokens
+        .by_model
+        .iter()
+        .map(|(model, usage)| row(model.clone(), usage))
+        .collect();
+    rows.push(row("Total".to_string(), &tokens.usage));
+    rows
+}
+
+fn usage_table_markdown(tokens: &TokenStatus) -> String {
+    let mut out = format!(
+        "| {} |\n|:--|--:|--:|--:|--:|--:|\n",
+        USAGE_COLUMNS.join(" | ")
+    );
+    let rows = usage_rows(tokens);
+    let last = rows.len() - 1;
+    for (i, row) in rows.into_iter().enumerate() {
+        let row = if i == last {
+            row.map(|cell| format!("**{cell}**"))
+        } else {
+            row
+        };
+        out.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+    if let Some(limit) = tokens.limit {
+        out.push_str(&format!(
+            "\nLimit: **{limit}** tokens (`/override` to lift)\n"
+        ));
+    }
+    out
+}
+
+fn usage_table_plain(tokens: &TokenStatus) -> String {
+    let rows = usage_rows(tokens);
+    let widths: Vec<usize> = (0..USAGE_COLUMNS.len())
+        .map(|col| {
+            rows.iter()
+                .map(|row| row[col].chars().count())
+                .chain([USAGE_COLUMNS[col].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |cells: &[&str]| {
+        let mut line = String::from("   ");
+        for (col, cell) in cells.iter().enumerate() {
+            let pad = widths[col] - cell.chars().count();
+            if col == 0 {
+                line.push_str(&format!(" {cell}{}", " ".repeat(pad)));
+            } else {
+                line.push_str(&format!("  {}{cell}", " ".repeat(pad)));
+            }
+        }
+        line.push('\n');
+        line
+    };
+    let mut out = line(&USAGE_COLUMNS[..]);
+    for row in &rows {
+        let cells: Vec<&str> = row.iter().map(String::as_str).collect();
+        out.push_str(&line(&cells));
+    }
+    if let Some(limit) = tokens.limit {
+        out.push_str(&format!("\n    Limit: {limit} (/override to lift)\n"));
+    }
+    out
+}
+
+fn spend_table_markdown(spend: &SpendStatus) -> String {
+    fn row(period: &str, spent: f64, limit: Option<f64>) -> String {
+        match limit {
+            Some(limit) => format!(
+                "| {period} | ${spent:.2} | ${limit:.2} | ${:.2} |\n",
+                (limit - spent).max(0.0)
+            ),
+            None => format!("| {period} | ${spent:.2} | – | – |\n"),
+        }
+    }
+    let mut out = String::from("| Period | Spent | Limit | Left |\n|:--|--:|--:|--:|\n");
+    out.push_str(&row("Today", spend.daily_spent_usd, spend.daily_limit_usd));
+    out.push_str(&row(
+        "This week",
+        spend.weekly_spent_usd,
+        spend.weekly_limit_usd,
+    ));
+    out
+}
@@ -0,0 +1,107 @@
+//! Prompts that arrive while the session's turn is still running.
+//!
+//! By default (`queue`) a second prompt waits for the running turn to end and
+//! then runs as the next turn. With `interrupt` (`CODEX_ACP_STEERING=interrupt`,
+//! `NewSessionRequest.meta.steering`, or `PromptRequest.meta.steering` for a
+//! single prompt) the running turn is interrupted and the new prompt is
+//! submitted with the replaced request and its partial answer as context, so
+//! the model follows the correction instead of starting over.
+//!
+//! The replaced turn ends with `StopReason::Cancelled` and
+//! `_meta.stopReason = "steered"`, and is journaled as `steered`.
+
+use agent_client_protocol::{Error, SessionId};
+use codex_core::protocol::Op;
+use serde_json::json;
+
+use super::{context::ContextItem, core::CodexAgent};
+use crate::fs::text;
+
+/// Environment variable choosing how mid-turn prompts are handled (`queue`, `interrupt`).
+pub const STEERING_ENV: &str = "CODEX_ACP_STEERING";
+
+/// `_meta.stopReason` of a turn replaced by a steering prompt.
+pub const STEERED_STOP_REASON: &str = "steered";
+
+/// Bytes of the replaced turn's answer handed to the next turn.
+pub const MAX_STEER_OUTPUT: usize = 4 * 1024;
+
+/// What happens to a prompt sent while a turn is running.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Steering {
+    /// Run it after the current turn.
+    #[default]
+    Queue,
+    /// Interrupt the current turn and run it with the turn's progress as context.
+    Interrupt,
+}
+
+impl Steering {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "queue" => Some(Self::Queue),
+            "interrupt" => Some(Self::Interrupt),
+            _ => None,
+        }
+    }
+}
+
+/// Steering for new sessions, from `CODEX_ACP_STEERING`.
+pub fn default_steering() -> Steering {
+    std::env::var(STEERING_ENV)
+        .ok()
+        .and_then(|v| Steering::parse(&v))
+        .unwrap_or_default()
+}
+
+/// Read `meta.steering` of a `NewSessionRequest` or `PromptRequest`.
+pub fn steering_from_meta(meta: Option<&serde_json::Value>) -> Result<Option<Steering>, Error> {
+    match meta.and_then(|m| m.get("steering")) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => value
+            .as_str()
+            .and_then(Steering::parse)
+            .map(Some)
+            .ok_or_else(|| {
+                Error::invalid_params().with_data("steering must be \"queue\" or \"interrupt\"")
+            }),
+    }
+}
+
+/// `PromptResponse.meta` of a replaced turn.
+pub fn steered_meta() -> serde_json::Value {
+    json!({ "stopReason": STEERED_STOP_REASON })
+}
+
+/// Context carried from a replaced turn into the prompt that replaced it.
+pub fn steer_context(prompt: &str, output: &str) -> ContextItem {
+    let output = output.trim();
+    let output = if output.is_empty() {
+        "(nothing yet)".to_string()
+    } else {
+        text::elide_head(output, MAX_STEER_OUTPUT)
+    };
+    ContextItem::Text {
+        text: format!(
+            "The user interrupted their previous request with the message that follows. Take it into account as a correction or addition to that request; do not redo work that was already done.\n\nPrevious request:\n{}\n\nYour answer so far:\n{output}",
+            prompt.trim()
+        ),
+        label: Some("interrupted-turn".to_string()),
+    }
+}
+
+impl CodexAgent {
+    /// Interrupt the running turn so a new prompt can take its place.
+    pub(super) async fn steer(&self, session_id: &SessionId) -> Result<(), Error> {
+        self.with_session_state_mut(session_id, |state| {
+            state.steered = true;
+            state.turn_cancel.cancel();
+        });
+        self.get_conversation(session_id)
+            .await?
+            .submit(Op::Interrupt)
+            .await
+            .map_err(Error::into_internal_error)?;
+        Ok(())
+    }
+}
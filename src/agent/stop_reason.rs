@@ -0,0 +1,83 @@
+//! Stop reasons other than a plain end of turn.
+//!
+//! Codex ends a turn that ran out of output tokens, or that the provider's
+//! content filter cut short, with an error such as "Incomplete response
+//! returned, reason: max_output_tokens". Those turns are reported as
+//! [`StopReason::MaxTokens`] and [`StopReason::Refusal`] so clients can offer
+//! to continue or escalate, with the Codex message in
+//! `PromptResponse._meta.stopDetail`. A short reply that only declines the
+//! request is also reported as a refusal.
+
+use agent_client_protocol::StopReason;
+use serde_json::{Value, json};
+
+/// Longest agent message still considered a bare refusal.
+const MAX_REFUSAL_LEN: usize = 200;
+
+/// Openings of a reply that declines the request outright.
+const REFUSAL_OPENINGS: &[&str] = &[
+    "i'm sorry, but i can't",
+    "i'm sorry, but i cannot",
+    "i'm sorry, i can't",
+    "i can't help with that",
+    "i cannot help with that",
+    "i can't assist with that",
+    "i cannot assist with that",
+];
+
+/// The stop reason implied by a turn's error or, failing that, its output.
+pub fn classify(error: Option<&str>, output: &str) -> Option<StopReason> {
+    match error {
+        Some(error) => classify_error(error),
+        None => is_refusal_message(output).then_some(StopReason::Refusal),
+    }
+}
+
+fn classify_error(message: &str) -> Option<StopReason> {
+    let message = message.to_ascii_lowercase();
+    if message.contains("max_output_tokens")
+        || message.contains("max_tokens")
+        || message.contains("output token limit")
+    {
+        Some(StopReason::MaxTokens)
+    } else if message.contains("content_filter") || message.contains("refusal") {
+        Some(StopReason::Refusal)
+    } else {
+        None
+    }
+}
+
+/// Whether `output` is nothing but a short refusal.
+pub fn is_refusal_message(output: &str) -> bool {
+    let output = output.trim();
+    if output.is_empty() || output.len() > MAX_REFUSAL_LEN {
+        return false;
+    }
+    let output = output.replace('\u{2019}', "'").to_lowercase();
+    REFUSAL_OPENINGS
+        .iter()
+        .any(|opening| output.starts_with(opening))
+}
+
+/// `PromptResponse._meta` explaining a [`StopReason::MaxTokens`] or
+/// [`StopReason::Refusal`] stop.
+pub fn stop_meta(reason: &StopReason, error: Option<&str>) -> Option<Value> {
+    let (kind, guidance) = match reason {
+        StopReason::MaxTokens => (
+            "max_tokens",
+            "The response hit the model's output token limit; send /continue to resume it.",
+        ),
+        StopReason::Refusal => (
+            "refusal",
+            "The model declined this request; rephrase it or try another model.",
+        ),
+        _ => return None,
+    };
+    Some(json!({
+        "stopDetail": {
+            "reason": kind,
+            "message": error,
+            "guidance": guidance,
+        }
+    }))
+}
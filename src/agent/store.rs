@@ -0,0 +1,233 @@
+//! On-disk session records.
+//!
+//! A small JSON record per session lives under `$CODEX_HOME/acp/sessions/`
+//! so that per-session data which Codex itself does not persist (such as
+//! cumulative token usage, the client's MCP servers and the session's
+//! sampling, language, fallback and token-cap settings) survives agent
+//! restarts and `load_session`.
+//!
+//! Next to each record, `<session>.journal.jsonl` holds one [`JournalEntry`]
+//! per finished turn, including partial output of turns that failed. The
+//! journal keeps the newest [`MAX_JOURNAL_ENTRIES`] turns, with prompts and
+//! output cut to [`MAX_JOURNAL_TEXT`] bytes each.
+//!
+//! Session ids come from clients and name files, so only ids made of ASCII
+//! letters, digits, `-` and `_` (which covers Codex's UUIDs) are stored.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use codex_core::protocol::TokenUsage;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+use super::{sampling::SamplingOverrides, usage::UsageTracker};
+use crate::fs::{text, tools::command_history::CommandRecord};
+
+/// Turns kept in a session journal; older ones are dropped.
+pub const MAX_JOURNAL_ENTRIES: usize = 200;
+
+/// Bytes of prompt and of output stored per journaled turn.
+pub const MAX_JOURNAL_TEXT: usize = 16 * 1024;
+
+/// Persisted per-session data.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionRecord {
+    /// Cumulative token usage reported by Codex for the session.
+    #[serde(default)]
+    pub token_usage: Option<TokenUsage>,
+    /// Usage attributed to each `provider@model` used in the session.
+    #[serde(default)]
+    pub model_usage: UsageTracker,
+    /// Client MCP servers of the session, without secrets (see `mcp_servers`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mcp_servers: Vec<serde_json::Value>,
+    /// Settings a resumed session starts with; `None` for records written
+    /// before they were saved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<SessionSettings>,
+}
+
+/// Session settings Codex does not keep in its rollout.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSettings {
+    #[serde(default)]
+    pub sampling: SamplingOverrides,
+    #[serde(default)]
+    pub fallback_models: Vec<String>,
+    #[serde(default)]
+    pub max_session_tokens: Option<u64>,
+    #[serde(default)]
+    pub response_language: Option<String>,
+}
+
+/// How a journaled turn ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnStatus {
+    Completed,
+    Failed,
+    Cancelled,
+    /// Interrupted by a prompt that took its place.
+    Steered,
+}
+
+/// One turn in the session journal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Codex submission id of the turn.
+    pub submit_id: String,
+    /// Seconds since the Unix epoch when the turn finished.
+    pub finished_at: u64,
+    /// `provider@model` active for the turn, if known.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Text of the user prompt (non-text inputs are summarized).
+    pub prompt: String,
+    /// Agent message text streamed to the client, possibly partial.
+    pub output: String,
+    pub status: TurnStatus,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Seed requested for the turn (`PromptRequest.meta.seed`) or configured for the session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Commands that finished during the turn.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<CommandRecord>,
+}
+
+/// Directory-backed store of `SessionRecord`s keyed by ACP session id.
+#[derive(Debug, Clone)]
+pub struct RecordStore {
+    dir: PathBuf,
+}
+
+impl RecordStore {
+    pub fn new(codex_home: &Path) -> Self {
+        Self {
+            dir: codex_home.join("acp").join("sessions"),
+        }
+    }
+
+    fn record_path(&self, session_id: &str) -> io::Result<PathBuf> {
+        validate_session_id(session_id)?;
+        Ok(self.dir.join(format!("{session_id}.json")))
+    }
+
+    fn journal_path(&self, session_id: &str) -> io::Result<PathBuf> {
+        validate_session_id(session_id)?;
+        Ok(self.dir.join(format!("{session_id}.journal.jsonl")))
+    }
+
+    /// Load the record for a session; missing or unreadable records yield `None`.
+    pub async fn load(&self, session_id: &str) -> Option<SessionRecord> {
+        let path = match self.record_path(session_id) {
+            Ok(path) => path,
+            Err(err) => {
+                warn!(error = %err, "not loading session record");
+                return None;
+            }
+        };
+        let bytes = tokio::fs::read(&path).await.ok()?;
+        match serde_json::from_slice(&bytes) {
+            Ok(record) => Some(record),
+            Err(err) => {
+                warn!(path = %path.display(), error = %err, "ignoring malformed session record");
+                None
+            }
+        }
+    }
+
+    /// Persist the record for a session, creating the store directory if needed.
+    pub async fn save(&self, session_id: &str, record: &SessionRecord) -> io::Result<()> {
+        let path = self.record_path(session_id)?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let json = serde_json::to_vec_pretty(record)?;
+        tokio::fs::write(path, json).await
+    }
+
+    /// Load, modify, and save a session record in one step.
+    pub async fn update<F>(&self, session_id: &str, f: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut SessionRecord),
+    {
+        let mut record = self.load(session_id).await.unwrap_or_default();
+        f(&mut record);
+        self.save(session_id, &record).await
+    }
+
+    /// Append a finished turn to the session journal, dropping the oldest
+    /// turns beyond [`MAX_JOURNAL_ENTRIES`].
+    pub async fn append_journal(&self, session_id: &str, entry: &JournalEntry) -> io::Result<()> {
+        let path = self.journal_path(session_id)?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let mut entry = entry.clone();
+        entry.prompt = text::elide_tail(&entry.prompt, MAX_JOURNAL_TEXT);
+        entry.output = text::elide_head(&entry.output, MAX_JOURNAL_TEXT);
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        file.write_all(&line).await?;
+        drop(file);
+        self.trim_journal(&path).await
+    }
+
+    /// Keep only the newest [`MAX_JOURNAL_ENTRIES`] lines of a journal.
+    async fn trim_journal(&self, path: &Path) -> io::Result<()> {
+        let journal = tokio::fs::read_to_string(path).await?;
+        let lines: Vec<&str> = journal.lines().filter(|l| !l.trim().is_empty()).collect();
+        if lines.len() <= MAX_JOURNAL_ENTRIES {
+            return Ok(());
+        }
+        let mut kept = lines[lines.len() - MAX_JOURNAL_ENTRIES..].join("\n");
+        kept.push('\n');
+        let tmp = path.with_extension("jsonl.tmp");
+        tokio::fs::write(&tmp, kept).await?;
+        tokio::fs::rename(&tmp, path).await
+    }
+
+    /// Read the session journal, skipping malformed lines.
+    pub async fn load_journal(&self, session_id: &str) -> Vec<JournalEntry> {
+        let Ok(path) = self.journal_path(session_id) else {
+            return Vec::new();
+        };
+        let Ok(text) = tokio::fs::read_to_string(path).await else {
+            return Vec::new();
+        };
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(entry) => Some(entry),
+                Err(err) => {
+                    warn!(error = %err, "ignoring malformed journal entry");
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Refuse session ids that could name a file outside the store directory.
+pub fn validate_session_id(session_id: &str) -> io::Result<()> {
+    let valid = !session_id.is_empty()
+        && session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid session id {session_id:?}"),
+        ))
+    }
+}
@@ -0,0 +1,218 @@
+//! Running commands in client-managed terminals.
+//!
+//! Terminals are created through ACP `terminal/create`, so the client shows
+//! live output and, where it supports it, lets the user type into the
+//! process. The agent waits for exit, collects the output, and releases the
+//! terminal.
+//!
+//! Besides interactive commands, commands the user approves run here too when
+//! the client supports terminals, so long builds and tests scroll live in the
+//! IDE. Codex's own execution is declined and the output is passed back to the
+//! model as user input. Set `CODEX_ACP_CLIENT_TERMINAL_EXEC=0` to let Codex
+//! run approved commands itself.
+
+use std::{path::Path, time::Instant};
+
+use agent_client_protocol::{
+    CreateTerminalRequest, Error, SessionId, SessionUpdate, TerminalId, ToolCall, ToolCallContent,
+    ToolCallId, ToolCallStatus, ToolCallUpdate, ToolCallUpdateFields, ToolKind,
+};
+use codex_core::{
+    CodexConversation,
+    protocol::{Op, ReviewDecision},
+};
+use codex_protocol::user_input::UserInput;
+use serde_json::json;
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use super::{
+    approvals::PendingExecApproval, command_history, core::CodexAgent, interactive, redact, utils,
+};
+use crate::fs::{ClientOp, TerminalRun};
+
+/// Output cap requested from the client for terminal runs.
+const TERMINAL_OUTPUT_LIMIT: u64 = 256 * 1024;
+
+/// Bytes of terminal output passed back to the model.
+pub const MAX_REPORTED_OUTPUT: usize = 16 * 1024;
+
+/// Environment variable disabling client-terminal execution of approved commands (`0`/`false`).
+pub const TERMINAL_EXEC_ENV: &str = "CODEX_ACP_CLIENT_TERMINAL_EXEC";
+
+/// Whether approved commands run in client terminals when the client supports them.
+pub fn terminal_exec_enabled() -> bool {
+    std::env::var(TERMINAL_EXEC_ENV)
+        .map(|v| !matches!(v.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no"))
+        .unwrap_or(true)
+}
+
+impl CodexAgent {
+    /// Run `command` in a client terminal shown as tool call `call_id`, and wait for it to exit.
+    pub(super) async fn run_in_client_terminal(
+        &self,
+        session_id: &SessionId,
+        call_id: &str,
+        title: String,
+        command: &[String],
+        cwd: &Path,
+    ) -> Result<TerminalRun, Error> {
+        let (program, args) = command
+            .split_first()
+            .ok_or_else(|| Error::invalid_params().with_data("empty command"))?;
+
+        let (tx, rx) = oneshot::channel();
+        self.client_tx
+            .send(ClientOp::CreateTerminal {
+                session_id: session_id.clone(),
+                request: CreateTerminalRequest {
+                    session_id: session_id.clone(),
+                    command: program.clone(),
+                    args: args.to_vec(),
+                    env: Vec::new(),
+                    cwd: Some(cwd.to_path_buf()),
+                    output_byte_limit: Some(TERMINAL_OUTPUT_LIMIT),
+                    meta: None,
+                },
+                response_tx: tx,
+            })
+            .map_err(Error::into_internal_error)?;
+        let terminal_id = rx.await.map_err(Error::into_internal_error)??.terminal_id;
+        let started = Instant::now();
+
+        self.send_session_update(
+            session_id,
+            SessionUpdate::ToolCall(ToolCall {
+                id: ToolCallId(call_id.into()),
+                title,
+                kind: ToolKind::Execute,
+                status: ToolCallStatus::InProgress,
+                content: vec![ToolCallContent::Terminal {
+                    terminal_id: terminal_id.clone(),
+                }],
+                locations: vec![],
+                raw_input: Some(json!({ "command": redact::redact_command(command), "cwd": cwd })),
+                raw_output: None,
+                meta: Some(json!({
+                    "terminal_info": { "terminal_id": terminal_id.0, "cwd": cwd }
+                })),
+            }),
+        )
+        .await?;
+
+        let run = self
+            .wait_for_client_terminal(session_id, terminal_id)
+            .await?;
+        self.record_command(
+            session_id,
+            command_history::new_record(
+                command,
+                cwd.to_path_buf(),
+                run.exit_code.map_or(-1, |code| code as i32),
+                started.elapsed().as_millis() as u64,
+                &run.output,
+            ),
+        );
+
+        let status = if run.success() {
+            ToolCallStatus::Completed
+        } else {
+            ToolCallStatus::Failed
+        };
+        self.send_session_update(
+            session_id,
+            SessionUpdate::ToolCallUpdate(ToolCallUpdate {
+                id: ToolCallId(call_id.into()),
+                fields: ToolCallUpdateFields {
+                    status: Some(status),
+                    raw_output: Some(json!({
+                        "exit_code": run.exit_code,
+                        "signal": run.signal,
+                        "truncated": run.truncated,
+                    })),
+                    ..Default::default()
+                },
+                meta: None,
+            }),
+        )
+        .await?;
+        Ok(run)
+    }
+
+    /// Run an approved command in a client terminal instead of letting Codex run it.
+    ///
+    /// The output is queued as user input for the model and Codex's execution
+    /// is declined. If no terminal can be created, Codex runs the command
+    /// with the user's `decision` as usual.
+    pub(super) async fn run_approved_in_terminal(
+        &self,
+        session_id: &SessionId,
+        conversation: &CodexConversation,
+        pending: &PendingExecApproval,
+        decision: ReviewDecision,
+    ) -> Result<(), Error> {
+        let display = interactive::command_words(&pending.command).join(" ");
+        let title = utils::format_command_call(
+            &pending.cwd,
+            std::slice::from_ref(&pending.cwd),
+            &pending.parsed_cmd,
+        )
+        .title;
+        info!(command = %display, "running approved command in client terminal");
+
+        let decision = match self
+            .run_in_client_terminal(
+                session_id,
+                &pending.call_id,
+                title,
+                &pending.command,
+                &pending.cwd,
+            )
+            .await
+        {
+            Ok(run) => {
+                let report = format!(
+                    "The approved command `{display}` was run in the user's terminal ({}). Do not run it again. Output:\n{}",
+                    run.describe_exit(),
+                    run.output_tail(MAX_REPORTED_OUTPUT)
+                );
+                // Codex queues input submitted during a running turn for the model's next step.
+                conversation
+                    .submit(Op::UserInput {
+                        items: vec![UserInput::Text { text: report }],
+                    })
+                    .await
+                    .map_err(Error::into_internal_error)?;
+                ReviewDecision::Denied
+            }
+            Err(err) => {
+                warn!(error = ?err, "client terminal unavailable, letting Codex run the command");
+                decision
+            }
+        };
+        conversation
+            .submit(Op::ExecApproval {
+                id: pending.event_id.clone(),
+                decision,
+            })
+            .await
+            .map_err(Error::into_internal_error)?;
+        Ok(())
+    }
+
+    async fn wait_for_client_terminal(
+        &self,
+        session_id: &SessionId,
+        terminal_id: TerminalId,
+    ) -> Result<TerminalRun, Error> {
+        let (tx, rx) = oneshot::channel();
+        self.client_tx
+            .send(ClientOp::WaitForTerminal {
+                session_id: session_id.clone(),
+                terminal_id,
+                response_tx: tx,
+            })
+            .map_err(Error::into_internal_error)?;
+        rx.await.map_err(Error::into_internal_error)?
+    }
+}
@@ -0,0 +1,58 @@
+#![cfg(test)]
+
+use std::path::PathBuf;
+
+use crate::agent::acp_config::AcpConfig;
+
+#[test]
+fn parses_the_acp_section() {
+    let config = AcpConfig::parse(
+        r#"
+model = "gpt-5"
+
+[acp]
+max_session_tokens = 2_000_000
+
+[acp.shell]
+login_shell = true
+env_file = "/home/me/dev.env"
+
+[acp.logging]
+level = "debug"
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config,
+        AcpConfig {
+            max_session_tokens: Some(2_000_000),
+            login_shell: Some(true),
+            env_file: Some(PathBuf::from("/home/me/dev.env")),
+        }
+    );
+
+    assert_eq!(
+        AcpConfig::parse("model = \"gpt-5\"\n").unwrap(),
+        AcpConfig::default()
+    );
+    assert_eq!(
+        AcpConfig::parse("[acp.logging]\nlevel = \"info\"\n").unwrap(),
+        AcpConfig::default()
+    );
+}
+
+#[test]
+fn rejects_invalid_values() {
+    assert!(AcpConfig::parse("[acp]\nmax_session_tokens = 0\n").is_err());
+    assert!(AcpConfig::parse("[acp]\nmax_session_tokens = -5\n").is_err());
+    assert!(AcpConfig::parse("[acp]\nmax_session_tokens = \"lots\"\n").is_err());
+    assert!(AcpConfig::parse("[acp]\nshell = \"zsh\"\n").is_err());
+    assert!(AcpConfig::parse("[acp.shell]\nlogin_shell = \"yes\"\n").is_err());
+    assert!(AcpConfig::parse("[acp.shell]\nenv_file = 1\n").is_err());
+}
+
+#[tokio::test]
+async fn missing_config_file_gives_defaults() {
+    let home = std::env::temp_dir().join(format!("codex-acp-config-{}", uuid::Uuid::new_v4()));
+    assert_eq!(AcpConfig::load(&home).await, AcpConfig::default());
+}
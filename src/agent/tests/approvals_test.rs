@@ -0,0 +1,135 @@
+#![cfg(test)]
+
+use agent_client_protocol::{
+    CancelNotification, PermissionOptionId, RequestPermissionOutcome, RequestPermissionRequest,
+    RequestPermissionResponse, SessionId, ToolCallId, ToolCallUpdate, ToolCallUpdateFields,
+};
+use tokio::task::{self, LocalSet};
+
+use super::support::TestAgent;
+use crate::{
+    agent::approvals::{DigestOutcome, digest_outcome, is_low_risk},
+    fs::ClientOp,
+};
+
+fn argv(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+/// Inspection and build commands are batchable; anything else is not.
+#[test]
+fn low_risk_commands() {
+    assert!(is_low_risk(&argv(&["cargo", "check"])));
+    assert!(is_low_risk(&argv(&["bash", "-lc", "git diff --stat"])));
+    assert!(is_low_risk(&argv(&["rg", "-n", "TODO", "src"])));
+    assert!(!is_low_risk(&argv(&["cargo", "publish"])));
+    assert!(!is_low_risk(&argv(&["rm", "-rf", "target"])));
+    assert!(!is_low_risk(&argv(&[
+        "find", ".", "-name", "*.tmp", "-delete"
+    ])));
+    assert!(!is_low_risk(&argv(&["bash", "-lc", "cat a > b"])));
+    assert!(!is_low_risk(&argv(&["bash", "-lc", "cargo check && rm x"])));
+}
+
+/// Digest answers map to outcomes; unknown options and cancellation reject all.
+#[test]
+fn digest_outcomes() {
+    let pick = |id: &str| {
+        digest_outcome(RequestPermissionResponse {
+            outcome: RequestPermissionOutcome::Selected {
+                option_id: PermissionOptionId(id.into()),
+            },
+            meta: None,
+        })
+    };
+    assert_eq!(pick("approve-all"), DigestOutcome::ApproveAll);
+    assert_eq!(
+        pick("approve-all-for-session"),
+        DigestOutcome::ApproveAllForSession
+    );
+    assert_eq!(pick("review-individually"), DigestOutcome::Individually);
+    assert_eq!(pick("something-else"), DigestOutcome::RejectAll);
+    assert_eq!(
+        digest_outcome(RequestPermissionResponse {
+            outcome: RequestPermissionOutcome::Cancelled,
+            meta: None,
+        }),
+        DigestOutcome::RejectAll
+    );
+}
+
+fn permission_request(session_id: &SessionId) -> RequestPermissionRequest {
+    RequestPermissionRequest {
+        session_id: session_id.clone(),
+        tool_call: ToolCallUpdate {
+            id: ToolCallId("call-1".into()),
+            fields: ToolCallUpdateFields::default(),
+            meta: None,
+        },
+        options: Vec::new(),
+        meta: None,
+    }
+}
+
+/// `session/cancel` answers the session's pending permission requests as
+/// cancelled even though the client never does; other sessions keep waiting.
+#[tokio::test]
+async fn cancel_releases_pending_permission_requests() {
+    LocalSet::new()
+        .run_until(async {
+            let mut test = TestAgent::start();
+            let mut pending = Vec::new();
+            let mut answers = Vec::new();
+            for id in ["session-a", "session-b"] {
+                let session_id = test.add_session(id);
+                let agent = test.agent.clone();
+                pending.push(task::spawn_local(async move {
+                    let request = permission_request(&session_id);
+                    agent.request_turn_permission(&session_id, request).await
+                }));
+                let Some(ClientOp::RequestPermission { response_tx, .. }) =
+                    test.client_rx.recv().await
+                else {
+                    panic!("expected a permission request");
+                };
+                answers.push(response_tx);
+            }
+            let (pending_b, pending_a) = (pending.pop().unwrap(), pending.pop().unwrap());
+
+            // Without a Codex conversation the interrupt itself fails; the
+            // approvals are released before that.
+            let _ = test
+                .agent
+                .cancel(CancelNotification {
+                    session_id: SessionId("session-a".into()),
+                    meta: None,
+                })
+                .await;
+            let outcome = pending_a
+                .await
+                .expect("task")
+                .expect("request")
+                .expect("response");
+            assert!(matches!(outcome.outcome, RequestPermissionOutcome::Cancelled));
+
+            task::yield_now().await;
+            assert!(!pending_b.is_finished());
+            let answer_b = answers.pop().unwrap();
+            let _ = answer_b.send(Ok(RequestPermissionResponse {
+                outcome: RequestPermissionOutcome::Selected {
+                    option_id: PermissionOptionId("approved".into()),
+                },
+                meta: None,
+            }));
+            let outcome = pending_b
+                .await
+                .expect("task")
+                .expect("request")
+                .expect("response");
+            assert!(matches!(
+                outcome.outcome,
+                RequestPermissionOutcome::Selected { option_id } if option_id.0.as_ref() == "approved"
+            ));
+        })
+        .await;
+}
@@ -0,0 +1,95 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    agent::audit::command_entry,
+    fs::tools::audit::{AuditEntry, AuditEvent, AuditLog, GENESIS_HASH, verify_chain},
+};
+
+fn temp_home() -> PathBuf {
+    std::env::temp_dir().join(format!("codex-acp-audit-{}", uuid::Uuid::new_v4()))
+}
+
+fn write(session: &str, path: &str, bytes: u64) -> AuditEntry {
+    let mut entry = AuditEntry::new(session, AuditEvent::Write).with_decision("auto");
+    entry.paths = vec![PathBuf::from(path)];
+    entry.bytes = Some(bytes);
+    entry
+}
+
+/// Entries are chained in order, also across a reopened log.
+#[tokio::test]
+async fn appends_a_verifiable_chain() {
+    let home = temp_home();
+    let log = AuditLog::new(&home, true);
+    log.record(write("s1", "a.txt", 3));
+    log.record(command_entry(
+        "s2",
+        AuditEvent::Exec,
+        &["ls".to_string(), "-la".to_string()],
+        Path::new("/work"),
+    ));
+    AuditLog::new(&home, true).record(write("s1", "b.txt", 5));
+
+    let entries = log.entries().await.expect("read log");
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].prev_hash, GENESIS_HASH);
+    assert_eq!(entries[2].seq, 2);
+    assert_eq!(entries[2].prev_hash, entries[1].hash);
+    assert_eq!(entries[1].command.as_deref(), Some("ls -la"));
+    assert_eq!(verify_chain(&entries), None);
+
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+/// Editing or dropping an entry is detected at that entry.
+#[test]
+fn detects_tampering() {
+    let mut prev = GENESIS_HASH.to_string();
+    let mut entries: Vec<AuditEntry> = (0..3)
+        .map(|seq| {
+            let mut entry = write("s1", "a.txt", seq);
+            entry.seq = seq;
+            entry.prev_hash = prev.clone();
+            entry.hash = entry.compute_hash();
+            prev = entry.hash.clone();
+            entry
+        })
+        .collect();
+    assert_eq!(verify_chain(&entries), None);
+
+    let mut edited = entries.clone();
+    edited[1].bytes = Some(999);
+    assert_eq!(verify_chain(&edited), Some(1));
+
+    entries.remove(0);
+    assert_eq!(verify_chain(&entries), Some(0));
+}
+
+/// Secrets in commands are redacted before they are written.
+#[test]
+fn redacts_commands() {
+    let entry = command_entry(
+        "s1",
+        AuditEvent::ExecDecision,
+        &[
+            "bash".to_string(),
+            "-lc".to_string(),
+            "curl -H \"Authorization: Bearer abc.def.ghi\" https://api.example.com".to_string(),
+        ],
+        Path::new("/work"),
+    );
+    let command = entry.command.expect("command");
+    assert!(!command.contains("abc.def.ghi"), "{command}");
+}
+
+/// A disabled log writes nothing.
+#[tokio::test]
+async fn disabled_log_is_empty() {
+    let home = temp_home();
+    let log = AuditLog::new(&home, false);
+    log.record(write("s1", "a.txt", 3));
+    assert!(log.entries().await.expect("read log").is_empty());
+    assert!(!home.exists());
+}
@@ -0,0 +1,51 @@
+#![cfg(test)]
+
+use agent_client_protocol::{ContentChunk, SessionId, SessionNotification, SessionUpdate};
+use serde_json::json;
+
+use crate::agent::backfill::{Backlog, MAX_BACKFILL, mark_backfill};
+
+fn chunk(text: &str) -> SessionNotification {
+    SessionNotification {
+        session_id: SessionId("s".into()),
+        update: SessionUpdate::AgentMessageChunk(ContentChunk {
+            content: text.to_string().into(),
+            meta: None,
+        }),
+        meta: None,
+    }
+}
+
+#[test]
+fn keeps_the_newest_updates() {
+    let mut backlog = Backlog::default();
+    for i in 0..MAX_BACKFILL + 3 {
+        backlog.push(chunk(&i.to_string()));
+    }
+    let (kept, dropped) = backlog.take();
+    assert_eq!(kept.len(), MAX_BACKFILL);
+    assert_eq!(dropped, 3);
+    assert_eq!(
+        serde_json::to_value(&kept[0]).unwrap(),
+        serde_json::to_value(chunk("3")).unwrap()
+    );
+
+    let (kept, dropped) = backlog.take();
+    assert!(kept.is_empty());
+    assert_eq!(dropped, 0);
+}
+
+#[test]
+fn marks_replayed_updates_as_backfill() {
+    assert_eq!(
+        mark_backfill(chunk("a")).meta,
+        Some(json!({"backfill": true}))
+    );
+
+    let mut with_meta = chunk("a");
+    with_meta.meta = Some(json!({"turn": 2}));
+    assert_eq!(
+        mark_backfill(with_meta).meta,
+        Some(json!({"turn": 2, "backfill": true}))
+    );
+}
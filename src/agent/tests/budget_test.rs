@@ -0,0 +1,43 @@
+#![cfg(test)]
+
+use serde_json::json;
+
+use crate::agent::budget::{
+    CapOverride, TOKEN_CAP_ERROR_CODE, max_session_tokens_from_meta, parse_override,
+    token_cap_error,
+};
+
+/// `/override` lifts the cap without arguments and extends it with a count.
+#[test]
+fn parse_override_args() {
+    assert_eq!(parse_override(""), Ok(CapOverride::Lift));
+    assert_eq!(parse_override("off"), Ok(CapOverride::Lift));
+    assert_eq!(parse_override("50_000"), Ok(CapOverride::Extend(50_000)));
+    assert!(parse_override("0").is_err());
+    assert!(parse_override("lots").is_err());
+}
+
+/// Session meta must carry a positive integer cap.
+#[test]
+fn max_session_tokens_meta() {
+    let meta = json!({ "maxSessionTokens": 200000 });
+    assert_eq!(
+        max_session_tokens_from_meta(Some(&meta)).unwrap(),
+        Some(200_000)
+    );
+    assert_eq!(
+        max_session_tokens_from_meta(Some(&json!({}))).unwrap(),
+        None
+    );
+    assert!(max_session_tokens_from_meta(Some(&json!({ "maxSessionTokens": -1 }))).is_err());
+}
+
+/// The cap error uses its own code and reports usage against the limit.
+#[test]
+fn token_cap_error_shape() {
+    let err = token_cap_error(120, 100);
+    assert_eq!(err.code, TOKEN_CAP_ERROR_CODE);
+    let data = err.data.expect("data");
+    assert_eq!(data["used"], 120);
+    assert_eq!(data["limit"], 100);
+}
@@ -0,0 +1,46 @@
+#![cfg(test)]
+
+use crate::agent::checkpoints::{
+    checkpoint_ref, parse_log, parse_name_status, parse_rollback_args,
+};
+
+#[test]
+fn session_refs_are_valid_ref_names() {
+    assert_eq!(
+        checkpoint_ref("0b5c-41d2_x"),
+        "refs/codex/checkpoints/0b5c-41d2_x"
+    );
+    assert_eq!(checkpoint_ref("a/b:c d"), "refs/codex/checkpoints/a-b-c-d");
+}
+
+/// `git log` lists newest first; checkpoints are numbered from the oldest.
+#[test]
+fn numbers_checkpoints_from_the_oldest() {
+    let log = "c3\t1 minute ago\tBefore rollback to #1\n\
+               c2\t5 minutes ago\tAfter turn: 1 added, 0 modified, 0 deleted\n\
+               c1\t9 minutes ago\tSession start\n";
+    let checkpoints = parse_log(log);
+    assert_eq!(checkpoints.len(), 3);
+    assert_eq!(checkpoints[0].number, 1);
+    assert_eq!(checkpoints[0].commit, "c1");
+    assert_eq!(checkpoints[0].message, "Session start");
+    assert_eq!(checkpoints[2].number, 3);
+    assert_eq!(checkpoints[2].age, "1 minute ago");
+    assert!(parse_log("").is_empty());
+}
+
+#[test]
+fn splits_restores_from_deletions() {
+    let (checkout, delete) = parse_name_status("M\0src/a.rs\0D\0new file.rs\0A\0gone.rs\0");
+    assert_eq!(checkout, vec!["src/a.rs", "gone.rs"]);
+    assert_eq!(delete, vec!["new file.rs"]);
+}
+
+#[test]
+fn parses_rollback_arguments() {
+    assert_eq!(parse_rollback_args(""), Ok(None));
+    assert_eq!(parse_rollback_args(" 2 "), Ok(Some(2)));
+    assert_eq!(parse_rollback_args("#3"), Ok(Some(3)));
+    assert!(parse_rollback_args("0").is_err());
+    assert!(parse_rollback_args("last").is_err());
+}
@@ -0,0 +1,109 @@
+#![cfg(test)]
+
+use std::path::PathBuf;
+
+use crate::{
+    agent::{
+        command_history::{
+            MAX_COMMAND_HISTORY, MAX_RECORDED_OUTPUT, history_from_journal, new_record,
+        },
+        store::{JournalEntry, TurnStatus},
+    },
+    fs::tools::command_history::{CommandHistoryArgs, CommandRecord, format_history},
+};
+
+fn record(command: &str, exit_code: i32, output: &str) -> CommandRecord {
+    new_record(
+        &[command.to_string()],
+        PathBuf::from("/repo"),
+        exit_code,
+        1200,
+        output,
+    )
+}
+
+/// Shell wrappers are unwrapped and only the output tail is kept.
+#[test]
+fn records_command_and_output_tail() {
+    let wrapped = new_record(
+        &["bash".into(), "-lc".into(), "cargo test".into()],
+        PathBuf::from("/repo"),
+        101,
+        5000,
+        &"x".repeat(MAX_RECORDED_OUTPUT + 100),
+    );
+    assert_eq!(wrapped.command, "cargo test");
+    assert!(wrapped.output.starts_with('…'));
+    assert_eq!(
+        wrapped.output.trim_start_matches('…').len(),
+        MAX_RECORDED_OUTPUT
+    );
+}
+
+/// The latest commands are listed with status, and failures can be filtered.
+#[test]
+fn formats_recent_and_failed_commands() {
+    let records = vec![
+        record("cargo build", 0, "Finished"),
+        record("cargo test", 101, "test foo ... FAILED"),
+        record("git status", 0, ""),
+    ];
+
+    let all = format_history(&records, &CommandHistoryArgs::default());
+    assert!(all.starts_with("3 of 3 command(s):"));
+    assert!(
+        all.contains("$ cargo test\n  cwd: /repo · exit 101 · 1200 ms\n  | test foo ... FAILED\n")
+    );
+
+    let latest = format_history(
+        &records,
+        &CommandHistoryArgs {
+            limit: Some(1),
+            failed_only: false,
+        },
+    );
+    assert!(latest.starts_with("1 of 3 command(s):"));
+    assert!(latest.contains("$ git status"));
+    assert!(!latest.contains("cargo build"));
+
+    let failed = format_history(
+        &records,
+        &CommandHistoryArgs {
+            limit: None,
+            failed_only: true,
+        },
+    );
+    assert!(failed.starts_with("1 of 1 command(s):"));
+    assert!(failed.contains("$ cargo test"));
+
+    assert_eq!(
+        format_history(&[], &CommandHistoryArgs::default()),
+        "No commands have run in this session yet."
+    );
+}
+
+/// History is rebuilt from the journal, keeping the most recent commands.
+#[test]
+fn rebuilds_history_from_journal() {
+    let entry = |commands: Vec<CommandRecord>| JournalEntry {
+        submit_id: "1".into(),
+        finished_at: 0,
+        model: None,
+        prompt: String::new(),
+        output: String::new(),
+        status: TurnStatus::Completed,
+        error: None,
+        seed: None,
+        commands,
+    };
+    let many: Vec<CommandRecord> = (0..MAX_COMMAND_HISTORY)
+        .map(|i| record(&format!("echo {i}"), 0, ""))
+        .collect();
+    let history = history_from_journal(vec![entry(vec![record("first", 0, "")]), entry(many)]);
+    assert_eq!(history.len(), MAX_COMMAND_HISTORY);
+    assert_eq!(history[0].command, "echo 0");
+    assert_eq!(
+        history.last().unwrap().command,
+        format!("echo {}", MAX_COMMAND_HISTORY - 1)
+    );
+}
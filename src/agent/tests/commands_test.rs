@@ -0,0 +1,95 @@
+#![cfg(test)]
+
+use std::collections::HashSet;
+
+use crate::agent::{
+    commands::{AVAILABLE_COMMANDS, BUILTIN_COMMANDS, render_help, session_commands},
+    custom_prompts::CustomPrompt,
+    mcp_commands::server_commands,
+};
+
+/// Every advertised command should carry typed palette metadata.
+#[test]
+fn available_commands_carry_meta() {
+    for cmd in AVAILABLE_COMMANDS.iter() {
+        let meta = cmd
+            .meta
+            .as_ref()
+            .unwrap_or_else(|| panic!("missing meta for /{}", cmd.name));
+        assert!(meta.get("category").and_then(|v| v.as_str()).is_some());
+        assert!(meta.get("icon").and_then(|v| v.as_str()).is_some());
+        assert!(
+            meta.get("expects_followup")
+                .and_then(|v| v.as_bool())
+                .is_some()
+        );
+    }
+}
+
+/// Command names in the registry should be unique.
+#[test]
+fn builtin_command_names_unique() {
+    let mut seen = HashSet::new();
+    for spec in BUILTIN_COMMANDS {
+        assert!(seen.insert(spec.name), "duplicate command /{}", spec.name);
+    }
+}
+
+/// `/help` without a topic should list every registered command.
+#[test]
+fn help_overview_lists_all_commands() {
+    let prompts = [CustomPrompt::parse(
+        "changelog",
+        "---\ndescription: draft a changelog entry\n---\nWrite it.",
+    )];
+    let mcp_commands = server_commands(&["docs".to_string(), "acp_fs".to_string()]);
+    let out = render_help(None, &prompts, &mcp_commands);
+    for cmd in session_commands(&prompts, &mcp_commands) {
+        assert!(
+            out.contains(cmd.name.as_str()),
+            "missing /{} in help",
+            cmd.name
+        );
+    }
+    assert!(out.contains("draft a changelog entry"));
+    assert!(out.contains("mcp:docs"));
+    assert!(!out.contains("mcp:acp_fs"));
+    assert!(!render_help(None, &[], &[]).contains("Custom prompts"));
+    assert!(!render_help(None, &[], &[]).contains("MCP servers"));
+}
+
+/// `/help <command>` should include usage and examples for that command.
+#[test]
+fn help_for_single_command_includes_usage() {
+    let out = render_help(Some("help"), &[], &[]);
+    assert!(out.contains("/help [command]"));
+    assert!(out.contains("/help review"));
+
+    let unknown = render_help(Some("/nope"), &[], &[]);
+    assert!(unknown.starts_with("Unknown command `/nope`"));
+
+    let prompts = [CustomPrompt::parse(
+        "fix",
+        "---\nargument-hint: <issue>\n---\nFix $1.",
+    )];
+    let out = render_help(Some("/prompts:fix"), &prompts, &[]);
+    assert!(out.contains("/prompts:fix <issue>"));
+
+    let mcp_commands = server_commands(&["Docs".to_string()]);
+    let out = render_help(Some("/mcp:docs"), &[], &mcp_commands);
+    assert!(out.contains("/mcp:Docs [task]"));
+}
+
+/// `/retry` accepts nothing or `with <modifier>`.
+#[test]
+fn parse_retry_modifier_variants() {
+    use crate::agent::commands::parse_retry_modifier;
+
+    assert_eq!(parse_retry_modifier(""), Ok(None));
+    assert_eq!(
+        parse_retry_modifier("with  be brief "),
+        Ok(Some("be brief".to_string()))
+    );
+    assert!(parse_retry_modifier("with").is_err());
+    assert!(parse_retry_modifier("please").is_err());
+}
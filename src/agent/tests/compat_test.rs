@@ -0,0 +1,77 @@
+#![cfg(all(test, feature = "acp-compat"))]
+
+use agent_client_protocol::{
+    ContentBlock, ContentChunk, CurrentModeUpdate, SessionId, SessionModeId, SessionNotification,
+    SessionUpdate, TextContent,
+};
+use serde_json::json;
+
+use crate::agent::compat::{ProtocolCompat, requests_legacy};
+
+fn chunk(text: &str) -> SessionNotification {
+    SessionNotification {
+        session_id: SessionId("s1".into()),
+        update: SessionUpdate::AgentMessageChunk(ContentChunk {
+            content: ContentBlock::Text(TextContent {
+                annotations: None,
+                text: text.to_string(),
+                meta: Some(json!({ "format": "markdown" })),
+            }),
+            meta: Some(json!({ "seq": 1 })),
+        }),
+        meta: Some(json!({ "trace": "x" })),
+    }
+}
+
+fn mode_update() -> SessionNotification {
+    SessionNotification {
+        session_id: SessionId("s1".into()),
+        update: SessionUpdate::CurrentModeUpdate(CurrentModeUpdate {
+            current_mode_id: SessionModeId("read-only".into()),
+            meta: None,
+        }),
+        meta: None,
+    }
+}
+
+#[test]
+fn legacy_shape_is_requested_through_meta() {
+    let legacy = json!({ "acpCompat": "0.4" });
+    assert!(requests_legacy(Some(&legacy), None));
+    assert!(requests_legacy(None, Some(&legacy)));
+    assert!(!requests_legacy(Some(&json!({ "acpCompat": "0.5" })), None));
+    assert!(!requests_legacy(Some(&json!({})), None));
+    assert!(!requests_legacy(None, None));
+}
+
+/// Legacy clients get no `_meta` and no mode updates.
+#[test]
+fn legacy_shape_strips_newer_fields() {
+    let compat = ProtocolCompat::new(true);
+    let mut notification = chunk("hi");
+    assert!(compat.notification(&mut notification));
+    assert_eq!(notification.meta, None);
+    let SessionUpdate::AgentMessageChunk(chunk) = &notification.update else {
+        panic!("update kind changed");
+    };
+    assert_eq!(chunk.meta, None);
+    let ContentBlock::Text(text) = &chunk.content else {
+        panic!("content kind changed");
+    };
+    assert_eq!(text.meta, None);
+    assert_eq!(text.text, "hi");
+
+    assert!(!compat.notification(&mut mode_update()));
+}
+
+#[test]
+fn current_shape_is_left_alone() {
+    let compat = ProtocolCompat::new(false);
+    let mut notification = chunk("hi");
+    assert!(compat.notification(&mut notification));
+    assert_eq!(
+        serde_json::to_value(&notification).expect("serialize"),
+        serde_json::to_value(chunk("hi")).expect("serialize")
+    );
+    assert!(compat.notification(&mut mode_update()));
+}
@@ -0,0 +1,158 @@
+#![cfg(test)]
+
+use agent_client_protocol::{Error, ExtRequest, ExtResponse, SessionId};
+use codex_protocol::user_input::UserInput;
+use serde_json::{Value, json};
+use tokio::task::LocalSet;
+
+use super::support::TestAgent;
+use crate::agent::ext::MAX_PENDING_CONTEXT_ITEMS;
+
+/// Text of the items the next turn would be prefixed with.
+async fn context_texts(test: &TestAgent, session_id: &SessionId) -> Vec<String> {
+    test.agent
+        .context_items(session_id)
+        .await
+        .into_iter()
+        .filter_map(|item| match item {
+            UserInput::Text { text } => Some(text),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Push `items` to a session through `method`.
+async fn add_context(
+    test: &TestAgent,
+    method: &str,
+    session_id: &str,
+    items: Value,
+) -> Result<ExtResponse, Error> {
+    let params = json!({ "sessionId": session_id, "items": items });
+    test.agent
+        .ext_method(ExtRequest {
+            method: method.into(),
+            params: serde_json::value::to_raw_value(&params).unwrap().into(),
+        })
+        .await
+}
+
+/// Attached files are read again for every turn, and one that cannot be read
+/// is reported instead of failing the turn.
+#[tokio::test]
+async fn attachments_are_reread_every_turn() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestAgent::start();
+            let sid = test.add_session("s1");
+            let notes = test.home.join("work").join("notes.md");
+            std::fs::write(&notes, "first").expect("file");
+
+            test.agent
+                .handle_slash_command(&sid, "attach", "notes.md missing.md")
+                .await;
+            assert_eq!(
+                context_texts(&test, &sid).await,
+                vec!["<attached-file path=\"notes.md\">\nfirst\n</attached-file>"]
+            );
+            assert!(
+                test.messages()
+                    .iter()
+                    .any(|text| text.starts_with("⚠️ Could not read attachment missing.md")),
+                "{:?}",
+                test.messages()
+            );
+
+            std::fs::write(&notes, "second").expect("edit");
+            assert_eq!(
+                context_texts(&test, &sid).await,
+                vec!["<attached-file path=\"notes.md\">\nsecond\n</attached-file>"]
+            );
+
+            test.agent.handle_slash_command(&sid, "detach", "").await;
+            assert!(context_texts(&test, &sid).await.is_empty());
+        })
+        .await;
+}
+
+/// Pushed items are rendered in order into the next turn only, under either
+/// spelling of the method name.
+#[tokio::test]
+async fn pushed_context_reaches_the_next_turn_only() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestAgent::start();
+            let sid = test.add_session("s1");
+            std::fs::write(test.home.join("work").join("lib.rs"), "fn main() {}").expect("file");
+
+            let response = add_context(
+                &test,
+                "codex/addContext",
+                "s1",
+                json!([{ "type": "text", "text": "2 failed", "label": "pytest" }]),
+            )
+            .await
+            .expect("queued");
+            assert_eq!(
+                serde_json::to_value(response).unwrap(),
+                json!({ "queued": 1 })
+            );
+            let response = add_context(
+                &test,
+                "_codex/addContext",
+                "s1",
+                json!([
+                    { "type": "file", "path": "lib.rs" },
+                    { "type": "file", "path": "missing.rs" },
+                ]),
+            )
+            .await
+            .expect("queued");
+            assert_eq!(
+                serde_json::to_value(response).unwrap(),
+                json!({ "queued": 3 })
+            );
+
+            // A file that cannot be read is dropped from the turn.
+            assert_eq!(
+                context_texts(&test, &sid).await,
+                vec![
+                    "<context label=\"pytest\">\n2 failed\n</context>",
+                    "<context-file path=\"lib.rs\">\nfn main() {}\n</context-file>",
+                ]
+            );
+            assert!(context_texts(&test, &sid).await.is_empty());
+        })
+        .await;
+}
+
+/// Pushes beyond the pending cap or for unknown sessions are refused.
+#[tokio::test]
+async fn pushed_context_is_bounded() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestAgent::start();
+            let sid = test.add_session("s1");
+            let item = json!({ "type": "text", "text": "note" });
+
+            let full = Value::Array(vec![item.clone(); MAX_PENDING_CONTEXT_ITEMS]);
+            add_context(&test, "codex/addContext", "s1", full)
+                .await
+                .expect("queued");
+            let err = add_context(&test, "codex/addContext", "s1", json!([item]))
+                .await
+                .expect_err("over the cap");
+            assert_eq!(err.code, Error::invalid_params().code);
+            assert_eq!(
+                context_texts(&test, &sid).await.len(),
+                MAX_PENDING_CONTEXT_ITEMS
+            );
+
+            assert!(
+                add_context(&test, "codex/addContext", "missing", json!([item]))
+                    .await
+                    .is_err()
+            );
+        })
+        .await;
+}
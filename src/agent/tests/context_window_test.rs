@@ -0,0 +1,26 @@
+#![cfg(test)]
+
+use crate::agent::context_window::{
+    CONTEXT_WINDOW_ERROR_CODE, context_window_error, is_context_window_error,
+};
+
+/// Codex and provider phrasings of the overflow are recognised.
+#[test]
+fn detects_context_window_errors() {
+    assert!(is_context_window_error(
+        "Codex ran out of room in the model's context window. Start a new conversation or clear earlier history before retrying."
+    ));
+    assert!(is_context_window_error(
+        "This model's maximum context length is 8192 tokens"
+    ));
+    assert!(!is_context_window_error("rate limit exceeded"));
+}
+
+/// The error carries the dedicated code and points at /compact.
+#[test]
+fn context_window_error_offers_compaction() {
+    let err = context_window_error("context window exceeded");
+    assert_eq!(err.code, CONTEXT_WINDOW_ERROR_CODE);
+    let data = err.data.expect("error data");
+    assert!(data["guidance"].as_str().unwrap().contains("/compact"));
+}
@@ -0,0 +1,34 @@
+#![cfg(test)]
+
+use crate::agent::continuation::{
+    MAX_AUTO_CONTINUE, continuation_prompt, output_tail, parse_auto_continue,
+};
+
+/// Counts are capped; booleans mean one continuation or none.
+#[test]
+fn parses_auto_continue() {
+    assert_eq!(parse_auto_continue("3"), Some(3));
+    assert_eq!(parse_auto_continue(" true "), Some(1));
+    assert_eq!(parse_auto_continue("off"), Some(0));
+    assert_eq!(parse_auto_continue("1000"), Some(MAX_AUTO_CONTINUE));
+    assert_eq!(parse_auto_continue("many"), None);
+}
+
+/// The tail keeps the end of the output on a character boundary.
+#[test]
+fn output_tail_keeps_the_end() {
+    assert_eq!(output_tail("short"), "short");
+    let long = format!("{}é{}", "a".repeat(1000), "b".repeat(399));
+    let tail = output_tail(&long);
+    assert_eq!(tail.chars().count(), 400);
+    assert!(tail.starts_with('é'));
+}
+
+/// The prompt quotes where the previous response stopped.
+#[test]
+fn continuation_prompt_quotes_the_tail() {
+    let prompt = continuation_prompt("fn main() {");
+    assert!(prompt.contains("Continue exactly where"));
+    assert!(prompt.ends_with("fn main() {"));
+    assert!(!continuation_prompt("  ").contains("ended with"));
+}
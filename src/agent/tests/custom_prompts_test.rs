@@ -0,0 +1,52 @@
+#![cfg(test)]
+
+use crate::agent::custom_prompts::{CustomPrompt, discover, find_prompt};
+
+/// Front matter sets the description and argument hint and is not part of the prompt.
+#[test]
+fn front_matter_is_parsed() {
+    let prompt = CustomPrompt::parse(
+        "review-pr",
+        "---\ndescription: \"Review a pull request\"\nargument-hint: <number>\n---\nReview PR $1.\n",
+    );
+    assert_eq!(prompt.description.as_deref(), Some("Review a pull request"));
+    assert_eq!(prompt.argument_hint.as_deref(), Some("<number>"));
+    assert_eq!(prompt.body, "Review PR $1.\n");
+    assert_eq!(prompt.command_name(), "prompts:review-pr");
+
+    let plain = CustomPrompt::parse("plain", "Just text.");
+    assert_eq!(plain.description(), "custom prompt");
+    assert_eq!(plain.body, "Just text.");
+}
+
+#[test]
+fn arguments_are_substituted() {
+    let prompt = CustomPrompt::parse("p", "Fix $1 in $2. Context: $ARGUMENTS");
+    assert_eq!(
+        prompt.expand(" parser lexer.rs "),
+        "Fix parser in lexer.rs. Context: parser lexer.rs"
+    );
+    assert_eq!(prompt.expand(""), "Fix  in . Context: ");
+}
+
+/// Only `.md` files are prompts, and command names match in any case.
+#[tokio::test]
+async fn prompts_are_discovered_from_markdown_files() {
+    let dir = std::env::temp_dir().join(format!("codex-acp-prompts-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).expect("mkdir");
+    std::fs::write(dir.join("Zeta.md"), "z").expect("write");
+    std::fs::write(dir.join("alpha.md"), "a").expect("write");
+    std::fs::write(dir.join("notes.txt"), "n").expect("write");
+
+    let prompts = discover(&dir).await;
+    let names: Vec<&str> = prompts.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["Zeta", "alpha"]);
+    assert_eq!(
+        find_prompt(&prompts, "prompts:zeta").map(|p| p.body.as_str()),
+        Some("z")
+    );
+    assert!(find_prompt(&prompts, "zeta").is_none());
+    assert!(discover(&dir.join("missing")).await.is_empty());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,72 @@
+#![cfg(test)]
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::agent::diagnostics::{
+    Diagnostic, Position, Range, collect_diagnostics, diagnostic_path,
+};
+
+fn diagnostic(message: &str) -> Diagnostic {
+    let position = Position {
+        line: 0,
+        character: 0,
+    };
+    Diagnostic {
+        range: Range {
+            start: position.clone(),
+            end: position,
+        },
+        severity: Some(1),
+        message: message.to_string(),
+        source: None,
+        code: None,
+    }
+}
+
+/// Paths, `./` paths and `file://` URIs of the same file share one key.
+#[test]
+fn published_paths_are_normalized_against_cwd() {
+    let cwd = Path::new("/work/app");
+    let expected = "/work/app/src/main.rs";
+    for raw in [
+        "src/main.rs",
+        "./src/main.rs",
+        "src/../src/main.rs",
+        "/work/app/src/main.rs",
+        "file:///work/app/src/main.rs",
+        "file://localhost/work/app/src/./main.rs",
+    ] {
+        assert_eq!(diagnostic_path(cwd, &[], raw), expected, "{raw}");
+    }
+    assert_eq!(
+        diagnostic_path(cwd, &[], "file:///work/my%20lib/a.rs"),
+        "/work/my lib/a.rs"
+    );
+
+    let roots = [PathBuf::from("/work/lib")];
+    assert_eq!(diagnostic_path(cwd, &roots, "lib/a.rs"), "/work/lib/a.rs");
+}
+
+/// A file asked for in any form finds diagnostics published in another.
+#[test]
+fn collect_matches_equivalent_paths() {
+    let cwd = Path::new("/work/app");
+    let mut store = HashMap::new();
+    store.insert(
+        diagnostic_path(cwd, &[], "file:///work/app/src/lib.rs"),
+        vec![diagnostic("mismatched types")],
+    );
+    store.insert("/work/app/src/main.rs".to_string(), Vec::new());
+
+    for filter in ["src/lib.rs", "./src/lib.rs", "/work/app/src/lib.rs"] {
+        let files = collect_diagnostics(&store, Some(filter), cwd, &[]);
+        assert_eq!(files.len(), 1, "{filter}");
+        assert_eq!(files[0].path, "/work/app/src/lib.rs");
+        assert_eq!(files[0].diagnostics[0].message, "mismatched types");
+    }
+    assert!(collect_diagnostics(&store, Some("src/main.rs"), cwd, &[]).is_empty());
+    assert_eq!(collect_diagnostics(&store, None, cwd, &[]).len(), 1);
+}
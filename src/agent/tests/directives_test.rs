@@ -0,0 +1,130 @@
+#![cfg(test)]
+
+use std::time::Duration;
+
+use agent_client_protocol::{Error, SessionModeId};
+use serde_json::json;
+use tokio::task::{self, LocalSet};
+
+use super::support::TestAgent;
+use crate::{
+    agent::{
+        SessionModeLookup,
+        directives::{directive_model_id, parse_directives},
+    },
+    fs::{ClientOp, FsBridge, bridge_client::BridgeClient, tools::bridge_timeouts::BridgeTimeouts},
+};
+
+#[test]
+fn parses_mode_and_model_directives() {
+    let (d, rest) = parse_directives("!ro !m gpt-5 explain this function");
+    assert_eq!(d.mode.as_ref().map(|m| m.0.as_ref()), Some("read-only"));
+    assert_eq!(d.model.as_deref(), Some("gpt-5"));
+    assert_eq!(rest, "explain this function");
+}
+
+#[test]
+fn explicit_mode_directive_takes_value() {
+    let (d, rest) = parse_directives("!mode full-access run the tests");
+    assert_eq!(d.mode.as_ref().map(|m| m.0.as_ref()), Some("full-access"));
+    assert!(d.model.is_none());
+    assert_eq!(rest, "run the tests");
+}
+
+#[test]
+fn unknown_bang_token_is_left_as_text() {
+    let (d, rest) = parse_directives("!important do not touch main.rs");
+    assert!(d.is_empty());
+    assert_eq!(rest, "!important do not touch main.rs");
+}
+
+#[test]
+fn model_directive_without_value_is_ignored() {
+    let (d, rest) = parse_directives("!m");
+    assert!(d.is_empty());
+    assert_eq!(rest, "!m");
+}
+
+/// A bare `!model` name runs on the session's provider.
+#[test]
+fn directive_models_get_a_provider() {
+    assert_eq!(
+        directive_model_id(Some("openai@gpt-5"), "gpt-5-mini"),
+        "openai@gpt-5-mini"
+    );
+    assert_eq!(
+        directive_model_id(Some("openai@gpt-5"), "ollama@llama3"),
+        "ollama@llama3"
+    );
+    assert_eq!(directive_model_id(None, "gpt-5-mini"), "gpt-5-mini");
+}
+
+/// A `!ro` turn refuses bridge writes of its session, and the session's own
+/// mode applies again once the turn's mode is cleared.
+#[tokio::test]
+async fn read_only_directive_refuses_bridge_writes() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestAgent::start();
+            test.add_session("a");
+            let modes = SessionModeLookup::from(&test.agent);
+            let work = test.home.join("work");
+            let file = work.join("notes.txt");
+            std::fs::write(&file, "original").unwrap();
+
+            // Answer mode lookups the way `main` does; the client cannot write.
+            let (client_tx, mut client_rx) = tokio::sync::mpsc::unbounded_channel();
+            task::spawn_local(async move {
+                while let Some(op) = client_rx.recv().await {
+                    match op {
+                        ClientOp::GetReadOnly {
+                            session_id,
+                            response_tx,
+                        } => {
+                            let _ = response_tx.send(Ok(modes.is_read_only(&session_id)));
+                        }
+                        ClientOp::GetDryRun { response_tx, .. } => {
+                            let _ = response_tx.send(Ok(false));
+                        }
+                        ClientOp::WriteTextFile { response_tx, .. } => {
+                            let _ = response_tx.send(Err(Error::method_not_found()));
+                        }
+                        _ => {}
+                    }
+                }
+            });
+            let bridge = FsBridge::start(client_tx, work.clone(), &test.home)
+                .await
+                .expect("bridge");
+            let client = BridgeClient::new(
+                bridge.session_address("fs-a"),
+                bridge.session_token("fs-a"),
+                BridgeTimeouts::default(),
+            );
+            let write = |content: &str| {
+                client.send(
+                    json!({
+                        "session_id": "fs-a",
+                        "op": "write",
+                        "path": file.display().to_string(),
+                        "content": content,
+                    }),
+                    Duration::from_secs(10),
+                )
+            };
+
+            test.agent.sessions.update("a", |state| {
+                state.turn_mode = Some(SessionModeId("read-only".into()));
+            });
+            let err = write("changed").await.expect_err("read-only turn");
+            assert!(err.to_string().contains("read-only"), "{err}");
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), "original");
+
+            test.agent
+                .sessions
+                .update("a", |state| state.turn_mode = None);
+            write("changed").await.expect("write");
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), "changed");
+        })
+        .await;
+}
@@ -0,0 +1,57 @@
+#![cfg(test)]
+
+use std::time::Instant;
+
+use agent_client_protocol::{ContentBlock, SessionUpdate, ToolCallContent};
+
+use crate::agent::events::{EXEC_OUTPUT_FLUSH_INTERVAL, ExecOutputBuffer, MAX_STREAMED_OUTPUT};
+
+fn text(update: Option<SessionUpdate>) -> String {
+    let Some(SessionUpdate::ToolCallUpdate(update)) = update else {
+        panic!("expected a tool call update");
+    };
+    let content = update.fields.content.expect("content");
+    match &content[..] {
+        [
+            ToolCallContent::Content {
+                content: ContentBlock::Text(text),
+            },
+        ] => text.text.clone(),
+        other => panic!("unexpected content {other:?}"),
+    }
+}
+
+/// The first chunk is sent right away, later ones once the interval passed.
+#[test]
+fn throttles_updates() {
+    let mut buffer = ExecOutputBuffer::new();
+    let start = Instant::now();
+    buffer.begin("call");
+    assert_eq!(text(buffer.push("call", b"one\n", start)), "one\n");
+    assert!(buffer.push("call", b"two\n", start).is_none());
+    assert_eq!(
+        text(buffer.push("call", b"three\n", start + EXEC_OUTPUT_FLUSH_INTERVAL)),
+        "one\ntwo\nthree\n"
+    );
+}
+
+/// Output of calls that were not begun, or already finished, is ignored.
+#[test]
+fn ignores_unknown_calls() {
+    let mut buffer = ExecOutputBuffer::new();
+    assert!(buffer.push("call", b"x", Instant::now()).is_none());
+    buffer.begin("call");
+    buffer.finish("call");
+    assert!(buffer.push("call", b"x", Instant::now()).is_none());
+}
+
+/// Only the tail of long output is kept.
+#[test]
+fn keeps_output_tail() {
+    let mut buffer = ExecOutputBuffer::new();
+    buffer.begin("call");
+    let chunk = vec![b'a'; MAX_STREAMED_OUTPUT + 10];
+    let out = text(buffer.push("call", &chunk, Instant::now()));
+    assert!(out.starts_with('…'));
+    assert_eq!(out.trim_start_matches('…').len(), MAX_STREAMED_OUTPUT);
+}
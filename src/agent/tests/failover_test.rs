@@ -0,0 +1,41 @@
+#![cfg(test)]
+
+use serde_json::json;
+
+use crate::agent::failover::{can_fail_over, fallback_models_from_meta, parse_fallback_list};
+
+/// The env var format tolerates spaces and empty entries.
+#[test]
+fn parse_fallback_list_trims_entries() {
+    assert_eq!(
+        parse_fallback_list(" ollama@llama3 ,, lmstudio@qwen2.5 "),
+        vec!["ollama@llama3".to_string(), "lmstudio@qwen2.5".to_string()]
+    );
+    assert!(parse_fallback_list("").is_empty());
+}
+
+/// Session meta carries the chain as a string array.
+#[test]
+fn fallback_models_from_meta_reads_array() {
+    let meta = json!({ "fallbackModels": ["ollama@llama3"] });
+    assert_eq!(
+        fallback_models_from_meta(Some(&meta)).expect("valid meta"),
+        Some(vec!["ollama@llama3".to_string()])
+    );
+    assert_eq!(fallback_models_from_meta(None).expect("no meta"), None);
+    assert!(fallback_models_from_meta(Some(&json!({ "fallbackModels": "x" }))).is_err());
+}
+
+/// Provider errors fail over unless the attempt already touched tools.
+#[test]
+fn can_fail_over_only_without_tool_activity() {
+    assert!(can_fail_over(
+        "stream disconnected before completion",
+        false
+    ));
+    assert!(!can_fail_over(
+        "stream disconnected before completion",
+        true
+    ));
+    assert!(!can_fail_over("context_length_exceeded", false));
+}
@@ -0,0 +1,51 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use agent_client_protocol::ToolCallContent;
+use serde_json::json;
+
+use crate::agent::fs_diff::{diff_hunks, tool_call_diffs};
+
+const DIFF: &str = "--- src/lib.rs\n+++ src/lib.rs\n--- original\n+++ modified\n\
+@@ -1,3 +1,3 @@\n fn a() {}\n-fn b() {}\n+fn b() -> u8 { 1 }\n fn c() {}\n\
+@@ -10,2 +10,3 @@\n fn j() {}\n+fn k() {}\n fn l() {}\n";
+
+/// Each hunk yields its old and new side, context on both.
+#[test]
+fn splits_hunks_into_sides() {
+    let hunks = diff_hunks(DIFF);
+    assert_eq!(hunks.len(), 2);
+    assert_eq!(hunks[0].0, "fn a() {}\nfn b() {}\nfn c() {}\n");
+    assert_eq!(hunks[0].1, "fn a() {}\nfn b() -> u8 { 1 }\nfn c() {}\n");
+    assert_eq!(hunks[1].0, "fn j() {}\nfn l() {}\n");
+    assert_eq!(hunks[1].1, "fn j() {}\nfn k() {}\nfn l() {}\n");
+    assert!(diff_hunks("No textual differences for src/lib.rs.").is_empty());
+}
+
+/// Diffs are found in a serialized tool result and resolved against the cwd.
+#[test]
+fn finds_diffs_in_tool_results() {
+    let result = json!({
+        "Ok": {
+            "content": [
+                {
+                    "type": "text",
+                    "text": DIFF,
+                    "_meta": { "codex_fs_diff": { "path": "src/lib.rs", "new_ranges": [], "old_ranges": [] } }
+                },
+                { "type": "text", "text": "Write completed for src/lib.rs." }
+            ]
+        }
+    });
+    let diffs = tool_call_diffs(&result, Path::new("/work/app"));
+    assert_eq!(diffs.len(), 2);
+    let ToolCallContent::Diff { diff } = &diffs[1] else {
+        panic!("expected a diff");
+    };
+    assert_eq!(diff.path, PathBuf::from("/work/app/src/lib.rs"));
+    assert_eq!(diff.old_text.as_deref(), Some("fn j() {}\nfn l() {}\n"));
+
+    let plain = json!({ "Ok": { "content": [{ "type": "text", "text": DIFF }] } });
+    assert!(tool_call_diffs(&plain, Path::new("/work/app")).is_empty());
+}
@@ -0,0 +1,164 @@
+#![cfg(test)]
+
+use agent_client_protocol::{SessionUpdate, ToolCallStatus, ToolKind};
+use serde_json::json;
+
+use crate::agent::history::{
+    HistoryItem, history_updates, parse_rollout_line, summarize_rollout, title_from_message,
+};
+
+fn line(kind: &str, payload: serde_json::Value) -> String {
+    json!({"timestamp": "2025-01-01T00:00:00Z", "type": kind, "payload": payload}).to_string()
+}
+
+/// User and agent messages come from the persisted events.
+#[test]
+fn parses_messages() {
+    assert_eq!(
+        parse_rollout_line(&line(
+            "event_msg",
+            json!({"type": "user_message", "message": "fix the build"})
+        )),
+        Some(HistoryItem::User("fix the build".into()))
+    );
+    assert_eq!(
+        parse_rollout_line(&line(
+            "event_msg",
+            json!({"type": "agent_message", "message": "Done."})
+        )),
+        Some(HistoryItem::Agent("Done.".into()))
+    );
+}
+
+/// Shell calls get a command title; MCP tools a `server.tool` title.
+#[test]
+fn parses_tool_calls() {
+    let shell = parse_rollout_line(&line(
+        "response_item",
+        json!({
+            "type": "function_call",
+            "name": "shell",
+            "arguments": "{\"command\":[\"cargo\",\"check\"]}",
+            "call_id": "call_1"
+        }),
+    ));
+    let Some(HistoryItem::ToolCall { title, kind, .. }) = shell else {
+        panic!("expected a tool call, got {shell:?}");
+    };
+    assert_eq!(title, "Run cargo check");
+    assert_eq!(kind, ToolKind::Execute);
+
+    let mcp = parse_rollout_line(&line(
+        "response_item",
+        json!({
+            "type": "function_call",
+            "name": "acp_fs__read_text_file",
+            "arguments": "{}",
+            "call_id": "call_2"
+        }),
+    ));
+    let Some(HistoryItem::ToolCall { title, .. }) = mcp else {
+        panic!("expected a tool call, got {mcp:?}");
+    };
+    assert_eq!(title, "acp_fs.read_text_file");
+}
+
+/// Outputs may be a plain string or an object with a success flag.
+#[test]
+fn parses_tool_outputs() {
+    assert_eq!(
+        parse_rollout_line(&line(
+            "response_item",
+            json!({"type": "function_call_output", "call_id": "c", "output": "ok"})
+        )),
+        Some(HistoryItem::ToolResult {
+            call_id: "c".into(),
+            output: "ok".into(),
+            success: None,
+        })
+    );
+    assert_eq!(
+        parse_rollout_line(&line(
+            "response_item",
+            json!({
+                "type": "function_call_output",
+                "call_id": "c",
+                "output": {"content": "boom", "success": false}
+            })
+        )),
+        Some(HistoryItem::ToolResult {
+            call_id: "c".into(),
+            output: "boom".into(),
+            success: Some(false),
+        })
+    );
+}
+
+/// Session metadata, turn context, and malformed lines are skipped.
+#[test]
+fn skips_other_lines() {
+    assert_eq!(parse_rollout_line("not json"), None);
+    assert_eq!(
+        parse_rollout_line(&line("session_meta", json!({"id": "abc"}))),
+        None
+    );
+    assert_eq!(
+        parse_rollout_line(&line(
+            "event_msg",
+            json!({"type": "token_count", "info": null})
+        )),
+        None
+    );
+}
+
+/// Failed tool results replay as failed updates.
+#[test]
+fn replays_failed_results() {
+    let updates = history_updates(vec![
+        HistoryItem::User("hi".into()),
+        HistoryItem::ToolResult {
+            call_id: "c".into(),
+            output: "boom".into(),
+            success: Some(false),
+        },
+    ]);
+    assert!(matches!(updates[0], SessionUpdate::UserMessageChunk(_)));
+    let SessionUpdate::ToolCallUpdate(update) = &updates[1] else {
+        panic!("expected a tool call update");
+    };
+    assert_eq!(update.fields.status, Some(ToolCallStatus::Failed));
+}
+
+/// Summaries take the id and cwd from the metadata line and the title from the first prompt.
+#[test]
+fn summarizes_rollouts() {
+    let lines = [
+        line(
+            "session_meta",
+            json!({"id": "abc", "cwd": "/work", "timestamp": "2025-01-01T00:00:00Z"}),
+        ),
+        line(
+            "response_item",
+            json!({"type": "message", "role": "user", "content": []}),
+        ),
+        line(
+            "event_msg",
+            json!({"type": "user_message", "message": "\n  Fix the flaky test\nin ci"}),
+        ),
+    ];
+    let summary = summarize_rollout(&lines).expect("summary");
+    assert_eq!(summary.session_id, "abc");
+    assert_eq!(summary.cwd.as_deref(), Some(std::path::Path::new("/work")));
+    assert_eq!(summary.title.as_deref(), Some("Fix the flaky test"));
+
+    assert!(summarize_rollout(&lines[1..]).is_none());
+}
+
+/// Long first lines are cut to a short title.
+#[test]
+fn truncates_titles() {
+    let title = title_from_message(&"x".repeat(200)).expect("title");
+    assert_eq!(title.chars().count(), 81);
+    assert!(title.ends_with('…'));
+    assert_eq!(title_from_message("  \n "), None);
+}
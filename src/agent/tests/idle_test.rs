@@ -0,0 +1,96 @@
+#![cfg(test)]
+
+use std::time::{Duration, Instant};
+
+use agent_client_protocol::SessionModeId;
+use serde_json::json;
+
+use codex_core::config::{Config, ConfigOverrides, ConfigToml};
+
+use crate::agent::{
+    idle::{idle_check, idle_timeout_from_meta, should_drop_to_read_only},
+    session::SessionState,
+};
+
+/// Only sessions outside read-only mode that have been idle long enough drop.
+#[test]
+fn drops_after_timeout_unless_read_only() {
+    let timeout = Duration::from_secs(600);
+    let start = Instant::now();
+    let later = start + Duration::from_secs(601);
+    let full = SessionModeId("full-access".into());
+
+    assert!(should_drop_to_read_only(&full, start, later, timeout));
+    assert!(!should_drop_to_read_only(
+        &full,
+        start,
+        start + Duration::from_secs(60),
+        timeout
+    ));
+    assert!(!should_drop_to_read_only(
+        &SessionModeId("read-only".into()),
+        start,
+        later,
+        timeout
+    ));
+}
+
+/// Session meta sets the timeout in minutes; zero disables it.
+#[test]
+fn idle_timeout_meta() {
+    let meta = json!({ "idleReadOnlyMinutes": 15 });
+    assert_eq!(
+        idle_timeout_from_meta(Some(&meta)).unwrap(),
+        Some(Some(Duration::from_secs(900)))
+    );
+    let disabled = json!({ "idleReadOnlyMinutes": 0 });
+    assert_eq!(idle_timeout_from_meta(Some(&disabled)).unwrap(), Some(None));
+    assert_eq!(idle_timeout_from_meta(None).unwrap(), None);
+    assert!(idle_timeout_from_meta(Some(&json!({ "idleReadOnlyMinutes": "soon" }))).is_err());
+}
+
+/// Dry-run sessions never change anything, so they stay put.
+#[test]
+fn dry_run_sessions_stay() {
+    let start = Instant::now();
+    assert!(!should_drop_to_read_only(
+        &SessionModeId("dry-run".into()),
+        start,
+        start + Duration::from_secs(3600),
+        Duration::from_secs(60),
+    ));
+}
+
+/// A running turn holds the turn lock; it counts as activity.
+#[test]
+fn running_turn_is_never_downgraded() {
+    let home = std::env::temp_dir().join(format!("codex-acp-idle-{}", uuid::Uuid::new_v4()));
+    let config = Config::load_from_base_config_with_overrides(
+        ConfigToml::default(),
+        ConfigOverrides::default(),
+        home,
+    )
+    .expect("config");
+    let mut state = SessionState::new(
+        "fs-1".to_string(),
+        None,
+        &config,
+        SessionModeId("full-access".into()),
+    );
+    let timeout = Duration::from_secs(600);
+    let start = state.last_activity;
+    let later = start + Duration::from_secs(601);
+
+    let turn_lock = state.turn_lock.clone();
+    let turn = turn_lock.try_lock().expect("turn lock");
+    assert!(!idle_check(&mut state, later, timeout));
+    assert_eq!(state.last_activity, later);
+    drop(turn);
+
+    assert!(!idle_check(
+        &mut state,
+        later + Duration::from_secs(60),
+        timeout
+    ));
+    assert!(idle_check(&mut state, later + timeout, timeout));
+}
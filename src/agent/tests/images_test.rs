@@ -0,0 +1,71 @@
+#![cfg(test)]
+
+use std::io::Cursor;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use image::{DynamicImage, ImageFormat, RgbImage};
+
+use crate::agent::images::{ImageLimits, ImageOutcome, preprocess_image};
+
+fn png_base64(width: u32, height: u32) -> String {
+    let img = DynamicImage::ImageRgb8(RgbImage::new(width, height));
+    let mut buf = Cursor::new(Vec::new());
+    img.write_to(&mut buf, ImageFormat::Png).unwrap();
+    STANDARD.encode(buf.into_inner())
+}
+
+#[test]
+fn small_image_passes_through_unchanged() {
+    let limits = ImageLimits::for_model("gpt-5");
+    let data = png_base64(16, 16);
+    let outcome = preprocess_image("image/png", &data, &limits);
+    assert_eq!(
+        outcome,
+        ImageOutcome::Unchanged {
+            data_url: format!("data:image/png;base64,{data}")
+        }
+    );
+}
+
+#[test]
+fn oversized_image_is_downscaled_to_max_dimension() {
+    let limits = ImageLimits {
+        max_dimension: 100,
+        max_bytes: 1024 * 1024,
+        max_images: 1,
+    };
+    let outcome = preprocess_image("image/png", &png_base64(400, 200), &limits);
+    match outcome {
+        ImageOutcome::Downscaled { data_url, from, to } => {
+            assert!(data_url.starts_with("data:image/jpeg;base64,"));
+            assert_eq!(from, (400, 200));
+            assert_eq!(to, (100, 50));
+        }
+        other => panic!("expected downscale, got {other:?}"),
+    }
+}
+
+#[test]
+fn invalid_base64_is_skipped() {
+    let limits = ImageLimits::for_model("custom@model");
+    assert!(matches!(
+        preprocess_image("image/png", "not base64!", &limits),
+        ImageOutcome::Skipped(_)
+    ));
+}
+
+#[test]
+fn image_mime_type_from_declared_type_or_extension() {
+    use crate::agent::images::image_mime_type;
+
+    assert_eq!(
+        image_mime_type(Some("image/png"), "file:///a/b"),
+        Some("image/png".to_string())
+    );
+    assert_eq!(image_mime_type(Some("text/plain"), "file:///a.png"), None);
+    assert_eq!(
+        image_mime_type(None, "file:///shots/Screen.JPG"),
+        Some("image/jpeg".to_string())
+    );
+    assert_eq!(image_mime_type(None, "file:///src/main.rs"), None);
+}
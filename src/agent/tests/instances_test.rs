@@ -0,0 +1,71 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use crate::agent::instances::{
+    InstanceRecord, InstanceRegistry, STALE_AFTER, duplicate_notice, workspace_key,
+};
+
+fn record(pid: u32, cwd: &Path, heartbeat_at: u64) -> InstanceRecord {
+    InstanceRecord {
+        pid,
+        version: "0.1.0".to_string(),
+        cwd: cwd.to_path_buf(),
+        started_at: 0,
+        heartbeat_at,
+    }
+}
+
+/// The key is stable and differs between workspaces.
+#[test]
+fn workspace_keys_are_stable() {
+    let key = workspace_key(Path::new("/work/app"));
+    assert_eq!(key.len(), 16);
+    assert_eq!(key, workspace_key(Path::new("/work/app")));
+    assert_ne!(key, workspace_key(Path::new("/work/other")));
+}
+
+/// Other live agents are reported; stale entries are cleaned up.
+#[tokio::test]
+async fn finds_other_live_instances() {
+    let home = std::env::temp_dir().join(format!("codex-acp-instances-{}", uuid::Uuid::new_v4()));
+    let registry = InstanceRegistry::new(&home);
+    let cwd = PathBuf::from("/work/app");
+    let now = 10_000;
+    let stale = now - STALE_AFTER.as_secs() - 1;
+
+    registry
+        .save(&record(1, &cwd, now))
+        .await
+        .expect("save own");
+    registry
+        .save(&record(2, &cwd, now - 5))
+        .await
+        .expect("save live");
+    registry
+        .save(&record(3, &cwd, stale))
+        .await
+        .expect("save stale");
+    registry
+        .save(&record(4, Path::new("/work/other"), now))
+        .await
+        .expect("save other workspace");
+
+    let others = registry.others(&cwd, 1, now).await;
+    assert_eq!(others, vec![record(2, &cwd, now - 5)]);
+    let entries = std::fs::read_dir(home.join("acp/instances").join(workspace_key(&cwd)))
+        .expect("entries")
+        .count();
+    assert_eq!(entries, 2);
+
+    registry.remove(&cwd, 2);
+    assert!(registry.others(&cwd, 1, now).await.is_empty());
+    let _ = std::fs::remove_dir_all(&home);
+}
+
+#[test]
+fn notice_names_the_other_processes() {
+    let cwd = PathBuf::from("/work/app");
+    let notice = duplicate_notice(&[record(7, &cwd, 0), record(9, &cwd, 0)]);
+    assert!(notice.contains("PID 7, 9"));
+}
@@ -0,0 +1,74 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use crate::agent::instructions::{
+    INSTRUCTIONS_USAGE, InstructionStore, InstructionsCommand, MAX_SNIPPET_CHARS, format_snippets,
+    parse_instructions_command, render_snippets,
+};
+
+#[test]
+fn parses_instructions_commands() {
+    assert_eq!(
+        parse_instructions_command(""),
+        Ok(InstructionsCommand::List)
+    );
+    assert_eq!(
+        parse_instructions_command("list"),
+        Ok(InstructionsCommand::List)
+    );
+    assert_eq!(
+        parse_instructions_command("add  Prefer pnpm over npm "),
+        Ok(InstructionsCommand::Add("Prefer pnpm over npm".to_string()))
+    );
+    assert_eq!(
+        parse_instructions_command("remove 2"),
+        Ok(InstructionsCommand::Remove(2))
+    );
+    assert_eq!(
+        parse_instructions_command("remove 0"),
+        Err(INSTRUCTIONS_USAGE.to_string())
+    );
+    assert_eq!(
+        parse_instructions_command("add"),
+        Err(INSTRUCTIONS_USAGE.to_string())
+    );
+    assert!(
+        parse_instructions_command(&format!("add {}", "x".repeat(MAX_SNIPPET_CHARS + 1))).is_err()
+    );
+    assert!(parse_instructions_command("clear").is_err());
+}
+
+#[test]
+fn renders_snippets_for_instructions_and_listing() {
+    assert_eq!(render_snippets(&[]), None);
+    let snippets = vec!["Use tabs".to_string(), "No emojis".to_string()];
+    assert_eq!(
+        render_snippets(&snippets).unwrap(),
+        "Standing instructions from the user for this workspace:\n- Use tabs\n- No emojis"
+    );
+    let listing = format_snippets(&snippets);
+    assert!(listing.contains("1. Use tabs\n2. No emojis\n"));
+    assert!(format_snippets(&[]).starts_with("No instructions saved"));
+}
+
+#[tokio::test]
+async fn store_keeps_snippets_per_workspace() {
+    let home =
+        std::env::temp_dir().join(format!("codex-acp-instructions-{}", uuid::Uuid::new_v4()));
+    let store = InstructionStore::new(&home);
+    let a = Path::new("/work/a");
+    let b = Path::new("/work/b");
+
+    store.add(a, "first".to_string()).await.unwrap();
+    let snippets = store.add(a, "second".to_string()).await.unwrap();
+    assert_eq!(snippets, vec!["first".to_string(), "second".to_string()]);
+    store.add(b, "other".to_string()).await.unwrap();
+
+    assert_eq!(store.remove(a, 3).await.unwrap(), None);
+    assert_eq!(store.remove(a, 1).await.unwrap(), Some("first".to_string()));
+    assert_eq!(store.list(a).await, vec!["second".to_string()]);
+    assert_eq!(store.list(b).await, vec!["other".to_string()]);
+
+    let _ = std::fs::remove_dir_all(home);
+}
@@ -0,0 +1,25 @@
+#![cfg(test)]
+
+use crate::agent::interactive::interactive_reason;
+
+fn argv(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+/// Commands that wait for input are flagged, including inside `bash -lc`.
+#[test]
+fn detects_interactive_commands() {
+    assert!(interactive_reason(&argv(&["npm", "init"])).is_some());
+    assert!(interactive_reason(&argv(&["bash", "-lc", "git commit"])).is_some());
+    assert!(interactive_reason(&argv(&["python3"])).is_some());
+    assert!(interactive_reason(&argv(&["/usr/bin/vim", "src/main.rs"])).is_some());
+}
+
+/// Non-interactive forms pass through.
+#[test]
+fn ignores_non_interactive_commands() {
+    assert!(interactive_reason(&argv(&["npm", "init", "-y"])).is_none());
+    assert!(interactive_reason(&argv(&["bash", "-lc", "git commit -m fix"])).is_none());
+    assert!(interactive_reason(&argv(&["python3", "script.py"])).is_none());
+    assert!(interactive_reason(&argv(&["cargo", "test"])).is_none());
+}
@@ -0,0 +1,160 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use agent_client_protocol::{Error, LoadSessionRequest, SessionId};
+use serde_json::json;
+use tokio::task::LocalSet;
+
+use super::support::TestAgent;
+use crate::agent::{acp_config::AcpConfig, sampling::SamplingOverrides, store::SessionSettings};
+
+fn line(kind: &str, payload: serde_json::Value) -> String {
+    json!({"timestamp": "2025-01-01T00:00:00Z", "type": kind, "payload": payload}).to_string()
+}
+
+/// Write a rollout for conversation `id` where Codex keeps them.
+fn write_rollout(home: &Path, id: &str, lines: &[String]) {
+    let dir = home.join("sessions").join("2025").join("01").join("01");
+    std::fs::create_dir_all(&dir).expect("sessions dir");
+    let path = dir.join(format!("rollout-2025-01-01T00-00-00-{id}.jsonl"));
+    std::fs::write(path, lines.join("\n") + "\n").expect("rollout");
+}
+
+fn load_request(session_id: &SessionId, cwd: &Path) -> LoadSessionRequest {
+    LoadSessionRequest {
+        mcp_servers: Vec::new(),
+        cwd: cwd.to_path_buf(),
+        session_id: session_id.clone(),
+        meta: None,
+    }
+}
+
+/// A session unknown to this process and to Codex cannot be loaded, and
+/// nothing is registered for it.
+#[tokio::test]
+async fn unknown_session_without_rollout_is_not_found() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestAgent::start();
+            let session_id = SessionId(uuid::Uuid::new_v4().to_string().into());
+            let err = test
+                .agent
+                .load_session(load_request(&session_id, &test.home))
+                .await
+                .expect_err("no rollout to resume");
+            assert_eq!(err.code, Error::invalid_params().code);
+            assert!(!test.agent.sessions.contains(session_id.0.as_ref()));
+        })
+        .await;
+}
+
+/// A rollout Codex cannot resume from fails the load without leaving a
+/// half-registered session behind.
+#[tokio::test]
+async fn unreadable_rollout_leaves_no_session() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestAgent::start();
+            let id = uuid::Uuid::new_v4().to_string();
+            write_rollout(&test.home, &id, &["not a rollout line".to_string()]);
+            let session_id = SessionId(id.clone().into());
+            let err = test
+                .agent
+                .load_session(load_request(&session_id, &test.home))
+                .await
+                .expect_err("nothing to resume from");
+            assert_eq!(err.code, Error::internal_error().code);
+            assert!(!test.agent.sessions.contains(&id));
+            assert_eq!(test.agent.sessions.count(), 0);
+        })
+        .await;
+}
+
+/// Loading a session streams the transcript from its rollout back to the
+/// client before answering.
+#[tokio::test]
+async fn load_replays_the_rollout_transcript() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestAgent::start();
+            let id = uuid::Uuid::new_v4().to_string();
+            write_rollout(
+                &test.home,
+                &id,
+                &[
+                    line(
+                        "session_meta",
+                        json!({"id": id, "cwd": "/work", "timestamp": "2025-01-01T00:00:00Z"}),
+                    ),
+                    line(
+                        "event_msg",
+                        json!({"type": "user_message", "message": "fix the build"}),
+                    ),
+                    line(
+                        "event_msg",
+                        json!({"type": "agent_message", "message": "Done."}),
+                    ),
+                ],
+            );
+            let session_id = test.add_session(&id);
+
+            let response = test
+                .agent
+                .load_session(load_request(&session_id, &test.home))
+                .await
+                .expect("load");
+            assert_eq!(test.messages(), vec!["fix the build", "Done."]);
+            assert!(
+                test.updates
+                    .borrow()
+                    .iter()
+                    .all(|update| update.session_id == session_id)
+            );
+            let modes = response.modes.expect("modes");
+            assert_eq!(modes.current_mode_id.0.as_ref(), "auto");
+        })
+        .await;
+}
+
+/// Sampling, language, fallback and token-cap settings saved with a session
+/// are the ones it is resumed with; records without them get new-session
+/// defaults.
+#[tokio::test]
+async fn saved_settings_survive_a_reload() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestAgent::start();
+            let session_id = test.add_session("a");
+            let settings = SessionSettings {
+                sampling: SamplingOverrides {
+                    temperature: Some(0.3),
+                    top_p: None,
+                    seed: Some(9),
+                },
+                fallback_models: vec!["qwen3@ollama".to_string()],
+                max_session_tokens: Some(50_000),
+                response_language: Some("French".to_string()),
+            };
+            test.agent.with_session_state_mut(&session_id, |state| {
+                state.sampling = settings.sampling;
+                state.fallback_models = settings.fallback_models.clone();
+                state.max_session_tokens = settings.max_session_tokens;
+                state.response_language = settings.response_language.clone();
+            });
+            test.agent.save_session_settings(&session_id).await;
+
+            let record = test.agent.record_store.load("a").await.expect("record");
+            assert_eq!(record.settings.as_ref(), Some(&settings));
+            let config = AcpConfig::default();
+            assert_eq!(
+                test.agent.resumed_settings(record.settings, &config),
+                settings
+            );
+
+            let defaults = test.agent.resumed_settings(None, &config);
+            assert_eq!(defaults.sampling, SamplingOverrides::default());
+            assert_eq!(defaults.max_session_tokens, None);
+        })
+        .await;
+}
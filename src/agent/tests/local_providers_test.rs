@@ -0,0 +1,114 @@
+#![cfg(test)]
+
+use agent_client_protocol::Error;
+use tokio::task::LocalSet;
+use toml_edit::DocumentMut;
+
+use super::support::TestAgent;
+use crate::agent::local_providers::{
+    ConnectLocalProviderParams, MANAGED_COMMENT, ProbedEndpoint, is_valid_provider_id,
+    profile_name, write_provider_entries,
+};
+
+fn ollama() -> ProbedEndpoint {
+    ProbedEndpoint {
+        provider: "ollama".to_string(),
+        name: "Ollama".to_string(),
+        base_url: "http://localhost:11434/v1".to_string(),
+        reachable: true,
+        models: vec!["llama3.1:8b".to_string()],
+    }
+}
+
+/// Model names with `:` / `.` are turned into valid profile keys.
+#[test]
+fn profile_name_sanitizes_model() {
+    assert_eq!(profile_name("ollama", "llama3.1:8b"), "ollama-llama3-1-8b");
+}
+
+/// Entries are added without disturbing existing config.
+#[test]
+fn write_provider_entries_preserves_existing_config() {
+    let mut doc: DocumentMut = "model = \"gpt-5\"\n\n[profiles.work]\nmodel = \"o3\"\n"
+        .parse()
+        .expect("valid toml");
+    let wire_api =
+        write_provider_entries(&mut doc, &ollama(), &["llama3.1:8b".to_string()]).expect("written");
+    assert_eq!(wire_api, "chat");
+
+    assert_eq!(doc["model"].as_str(), Some("gpt-5"));
+    assert_eq!(doc["profiles"]["work"]["model"].as_str(), Some("o3"));
+    assert_eq!(
+        doc["model_providers"]["ollama"]["base_url"].as_str(),
+        Some("http://localhost:11434/v1")
+    );
+    assert_eq!(
+        doc["model_providers"]["ollama"]["wire_api"].as_str(),
+        Some("chat")
+    );
+    let profile = &doc["profiles"]["ollama-llama3-1-8b"];
+    assert_eq!(profile["model"].as_str(), Some("llama3.1:8b"));
+    assert_eq!(profile["model_provider"].as_str(), Some("ollama"));
+}
+
+/// An existing `wire_api` choice is kept when re-connecting, and returned
+/// so the provider is registered with it.
+#[test]
+fn write_provider_entries_keeps_wire_api() {
+    let mut doc = DocumentMut::new();
+    write_provider_entries(&mut doc, &ollama(), &[]).expect("written");
+    let mut doc: DocumentMut = doc
+        .to_string()
+        .replace("wire_api = \"chat\"", "wire_api = \"responses\"")
+        .parse()
+        .expect("valid toml");
+    assert!(doc.to_string().contains(MANAGED_COMMENT));
+
+    let wire_api = write_provider_entries(&mut doc, &ollama(), &[]).expect("rewritten");
+    assert_eq!(wire_api, "responses");
+    assert_eq!(
+        doc["model_providers"]["ollama"]["wire_api"].as_str(),
+        Some("responses")
+    );
+}
+
+/// Provider tables configured by hand are never overwritten.
+#[test]
+fn write_provider_entries_refuses_foreign_providers() {
+    let config = "[model_providers.ollama]\nbase_url = \"http://gpu-box:11434/v1\"\n";
+    let mut doc: DocumentMut = config.parse().expect("valid toml");
+    let err = write_provider_entries(&mut doc, &ollama(), &[]).expect_err("foreign provider");
+    assert_eq!(err.code, Error::invalid_params().code);
+    assert_eq!(doc.to_string(), config);
+}
+
+#[test]
+fn provider_ids_are_restricted() {
+    assert!(is_valid_provider_id("my-vllm_2"));
+    assert!(!is_valid_provider_id(""));
+    assert!(!is_valid_provider_id("a.b"));
+    assert!(!is_valid_provider_id("x]\n[profiles"));
+}
+
+/// Custom ids that cannot name a TOML table are refused before probing.
+#[tokio::test]
+async fn custom_provider_ids_are_validated() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestAgent::start();
+            let params = ConnectLocalProviderParams {
+                provider: Some("bad id".to_string()),
+                base_url: Some("http://localhost:9/v1".to_string()),
+                confirm: true,
+                ..Default::default()
+            };
+            let err = test
+                .agent
+                .connect_local_provider(params)
+                .await
+                .expect_err("invalid id");
+            assert_eq!(err.code, Error::invalid_params().code);
+            assert!(!test.home.join("config.toml").exists());
+        })
+        .await;
+}
@@ -0,0 +1,62 @@
+#![cfg(test)]
+
+use std::path::PathBuf;
+
+use crate::logging::{Destination, LogFormat, LoggingConfig, merge_directives};
+
+const CONFIG: &str = r#"
+model = "gpt-5"
+
+[acp.logging]
+level = "warn"
+format = "compact"
+destinations = ["file"]
+file = "/tmp/acp.log"
+
+[acp.logging.modules]
+codex_acp = "debug"
+rmcp = "error"
+"#;
+
+#[test]
+fn parses_the_acp_logging_section() {
+    let config = LoggingConfig::parse(CONFIG).unwrap();
+    assert_eq!(config.level.as_deref(), Some("warn"));
+    assert_eq!(config.format, LogFormat::Compact);
+    assert_eq!(config.file, Some(PathBuf::from("/tmp/acp.log")));
+    assert_eq!(
+        config.modules,
+        vec![
+            ("codex_acp".to_string(), "debug".to_string()),
+            ("rmcp".to_string(), "error".to_string()),
+        ]
+    );
+    assert!(config.logs_to(Destination::File));
+    assert!(!config.logs_to(Destination::Stderr));
+
+    let default = LoggingConfig::parse("model = \"gpt-5\"\n").unwrap();
+    assert_eq!(default, LoggingConfig::default());
+    assert!(default.logs_to(Destination::Stderr));
+
+    assert!(LoggingConfig::parse("[acp.logging]\nformat = \"json\"\n").is_err());
+    assert!(LoggingConfig::parse("[acp.logging]\ndestinations = [\"syslog\"]\n").is_err());
+}
+
+/// RUST_LOG wins per module and for the default level.
+#[test]
+fn merges_rust_log_over_configured_levels() {
+    let config = LoggingConfig::parse(CONFIG).unwrap();
+    assert_eq!(
+        merge_directives(&config, None),
+        "warn,codex_acp=debug,rmcp=error"
+    );
+    assert_eq!(
+        merge_directives(&config, Some("rmcp=trace")),
+        "warn,codex_acp=debug,rmcp=trace"
+    );
+    assert_eq!(
+        merge_directives(&config, Some("info, codex_acp[turn]=trace")),
+        "rmcp=error,info,codex_acp[turn]=trace"
+    );
+    assert_eq!(merge_directives(&LoggingConfig::default(), None), "info");
+}
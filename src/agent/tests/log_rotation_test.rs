@@ -0,0 +1,55 @@
+#![cfg(test)]
+
+use std::{io::Write, path::PathBuf};
+
+use crate::logging::SizeRotatingFile;
+
+fn temp_log() -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("codex-acp-log-{}", uuid::Uuid::new_v4()))
+        .join("acp.log")
+}
+
+fn read(path: PathBuf) -> String {
+    std::fs::read_to_string(path).unwrap_or_default()
+}
+
+#[test]
+fn rotates_by_size_and_keeps_max_files() {
+    let path = temp_log();
+    let mut log = SizeRotatingFile::open(&path, 10, 2).unwrap();
+    for line in ["12345678\n", "abc\n", "defghi\n", "x\n", "0123456789\n"] {
+        log.write_all(line.as_bytes()).unwrap();
+    }
+    log.flush().unwrap();
+
+    assert_eq!(read(path.clone()), "0123456789\n");
+    assert_eq!(read(log.rotated_path(1)), "defghi\nx\n");
+    assert_eq!(read(log.rotated_path(2)), "abc\n");
+    assert!(!log.rotated_path(3).exists());
+}
+
+/// The size of an existing file counts after a restart.
+#[test]
+fn resumes_from_the_size_on_disk() {
+    let path = temp_log();
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "12345678\n").unwrap();
+
+    let mut log = SizeRotatingFile::open(&path, 10, 1).unwrap();
+    log.write_all(b"abc\n").unwrap();
+    log.flush().unwrap();
+    assert_eq!(read(path.clone()), "abc\n");
+    assert_eq!(read(log.rotated_path(1)), "12345678\n");
+}
+
+#[test]
+fn truncates_without_rotated_files() {
+    let path = temp_log();
+    let mut log = SizeRotatingFile::open(&path, 10, 0).unwrap();
+    log.write_all(b"12345678\n").unwrap();
+    log.write_all(b"abc\n").unwrap();
+    log.flush().unwrap();
+    assert_eq!(read(path.clone()), "abc\n");
+    assert!(!log.rotated_path(1).exists());
+}
@@ -0,0 +1,143 @@
+#![cfg(test)]
+
+use std::path::PathBuf;
+
+use agent_client_protocol::{EnvVariable, McpServer};
+use serde_json::{Value, json};
+
+use crate::agent::mcp_servers::{
+    FS_SERVER_NAME, ServerRename, merge, persistable, restore, unique_server_name,
+};
+
+fn env(name: &str, value: &str) -> EnvVariable {
+    serde_json::from_value(json!({ "name": name, "value": value })).unwrap()
+}
+
+fn stdio(name: &str, args: &[&str], vars: Vec<EnvVariable>) -> McpServer {
+    McpServer::Stdio {
+        name: name.to_string(),
+        command: PathBuf::from("/usr/bin/mcp-server"),
+        args: args.iter().map(|a| a.to_string()).collect(),
+        env: vars,
+    }
+}
+
+fn http(name: &str, url: &str, headers: Value) -> McpServer {
+    serde_json::from_value(json!({
+        "type": "http",
+        "name": name,
+        "url": url,
+        "headers": headers,
+    }))
+    .unwrap()
+}
+
+fn names(servers: &[McpServer]) -> Vec<String> {
+    servers
+        .iter()
+        .map(|server| match server {
+            McpServer::Http { name, .. }
+            | McpServer::Sse { name, .. }
+            | McpServer::Stdio { name, .. } => name.clone(),
+        })
+        .collect()
+}
+
+#[test]
+fn secret_values_are_not_persisted() {
+    let server = stdio(
+        "db",
+        &["--port", "5432"],
+        vec![env("DB_PASSWORD", "hunter22"), env("DB_HOST", "localhost")],
+    );
+    let saved = persistable(&server).unwrap();
+    let text = saved.to_string();
+    assert!(!text.contains("hunter22"));
+    assert!(text.contains("DB_PASSWORD"));
+    assert!(text.contains("localhost"));
+
+    let McpServer::Stdio { env, .. } = restore(saved).unwrap() else {
+        panic!("expected a stdio server");
+    };
+    assert_eq!(env.len(), 1);
+    assert_eq!(env[0].name, "DB_HOST");
+}
+
+#[test]
+fn secret_headers_are_dropped() {
+    let server = http(
+        "api",
+        "https://mcp.example.com/v1",
+        json!([
+            { "name": "Authorization", "value": "Bearer abc" },
+            { "name": "Cookie", "value": "session=1" },
+            { "name": "X-Team", "value": "core" },
+        ]),
+    );
+    let saved = persistable(&server).unwrap();
+    let McpServer::Http { headers, .. } = restore(saved).unwrap() else {
+        panic!("expected an http server");
+    };
+    let kept: Vec<&str> = headers.iter().map(|h| h.name.as_str()).collect();
+    assert_eq!(kept, vec!["X-Team"]);
+}
+
+#[test]
+fn servers_with_inline_credentials_are_skipped() {
+    let url = http("api", "https://mcp.example.com/v1?token=abc", json!([]));
+    assert!(persistable(&url).is_none());
+    let args = stdio("gh", &["--token", "ghp_0123456789abcdefghij"], Vec::new());
+    assert!(persistable(&args).is_none());
+}
+
+#[test]
+fn requested_servers_replace_saved_ones() {
+    let saved = vec![
+        persistable(&stdio("db", &[], vec![env("DB_PASSWORD", "old-secret")])).unwrap(),
+        persistable(&stdio("search", &[], Vec::new())).unwrap(),
+    ];
+    let requested = vec![stdio("db", &[], vec![env("DB_PASSWORD", "new-secret")])];
+    let servers = merge(saved, requested);
+    assert_eq!(
+        names(&servers),
+        vec!["db".to_string(), "search".to_string()]
+    );
+    let McpServer::Stdio { env, .. } = &servers[0] else {
+        panic!("expected a stdio server");
+    };
+    assert_eq!(env[0].value, "new-secret");
+}
+
+#[test]
+fn unique_server_name_keeps_free_valid_names() {
+    assert_eq!(unique_server_name("github", |_| false), "github");
+    assert_eq!(unique_server_name("my-server", |_| false), "my-server");
+}
+
+#[test]
+fn unique_server_name_suffixes_taken_names() {
+    let taken = |name: &str| name == FS_SERVER_NAME;
+    assert_eq!(unique_server_name("acp_fs", taken), "acp_fs_2");
+    let taken = |name: &str| ["docs", "docs_2"].contains(&name);
+    assert_eq!(unique_server_name("docs", taken), "docs_3");
+}
+
+#[test]
+fn unique_server_name_sanitizes_names() {
+    assert_eq!(unique_server_name("my server", |_| false), "my_server");
+    assert_eq!(unique_server_name("a__b", |_| false), "a_b");
+    assert_eq!(unique_server_name("_x_", |_| false), "x");
+    assert_eq!(unique_server_name("", |_| false), "mcp");
+    assert_eq!(unique_server_name("__", |_| false), "mcp");
+}
+
+#[test]
+fn rename_notice_names_both_servers() {
+    let notice = ServerRename {
+        requested: "acp_fs".to_string(),
+        assigned: "acp_fs_2".to_string(),
+    }
+    .notice();
+    assert!(notice.contains("`acp_fs`"));
+    assert!(notice.contains("`acp_fs_2__<tool>`"));
+}
@@ -1,2 +1,64 @@
+mod acp_config_test;
+mod approvals_test;
+mod audit_test;
+mod backfill_test;
+mod budget_test;
+mod checkpoints_test;
+mod command_history_test;
+mod commands_test;
+mod compat_test;
+mod context_test;
+mod context_window_test;
+mod continuation_test;
+mod custom_prompts_test;
+mod diagnostics_test;
+mod directives_test;
+mod exec_output_test;
+mod failover_test;
+mod fs_diff_test;
+mod history_test;
+mod idle_test;
+mod images_test;
+mod instances_test;
+mod instructions_test;
+mod interactive_test;
+mod language_test;
+mod load_session_test;
+mod local_providers_test;
+mod log_config_test;
+mod log_rotation_test;
+mod mcp_servers_test;
+mod modified_files_test;
+mod network_test;
+mod plain_output_test;
+mod plan_test;
+mod process_info_test;
+mod prompt_test;
 mod reasoning_test;
+mod redact_test;
+mod risk_test;
+mod sampling_proxy_test;
+mod sampling_test;
+mod schema_check_test;
+mod session_events_test;
 mod session_modes_test;
+mod session_store_test;
+mod shell_env_test;
+mod spend_test;
+mod status_test;
+mod steering_test;
+mod stop_reason_test;
+mod store_test;
+mod support;
+mod terminal_test;
+mod thoughts_test;
+mod transcript_test;
+mod turn_changes_test;
+mod turn_result_test;
+mod undo_test;
+mod update_check_test;
+mod update_clock_test;
+mod usage_test;
+mod utils_test;
+mod workspace_mcp_test;
+mod workspace_roots_test;
@@ -0,0 +1,71 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    agent::modified_files::{Baseline, ModifiedFiles, file_diff},
+    fs::tools::undo::FileSnapshot,
+};
+
+fn snapshot(path: &str, before: Option<&str>, moved_to: Option<&str>) -> FileSnapshot {
+    FileSnapshot {
+        path: PathBuf::from(path),
+        before: before.map(str::to_string),
+        moved_to: moved_to.map(PathBuf::from),
+    }
+}
+
+/// The first change of a file sets its baseline; later ones do not.
+#[test]
+fn keeps_the_first_baseline() {
+    let mut modified = ModifiedFiles::default();
+    modified.record(&[snapshot("/w/a.rs", Some("one\n"), None)]);
+    modified.record(&[snapshot("/w/a.rs", Some("two\n"), None)]);
+    modified.touch(Path::new("/w/a.rs"));
+    modified.record(&[snapshot("/w/new.rs", None, None)]);
+    modified.touch(Path::new("/w/lost.rs"));
+
+    assert_eq!(
+        modified.files(),
+        vec![
+            (
+                PathBuf::from("/w/a.rs"),
+                Baseline::Content("one\n".to_string())
+            ),
+            (PathBuf::from("/w/lost.rs"), Baseline::Unknown),
+            (PathBuf::from("/w/new.rs"), Baseline::Absent),
+        ]
+    );
+}
+
+/// A move also marks its destination, which did not exist before.
+#[test]
+fn records_move_destinations() {
+    let mut modified = ModifiedFiles::default();
+    modified.record(&[snapshot("/w/old.rs", Some("x\n"), Some("/w/new.rs"))]);
+    assert_eq!(
+        modified.files(),
+        vec![
+            (PathBuf::from("/w/new.rs"), Baseline::Absent),
+            (
+                PathBuf::from("/w/old.rs"),
+                Baseline::Content("x\n".to_string())
+            ),
+        ]
+    );
+}
+
+#[test]
+fn diffs_only_changed_files() {
+    let path = Path::new("/w/a.rs");
+    assert!(file_diff(path, Some("same".to_string()), Some("same".to_string())).is_none());
+    assert!(file_diff(path, None, None).is_none());
+
+    let diff = file_diff(path, Some("old".to_string()), None).expect("deleted file");
+    assert_eq!(diff.old_text.as_deref(), Some("old"));
+    assert_eq!(diff.new_text, "");
+
+    let diff = file_diff(path, None, Some("new".to_string())).expect("created file");
+    assert_eq!(diff.old_text, None);
+    assert_eq!(diff.new_text, "new");
+}
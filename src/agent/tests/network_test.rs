@@ -0,0 +1,67 @@
+#![cfg(test)]
+
+use crate::agent::network::{
+    NetworkIssue, OFFLINE_ERROR_CODE, endpoint_host_port, looks_like_network_error, offline_error,
+    proxy_configured_with,
+};
+
+/// Provider URLs resolve to host and default port.
+#[test]
+fn endpoint_host_port_uses_scheme_default() {
+    assert_eq!(
+        endpoint_host_port("https://api.openai.com/v1"),
+        Some(("api.openai.com".to_string(), 443))
+    );
+    assert_eq!(
+        endpoint_host_port("http://localhost:11434/v1"),
+        Some(("localhost".to_string(), 11434))
+    );
+    assert_eq!(endpoint_host_port("not a url"), None);
+}
+
+/// Transport failures are recognised; model errors are not.
+#[test]
+fn network_error_heuristic() {
+    assert!(looks_like_network_error(
+        "error sending request for url (https://api.openai.com/v1/responses)"
+    ));
+    assert!(looks_like_network_error(
+        "dns error: failed to lookup address information"
+    ));
+    assert!(!looks_like_network_error("context window exceeded"));
+}
+
+/// The offline error carries the dedicated code and guidance.
+#[test]
+fn offline_error_has_code_and_guidance() {
+    let issue = NetworkIssue::Dns {
+        host: "api.openai.com".to_string(),
+    };
+    let err = offline_error(&issue, "https://api.openai.com/v1");
+    assert_eq!(err.code, OFFLINE_ERROR_CODE);
+    let data = err.data.expect("error data");
+    assert_eq!(data["endpoint"], "https://api.openai.com/v1");
+    assert!(
+        data["guidance"]
+            .as_str()
+            .unwrap()
+            .contains("api.openai.com")
+    );
+}
+
+/// Any non-empty proxy variable counts; unrelated or blank ones do not.
+#[test]
+fn proxy_variables_are_detected() {
+    assert!(proxy_configured_with(|name| {
+        (name == "all_proxy").then(|| "socks5://127.0.0.1:1080".to_string())
+    }));
+    assert!(proxy_configured_with(|name| {
+        (name == "HTTPS_PROXY").then(|| "http://proxy:3128".to_string())
+    }));
+    assert!(!proxy_configured_with(|name| {
+        (name == "HTTPS_PROXY").then(|| " ".to_string())
+    }));
+    assert!(!proxy_configured_with(|name| {
+        (name == "NO_PROXY").then(|| "localhost".to_string())
+    }));
+}
@@ -0,0 +1,70 @@
+#![cfg(test)]
+
+use std::borrow::Cow;
+
+use agent_client_protocol as acp;
+use serde_json::json;
+
+use crate::agent::PlainOutput;
+use crate::agent::plain_output::to_ascii;
+
+/// Emoji become tags, variation selectors included; other text is kept.
+#[test]
+fn replaces_emoji_with_tags() {
+    assert_eq!(
+        to_ascii("⚠️ Failed to save instruction: denied"),
+        "[!] Failed to save instruction: denied"
+    );
+    assert_eq!(to_ascii("📊 Token Usage"), "[usage] Token Usage");
+    assert_eq!(
+        to_ascii("🗑️ Removed #2 → done"),
+        "[removed] Removed #2 → done"
+    );
+    assert!(matches!(
+        to_ascii("plain text"),
+        Cow::Borrowed("plain text")
+    ));
+}
+
+/// Message chunks and command icons are rewritten before they go out.
+#[test]
+fn rewrites_notifications() {
+    let mut notification = acp::SessionNotification {
+        session_id: acp::SessionId("s".into()),
+        update: acp::SessionUpdate::AgentMessageChunk(acp::ContentChunk {
+            content: "🔁 Retrying the last prompt...".into(),
+            meta: None,
+        }),
+        meta: None,
+    };
+    PlainOutput.notification(&mut notification);
+    let acp::SessionUpdate::AgentMessageChunk(chunk) = &notification.update else {
+        panic!("update kind changed");
+    };
+    assert!(matches!(
+        &chunk.content,
+        acp::ContentBlock::Text(text) if text.text == "[retry] Retrying the last prompt..."
+    ));
+
+    let mut notification = acp::SessionNotification {
+        session_id: acp::SessionId("s".into()),
+        update: acp::SessionUpdate::AvailableCommandsUpdate(acp::AvailableCommandsUpdate {
+            available_commands: vec![acp::AvailableCommand {
+                name: "status".into(),
+                description: "Show status".into(),
+                input: None,
+                meta: Some(json!({ "category": "diagnostics", "icon": "📊" })),
+            }],
+            meta: None,
+        }),
+        meta: None,
+    };
+    PlainOutput.notification(&mut notification);
+    let acp::SessionUpdate::AvailableCommandsUpdate(update) = &notification.update else {
+        panic!("update kind changed");
+    };
+    assert_eq!(
+        update.available_commands[0].meta,
+        Some(json!({ "category": "diagnostics", "icon": "[usage]" }))
+    );
+}
@@ -0,0 +1,35 @@
+#![cfg(test)]
+
+use serde_json::json;
+
+use crate::agent::events::{PlanExplanation, plan_explanation_from_meta};
+
+#[test]
+fn parses_targets() {
+    assert_eq!(
+        PlanExplanation::parse("Thought"),
+        Some(PlanExplanation::Thought)
+    );
+    assert_eq!(
+        PlanExplanation::parse(" message "),
+        Some(PlanExplanation::Message)
+    );
+    assert_eq!(PlanExplanation::parse("plan"), Some(PlanExplanation::Plan));
+    assert_eq!(PlanExplanation::parse("chat"), None);
+    assert_eq!(PlanExplanation::default(), PlanExplanation::Thought);
+}
+
+#[test]
+fn reads_session_meta() {
+    assert_eq!(plan_explanation_from_meta(None).unwrap(), None);
+    assert_eq!(
+        plan_explanation_from_meta(Some(&json!({ "planExplanation": null }))).unwrap(),
+        None
+    );
+    assert_eq!(
+        plan_explanation_from_meta(Some(&json!({ "planExplanation": "message" }))).unwrap(),
+        Some(PlanExplanation::Message)
+    );
+    assert!(plan_explanation_from_meta(Some(&json!({ "planExplanation": true }))).is_err());
+    assert!(plan_explanation_from_meta(Some(&json!({ "planExplanation": "loud" }))).is_err());
+}
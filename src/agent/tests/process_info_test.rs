@@ -0,0 +1,28 @@
+#![cfg(test)]
+
+use crate::agent::process_info::{format_bytes, format_uptime, parse_vm_rss};
+
+/// Uptime keeps the two most significant units.
+#[test]
+fn formats_uptime() {
+    assert_eq!(format_uptime(45), "45s");
+    assert_eq!(format_uptime(12 * 60 + 5), "12m 05s");
+    assert_eq!(format_uptime(3 * 3_600 + 7 * 60 + 59), "3h 07m");
+    assert_eq!(format_uptime(2 * 86_400 + 4 * 3_600), "2d 04h");
+}
+
+#[test]
+fn formats_bytes() {
+    assert_eq!(format_bytes(512 * 1024), "512 KiB");
+    assert_eq!(format_bytes(48 * 1024 * 1024 + 300 * 1024), "48.3 MiB");
+    assert_eq!(format_bytes(3 * 1024 * 1024 * 1024 / 2), "1.5 GiB");
+}
+
+/// `VmRSS` is reported in kB.
+#[test]
+fn parses_vm_rss() {
+    let status = "Name:\tcodex-acp\nVmPeak:\t  90000 kB\nVmRSS:\t   51200 kB\nThreads:\t4\n";
+    assert_eq!(parse_vm_rss(status), Some(51_200 * 1024));
+    assert_eq!(parse_vm_rss("Name:\tcodex-acp\n"), None);
+    assert_eq!(parse_vm_rss("VmRSS:\t12 pages\n"), None);
+}
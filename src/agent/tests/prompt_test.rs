@@ -0,0 +1,63 @@
+#![cfg(test)]
+
+use agent_client_protocol::{Error, PromptRequest, SessionId};
+use tokio::task::{self, LocalSet};
+
+use super::support::TestAgent;
+
+fn prompt_request(session_id: &SessionId) -> PromptRequest {
+    PromptRequest {
+        session_id: session_id.clone(),
+        prompt: vec!["hello".into()],
+        meta: None,
+    }
+}
+
+/// A prompt for a session that does not exist is refused before any turn runs.
+#[tokio::test]
+async fn prompt_for_unknown_session_is_refused() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestAgent::start();
+            let err = test
+                .agent
+                .prompt(prompt_request(&SessionId("missing".into())))
+                .await
+                .expect_err("unknown session");
+            assert_eq!(err.code, Error::invalid_params().code);
+        })
+        .await;
+}
+
+/// A second prompt for a session waits for its running turn, while prompts
+/// for other sessions go ahead.
+#[tokio::test]
+async fn prompts_wait_only_for_their_own_session() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestAgent::start();
+            let busy = test.add_session("busy");
+            let other = test.add_session("other");
+            let turn_lock = test
+                .agent
+                .sessions
+                .with("busy", |state| state.turn_lock.clone())
+                .expect("session");
+            let running_turn = turn_lock.lock().await;
+
+            let agent = test.agent.clone();
+            let queued =
+                task::spawn_local(async move { agent.prompt(prompt_request(&busy)).await });
+
+            // Without a Codex conversation the turn fails as soon as it starts;
+            // that it ends at all shows it did not wait for the busy session.
+            assert!(test.agent.prompt(prompt_request(&other)).await.is_err());
+            task::yield_now().await;
+            assert!(!queued.is_finished());
+
+            drop(running_turn);
+            assert!(queued.await.expect("task").is_err());
+            assert!(turn_lock.try_lock().is_ok());
+        })
+        .await;
+}
@@ -108,3 +108,38 @@ fn take_text_trims_trailing_whitespace_and_preserves_internal_newlines() {
     // trailing spaces on each section trimmed, but internal newlines preserved
     assert_eq!(out, "line1\nline2\n\n  line3\n\nline4");
 }
+
+/// Streaming mode hands deltas back right away and buffers nothing.
+#[test]
+fn streaming_forwards_deltas() {
+    let mut r = ReasoningAggregator::streaming();
+    assert!(r.is_streaming());
+    assert_eq!(r.stream_delta("thinking").as_deref(), Some("thinking"));
+    assert_eq!(r.stream_delta(" more").as_deref(), Some(" more"));
+    // The final text of a streamed item is not sent again.
+    assert_eq!(r.stream_item_end(Some("thinking more".into())), None);
+    assert_eq!(r.take_text(), None);
+}
+
+/// New sections after streamed text start with a blank line.
+#[test]
+fn streaming_separates_sections() {
+    let mut r = ReasoningAggregator::streaming();
+    r.stream_delta("first");
+    r.section_break();
+    assert_eq!(r.stream_delta("second").as_deref(), Some("\n\nsecond"));
+    r.stream_item_end(None);
+    // An item without deltas falls back to its final text.
+    assert_eq!(
+        r.stream_item_end(Some("third  ".into())).as_deref(),
+        Some("\n\nthird")
+    );
+}
+
+/// Without streaming, deltas are buffered as before.
+#[test]
+fn buffered_mode_keeps_deltas() {
+    let mut r = ReasoningAggregator::new();
+    assert_eq!(r.stream_delta("hello"), None);
+    assert_eq!(r.take_text().as_deref(), Some("hello"));
+}
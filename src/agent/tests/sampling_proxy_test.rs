@@ -0,0 +1,255 @@
+#![cfg(test)]
+
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    task::LocalSet,
+};
+
+use agent_client_protocol::{Error, PromptRequest, SessionId};
+
+use super::support::TestAgent;
+use crate::agent::{
+    sampling::SamplingOverrides,
+    sampling_proxy::{MAX_BODY_BYTES, SamplingProxy, with_sampling},
+};
+
+/// What the fake provider received.
+struct Received {
+    request_line: String,
+    authorization: Option<String>,
+    title: Option<String>,
+    body: Value,
+}
+
+/// A provider that records each request and answers with one SSE event.
+async fn fake_provider() -> (String, mpsc::UnboundedReceiver<Received>) {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await.expect("bind");
+    let base_url = format!("http://{}/v1", listener.local_addr().expect("address"));
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::task::spawn_local(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mut stream = BufReader::new(stream);
+            let mut request_line = String::new();
+            stream
+                .read_line(&mut request_line)
+                .await
+                .expect("request line");
+            let (mut length, mut authorization, mut title) = (0, None, None);
+            loop {
+                let mut line = String::new();
+                stream.read_line(&mut line).await.expect("header");
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let (name, value) = line.split_once(':').expect("header");
+                match name.to_ascii_lowercase().as_str() {
+                    "content-length" => length = value.trim().parse().expect("length"),
+                    "authorization" => authorization = Some(value.trim().to_string()),
+                    "x-title" => title = Some(value.trim().to_string()),
+                    _ => {}
+                }
+            }
+            let mut body = vec![0; length];
+            stream.read_exact(&mut body).await.expect("body");
+            let _ = tx.send(Received {
+                request_line: request_line.trim_end().to_string(),
+                authorization,
+                title,
+                body: serde_json::from_slice(&body).expect("json body"),
+            });
+            let event = "data: {\"done\":true}\n\n";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\n\r\n{event}",
+                event.len()
+            );
+            let _ = stream.get_mut().write_all(response.as_bytes()).await;
+        }
+    });
+    (base_url, rx)
+}
+
+async fn post(base_url: &str, body: Value) -> String {
+    reqwest::Client::new()
+        .post(format!("{base_url}/chat/completions"))
+        .bearer_auth("sk-test")
+        .json(&body)
+        .send()
+        .await
+        .expect("request")
+        .text()
+        .await
+        .expect("response")
+}
+
+/// The provider receives the overrides in the request body, and a change
+/// applies to the next request of the same session.
+#[tokio::test]
+async fn provider_receives_sampling_in_the_body() {
+    LocalSet::new()
+        .run_until(async {
+            let (upstream, mut received) = fake_provider().await;
+            let proxy = SamplingProxy::start().expect("proxy");
+            let sampling = SamplingOverrides {
+                temperature: Some(0.2),
+                top_p: Some(0.9),
+                seed: Some(7),
+            };
+            let base_url = proxy.add_route("fs-1", &upstream, sampling);
+
+            let body = json!({ "model": "llama3", "messages": [], "stream": true });
+            let answer = post(&base_url, body.clone()).await;
+            assert_eq!(answer, "data: {\"done\":true}\n\n");
+            let request = received.recv().await.expect("request");
+            assert_eq!(request.request_line, "POST /v1/chat/completions HTTP/1.1");
+            assert_eq!(request.authorization.as_deref(), Some("Bearer sk-test"));
+            assert_eq!(
+                request.body,
+                json!({
+                    "model": "llama3",
+                    "messages": [],
+                    "stream": true,
+                    "temperature": 0.2,
+                    "top_p": 0.9,
+                    "seed": 7,
+                })
+            );
+
+            assert!(proxy.set_sampling(
+                "fs-1",
+                SamplingOverrides {
+                    temperature: Some(1.1),
+                    ..Default::default()
+                }
+            ));
+            post(&base_url, body).await;
+            let request = received.recv().await.expect("request");
+            assert_eq!(request.body["temperature"], json!(1.1));
+            assert!(request.body.get("top_p").is_none());
+            assert!(request.body.get("seed").is_none());
+
+            assert!(!proxy.set_sampling("fs-unknown", SamplingOverrides::default()));
+        })
+        .await;
+}
+
+/// Header values that are not visible ASCII reach the provider unchanged.
+#[tokio::test]
+async fn provider_receives_header_bytes_unchanged() {
+    LocalSet::new()
+        .run_until(async {
+            let (upstream, mut received) = fake_provider().await;
+            let proxy = SamplingProxy::start().expect("proxy");
+            let base_url = proxy.add_route("fs-1", &upstream, SamplingOverrides::default());
+
+            let answer = reqwest::Client::new()
+                .post(format!("{base_url}/chat/completions"))
+                .header(
+                    "x-title",
+                    reqwest::header::HeaderValue::from_bytes("café".as_bytes()).expect("value"),
+                )
+                .json(&json!({ "model": "llama3" }))
+                .send()
+                .await
+                .expect("request");
+            assert_eq!(answer.status(), 200);
+            let request = received.recv().await.expect("request");
+            assert_eq!(request.title.as_deref(), Some("café"));
+        })
+        .await;
+}
+
+/// A body over the cap is refused before it is read, and never forwarded.
+#[tokio::test]
+async fn oversized_bodies_are_refused() {
+    LocalSet::new()
+        .run_until(async {
+            let (upstream, mut received) = fake_provider().await;
+            let proxy = SamplingProxy::start().expect("proxy");
+            let base_url = proxy.add_route("fs-1", &upstream, SamplingOverrides::default());
+            let address = base_url
+                .trim_start_matches("http://")
+                .split('/')
+                .next()
+                .expect("address")
+                .to_string();
+
+            let mut stream = TcpStream::connect(address).await.expect("connect");
+            let head = format!(
+                "POST /fs-1/chat/completions HTTP/1.1\r\nhost: proxy\r\n\
+                 content-length: {}\r\n\r\n",
+                MAX_BODY_BYTES + 1
+            );
+            stream.write_all(head.as_bytes()).await.expect("head");
+            let mut answer = String::new();
+            stream.read_to_string(&mut answer).await.expect("answer");
+            assert!(answer.starts_with("HTTP/1.1 413 "), "{answer}");
+            assert!(received.try_recv().is_err());
+        })
+        .await;
+}
+
+fn seeded_prompt(session_id: &SessionId, seed: u64) -> PromptRequest {
+    PromptRequest {
+        session_id: session_id.clone(),
+        prompt: vec!["hello".into()],
+        meta: Some(json!({ "seed": seed })),
+    }
+}
+
+/// A prompt's seed is sent to the provider for that turn only, and refused
+/// for sessions whose requests do not go through the proxy.
+#[tokio::test]
+async fn prompt_seeds_reach_the_provider_for_one_turn() {
+    LocalSet::new()
+        .run_until(async {
+            let (upstream, mut received) = fake_provider().await;
+            let test = TestAgent::start();
+            let session_id = test.add_session("a");
+            let proxy = SamplingProxy::start().expect("proxy");
+            let base_url = proxy.add_route("fs-a", &upstream, SamplingOverrides::default());
+            *test.agent.sampling_proxy.borrow_mut() = Some(proxy);
+            let body = json!({ "model": "llama3", "messages": [] });
+
+            let seed = test
+                .agent
+                .turn_seed(&seeded_prompt(&session_id, 42))
+                .expect("seed");
+            assert_eq!(seed, Some(42));
+            assert!(test.agent.apply_turn_seed(&session_id, seed));
+            post(&base_url, body.clone()).await;
+            let request = received.recv().await.expect("request");
+            assert_eq!(request.body["seed"], json!(42));
+
+            test.agent.restore_sampling(&session_id);
+            post(&base_url, body).await;
+            let request = received.recv().await.expect("request");
+            assert!(request.body.get("seed").is_none());
+
+            let unrouted = test.add_session("b");
+            let err = test
+                .agent
+                .turn_seed(&seeded_prompt(&unrouted, 42))
+                .expect_err("not routed");
+            assert_eq!(err.code, Error::invalid_params().code);
+        })
+        .await;
+}
+
+#[test]
+fn bodies_without_overrides_or_json_pass_unchanged() {
+    let sampling = SamplingOverrides {
+        seed: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(with_sampling(b"not json".to_vec(), &sampling), b"not json");
+    assert_eq!(with_sampling(b"[1,2]".to_vec(), &sampling), b"[1,2]");
+    let body = br#"{"model":"m"}"#.to_vec();
+    assert_eq!(
+        with_sampling(body.clone(), &SamplingOverrides::default()),
+        body
+    );
+}
@@ -0,0 +1,61 @@
+#![cfg(test)]
+
+use serde_json::json;
+
+use crate::agent::sampling::SamplingOverrides;
+
+/// `/sampling` arguments layer on top of existing defaults.
+#[test]
+fn parse_args_updates_only_given_keys() {
+    let base = SamplingOverrides {
+        temperature: Some(0.7),
+        ..Default::default()
+    };
+    let parsed = base.parse_args("top_p=0.9").expect("valid args");
+    assert_eq!(parsed.temperature, Some(0.7));
+    assert_eq!(parsed.top_p, Some(0.9));
+
+    let cleared = parsed
+        .parse_args("temperature=default")
+        .expect("valid args");
+    assert_eq!(cleared.temperature, None);
+}
+
+/// Out-of-range and malformed values are rejected.
+#[test]
+fn parse_args_rejects_invalid_values() {
+    let base = SamplingOverrides::default();
+    assert!(base.parse_args("temperature=3").is_err());
+    assert!(base.parse_args("top_p=0").is_err());
+    assert!(base.parse_args("temperature").is_err());
+    assert!(base.parse_args("seed=1").is_err());
+}
+
+/// Session meta accepts both camelCase and snake_case keys.
+#[test]
+fn from_meta_reads_sampling_object() {
+    let meta = json!({ "sampling": { "temperature": 0.2, "top_p": 0.5 } });
+    let parsed = SamplingOverrides::from_meta(Some(&meta))
+        .expect("valid meta")
+        .expect("sampling present");
+    assert_eq!(parsed.temperature, Some(0.2));
+    assert_eq!(parsed.top_p, Some(0.5));
+
+    assert!(
+        SamplingOverrides::from_meta(Some(&json!({})))
+            .expect("valid meta")
+            .is_none()
+    );
+    assert!(SamplingOverrides::from_meta(Some(&json!({ "sampling": { "topP": 2.0 } }))).is_err());
+}
+
+/// Seeds parse as integers and are forwarded alongside the other overrides.
+#[test]
+fn seed_is_parsed_and_described() {
+    let parsed = SamplingOverrides::default()
+        .parse_args("seed=42")
+        .expect("valid args");
+    assert_eq!(parsed.seed, Some(42));
+    assert!(parsed.describe().contains("seed=42"));
+    assert!(SamplingOverrides::default().parse_args("seed=-1").is_err());
+}
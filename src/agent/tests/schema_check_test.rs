@@ -0,0 +1,98 @@
+#![cfg(all(test, feature = "strict-schema"))]
+
+use std::path::{Path, PathBuf};
+
+use agent_client_protocol::{
+    ContentBlock, ContentChunk, SessionId, SessionNotification, SessionUpdate, TextContent,
+    ToolCall, ToolCallId, ToolCallLocation, ToolCallStatus, ToolKind,
+};
+use serde_json::json;
+
+use crate::{
+    agent::schema_check::{check_notification, check_permission_request},
+    fs::tools::write_approval::write_permission_request,
+};
+
+fn tool_call(title: &str, location: &str) -> SessionNotification {
+    SessionNotification {
+        session_id: SessionId("s1".into()),
+        update: SessionUpdate::ToolCall(ToolCall {
+            id: ToolCallId("call-1".into()),
+            title: title.to_string(),
+            kind: ToolKind::Read,
+            status: ToolCallStatus::Completed,
+            content: Vec::new(),
+            locations: vec![ToolCallLocation {
+                path: PathBuf::from(location),
+                line: None,
+                meta: None,
+            }],
+            raw_input: None,
+            raw_output: None,
+            meta: None,
+        }),
+        meta: None,
+    }
+}
+
+fn paths(violations: Vec<crate::agent::Violation>) -> Vec<String> {
+    violations.into_iter().map(|v| v.path).collect()
+}
+
+#[test]
+fn accepts_well_formed_updates() {
+    assert_eq!(
+        check_notification(&tool_call("Read a.rs", "/w/a.rs")),
+        Vec::new()
+    );
+    let chunk = SessionNotification {
+        session_id: SessionId("s1".into()),
+        update: SessionUpdate::AgentMessageChunk(ContentChunk {
+            content: ContentBlock::Text(TextContent {
+                annotations: None,
+                text: String::new(),
+                meta: Some(json!({ "format": "markdown" })),
+            }),
+            meta: None,
+        }),
+        meta: None,
+    };
+    assert_eq!(check_notification(&chunk), Vec::new());
+}
+
+/// Empty titles, relative paths and non-object meta are reported by path.
+#[test]
+fn reports_violations_by_path() {
+    let mut notification = tool_call("", "a.rs");
+    notification.meta = Some(json!("trace"));
+    assert_eq!(
+        paths(check_notification(&notification)),
+        vec![
+            "_meta",
+            "update[tool_call].title",
+            "update[tool_call].locations[0].path",
+        ]
+    );
+}
+
+#[test]
+fn checks_permission_requests() {
+    let request = |path: &str| {
+        write_permission_request(
+            SessionId("s1".into()),
+            "call-1".to_string(),
+            Path::new(path),
+            None,
+            "new".to_string(),
+        )
+    };
+    assert_eq!(check_permission_request(&request("/w/a.rs")), Vec::new());
+    assert_eq!(
+        paths(check_permission_request(&request("a.rs"))),
+        vec!["toolCall.content[0].path", "toolCall.locations[0].path"]
+    );
+
+    let mut empty = request("/w/a.rs");
+    empty.options.clear();
+    assert_eq!(paths(check_permission_request(&empty)), vec!["options"]);
+}
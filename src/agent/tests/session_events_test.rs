@@ -0,0 +1,148 @@
+#![cfg(test)]
+
+use std::path::PathBuf;
+
+use agent_client_protocol as acp;
+use futures::StreamExt;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::agent::session_events::{
+    SessionEvent, SessionEvents, StepState, ToolCallEvent, ToolStatus,
+};
+
+fn notification(update: acp::SessionUpdate) -> acp::SessionNotification {
+    acp::SessionNotification {
+        session_id: acp::SessionId("s1".into()),
+        update,
+        meta: None,
+    }
+}
+
+/// ACP updates become typed events; updates without a variant are skipped.
+#[test]
+fn converts_notifications() {
+    let chunk = notification(acp::SessionUpdate::AgentMessageChunk(acp::ContentChunk {
+        content: "hello".into(),
+        meta: None,
+    }));
+    assert_eq!(
+        SessionEvent::from_notification(&chunk),
+        Some(SessionEvent::MessageChunk {
+            session_id: "s1".to_string(),
+            text: "hello".to_string(),
+        })
+    );
+
+    let update = notification(acp::SessionUpdate::ToolCallUpdate(acp::ToolCallUpdate {
+        id: acp::ToolCallId("call-1".into()),
+        fields: acp::ToolCallUpdateFields {
+            status: Some(acp::ToolCallStatus::Completed),
+            content: Some(vec![
+                "done".into(),
+                acp::ToolCallContent::from(acp::Diff {
+                    path: PathBuf::from("/work/a.rs"),
+                    old_text: None,
+                    new_text: "fn main() {}\n".to_string(),
+                    meta: None,
+                }),
+            ]),
+            ..Default::default()
+        },
+        meta: None,
+    }));
+    assert_eq!(
+        SessionEvent::from_notification(&update),
+        Some(SessionEvent::ToolCall {
+            session_id: "s1".to_string(),
+            call: ToolCallEvent {
+                id: "call-1".to_string(),
+                started: false,
+                title: None,
+                kind: None,
+                status: Some(ToolStatus::Completed),
+                output: vec!["done".to_string()],
+                edited: vec![PathBuf::from("/work/a.rs")],
+            },
+        })
+    );
+
+    let plan = notification(acp::SessionUpdate::Plan(acp::Plan {
+        entries: vec![acp::PlanEntry {
+            content: "Write tests".to_string(),
+            priority: acp::PlanEntryPriority::Medium,
+            status: acp::PlanEntryStatus::InProgress,
+            meta: None,
+        }],
+        meta: None,
+    }));
+    let Some(SessionEvent::Plan { steps, .. }) = SessionEvent::from_notification(&plan) else {
+        panic!("expected a plan event");
+    };
+    assert_eq!(steps[0].text, "Write tests");
+    assert_eq!(steps[0].status, StepState::InProgress);
+
+    let mode = notification(acp::SessionUpdate::CurrentModeUpdate(
+        acp::CurrentModeUpdate {
+            current_mode_id: acp::SessionModeId("read-only".into()),
+            meta: None,
+        },
+    ));
+    assert_eq!(
+        SessionEvent::from_notification(&mode),
+        Some(SessionEvent::ModeChanged {
+            session_id: "s1".to_string(),
+            mode_id: "read-only".to_string(),
+        })
+    );
+
+    let commands = notification(acp::SessionUpdate::AvailableCommandsUpdate(
+        acp::AvailableCommandsUpdate {
+            available_commands: Vec::new(),
+            meta: None,
+        },
+    ));
+    assert_eq!(SessionEvent::from_notification(&commands), None);
+}
+
+/// The stream acknowledges updates and reports turn ends after them.
+#[tokio::test]
+async fn acknowledges_updates_in_order() {
+    let (update_tx, updates) = mpsc::unbounded_channel();
+    let (turn_tx, turns) = mpsc::unbounded_channel();
+    let mut events = SessionEvents::new(updates, turns);
+
+    let (ack_tx, ack_rx) = oneshot::channel();
+    update_tx
+        .send((
+            notification(acp::SessionUpdate::AgentThoughtChunk(acp::ContentChunk {
+                content: "thinking".into(),
+                meta: None,
+            })),
+            ack_tx,
+        ))
+        .expect("send update");
+    turn_tx
+        .send(SessionEvent::TurnEnded {
+            session_id: "s1".to_string(),
+            stop_reason: Some(acp::StopReason::EndTurn),
+            error: None,
+        })
+        .expect("send turn end");
+
+    assert!(matches!(
+        events.next().await,
+        Some(SessionEvent::Thought { text, .. }) if text == "thinking"
+    ));
+    ack_rx.await.expect("update acknowledged");
+    assert!(matches!(
+        events.next().await,
+        Some(SessionEvent::TurnEnded {
+            stop_reason: Some(acp::StopReason::EndTurn),
+            ..
+        })
+    ));
+
+    drop(update_tx);
+    drop(turn_tx);
+    assert_eq!(events.next().await, None);
+}
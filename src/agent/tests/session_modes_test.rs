@@ -127,3 +127,50 @@ fn is_custom_provider_detection() {
 // Note: Tests for available_models_from_profiles would require constructing
 // a CodexConfig which doesn't have a Default implementation. These tests
 // would be better as integration tests with a real config file.
+
+/// Reasoning summary settings parse case-insensitively and reject unknown values.
+#[test]
+fn parse_reasoning_summary_values() {
+    use codex_core::protocol_config_types::ReasoningSummary;
+
+    assert_eq!(
+        session::parse_reasoning_summary("Detailed"),
+        Some(ReasoningSummary::Detailed)
+    );
+    assert_eq!(
+        session::parse_reasoning_summary(" none "),
+        Some(ReasoningSummary::None)
+    );
+    assert_eq!(session::parse_reasoning_summary("verbose"), None);
+}
+
+/// Verbosity labels round-trip through the parser.
+#[test]
+fn verbosity_labels_round_trip() {
+    use codex_core::protocol_config_types::Verbosity;
+
+    for verbosity in [Verbosity::Low, Verbosity::Medium, Verbosity::High] {
+        let label = session::verbosity_label(verbosity);
+        assert_eq!(session::parse_verbosity(label), Some(verbosity));
+    }
+    assert_eq!(session::parse_verbosity("HIGH"), Some(Verbosity::High));
+    assert_eq!(session::parse_verbosity("max"), None);
+}
+
+/// The synthetic dry-run mode is advertised and layered over a read-only sandbox.
+#[test]
+fn dry_run_mode_is_read_only_and_asks() {
+    use codex_core::protocol::{AskForApproval, SandboxPolicy};
+
+    let mode_id = acp::SessionModeId(session::DRY_RUN_MODE_ID.into());
+    assert!(
+        session::available_modes()
+            .iter()
+            .any(|mode| mode.id == mode_id)
+    );
+    let preset = session::find_preset_by_mode_id(&mode_id).expect("dry-run preset");
+    assert_eq!(preset.approval, AskForApproval::UnlessTrusted);
+    assert_eq!(preset.sandbox, SandboxPolicy::new_read_only_policy());
+    assert!(session::is_dry_run_mode(&mode_id));
+    assert!(!session::is_read_only_mode(&mode_id));
+}
@@ -0,0 +1,125 @@
+#![cfg(test)]
+
+use agent_client_protocol::{Error, SessionModeId};
+use codex_core::config::{Config, ConfigOverrides, ConfigToml};
+
+use crate::agent::{session::SessionState, session_store::SessionStore};
+
+fn state(fs_session_id: &str) -> SessionState {
+    let home =
+        std::env::temp_dir().join(format!("codex-acp-session-store-{}", uuid::Uuid::new_v4()));
+    let config = Config::load_from_base_config_with_overrides(
+        ConfigToml::default(),
+        ConfigOverrides::default(),
+        home,
+    )
+    .expect("config");
+    SessionState::new(
+        fs_session_id.to_string(),
+        None,
+        &config,
+        SessionModeId("auto".into()),
+    )
+}
+
+#[test]
+fn accessors_return_owned_results() {
+    let store = SessionStore::default();
+    store.insert("acp-1".to_string(), state("fs-1"));
+
+    assert_eq!(store.count(), 1);
+    assert!(store.contains("acp-1"));
+    assert_eq!(store.resolve("fs-1").as_deref(), Some("acp-1"));
+    assert_eq!(store.resolve("acp-1").as_deref(), Some("acp-1"));
+    assert_eq!(store.resolve("other"), None);
+
+    store.with_mut("acp-1", |state| state.attachments.push("a.rs".to_string()));
+    assert_eq!(
+        store.with("acp-1", |state| state.attachments.clone()),
+        Some(vec!["a.rs".to_string()])
+    );
+    assert_eq!(store.with("missing", |_| ()), None);
+}
+
+/// Changes made while the table is in use wait until the access ends.
+#[test]
+fn mutations_inside_accessors_are_queued() {
+    let store = SessionStore::default();
+    store.insert("acp-1".to_string(), state("fs-1"));
+
+    let inner = store.clone();
+    store.with("acp-1", |_| {
+        inner.update("acp-1", |state| state.steered = true);
+        inner.insert("acp-2".to_string(), state("fs-2"));
+    });
+    assert_eq!(store.with("acp-1", |state| state.steered), Some(true));
+    assert!(store.contains("acp-2"));
+
+    let inner = store.clone();
+    store.with_mut("acp-2", |state| {
+        state.budget_override = true;
+        inner.update("acp-2", |state| state.budget_override = false);
+    });
+    assert_eq!(
+        store.with("acp-2", |state| state.budget_override),
+        Some(false)
+    );
+}
+
+/// Reads nest inside reads; a missing session is reported as such.
+#[test]
+fn reads_nest_inside_reads() {
+    let store = SessionStore::default();
+    store.insert("acp-1".to_string(), state("fs-1"));
+
+    let inner = store.clone();
+    let nested = store
+        .try_with("acp-1", |_| inner.try_with("acp-1", |state| state.steered))
+        .expect("outer access");
+    assert_eq!(nested.ok(), Some(false));
+
+    let err = store.try_with("missing", |_| ()).expect_err("missing");
+    assert_eq!(err.code, Error::invalid_params().code);
+}
+
+/// In release builds, reaching the table during a mutable access fails with
+/// its own error, not as a missing session.
+#[cfg(not(debug_assertions))]
+#[test]
+fn reentrant_access_is_not_reported_as_missing() {
+    let store = SessionStore::default();
+    store.insert("acp-1".to_string(), state("fs-1"));
+
+    let inner = store.clone();
+    let nested = store
+        .try_with_mut("acp-1", |_| inner.try_with("acp-1", |_| ()))
+        .expect("outer access");
+    let err = nested.expect_err("table in use");
+    assert_eq!(err.code, Error::internal_error().code);
+}
+
+/// In debug builds, a mutable access from inside a read panics.
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "session store reentered by with_mut")]
+fn with_mut_inside_with_panics() {
+    let store = SessionStore::default();
+    store.insert("acp-1".to_string(), state("fs-1"));
+
+    let inner = store.clone();
+    store.with("acp-1", |_| {
+        inner.with_mut("acp-1", |state| state.steered = true)
+    });
+}
+
+/// In debug builds, a read from inside a mutable access panics.
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "session store reentered by with")]
+fn with_inside_with_mut_panics() {
+    let store = SessionStore::default();
+    store.insert("acp-1".to_string(), state("fs-1"));
+
+    let inner = store.clone();
+    store.with_mut("acp-1", |_| inner.with("acp-1", |state| state.steered));
+}
@@ -0,0 +1,52 @@
+#![cfg(test)]
+
+use std::path::PathBuf;
+
+use serde_json::json;
+
+use crate::agent::shell_env::{ShellParity, parse_env_file};
+
+/// Env files accept comments, `export` prefixes, and quoted values.
+#[test]
+fn parse_env_file_handles_common_syntax() {
+    let vars = parse_env_file(
+        "# toolchain\nexport PATH=\"/opt/bin:/usr/bin\"\nNODE_ENV='development'\n\nbad line\nFOO = bar\n",
+    );
+    assert_eq!(
+        vars,
+        vec![
+            ("PATH".to_string(), "/opt/bin:/usr/bin".to_string()),
+            ("NODE_ENV".to_string(), "development".to_string()),
+            ("FOO".to_string(), "bar".to_string()),
+        ]
+    );
+}
+
+/// Session meta selects the login shell and env file.
+#[test]
+fn from_meta_reads_shell_settings() {
+    let meta = json!({ "shell": { "loginShell": true, "envFile": "/tmp/dev.env" } });
+    let shell = ShellParity::from_meta(Some(&meta))
+        .expect("valid meta")
+        .expect("shell present");
+    assert!(shell.login_shell);
+    assert_eq!(shell.env_file, Some(PathBuf::from("/tmp/dev.env")));
+
+    assert!(ShellParity::from_meta(Some(&json!({}))).unwrap().is_none());
+    assert!(ShellParity::from_meta(Some(&json!({ "shell": { "loginShell": "yes" } }))).is_err());
+}
+
+/// The summary shown in `/status` names the env file and variable count.
+#[test]
+fn describe_reports_mode() {
+    assert_eq!(ShellParity::default().describe(), "default");
+    let shell = ShellParity {
+        login_shell: true,
+        env_file: Some(PathBuf::from("/tmp/dev.env")),
+        loaded_vars: 2,
+    };
+    assert_eq!(
+        shell.describe(),
+        "login shell profile, env file /tmp/dev.env (2 vars)"
+    );
+}
@@ -0,0 +1,51 @@
+#![cfg(test)]
+
+use codex_core::protocol::TokenUsage;
+
+use crate::agent::spend::{BudgetPeriod, SpendBudget, SpendLedger, price_for};
+
+/// Cached input is billed at the cached rate and the rest at the input rate.
+#[test]
+fn price_cost_splits_cached_input() {
+    let price = price_for("openai@gpt-5-codex").expect("built-in price");
+    let usage = TokenUsage {
+        input_tokens: 1_000_000,
+        cached_input_tokens: 400_000,
+        output_tokens: 100_000,
+        reasoning_output_tokens: 0,
+        total_tokens: 1_100_000,
+    };
+    let cost = price.cost(&usage);
+    assert!((cost - (0.6 * 1.25 + 0.4 * 0.125 + 0.1 * 10.0)).abs() < 1e-9);
+    assert!(price_for("ollama@llama3.1").is_none());
+}
+
+/// Weekly totals cover the seven days ending today; old days are pruned.
+#[test]
+fn ledger_sums_daily_and_weekly() {
+    let mut ledger = SpendLedger::default();
+    ledger.add(100, 1.0);
+    ledger.add(106, 2.0);
+    ledger.add(107, 0.5);
+    assert_eq!(ledger.daily(107), 0.5);
+    assert_eq!(ledger.weekly(107), 2.5);
+    ledger.add(130, 0.1);
+    assert_eq!(ledger.weekly(107), 0.0);
+}
+
+/// The first exhausted period is reported with its spend and limit.
+#[test]
+fn budget_reports_exhausted_period() {
+    let mut ledger = SpendLedger::default();
+    ledger.add(10, 4.0);
+    ledger.add(11, 3.0);
+    let budget = SpendBudget {
+        daily: Some(5.0),
+        weekly: Some(6.0),
+    };
+    assert_eq!(
+        budget.exceeded(&ledger, 11),
+        Some((BudgetPeriod::Weekly, 7.0, 6.0))
+    );
+    assert_eq!(budget.exceeded(&ledger, 20), None);
+}
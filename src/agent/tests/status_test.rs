@@ -0,0 +1,184 @@
+#![cfg(test)]
+
+use std::collections::BTreeMap;
+
+use codex_core::protocol::TokenUsage;
+
+use crate::agent::{
+    process_info::ProcessInfo,
+    spend::SpendStatus,
+    status::{
+        AccountStatus, ModelStatus, StatusReport, TokenStatus, WorkspaceStatus, format_status,
+        title_case,
+    },
+};
+
+fn usage(input: u64, output: u64) -> TokenUsage {
+    TokenUsage {
+        input_tokens: input,
+        output_tokens: output,
+        total_tokens: input + output,
+        ..Default::default()
+    }
+}
+
+fn report() -> StatusReport {
+    StatusReport {
+        session_id: "s-1".to_string(),
+        workspace: WorkspaceStatus {
+            path: "~/proj".to_string(),
+            approval_mode: "on-request".to_string(),
+            sandbox: "workspace-write".to_string(),
+            shell: "default".to_string(),
+        },
+        account: AccountStatus {
+            auth_mode: "API key".to_string(),
+            email: None,
+            plan: None,
+        },
+        model: ModelStatus {
+            name: "gpt-5".to_string(),
+            provider: "openai".to_string(),
+            reasoning_effort: "medium".to_string(),
+            reasoning_summaries: "auto".to_string(),
+            verbosity: None,
+            response_language: None,
+        },
+        tokens: TokenStatus {
+            usage: usage(10, 5),
+            limit: None,
+            by_model: BTreeMap::new(),
+        },
+        spend: None,
+        attachments: Vec::new(),
+        process: ProcessInfo {
+            pid: 4242,
+            version: "0.1.0".to_string(),
+            uptime_secs: 3_725,
+            rss_bytes: None,
+            open_sessions: 2,
+            bridge_connections: 1,
+        },
+    }
+}
+
+#[test]
+fn title_case_upper_cases_the_first_character() {
+    assert_eq!(title_case("openai"), "Openai");
+    assert_eq!(title_case(""), "");
+}
+
+/// The text fallback fills unknown values with placeholders.
+#[test]
+fn formats_text_fallback() {
+    let text = format_status(&report());
+    assert!(text.contains("Path:          ~/proj"));
+    assert!(text.contains("Login:          (none)"));
+    assert!(text.contains("Provider:            Openai"));
+    assert!(text.contains("Verbosity:           Default"));
+    assert!(text.contains("Total:          15"));
+    assert!(!text.contains("Spend Budget"));
+    assert!(!text.contains("By model"));
+    assert!(text.contains("PID:                4242"));
+    assert!(text.contains("Uptime:             1h 02m"));
+    assert!(text.contains("Memory (RSS):       (unknown)"));
+}
+
+/// Optional sections appear when there is something to show.
+#[test]
+fn formats_optional_sections() {
+    let mut report = report();
+    report.tokens.limit = Some(1000);
+    report.tokens.by_model = BTreeMap::from([
+        ("openai@gpt-5".to_string(), usage(8, 4)),
+        ("ollama@llama3".to_string(), usage(2, 1)),
+    ]);
+    report.spend = Some(SpendStatus {
+        daily_spent_usd: 1.2,
+        daily_limit_usd: Some(5.0),
+        weekly_spent_usd: 3.0,
+        weekly_limit_usd: None,
+    });
+    report.attachments = vec!["src/lib.rs".to_string()];
+    let text = format_status(&report);
+    assert!(text.contains("Limit:          1000"));
+    assert!(text.contains("Today:     $1.20 of $5.00 ($3.80 left)"));
+    assert!(text.contains("This week: $3.00\n"));
+    assert!(text.contains("ollama@llama3: 2 in / 1 out / 3 total"));
+    assert!(text.contains("    src/lib.rs"));
+}
+
+/// The structured report uses camelCase keys and nulls for unknown values.
+#[test]
+fn serializes_structured_report() {
+    let value = serde_json::to_value(report()).unwrap();
+    assert_eq!(value["sessionId"], "s-1");
+    assert_eq!(value["workspace"]["approvalMode"], "on-request");
+    assert!(value["account"]["email"].is_null());
+    assert_eq!(value["model"]["reasoningEffort"], "medium");
+    assert_eq!(value["tokens"]["usage"]["total_tokens"], 15);
+    assert!(value["spend"].is_null());
+    assert_eq!(value["process"]["openSessions"], 2);
+    assert!(value["process"]["rssBytes"].is_null());
+}
+
+/// The client's markdown flag is read from either meta.
+#[test]
+fn report_format_from_client_meta() {
+    use crate::agent::status::ReportFormat;
+    use serde_json::json;
+
+    assert_eq!(ReportFormat::from_meta(None, None), ReportFormat::Plain);
+    let yes = json!({ "clientRendersMarkdown": true });
+    assert_eq!(
+        ReportFormat::from_meta(None, Some(&yes)),
+        ReportFormat::Markdown
+    );
+    let no = json!({ "client_renders_markdown": false });
+    assert_eq!(
+        ReportFormat::from_meta(Some(&no), Some(&yes)),
+        ReportFormat::Plain
+    );
+    assert_eq!(ReportFormat::parse(" MD "), Some(ReportFormat::Markdown));
+    assert_eq!(ReportFormat::parse("html"), None);
+}
+
+/// Markdown reports use headings and a usage table with a bold total.
+#[test]
+fn formats_markdown_tables() {
+    use crate::agent::status::{ReportFormat, render_stats, render_status};
+
+    let mut report = report();
+    report.tokens.by_model = BTreeMap::from([("openai@gpt-5".to_string(), usage(10, 5))]);
+    report.spend = Some(SpendStatus {
+        daily_spent_usd: 1.0,
+        daily_limit_usd: Some(4.0),
+        weekly_spent_usd: 2.0,
+        weekly_limit_usd: None,
+    });
+    let status = render_status(&report, ReportFormat::Markdown);
+    assert!(status.contains("### 🧠 Model"));
+    assert!(status.contains("- **Provider:** Openai"));
+    assert!(status.contains("| Model | Input | Cached | Output | Reasoning | Total |"));
+    assert!(status.contains("| openai@gpt-5 | 10 | 0 | 5 | 0 | 15 |"));
+    assert!(status.contains("| **Total** | **10** |"));
+    assert!(status.contains("| Today | $1.00 | $4.00 | $3.00 |"));
+
+    let stats = render_stats(&report, ReportFormat::Markdown);
+    assert!(stats.starts_with("### 📊 Token Usage"));
+    assert!(!stats.contains("Workspace"));
+}
+
+/// Plain stats align the usage columns.
+#[test]
+fn formats_plain_stats() {
+    use crate::agent::status::{ReportFormat, render_stats};
+
+    let mut report = report();
+    report.tokens.by_model = BTreeMap::from([("openai@gpt-5".to_string(), usage(10, 5))]);
+    let stats = render_stats(&report, ReportFormat::Plain);
+    let lines: Vec<&str> = stats.lines().filter(|l| l.contains("Total")).collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].len(), lines[1].len());
+    assert!(stats.contains("openai@gpt-5"));
+}
@@ -0,0 +1,68 @@
+#![cfg(test)]
+
+use serde_json::json;
+
+use crate::agent::{
+    context::ContextItem,
+    steering::{MAX_STEER_OUTPUT, Steering, steer_context, steered_meta, steering_from_meta},
+    store::TurnStatus,
+};
+
+#[test]
+fn parses_steering_modes() {
+    assert_eq!(Steering::parse("queue"), Some(Steering::Queue));
+    assert_eq!(Steering::parse(" Interrupt "), Some(Steering::Interrupt));
+    assert_eq!(Steering::parse("replace"), None);
+    assert_eq!(Steering::default(), Steering::Queue);
+}
+
+#[test]
+fn reads_steering_from_meta() {
+    assert_eq!(steering_from_meta(None).unwrap(), None);
+    assert_eq!(
+        steering_from_meta(Some(&json!({ "steering": "interrupt" }))).unwrap(),
+        Some(Steering::Interrupt)
+    );
+    assert_eq!(
+        steering_from_meta(Some(&json!({ "steering": null }))).unwrap(),
+        None
+    );
+    assert!(steering_from_meta(Some(&json!({ "steering": true }))).is_err());
+}
+
+#[test]
+fn steered_turns_are_marked() {
+    assert_eq!(steered_meta(), json!({ "stopReason": "steered" }));
+    assert_eq!(
+        serde_json::to_value(TurnStatus::Steered).unwrap(),
+        json!("steered")
+    );
+}
+
+#[test]
+fn context_carries_the_replaced_request_and_answer() {
+    let ContextItem::Text { text, label } = steer_context("Add tests", "Reading src/lib.rs…\n")
+    else {
+        panic!("expected a text context item");
+    };
+    assert_eq!(label.as_deref(), Some("interrupted-turn"));
+    assert!(text.contains("Previous request:\nAdd tests"));
+    assert!(text.ends_with("Your answer so far:\nReading src/lib.rs…"));
+
+    let ContextItem::Text { text, .. } = steer_context("Add tests", "  ") else {
+        panic!("expected a text context item");
+    };
+    assert!(text.ends_with("(nothing yet)"));
+}
+
+#[test]
+fn long_answers_keep_their_tail() {
+    let output = format!("{}end", "é".repeat(MAX_STEER_OUTPUT));
+    let ContextItem::Text { text, .. } = steer_context("Refactor", &output) else {
+        panic!("expected a text context item");
+    };
+    let answer = text.split("Your answer so far:\n").nth(1).unwrap();
+    assert!(answer.starts_with('…'));
+    assert!(answer.ends_with("end"));
+    assert!(answer.len() <= MAX_STEER_OUTPUT + '…'.len_utf8());
+}
@@ -0,0 +1,60 @@
+#![cfg(test)]
+
+use agent_client_protocol::StopReason;
+
+use crate::agent::stop_reason::{classify, is_refusal_message, stop_meta};
+
+/// Incomplete responses map to the matching stop reason.
+#[test]
+fn classifies_incomplete_response_errors() {
+    assert!(matches!(
+        classify(
+            Some("Incomplete response returned, reason: max_output_tokens"),
+            ""
+        ),
+        Some(StopReason::MaxTokens)
+    ));
+    assert!(matches!(
+        classify(
+            Some("Incomplete response returned, reason: content_filter"),
+            ""
+        ),
+        Some(StopReason::Refusal)
+    ));
+    assert!(classify(Some("rate limit exceeded"), "").is_none());
+}
+
+/// Only a short reply that opens by declining counts as a refusal.
+#[test]
+fn detects_bare_refusals() {
+    assert!(is_refusal_message("I’m sorry, but I can’t help with that."));
+    assert!(is_refusal_message("  I cannot assist with that request.\n"));
+    assert!(!is_refusal_message(
+        "Done. I can't help with that other file though."
+    ));
+    let long = format!(
+        "I'm sorry, but I can't do X. {}",
+        "Here is why. ".repeat(30)
+    );
+    assert!(!is_refusal_message(&long));
+    assert!(matches!(
+        classify(None, "I'm sorry, but I can't help with that."),
+        Some(StopReason::Refusal)
+    ));
+    assert!(classify(None, "All tests pass.").is_none());
+}
+
+/// The meta carries the reason, Codex's message, and guidance.
+#[test]
+fn stop_meta_explains_the_stop() {
+    let meta = stop_meta(&StopReason::MaxTokens, Some("reason: max_output_tokens")).unwrap();
+    assert_eq!(meta["stopDetail"]["reason"], "max_tokens");
+    assert_eq!(meta["stopDetail"]["message"], "reason: max_output_tokens");
+    assert!(
+        meta["stopDetail"]["guidance"]
+            .as_str()
+            .unwrap()
+            .contains("continue")
+    );
+    assert!(stop_meta(&StopReason::EndTurn, None).is_none());
+}
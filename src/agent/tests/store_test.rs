@@ -0,0 +1,98 @@
+#![cfg(test)]
+
+use crate::agent::store::{
+    JournalEntry, MAX_JOURNAL_ENTRIES, MAX_JOURNAL_TEXT, RecordStore, SessionRecord, TurnStatus,
+    validate_session_id,
+};
+
+fn entry(submit_id: &str, status: TurnStatus, output: &str) -> JournalEntry {
+    JournalEntry {
+        submit_id: submit_id.to_string(),
+        finished_at: 0,
+        model: Some("openai@gpt-5".to_string()),
+        prompt: "fix the build".to_string(),
+        output: output.to_string(),
+        status,
+        error: (status == TurnStatus::Failed).then(|| "stream disconnected".to_string()),
+        seed: None,
+        commands: Vec::new(),
+    }
+}
+
+/// Journal entries are appended in order and keep partial output of failed turns.
+#[tokio::test]
+async fn journal_round_trip_keeps_partial_output() {
+    let home = std::env::temp_dir().join(format!("codex-acp-store-{}", uuid::Uuid::new_v4()));
+    let store = RecordStore::new(&home);
+
+    store
+        .append_journal("s1", &entry("1", TurnStatus::Completed, "done"))
+        .await
+        .expect("append");
+    store
+        .append_journal("s1", &entry("2", TurnStatus::Failed, "half an ans"))
+        .await
+        .expect("append");
+
+    let journal = store.load_journal("s1").await;
+    assert_eq!(journal.len(), 2);
+    assert_eq!(journal[1].status, TurnStatus::Failed);
+    assert_eq!(journal[1].output, "half an ans");
+    assert!(store.load_journal("missing").await.is_empty());
+
+    let _ = std::fs::remove_dir_all(home);
+}
+
+/// Session ids that would leave the store directory are refused.
+#[tokio::test]
+async fn traversing_session_ids_are_refused() {
+    let home = std::env::temp_dir().join(format!("codex-acp-store-{}", uuid::Uuid::new_v4()));
+    let store = RecordStore::new(&home);
+
+    for id in ["../escape", "..", "a/b", "/tmp/x", "a\\b", ""] {
+        assert!(validate_session_id(id).is_err(), "{id:?}");
+        assert!(store.save(id, &SessionRecord::default()).await.is_err());
+        assert!(
+            store
+                .append_journal(id, &entry("1", TurnStatus::Completed, "done"))
+                .await
+                .is_err()
+        );
+        assert!(store.load(id).await.is_none());
+        assert!(store.load_journal(id).await.is_empty());
+    }
+    assert!(!home.join("acp").join("escape.json").exists());
+    assert!(!home.join("acp").join("escape.journal.jsonl").exists());
+
+    let uuid = uuid::Uuid::new_v4().to_string();
+    store
+        .save(&uuid, &SessionRecord::default())
+        .await
+        .expect("save");
+    assert!(store.load(&uuid).await.is_some());
+
+    let _ = std::fs::remove_dir_all(home);
+}
+
+/// The journal keeps the newest turns and cuts long prompts and output.
+#[tokio::test]
+async fn journal_is_capped() {
+    let home = std::env::temp_dir().join(format!("codex-acp-store-{}", uuid::Uuid::new_v4()));
+    let store = RecordStore::new(&home);
+
+    let long = "x".repeat(MAX_JOURNAL_TEXT * 2);
+    for n in 0..MAX_JOURNAL_ENTRIES + 5 {
+        let mut turn = entry(&n.to_string(), TurnStatus::Completed, &long);
+        turn.prompt = long.clone();
+        store.append_journal("s1", &turn).await.expect("append");
+    }
+
+    let journal = store.load_journal("s1").await;
+    assert_eq!(journal.len(), MAX_JOURNAL_ENTRIES);
+    assert_eq!(journal[0].submit_id, "5");
+    assert!(journal[0].prompt.ends_with('…'));
+    assert!(journal[0].output.starts_with('…'));
+    assert!(journal[0].prompt.len() <= MAX_JOURNAL_TEXT + '…'.len_utf8());
+
+    let _ = std::fs::remove_dir_all(home);
+}
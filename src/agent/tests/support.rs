@@ -0,0 +1,101 @@
+#![cfg(test)]
+
+//! Setup shared by tests that drive a whole [`CodexAgent`].
+
+use std::{cell::RefCell, collections::HashMap, path::PathBuf, rc::Rc};
+
+use agent_client_protocol::{
+    ContentBlock, ContentChunk, SessionId, SessionModeId, SessionNotification, SessionUpdate,
+};
+use codex_core::config::{Config, ConfigOverrides, ConfigToml};
+use tokio::{sync::mpsc::UnboundedReceiver, task};
+
+use crate::{
+    agent::{CodexAgent, session::SessionState},
+    fs::ClientOp,
+};
+
+/// An agent on fresh directories, without an FS bridge.
+pub struct TestAgent {
+    pub agent: CodexAgent,
+    /// The agent's `codex_home`; its working directory is `work` inside.
+    pub home: PathBuf,
+    /// Requests the agent sent towards the client, left unanswered.
+    pub client_rx: UnboundedReceiver<ClientOp>,
+    /// Session updates sent so far, each acknowledged as delivered.
+    pub updates: Rc<RefCell<Vec<SessionNotification>>>,
+}
+
+impl TestAgent {
+    /// Create the agent. Must run inside a `LocalSet`.
+    pub fn start() -> Self {
+        let home = std::env::temp_dir().join(format!("codex-acp-agent-{}", uuid::Uuid::new_v4()));
+        let cwd = home.join("work");
+        std::fs::create_dir_all(&cwd).expect("working directory");
+        let config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides {
+                cwd: Some(cwd),
+                ..Default::default()
+            },
+            home.clone(),
+        )
+        .expect("config");
+        let (update_tx, mut update_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (client_tx, client_rx) = tokio::sync::mpsc::unbounded_channel();
+        let agent = CodexAgent::with_config(update_tx, client_tx, config, HashMap::new(), None);
+
+        let updates = Rc::<RefCell<Vec<SessionNotification>>>::default();
+        let received = updates.clone();
+        task::spawn_local(async move {
+            while let Some((notification, delivered)) = update_rx.recv().await {
+                received.borrow_mut().push(notification);
+                let _ = delivered.send(());
+            }
+        });
+        Self {
+            agent,
+            home,
+            client_rx,
+            updates,
+        }
+    }
+
+    /// Register a session the way `session/new` does, without a Codex
+    /// conversation behind it.
+    pub fn add_session(&self, id: &str) -> SessionId {
+        let state = SessionState::new(
+            format!("fs-{id}"),
+            None,
+            &self.agent.config,
+            SessionModeId("auto".into()),
+        );
+        self.agent.sessions.insert(id.to_string(), state);
+        SessionId(id.into())
+    }
+
+    /// Text of the user and agent message chunks sent so far, in order.
+    pub fn messages(&self) -> Vec<String> {
+        self.updates
+            .borrow()
+            .iter()
+            .filter_map(|notification| match &notification.update {
+                SessionUpdate::UserMessageChunk(ContentChunk {
+                    content: ContentBlock::Text(text),
+                    ..
+                })
+                | SessionUpdate::AgentMessageChunk(ContentChunk {
+                    content: ContentBlock::Text(text),
+                    ..
+                }) => Some(text.text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Drop for TestAgent {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.home);
+    }
+}
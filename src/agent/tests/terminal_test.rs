@@ -0,0 +1,34 @@
+#![cfg(test)]
+
+use crate::agent::TerminalRun;
+
+fn run(output: &str) -> TerminalRun {
+    TerminalRun {
+        output: output.to_string(),
+        exit_code: Some(0),
+        ..Default::default()
+    }
+}
+
+/// Short output is passed back whole.
+#[test]
+fn output_tail_keeps_short_output() {
+    assert_eq!(run("ok\n").output_tail(16), "ok\n");
+}
+
+/// Long output keeps its end, marked as cut, on a char boundary.
+#[test]
+fn output_tail_cuts_from_the_front() {
+    assert_eq!(run("0123456789").output_tail(4), "…6789");
+    assert_eq!(run("ééééé").output_tail(3), "…é");
+}
+
+#[test]
+fn describes_exit() {
+    assert_eq!(run("").describe_exit(), "exit code 0");
+    let killed = TerminalRun {
+        signal: Some("SIGKILL".into()),
+        ..Default::default()
+    };
+    assert_eq!(killed.describe_exit(), "killed by signal SIGKILL");
+}
@@ -0,0 +1,80 @@
+#![cfg(test)]
+
+use agent_client_protocol::{ContentBlock, ContentChunk, SessionUpdate};
+use serde_json::json;
+
+use crate::agent::thoughts::{ThoughtDelivery, fold};
+
+fn thought(text: &str) -> SessionUpdate {
+    SessionUpdate::AgentThoughtChunk(ContentChunk {
+        content: text.into(),
+        meta: None,
+    })
+}
+
+/// The request's meta wins over the capabilities' meta.
+#[test]
+fn delivery_from_client_meta() {
+    assert_eq!(
+        ThoughtDelivery::from_meta(None, None),
+        ThoughtDelivery::Chunks
+    );
+    let hide = json!({ "thoughts": "hide" });
+    let fold = json!({ "thoughts": "fold" });
+    assert_eq!(
+        ThoughtDelivery::from_meta(None, Some(&hide)),
+        ThoughtDelivery::Hide
+    );
+    assert_eq!(
+        ThoughtDelivery::from_meta(Some(&fold), Some(&hide)),
+        ThoughtDelivery::Fold
+    );
+    let unknown = json!({ "thoughts": "whisper" });
+    assert_eq!(
+        ThoughtDelivery::from_meta(Some(&unknown), None),
+        ThoughtDelivery::Chunks
+    );
+    assert_eq!(
+        ThoughtDelivery::parse(" Suppress "),
+        Some(ThoughtDelivery::Hide)
+    );
+}
+
+#[test]
+fn chunks_pass_thoughts_through() {
+    let update = ThoughtDelivery::Chunks.apply(thought("hmm"));
+    assert!(matches!(update, Some(SessionUpdate::AgentThoughtChunk(_))));
+    assert!(ThoughtDelivery::Chunks.streams());
+}
+
+#[test]
+fn hide_drops_only_thoughts() {
+    assert!(ThoughtDelivery::Hide.apply(thought("hmm")).is_none());
+    let message = SessionUpdate::AgentMessageChunk(ContentChunk {
+        content: "answer".into(),
+        meta: None,
+    });
+    assert!(matches!(
+        ThoughtDelivery::Hide.apply(message),
+        Some(SessionUpdate::AgentMessageChunk(_))
+    ));
+    assert!(!ThoughtDelivery::Hide.streams());
+}
+
+#[test]
+fn fold_moves_thoughts_into_details_block() {
+    let Some(SessionUpdate::AgentMessageChunk(chunk)) =
+        ThoughtDelivery::Fold.apply(thought("  check the tests\n"))
+    else {
+        panic!("expected a message chunk");
+    };
+    let ContentBlock::Text(text) = chunk.content else {
+        panic!("expected text");
+    };
+    assert_eq!(text.text, fold("check the tests"));
+    assert!(
+        text.text
+            .contains("<details>\n<summary>Reasoning</summary>\n\ncheck the tests\n\n</details>")
+    );
+    assert!(!ThoughtDelivery::Fold.streams());
+}
@@ -0,0 +1,79 @@
+#![cfg(all(test, feature = "transcripts"))]
+
+use std::path::{Path, PathBuf};
+
+use codex_core::protocol::{EventMsg, PatchApplyEndEvent};
+use serde_json::json;
+
+use crate::agent::transcript::{BLESS_ENV, Transcript, check_fixture};
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("src/agent/tests/transcripts")
+}
+
+/// Every checked-in fixture replays to its expected output.
+#[test]
+fn golden_transcripts_replay_stably() {
+    let bless = std::env::var(BLESS_ENV).is_ok_and(|v| v.trim() == "1");
+    let mut failures = Vec::new();
+    for entry in std::fs::read_dir(fixtures_dir()).expect("fixtures dir") {
+        let path = entry.expect("fixture entry").path();
+        if path.extension().is_some_and(|ext| ext == "json")
+            && let Err(err) = check_fixture(&path, bless)
+        {
+            failures.push(format!("{}: {err}", path.display()));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+fn patch_end(call_id: &str, success: bool) -> EventMsg {
+    EventMsg::PatchApplyEnd(PatchApplyEndEvent {
+        call_id: call_id.to_string(),
+        stdout: String::new(),
+        stderr: String::new(),
+        success,
+    })
+}
+
+/// A fixture needs expected output, and drift from it is reported.
+#[test]
+fn detects_missing_and_changed_output() {
+    let mut transcript = Transcript::new(PathBuf::from("/w"), false);
+    transcript.events = vec![patch_end("call-1", true), patch_end("call-2", false)];
+    assert!(transcript.check().is_err());
+
+    let replayed = transcript.replay();
+    assert_eq!(replayed.len(), 2);
+    assert_eq!(replayed[0]["sessionUpdate"], "tool_call_update");
+    assert_eq!(replayed[0]["toolCallId"], "call-1");
+    assert_eq!(replayed[1]["status"], "failed");
+    transcript.expected = Some(replayed.clone());
+    assert_eq!(transcript.check(), Ok(()));
+
+    let mut drifted = replayed;
+    drifted[1]["status"] = json!("completed");
+    transcript.expected = Some(drifted);
+    let err = transcript.check().expect_err("drift is reported");
+    assert!(err.starts_with("output 1 differs"), "{err}");
+}
+
+/// Captures survive saving, and blessing writes the expected output.
+#[test]
+fn saves_and_blesses_fixtures() {
+    let path = std::env::temp_dir().join(format!(
+        "codex-acp-transcript-{}.json",
+        uuid::Uuid::new_v4()
+    ));
+    let mut transcript = Transcript::new(PathBuf::from("/w"), true);
+    transcript.events = vec![patch_end("call-1", true)];
+    transcript.save(&path).expect("save");
+
+    assert!(check_fixture(&path, false).is_err());
+    assert_eq!(check_fixture(&path, true), Ok(()));
+    assert_eq!(check_fixture(&path, false), Ok(()));
+    let loaded = Transcript::load(&path).expect("load");
+    assert_eq!(loaded.events.len(), 1);
+    assert!(loaded.support_terminal);
+    let _ = std::fs::remove_file(&path);
+}
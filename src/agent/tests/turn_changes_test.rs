@@ -0,0 +1,176 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::{
+    agent::{
+        command_history::new_record,
+        turn_changes::{
+            ChangeSummary, FileStat, FileStatus, TurnChanges, TurnCommand, line_counts,
+            with_change_summary,
+        },
+    },
+    fs::tools::undo::FileSnapshot,
+};
+
+fn snapshot(path: &str, before: Option<&str>, moved_to: Option<&str>) -> FileSnapshot {
+    FileSnapshot {
+        path: PathBuf::from(path),
+        before: before.map(str::to_string),
+        moved_to: moved_to.map(PathBuf::from),
+    }
+}
+
+/// Files are classified by whether they existed before and exist now.
+#[test]
+fn classifies_files() {
+    let mut changes = TurnChanges::default();
+    changes.record_files(&[
+        snapshot("/w/new.rs", None, None),
+        snapshot("/w/edited.rs", Some("a"), None),
+        snapshot("/w/gone.rs", Some("b"), None),
+        snapshot("/w/temp.rs", None, None),
+        snapshot("/w/from.rs", Some("c"), Some("/w/to.rs")),
+    ]);
+    // A later change does not make an added file "modified".
+    changes.record_files(&[snapshot("/w/new.rs", Some("x"), None)]);
+    changes.touch(Path::new("/w/unknown.rs"));
+
+    let current = |path: &Path| {
+        ["/w/new.rs", "/w/edited.rs", "/w/to.rs", "/w/unknown.rs"]
+            .iter()
+            .any(|p| Path::new(p) == path)
+            .then(|| b"x\n".to_vec())
+    };
+    let summary = changes.summarize_with(current);
+    assert_eq!(
+        summary.added,
+        vec![PathBuf::from("/w/new.rs"), PathBuf::from("/w/to.rs")]
+    );
+    assert_eq!(
+        summary.modified,
+        vec![
+            PathBuf::from("/w/edited.rs"),
+            PathBuf::from("/w/unknown.rs")
+        ]
+    );
+    assert_eq!(
+        summary.deleted,
+        vec![PathBuf::from("/w/from.rs"), PathBuf::from("/w/gone.rs")]
+    );
+}
+
+/// Line counts come from the content before the turn and now.
+#[test]
+fn counts_lines_per_file() {
+    let mut changes = TurnChanges::default();
+    changes.record_files(&[
+        snapshot("/w/a.rs", Some("one\ntwo\nthree\n"), None),
+        snapshot("/w/b.rs", None, None),
+    ]);
+    changes.touch(Path::new("/w/c.rs"));
+    let summary = changes.summarize_with(|path| match path.to_str() {
+        Some("/w/a.rs") => Some(b"one\n2\nthree\nfour\n".to_vec()),
+        Some("/w/b.rs") => Some(b"x\ny\n".to_vec()),
+        Some("/w/c.rs") => Some(b"z\n".to_vec()),
+        _ => None,
+    });
+    assert_eq!(
+        summary.files,
+        vec![
+            FileStat {
+                path: PathBuf::from("/w/a.rs"),
+                status: FileStatus::Modified,
+                lines_added: Some(2),
+                lines_removed: Some(1),
+            },
+            FileStat {
+                path: PathBuf::from("/w/b.rs"),
+                status: FileStatus::Added,
+                lines_added: Some(2),
+                lines_removed: Some(0),
+            },
+            FileStat {
+                path: PathBuf::from("/w/c.rs"),
+                status: FileStatus::Modified,
+                lines_added: None,
+                lines_removed: None,
+            },
+        ]
+    );
+    assert_eq!(line_counts("a\nb\n", ""), (0, 2));
+    assert_eq!(line_counts("same\n", "same\n"), (0, 0));
+}
+
+#[test]
+fn records_commands_and_tool_calls() {
+    let mut changes = TurnChanges::default();
+    changes.record_command(&new_record(
+        &["cargo".to_string(), "test".to_string()],
+        PathBuf::from("/w"),
+        101,
+        1200,
+        "",
+    ));
+    changes.record_tool_call("call-1".to_string());
+    changes.record_tool_call("call-2".to_string());
+    changes.record_tool_call("call-1".to_string());
+    let summary = changes.summarize_with(|_| None);
+    assert_eq!(
+        summary.commands,
+        vec![TurnCommand {
+            command: "cargo test".to_string(),
+            exit_code: 101,
+        }]
+    );
+    assert_eq!(summary.tool_call_ids, vec!["call-1", "call-2"]);
+    assert!(!summary.is_empty());
+}
+
+/// The summary joins other meta keys; an empty one adds nothing.
+#[test]
+fn merges_into_response_meta() {
+    let meta = Some(json!({ "stopDetail": { "reason": "max_tokens" } }));
+    assert_eq!(
+        with_change_summary(meta.clone(), &ChangeSummary::default()),
+        meta
+    );
+
+    let summary = ChangeSummary {
+        added: vec![PathBuf::from("/w/a.rs")],
+        files: vec![FileStat {
+            path: PathBuf::from("/w/a.rs"),
+            status: FileStatus::Added,
+            lines_added: Some(3),
+            lines_removed: Some(0),
+        }],
+        tool_call_ids: vec!["call-1".to_string()],
+        ..ChangeSummary::default()
+    };
+    let expected = json!({
+        "added": ["/w/a.rs"],
+        "modified": [],
+        "deleted": [],
+        "files": [{
+            "path": "/w/a.rs",
+            "status": "added",
+            "linesAdded": 3,
+            "linesRemoved": 0,
+        }],
+        "commands": [],
+        "toolCallIds": ["call-1"],
+    });
+    assert_eq!(
+        with_change_summary(meta, &summary),
+        Some(json!({
+            "stopDetail": { "reason": "max_tokens" },
+            "changeSummary": expected,
+        }))
+    );
+    assert_eq!(
+        with_change_summary(None, &summary).and_then(|meta| meta.get("changeSummary").cloned()),
+        Some(expected)
+    );
+}
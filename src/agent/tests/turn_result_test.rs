@@ -0,0 +1,71 @@
+#![cfg(test)]
+
+use agent_client_protocol::StopReason;
+
+use crate::agent::{
+    session_events::{SessionEvent, ToolCallEvent, ToolStatus},
+    turn_result::{ToolSummary, TurnResult},
+};
+
+fn tool_event(id: &str, title: Option<&str>, status: ToolStatus, output: &[&str]) -> SessionEvent {
+    SessionEvent::ToolCall {
+        session_id: "s1".to_string(),
+        call: ToolCallEvent {
+            id: id.to_string(),
+            started: title.is_some(),
+            title: title.map(str::to_string),
+            kind: title.map(|_| "execute".to_string()),
+            status: Some(status),
+            output: output.iter().map(|line| line.to_string()).collect(),
+            edited: Vec::new(),
+        },
+    }
+}
+
+/// Chunks are joined, tool calls merged by id, and the turn end is detected.
+#[test]
+fn collects_a_turn() {
+    let mut result = TurnResult::new("s1");
+    let events = vec![
+        SessionEvent::Thought {
+            session_id: "s1".to_string(),
+            text: "Listing files".to_string(),
+        },
+        tool_event("call-1", Some("ls"), ToolStatus::Running, &[]),
+        SessionEvent::MessageChunk {
+            session_id: "other".to_string(),
+            text: "not mine".to_string(),
+        },
+        tool_event("call-1", None, ToolStatus::Completed, &["a.txt"]),
+        SessionEvent::MessageChunk {
+            session_id: "s1".to_string(),
+            text: "There is ".to_string(),
+        },
+        SessionEvent::MessageChunk {
+            session_id: "s1".to_string(),
+            text: "one file.".to_string(),
+        },
+    ];
+    for event in events {
+        assert!(!result.apply(event));
+    }
+    assert!(result.apply(SessionEvent::TurnEnded {
+        session_id: "s1".to_string(),
+        stop_reason: Some(StopReason::EndTurn),
+        error: None,
+    }));
+
+    assert_eq!(result.text, "There is one file.");
+    assert_eq!(result.thoughts, "Listing files");
+    assert_eq!(
+        result.tool_calls,
+        vec![ToolSummary {
+            id: "call-1".to_string(),
+            title: "ls".to_string(),
+            kind: Some("execute".to_string()),
+            status: Some(ToolStatus::Completed),
+            output: vec!["a.txt".to_string()],
+        }]
+    );
+    assert_eq!(result.stop_reason, StopReason::EndTurn);
+}
@@ -0,0 +1,82 @@
+#![cfg(test)]
+
+use std::collections::HashMap;
+
+use codex_core::protocol::FileChange;
+
+use crate::{
+    agent::undo::{parse_undo_args, patch_snapshots, unapply},
+    fs::tools::undo::FileSnapshot,
+};
+
+#[test]
+fn parses_args() {
+    assert_eq!(parse_undo_args(""), Ok(false));
+    assert_eq!(parse_undo_args(" all "), Ok(true));
+    assert!(parse_undo_args("everything").is_err());
+}
+
+/// Reversing a patch's diff rebuilds the content before the patch.
+#[test]
+fn unapplies_diffs() {
+    let diff = "--- a/f.txt\n+++ b/f.txt\n@@ -1,3 +1,3 @@\n one\n-two\n+TWO\n three\n";
+    assert_eq!(
+        unapply("one\nTWO\nthree\n", diff).as_deref(),
+        Some("one\ntwo\nthree\n")
+    );
+    assert_eq!(unapply("something else\n", diff), None);
+}
+
+/// Patch snapshots cover added, deleted and updated files.
+#[tokio::test]
+async fn snapshots_applied_patches() {
+    let dir = std::env::temp_dir().join(format!("codex-acp-undo-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).expect("create dir");
+    let updated = dir.join("updated.txt");
+    std::fs::write(&updated, "one\nTWO\n").expect("write file");
+
+    let mut changes = HashMap::new();
+    changes.insert(
+        dir.join("added.txt"),
+        FileChange::Add {
+            content: "new\n".to_string(),
+        },
+    );
+    changes.insert(
+        dir.join("deleted.txt"),
+        FileChange::Delete {
+            content: "old\n".to_string(),
+        },
+    );
+    changes.insert(
+        updated.clone(),
+        FileChange::Update {
+            unified_diff: "@@ -1,2 +1,2 @@\n one\n-two\n+TWO\n".to_string(),
+            move_path: None,
+        },
+    );
+
+    let files = patch_snapshots(&changes).await;
+    assert_eq!(
+        files,
+        vec![
+            FileSnapshot {
+                path: dir.join("added.txt"),
+                before: None,
+                moved_to: None,
+            },
+            FileSnapshot {
+                path: dir.join("deleted.txt"),
+                before: Some("old\n".to_string()),
+                moved_to: None,
+            },
+            FileSnapshot {
+                path: updated,
+                before: Some("one\ntwo\n".to_string()),
+                moved_to: None,
+            },
+        ]
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
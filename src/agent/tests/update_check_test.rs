@@ -0,0 +1,52 @@
+#![cfg(test)]
+
+use crate::agent::update_check::{
+    CHECK_INTERVAL, NOTIFY_INTERVAL, UpdateCache, is_newer, parse_version,
+};
+
+#[test]
+fn parses_tags_and_versions() {
+    assert_eq!(parse_version("v0.3.1"), Some((0, 3, 1)));
+    assert_eq!(parse_version("1.2.0-beta.1"), Some((1, 2, 0)));
+    assert_eq!(parse_version("2.1"), Some((2, 1, 0)));
+    assert_eq!(parse_version("nightly"), None);
+
+    assert!(is_newer("v0.10.0", "0.9.3"));
+    assert!(!is_newer("v0.9.3", "0.9.3"));
+    assert!(!is_newer("v0.9.2", "0.9.3"));
+    assert!(!is_newer("nightly", "0.9.3"));
+}
+
+/// GitHub is asked again only once the last check is a day old.
+#[test]
+fn checks_at_most_daily() {
+    let now = 10 * CHECK_INTERVAL.as_secs();
+    assert!(UpdateCache::default().needs_check(now));
+
+    let cache = UpdateCache {
+        checked_at: now - 60,
+        ..UpdateCache::default()
+    };
+    assert!(!cache.needs_check(now));
+    assert!(cache.needs_check(now - 60 + CHECK_INTERVAL.as_secs()));
+}
+
+/// A newer release is announced, then not again for a day.
+#[test]
+fn notifies_once_per_day() {
+    let now = 10 * NOTIFY_INTERVAL.as_secs();
+    let mut cache = UpdateCache {
+        checked_at: now,
+        latest: Some("v0.5.0".to_string()),
+        notified_at: None,
+    };
+    assert_eq!(cache.pending_notice("0.4.2", now), Some("v0.5.0"));
+    assert_eq!(cache.pending_notice("0.5.0", now), None);
+
+    cache.notified_at = Some(now);
+    assert_eq!(cache.pending_notice("0.4.2", now + 60), None);
+    assert_eq!(
+        cache.pending_notice("0.4.2", now + NOTIFY_INTERVAL.as_secs()),
+        Some("v0.5.0")
+    );
+}
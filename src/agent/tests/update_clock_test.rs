@@ -0,0 +1,61 @@
+#![cfg(test)]
+
+use agent_client_protocol::{ContentChunk, SessionId, SessionNotification, SessionUpdate};
+use serde_json::json;
+
+use crate::agent::{UpdateClock, backfill::mark_backfill};
+
+fn chunk(session: &str) -> SessionNotification {
+    SessionNotification {
+        session_id: SessionId(session.into()),
+        update: SessionUpdate::AgentMessageChunk(ContentChunk {
+            content: "hi".into(),
+            meta: None,
+        }),
+        meta: None,
+    }
+}
+
+/// Each session counts on its own.
+#[test]
+fn numbers_updates_per_session() {
+    let mut clock = UpdateClock::default();
+    let mut stamped = Vec::new();
+    for session in ["a", "a", "b", "a"] {
+        let mut notification = chunk(session);
+        clock.stamp_at(&mut notification, 1_000);
+        stamped.push(notification.meta);
+    }
+    assert_eq!(
+        stamped,
+        vec![
+            Some(json!({ "seq": 0, "timestampMs": 1_000 })),
+            Some(json!({ "seq": 1, "timestampMs": 1_000 })),
+            Some(json!({ "seq": 0, "timestampMs": 1_000 })),
+            Some(json!({ "seq": 2, "timestampMs": 1_000 })),
+        ]
+    );
+}
+
+/// Replayed updates keep their stamps; other meta is kept.
+#[test]
+fn keeps_existing_stamps_and_meta() {
+    let mut clock = UpdateClock::default();
+    let mut first = chunk("a");
+    clock.stamp_at(&mut first, 1_000);
+
+    let mut replayed = mark_backfill(first);
+    clock.stamp_at(&mut replayed, 5_000);
+    assert_eq!(
+        replayed.meta,
+        Some(json!({ "seq": 0, "timestampMs": 1_000, "backfill": true }))
+    );
+
+    let mut next = chunk("a");
+    next.meta = Some(json!({ "trace": "x" }));
+    clock.stamp_at(&mut next, 6_000);
+    assert_eq!(
+        next.meta,
+        Some(json!({ "trace": "x", "seq": 1, "timestampMs": 6_000 }))
+    );
+}
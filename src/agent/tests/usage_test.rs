@@ -0,0 +1,72 @@
+#![cfg(test)]
+
+use agent_client_protocol::SessionModeId;
+use codex_core::{
+    config::{Config, ConfigOverrides, ConfigToml},
+    protocol::TokenUsage,
+};
+
+use crate::agent::{session::SessionState, usage::UsageTracker};
+
+fn usage(input: u64, output: u64) -> TokenUsage {
+    TokenUsage {
+        input_tokens: input,
+        cached_input_tokens: 0,
+        output_tokens: output,
+        reasoning_output_tokens: 0,
+        total_tokens: input + output,
+    }
+}
+
+#[test]
+fn usage_is_attributed_to_active_model_by_delta() {
+    let mut tracker = UsageTracker::default();
+    tracker.record("local@llama", &usage(100, 10));
+    tracker.record("local@llama", &usage(150, 20));
+    tracker.record("remote@big", &usage(400, 60));
+
+    let by_model = tracker.by_model();
+    assert_eq!(by_model["local@llama"].input_tokens, 150);
+    assert_eq!(by_model["local@llama"].output_tokens, 20);
+    assert_eq!(by_model["remote@big"].input_tokens, 250);
+    assert_eq!(by_model["remote@big"].total_tokens, 290);
+}
+
+#[test]
+fn shrinking_totals_do_not_underflow() {
+    let mut tracker = UsageTracker::default();
+    tracker.record("a@m", &usage(100, 10));
+    tracker.record("a@m", &usage(50, 5));
+    assert_eq!(tracker.by_model()["a@m"].total_tokens, 110);
+}
+
+/// A turn answered by a directive or fallback model is credited to that model.
+#[test]
+fn usage_goes_to_the_model_answering_the_turn() {
+    let home = std::env::temp_dir().join(format!("codex-acp-usage-{}", uuid::Uuid::new_v4()));
+    let config = Config::load_from_base_config_with_overrides(
+        ConfigToml::default(),
+        ConfigOverrides::default(),
+        home,
+    )
+    .expect("config");
+    let mut state = SessionState::new(
+        "fs-1".to_string(),
+        None,
+        &config,
+        SessionModeId("auto".into()),
+    );
+    let session_model = state.current_model.clone().expect("session model");
+    assert_eq!(state.usage_model(), Some(session_model.as_str()));
+
+    state.turn_model = Some("local@llama".to_string());
+    let model = state.usage_model().expect("turn model").to_string();
+    state.model_usage.record(&model, &usage(100, 10));
+    state.turn_model = None;
+    let model = state.usage_model().expect("session model").to_string();
+    state.model_usage.record(&model, &usage(130, 15));
+
+    let by_model = state.model_usage.by_model();
+    assert_eq!(by_model["local@llama"].total_tokens, 110);
+    assert_eq!(by_model[&session_model].total_tokens, 35);
+}
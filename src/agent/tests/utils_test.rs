@@ -0,0 +1,230 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use agent_client_protocol::ToolKind;
+use codex_core::protocol::McpInvocation;
+use codex_protocol::parse_command::ParsedCommand;
+use serde_json::json;
+
+use crate::agent::utils::{
+    describe_mcp_tool, display_path, file_uri_path, format_command_call, mcp_tool_kind,
+    resolve_fs_path, resolve_path, search_result_locations, tool_location,
+};
+
+/// `.` and `..` are dropped and relative paths are joined to the base.
+#[test]
+fn resolve_path_normalizes_components() {
+    let base = Path::new("/work/app/src");
+    assert_eq!(
+        resolve_path(base, Path::new("../README.md")),
+        PathBuf::from("/work/app/README.md")
+    );
+    assert_eq!(
+        resolve_path(base, Path::new("/etc/./hosts")),
+        PathBuf::from("/etc/hosts")
+    );
+}
+
+/// Paths display relative to the deepest matching root; several roots add the root name.
+#[test]
+fn display_path_is_workspace_relative() {
+    let single = vec![PathBuf::from("/work/app")];
+    assert_eq!(
+        display_path(&single, Path::new("/work/app/src/main.rs")),
+        "src/main.rs"
+    );
+    assert_eq!(display_path(&single, Path::new("/work/app")), ".");
+    assert_eq!(display_path(&single, Path::new("/tmp/x")), "/tmp/x");
+
+    let multi = vec![PathBuf::from("/work/api"), PathBuf::from("/work/web")];
+    assert_eq!(
+        display_path(&multi, Path::new("/work/web/index.ts")),
+        "web/index.ts"
+    );
+}
+
+/// Locations carry the absolute path plus both forms in meta.
+#[test]
+fn tool_location_includes_both_forms() {
+    let roots = vec![PathBuf::from("/work/app")];
+    let location = tool_location(
+        Path::new("/work/app/src"),
+        &roots,
+        Path::new("lib.rs"),
+        Some(3),
+    );
+    assert_eq!(location.path, PathBuf::from("/work/app/src/lib.rs"));
+    assert_eq!(location.line, Some(3));
+    let meta = location.meta.expect("meta");
+    assert_eq!(meta["absolute_path"], "/work/app/src/lib.rs");
+    assert_eq!(meta["relative_path"], "src/lib.rs");
+    assert_eq!(meta["workspace_root"], "/work/app");
+}
+
+/// Relative command paths resolve against the call's own cwd, not the workspace root.
+#[test]
+fn format_command_call_pins_call_cwd() {
+    let roots = vec![PathBuf::from("/work/app")];
+    let parsed = vec![ParsedCommand::ListFiles {
+        cmd: "ls src".to_string(),
+        path: Some("src".to_string()),
+    }];
+    let call = format_command_call(Path::new("/work/app/crates/core"), &roots, &parsed);
+    assert_eq!(call.title, "List crates/core/src");
+    assert_eq!(
+        call.locations[0].path,
+        PathBuf::from("/work/app/crates/core/src")
+    );
+}
+
+fn invocation(server: &str, tool: &str, arguments: serde_json::Value) -> McpInvocation {
+    McpInvocation {
+        server: server.to_string(),
+        tool: tool.to_string(),
+        arguments: Some(arguments),
+    }
+}
+
+/// `acp_fs` tools are reported by what they do; other servers' tools as fetches.
+#[test]
+fn mcp_tool_kind_follows_fs_tools() {
+    let kind = |server: &str, tool: &str| mcp_tool_kind(&invocation(server, tool, json!({})));
+    assert!(matches!(kind("acp_fs", "read_text_file"), ToolKind::Read));
+    assert!(matches!(kind("acp_fs", "edit_text_file"), ToolKind::Edit));
+    assert!(matches!(kind("acp_fs", "create_directory"), ToolKind::Edit));
+    assert!(matches!(kind("acp_fs", "delete_file"), ToolKind::Delete));
+    assert!(matches!(kind("acp_fs", "move_file"), ToolKind::Move));
+    assert!(matches!(kind("acp_fs", "rename_file"), ToolKind::Move));
+    assert!(matches!(kind("acp_fs", "search_text"), ToolKind::Search));
+    assert!(matches!(
+        kind("acp_fs", "run_cached_command"),
+        ToolKind::Execute
+    ));
+    assert!(matches!(kind("acp_fs", "ask_user"), ToolKind::Fetch));
+    assert!(matches!(kind("docs", "delete_file"), ToolKind::Fetch));
+}
+
+/// Moves and renames show both ends of the move.
+#[test]
+fn describe_mcp_tool_shows_move_targets() {
+    let cwd = Path::new("/work/app");
+    let roots = vec![PathBuf::from("/work/app")];
+
+    let (title, locations) = describe_mcp_tool(
+        &invocation(
+            "acp_fs",
+            "move_file",
+            json!({"path": "src/a.rs", "destination": "lib/b.rs"}),
+        ),
+        cwd,
+        &roots,
+    );
+    assert_eq!(title, "acp_fs.move_file (src/a.rs → lib/b.rs)");
+    let paths: Vec<PathBuf> = locations.into_iter().map(|l| l.path).collect();
+    assert_eq!(
+        paths,
+        vec![
+            PathBuf::from("/work/app/src/a.rs"),
+            PathBuf::from("/work/app/lib/b.rs")
+        ]
+    );
+
+    let (title, _) = describe_mcp_tool(
+        &invocation(
+            "acp_fs",
+            "rename_file",
+            json!({"path": "src/a.rs", "new_name": "c.rs"}),
+        ),
+        cwd,
+        &roots,
+    );
+    assert_eq!(title, "acp_fs.rename_file (src/a.rs → src/c.rs)");
+
+    let (title, locations) = describe_mcp_tool(
+        &invocation("acp_fs", "delete_file", json!({"path": "old.txt"})),
+        cwd,
+        &roots,
+    );
+    assert_eq!(title, "acp_fs.delete_file (old.txt)");
+    assert_eq!(locations.len(), 1);
+}
+
+/// With several roots, `acp_fs` paths may name their root and are displayed that way.
+#[test]
+fn fs_paths_resolve_in_named_roots() {
+    let cwd = Path::new("/work/api");
+    let roots = vec![PathBuf::from("/work/api"), PathBuf::from("/work/web")];
+    assert_eq!(
+        resolve_fs_path(cwd, &roots, "web/src/app.ts"),
+        PathBuf::from("/work/web/src/app.ts")
+    );
+    assert_eq!(
+        resolve_fs_path(cwd, &roots, "src/main.rs"),
+        PathBuf::from("/work/api/src/main.rs")
+    );
+
+    let (title, locations) = describe_mcp_tool(
+        &invocation(
+            "acp_fs",
+            "read_text_file",
+            json!({"path": "web/src/app.ts"}),
+        ),
+        cwd,
+        &roots,
+    );
+    assert_eq!(title, "acp_fs.read_text_file (web/src/app.ts)");
+    let meta = locations[0].meta.clone().expect("meta");
+    assert_eq!(meta["workspace_root"], "/work/web");
+    assert_eq!(meta["relative_path"], "src/app.ts");
+}
+
+/// `file://` URIs are percent-decoded and may name `localhost`.
+#[test]
+fn file_uri_path_decodes_file_uris() {
+    assert_eq!(
+        file_uri_path("file:///tmp/shots/my%20screen.png"),
+        Some(PathBuf::from("/tmp/shots/my screen.png"))
+    );
+    assert_eq!(
+        file_uri_path("file://localhost/tmp/a.png"),
+        Some(PathBuf::from("/tmp/a.png"))
+    );
+    assert_eq!(file_uri_path("file://otherhost/tmp/a.png"), None);
+    assert_eq!(file_uri_path("https://example.com/a.png"), None);
+    assert_eq!(file_uri_path("/tmp/a.png"), None);
+}
+
+#[test]
+fn result_locations_come_from_search_metadata() {
+    let result = json!({
+        "Ok": {
+            "content": [{
+                "type": "text",
+                "text": "src/a.rs:4: hit",
+                "_meta": {
+                    "codex_fs_search": {
+                        "locations": [
+                            {"path": "/w/src/a.rs", "line": 4},
+                            {"path": "/w/src/b.rs", "line": 9},
+                        ]
+                    }
+                }
+            }]
+        }
+    });
+    let roots = vec![PathBuf::from("/w")];
+    let locations = search_result_locations(&result, Path::new("/w"), &roots);
+    let found: Vec<(PathBuf, Option<u32>)> = locations
+        .into_iter()
+        .map(|location| (location.path, location.line))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (PathBuf::from("/w/src/a.rs"), Some(4)),
+            (PathBuf::from("/w/src/b.rs"), Some(9)),
+        ]
+    );
+    assert!(search_result_locations(&json!({"Ok": {}}), Path::new("/w"), &roots).is_empty());
+}
@@ -0,0 +1,98 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use agent_client_protocol::McpServer;
+
+use crate::agent::workspace_mcp::{
+    MCP_USAGE, McpCommand, McpTrustStore, describe_server, parse_mcp_command, parse_workspace_mcp,
+    untrusted_notice,
+};
+
+const MCP_TOML: &str = r#"
+[mcp_servers.db]
+command = "./tools/db-mcp"
+args = ["--readonly"]
+env = { DB_URL = "postgres://localhost/dev" }
+
+[mcp_servers.docs]
+command = "npx"
+
+[mcp_servers.api]
+url = "http://localhost:4000/mcp"
+headers = { X-Team = "platform" }
+"#;
+
+#[test]
+fn parses_mcp_commands() {
+    assert_eq!(parse_mcp_command(""), Ok(McpCommand::List));
+    assert_eq!(parse_mcp_command(" Trust "), Ok(McpCommand::Trust));
+    assert_eq!(parse_mcp_command("untrust"), Ok(McpCommand::Untrust));
+    assert_eq!(parse_mcp_command("add x"), Err(MCP_USAGE.to_string()));
+}
+
+#[test]
+fn parses_stdio_and_http_servers() {
+    let servers = parse_workspace_mcp(MCP_TOML, Path::new("/repo")).unwrap();
+    assert_eq!(servers.len(), 3);
+
+    let McpServer::Stdio {
+        name,
+        command,
+        args,
+        env,
+    } = &servers[0]
+    else {
+        panic!("expected a stdio server");
+    };
+    assert_eq!(name, "db");
+    // Relative paths are taken from the workspace, bare names from PATH.
+    assert_eq!(command, &PathBuf::from("/repo/tools/db-mcp"));
+    assert_eq!(args, &vec!["--readonly".to_string()]);
+    assert_eq!(env[0].name, "DB_URL");
+    assert_eq!(describe_server(&servers[1]), "docs: npx");
+
+    let McpServer::Http { name, url, headers } = &servers[2] else {
+        panic!("expected an http server");
+    };
+    assert_eq!(name, "api");
+    assert_eq!(url, "http://localhost:4000/mcp");
+    assert_eq!(headers[0].value, "platform");
+}
+
+#[test]
+fn rejects_invalid_files() {
+    let root = Path::new("/repo");
+    assert!(parse_workspace_mcp("", root).unwrap().is_empty());
+    assert!(parse_workspace_mcp("[mcp_servers.x]\n", root).is_err());
+    assert!(parse_workspace_mcp("[mcp_servers.x]\ncommand = 1\n", root).is_err());
+    assert!(parse_workspace_mcp("[mcp_servers.x]\ncommand = \"a\"\nargs = [1]\n", root).is_err());
+    assert!(parse_workspace_mcp("not toml [", root).is_err());
+}
+
+#[test]
+fn notice_names_untrusted_servers() {
+    let servers = parse_workspace_mcp(MCP_TOML, Path::new("/repo")).unwrap();
+    let notice = untrusted_notice(&servers);
+    assert!(notice.contains("`db`, `docs`, `api`"));
+    assert!(notice.contains("/mcp trust"));
+}
+
+#[tokio::test]
+async fn trust_covers_exact_content() {
+    let home = std::env::temp_dir().join(format!("codex-acp-mcp-trust-{}", uuid::Uuid::new_v4()));
+    let store = McpTrustStore::new(&home);
+    let workspace = Path::new("/work/a");
+
+    assert!(!store.is_trusted(workspace, MCP_TOML).await);
+    store.trust(workspace, MCP_TOML.to_string()).await.unwrap();
+    assert!(store.is_trusted(workspace, MCP_TOML).await);
+    assert!(!store.is_trusted(workspace, "[mcp_servers]\n").await);
+    assert!(!store.is_trusted(Path::new("/work/b"), MCP_TOML).await);
+
+    assert!(store.untrust(workspace).await.unwrap());
+    assert!(!store.untrust(workspace).await.unwrap());
+    assert!(!store.is_trusted(workspace, MCP_TOML).await);
+
+    let _ = std::fs::remove_dir_all(home);
+}
@@ -0,0 +1,54 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::agent::{
+    context::ContextItem,
+    workspace_roots::{extra_roots, parse_roots, roots_context, workspace_roots_from_meta},
+};
+
+#[test]
+fn roots_from_meta_must_be_absolute() {
+    assert!(workspace_roots_from_meta(None).expect("no meta").is_none());
+    let meta = json!({ "workspaceRoots": ["/work/web", "/work/docs/../lib"] });
+    assert_eq!(
+        workspace_roots_from_meta(Some(&meta)).expect("valid"),
+        Some(vec![PathBuf::from("/work/web"), PathBuf::from("/work/lib")])
+    );
+    assert!(parse_roots(&json!(["web"])).is_err());
+    assert!(parse_roots(&json!("/work/web")).is_err());
+}
+
+/// The working directory and repeated roots are dropped.
+#[test]
+fn extra_roots_skip_cwd_and_duplicates() {
+    let roots = vec![
+        PathBuf::from("/work/api"),
+        PathBuf::from("/work/web"),
+        PathBuf::from("/work/web"),
+    ];
+    assert_eq!(
+        extra_roots(Path::new("/work/api/."), roots),
+        vec![PathBuf::from("/work/web")]
+    );
+}
+
+#[test]
+fn roots_context_lists_every_root() {
+    let ContextItem::Text { text, label } =
+        roots_context(Path::new("/work/api"), &[PathBuf::from("/work/web")])
+    else {
+        panic!("expected text");
+    };
+    assert_eq!(label.as_deref(), Some("workspace roots"));
+    assert!(text.contains("- api: /work/api"));
+    assert!(text.contains("- web: /work/web"));
+    assert!(text.contains("`web/README.md`"));
+
+    let ContextItem::Text { text, .. } = roots_context(Path::new("/work/api"), &[]) else {
+        panic!("expected text");
+    };
+    assert!(text.contains("single root"));
+}
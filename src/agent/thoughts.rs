@@ -0,0 +1,101 @@
+//! How reasoning reaches clients that cannot render thought chunks.
+//!
+//! Some clients show `agent_thought_chunk` updates as ordinary messages, so
+//! the model's reasoning ends up mixed into the answer. Such clients can say
+//! at initialize with `_meta.thoughts` (on the request or its client
+//! capabilities) how they want reasoning delivered: `chunks` (the default)
+//! sends thought chunks, `hide` drops reasoning entirely, and `fold` sends
+//! each reasoning section as a collapsed `<details>` block in the message
+//! stream. Streamed reasoning is collected into whole sections when hidden or
+//! folded. `CODEX_ACP_THOUGHTS=chunks|hide|fold` overrides the client's
+//! choice.
+
+use agent_client_protocol::{ContentBlock, ContentChunk, SessionUpdate, TextContent};
+use serde_json::Value;
+
+/// Environment variable forcing the delivery (`chunks`, `hide`, `fold`).
+pub const THOUGHTS_ENV: &str = "CODEX_ACP_THOUGHTS";
+
+/// How reasoning is sent to the client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThoughtDelivery {
+    /// As `AgentThoughtChunk` updates.
+    #[default]
+    Chunks,
+    /// Not at all.
+    Hide,
+    /// As collapsed `<details>` blocks in `AgentMessageChunk` updates.
+    Fold,
+}
+
+impl ThoughtDelivery {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "chunks" | "show" => Some(Self::Chunks),
+            "hide" | "suppress" => Some(Self::Hide),
+            "fold" | "details" => Some(Self::Fold),
+            _ => None,
+        }
+    }
+
+    /// The delivery for a client, from `CODEX_ACP_THOUGHTS` or else the
+    /// `thoughts` key in the initialize request's or its client capabilities'
+    /// meta.
+    pub fn for_client(request_meta: Option<&Value>, capabilities_meta: Option<&Value>) -> Self {
+        if let Some(delivery) = std::env::var(THOUGHTS_ENV)
+            .ok()
+            .and_then(|v| Self::parse(&v))
+        {
+            return delivery;
+        }
+        Self::from_meta(request_meta, capabilities_meta)
+    }
+
+    /// The delivery requested by the `thoughts` meta key.
+    pub fn from_meta(request_meta: Option<&Value>, capabilities_meta: Option<&Value>) -> Self {
+        [request_meta, capabilities_meta]
+            .into_iter()
+            .flatten()
+            .find_map(|meta| meta.get("thoughts").and_then(Value::as_str))
+            .and_then(Self::parse)
+            .unwrap_or_default()
+    }
+
+    /// Whether reasoning deltas can be sent as they arrive.
+    pub fn streams(self) -> bool {
+        self == Self::Chunks
+    }
+
+    /// `update` as this delivery sends it; updates other than thought chunks
+    /// pass unchanged.
+    pub fn apply(self, update: SessionUpdate) -> Option<SessionUpdate> {
+        let SessionUpdate::AgentThoughtChunk(chunk) = update else {
+            return Some(update);
+        };
+        match self {
+            Self::Chunks => Some(SessionUpdate::AgentThoughtChunk(chunk)),
+            Self::Hide => None,
+            Self::Fold => {
+                let content = match chunk.content {
+                    ContentBlock::Text(text) => ContentBlock::Text(TextContent {
+                        text: fold(&text.text),
+                        ..text
+                    }),
+                    other => other,
+                };
+                Some(SessionUpdate::AgentMessageChunk(ContentChunk {
+                    content,
+                    meta: chunk.meta,
+                }))
+            }
+        }
+    }
+}
+
+/// `text` as a collapsed markdown details block.
+pub fn fold(text: &str) -> String {
+    format!(
+        "\n\n<details>\n<summary>Reasoning</summary>\n\n{}\n\n</details>\n\n",
+        text.trim()
+    )
+}
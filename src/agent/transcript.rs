@@ -0,0 +1,249 @@
+//! Golden transcripts of prompt turns (`transcripts` feature).
+//!
+//! Regression tests for the Codex event → ACP update mapping from real
+//! traces. With `CODEX_ACP_TRANSCRIPT_DIR=<dir>`, every prompt turn is
+//! captured as `<dir>/<session>-<millis>.json`: the workspace, the Codex
+//! events of the turn, and the ACP updates the agent sent for reference.
+//! Copied into `src/agent/tests/transcripts/`, a capture becomes a fixture:
+//! the test harness replays its events through [`EventHandler`] and compares
+//! the result with the fixture's `expected` output. Run the tests with
+//! `CODEX_ACP_BLESS=1` to write `expected` for new fixtures, or after an
+//! intended change of the mapping.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use agent_client_protocol::{SessionId, SessionNotification};
+use codex_core::protocol::EventMsg;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use super::{
+    core::CodexAgent,
+    events::{EventHandler, ExecEndArgs},
+    store,
+};
+
+/// Directory captures are written to; capturing is off when unset.
+pub const TRANSCRIPT_DIR_ENV: &str = "CODEX_ACP_TRANSCRIPT_DIR";
+
+/// Set to `1` to (re)write the `expected` output of fixtures.
+pub const BLESS_ENV: &str = "CODEX_ACP_BLESS";
+
+/// Session id in permission requests produced by a replay.
+pub const REPLAY_SESSION_ID: &str = "transcript";
+
+/// One captured turn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transcript {
+    pub cwd: PathBuf,
+    /// Workspace roots besides `cwd`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workspace_roots: Vec<PathBuf>,
+    #[serde(default)]
+    pub support_terminal: bool,
+    pub events: Vec<EventMsg>,
+    /// The session updates the agent sent during the turn.
+    #[serde(default)]
+    pub updates: Vec<SessionNotification>,
+    /// Replay output the fixture is checked against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<Vec<Value>>,
+}
+
+impl Transcript {
+    pub fn new(cwd: PathBuf, support_terminal: bool) -> Self {
+        Self {
+            cwd,
+            workspace_roots: Vec::new(),
+            support_terminal,
+            events: Vec::new(),
+            updates: Vec::new(),
+            expected: None,
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(io::Error::other)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut text = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        text.push('\n');
+        std::fs::write(path, text)
+    }
+
+    /// The events replayed through a fresh [`EventHandler`].
+    pub fn replay(&self) -> Vec<Value> {
+        let handler = EventHandler::new(self.cwd.clone(), self.support_terminal)
+            .with_roots(&self.workspace_roots);
+        replay(&handler, &self.events)
+    }
+
+    /// Compare the replay with `expected`, describing the first difference.
+    pub fn check(&self) -> Result<(), String> {
+        let Some(expected) = &self.expected else {
+            return Err(format!(
+                "no expected output; run with {BLESS_ENV}=1 to write it"
+            ));
+        };
+        let actual = self.replay();
+        if let Some(index) = (0..actual.len().max(expected.len()))
+            .find(|&index| actual.get(index) != expected.get(index))
+        {
+            return Err(format!(
+                "output {index} differs:\n  expected: {}\n  actual:   {}",
+                describe(expected.get(index)),
+                describe(actual.get(index)),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn describe(value: Option<&Value>) -> String {
+    value.map_or_else(|| "(none)".to_string(), Value::to_string)
+}
+
+/// The ACP updates and permission requests `handler` produces for `events`,
+/// as JSON. Events without an [`EventHandler`] mapping are skipped.
+pub fn replay(handler: &EventHandler, events: &[EventMsg]) -> Vec<Value> {
+    let session_id = SessionId(REPLAY_SESSION_ID.into());
+    events
+        .iter()
+        .filter_map(|event| {
+            let output = match event {
+                EventMsg::McpToolCallBegin(begin) => serde_json::to_value(
+                    handler.on_mcp_tool_call_begin(&begin.call_id, &begin.invocation),
+                ),
+                EventMsg::McpToolCallEnd(end) => {
+                    let result = serde_json::to_value(&end.result).unwrap_or(Value::Null);
+                    serde_json::to_value(handler.on_mcp_tool_call_end(
+                        &end.call_id,
+                        &end.invocation,
+                        &result,
+                        end.is_success(),
+                    ))
+                }
+                EventMsg::ExecCommandBegin(begin) => {
+                    serde_json::to_value(handler.on_exec_command_begin(
+                        &begin.call_id,
+                        &begin.cwd,
+                        &begin.command,
+                        &begin.parsed_cmd,
+                    ))
+                }
+                EventMsg::ExecCommandEnd(end) => {
+                    serde_json::to_value(handler.on_exec_command_end(ExecEndArgs {
+                        call_id: end.call_id.clone(),
+                        exit_code: end.exit_code,
+                        aggregated_output: end.aggregated_output.clone(),
+                        stdout: end.stdout.clone(),
+                        stderr: end.stderr.clone(),
+                        duration_ms: end.duration.as_millis(),
+                        formatted_output: end.formatted_output.clone(),
+                    }))
+                }
+                EventMsg::ExecApprovalRequest(req) => {
+                    serde_json::to_value(handler.on_exec_approval_request(
+                        &session_id,
+                        &req.call_id,
+                        &req.command,
+                        &req.cwd,
+                        &req.parsed_cmd,
+                    ))
+                }
+                EventMsg::ApplyPatchApprovalRequest(req) => {
+                    let mut changes: Vec<(String, _)> = req
+                        .changes
+                        .iter()
+                        .map(|(path, change)| (path.display().to_string(), change.clone()))
+                        .collect();
+                    // Map order is not stable across runs.
+                    changes.sort_by(|a, b| a.0.cmp(&b.0));
+                    serde_json::to_value(handler.on_apply_patch_approval_request(
+                        &session_id,
+                        &req.call_id,
+                        &changes,
+                    ))
+                }
+                EventMsg::PatchApplyEnd(end) => serde_json::to_value(handler.on_patch_apply_end(
+                    &end.call_id,
+                    end.success,
+                    serde_json::json!(end),
+                )),
+                _ => return None,
+            };
+            output.ok()
+        })
+        .collect()
+}
+
+/// Check the fixture at `path`, first writing its `expected` output when
+/// `bless` is set.
+pub fn check_fixture(path: &Path, bless: bool) -> Result<(), String> {
+    let mut transcript = Transcript::load(path).map_err(|err| err.to_string())?;
+    if bless {
+        transcript.expected = Some(transcript.replay());
+        transcript.save(path).map_err(|err| err.to_string())?;
+    }
+    transcript.check()
+}
+
+impl CodexAgent {
+    /// Start capturing the session's turn, if a capture directory is set.
+    pub(super) fn begin_transcript(&self, session_id: &SessionId) {
+        if std::env::var_os(TRANSCRIPT_DIR_ENV).is_none() {
+            return;
+        }
+        let mut transcript = Transcript::new(self.config.cwd.clone(), self.support_terminal());
+        transcript.workspace_roots = self.workspace_roots(session_id);
+        self.with_session_state_mut(session_id, |state| state.transcript = Some(transcript));
+    }
+
+    pub(super) fn record_transcript_event(&self, session_id: &SessionId, event: &EventMsg) {
+        self.with_session_state_mut(session_id, |state| {
+            if let Some(transcript) = &mut state.transcript {
+                transcript.events.push(event.clone());
+            }
+        });
+    }
+
+    pub(super) fn record_transcript_update(&self, notification: &SessionNotification) {
+        self.with_session_state_mut(&notification.session_id, |state| {
+            if let Some(transcript) = &mut state.transcript {
+                transcript.updates.push(notification.clone());
+            }
+        });
+    }
+
+    /// Write the captured turn to the capture directory.
+    pub(super) fn finish_transcript(&self, session_id: &SessionId) {
+        let Some(transcript) = self
+            .with_session_state_mut(session_id, |state| state.transcript.take())
+            .flatten()
+        else {
+            return;
+        };
+        let Some(dir) = std::env::var_os(TRANSCRIPT_DIR_ENV).map(PathBuf::from) else {
+            return;
+        };
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let path = dir.join(format!("{}-{millis}.json", session_id.0));
+        let result = store::validate_session_id(&session_id.0)
+            .and_then(|()| std::fs::create_dir_all(&dir))
+            .and_then(|()| transcript.save(&path));
+        if let Err(err) = result {
+            warn!(path = %path.display(), error = %err, "failed to write transcript");
+        }
+    }
+}
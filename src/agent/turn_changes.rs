@@ -0,0 +1,211 @@
+//! What a prompt turn changed, summarized in the `PromptResponse`.
+//!
+//! Files touched by applied patches and `acp_fs` writes, the commands run and
+//! the tool calls reported during a turn are collected per session. When the
+//! turn ends, the response's `_meta.changeSummary` lists the files added,
+//! modified and deleted (judged by whether each existed before and exists
+//! now), each file again with its added and removed line counts, the
+//! commands run with their exit codes, and the tool call ids, so scripting
+//! clients can act on the results without following every tool call or
+//! scraping message text. Turns that did none of this carry no summary.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use diffy::{Line, create_patch};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use super::modified_files::{Baseline, MAX_BASELINE_BYTES};
+use crate::fs::tools::{command_history::CommandRecord, undo::FileSnapshot};
+
+/// One command run during the turn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnCommand {
+    pub command: String,
+    pub exit_code: i32,
+}
+
+/// The changes of the running turn.
+#[derive(Debug, Default)]
+pub struct TurnChanges {
+    /// Each touched file as it was before the turn.
+    files: BTreeMap<PathBuf, Baseline>,
+    bytes: usize,
+    commands: Vec<TurnCommand>,
+    tool_call_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileStatus {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// One changed file with its line counts; counts are `None` when the
+/// content before or after is not known or not text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileStat {
+    pub path: PathBuf,
+    pub status: FileStatus,
+    pub lines_added: Option<usize>,
+    pub lines_removed: Option<usize>,
+}
+
+/// The `changeSummary` of a finished turn.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSummary {
+    pub added: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+    pub deleted: Vec<PathBuf>,
+    pub files: Vec<FileStat>,
+    pub commands: Vec<TurnCommand>,
+    pub tool_call_ids: Vec<String>,
+}
+
+impl ChangeSummary {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.commands.is_empty() && self.tool_call_ids.is_empty()
+    }
+}
+
+impl TurnChanges {
+    /// Remember the files of one change; a file's first change in the turn
+    /// decides what it was before.
+    pub fn record_files(&mut self, files: &[FileSnapshot]) {
+        for file in files {
+            let baseline = match &file.before {
+                Some(content) if self.bytes + content.len() <= MAX_BASELINE_BYTES => {
+                    Baseline::Content(content.clone())
+                }
+                // It existed, but its content is not kept.
+                Some(_) => Baseline::Unknown,
+                None => Baseline::Absent,
+            };
+            self.insert(&file.path, baseline);
+            if let Some(moved_to) = &file.moved_to {
+                self.insert(moved_to, Baseline::Absent);
+            }
+        }
+    }
+
+    /// Remember a changed file whose earlier state was not captured.
+    pub fn touch(&mut self, path: &Path) {
+        self.insert(path, Baseline::Unknown);
+    }
+
+    fn insert(&mut self, path: &Path, baseline: Baseline) {
+        if self.files.contains_key(path) {
+            return;
+        }
+        if let Baseline::Content(content) = &baseline {
+            self.bytes += content.len();
+        }
+        self.files.insert(path.to_path_buf(), baseline);
+    }
+
+    pub fn record_command(&mut self, record: &CommandRecord) {
+        self.commands.push(TurnCommand {
+            command: record.command.clone(),
+            exit_code: record.exit_code,
+        });
+    }
+
+    pub fn record_tool_call(&mut self, id: String) {
+        if !self.tool_call_ids.contains(&id) {
+            self.tool_call_ids.push(id);
+        }
+    }
+
+    /// The summary, reading the touched files as they are now.
+    pub async fn summarize(&self) -> ChangeSummary {
+        let mut current = HashMap::new();
+        for path in self.files.keys() {
+            current.insert(path.clone(), tokio::fs::read(path).await.ok());
+        }
+        self.summarize_with(|path| current.get(path).cloned().flatten())
+    }
+
+    /// The summary, given each file's bytes now (`None` if it is gone).
+    pub fn summarize_with(&self, current: impl Fn(&Path) -> Option<Vec<u8>>) -> ChangeSummary {
+        let mut summary = ChangeSummary {
+            commands: self.commands.clone(),
+            tool_call_ids: self.tool_call_ids.clone(),
+            ..ChangeSummary::default()
+        };
+        for (path, baseline) in &self.files {
+            let now = current(path);
+            let status = match (baseline, &now) {
+                (Baseline::Absent, Some(_)) => FileStatus::Added,
+                // Created and removed again within the turn.
+                (Baseline::Absent, None) => continue,
+                (_, Some(_)) => FileStatus::Modified,
+                (_, None) => FileStatus::Deleted,
+            };
+            let before = match baseline {
+                Baseline::Content(content) => Some(content.as_str()),
+                Baseline::Absent => Some(""),
+                Baseline::Unknown => None,
+            };
+            let after = match &now {
+                Some(bytes) => std::str::from_utf8(bytes).ok(),
+                None => Some(""),
+            };
+            let (lines_added, lines_removed) = match (before, after) {
+                (Some(before), Some(after)) => {
+                    let (added, removed) = line_counts(before, after);
+                    (Some(added), Some(removed))
+                }
+                _ => (None, None),
+            };
+            match status {
+                FileStatus::Added => summary.added.push(path.clone()),
+                FileStatus::Modified => summary.modified.push(path.clone()),
+                FileStatus::Deleted => summary.deleted.push(path.clone()),
+            }
+            summary.files.push(FileStat {
+                path: path.clone(),
+                status,
+                lines_added,
+                lines_removed,
+            });
+        }
+        summary
+    }
+}
+
+/// Lines added and removed between `before` and `after`.
+pub fn line_counts(before: &str, after: &str) -> (usize, usize) {
+    let patch = create_patch(before, after);
+    let mut added = 0;
+    let mut removed = 0;
+    for line in patch.hunks().iter().flat_map(|hunk| hunk.lines()) {
+        match line {
+            Line::Insert(_) => added += 1,
+            Line::Delete(_) => removed += 1,
+            Line::Context(_) => {}
+        }
+    }
+    (added, removed)
+}
+
+/// Add `summary` to a response's meta as `changeSummary`, keeping any other
+/// keys.
+pub fn with_change_summary(meta: Option<Value>, summary: &ChangeSummary) -> Option<Value> {
+    if summary.is_empty() {
+        return meta;
+    }
+    let mut meta = match meta {
+        Some(Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    meta.insert("changeSummary".to_string(), json!(summary));
+    Some(Value::Object(meta))
+}
@@ -0,0 +1,132 @@
+//! Running a prompt to completion, for tests and non-interactive embedders.
+//!
+//! [`CodexAgent::run_prompt_to_completion`] sends a prompt, drains the
+//! [`SessionEvents`] stream while the turn runs, and returns what the turn
+//! produced as one [`TurnResult`].
+
+use agent_client_protocol::{ContentBlock, Error, PromptRequest, SessionId, StopReason};
+use futures::StreamExt;
+
+use super::{
+    core::CodexAgent,
+    session_events::{SessionEvent, SessionEvents, ToolStatus},
+};
+
+/// Everything a prompt turn produced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnResult {
+    pub session_id: String,
+    /// The agent's answer, chunks joined.
+    pub text: String,
+    /// The reasoning summary, chunks joined.
+    pub thoughts: String,
+    /// Tool calls in the order they started, with their final state.
+    pub tool_calls: Vec<ToolSummary>,
+    pub stop_reason: StopReason,
+}
+
+/// The final state of one tool call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolSummary {
+    pub id: String,
+    pub title: String,
+    pub kind: Option<String>,
+    pub status: Option<ToolStatus>,
+    /// Text output of the call, in order.
+    pub output: Vec<String>,
+}
+
+impl TurnResult {
+    pub fn new(session_id: impl Into<String>) -> Self {
+        Self {
+            session_id: session_id.into(),
+            text: String::new(),
+            thoughts: String::new(),
+            tool_calls: Vec::new(),
+            stop_reason: StopReason::EndTurn,
+        }
+    }
+
+    /// Fold one event of this session into the result. Returns `true` once
+    /// the turn has ended.
+    pub fn apply(&mut self, event: SessionEvent) -> bool {
+        if event.session_id() != self.session_id {
+            return false;
+        }
+        match event {
+            SessionEvent::MessageChunk { text, .. } => self.text.push_str(&text),
+            SessionEvent::Thought { text, .. } => self.thoughts.push_str(&text),
+            SessionEvent::ToolCall { call, .. } => {
+                let index = match self.tool_calls.iter().position(|tool| tool.id == call.id) {
+                    Some(index) => index,
+                    None => {
+                        self.tool_calls.push(ToolSummary {
+                            id: call.id.clone(),
+                            title: String::new(),
+                            kind: None,
+                            status: None,
+                            output: Vec::new(),
+                        });
+                        self.tool_calls.len() - 1
+                    }
+                };
+                let tool = &mut self.tool_calls[index];
+                if let Some(title) = call.title {
+                    tool.title = title;
+                }
+                if call.kind.is_some() {
+                    tool.kind = call.kind;
+                }
+                if call.status.is_some() {
+                    tool.status = call.status;
+                }
+                tool.output.extend(call.output);
+            }
+            SessionEvent::TurnEnded { stop_reason, .. } => {
+                if let Some(stop_reason) = stop_reason {
+                    self.stop_reason = stop_reason;
+                }
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+}
+
+impl CodexAgent {
+    /// Send `blocks` as a prompt and wait for the turn to finish, collecting
+    /// its events from `events` (from [`CodexAgent::with_event_stream`]).
+    /// Events of other sessions arriving meanwhile are dropped.
+    pub async fn run_prompt_to_completion(
+        &self,
+        events: &mut SessionEvents,
+        session_id: &SessionId,
+        blocks: Vec<ContentBlock>,
+    ) -> Result<TurnResult, Error> {
+        let request = PromptRequest {
+            session_id: session_id.clone(),
+            prompt: blocks,
+            meta: None,
+        };
+        let mut result = TurnResult::new(session_id.0.to_string());
+        let mut ended = false;
+        // Updates wait for their acknowledgement, so drain while prompting.
+        let prompt = self.prompt(request);
+        tokio::pin!(prompt);
+        let response = loop {
+            tokio::select! {
+                response = &mut prompt => break response?,
+                Some(event) = events.next() => ended |= result.apply(event),
+            }
+        };
+        while !ended {
+            match events.next().await {
+                Some(event) => ended = result.apply(event),
+                None => break,
+            }
+        }
+        result.stop_reason = response.stop_reason;
+        Ok(result)
+    }
+}
@@ -0,0 +1,243 @@
+//! `/undo` for file changes made by the agent.
+//!
+//! Every `acp_fs` bridge write and every patch Codex applies records what the
+//! touched files looked like before, per session. Bridge writes read the old
+//! content before writing; patches are applied by Codex itself, so their old
+//! content is rebuilt after the fact by reversing the patch's diff. `/undo`
+//! restores the newest change and `/undo all` every change of the last turn
+//! that made one, reporting the restored files as a tool call with diffs.
+//! The bounded history itself is [`crate::fs::tools::undo`].
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use agent_client_protocol::{
+    Diff, SessionId, SessionUpdate, ToolCall, ToolCallContent, ToolCallId, ToolCallLocation,
+    ToolCallStatus, ToolKind,
+};
+use codex_core::protocol::FileChange;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::{context::ContextItem, core::CodexAgent};
+use crate::fs::tools::{undo::FileSnapshot, unified_diff};
+
+/// `/undo` restores the last change; `/undo all` the last turn's changes.
+pub fn parse_undo_args(args: &str) -> Result<bool, String> {
+    match args.trim() {
+        "" => Ok(false),
+        "all" => Ok(true),
+        _ => Err("Usage: /undo [all]\n".to_string()),
+    }
+}
+
+/// The diff that turns the new side of `diff` back into the old side.
+pub fn reverse_diff(diff: &str) -> String {
+    let mut reversed = String::with_capacity(diff.len());
+    for line in diff.lines() {
+        let line = if line.starts_with("--- ") || line.starts_with("+++ ") {
+            // File headers name the same file; they are not needed.
+            continue;
+        } else if let Some(header) = line.strip_prefix("@@") {
+            reverse_hunk_header(header).unwrap_or_else(|| line.to_string())
+        } else if let Some(added) = line.strip_prefix('+') {
+            format!("-{added}")
+        } else if let Some(removed) = line.strip_prefix('-') {
+            format!("+{removed}")
+        } else {
+            line.to_string()
+        };
+        reversed.push_str(&line);
+        reversed.push('\n');
+    }
+    reversed
+}
+
+/// ` -a,b +c,d @@ tail` becomes `@@ -c,d +a,b @@ tail`.
+fn reverse_hunk_header(header: &str) -> Option<String> {
+    let (ranges, tail) = header.trim_start().split_once("@@")?;
+    let mut parts = ranges.split_whitespace();
+    let old = parts.next()?.strip_prefix('-')?;
+    let new = parts.next()?.strip_prefix('+')?;
+    Some(format!("@@ -{new} +{old} @@{tail}"))
+}
+
+/// The content `new` had before `diff` was applied to it.
+pub fn unapply(new: &str, diff: &str) -> Option<String> {
+    let hunks = unified_diff::parse(&reverse_diff(diff)).ok()?;
+    let applied = unified_diff::apply(new, &hunks);
+    applied.all_applied().then_some(applied.content)
+}
+
+/// Snapshots of the files an applied patch changed. Files whose old content
+/// cannot be rebuilt are left out.
+pub async fn patch_snapshots(changes: &HashMap<PathBuf, FileChange>) -> Vec<FileSnapshot> {
+    let mut files = Vec::new();
+    for (path, change) in changes {
+        let snapshot = match change {
+            FileChange::Add { .. } => FileSnapshot {
+                path: path.clone(),
+                before: None,
+                moved_to: None,
+            },
+            FileChange::Delete { content } => FileSnapshot {
+                path: path.clone(),
+                before: Some(content.clone()),
+                moved_to: None,
+            },
+            FileChange::Update {
+                unified_diff,
+                move_path,
+            } => {
+                let current = move_path.as_deref().unwrap_or(path);
+                let before = match tokio::fs::read_to_string(current).await {
+                    Ok(new) => unapply(&new, unified_diff),
+                    Err(_) => None,
+                };
+                let Some(before) = before else {
+                    warn!(path = %path.display(), "cannot rebuild content before patch; not undoable");
+                    continue;
+                };
+                FileSnapshot {
+                    path: path.clone(),
+                    before: Some(before),
+                    moved_to: move_path.clone(),
+                }
+            }
+        };
+        files.push(snapshot);
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+/// Put one file back, returning the diff from its current to its restored
+/// content.
+async fn restore(snapshot: &FileSnapshot) -> io::Result<Diff> {
+    let current_path = snapshot.moved_to.as_deref().unwrap_or(&snapshot.path);
+    let current = tokio::fs::read_to_string(current_path).await.ok();
+    if let Some(moved_to) = &snapshot.moved_to {
+        remove_if_exists(moved_to).await?;
+    }
+    match &snapshot.before {
+        Some(before) => {
+            if let Some(dir) = snapshot.path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            tokio::fs::write(&snapshot.path, before).await?;
+        }
+        None => remove_if_exists(&snapshot.path).await?,
+    }
+    Ok(Diff {
+        path: snapshot.path.clone(),
+        old_text: current,
+        new_text: snapshot.before.clone().unwrap_or_default(),
+        meta: None,
+    })
+}
+
+async fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+impl CodexAgent {
+    /// Remember a change for `/undo` and `/diff`.
+    pub(super) fn record_undo(&self, session_id: &SessionId, files: Vec<FileSnapshot>) {
+        self.with_session_state_mut(session_id, |state| {
+            state.modified.record(&files);
+            state.turn_changes.record_files(&files);
+            state.undo.record(files)
+        });
+    }
+
+    /// `/undo [all]`: restore files and report them as a tool call.
+    pub(super) async fn undo_command(&self, session_id: &SessionId, args: &str) {
+        let all = match parse_undo_args(args) {
+            Ok(all) => all,
+            Err(usage) => {
+                drop(self.send_message_chunk(session_id, usage.into()).await);
+                return;
+            }
+        };
+        let changes = self
+            .with_session_state_mut(session_id, |state| {
+                if all {
+                    state.undo.pop_turn()
+                } else {
+                    state.undo.pop_last().into_iter().collect()
+                }
+            })
+            .unwrap_or_default();
+        if changes.is_empty() {
+            drop(
+                self.send_message_chunk(session_id, "Nothing to undo.\n".into())
+                    .await,
+            );
+            return;
+        }
+
+        let mut diffs = Vec::new();
+        let mut failures = Vec::new();
+        // Newest first, so a file changed twice ends at its oldest content.
+        for snapshot in changes.iter().flat_map(|change| change.files.iter().rev()) {
+            match restore(snapshot).await {
+                Ok(diff) => diffs.push(diff),
+                Err(err) => failures.push(format!("{}: {err}", snapshot.path.display())),
+            }
+        }
+
+        let restored: Vec<String> = diffs
+            .iter()
+            .map(|diff| diff.path.display().to_string())
+            .collect();
+        if !restored.is_empty() {
+            let note = ContextItem::Text {
+                text: format!(
+                    "The user undid your earlier changes; these files were restored to their \
+                     previous content: {}",
+                    restored.join(", ")
+                ),
+                label: Some("undo".to_string()),
+            };
+            self.with_session_state_mut(session_id, |state| state.pending_context.push(note));
+        }
+
+        let mut title = format!("Undo: restored {} file(s)", diffs.len());
+        if !failures.is_empty() {
+            title.push_str(&format!(", {} failed", failures.len()));
+        }
+        let locations = diffs
+            .iter()
+            .map(|diff| ToolCallLocation {
+                path: diff.path.clone(),
+                line: None,
+                meta: None,
+            })
+            .collect();
+        let mut content: Vec<ToolCallContent> =
+            diffs.into_iter().map(ToolCallContent::from).collect();
+        content.extend(failures.into_iter().map(ToolCallContent::from));
+        let update = SessionUpdate::ToolCall(ToolCall {
+            id: ToolCallId(format!("undo-{}", Uuid::new_v4()).into()),
+            title,
+            kind: ToolKind::Edit,
+            status: if restored.is_empty() {
+                ToolCallStatus::Failed
+            } else {
+                ToolCallStatus::Completed
+            },
+            content,
+            locations,
+            raw_input: None,
+            raw_output: None,
+            meta: None,
+        });
+        drop(self.send_session_update(session_id, update).await);
+    }
+}
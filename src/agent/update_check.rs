@@ -0,0 +1,207 @@
+//! Opt-in check for newer releases.
+//!
+//! With `CODEX_ACP_UPDATE_CHECK=1` the agent asks GitHub for the latest
+//! release in the background at startup, at most once a day; the answer is
+//! cached in `$CODEX_HOME/acp/update-check.json`. When that release is newer
+//! than the running version, the next new session gets a short message about
+//! it, at most once a day. The check is off unless the variable is set, and
+//! `CODEX_ACP_UPDATE_CHECK=0` turns it off again.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::task;
+use tracing::{debug, info, warn};
+
+use super::core::CodexAgent;
+
+/// Environment variable enabling the check.
+pub const UPDATE_CHECK_ENV: &str = "CODEX_ACP_UPDATE_CHECK";
+
+const LATEST_RELEASE_API: &str =
+    "https://api.github.com/repos/soddygo/codex-acp-agent/releases/latest";
+const RELEASES_PAGE: &str = "https://github.com/soddygo/codex-acp-agent/releases/latest";
+
+/// Minimum time between two requests to GitHub.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Minimum time between two notices about the same or a newer release.
+pub const NOTIFY_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether `CODEX_ACP_UPDATE_CHECK` enables the check.
+pub fn enabled_from_env() -> bool {
+    std::env::var(UPDATE_CHECK_ENV).is_ok_and(|value| {
+        matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "on" | "yes"
+        )
+    })
+}
+
+/// `major.minor.patch` of a version or release tag such as `v0.3.1`.
+/// Pre-release and build suffixes are ignored.
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim();
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch))
+}
+
+/// Whether `latest` is a newer version than `current`.
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// What the last check found, and when the user was last told.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpdateCache {
+    /// When GitHub was last asked, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub checked_at: u64,
+    /// Tag of the latest release, when the last check found one.
+    #[serde(default)]
+    pub latest: Option<String>,
+    #[serde(default)]
+    pub notified_at: Option<u64>,
+}
+
+impl UpdateCache {
+    pub fn needs_check(&self, now: u64) -> bool {
+        now.saturating_sub(self.checked_at) >= CHECK_INTERVAL.as_secs()
+    }
+
+    /// The release to tell the user about now, if any.
+    pub fn pending_notice(&self, current: &str, now: u64) -> Option<&str> {
+        let latest = self.latest.as_deref()?;
+        let recently_notified = self
+            .notified_at
+            .is_some_and(|at| now.saturating_sub(at) < NOTIFY_INTERVAL.as_secs());
+        (is_newer(latest, current) && !recently_notified).then_some(latest)
+    }
+}
+
+/// Message shown in a session when `latest` is available.
+pub fn update_notice(latest: &str, current: &str) -> String {
+    format!(
+        "ℹ️ codex-acp {latest} is available (running {current}). Release notes: {RELEASES_PAGE}\n\n"
+    )
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The cache file under `$CODEX_HOME/acp`.
+#[derive(Debug, Clone)]
+pub struct UpdateStore {
+    path: PathBuf,
+}
+
+impl UpdateStore {
+    pub fn new(codex_home: &Path) -> Self {
+        Self {
+            path: codex_home.join("acp").join("update-check.json"),
+        }
+    }
+
+    pub async fn load(&self) -> UpdateCache {
+        let Ok(bytes) = tokio::fs::read(&self.path).await else {
+            return UpdateCache::default();
+        };
+        serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+            warn!(path = %self.path.display(), error = %err, "ignoring malformed update cache");
+            UpdateCache::default()
+        })
+    }
+
+    pub async fn save(&self, cache: &UpdateCache) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&self.path, serde_json::to_vec_pretty(cache)?).await
+    }
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+async fn fetch_latest_release() -> reqwest::Result<String> {
+    let client = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent(concat!("codex-acp/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let release: Release = client
+        .get(LATEST_RELEASE_API)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(release.tag_name)
+}
+
+impl CodexAgent {
+    /// Refresh the release cache in the background when the check is
+    /// enabled and the last check is more than a day old.
+    pub fn start_update_check(&self) {
+        if !enabled_from_env() {
+            return;
+        }
+        let store = UpdateStore::new(&self.config.codex_home);
+        task::spawn_local(async move {
+            let mut cache = store.load().await;
+            let now = now_secs();
+            if !cache.needs_check(now) {
+                return;
+            }
+            // Failed checks count too, so an offline machine is not retried on every start.
+            cache.checked_at = now;
+            match fetch_latest_release().await {
+                Ok(tag) => {
+                    info!(latest = %tag, "checked for codex-acp updates");
+                    cache.latest = Some(tag);
+                }
+                Err(err) => debug!(error = %err, "update check failed"),
+            }
+            if let Err(err) = store.save(&cache).await {
+                warn!(error = %err, "failed to save update cache");
+            }
+        });
+    }
+
+    /// The update message for a new session, at most once a day.
+    pub(super) async fn take_update_notice(&self) -> Option<String> {
+        if !enabled_from_env() {
+            return None;
+        }
+        let store = UpdateStore::new(&self.config.codex_home);
+        let mut cache = store.load().await;
+        let now = now_secs();
+        let current = env!("CARGO_PKG_VERSION");
+        let notice = update_notice(cache.pending_notice(current, now)?, current);
+        cache.notified_at = Some(now);
+        if let Err(err) = store.save(&cache).await {
+            warn!(error = %err, "failed to save update cache");
+        }
+        Some(notice)
+    }
+}
@@ -0,0 +1,65 @@
+//! Sequence numbers and timestamps on session updates.
+//!
+//! Every session notification the agent sends carries `_meta.seq`, counting
+//! up from 0 per session, and `_meta.timestampMs`, the wall-clock time it
+//! went out, so clients can order updates, measure latency and rebuild
+//! timelines. Updates replayed from the backfill keep the stamps they got
+//! when first sent. `CODEX_ACP_UPDATE_CLOCK=0` turns the stamps off for
+//! transports where every byte counts.
+
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use agent_client_protocol::SessionNotification;
+use serde_json::{Map, Value};
+
+/// Environment variable disabling the stamps (`0`, `false`, `no`).
+pub const UPDATE_CLOCK_ENV: &str = "CODEX_ACP_UPDATE_CLOCK";
+
+/// Per-session update counters.
+#[derive(Debug, Default)]
+pub struct UpdateClock {
+    next_seq: HashMap<String, u64>,
+}
+
+impl UpdateClock {
+    /// `Some` unless the stamps are disabled in the environment.
+    pub fn from_env() -> Option<Self> {
+        let disabled = std::env::var(UPDATE_CLOCK_ENV)
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no"))
+            .unwrap_or(false);
+        (!disabled).then(Self::default)
+    }
+
+    /// Stamp `notification` with the session's next sequence number and the
+    /// current time, keeping any other meta.
+    pub fn stamp(&mut self, notification: &mut SessionNotification) {
+        self.stamp_at(notification, now_millis());
+    }
+
+    pub fn stamp_at(&mut self, notification: &mut SessionNotification, timestamp_ms: u64) {
+        let mut meta = match notification.meta.take() {
+            Some(Value::Object(meta)) => meta,
+            _ => Map::new(),
+        };
+        if !meta.contains_key("seq") {
+            let next = self
+                .next_seq
+                .entry(notification.session_id.0.to_string())
+                .or_default();
+            meta.insert("seq".to_string(), Value::from(*next));
+            meta.insert("timestampMs".to_string(), Value::from(timestamp_ms));
+            *next += 1;
+        }
+        notification.meta = Some(Value::Object(meta));
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
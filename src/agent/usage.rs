@@ -0,0 +1,65 @@
+//! Per-model token usage attribution.
+//!
+//! Codex reports cumulative usage for the whole conversation. When the user
+//! switches models mid-session that total mixes models, so each update is
+//! turned into a delta against the previous total and credited to the model
+//! that answered the turn: the session's model, or the one picked for that
+//! turn by a `!model` directive or failover.
+
+use std::collections::BTreeMap;
+
+use codex_core::protocol::TokenUsage;
+use serde::{Deserialize, Serialize};
+
+/// Tracks usage per `provider@model` id from cumulative Codex totals.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageTracker {
+    /// Last cumulative total seen from Codex, used to compute deltas.
+    #[serde(default)]
+    last_total: Option<TokenUsage>,
+    #[serde(default)]
+    by_model: BTreeMap<String, TokenUsage>,
+}
+
+impl UsageTracker {
+    /// Credit the growth since the previous cumulative `total` to `model`,
+    /// returning that growth.
+    pub fn record(&mut self, model: &str, total: &TokenUsage) -> TokenUsage {
+        let delta = match &self.last_total {
+            Some(prev) => usage_delta(prev, total),
+            None => total.clone(),
+        };
+        self.last_total = Some(total.clone());
+        add_usage(self.by_model.entry(model.to_string()).or_default(), &delta);
+        delta
+    }
+
+    /// Usage per model id, sorted by id.
+    pub fn by_model(&self) -> &BTreeMap<String, TokenUsage> {
+        &self.by_model
+    }
+}
+
+/// Saturating difference `new - prev`, field by field.
+pub fn usage_delta(prev: &TokenUsage, new: &TokenUsage) -> TokenUsage {
+    TokenUsage {
+        input_tokens: new.input_tokens.saturating_sub(prev.input_tokens),
+        cached_input_tokens: new
+            .cached_input_tokens
+            .saturating_sub(prev.cached_input_tokens),
+        output_tokens: new.output_tokens.saturating_sub(prev.output_tokens),
+        reasoning_output_tokens: new
+            .reasoning_output_tokens
+            .saturating_sub(prev.reasoning_output_tokens),
+        total_tokens: new.total_tokens.saturating_sub(prev.total_tokens),
+    }
+}
+
+/// Add `delta` into `acc`, field by field.
+pub fn add_usage(acc: &mut TokenUsage, delta: &TokenUsage) {
+    acc.input_tokens += delta.input_tokens;
+    acc.cached_input_tokens += delta.cached_input_tokens;
+    acc.output_tokens += delta.output_tokens;
+    acc.reasoning_output_tokens += delta.reasoning_output_tokens;
+    acc.total_tokens += delta.total_tokens;
+}
@@ -1,8 +1,12 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use agent_client_protocol as acp;
 use codex_core::protocol::McpInvocation;
 use codex_protocol::parse_command::ParsedCommand;
+use serde_json::json;
+
+use super::redact;
+use crate::fs::tools::workspace_scope;
 
 /// Formatted summary for a command/tool call used by ACP updates.
 #[derive(Clone, Debug)]
@@ -25,7 +29,14 @@ pub struct FsToolMetadata {
 /// Format a tool/command call for display in the client, summarizing a
 /// sequence of parsed commands into a single title, the kind, locations,
 /// and whether terminal output should be rendered.
-pub fn format_command_call(cwd: &Path, parsed_cmd: &[ParsedCommand]) -> FormatCommandCall {
+///
+/// Relative paths are resolved against the call's own `cwd`; titles show
+/// them relative to the workspace `roots`.
+pub fn format_command_call(
+    cwd: &Path,
+    roots: &[PathBuf],
+    parsed_cmd: &[ParsedCommand],
+) -> FormatCommandCall {
     let mut titles = Vec::new();
     let mut locations = Vec::new();
     let mut terminal_output = false;
@@ -40,18 +51,17 @@ pub fn format_command_call(cwd: &Path, parsed_cmd: &[ParsedCommand]) -> FormatCo
                 kind = acp::ToolKind::Read;
             }
             ParsedCommand::ListFiles { cmd: _, path } => {
-                let dir = if let Some(path) = path.as_ref() {
-                    cwd.join(path)
-                } else {
-                    cwd.to_path_buf()
-                };
-                titles.push(format!("List {}", dir.display()));
+                let dir = resolve_path(cwd, Path::new(path.as_deref().unwrap_or(".")));
+                titles.push(format!("List {}", display_path(roots, &dir)));
                 cmd_path = path.as_ref().map(PathBuf::from);
                 kind = acp::ToolKind::Search;
             }
             ParsedCommand::Search { cmd, query, path } => {
                 let label = match (query, path.as_ref()) {
-                    (Some(query), Some(path)) => format!("Search {query} in {path}"),
+                    (Some(query), Some(path)) => format!(
+                        "Search {query} in {}",
+                        display_path(roots, &resolve_path(cwd, Path::new(path)))
+                    ),
                     (Some(query), None) => format!("Search {query}"),
                     _ => format!("Search {}", cmd),
                 };
@@ -66,37 +76,126 @@ pub fn format_command_call(cwd: &Path, parsed_cmd: &[ParsedCommand]) -> FormatCo
         }
 
         if let Some(path) = cmd_path {
-            locations.push(acp::ToolCallLocation {
-                path: if path.is_relative() {
-                    cwd.join(&path)
-                } else {
-                    path
-                },
-                line: None,
-                meta: None,
-            });
+            locations.push(tool_location(cwd, roots, &path, None));
         }
     }
 
     FormatCommandCall {
-        title: titles.join(", "),
+        title: redact::redact(&titles.join(", ")),
         terminal_output,
         locations,
         kind,
     }
 }
 
-/// Return a user-friendly display path for a raw path string.
-/// If `raw_path` is within `cwd`, return a relative path; otherwise, fall back
-/// to the file name or the original raw string.
-pub fn display_fs_path(cwd: &Path, raw_path: &str) -> String {
-    let path = Path::new(raw_path);
-    if let Ok(relative) = path.strip_prefix(cwd) {
-        let rel_display = relative.display().to_string();
-        if !rel_display.is_empty() {
-            return rel_display;
+/// Resolve `path` against `base` and lexically drop `.` and `..` components.
+pub fn resolve_path(base: &Path, path: &Path) -> PathBuf {
+    let joined = if path.is_relative() {
+        base.join(path)
+    } else {
+        path.to_path_buf()
+    };
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
         }
     }
+    resolved
+}
+
+/// The local path a `file://` URI names, decoding percent-escapes; `None`
+/// for other schemes and for hosts other than `localhost`.
+pub fn file_uri_path(uri: &str) -> Option<PathBuf> {
+    let url = reqwest::Url::parse(uri).ok()?;
+    if url.scheme() != "file" {
+        return None;
+    }
+    url.to_file_path().ok()
+}
+
+/// Resolve a path given to an `acp_fs` tool the way the bridge does:
+/// `<root name>/<path>` lies in the named root when there are several, other
+/// relative paths are relative to `cwd`.
+pub fn resolve_fs_path(cwd: &Path, roots: &[PathBuf], raw_path: &str) -> PathBuf {
+    match workspace_scope::named_root(roots, Path::new(raw_path)) {
+        Some((root, rest)) => resolve_path(root, rest),
+        None => resolve_path(cwd, Path::new(raw_path)),
+    }
+}
+
+/// Find the workspace root containing `path` (the deepest one when roots
+/// nest) and return it with the path relative to it.
+pub fn workspace_relative<'a>(roots: &'a [PathBuf], path: &Path) -> Option<(&'a Path, PathBuf)> {
+    roots
+        .iter()
+        .filter_map(|root| {
+            path.strip_prefix(root)
+                .ok()
+                .map(|rel| (root.as_path(), rel.to_path_buf()))
+        })
+        .max_by_key(|(root, _)| root.components().count())
+}
+
+/// Display `path` relative to the workspace. With several roots the root's
+/// directory name is prefixed so paths stay unambiguous; paths outside the
+/// workspace are shown in full.
+pub fn display_path(roots: &[PathBuf], path: &Path) -> String {
+    let Some((root, rel)) = workspace_relative(roots, path) else {
+        return path.display().to_string();
+    };
+    let rel = if roots.len() > 1 {
+        root.file_name().map(Path::new).unwrap_or(root).join(rel)
+    } else {
+        rel
+    };
+    let display = rel.display().to_string();
+    if display.is_empty() {
+        ".".to_string()
+    } else {
+        display
+    }
+}
+
+/// Build a `ToolCallLocation` for `path` resolved against the call's `cwd`.
+///
+/// `path` is always absolute; `meta` carries both the absolute and the
+/// workspace-relative form (and the matching root) so clients can pick.
+pub fn tool_location(
+    cwd: &Path,
+    roots: &[PathBuf],
+    path: &Path,
+    line: Option<u32>,
+) -> acp::ToolCallLocation {
+    let absolute = resolve_path(cwd, path);
+    let (root, relative) = match workspace_relative(roots, &absolute) {
+        Some((root, rel)) => (Some(root.to_path_buf()), Some(rel)),
+        None => (None, None),
+    };
+    acp::ToolCallLocation {
+        meta: Some(json!({
+            "absolute_path": absolute,
+            "relative_path": relative,
+            "workspace_root": root,
+            "display_path": display_path(roots, &absolute),
+        })),
+        path: absolute,
+        line,
+    }
+}
+
+/// Return a user-friendly display path for a raw path string.
+/// If `raw_path` is within the workspace, return a workspace-relative path;
+/// otherwise, fall back to the file name or the original raw string.
+pub fn display_fs_path(cwd: &Path, roots: &[PathBuf], raw_path: &str) -> String {
+    let path = resolve_fs_path(cwd, roots, raw_path);
+    if workspace_relative(roots, &path).is_some() {
+        return display_path(roots, &path);
+    }
 
     path.file_name()
         .map(|name| name.to_string_lossy().into_owned())
@@ -105,13 +204,19 @@ pub fn display_fs_path(cwd: &Path, raw_path: &str) -> String {
 
 /// Extract FS tool metadata from an MCP invocation, when applicable.
 /// Only tools from the "acp_fs" server and supported tool names are considered.
-pub fn fs_tool_metadata(invocation: &McpInvocation, cwd: &Path) -> Option<FsToolMetadata> {
+pub fn fs_tool_metadata(
+    invocation: &McpInvocation,
+    cwd: &Path,
+    roots: &[PathBuf],
+) -> Option<FsToolMetadata> {
     if invocation.server != "acp_fs" {
         return None;
     }
 
     match invocation.tool.as_str() {
-        "read_text_file" | "write_text_file" | "edit_text_file" => {}
+        "read_text_file" | "write_text_file" | "edit_text_file" | "list_directory"
+        | "search_text" | "glob_files" | "delete_file" | "move_file" | "rename_file"
+        | "file_info" | "apply_unified_diff" | "read_binary_file" | "create_directory" => {}
         _ => return None,
     }
 
@@ -121,8 +226,8 @@ pub fn fs_tool_metadata(invocation: &McpInvocation, cwd: &Path) -> Option<FsTool
         .get("line")
         .and_then(|value| value.as_u64())
         .map(|value| value as u32);
-    let display_path = display_fs_path(cwd, &path);
-    let location_path = PathBuf::from(&path);
+    let display_path = display_fs_path(cwd, roots, &path);
+    let location_path = resolve_fs_path(cwd, roots, &path);
 
     Some(FsToolMetadata {
         display_path,
@@ -131,19 +236,71 @@ pub fn fs_tool_metadata(invocation: &McpInvocation, cwd: &Path) -> Option<FsTool
     })
 }
 
+/// Where an `acp_fs` `move_file` or `rename_file` call moves its file.
+fn fs_move_target(invocation: &McpInvocation, cwd: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    let args = invocation.arguments.as_ref()?.as_object()?;
+    let path = args.get("path")?.as_str()?;
+    match invocation.tool.as_str() {
+        "move_file" => Some(resolve_fs_path(
+            cwd,
+            roots,
+            args.get("destination")?.as_str()?,
+        )),
+        "rename_file" => {
+            let new_name = args.get("new_name")?.as_str()?;
+            Some(resolve_fs_path(cwd, roots, path).with_file_name(new_name))
+        }
+        _ => None,
+    }
+}
+
+/// The ACP tool kind of an MCP tool call. `acp_fs` tools map to what they
+/// do; other servers' tools are reported as fetches.
+pub fn mcp_tool_kind(invocation: &McpInvocation) -> acp::ToolKind {
+    if invocation.server != "acp_fs" {
+        return acp::ToolKind::Fetch;
+    }
+    match invocation.tool.as_str() {
+        "read_text_file" | "read_binary_file" | "file_info" | "get_diagnostics"
+        | "command_history" => acp::ToolKind::Read,
+        "write_text_file"
+        | "edit_text_file"
+        | "multi_edit_text_file"
+        | "apply_unified_diff"
+        | "create_directory" => acp::ToolKind::Edit,
+        "delete_file" => acp::ToolKind::Delete,
+        "move_file" | "rename_file" => acp::ToolKind::Move,
+        "list_directory" | "search_text" | "glob_files" => acp::ToolKind::Search,
+        "run_cached_command" => acp::ToolKind::Execute,
+        _ => acp::ToolKind::Fetch,
+    }
+}
+
 /// Describe an MCP tool call for ACP by creating a human-friendly title and
 /// mapping to zero or more `ToolCallLocation`s. When the invocation is an
 /// FS tool, the title includes the display path and a single location entry.
 pub fn describe_mcp_tool(
     invocation: &McpInvocation,
     cwd: &Path,
+    roots: &[PathBuf],
 ) -> (String, Vec<acp::ToolCallLocation>) {
-    if let Some(metadata) = fs_tool_metadata(invocation, cwd) {
-        let location = acp::ToolCallLocation {
-            path: metadata.location_path,
-            line: metadata.line,
-            meta: None,
-        };
+    if let Some(metadata) = fs_tool_metadata(invocation, cwd, roots)
+        && let Some(target) = fs_move_target(invocation, cwd, roots)
+    {
+        let source = tool_location(cwd, roots, &metadata.location_path, None);
+        let destination = tool_location(cwd, roots, &target, None);
+        (
+            format!(
+                "{}.{} ({} → {})",
+                invocation.server,
+                invocation.tool,
+                metadata.display_path,
+                display_path(roots, &target)
+            ),
+            vec![source, destination],
+        )
+    } else if let Some(metadata) = fs_tool_metadata(invocation, cwd, roots) {
+        let location = tool_location(cwd, roots, &metadata.location_path, metadata.line);
         (
             format!(
                 "{}.{} ({})",
@@ -158,3 +315,43 @@ pub fn describe_mcp_tool(
         )
     }
 }
+
+/// Locations reported to the client per `acp_fs.search_text` call.
+pub const MAX_SEARCH_LOCATIONS: usize = 50;
+
+/// Match locations of an `acp_fs.search_text` result, taken from the
+/// `codex_fs_search` metadata wherever it sits in the result.
+pub fn search_result_locations(
+    result: &serde_json::Value,
+    cwd: &Path,
+    roots: &[PathBuf],
+) -> Vec<acp::ToolCallLocation> {
+    fn find<'a>(value: &'a serde_json::Value) -> Option<&'a serde_json::Value> {
+        match value {
+            serde_json::Value::Object(map) => map
+                .get("codex_fs_search")
+                .or_else(|| map.values().find_map(find)),
+            serde_json::Value::Array(items) => items.iter().find_map(find),
+            _ => None,
+        }
+    }
+
+    let Some(locations) = find(result)
+        .and_then(|meta| meta.get("locations"))
+        .and_then(|locations| locations.as_array())
+    else {
+        return Vec::new();
+    };
+    locations
+        .iter()
+        .filter_map(|location| {
+            let path = location.get("path")?.as_str()?;
+            let line = location
+                .get("line")
+                .and_then(|line| line.as_u64())
+                .map(|line| line as u32);
+            Some(tool_location(cwd, roots, Path::new(path), line))
+        })
+        .take(MAX_SEARCH_LOCATIONS)
+        .collect()
+}
@@ -0,0 +1,292 @@
+//! MCP servers shipped with the workspace in `.codex/mcp.toml`.
+//!
+//! Teams can list project tooling (a database inspector, an API docs server)
+//! once in the repository instead of in every user's config:
+//!
+//! ```toml
+//! [mcp_servers.docs]
+//! command = "npx"
+//! args = ["-y", "@acme/docs-mcp"]
+//! env = { DOCS_INDEX = "./docs" }
+//!
+//! [mcp_servers.api]
+//! url = "http://localhost:4000/mcp"
+//! headers = { X-Team = "platform" }
+//! ```
+//!
+//! The file comes with the checkout, so its servers only start once the user
+//! has trusted it with `/mcp trust`. Trust records the file's exact content in
+//! `$CODEX_HOME/acp/trusted-mcp.json`; any later change to the file has to be
+//! trusted again. Servers passed by the client take precedence by name.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use agent_client_protocol::{EnvVariable, HttpHeader, McpServer};
+use toml_edit::{DocumentMut, Item, Value};
+use tracing::warn;
+
+use super::{core::CodexAgent, mcp_servers::server_name};
+
+/// Workspace-relative location of the server list.
+pub const WORKSPACE_MCP_FILE: &str = ".codex/mcp.toml";
+
+/// A parsed `/mcp` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum McpCommand {
+    List,
+    Trust,
+    Untrust,
+}
+
+/// Usage text for `/mcp`.
+pub const MCP_USAGE: &str = "Usage: /mcp [list] | trust | untrust\n";
+
+/// Parse `/mcp` arguments.
+pub fn parse_mcp_command(args: &str) -> Result<McpCommand, String> {
+    match args.trim().to_ascii_lowercase().as_str() {
+        "" | "list" => Ok(McpCommand::List),
+        "trust" => Ok(McpCommand::Trust),
+        "untrust" | "revoke" => Ok(McpCommand::Untrust),
+        _ => Err(MCP_USAGE.to_string()),
+    }
+}
+
+/// Servers listed in a `.codex/mcp.toml`. Relative commands containing a
+/// path separator are taken from `workspace`.
+pub fn parse_workspace_mcp(text: &str, workspace: &Path) -> Result<Vec<McpServer>, String> {
+    let doc: DocumentMut = text.parse().map_err(|err| format!("invalid TOML: {err}"))?;
+    let Some(servers) = doc.get("mcp_servers") else {
+        return Ok(Vec::new());
+    };
+    let servers = servers
+        .as_table_like()
+        .ok_or_else(|| "`mcp_servers` must be a table".to_string())?;
+    servers
+        .iter()
+        .map(|(name, item)| parse_server(name, item, workspace))
+        .collect()
+}
+
+fn parse_server(name: &str, item: &Item, workspace: &Path) -> Result<McpServer, String> {
+    let table = item
+        .as_table_like()
+        .ok_or_else(|| format!("mcp_servers.{name} must be a table"))?;
+    let string = |key: &str| -> Result<Option<String>, String> {
+        match table.get(key) {
+            None => Ok(None),
+            Some(item) => item
+                .as_str()
+                .map(|s| Some(s.to_string()))
+                .ok_or_else(|| format!("mcp_servers.{name}.{key} must be a string")),
+        }
+    };
+    let pairs = |key: &str| -> Result<Vec<(String, String)>, String> {
+        let Some(item) = table.get(key) else {
+            return Ok(Vec::new());
+        };
+        let entries = item
+            .as_table_like()
+            .ok_or_else(|| format!("mcp_servers.{name}.{key} must be a table"))?;
+        entries
+            .iter()
+            .map(|(k, v)| {
+                v.as_str()
+                    .map(|v| (k.to_string(), v.to_string()))
+                    .ok_or_else(|| format!("mcp_servers.{name}.{key}.{k} must be a string"))
+            })
+            .collect()
+    };
+
+    match (string("command")?, string("url")?) {
+        (Some(command), None) => {
+            let args = match table.get("args") {
+                None => Vec::new(),
+                Some(item) => item
+                    .as_array()
+                    .and_then(|args| {
+                        args.iter()
+                            .map(|arg| Value::as_str(arg).map(str::to_string))
+                            .collect::<Option<Vec<_>>>()
+                    })
+                    .ok_or_else(|| format!("mcp_servers.{name}.args must be a list of strings"))?,
+            };
+            let command = PathBuf::from(command);
+            let command = if command.is_relative() && command.components().count() > 1 {
+                workspace.join(command)
+            } else {
+                command
+            };
+            Ok(McpServer::Stdio {
+                name: name.to_string(),
+                command,
+                args,
+                env: pairs("env")?
+                    .into_iter()
+                    .map(|(name, value)| EnvVariable {
+                        name,
+                        value,
+                        meta: None,
+                    })
+                    .collect(),
+            })
+        }
+        (None, Some(url)) => Ok(McpServer::Http {
+            name: name.to_string(),
+            url,
+            headers: pairs("headers")?
+                .into_iter()
+                .map(|(name, value)| HttpHeader {
+                    name,
+                    value,
+                    meta: None,
+                })
+                .collect(),
+        }),
+        _ => Err(format!(
+            "mcp_servers.{name} needs either `command` or `url`"
+        )),
+    }
+}
+
+/// Short description of a server for listings.
+pub fn describe_server(server: &McpServer) -> String {
+    match server {
+        McpServer::Stdio {
+            name,
+            command,
+            args,
+            ..
+        } => {
+            let mut line = format!("{name}: {}", command.display());
+            for arg in args {
+                line.push(' ');
+                line.push_str(arg);
+            }
+            line
+        }
+        McpServer::Http { name, url, .. } | McpServer::Sse { name, url, .. } => {
+            format!("{name}: {url}")
+        }
+    }
+}
+
+/// Message for a session whose workspace lists servers that were not started.
+pub fn untrusted_notice(servers: &[McpServer]) -> String {
+    let names: Vec<String> = servers
+        .iter()
+        .map(|server| format!("`{}`", server_name(server)))
+        .collect();
+    format!(
+        "⚠️ This workspace's {WORKSPACE_MCP_FILE} lists MCP servers that were not started ({}). Review the file, then run /mcp trust to start them in new sessions.\n\n",
+        names.join(", ")
+    )
+}
+
+/// Workspace server files the user has trusted, keyed by workspace path.
+#[derive(Debug, Clone)]
+pub struct McpTrustStore {
+    path: PathBuf,
+}
+
+impl McpTrustStore {
+    pub fn new(codex_home: &Path) -> Self {
+        Self {
+            path: codex_home.join("acp").join("trusted-mcp.json"),
+        }
+    }
+
+    async fn load_all(&self) -> BTreeMap<String, String> {
+        let Ok(bytes) = tokio::fs::read(&self.path).await else {
+            return BTreeMap::new();
+        };
+        serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+            warn!(path = %self.path.display(), error = %err, "ignoring malformed MCP trust file");
+            BTreeMap::new()
+        })
+    }
+
+    async fn save_all(&self, all: &BTreeMap<String, String>) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&self.path, serde_json::to_vec_pretty(all)?).await
+    }
+
+    fn key(workspace: &Path) -> String {
+        workspace.to_string_lossy().into_owned()
+    }
+
+    /// Whether `content` is exactly what the user trusted for `workspace`.
+    pub async fn is_trusted(&self, workspace: &Path, content: &str) -> bool {
+        self.load_all()
+            .await
+            .get(&Self::key(workspace))
+            .is_some_and(|trusted| trusted == content)
+    }
+
+    pub async fn trust(&self, workspace: &Path, content: String) -> io::Result<()> {
+        let mut all = self.load_all().await;
+        all.insert(Self::key(workspace), content);
+        self.save_all(&all).await
+    }
+
+    /// Forget the trust for `workspace`, returning whether there was any.
+    pub async fn untrust(&self, workspace: &Path) -> io::Result<bool> {
+        let mut all = self.load_all().await;
+        if all.remove(&Self::key(workspace)).is_none() {
+            return Ok(false);
+        }
+        self.save_all(&all).await?;
+        Ok(true)
+    }
+}
+
+/// Read the workspace's server file, `None` when there is none.
+pub async fn read_workspace_mcp(workspace: &Path) -> Option<String> {
+    tokio::fs::read_to_string(workspace.join(WORKSPACE_MCP_FILE))
+        .await
+        .ok()
+}
+
+impl CodexAgent {
+    /// Trusted workspace servers not already among `requested`, plus a
+    /// message for the user when the workspace's servers were not started.
+    pub(super) async fn workspace_mcp_servers(
+        &self,
+        requested: &[McpServer],
+    ) -> (Vec<McpServer>, Option<String>) {
+        let workspace = &self.config.cwd;
+        let Some(text) = read_workspace_mcp(workspace).await else {
+            return (Vec::new(), None);
+        };
+        let servers = match parse_workspace_mcp(&text, workspace) {
+            Ok(servers) => servers,
+            Err(err) => {
+                warn!(error = %err, "ignoring invalid {WORKSPACE_MCP_FILE}");
+                return (
+                    Vec::new(),
+                    Some(format!("⚠️ Ignoring {WORKSPACE_MCP_FILE}: {err}\n\n")),
+                );
+            }
+        };
+        let servers: Vec<McpServer> = servers
+            .into_iter()
+            .filter(|server| {
+                !requested
+                    .iter()
+                    .any(|requested| server_name(requested) == server_name(server))
+            })
+            .collect();
+        if servers.is_empty() {
+            (Vec::new(), None)
+        } else if self.mcp_trust_store.is_trusted(workspace, &text).await {
+            (servers, None)
+        } else {
+            (Vec::new(), Some(untrusted_notice(&servers)))
+        }
+    }
+}
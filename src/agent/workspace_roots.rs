@@ -0,0 +1,120 @@
+//! Additional workspace roots of a session.
+//!
+//! Editors often open several folders at once. Besides the agent's working
+//! directory, a session can have more roots, given as absolute paths in
+//! `NewSessionRequest.meta.workspaceRoots` (or `LoadSessionRequest.meta`) or
+//! set later with the `codex/setWorkspaceRoots` extension method. The
+//! `acp_fs` tools may use them like the working directory, tool call titles
+//! show paths as `<root name>/<path>`, and a relative path given to the
+//! tools in that form resolves inside the named root. Tool call locations
+//! carry the root they lie in. The model is told about the roots with its
+//! next turn.
+
+use std::path::{Path, PathBuf};
+
+use agent_client_protocol::{Error, SessionId};
+use serde_json::Value;
+
+use super::{context::ContextItem, core::CodexAgent};
+use crate::fs::tools::workspace_scope;
+
+/// Read `NewSessionRequest.meta.workspaceRoots`.
+pub fn workspace_roots_from_meta(meta: Option<&Value>) -> Result<Option<Vec<PathBuf>>, Error> {
+    match meta.and_then(|m| m.get("workspaceRoots")) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => parse_roots(value).map(Some),
+    }
+}
+
+/// Parse a JSON array of absolute paths.
+pub fn parse_roots(value: &Value) -> Result<Vec<PathBuf>, Error> {
+    let invalid =
+        || Error::invalid_params().with_data("workspaceRoots must be an array of absolute paths");
+    value
+        .as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|root| {
+            root.as_str()
+                .map(Path::new)
+                .filter(|root| root.is_absolute())
+                .map(workspace_scope::normalize)
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+/// `roots` without duplicates and without `cwd`, which is always a root.
+pub fn extra_roots(cwd: &Path, roots: Vec<PathBuf>) -> Vec<PathBuf> {
+    let cwd = workspace_scope::normalize(cwd);
+    let mut extra: Vec<PathBuf> = Vec::new();
+    for root in roots {
+        if root != cwd && !extra.contains(&root) {
+            extra.push(root);
+        }
+    }
+    extra
+}
+
+/// Tell the model which roots the workspace has.
+pub fn roots_context(cwd: &Path, roots: &[PathBuf]) -> ContextItem {
+    let text = if roots.is_empty() {
+        format!("The workspace is back to a single root: {}.", cwd.display())
+    } else {
+        let listed: Vec<String> = std::iter::once(cwd)
+            .chain(roots.iter().map(PathBuf::as_path))
+            .map(|root| format!("- {}: {}", root_name(root), root.display()))
+            .collect();
+        format!(
+            "The workspace has several roots:\n{}\nThe acp_fs tools accept absolute paths in \
+             any root, or relative paths starting with the root's name (e.g. `{}/README.md`); \
+             other relative paths are relative to {}.",
+            listed.join("\n"),
+            root_name(&roots[0]),
+            cwd.display()
+        )
+    };
+    ContextItem::Text {
+        text,
+        label: Some("workspace roots".to_string()),
+    }
+}
+
+fn root_name(root: &Path) -> String {
+    root.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| root.display().to_string())
+}
+
+impl CodexAgent {
+    /// Replace the session's extra workspace roots, returning them.
+    pub(super) fn set_workspace_roots(
+        &self,
+        session_id: &SessionId,
+        roots: Vec<PathBuf>,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let roots = extra_roots(&self.config.cwd, roots);
+        let context = roots_context(&self.config.cwd, &roots);
+        let fs_session_id = self.try_with_session_state_mut(session_id, |state| {
+            if state.workspace_roots == roots {
+                return None;
+            }
+            state.workspace_roots = roots.clone();
+            state.pending_context.push(context);
+            Some(state.fs_session_id.clone())
+        })?;
+        if let (Some(fs_session_id), Some(bridge)) = (fs_session_id, &self.fs_bridge) {
+            // The session's helper sends the FS session id; the agent's own
+            // reads (attachments, images) use the ACP one.
+            bridge.set_workspace_roots(&fs_session_id, roots.clone());
+            bridge.set_workspace_roots(session_id.0.as_ref(), roots.clone());
+        }
+        Ok(roots)
+    }
+
+    /// The session's extra workspace roots.
+    pub(super) fn workspace_roots(&self, session_id: &SessionId) -> Vec<PathBuf> {
+        self.with_session_state_mut(session_id, |state| state.workspace_roots.clone())
+            .unwrap_or_default()
+    }
+}
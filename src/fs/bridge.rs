@@ -1,50 +1,196 @@
 use std::{
+    collections::{HashMap, HashSet},
+    fmt, io,
     net::SocketAddr,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 
 use agent_client_protocol as acp;
+use diffy::{PatchFormatter, create_patch};
 use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter},
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, BufWriter},
     net::{TcpListener, TcpStream},
     sync::oneshot,
     task,
 };
 use tracing::{debug, error, warn};
+use uuid::Uuid;
 
-use crate::agent::ClientOp;
+use super::{
+    client_op::ClientOp,
+    framing::{self, Framing},
+    tools::{
+        audit::{AuditEntry, AuditEvent},
+        binary_read::{self, BinaryFile},
+        chunked_write::{UploadArgs, Uploads},
+        command_cache::{self, CommandCache},
+        command_history::{self, CommandHistoryArgs},
+        conflicts::{self, Resolution},
+        file_info,
+        glob_files::{self, GlobArgs},
+        line_range,
+        listing::{self, ListArgs},
+        protected::{self, ProtectedPaths},
+        search::{self, SearchArgs},
+        tail_read::{self, TailRead},
+        undo::FileSnapshot,
+        workspace_scope::{self, OutsidePolicy, WorkspaceScope},
+        write_approval::{self, WriteDecision},
+    },
+};
+
+/// Environment variable choosing the bridge transport (`socket`, `tcp`).
+pub const BRIDGE_TRANSPORT_ENV: &str = "CODEX_ACP_FS_BRIDGE_TRANSPORT";
+
+/// Prefix of the responses to changes a dry run only simulated.
+pub const DRY_RUN_PREFIX: &str = "[dry run]";
+
+/// Option id of the `ask_user` choice for an answer in the user's own words.
+const ASK_USER_OTHER: &str = "other";
+
+/// Where the `--acp-fs-mcp` helper reaches the bridge, passed to it in
+/// `ACP_FS_BRIDGE_ADDR`.
+///
+/// Written as `unix:<path>`, `pipe:<name>`, or a plain `host:port` for TCP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeAddress {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+    Pipe(String),
+}
+
+impl BridgeAddress {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        if let Some(path) = value.strip_prefix("unix:") {
+            Ok(Self::Unix(PathBuf::from(path)))
+        } else if let Some(name) = value.strip_prefix("pipe:") {
+            Ok(Self::Pipe(name.to_string()))
+        } else {
+            value
+                .parse()
+                .map(Self::Tcp)
+                .map_err(|err| format!("invalid bridge address {value:?}: {err}"))
+        }
+    }
+
+    /// Open a connection to the bridge.
+    pub async fn connect(&self) -> io::Result<Box<dyn BridgeStream>> {
+        match self {
+            Self::Tcp(addr) => Ok(Box::new(TcpStream::connect(addr).await?)),
+            #[cfg(unix)]
+            Self::Unix(path) => Ok(Box::new(tokio::net::UnixStream::connect(path).await?)),
+            #[cfg(windows)]
+            Self::Pipe(name) => {
+                use tokio::net::windows::named_pipe::ClientOptions;
+                // All instances may be busy for a moment while the server re-arms.
+                loop {
+                    match ClientOptions::new().open(name) {
+                        Ok(client) => return Ok(Box::new(client)),
+                        Err(err) if err.raw_os_error() == Some(231) => {
+                            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("bridge transport {self} is not supported on this platform"),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for BridgeAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{addr}"),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+            Self::Pipe(name) => write!(f, "pipe:{name}"),
+        }
+    }
+}
+
+/// A connection to the bridge over any transport.
+pub trait BridgeStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> BridgeStream for T {}
+
+/// Removes a session's socket file when its listener goes away.
+struct SocketFile(PathBuf);
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// A session's socket or named pipe and the task accepting connections on
+/// it. Dropping it stops accepting and removes the socket file.
+struct SessionListener {
+    address: BridgeAddress,
+    accept: Option<task::JoinHandle<()>>,
+    _socket: Option<SocketFile>,
+}
+
+impl Drop for SessionListener {
+    fn drop(&mut self) {
+        if let Some(accept) = &self.accept {
+            accept.abort();
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct FsBridge {
+    /// Shared localhost port, used when a session socket cannot be created.
     address: SocketAddr,
-    _inner: Arc<FsBridgeInner>,
+    /// Directory for per-session sockets; `None` keeps every session on TCP.
+    socket_dir: Option<PathBuf>,
+    inner: Arc<FsBridgeInner>,
 }
 
 impl FsBridge {
+    /// Start the bridge. Sessions get their own socket under
+    /// `<codex_home>/acp/fs-bridge` (a named pipe on Windows) unless
+    /// `CODEX_ACP_FS_BRIDGE_TRANSPORT=tcp`; the localhost TCP port remains
+    /// the fallback.
     pub async fn start(
         client_tx: tokio::sync::mpsc::UnboundedSender<ClientOp>,
         workspace_root: PathBuf,
+        codex_home: &Path,
     ) -> anyhow::Result<Arc<FsBridge>> {
         let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
         let address = listener.local_addr()?;
+        let tcp_only =
+            std::env::var(BRIDGE_TRANSPORT_ENV).is_ok_and(|v| v.trim().eq_ignore_ascii_case("tcp"));
+        let socket_dir = (!tcp_only).then(|| codex_home.join("acp").join("fs-bridge"));
         let inner = Arc::new(FsBridgeInner {
             client_tx,
+            scope: WorkspaceScope::from_env(&workspace_root),
             workspace_root,
+            session_roots: Mutex::new(HashMap::new()),
+            protected: ProtectedPaths::from_env(),
+            snapshots: Mutex::new(HashMap::new()),
+            tokens: Mutex::new(HashMap::new()),
+            uploads: Mutex::new(Uploads::default()),
+            connections: AtomicUsize::new(0),
+            confirm_writes: write_approval::confirm_writes_from_env(),
+            writes_allowed: Mutex::new(HashSet::new()),
+            listeners: Mutex::new(HashMap::new()),
+            command_caches: Mutex::new(HashMap::new()),
         });
         let accept_inner = inner.clone();
         task::spawn_local(async move {
             let listener = listener;
             loop {
                 match listener.accept().await {
-                    Ok((stream, addr)) => {
-                        let connection_inner = accept_inner.clone();
-                        task::spawn_local(async move {
-                            if let Err(err) = handle_connection(stream, connection_inner).await {
-                                warn!(error = %err, remote = %addr, "fs bridge connection errored");
-                            }
-                        });
-                    }
+                    Ok((stream, _)) => spawn_connection(stream, accept_inner.clone(), None),
                     Err(err) => {
                         error!(error = %err, "fs bridge listener failed");
                         break;
@@ -55,12 +201,239 @@ impl FsBridge {
 
         Ok(Arc::new(FsBridge {
             address,
-            _inner: inner,
+            socket_dir,
+            inner,
         }))
     }
 
-    pub fn address(&self) -> SocketAddr {
-        self.address
+    /// The secret a session's `--acp-fs-mcp` helper must send with every
+    /// request, generated on first use and kept until the session is removed.
+    pub fn session_token(&self, session_id: &str) -> String {
+        let Ok(mut tokens) = self.inner.tokens.lock() else {
+            // Nothing can be authorized without the table; refuse everything.
+            return String::new();
+        };
+        tokens
+            .entry(session_id.to_string())
+            .or_insert_with(|| format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()))
+            .clone()
+    }
+
+    /// Whether `token` is the secret issued for `session_id`.
+    pub fn is_authorized(&self, session_id: &str, token: &str) -> bool {
+        self.inner.is_authorized(session_id, token)
+    }
+
+    /// Forget a session: its token stops working, its socket is closed and
+    /// its roots, snapshots and write permission are dropped.
+    pub fn remove_session(&self, session_id: &str) {
+        if let Ok(mut tokens) = self.inner.tokens.lock() {
+            tokens.remove(session_id);
+        }
+        if let Ok(mut listeners) = self.inner.listeners.lock() {
+            listeners.remove(session_id);
+        }
+        if let Ok(mut session_roots) = self.inner.session_roots.lock() {
+            session_roots.remove(session_id);
+        }
+        if let Ok(mut snapshots) = self.inner.snapshots.lock() {
+            snapshots.retain(|(session, _), _| session != session_id);
+        }
+        if let Ok(mut writes_allowed) = self.inner.writes_allowed.lock() {
+            writes_allowed.remove(session_id);
+        }
+        if let Ok(mut command_caches) = self.inner.command_caches.lock() {
+            command_caches.remove(session_id);
+        }
+    }
+
+    /// Drop the cached command results of a session: a turn started or the
+    /// workspace may have changed outside the bridge.
+    pub fn invalidate_commands(&self, session_id: &str) {
+        self.inner.invalidate_commands(session_id);
+    }
+
+    /// Set the extra workspace roots of a session, replacing earlier ones.
+    pub fn set_workspace_roots(&self, session_id: &str, roots: Vec<PathBuf>) {
+        if let Ok(mut session_roots) = self.inner.session_roots.lock() {
+            if roots.is_empty() {
+                session_roots.remove(session_id);
+            } else {
+                session_roots.insert(session_id.to_string(), roots);
+            }
+        }
+    }
+
+    /// What the agent last read in full or wrote of `path`, and the file as it
+    /// is now, if the user changed it in between.
+    pub async fn user_edit(&self, session_id: &str, path: &Path) -> Option<(String, String)> {
+        let session_id = acp::SessionId(session_id.into());
+        let base = self.inner.snapshots.lock().ok().and_then(|snapshots| {
+            snapshots
+                .get(&FsBridgeInner::snapshot_key(&session_id, path))
+                .cloned()
+        })?;
+        let current = self
+            .inner
+            .read_with_fallback(&session_id, path, None, None)
+            .await
+            .ok()?;
+        (current != base).then_some((base, current))
+    }
+
+    /// Take `content` as what the agent last saw of `path`.
+    pub fn remember_file(&self, session_id: &str, path: &Path, content: &str) {
+        self.inner
+            .remember_snapshot(&acp::SessionId(session_id.into()), path, content);
+    }
+
+    /// Forget what the agent saw of `path`, e.g. after Codex changed it.
+    pub fn forget_file(&self, session_id: &str, path: &Path) {
+        self.inner
+            .forget_snapshot(&acp::SessionId(session_id.into()), path);
+    }
+
+    /// Number of `--acp-fs-mcp` helpers currently connected.
+    pub fn connection_count(&self) -> usize {
+        self.inner.connections.load(Ordering::Relaxed)
+    }
+
+    /// Bridge address for one session's `--acp-fs-mcp` helper.
+    ///
+    /// A session socket only accepts requests for that session; it is created
+    /// on first use and kept until the session is removed. If it cannot be
+    /// created (e.g. the path is too long), the shared TCP port is used.
+    pub fn session_address(&self, session_id: &str) -> BridgeAddress {
+        let Some(dir) = &self.socket_dir else {
+            return BridgeAddress::Tcp(self.address);
+        };
+        let Ok(mut listeners) = self.inner.listeners.lock() else {
+            return BridgeAddress::Tcp(self.address);
+        };
+        if let Some(listener) = listeners.get(session_id) {
+            return listener.address.clone();
+        }
+        match self.listen_session(dir, session_id) {
+            Ok(listener) => {
+                let address = listener.address.clone();
+                listeners.insert(session_id.to_string(), listener);
+                address
+            }
+            Err(err) => {
+                warn!(error = %err, "failed to create fs bridge socket; falling back to TCP");
+                BridgeAddress::Tcp(self.address)
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn listen_session(&self, dir: &Path, session_id: &str) -> io::Result<SessionListener> {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::create_dir_all(dir)?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+        let path = dir.join(format!("{session_id}.sock"));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path)?;
+        let socket = SocketFile(path.clone());
+        let inner = self.inner.clone();
+        let session_id = session_id.to_string();
+        let accept = task::spawn_local(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        spawn_connection(stream, inner.clone(), Some(session_id.clone()))
+                    }
+                    Err(err) => {
+                        error!(error = %err, "fs bridge socket listener failed");
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(SessionListener {
+            address: BridgeAddress::Unix(path),
+            accept: Some(accept),
+            _socket: Some(socket),
+        })
+    }
+
+    #[cfg(windows)]
+    fn listen_session(&self, _dir: &Path, session_id: &str) -> io::Result<SessionListener> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let name = format!(r"\\.\pipe\codex-acp-fs-{session_id}");
+        let mut server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .reject_remote_clients(true)
+            .create(&name)?;
+        let inner = self.inner.clone();
+        let session_id = session_id.to_string();
+        let pipe = name.clone();
+        let accept = task::spawn_local(async move {
+            loop {
+                if let Err(err) = server.connect().await {
+                    error!(error = %err, "fs bridge pipe listener failed");
+                    break;
+                }
+                // Re-arm before handing off so the next client finds an instance.
+                let next = match ServerOptions::new()
+                    .reject_remote_clients(true)
+                    .create(&pipe)
+                {
+                    Ok(next) => next,
+                    Err(err) => {
+                        error!(error = %err, "fs bridge pipe listener failed");
+                        break;
+                    }
+                };
+                let connected = std::mem::replace(&mut server, next);
+                spawn_connection(connected, inner.clone(), Some(session_id.clone()));
+            }
+        });
+        Ok(SessionListener {
+            address: BridgeAddress::Pipe(name),
+            accept: Some(accept),
+            _socket: None,
+        })
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn listen_session(&self, _dir: &Path, _session_id: &str) -> io::Result<SessionListener> {
+        Ok(SessionListener {
+            address: BridgeAddress::Tcp(self.address),
+            accept: None,
+            _socket: None,
+        })
+    }
+
+    /// Read raw file bytes from local disk (ACP has no binary read), resolving
+    /// relative paths against the session's workspace roots.
+    pub async fn read_file_bytes(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+    ) -> Result<Vec<u8>, String> {
+        let resolved = self.inner.resolve_path(session_id, path);
+        tokio::fs::read(&resolved)
+            .await
+            .map_err(|err| format!("failed to read {}: {err}", resolved.display()))
+    }
+
+    /// Read a workspace file on behalf of the agent itself, using the same
+    /// client-first/local-fallback path as MCP tool requests. Paths named by
+    /// the user are not limited to the workspace.
+    pub async fn read_text_file(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+        line: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<String, String> {
+        let resolved = self.inner.resolve_path(session_id, path);
+        self.inner
+            .read_with_fallback(session_id, &resolved, line, limit)
+            .await
     }
 }
 
@@ -69,17 +442,66 @@ impl FsBridge {
 pub enum BridgeOp {
     Read,
     Write,
+    Diagnostics,
+    AskUser,
+    CommandHistory,
+    ListDirectory,
+    SearchText,
+    GlobFiles,
+    /// Chunked write frames, for content too large for one request line.
+    WriteBegin,
+    WriteAppend,
+    WriteCommit,
+    WriteAbort,
+    Delete,
+    Move,
+    FileInfo,
+    /// Whole small file, base64-encoded; ACP only reads text.
+    ReadBinary,
+    CreateDirectory,
+    /// A read-only command, answered from the session's command cache when
+    /// it ran before.
+    RunCommand,
 }
 
 #[derive(Debug, serde::Deserialize)]
 struct BridgeRequest {
     id: u64,
     session_id: String,
+    /// The session's secret from [`FsBridge::session_token`].
+    #[serde(default)]
+    token: String,
     op: BridgeOp,
+    #[serde(default)]
     path: String,
     line: Option<u32>,
     limit: Option<u32>,
+    /// Read the last `limit` lines instead of from `line`.
+    #[serde(default)]
+    from_end: bool,
     content: Option<String>,
+    /// Structured arguments for ops beyond plain read/write.
+    #[serde(default)]
+    args: Option<serde_json::Value>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct MoveArgs {
+    destination: String,
+    #[serde(default)]
+    overwrite: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct RunCommandArgs {
+    command: Vec<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct AskUserArgs {
+    question: String,
+    #[serde(default)]
+    options: Vec<String>,
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -92,22 +514,120 @@ struct BridgeResponse {
     error: Option<String>,
 }
 
+impl BridgeResponse {
+    fn success(id: u64, content: Option<String>) -> Self {
+        Self {
+            id,
+            success: true,
+            content,
+            error: None,
+        }
+    }
+
+    fn failure(id: u64, error: String) -> Self {
+        Self {
+            id,
+            success: false,
+            content: None,
+            error: Some(error),
+        }
+    }
+}
+
 struct FsBridgeInner {
     client_tx: tokio::sync::mpsc::UnboundedSender<ClientOp>,
     workspace_root: PathBuf,
+    /// Roots the model may use without asking, see [`workspace_scope`].
+    scope: WorkspaceScope,
+    /// Extra workspace roots of each session, by FS or ACP session id.
+    session_roots: Mutex<HashMap<String, Vec<PathBuf>>>,
+    protected: ProtectedPaths,
+    /// Content the agent last read in full or wrote, per session and file,
+    /// used to notice edits the user made in between.
+    snapshots: Mutex<HashMap<(String, PathBuf), String>>,
+    /// Secret each session's requests must carry, by FS session id.
+    tokens: Mutex<HashMap<String, String>>,
+    /// Chunked writes in progress, see [`chunked_write`].
+    uploads: Mutex<Uploads>,
+    /// Helper connections currently open.
+    connections: AtomicUsize,
+    /// Ask before each write, see [`write_approval`].
+    confirm_writes: bool,
+    /// Sessions whose user chose to always allow writes.
+    writes_allowed: Mutex<HashSet<String>>,
+    /// Per-session sockets, by FS session id.
+    listeners: Mutex<HashMap<String, SessionListener>>,
+    /// Results of read-only commands, by FS session id.
+    command_caches: Mutex<HashMap<String, CommandCache>>,
+}
+
+/// Serve a connection on its own task; `session` pins it to one session.
+fn spawn_connection<S>(stream: S, inner: Arc<FsBridgeInner>, session: Option<String>)
+where
+    S: AsyncRead + AsyncWrite + 'static,
+{
+    inner.connections.fetch_add(1, Ordering::Relaxed);
+    task::spawn_local(async move {
+        if let Err(err) = handle_connection(stream, inner.clone(), session).await {
+            warn!(error = %err, "fs bridge connection errored");
+        }
+        inner.connections.fetch_sub(1, Ordering::Relaxed);
+    });
 }
 
-async fn handle_connection(stream: TcpStream, inner: Arc<FsBridgeInner>) -> anyhow::Result<()> {
-    let (read_half, write_half) = stream.into_split();
-    let mut reader = BufReader::new(read_half).lines();
+async fn handle_connection<S>(
+    stream: S,
+    inner: Arc<FsBridgeInner>,
+    session: Option<String>,
+) -> anyhow::Result<()>
+where
+    S: AsyncRead + AsyncWrite + 'static,
+{
+    let (read_half, write_half) = tokio::io::split(stream);
+    let mut reader = BufReader::new(read_half);
     let mut writer = BufWriter::new(write_half);
 
-    while let Some(line) = reader.next_line().await? {
-        if line.trim().is_empty() {
-            continue;
+    // A helper that wants other framing says hello first; anything else is
+    // already a request in newline-delimited JSON.
+    let mut first = match framing::read_message(&mut reader, Framing::Lines).await? {
+        Some(first) => Some(first),
+        None => return Ok(()),
+    };
+    let framing = match first
+        .as_deref()
+        .and_then(|line| std::str::from_utf8(line).ok())
+        .and_then(framing::parse_hello)
+    {
+        Some(framing) => {
+            first = None;
+            writer.write_all(framing::hello(framing).as_bytes()).await?;
+            writer.flush().await?;
+            framing
+        }
+        None => Framing::Lines,
+    };
+
+    // Requests are answered as they finish, so a slow one (an `ask_user`
+    // question, a write waiting on a prompt) does not hold up the others.
+    let (response_tx, mut response_rx) = tokio::sync::mpsc::unbounded_channel();
+    let writer = task::spawn_local(async move {
+        while let Some(response) = response_rx.recv().await {
+            let response_json = serde_json::to_vec::<BridgeResponse>(&response)?;
+            framing::write_message(&mut writer, framing, &response_json).await?;
         }
+        anyhow::Ok(())
+    });
+
+    loop {
+        let message = match first.take() {
+            Some(message) => message,
+            None => match framing::read_message(&mut reader, framing).await? {
+                Some(message) => message,
+                None => break,
+            },
+        };
 
-        let request: BridgeRequest = match serde_json::from_str(&line) {
+        let request: BridgeRequest = match serde_json::from_slice(&message) {
             Ok(req) => req,
             Err(err) => {
                 warn!(error = %err, "fs bridge received malformed request");
@@ -115,116 +635,1329 @@ async fn handle_connection(stream: TcpStream, inner: Arc<FsBridgeInner>) -> anyh
             }
         };
 
-        let response = inner.handle_request(request).await;
-        let response_json = serde_json::to_string(&response)?;
-        writer.write_all(response_json.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
-        writer.flush().await?;
+        let inner = inner.clone();
+        let session = session.clone();
+        let response_tx = response_tx.clone();
+        task::spawn_local(async move {
+            let response = match &session {
+                _ if !inner.is_authorized(&request.session_id, &request.token) => {
+                    warn!(session_id = %request.session_id, "fs bridge request with a wrong token");
+                    BridgeResponse::failure(request.id, "unauthorized bridge request".to_string())
+                }
+                Some(session) if *session != request.session_id => {
+                    warn!(session_id = %request.session_id, "fs bridge request for another session");
+                    BridgeResponse::failure(
+                        request.id,
+                        "request is for a different session than this bridge socket".to_string(),
+                    )
+                }
+                _ => inner.handle_request(request).await,
+            };
+            let _ = response_tx.send(response);
+        });
     }
 
-    Ok(())
+    // Let requests still in flight answer before the connection closes.
+    drop(response_tx);
+    writer.await?
 }
 
 impl FsBridgeInner {
+    /// Whether `token` is the secret issued for `session_id`.
+    fn is_authorized(&self, session_id: &str, token: &str) -> bool {
+        let Ok(tokens) = self.tokens.lock() else {
+            return false;
+        };
+        let Some(expected) = tokens.get(session_id) else {
+            return false;
+        };
+        // Compare without stopping at the first difference.
+        expected.len() == token.len()
+            && expected
+                .bytes()
+                .zip(token.bytes())
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+
     async fn handle_request(&self, request: BridgeRequest) -> BridgeResponse {
         let BridgeRequest {
             id,
             session_id,
+            token: _,
             op,
             path,
             line,
             limit,
+            from_end,
             content,
+            args,
         } = request;
 
-        let resolved_path = match self.resolve_path(&path) {
-            Ok(p) => p,
-            Err(err) => {
-                return BridgeResponse {
-                    id,
-                    success: false,
-                    content: None,
-                    error: Some(err),
-                };
+        let session_id = acp::SessionId(session_id.into());
+
+        let result = match op {
+            BridgeOp::Read if from_end => self.handle_read_tail(&session_id, &path, limit).await,
+            BridgeOp::Read => self.handle_read(&session_id, &path, line, limit).await,
+            BridgeOp::Write => self.handle_write(&session_id, &path, content).await,
+            BridgeOp::Diagnostics => self.handle_diagnostics(session_id, &path).await,
+            BridgeOp::AskUser => self.handle_ask_user(session_id, args).await.map(Some),
+            BridgeOp::CommandHistory => self
+                .handle_command_history(session_id, args)
+                .await
+                .map(Some),
+            BridgeOp::ListDirectory => self
+                .handle_list_directory(&session_id, &path, args)
+                .await
+                .map(Some),
+            BridgeOp::SearchText => self
+                .handle_search_text(&session_id, &path, args)
+                .await
+                .map(Some),
+            BridgeOp::GlobFiles => self
+                .handle_glob_files(&session_id, &path, args)
+                .await
+                .map(Some),
+            BridgeOp::WriteBegin => self
+                .handle_write_begin(&session_id, &path, args)
+                .await
+                .map(Some),
+            BridgeOp::WriteAppend => self
+                .handle_write_append(&session_id, args, content)
+                .map(|_| None),
+            BridgeOp::WriteCommit => self.handle_write_commit(&session_id, args).await,
+            BridgeOp::WriteAbort => self.handle_write_abort(&session_id, args).map(|_| None),
+            BridgeOp::Delete => self.handle_delete(&session_id, &path).await.map(Some),
+            BridgeOp::Move => self.handle_move(&session_id, &path, args).await.map(Some),
+            BridgeOp::FileInfo => self.handle_file_info(&session_id, &path).await.map(Some),
+            BridgeOp::ReadBinary => self.handle_read_binary(&session_id, &path).await.map(Some),
+            BridgeOp::CreateDirectory => self
+                .handle_create_directory(&session_id, &path)
+                .await
+                .map(Some),
+            BridgeOp::RunCommand => self
+                .handle_run_command(&session_id, &path, args)
+                .await
+                .map(Some),
+        };
+        if matches!(
+            op,
+            BridgeOp::Write
+                | BridgeOp::WriteCommit
+                | BridgeOp::Delete
+                | BridgeOp::Move
+                | BridgeOp::CreateDirectory
+        ) {
+            self.invalidate_commands(session_id.0.as_ref());
+        }
+
+        match result {
+            Ok(content) => BridgeResponse::success(id, content),
+            Err(err) => BridgeResponse::failure(id, err),
+        }
+    }
+
+    async fn handle_read(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+        line: Option<u32>,
+        limit: Option<u32>,
+    ) -> Result<Option<String>, String> {
+        let resolved_path = self.resolve_path(session_id, path);
+        self.check_scope(session_id, &resolved_path, acp::ToolKind::Read)
+            .await?;
+        let ranged = line.is_some() || limit.is_some();
+        // Small files are read whole even for a range, so the snapshot a
+        // later write is checked against covers ranged reads too.
+        let small = tokio::fs::metadata(&resolved_path)
+            .await
+            .is_ok_and(|meta| meta.len() <= line_range::MAX_RANGE_BYTES as u64);
+        if ranged && !small {
+            return self
+                .read_with_fallback(session_id, &resolved_path, line, limit)
+                .await
+                .map(Some);
+        }
+        let content = self
+            .read_with_fallback(session_id, &resolved_path, None, None)
+            .await?;
+        self.remember_snapshot(session_id, &resolved_path, &content);
+        if !ranged {
+            return Ok(Some(content));
+        }
+        line_range::read_line_range(
+            &mut content.as_bytes(),
+            line.unwrap_or(1),
+            limit,
+            line_range::MAX_RANGE_BYTES,
+        )
+        .await
+        .map(Some)
+        .map_err(|err| format!("failed to read {}: {err}", resolved_path.display()))
+    }
+
+    /// Read the last `limit` lines of a file, returned as a [`TailRead`].
+    ///
+    /// Files too large for a ranged read are read backwards from disk.
+    async fn handle_read_tail(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+        limit: Option<u32>,
+    ) -> Result<Option<String>, String> {
+        let resolved_path = self.resolve_path(session_id, path);
+        self.check_scope(session_id, &resolved_path, acp::ToolKind::Read)
+            .await?;
+        let count = limit.unwrap_or(u32::MAX);
+        let large = tokio::fs::metadata(&resolved_path)
+            .await
+            .is_ok_and(|meta| meta.len() > line_range::MAX_RANGE_BYTES as u64);
+        let tail = if large {
+            tail_read::read_tail(&resolved_path, count, tail_read::TAIL_BLOCK_BYTES)
+                .await
+                .map_err(|err| match err.kind() {
+                    io::ErrorKind::InvalidData => format!(
+                        "{} is not UTF-8 text; use read_binary_file for binary files",
+                        resolved_path.display()
+                    ),
+                    _ => format!("failed to read {}: {err}", resolved_path.display()),
+                })?
+        } else {
+            let content = self
+                .read_with_fallback(session_id, &resolved_path, None, None)
+                .await?;
+            self.remember_snapshot(session_id, &resolved_path, &content);
+            TailRead::new(&content, count)
+        };
+        serde_json::to_string(&tail)
+            .map(Some)
+            .map_err(|err| err.to_string())
+    }
+
+    /// Write a file; when the user's concurrent edit was rebased into the
+    /// write, the merged content is returned so the caller can re-sync. A
+    /// dry run asks nothing and answers with a [`DRY_RUN_PREFIX`] note and
+    /// the diff the write would make instead.
+    async fn handle_write(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+        content: Option<String>,
+    ) -> Result<Option<String>, String> {
+        let resolved_path = self.resolve_path(session_id, path);
+        let content = content.ok_or_else(|| "missing content for write".to_string())?;
+        self.ensure_writable(session_id).await?;
+        self.check_scope(session_id, &resolved_path, acp::ToolKind::Edit)
+            .await?;
+        // A dry run leaves the disk alone, including missing parent directories.
+        if self.is_dry_run(session_id).await? {
+            let before = self
+                .read_with_fallback(session_id, &resolved_path, None, None)
+                .await
+                .unwrap_or_default();
+            return Ok(Some(dry_run_write(&resolved_path, &before, &content)));
+        }
+        let protected_pattern = self
+            .protected
+            .matching(&self.root_of(session_id, &resolved_path), &resolved_path)
+            .map(str::to_string);
+        if let Some(pattern) = &protected_pattern
+            && let Err(err) = self
+                .confirm_protected_write(session_id, &resolved_path, pattern, &content)
+                .await
+        {
+            self.audit_write(session_id, &resolved_path, content.len(), "rejected");
+            return Err(err);
+        }
+        let (content, rebased) = self
+            .arbitrate_conflict(session_id, &resolved_path, content)
+            .await?;
+        let decision = if protected_pattern.is_some() {
+            "approved"
+        } else {
+            match self
+                .confirm_write(session_id, &resolved_path, &content)
+                .await
+            {
+                Ok(decision) => decision,
+                Err(err) => {
+                    self.audit_write(session_id, &resolved_path, content.len(), "rejected");
+                    return Err(err);
+                }
             }
         };
+        let before = match tokio::fs::read_to_string(&resolved_path).await {
+            Ok(text) => Some(Some(text)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Some(None),
+            // Unreadable or not text: the write cannot be undone.
+            Err(_) => None,
+        };
+        self.write_with_fallback(session_id, &resolved_path, content.clone())
+            .await?;
+        self.audit_write(session_id, &resolved_path, content.len(), decision);
+        if let Some(before) = before {
+            let _ = self.client_tx.send(ClientOp::RecordUndo {
+                session_id: session_id.clone(),
+                files: vec![FileSnapshot {
+                    path: resolved_path.clone(),
+                    before,
+                    moved_to: None,
+                }],
+            });
+        }
+        self.remember_snapshot(session_id, &resolved_path, &content);
+        Ok(rebased.then_some(content))
+    }
 
-        let session_id = acp::SessionId(session_id.into());
+    /// Start a chunked write, returning the upload id.
+    async fn handle_write_begin(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+        args: Option<serde_json::Value>,
+    ) -> Result<String, String> {
+        let args = Self::upload_args(args)?;
+        // Fail before the content is sent; the commit checks everything again.
+        self.ensure_writable(session_id).await?;
+        self.uploads
+            .lock()
+            .map_err(|_| "upload state poisoned".to_string())?
+            .begin(&session_id.0, path, args.size, std::time::Instant::now())
+    }
+
+    fn handle_write_append(
+        &self,
+        session_id: &acp::SessionId,
+        args: Option<serde_json::Value>,
+        content: Option<String>,
+    ) -> Result<usize, String> {
+        let args = Self::upload_args(args)?;
+        let upload_id = args
+            .upload_id
+            .ok_or_else(|| "missing upload_id".to_string())?;
+        let chunk = content.ok_or_else(|| "missing content for write_append".to_string())?;
+        self.uploads
+            .lock()
+            .map_err(|_| "upload state poisoned".to_string())?
+            .append(&session_id.0, &upload_id, args.offset, &chunk)
+    }
+
+    /// Write an upload's assembled content like a single `write`.
+    async fn handle_write_commit(
+        &self,
+        session_id: &acp::SessionId,
+        args: Option<serde_json::Value>,
+    ) -> Result<Option<String>, String> {
+        let args = Self::upload_args(args)?;
+        let upload_id = args
+            .upload_id
+            .ok_or_else(|| "missing upload_id".to_string())?;
+        let (path, content) = self
+            .uploads
+            .lock()
+            .map_err(|_| "upload state poisoned".to_string())?
+            .finish(&session_id.0, &upload_id)?;
+        self.handle_write(session_id, &path, Some(content)).await
+    }
+
+    fn handle_write_abort(
+        &self,
+        session_id: &acp::SessionId,
+        args: Option<serde_json::Value>,
+    ) -> Result<bool, String> {
+        let args = Self::upload_args(args)?;
+        let upload_id = args
+            .upload_id
+            .ok_or_else(|| "missing upload_id".to_string())?;
+        Ok(self
+            .uploads
+            .lock()
+            .map_err(|_| "upload state poisoned".to_string())?
+            .abort(&session_id.0, &upload_id))
+    }
+
+    fn upload_args(args: Option<serde_json::Value>) -> Result<UploadArgs, String> {
+        serde_json::from_value(args.ok_or_else(|| "missing upload args".to_string())?)
+            .map_err(|err| format!("invalid upload args: {err}"))
+    }
+
+    fn snapshot_key(session_id: &acp::SessionId, path: &Path) -> (String, PathBuf) {
+        (session_id.0.to_string(), path.to_path_buf())
+    }
+
+    fn remember_snapshot(&self, session_id: &acp::SessionId, path: &Path, content: &str) {
+        if let Ok(mut snapshots) = self.snapshots.lock() {
+            snapshots.insert(Self::snapshot_key(session_id, path), content.to_string());
+        }
+    }
+
+    fn forget_snapshot(&self, session_id: &acp::SessionId, path: &Path) {
+        if let Ok(mut snapshots) = self.snapshots.lock() {
+            snapshots.remove(&Self::snapshot_key(session_id, path));
+        }
+    }
+
+    /// Carry what the agent last saw of `from` over to `to`.
+    fn move_snapshot(&self, session_id: &acp::SessionId, from: &Path, to: &Path) {
+        if let Ok(mut snapshots) = self.snapshots.lock() {
+            match snapshots.remove(&Self::snapshot_key(session_id, from)) {
+                Some(content) => {
+                    snapshots.insert(Self::snapshot_key(session_id, to), content);
+                }
+                None => {
+                    snapshots.remove(&Self::snapshot_key(session_id, to));
+                }
+            }
+        }
+    }
+
+    /// Check whether the user changed `path` since the agent last saw it and,
+    /// if so, let them decide how the agent's write proceeds.
+    ///
+    /// Returns the content to write and whether it was rebased onto the
+    /// user's edit.
+    async fn arbitrate_conflict(
+        &self,
+        session_id: &acp::SessionId,
+        path: &Path,
+        content: String,
+    ) -> Result<(String, bool), String> {
+        let base = self.snapshots.lock().ok().and_then(|snapshots| {
+            snapshots
+                .get(&Self::snapshot_key(session_id, path))
+                .cloned()
+        });
+        let Some(base) = base else {
+            return Ok((content, false));
+        };
+        // A file that vanished or cannot be read is not something to merge with.
+        let Ok(current) = self.read_with_fallback(session_id, path, None, None).await else {
+            return Ok((content, false));
+        };
+        if !conflicts::is_conflict(&base, &current, &content) {
+            return Ok((content, false));
+        }
+
+        let call_id = format!("write_conflict-{}", Uuid::new_v4());
+        let request =
+            conflicts::conflict_request(session_id, &call_id, path, &base, &current, &content);
+        let (tx, rx) = oneshot::channel();
+        self.client_tx
+            .send(ClientOp::RequestPermission {
+                session_id: session_id.clone(),
+                request,
+                response_tx: tx,
+            })
+            .map_err(|_| "client request_permission channel closed".to_string())?;
+        let resolution = match rx.await {
+            Ok(Ok(resp)) => conflicts::resolution(&resp),
+            _ => Resolution::Abort,
+        };
+
+        let display = path.display();
+        match resolution {
+            Resolution::Overwrite => Ok((content, false)),
+            Resolution::Rebase => conflicts::rebase(&base, &current, &content)
+                .map(|merged| (merged, true))
+                .map_err(|err| {
+                    // Let the next attempt start from what is on disk now.
+                    self.remember_snapshot(session_id, path, &current);
+                    format!(
+                        "could not rebase the user's edit of {display}: {err}; \
+                         re-read the file and apply the change again"
+                    )
+                }),
+            Resolution::Abort => {
+                self.remember_snapshot(session_id, path, &current);
+                Err(format!(
+                    "write to {display} aborted: the user changed the file since it was read; \
+                     re-read it before editing"
+                ))
+            }
+        }
+    }
 
-        match op {
-            BridgeOp::Read => {
-                match self
-                    .read_with_fallback(&session_id, &resolved_path, line, limit)
+    /// With `CODEX_ACP_CONFIRM_WRITES`, ask the user before writing `content`
+    /// unless they already allowed all writes for the session. Dry runs
+    /// write nothing and are not asked about.
+    async fn confirm_write(
+        &self,
+        session_id: &acp::SessionId,
+        path: &Path,
+        content: &str,
+    ) -> Result<&'static str, String> {
+        if !self.confirm_writes {
+            return Ok("auto");
+        }
+        let allowed = self
+            .writes_allowed
+            .lock()
+            .map_err(|_| "write approval state poisoned".to_string())?
+            .contains(session_id.0.as_ref());
+        if allowed {
+            return Ok("approved_for_session");
+        }
+        let old_text = tokio::fs::read_to_string(path).await.ok();
+        let request = write_approval::write_permission_request(
+            session_id.clone(),
+            format!("fs_write-{}", Uuid::new_v4()),
+            path,
+            old_text,
+            content.to_string(),
+        );
+        let display = path.display();
+        let (tx, rx) = oneshot::channel();
+        self.client_tx
+            .send(ClientOp::RequestPermission {
+                session_id: session_id.clone(),
+                request,
+                response_tx: tx,
+            })
+            .map_err(|_| "client request_permission channel closed".to_string())?;
+        let response = match rx.await {
+            Ok(Ok(response)) => response,
+            Ok(Err(err)) => {
+                return Err(format!(
+                    "write to {display} refused: approval unavailable ({})",
+                    err.message
+                ));
+            }
+            Err(_) => return Err(format!("write to {display} refused: approval unavailable")),
+        };
+        match WriteDecision::from_response(&response) {
+            WriteDecision::AllowOnce => Ok("approved"),
+            WriteDecision::AllowAlways => {
+                if let Ok(mut allowed) = self.writes_allowed.lock() {
+                    allowed.insert(session_id.0.to_string());
+                }
+                Ok("approved_for_session")
+            }
+            WriteDecision::Reject => Err(format!("write to {display} was rejected by the user")),
+        }
+    }
+
+    /// Record a bridge write, or its refusal, in the agent's audit log.
+    fn audit_write(&self, session_id: &acp::SessionId, path: &Path, bytes: usize, decision: &str) {
+        self.audit_change(
+            session_id,
+            AuditEvent::Write,
+            vec![path.to_path_buf()],
+            bytes,
+            decision,
+        );
+    }
+
+    fn audit_change(
+        &self,
+        session_id: &acp::SessionId,
+        event: AuditEvent,
+        paths: Vec<PathBuf>,
+        bytes: usize,
+        decision: &str,
+    ) {
+        let mut entry = AuditEntry::new(session_id.0.to_string(), event).with_decision(decision);
+        entry.paths = paths;
+        entry.bytes = Some(bytes as u64);
+        let _ = self.client_tx.send(ClientOp::RecordAudit {
+            session_id: session_id.clone(),
+            entry,
+        });
+    }
+
+    /// Ask the user to explicitly allow a write to a protected path.
+    async fn confirm_protected_write(
+        &self,
+        session_id: &acp::SessionId,
+        path: &Path,
+        pattern: &str,
+        content: &str,
+    ) -> Result<(), String> {
+        let diff = acp::ToolCallContent::from(acp::Diff {
+            path: path.to_path_buf(),
+            old_text: None,
+            new_text: content.to_string(),
+            meta: None,
+        });
+        self.confirm_protected(
+            session_id,
+            path,
+            pattern,
+            acp::ToolKind::Edit,
+            "Write file",
+            vec![diff],
+        )
+        .await
+    }
+
+    /// Ask the user to explicitly allow a change of `kind` (an edit, delete,
+    /// or move) to a protected path.
+    async fn confirm_protected(
+        &self,
+        session_id: &acp::SessionId,
+        path: &Path,
+        pattern: &str,
+        kind: acp::ToolKind,
+        title: &str,
+        content: Vec<acp::ToolCallContent>,
+    ) -> Result<(), String> {
+        let action = match kind {
+            acp::ToolKind::Delete => "delete of",
+            acp::ToolKind::Move => "move of",
+            _ => "write to",
+        };
+        let mut request = acp::RequestPermissionRequest {
+            session_id: session_id.clone(),
+            tool_call: acp::ToolCallUpdate {
+                id: acp::ToolCallId(format!("protected_write-{}", Uuid::new_v4()).into()),
+                fields: acp::ToolCallUpdateFields {
+                    kind: Some(kind),
+                    status: Some(acp::ToolCallStatus::Pending),
+                    title: Some(title.into()),
+                    content: Some(content),
+                    locations: Some(vec![acp::ToolCallLocation {
+                        path: path.to_path_buf(),
+                        line: None,
+                        meta: None,
+                    }]),
+                    ..Default::default()
+                },
+                meta: None,
+            },
+            options: Vec::new(),
+            meta: None,
+        };
+        let display = path.display().to_string();
+        protected::escalate(&mut request, &[(display.clone(), pattern.to_string())]);
+
+        let (tx, rx) = oneshot::channel();
+        self.client_tx
+            .send(ClientOp::RequestPermission {
+                session_id: session_id.clone(),
+                request,
+                response_tx: tx,
+            })
+            .map_err(|_| "client request_permission channel closed".to_string())?;
+        match rx.await {
+            Ok(Ok(resp)) if protected::is_allowed(&resp) => Ok(()),
+            Ok(Ok(_)) => Err(format!(
+                "{action} protected path {display} (matches `{pattern}`) was rejected by the user"
+            )),
+            Ok(Err(err)) => Err(format!(
+                "{action} protected path {display} refused: approval unavailable ({})",
+                err.message
+            )),
+            Err(_) => Err(format!(
+                "{action} protected path {display} refused: approval unavailable"
+            )),
+        }
+    }
+
+    /// Stat a path on local disk, without reading it through the client.
+    async fn handle_file_info(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+    ) -> Result<String, String> {
+        let resolved_path = self.resolve_path(session_id, path);
+        self.check_scope(session_id, &resolved_path, acp::ToolKind::Read)
+            .await?;
+        let info = file_info::file_info(&resolved_path)
+            .await
+            .map_err(|err| format!("failed to inspect {}: {err}", resolved_path.display()))?;
+        serde_json::to_string(&info).map_err(|err| err.to_string())
+    }
+
+    /// Run a read-only command in `cwd`, or return its earlier output while
+    /// the workspace is unchanged.
+    async fn handle_run_command(
+        &self,
+        session_id: &acp::SessionId,
+        cwd: &str,
+        args: Option<serde_json::Value>,
+    ) -> Result<String, String> {
+        let RunCommandArgs { command } =
+            serde_json::from_value(args.ok_or_else(|| "missing run_command args".to_string())?)
+                .map_err(|err| format!("invalid run_command args: {err}"))?;
+        if !command_cache::is_cacheable(&command) {
+            return Err(format!(
+                "`{}` is not a read-only command this tool runs; use the shell tool",
+                command.join(" ")
+            ));
+        }
+        let cwd = self.resolve_path(session_id, cwd);
+        self.check_scope(session_id, &cwd, acp::ToolKind::Read)
+            .await?;
+
+        let key = session_id.0.to_string();
+        let (generation, cached) = {
+            let mut caches = self
+                .command_caches
+                .lock()
+                .map_err(|_| "command cache poisoned".to_string())?;
+            let cache = caches.entry(key.clone()).or_default();
+            (cache.generation(), cache.lookup(&command, &cwd))
+        };
+        let output = match cached {
+            Some(output) => output,
+            None => {
+                let output = command_cache::run(&command, &cwd)
                     .await
-                {
-                    Ok(text) => BridgeResponse {
-                        id,
-                        success: true,
-                        content: Some(text),
-                        error: None,
-                    },
-                    Err(err) => BridgeResponse {
-                        id,
-                        success: false,
-                        content: None,
-                        error: Some(err),
-                    },
+                    .map_err(|err| format!("failed to run {}: {err}", command[0]))?;
+                if let Ok(mut caches) = self.command_caches.lock() {
+                    caches
+                        .entry(key)
+                        .or_default()
+                        .store(command, cwd, generation, output.clone());
                 }
+                output
             }
-            BridgeOp::Write => {
-                let Some(content) = content else {
-                    return BridgeResponse {
-                        id,
-                        success: false,
-                        content: None,
-                        error: Some("missing content for write".to_string()),
-                    };
-                };
+        };
+        serde_json::to_string(&output).map_err(|err| err.to_string())
+    }
+
+    fn invalidate_commands(&self, session_id: &str) {
+        if let Ok(mut caches) = self.command_caches.lock() {
+            caches
+                .entry(session_id.to_string())
+                .or_default()
+                .invalidate();
+        }
+    }
+
+    /// Read a small file from local disk as base64 (ACP only reads text).
+    async fn handle_read_binary(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+    ) -> Result<String, String> {
+        let resolved_path = self.resolve_path(session_id, path);
+        let display = resolved_path.display().to_string();
+        self.check_scope(session_id, &resolved_path, acp::ToolKind::Read)
+            .await?;
+        let metadata = tokio::fs::metadata(&resolved_path)
+            .await
+            .map_err(|err| format!("failed to read {display}: {err}"))?;
+        if !metadata.is_file() {
+            return Err(format!("{display} is not a file"));
+        }
+        binary_read::check_size(&resolved_path, metadata.len())?;
+        let bytes = tokio::fs::read(&resolved_path)
+            .await
+            .map_err(|err| format!("failed to read {display}: {err}"))?;
+        // The file may have grown since it was inspected.
+        binary_read::check_size(&resolved_path, bytes.len() as u64)?;
+        serde_json::to_string(&BinaryFile::encode(&resolved_path, &bytes))
+            .map_err(|err| err.to_string())
+    }
+
+    /// Delete a file from local disk (ACP has no delete).
+    async fn handle_delete(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+    ) -> Result<String, String> {
+        let resolved_path = self.resolve_path(session_id, path);
+        let display = resolved_path.display().to_string();
+        self.ensure_writable(session_id).await?;
+        self.check_scope(session_id, &resolved_path, acp::ToolKind::Delete)
+            .await?;
+        let metadata = tokio::fs::symlink_metadata(&resolved_path)
+            .await
+            .map_err(|err| format!("failed to delete {display}: {err}"))?;
+        if metadata.is_dir() {
+            return Err(format!("{display} is a directory"));
+        }
+        if self.is_dry_run(session_id).await? {
+            return Ok(format!("{DRY_RUN_PREFIX} would delete {display}"));
+        }
+        let bytes = metadata.len() as usize;
+        let mut decision = "auto";
+        if let Some(pattern) = self
+            .protected
+            .matching(&self.root_of(session_id, &resolved_path), &resolved_path)
+        {
+            let pattern = pattern.to_string();
+            if let Err(err) = self
+                .confirm_protected(
+                    session_id,
+                    &resolved_path,
+                    &pattern,
+                    acp::ToolKind::Delete,
+                    "Delete file",
+                    Vec::new(),
+                )
+                .await
+            {
+                self.audit_change(
+                    session_id,
+                    AuditEvent::Delete,
+                    vec![resolved_path.clone()],
+                    bytes,
+                    "rejected",
+                );
+                return Err(err);
+            }
+            decision = "approved";
+        }
+        // Files that are not text cannot be restored by `/undo`.
+        let before = tokio::fs::read_to_string(&resolved_path).await.ok();
+        tokio::fs::remove_file(&resolved_path)
+            .await
+            .map_err(|err| format!("failed to delete {display}: {err}"))?;
+        self.audit_change(
+            session_id,
+            AuditEvent::Delete,
+            vec![resolved_path.clone()],
+            bytes,
+            decision,
+        );
+        if let Some(before) = before {
+            let _ = self.client_tx.send(ClientOp::RecordUndo {
+                session_id: session_id.clone(),
+                files: vec![FileSnapshot {
+                    path: resolved_path.clone(),
+                    before: Some(before),
+                    moved_to: None,
+                }],
+            });
+        }
+        self.forget_snapshot(session_id, &resolved_path);
+        Ok(format!("deleted {display}"))
+    }
+
+    /// Create a directory and its missing parents on local disk (ACP has no
+    /// mkdir, and client writes do not create parents).
+    async fn handle_create_directory(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+    ) -> Result<String, String> {
+        let resolved_path = self.resolve_path(session_id, path);
+        let display = resolved_path.display().to_string();
+        if path.trim().is_empty() {
+            return Err("missing directory path".to_string());
+        }
+        self.ensure_writable(session_id).await?;
+        self.check_scope(session_id, &resolved_path, acp::ToolKind::Edit)
+            .await?;
+        match tokio::fs::metadata(&resolved_path).await {
+            Ok(metadata) if metadata.is_dir() => {
+                return Ok(format!("{display} already exists"));
+            }
+            Ok(_) => return Err(format!("{display} exists and is not a directory")),
+            Err(_) => {}
+        }
+        if self.is_dry_run(session_id).await? {
+            return Ok(format!("{DRY_RUN_PREFIX} would create directory {display}"));
+        }
+        let mut decision = "auto";
+        if let Some(pattern) = self
+            .protected
+            .matching(&self.root_of(session_id, &resolved_path), &resolved_path)
+        {
+            let pattern = pattern.to_string();
+            if let Err(err) = self
+                .confirm_protected(
+                    session_id,
+                    &resolved_path,
+                    &pattern,
+                    acp::ToolKind::Edit,
+                    "Create directory",
+                    Vec::new(),
+                )
+                .await
+            {
+                self.audit_change(
+                    session_id,
+                    AuditEvent::CreateDirectory,
+                    vec![resolved_path.clone()],
+                    0,
+                    "rejected",
+                );
+                return Err(err);
+            }
+            decision = "approved";
+        }
+        tokio::fs::create_dir_all(&resolved_path)
+            .await
+            .map_err(|err| format!("failed to create {display}: {err}"))?;
+        self.audit_change(
+            session_id,
+            AuditEvent::CreateDirectory,
+            vec![resolved_path.clone()],
+            0,
+            decision,
+        );
+        Ok(format!("created directory {display}"))
+    }
 
-                match self
-                    .write_with_fallback(&session_id, &resolved_path, content)
+    /// Move or rename a file on local disk (ACP has no move).
+    async fn handle_move(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+        args: Option<serde_json::Value>,
+    ) -> Result<String, String> {
+        let args: MoveArgs =
+            serde_json::from_value(args.ok_or_else(|| "missing move args".to_string())?)
+                .map_err(|err| format!("invalid move args: {err}"))?;
+        let source = self.resolve_path(session_id, path);
+        let destination = self.resolve_path(session_id, &args.destination);
+        let (from, to) = (
+            source.display().to_string(),
+            destination.display().to_string(),
+        );
+        if args.destination.trim().is_empty() || destination == source {
+            return Err(format!("cannot move {from} onto itself"));
+        }
+        self.ensure_writable(session_id).await?;
+        self.check_scope(session_id, &source, acp::ToolKind::Move)
+            .await?;
+        self.check_scope(session_id, &destination, acp::ToolKind::Move)
+            .await?;
+        let metadata = tokio::fs::symlink_metadata(&source)
+            .await
+            .map_err(|err| format!("failed to move {from}: {err}"))?;
+        if metadata.is_dir() {
+            return Err(format!("{from} is a directory"));
+        }
+        let replaces = match tokio::fs::symlink_metadata(&destination).await {
+            Ok(existing) if existing.is_dir() => {
+                return Err(format!("{to} is a directory"));
+            }
+            Ok(_) if !args.overwrite => {
+                return Err(format!("{to} already exists; pass overwrite to replace it"));
+            }
+            Ok(_) => true,
+            Err(_) => false,
+        };
+        if self.is_dry_run(session_id).await? {
+            return Ok(format!("{DRY_RUN_PREFIX} would move {from} to {to}"));
+        }
+        let paths = vec![source.clone(), destination.clone()];
+        let bytes = metadata.len() as usize;
+        let mut decision = "auto";
+        for path in [&source, &destination] {
+            if let Some(pattern) = self
+                .protected
+                .matching(&self.root_of(session_id, path), path)
+            {
+                let pattern = pattern.to_string();
+                if let Err(err) = self
+                    .confirm_protected(
+                        session_id,
+                        path,
+                        &pattern,
+                        acp::ToolKind::Move,
+                        &format!("Move {from} to {to}"),
+                        Vec::new(),
+                    )
                     .await
                 {
-                    Ok(()) => BridgeResponse {
-                        id,
-                        success: true,
-                        content: None,
-                        error: None,
-                    },
-                    Err(err) => BridgeResponse {
-                        id,
-                        success: false,
-                        content: None,
-                        error: Some(err),
-                    },
+                    self.audit_change(session_id, AuditEvent::Move, paths, bytes, "rejected");
+                    return Err(err);
                 }
+                decision = "approved";
             }
         }
+        // `/undo` moves the file back and restores a replaced destination;
+        // files that are not text cannot be restored.
+        let before = tokio::fs::read_to_string(&source).await.ok();
+        let replaced = if replaces {
+            tokio::fs::read_to_string(&destination).await.ok()
+        } else {
+            None
+        };
+        if let Some(parent) = destination.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|err| format!("failed to create {}: {err}", parent.display()))?;
+        }
+        match tokio::fs::rename(&source, &destination).await {
+            Ok(()) => {}
+            // Renames cannot cross filesystems; copy and remove instead.
+            Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+                tokio::fs::copy(&source, &destination)
+                    .await
+                    .map_err(|err| format!("failed to move {from} to {to}: {err}"))?;
+                tokio::fs::remove_file(&source).await.map_err(|err| {
+                    format!("copied {from} to {to} but failed to remove it: {err}")
+                })?;
+            }
+            Err(err) => return Err(format!("failed to move {from} to {to}: {err}")),
+        }
+        self.audit_change(session_id, AuditEvent::Move, paths, bytes, decision);
+        if let Some(before) = before {
+            // Restored newest first: the move is undone before the replaced
+            // destination is written back.
+            let mut files: Vec<FileSnapshot> = replaced
+                .map(|replaced| FileSnapshot {
+                    path: destination.clone(),
+                    before: Some(replaced),
+                    moved_to: None,
+                })
+                .into_iter()
+                .collect();
+            files.push(FileSnapshot {
+                path: source.clone(),
+                before: Some(before),
+                moved_to: Some(destination.clone()),
+            });
+            let _ = self.client_tx.send(ClientOp::RecordUndo {
+                session_id: session_id.clone(),
+                files,
+            });
+        }
+        self.move_snapshot(session_id, &source, &destination);
+        Ok(format!("moved {from} to {to}"))
     }
 
-    fn resolve_path(&self, path: &str) -> Result<PathBuf, String> {
-        let candidate = PathBuf::from(path);
-        if candidate.is_absolute() {
-            return Ok(candidate);
-        }
+    /// List a directory from local disk (ACP has no directory listing); an
+    /// empty `path` lists the workspace root.
+    async fn handle_list_directory(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+        args: Option<serde_json::Value>,
+    ) -> Result<String, String> {
+        let args: ListArgs = match args {
+            Some(args) => serde_json::from_value(args)
+                .map_err(|err| format!("invalid list_directory args: {err}"))?,
+            None => ListArgs::default(),
+        };
+        let resolved_path = self.resolve_path(session_id, path);
+        self.check_scope(session_id, &resolved_path, acp::ToolKind::Read)
+            .await?;
+        let listing = listing::list_directory(
+            &self.root_of(session_id, &resolved_path),
+            &resolved_path,
+            &args,
+        )
+        .await
+        .map_err(|err| format!("failed to list {}: {err}", resolved_path.display()))?;
+        serde_json::to_string(&listing).map_err(|err| err.to_string())
+    }
 
-        let mut resolved = self.workspace_root.clone();
-        for component in Path::new(&candidate).components() {
-            use std::path::Component;
-            match component {
-                Component::CurDir => {}
-                Component::ParentDir => {
-                    if !resolved.pop() {
-                        return Err("path escapes workspace root".to_string());
-                    }
+    /// Search files on local disk; an empty `path` searches the workspace root.
+    async fn handle_search_text(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+        args: Option<serde_json::Value>,
+    ) -> Result<String, String> {
+        let args: SearchArgs =
+            serde_json::from_value(args.ok_or_else(|| "missing search_text args".to_string())?)
+                .map_err(|err| format!("invalid search_text args: {err}"))?;
+        let resolved_path = self.resolve_path(session_id, path);
+        self.check_scope(session_id, &resolved_path, acp::ToolKind::Read)
+            .await?;
+        let results = search::search(
+            &self.root_of(session_id, &resolved_path),
+            &resolved_path,
+            &args,
+        )
+        .await
+        .map_err(|err| format!("failed to search {}: {err}", resolved_path.display()))?;
+        serde_json::to_string(&results).map_err(|err| err.to_string())
+    }
+
+    /// Match files on local disk; an empty `path` matches from the workspace root.
+    async fn handle_glob_files(
+        &self,
+        session_id: &acp::SessionId,
+        path: &str,
+        args: Option<serde_json::Value>,
+    ) -> Result<String, String> {
+        let args: GlobArgs =
+            serde_json::from_value(args.ok_or_else(|| "missing glob_files args".to_string())?)
+                .map_err(|err| format!("invalid glob_files args: {err}"))?;
+        let resolved_path = self.resolve_path(session_id, path);
+        self.check_scope(session_id, &resolved_path, acp::ToolKind::Read)
+            .await?;
+        let results = glob_files::glob_files(
+            &self.root_of(session_id, &resolved_path),
+            &resolved_path,
+            &args,
+        )
+        .await
+        .map_err(|err| {
+            format!(
+                "failed to match files in {}: {err}",
+                resolved_path.display()
+            )
+        })?;
+        serde_json::to_string(&results).map_err(|err| err.to_string())
+    }
+
+    /// Diagnostics live in agent session state; an empty `path` returns all files.
+    async fn handle_diagnostics(
+        &self,
+        session_id: acp::SessionId,
+        path: &str,
+    ) -> Result<Option<String>, String> {
+        let filter = if path.is_empty() {
+            None
+        } else {
+            Some(self.resolve_path(&session_id, path).display().to_string())
+        };
+        self.diagnostics_via_agent(session_id, filter)
+            .await
+            .map(Some)
+    }
+
+    /// Ask the user a question through an ACP permission request whose options
+    /// are the answer choices, followed by `Other` and `Skip`. Returns
+    /// `{"answer": "..."}`, or `{"answer": null}` when the user dismisses the
+    /// prompt. A client that collects free text sends it as
+    /// `RequestPermissionResponse.meta.answer`, which becomes the answer; `Other`
+    /// picked without text returns `{"answer": null, "other": true}`.
+    async fn handle_ask_user(
+        &self,
+        session_id: acp::SessionId,
+        args: Option<serde_json::Value>,
+    ) -> Result<String, String> {
+        let AskUserArgs { question, options } =
+            serde_json::from_value(args.ok_or_else(|| "missing ask_user args".to_string())?)
+                .map_err(|err| format!("invalid ask_user args: {err}"))?;
+        let choices = if options.is_empty() {
+            vec!["Yes".to_string(), "No".to_string()]
+        } else {
+            options
+        };
+
+        let mut permission_options: Vec<acp::PermissionOption> = choices
+            .iter()
+            .enumerate()
+            .map(|(idx, choice)| acp::PermissionOption {
+                id: acp::PermissionOptionId(format!("choice-{idx}").into()),
+                name: choice.clone(),
+                kind: acp::PermissionOptionKind::AllowOnce,
+                meta: None,
+            })
+            .collect();
+        permission_options.push(acp::PermissionOption {
+            id: acp::PermissionOptionId(ASK_USER_OTHER.into()),
+            name: "Other".into(),
+            kind: acp::PermissionOptionKind::AllowOnce,
+            meta: None,
+        });
+        permission_options.push(acp::PermissionOption {
+            id: acp::PermissionOptionId("dismiss".into()),
+            name: "Skip".into(),
+            kind: acp::PermissionOptionKind::RejectOnce,
+            meta: None,
+        });
+
+        let request = acp::RequestPermissionRequest {
+            session_id: session_id.clone(),
+            tool_call: acp::ToolCallUpdate {
+                id: acp::ToolCallId(format!("ask_user-{}", Uuid::new_v4()).into()),
+                fields: acp::ToolCallUpdateFields {
+                    kind: Some(acp::ToolKind::Other),
+                    status: Some(acp::ToolCallStatus::Pending),
+                    title: Some(question),
+                    ..Default::default()
+                },
+                meta: None,
+            },
+            options: permission_options,
+            meta: None,
+        };
+
+        let (tx, rx) = oneshot::channel();
+        self.client_tx
+            .send(ClientOp::RequestPermission {
+                session_id,
+                request,
+                response_tx: tx,
+            })
+            .map_err(|_| "client request_permission channel closed".to_string())?;
+
+        let resp = match rx.await {
+            Ok(Ok(resp)) => resp,
+            Ok(Err(err)) => return Err(err.message),
+            Err(_) => return Err("client request_permission response dropped".to_string()),
+        };
+        let typed = resp
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.get("answer"))
+            .and_then(|answer| answer.as_str())
+            .map(str::trim)
+            .filter(|answer| !answer.is_empty());
+        if let Some(typed) = typed {
+            return Ok(serde_json::json!({ "answer": typed }).to_string());
+        }
+        let answer = match resp.outcome {
+            acp::RequestPermissionOutcome::Selected { option_id } => {
+                if &*option_id.0 == ASK_USER_OTHER {
+                    return Ok(serde_json::json!({ "answer": null, "other": true }).to_string());
                 }
-                Component::Normal(part) => resolved.push(part),
-                Component::RootDir => {}
-                Component::Prefix(_) => {}
+                option_id
+                    .0
+                    .strip_prefix("choice-")
+                    .and_then(|idx| idx.parse::<usize>().ok())
+                    .and_then(|idx| choices.get(idx).cloned())
             }
+            acp::RequestPermissionOutcome::Cancelled => None,
+        };
+        Ok(serde_json::json!({ "answer": answer }).to_string())
+    }
+
+    /// Commands executed in the session, as text for the model.
+    async fn handle_command_history(
+        &self,
+        session_id: acp::SessionId,
+        args: Option<serde_json::Value>,
+    ) -> Result<String, String> {
+        let args: CommandHistoryArgs = match args {
+            Some(args) => serde_json::from_value(args)
+                .map_err(|err| format!("invalid command_history args: {err}"))?,
+            None => CommandHistoryArgs::default(),
+        };
+        let (tx, rx) = oneshot::channel();
+        self.client_tx
+            .send(ClientOp::GetCommandHistory {
+                session_id,
+                response_tx: tx,
+            })
+            .map_err(|_| "agent command history channel closed".to_string())?;
+
+        match rx.await {
+            Ok(Ok(records)) => Ok(command_history::format_history(&records, &args)),
+            Ok(Err(err)) => Err(err.message),
+            Err(_) => Err("agent command history response dropped".to_string()),
+        }
+    }
+
+    /// Refuse writes while the session is read-only. The client write path
+    /// checks this too, but the local fallback would otherwise bypass it.
+    async fn ensure_writable(&self, session_id: &acp::SessionId) -> Result<(), String> {
+        let (tx, rx) = oneshot::channel();
+        self.client_tx
+            .send(ClientOp::GetReadOnly {
+                session_id: session_id.clone(),
+                response_tx: tx,
+            })
+            .map_err(|_| "agent session mode channel closed".to_string())?;
+
+        match rx.await {
+            Ok(Ok(false)) => Ok(()),
+            Ok(Ok(true)) => Err("writes are disabled while session mode is read-only".to_string()),
+            Ok(Err(err)) => Err(err.message),
+            Err(_) => Err("agent session mode response dropped".to_string()),
+        }
+    }
+
+    async fn is_dry_run(&self, session_id: &acp::SessionId) -> Result<bool, String> {
+        let (tx, rx) = oneshot::channel();
+        self.client_tx
+            .send(ClientOp::GetDryRun {
+                session_id: session_id.clone(),
+                response_tx: tx,
+            })
+            .map_err(|_| "agent session mode channel closed".to_string())?;
+
+        match rx.await {
+            Ok(Ok(dry_run)) => Ok(dry_run),
+            Ok(Err(err)) => Err(err.message),
+            Err(_) => Err("agent session mode response dropped".to_string()),
+        }
+    }
+
+    async fn diagnostics_via_agent(
+        &self,
+        session_id: acp::SessionId,
+        path: Option<String>,
+    ) -> Result<String, String> {
+        let (tx, rx) = oneshot::channel();
+        self.client_tx
+            .send(ClientOp::GetDiagnostics {
+                session_id,
+                path,
+                response_tx: tx,
+            })
+            .map_err(|_| "agent diagnostics channel closed".to_string())?;
+
+        match rx.await {
+            Ok(Ok(files)) => Ok(files),
+            Ok(Err(err)) => Err(err.message),
+            Err(_) => Err("agent diagnostics response dropped".to_string()),
+        }
+    }
+
+    /// The workspace root followed by the session's extra roots.
+    fn roots(&self, session_id: &acp::SessionId) -> Vec<PathBuf> {
+        let mut roots = vec![self.workspace_root.clone()];
+        if let Ok(session_roots) = self.session_roots.lock()
+            && let Some(extra) = session_roots.get(session_id.0.as_ref())
+        {
+            roots.extend(extra.iter().cloned());
+        }
+        roots
+    }
+
+    /// Resolve `path` against the session's workspace roots and normalize it:
+    /// `<root name>/<path>` lies in the named root, other relative paths in
+    /// the workspace root.
+    fn resolve_path(&self, session_id: &acp::SessionId, path: &str) -> PathBuf {
+        let roots = self.roots(session_id);
+        match workspace_scope::named_root(&roots, Path::new(path)) {
+            Some((root, rest)) => workspace_scope::normalize(&root.join(rest)),
+            None => workspace_scope::normalize(&self.workspace_root.join(path)),
+        }
+    }
+
+    /// The deepest workspace root of the session containing `path`, or the
+    /// workspace root for paths outside all of them.
+    fn root_of(&self, session_id: &acp::SessionId, path: &Path) -> PathBuf {
+        self.roots(session_id)
+            .into_iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .unwrap_or_else(|| self.workspace_root.clone())
+    }
+
+    /// Refuse, or ask about, model access to a path outside the allowed roots.
+    async fn check_scope(
+        &self,
+        session_id: &acp::SessionId,
+        path: &Path,
+        kind: acp::ToolKind,
+    ) -> Result<(), String> {
+        if self.scope.contains(path, &self.roots(session_id)).await {
+            return Ok(());
+        }
+        let display = path.display();
+        if self.scope.policy() == OutsidePolicy::Reject {
+            return Err(format!(
+                "{display} is outside the workspace; only paths under the workspace root are allowed"
+            ));
         }
 
-        Ok(resolved)
+        let request = workspace_scope::outside_request(session_id.clone(), path, kind);
+        let (tx, rx) = oneshot::channel();
+        self.client_tx
+            .send(ClientOp::RequestPermission {
+                session_id: session_id.clone(),
+                request,
+                response_tx: tx,
+            })
+            .map_err(|_| "client request_permission channel closed".to_string())?;
+        match rx.await {
+            Ok(Ok(resp)) if workspace_scope::is_allowed(&resp) => Ok(()),
+            Ok(Ok(_)) => Err(format!(
+                "access to {display} outside the workspace was rejected by the user"
+            )),
+            Ok(Err(err)) => Err(format!(
+                "access to {display} outside the workspace refused: approval unavailable ({})",
+                err.message
+            )),
+            Err(_) => Err(format!(
+                "access to {display} outside the workspace refused: approval unavailable"
+            )),
+        }
     }
 
     async fn read_with_fallback(
@@ -282,24 +2015,26 @@ impl FsBridgeInner {
         line: Option<u32>,
         limit: Option<u32>,
     ) -> Result<String, String> {
-        let content = tokio::fs::read_to_string(path)
-            .await
-            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
-
-        match line {
-            Some(start_line) => {
-                let start = start_line.saturating_sub(1) as usize;
-                let count = limit.unwrap_or(u32::MAX) as usize;
-                let lines: Vec<&str> = content.lines().collect();
-                if start >= lines.len() {
-                    Ok(String::new())
-                } else {
-                    let end = (start + count).min(lines.len());
-                    Ok(lines[start..end].join("\n"))
-                }
-            }
-            None => Ok(content),
+        let read_error = |err: io::Error| match err.kind() {
+            io::ErrorKind::InvalidData => format!(
+                "{} is not UTF-8 text; use read_binary_file for binary files",
+                path.display()
+            ),
+            _ => format!("failed to read {}: {err}", path.display()),
+        };
+        if line.is_none() && limit.is_none() {
+            return tokio::fs::read_to_string(path).await.map_err(read_error);
         }
+        // Stream ranged reads so a few lines of a huge file stay cheap.
+        let file = tokio::fs::File::open(path).await.map_err(read_error)?;
+        line_range::read_line_range(
+            &mut BufReader::new(file),
+            line.unwrap_or(1),
+            limit,
+            line_range::MAX_RANGE_BYTES,
+        )
+        .await
+        .map_err(read_error)
     }
 
     async fn write_with_fallback(
@@ -308,6 +2043,18 @@ impl FsBridgeInner {
         path: &Path,
         content: String,
     ) -> Result<(), String> {
+        // Clients differ on whether they create missing parents; make sure
+        // both paths behave like `create_directory`.
+        if let Some(parent) = path.parent()
+            && tokio::fs::metadata(parent).await.is_err()
+        {
+            tokio::fs::create_dir_all(parent).await.map_err(|err| {
+                format!(
+                    "failed to create parent directories {}: {err}",
+                    parent.display()
+                )
+            })?;
+        }
         match self
             .write_via_client(
                 session_id.clone(),
@@ -318,6 +2065,8 @@ impl FsBridgeInner {
         {
             Ok(()) => Ok(()),
             Err(err) => {
+                // The mode may have changed while the client write was pending.
+                self.ensure_writable(session_id).await?;
                 debug!(error = %err, path = %path.display(), "client write failed, falling back to local write");
                 self.write_locally(path, content).await
             }
@@ -366,3 +2115,14 @@ impl FsBridgeInner {
             .map_err(|err| format!("failed to write {}: {err}", path.display()))
     }
 }
+
+/// The dry-run answer to writing `content` over `before` at `path`.
+fn dry_run_write(path: &Path, before: &str, content: &str) -> String {
+    let display = path.display();
+    let patch = create_patch(before, content);
+    if patch.hunks().is_empty() {
+        return format!("{DRY_RUN_PREFIX} would write {display} (no changes)");
+    }
+    let diff = PatchFormatter::new().fmt_patch(&patch).to_string();
+    format!("{DRY_RUN_PREFIX} would write {display}\n--- {display}\n+++ {display}\n{diff}")
+}
@@ -0,0 +1,242 @@
+//! The `--acp-fs-mcp` helper's side of the bridge connection.
+//!
+//! One connection is kept open for the life of the helper and shared by all
+//! tool calls. Requests carry an id and the bridge answers each as soon as it
+//! is done, so a slow request (an `ask_user` question, a write waiting on a
+//! conflict prompt) does not hold up the others. A broken connection is
+//! re-established on the next request. Each connection asks the bridge for
+//! length-prefixed frames and falls back to lines if the bridge does not
+//! answer, see [`framing`](super::framing).
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use anyhow::{Context, Result, anyhow};
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, WriteHalf},
+    sync::oneshot,
+    task::JoinHandle,
+    time::{Duration, timeout},
+};
+use tracing::warn;
+
+use super::{
+    bridge::{BridgeAddress, BridgeStream},
+    framing::{self, Framing},
+    tools::bridge_timeouts::BridgeTimeouts,
+};
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A request that got no response in time. The bridge may still carry it
+/// out, so only requests without side effects should be sent again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut(pub Duration);
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "bridge request timed out after {}s", self.0.as_secs())
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Responses awaited on a connection, by request id.
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>;
+
+/// A multiplexed, reconnecting connection to the FS bridge.
+#[derive(Clone)]
+pub struct BridgeClient {
+    address: BridgeAddress,
+    /// Session secret sent with every request.
+    token: String,
+    timeouts: BridgeTimeouts,
+    connection: Arc<tokio::sync::Mutex<Option<Connection>>>,
+}
+
+/// How long a new connection waits for the bridge to answer its hello.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(2);
+
+struct Connection {
+    writer: WriteHalf<Box<dyn BridgeStream>>,
+    framing: Framing,
+    pending: Pending,
+    reader: JoinHandle<()>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+impl Connection {
+    async fn open(address: &BridgeAddress) -> Result<Self> {
+        let stream = address
+            .connect()
+            .await
+            .with_context(|| format!("failed to connect to bridge at {address}"))?;
+        let (read_half, mut writer) = tokio::io::split(stream);
+        let mut read_half = BufReader::new(read_half);
+
+        writer
+            .write_all(framing::hello(Framing::LengthPrefixed).as_bytes())
+            .await?;
+        writer.flush().await?;
+        // Bridges from before framing was negotiable ignore the hello.
+        let mut answer = String::new();
+        let framing = match timeout(HELLO_TIMEOUT, read_half.read_line(&mut answer)).await {
+            Ok(Ok(_)) => framing::parse_hello(&answer).unwrap_or(Framing::Lines),
+            Ok(Err(err)) => return Err(err).context("failed to negotiate bridge framing"),
+            Err(_) => Framing::Lines,
+        };
+
+        let pending = Pending::default();
+        let reader_pending = pending.clone();
+        let reader = tokio::spawn(async move {
+            loop {
+                let message = match framing::read_message(&mut read_half, framing).await {
+                    Ok(Some(message)) => message,
+                    Ok(None) => break,
+                    Err(err) => {
+                        warn!(error = %err, "fs bridge connection failed");
+                        break;
+                    }
+                };
+                let response: Value = match serde_json::from_slice(&message) {
+                    Ok(response) => response,
+                    Err(err) => {
+                        warn!(error = %err, "fs bridge sent a malformed response");
+                        continue;
+                    }
+                };
+                let waiter = response
+                    .get("id")
+                    .and_then(Value::as_u64)
+                    .and_then(|id| reader_pending.lock().ok()?.remove(&id));
+                if let Some(waiter) = waiter {
+                    let _ = waiter.send(response);
+                }
+            }
+            // Waiters still registered learn that the connection is gone.
+            if let Ok(mut pending) = reader_pending.lock() {
+                pending.clear();
+            }
+        });
+        Ok(Self {
+            writer,
+            framing,
+            pending,
+            reader,
+        })
+    }
+
+    fn is_open(&self) -> bool {
+        !self.reader.is_finished()
+    }
+
+    async fn send_message(&mut self, message: &str) -> std::io::Result<()> {
+        framing::write_message(&mut self.writer, self.framing, message.as_bytes()).await
+    }
+}
+
+impl BridgeClient {
+    pub fn new(address: BridgeAddress, token: String, timeouts: BridgeTimeouts) -> Self {
+        Self {
+            address,
+            token,
+            timeouts,
+            connection: Arc::default(),
+        }
+    }
+
+    pub fn timeouts(&self) -> &BridgeTimeouts {
+        &self.timeouts
+    }
+
+    /// Send `payload` as a request and wait up to `wait` for its response,
+    /// returning the response `content` or its `error`.
+    pub async fn send(&self, mut payload: Value, wait: Duration) -> Result<String> {
+        let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        if let Some(obj) = payload.as_object_mut() {
+            obj.insert("id".to_string(), json!(request_id));
+            obj.insert("token".to_string(), json!(self.token));
+        }
+        let line = serde_json::to_string(&payload)?;
+
+        let (tx, rx) = oneshot::channel();
+        let pending = self.write_request(request_id, tx, &line).await?;
+        let response = match timeout(wait, rx).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(_)) => return Err(anyhow!("bridge closed connection")),
+            Err(_) => {
+                if let Ok(mut pending) = pending.lock() {
+                    pending.remove(&request_id);
+                }
+                return Err(TimedOut(wait).into());
+            }
+        };
+
+        let success = response
+            .get("success")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        if success {
+            Ok(response
+                .get("content")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string())
+        } else {
+            let message = response
+                .get("error")
+                .and_then(Value::as_str)
+                .unwrap_or("bridge error");
+            Err(anyhow!(message.to_string()))
+        }
+    }
+
+    /// Register the waiter and write the request, reconnecting once if the
+    /// current connection turns out to be broken.
+    async fn write_request(
+        &self,
+        request_id: u64,
+        tx: oneshot::Sender<Value>,
+        line: &str,
+    ) -> Result<Pending> {
+        let mut tx = Some(tx);
+        let mut connection = self.connection.lock().await;
+        for attempt in 0..2 {
+            let conn = match connection.take().filter(Connection::is_open) {
+                Some(conn) => connection.insert(conn),
+                None => connection.insert(Connection::open(&self.address).await?),
+            };
+            if let Some(tx) = tx.take()
+                && let Ok(mut pending) = conn.pending.lock()
+            {
+                pending.insert(request_id, tx);
+            }
+            match conn.send_message(line).await {
+                Ok(()) => return Ok(conn.pending.clone()),
+                Err(err) => {
+                    tx = conn
+                        .pending
+                        .lock()
+                        .ok()
+                        .and_then(|mut p| p.remove(&request_id));
+                    *connection = None;
+                    if attempt == 1 {
+                        return Err(anyhow!(err).context("failed to send bridge request"));
+                    }
+                }
+            }
+        }
+        Err(anyhow!("failed to send bridge request"))
+    }
+}
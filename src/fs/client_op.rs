@@ -0,0 +1,110 @@
+//! Requests for the client connection.
+//!
+//! The agent and the `acp_fs` bridge send [`ClientOp`]s over one channel to
+//! the connection loop, which forwards ACP requests to the client and answers
+//! the others from session state.
+
+use agent_client_protocol::{
+    CreateTerminalRequest, CreateTerminalResponse, Error, ReadTextFileRequest,
+    ReadTextFileResponse, RequestPermissionRequest, RequestPermissionResponse, SessionId,
+    TerminalId, WriteTextFileRequest, WriteTextFileResponse,
+};
+use tokio::sync::oneshot::Sender;
+
+use super::{
+    text,
+    tools::{audit::AuditEntry, command_history::CommandRecord, undo::FileSnapshot},
+};
+
+/// Operations that require client interaction.
+///
+/// These operations are sent to the client handler to request permissions,
+/// read files, or write files based on client capabilities.
+pub enum ClientOp {
+    RequestPermission {
+        session_id: SessionId,
+        request: RequestPermissionRequest,
+        response_tx: Sender<Result<RequestPermissionResponse, Error>>,
+    },
+    ReadTextFile {
+        session_id: SessionId,
+        request: ReadTextFileRequest,
+        response_tx: Sender<Result<ReadTextFileResponse, Error>>,
+    },
+    WriteTextFile {
+        session_id: SessionId,
+        request: WriteTextFileRequest,
+        response_tx: Sender<Result<WriteTextFileResponse, Error>>,
+    },
+    CreateTerminal {
+        session_id: SessionId,
+        request: CreateTerminalRequest,
+        response_tx: Sender<Result<CreateTerminalResponse, Error>>,
+    },
+    /// Wait for a client terminal to exit, then collect its output and release it.
+    WaitForTerminal {
+        session_id: SessionId,
+        terminal_id: TerminalId,
+        response_tx: Sender<Result<TerminalRun, Error>>,
+    },
+    /// Answered by the agent from session state rather than forwarded to the client.
+    GetDiagnostics {
+        session_id: SessionId,
+        path: Option<String>,
+        /// The matching diagnostics as JSON.
+        response_tx: Sender<Result<String, Error>>,
+    },
+    GetCommandHistory {
+        session_id: SessionId,
+        response_tx: Sender<Result<Vec<CommandRecord>, Error>>,
+    },
+    /// Whether the session is currently in the read-only mode.
+    GetReadOnly {
+        session_id: SessionId,
+        response_tx: Sender<Result<bool, Error>>,
+    },
+    /// Whether the session is currently in the dry-run mode.
+    GetDryRun {
+        session_id: SessionId,
+        response_tx: Sender<Result<bool, Error>>,
+    },
+    /// Append an entry for a bridge write to the audit log.
+    RecordAudit {
+        session_id: SessionId,
+        entry: AuditEntry,
+    },
+    /// Remember a bridge write for `/undo`.
+    RecordUndo {
+        session_id: SessionId,
+        files: Vec<FileSnapshot>,
+    },
+}
+
+/// Result of a command run in a client terminal.
+#[derive(Debug, Clone, Default)]
+pub struct TerminalRun {
+    pub output: String,
+    pub truncated: bool,
+    pub exit_code: Option<u32>,
+    pub signal: Option<String>,
+}
+
+impl TerminalRun {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// The last `max` bytes of output, prefixed with `…` when cut.
+    pub fn output_tail(&self, max: usize) -> String {
+        text::elide_head(&self.output, max)
+    }
+
+    /// One-line description of how the process ended.
+    pub fn describe_exit(&self) -> String {
+        match (&self.exit_code, &self.signal) {
+            (Some(code), _) => format!("exit code {code}"),
+            (None, Some(signal)) => format!("killed by signal {signal}"),
+            (None, None) => "unknown exit status".to_string(),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Message framing on bridge connections.
+//!
+//! Bridge messages were originally newline-delimited JSON, which ties every
+//! message to one line. Helpers that know better open a connection with a
+//! hello line asking for length-prefixed frames (a 4-byte big-endian length,
+//! then that many bytes of JSON); the bridge answers with the framing both
+//! sides use from then on. A helper that does not say hello, such as one from
+//! an older build, keeps newline-delimited JSON, and so does a helper whose
+//! hello goes unanswered.
+
+use std::io;
+
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest length-prefixed frame accepted; large writes are chunked well below it.
+pub const MAX_FRAME_BYTES: usize = 64 * 1024 * 1024;
+
+const HELLO_KEY: &str = "bridge_hello";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// One JSON message per line.
+    Lines,
+    /// Each JSON message preceded by its length as a big-endian `u32`.
+    LengthPrefixed,
+}
+
+impl Framing {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Lines => "lines",
+            Self::LengthPrefixed => "length_prefixed",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "lines" => Some(Self::Lines),
+            "length_prefixed" => Some(Self::LengthPrefixed),
+            _ => None,
+        }
+    }
+}
+
+/// The hello line (newline included) asking for `framing`; the bridge's
+/// answer uses the same shape.
+pub fn hello(framing: Framing) -> String {
+    format!(
+        "{}\n",
+        json!({ HELLO_KEY: { "framing": framing.name(), "version": 1 } })
+    )
+}
+
+/// The framing named by a hello line, or `None` when `line` is not a hello.
+/// Unknown framings fall back to [`Framing::Lines`].
+pub fn parse_hello(line: &str) -> Option<Framing> {
+    let value: Value = serde_json::from_str(line.trim()).ok()?;
+    let hello = value.get(HELLO_KEY)?;
+    Some(
+        hello
+            .get("framing")
+            .and_then(Value::as_str)
+            .and_then(Framing::parse)
+            .unwrap_or(Framing::Lines),
+    )
+}
+
+/// Write one message.
+pub async fn write_message<W>(writer: &mut W, framing: Framing, message: &[u8]) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    match framing {
+        Framing::Lines => {
+            writer.write_all(message).await?;
+            writer.write_all(b"\n").await?;
+        }
+        Framing::LengthPrefixed => {
+            let len = u32::try_from(message.len())
+                .ok()
+                .filter(|len| *len as usize <= MAX_FRAME_BYTES)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("bridge message of {} bytes is too large", message.len()),
+                    )
+                })?;
+            writer.write_all(&len.to_be_bytes()).await?;
+            writer.write_all(message).await?;
+        }
+    }
+    writer.flush().await
+}
+
+/// Read one message, `None` at the end of the stream. Blank lines are skipped.
+pub async fn read_message<R>(reader: &mut R, framing: Framing) -> io::Result<Option<Vec<u8>>>
+where
+    R: AsyncBufRead + Unpin + ?Sized,
+{
+    match framing {
+        Framing::Lines => loop {
+            let mut line = Vec::new();
+            if reader.read_until(b'\n', &mut line).await? == 0 {
+                return Ok(None);
+            }
+            while matches!(line.last(), Some(b'\n' | b'\r')) {
+                line.pop();
+            }
+            if !line.iter().all(u8::is_ascii_whitespace) {
+                return Ok(Some(line));
+            }
+        },
+        Framing::LengthPrefixed => {
+            let mut len = [0u8; 4];
+            match reader.read_exact(&mut len).await {
+                Ok(_) => {}
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(err) => return Err(err),
+            }
+            let len = u32::from_be_bytes(len) as usize;
+            if len > MAX_FRAME_BYTES {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("bridge frame of {len} bytes exceeds {MAX_FRAME_BYTES}"),
+                ));
+            }
+            let mut message = vec![0u8; len];
+            reader.read_exact(&mut message).await?;
+            Ok(Some(message))
+        }
+    }
+}
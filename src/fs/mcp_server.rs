@@ -1,12 +1,27 @@
 use std::{
     collections::HashMap,
-    sync::{
-        Arc,
-        atomic::{AtomicU64, Ordering},
-    },
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
 };
 
-use super::bridge;
+use super::{
+    bridge::{self, BridgeAddress},
+    bridge_client::{BridgeClient, TimedOut},
+    text,
+    tools::{
+        binary_read::{self, BinaryFile},
+        bridge_timeouts::{ASK_USER_TIMEOUT, BridgeTimeouts},
+        chunked_write::{self, UploadArgs},
+        command_cache::{self, CommandOutput},
+        edit_match::{self, MatchKind},
+        file_info::{self, FileInfo},
+        glob_files::{self, GlobArgs, GlobResults},
+        listing::{self, ListArgs, Listing},
+        search::{self, SearchArgs, SearchResults},
+        tail_read::TailRead,
+        unified_diff,
+    },
+};
 use anyhow::{Context, Result, anyhow};
 use diffy::{PatchFormatter, create_patch};
 use rmcp::{
@@ -21,17 +36,13 @@ use rmcp::{
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    net::TcpStream,
-    time::{Duration, timeout},
-};
-use tracing::info;
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
 
 const DEFAULT_READ_LINE_LIMIT: u32 = 1000;
 const MAX_READ_BYTES: usize = 50 * 1024;
-
-static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+/// Identical consecutive reads within this window reuse the previous result.
+const READ_DEDUP_WINDOW: Duration = Duration::from_secs(3);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct LineRange {
@@ -55,11 +66,15 @@ pub async fn run() -> Result<()> {
     // Capture required env to talk to our local bridge and session.
     let bridge_addr = std::env::var("ACP_FS_BRIDGE_ADDR")
         .context("ACP_FS_BRIDGE_ADDR environment variable is required")?;
+    let bridge_addr = BridgeAddress::parse(&bridge_addr).map_err(|err| anyhow!(err))?;
     let session_id = std::env::var("ACP_FS_SESSION_ID")
         .context("ACP_FS_SESSION_ID environment variable is required")?;
 
     // Build an rmcp server over stdio with our tools.
-    let server = FsTools::new(bridge_addr, session_id);
+    let token = std::env::var("ACP_FS_BRIDGE_TOKEN")
+        .context("ACP_FS_BRIDGE_TOKEN environment variable is required")?;
+    let client = BridgeClient::new(bridge_addr, token, BridgeTimeouts::from_env());
+    let server = FsTools::new(client, session_id);
     let transport = rmcp::transport::io::stdio();
     // Serve and wait until the client closes the connection.
     let running = service::serve_server(server, transport).await?;
@@ -76,66 +91,237 @@ struct StagedEdits {
 #[derive(Clone)]
 struct StagedFile {
     content: String,
+    /// Hash of `content`, compared with the file before staged content is
+    /// built on, so edits never clobber changes made since.
+    hash: u64,
 }
 
 impl StagedEdits {
     async fn stage(&self, path: String, content: String) {
         let mut map = self.entries.lock().await;
-        map.insert(path, StagedFile { content });
+        let hash = content_hash(&content);
+        map.insert(path, StagedFile { content, hash });
     }
     async fn get(&self, path: &str) -> Option<StagedFile> {
         let map = self.entries.lock().await;
         map.get(path).cloned()
     }
+    async fn discard(&self, path: &str) {
+        let mut map = self.entries.lock().await;
+        map.remove(path);
+    }
+}
+
+/// The last `read_text_file` bridge response. The model sometimes reads the
+/// same range again within seconds; such repeats are answered from here
+/// instead of another bridge and client round-trip. Writes through this
+/// server drop it.
+#[derive(Default, Clone)]
+struct LastRead {
+    entry: Arc<tokio::sync::Mutex<Option<CachedRead>>>,
+}
+
+struct CachedRead {
+    key: (String, Option<u32>, Option<u32>),
+    at: Instant,
+    response: String,
+}
+
+impl LastRead {
+    async fn get(&self, key: &(String, Option<u32>, Option<u32>)) -> Option<String> {
+        let entry = self.entry.lock().await;
+        entry
+            .as_ref()
+            .filter(|cached| cached.key == *key && cached.at.elapsed() < READ_DEDUP_WINDOW)
+            .map(|cached| cached.response.clone())
+    }
+    async fn store(&self, key: (String, Option<u32>, Option<u32>), response: String) {
+        *self.entry.lock().await = Some(CachedRead {
+            key,
+            at: Instant::now(),
+            response,
+        });
+    }
+    async fn clear(&self) {
+        *self.entry.lock().await = None;
+    }
 }
 
 #[derive(Clone)]
-struct FsTools {
-    bridge_addr: String,
+pub(crate) struct FsTools {
+    client: BridgeClient,
     session_id: String,
     staged_edits: StagedEdits,
+    last_read: LastRead,
     tool_router: ToolRouter<Self>,
 }
 
 impl FsTools {
-    fn new(bridge_addr: String, session_id: String) -> Self {
+    pub(crate) fn new(client: BridgeClient, session_id: String) -> Self {
         Self {
-            bridge_addr,
+            client,
             session_id,
             staged_edits: Default::default(),
+            last_read: Default::default(),
             tool_router: Self::tool_router(),
         }
     }
+
+    async fn move_via_bridge(
+        &self,
+        path: &str,
+        destination: &str,
+        overwrite: bool,
+    ) -> Result<CallToolResult, McpError> {
+        let result = perform_bridge_op(
+            &self.client,
+            &self.session_id,
+            bridge::BridgeOp::Move,
+            path,
+            json!({"destination": destination, "overwrite": overwrite}),
+        )
+        .await;
+        self.last_read.clear().await;
+        self.staged_edits.discard(path).await;
+        self.staged_edits.discard(destination).await;
+        let message = result.map_err(|e| {
+            McpError::internal_error("bridge move failed", Some(json!({"reason": e.to_string()})))
+        })?;
+        Ok(CallToolResult::success(vec![Content::text(message)]))
+    }
+
+    /// `read_text_file` with `from_end`: the last `limit` lines of `path`.
+    async fn read_tail(&self, path: &str, limit: u32) -> Result<CallToolResult, McpError> {
+        let response = send_bridge_payload(
+            &self.client,
+            bridge::BridgeOp::Read,
+            json!({
+                "session_id": self.session_id,
+                "op": bridge::BridgeOp::Read,
+                "path": path,
+                "limit": limit,
+                "from_end": true,
+            }),
+        )
+        .await
+        .map_err(|e| {
+            McpError::internal_error("bridge read failed", Some(json!({"reason": e.to_string()})))
+        })?;
+        let mut tail: TailRead = serde_json::from_str(&response).map_err(|e| {
+            McpError::internal_error(
+                "malformed tail read response",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+        let truncated_by_bytes = tail.fit_bytes(MAX_READ_BYTES);
+        let end_line = tail.end_line();
+        let lines_returned = (end_line + 1).saturating_sub(tail.start_line);
+
+        let mut text = tail.content;
+        if tail.start_line > 1 {
+            let mut hint = format!(
+                "<file-read-info>Read lines {}-{end_line} of {}",
+                tail.start_line, tail.total_lines
+            );
+            if truncated_by_bytes {
+                hint.push_str(&format!(" (hit {MAX_READ_BYTES} byte cap)"));
+            }
+            let previous = tail.start_line.saturating_sub(limit).max(1);
+            hint.push_str(&format!(
+                ". Earlier lines: line={previous} limit={}.</file-read-info>",
+                tail.start_line - previous
+            ));
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+            text.push_str(&hint);
+        }
+
+        let mut meta_obj = Meta::new();
+        meta_obj.insert(
+            "codex_fs_read".to_string(),
+            json!({
+                "path": path,
+                "from_end": true,
+                "start_line": tail.start_line,
+                "end_line": end_line,
+                "total_lines": tail.total_lines,
+                "lines_returned": lines_returned,
+                "line_limit": limit,
+                "truncated": tail.start_line > 1,
+                "truncated_by_bytes": truncated_by_bytes,
+            }),
+        );
+        let content = RawContent::Text(RawTextContent {
+            text,
+            meta: Some(meta_obj),
+        })
+        .no_annotation();
+        Ok(CallToolResult::success(vec![content]))
+    }
+}
+
+/// `path` with its file name replaced by `new_name`.
+fn renamed_path(path: &str, new_name: &str) -> Result<String, String> {
+    let new_name = new_name.trim();
+    if new_name.is_empty() || new_name == "." || new_name == ".." || new_name.contains(['/', '\\'])
+    {
+        return Err(format!("{new_name:?} is not a file name"));
+    }
+    let path = std::path::Path::new(path);
+    if path.file_name().is_none() {
+        return Err(format!("{} has no file name to replace", path.display()));
+    }
+    Ok(path.with_file_name(new_name).to_string_lossy().into_owned())
 }
 
 #[tool_router]
 impl FsTools {
     /// Read workspace files via ACP bridge (paged to ~1000 lines/50KB; use line/limit to continue).
     #[tool(
-        description = "Read workspace files via ACP bridge (paged to ~1000 lines/50KB; use line/limit to continue)."
+        description = "Read workspace files via ACP bridge (paged to ~1000 lines/50KB; use line/limit to continue). Set from_end to read the last `limit` lines instead, e.g. the end of a log."
     )]
-    async fn read_text_file(
+    pub(crate) async fn read_text_file(
         &self,
-        Parameters(ReadTextFileArgs { path, line, limit }): Parameters<ReadTextFileArgs>,
+        Parameters(ReadTextFileArgs {
+            path,
+            line,
+            limit,
+            from_end,
+        }): Parameters<ReadTextFileArgs>,
     ) -> Result<CallToolResult, McpError> {
         let start_line = line.unwrap_or(1).max(1);
         let requested_limit = limit
             .filter(|value| *value > 0)
             .unwrap_or(DEFAULT_READ_LINE_LIMIT);
+        if from_end {
+            return self.read_tail(&path, requested_limit).await;
+        }
         let bridge_limit = requested_limit.saturating_add(1);
-        let response = perform_bridge_request(
-            &self.bridge_addr,
-            &self.session_id,
-            bridge::BridgeOp::Read,
-            &path,
-            line,
-            Some(bridge_limit),
-            None,
-        )
-        .await
-        .map_err(|e| {
-            McpError::internal_error("bridge read failed", Some(json!({"reason": e.to_string()})))
-        })?;
+        let key = (path.clone(), line, Some(bridge_limit));
+        let (response, cached) = match self.last_read.get(&key).await {
+            Some(response) => (response, true),
+            None => {
+                let response = perform_bridge_request(
+                    &self.client,
+                    &self.session_id,
+                    bridge::BridgeOp::Read,
+                    &path,
+                    line,
+                    Some(bridge_limit),
+                    None,
+                )
+                .await
+                .map_err(|e| {
+                    McpError::internal_error(
+                        "bridge read failed",
+                        Some(json!({"reason": e.to_string()})),
+                    )
+                })?;
+                self.last_read.store(key, response.clone()).await;
+                (response, false)
+            }
+        };
 
         let mut snippet =
             prepare_read_snippet(&response, start_line, requested_limit, MAX_READ_BYTES);
@@ -181,6 +367,10 @@ impl FsTools {
             obj.insert("max_bytes".to_string(), json!(MAX_READ_BYTES));
         }
 
+        if cached && let Some(obj) = meta.as_object_mut() {
+            obj.insert("cached".to_string(), json!(true));
+        }
+
         let mut meta_obj = Meta::new();
         meta_obj.insert("codex_fs_read".to_string(), meta);
         let content = RawContent::Text(RawTextContent {
@@ -191,9 +381,307 @@ impl FsTools {
         Ok(CallToolResult::success(vec![content]))
     }
 
+    /// List a workspace directory with entry types and sizes.
+    #[tool(
+        description = "List a directory (default: the workspace root) with entry types and file sizes. Skips .git and .gitignore'd paths unless include_ignored is set. depth defaults to 1 (max 10); paged to 200 entries, use offset to continue. Prefer this over shelling out to ls or find."
+    )]
+    async fn list_directory(
+        &self,
+        Parameters(ListDirectoryArgs {
+            path,
+            depth,
+            offset,
+            limit,
+            include_ignored,
+        }): Parameters<ListDirectoryArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = path.unwrap_or_default();
+        let args = ListArgs {
+            depth,
+            offset,
+            limit,
+            include_ignored,
+        };
+        let response = perform_bridge_op(
+            &self.client,
+            &self.session_id,
+            bridge::BridgeOp::ListDirectory,
+            &path,
+            json!(args),
+        )
+        .await
+        .map_err(|e| {
+            McpError::internal_error(
+                "bridge list_directory failed",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+        let listing: Listing = serde_json::from_str(&response).map_err(|e| {
+            McpError::internal_error(
+                "malformed list_directory response",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+
+        let mut meta = json!({
+            "path": path,
+            "depth": listing.depth,
+            "offset": listing.offset,
+            "entries_returned": listing.entries.len(),
+            "total_entries": listing.total,
+            "complete": listing.complete,
+            "truncated": listing.next_offset.is_some(),
+        });
+        if let Some(next) = listing.next_offset
+            && let Some(obj) = meta.as_object_mut()
+        {
+            obj.insert("next_offset".to_string(), json!(next));
+        }
+
+        let mut meta_obj = Meta::new();
+        meta_obj.insert("codex_fs_list".to_string(), meta);
+        let content = RawContent::Text(RawTextContent {
+            text: listing::format_listing(&listing),
+            meta: Some(meta_obj),
+        })
+        .no_annotation();
+        Ok(CallToolResult::success(vec![content]))
+    }
+
+    /// Search workspace files for a literal string or a regex.
+    #[tool(
+        description = "Search files under a directory (default: the workspace root) or in one file for a literal string, or a regex when regex=true. Case-insensitive unless case_sensitive is set. glob filters files relative to the searched directory (e.g. \"*.rs\", \"src/**/*.ts\"). Skips .git, .gitignore'd paths (unless include_ignored), binaries, and files over 1 MiB. Returns path:line: text per match, with up to 5 context_lines. Stops at max_results (default 100, max 500). Prefer this over shelling out to grep or rg."
+    )]
+    async fn search_text(
+        &self,
+        Parameters(SearchTextArgs {
+            query,
+            path,
+            regex,
+            case_sensitive,
+            glob,
+            max_results,
+            context_lines,
+            include_ignored,
+        }): Parameters<SearchTextArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = path.unwrap_or_default();
+        let args = SearchArgs {
+            query: query.clone(),
+            regex,
+            case_sensitive,
+            glob,
+            max_results,
+            context_lines,
+            include_ignored,
+        };
+        if let Err(err) = search::build_matcher(&args) {
+            return Err(McpError::invalid_params(err, None));
+        }
+        let response = perform_bridge_op(
+            &self.client,
+            &self.session_id,
+            bridge::BridgeOp::SearchText,
+            &path,
+            json!(args),
+        )
+        .await
+        .map_err(|e| {
+            McpError::internal_error(
+                "bridge search_text failed",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+        let results: SearchResults = serde_json::from_str(&response).map_err(|e| {
+            McpError::internal_error(
+                "malformed search_text response",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+
+        let locations: Vec<serde_json::Value> = results
+            .matches
+            .iter()
+            .map(|m| json!({"path": m.location, "line": m.line}))
+            .collect();
+        let meta = json!({
+            "query": query,
+            "path": path,
+            "matches": results.matches.len(),
+            "files_searched": results.files_searched,
+            "truncated": results.truncated,
+            "complete": results.complete,
+            "locations": locations,
+        });
+
+        let mut meta_obj = Meta::new();
+        meta_obj.insert("codex_fs_search".to_string(), meta);
+        let content = RawContent::Text(RawTextContent {
+            text: search::format_results(&results),
+            meta: Some(meta_obj),
+        })
+        .no_annotation();
+        Ok(CallToolResult::success(vec![content]))
+    }
+
+    /// Find files by glob pattern, newest first.
+    #[tool(
+        description = "Find files matching a glob pattern relative to a directory (default: the workspace root), e.g. \"src/**/*.rs\"; a pattern without / matches file names at any depth. Supports **, *, and ?. Skips .git and .gitignore'd paths unless include_ignored is set. Returns paths most recently modified first, up to limit (default 200, max 1000). Prefer this over shelling out to find."
+    )]
+    async fn glob_files(
+        &self,
+        Parameters(GlobFilesArgs {
+            pattern,
+            path,
+            limit,
+            include_ignored,
+        }): Parameters<GlobFilesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        if pattern.trim().is_empty() {
+            return Err(McpError::invalid_params("pattern must not be empty", None));
+        }
+        let path = path.unwrap_or_default();
+        let args = GlobArgs {
+            pattern: pattern.clone(),
+            limit,
+            include_ignored,
+        };
+        let response = perform_bridge_op(
+            &self.client,
+            &self.session_id,
+            bridge::BridgeOp::GlobFiles,
+            &path,
+            json!(args),
+        )
+        .await
+        .map_err(|e| {
+            McpError::internal_error(
+                "bridge glob_files failed",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+        let results: GlobResults = serde_json::from_str(&response).map_err(|e| {
+            McpError::internal_error(
+                "malformed glob_files response",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+
+        let meta = json!({
+            "pattern": pattern,
+            "path": path,
+            "matches": results.matches,
+            "total_matches": results.total,
+            "truncated": results.matches.len() < results.total,
+            "complete": results.complete,
+        });
+
+        let mut meta_obj = Meta::new();
+        meta_obj.insert("codex_fs_glob".to_string(), meta);
+        let content = RawContent::Text(RawTextContent {
+            text: glob_files::format_results(&results),
+            meta: Some(meta_obj),
+        })
+        .no_annotation();
+        Ok(CallToolResult::success(vec![content]))
+    }
+
+    /// Report size, line count, mtime, and binary-ness without reading the file.
+    #[tool(
+        description = "Check a path without reading it: whether it exists, its type, size in bytes, line count (text files up to 64 MiB), last modification time, and whether it looks binary. Use it to choose between a full read, paged reads with line/limit, or skipping the file."
+    )]
+    async fn file_info(
+        &self,
+        Parameters(FileInfoArgs { path }): Parameters<FileInfoArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = perform_bridge_op(
+            &self.client,
+            &self.session_id,
+            bridge::BridgeOp::FileInfo,
+            &path,
+            serde_json::Value::Null,
+        )
+        .await
+        .map_err(|e| {
+            McpError::internal_error(
+                "bridge file_info failed",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+        let info: FileInfo = serde_json::from_str(&response).map_err(|e| {
+            McpError::internal_error(
+                "malformed file_info response",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+
+        let mut meta_obj = Meta::new();
+        meta_obj.insert("codex_fs_info".to_string(), json!(info));
+        let content = RawContent::Text(RawTextContent {
+            text: file_info::format_info(&path, &info, std::time::SystemTime::now()),
+            meta: Some(meta_obj),
+        })
+        .no_annotation();
+        Ok(CallToolResult::success(vec![content]))
+    }
+
+    /// Read a small binary file, returning images as image content.
+    #[tool(
+        description = "Read a small binary file (up to 512 KiB) that read_text_file cannot decode. PNG, JPEG, GIF and WebP images come back as images you can look at; other files come back base64-encoded with their detected MIME type. Use file_info first to check the size."
+    )]
+    async fn read_binary_file(
+        &self,
+        Parameters(ReadBinaryFileArgs { path }): Parameters<ReadBinaryFileArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = perform_bridge_op(
+            &self.client,
+            &self.session_id,
+            bridge::BridgeOp::ReadBinary,
+            &path,
+            serde_json::Value::Null,
+        )
+        .await
+        .map_err(|e| {
+            McpError::internal_error(
+                "bridge read_binary_file failed",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+        let file: BinaryFile = serde_json::from_str(&response).map_err(|e| {
+            McpError::internal_error(
+                "malformed read_binary_file response",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+
+        let mut meta_obj = Meta::new();
+        meta_obj.insert(
+            "codex_fs_binary".to_string(),
+            json!({"path": path, "mime_type": file.mime_type, "size": file.size}),
+        );
+        let summary = binary_read::format_summary(&path, &file);
+        if file.is_image() {
+            let summary = RawContent::Text(RawTextContent {
+                text: summary,
+                meta: Some(meta_obj),
+            })
+            .no_annotation();
+            return Ok(CallToolResult::success(vec![
+                summary,
+                Content::image(file.data, file.mime_type),
+            ]));
+        }
+        let content = RawContent::Text(RawTextContent {
+            text: format!("{summary}\n\n{}", file.data),
+            meta: Some(meta_obj),
+        })
+        .no_annotation();
+        Ok(CallToolResult::success(vec![content]))
+    }
+
     /// Write workspace files via ACP bridge.
     #[tool(description = "Write workspace files via ACP bridge.")]
-    async fn write_text_file(
+    pub(crate) async fn write_text_file(
         &self,
         Parameters(WriteTextFileArgs { path, content }): Parameters<WriteTextFileArgs>,
     ) -> Result<CallToolResult, McpError> {
@@ -205,30 +693,39 @@ impl FsTools {
             .await
             .filter(|entry| final_content.is_empty() || final_content == entry.content)
         {
+            read_edit_base(&self.client, &self.session_id, &path, &self.staged_edits).await?;
             final_content = entry.content.clone();
             staged_applied = true;
         }
 
-        perform_bridge_request(
-            &self.bridge_addr,
-            &self.session_id,
-            bridge::BridgeOp::Write,
-            &path,
-            None,
-            None,
-            Some(final_content.clone()),
-        )
-        .await
-        .map_err(|e| {
-            McpError::internal_error(
-                "bridge write failed",
-                Some(json!({"reason": e.to_string()})),
-            )
-        })?;
+        let result =
+            write_via_bridge(&self.client, &self.session_id, &path, final_content.clone()).await;
+        self.last_read.clear().await;
+        let merged = match result {
+            Ok(merged) => merged,
+            Err(e) => {
+                // The file may have changed under us; re-read it next time.
+                self.staged_edits.discard(&path).await;
+                return Err(McpError::internal_error(
+                    "bridge write failed",
+                    Some(json!({"reason": e.to_string()})),
+                ));
+            }
+        };
 
+        // Nothing was written, so there is nothing to build further edits on.
+        if merged.starts_with(bridge::DRY_RUN_PREFIX) {
+            self.staged_edits.discard(&path).await;
+            return Ok(CallToolResult::success(vec![Content::text(merged)]));
+        }
+        // A non-empty response means the user's concurrent edit was merged in.
+        let rebased = !merged.is_empty();
+        let final_content = if rebased { merged } else { final_content };
         self.staged_edits.stage(path.clone(), final_content).await;
 
-        let response_text = if staged_applied {
+        let response_text = if rebased {
+            "write completed (merged with the user's concurrent edit; re-read the file before further edits)"
+        } else if staged_applied {
             "write completed (applied staged edits)"
         } else {
             "write completed"
@@ -237,13 +734,16 @@ impl FsTools {
     }
 
     /// Apply a focused replacement in a file and persist the result.
-    #[tool(description = "Apply a focused replacement in a file and persist the result.")]
-    async fn edit_text_file(
+    #[tool(
+        description = "Apply a focused replacement in a file and persist the result. If `old_string` is not found verbatim, a unique match ignoring whitespace (or, for 3+ lines, a close match anchored on its first and last lines) is used and reported. With `preview: true` the diff is returned without writing."
+    )]
+    pub(crate) async fn edit_text_file(
         &self,
         Parameters(EditTextFileArgs {
             path,
             old_string,
             new_string,
+            preview,
         }): Parameters<EditTextFileArgs>,
     ) -> Result<CallToolResult, McpError> {
         let instructions = vec![EditInstruction {
@@ -251,47 +751,328 @@ impl FsTools {
             new_text: new_string,
             replace_all: false,
         }];
-        stage_edits(
-            &self.bridge_addr,
+        let result = stage_edits(
+            &self.client,
+            &self.session_id,
+            &path,
+            instructions,
+            &self.staged_edits,
+            preview,
+        )
+        .await;
+        if !preview {
+            self.last_read.clear().await;
+        }
+        result
+    }
+
+    /// Apply multiple sequential replacements in a file and persist the result.
+    #[tool(
+        description = "Apply multiple sequential replacements in a file and persist the result. Each `old_string` not found verbatim may match ignoring whitespace, as in edit_text_file; `replace_all` edits only match verbatim. With `preview: true` the diff is returned without writing."
+    )]
+    async fn multi_edit_text_file(
+        &self,
+        Parameters(MultiEditTextFileArgs {
+            path,
+            edits,
+            preview,
+        }): Parameters<MultiEditTextFileArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        if edits.is_empty() {
+            return Err(McpError::invalid_params(
+                "edits array must not be empty",
+                None,
+            ));
+        }
+        let instructions = edits
+            .into_iter()
+            .map(|edit| EditInstruction {
+                old_text: edit.old_string,
+                new_text: edit.new_string,
+                replace_all: edit.replace_all,
+            })
+            .collect::<Vec<_>>();
+
+        let result = stage_edits(
+            &self.client,
             &self.session_id,
             &path,
             instructions,
             &self.staged_edits,
+            preview,
+        )
+        .await;
+        if !preview {
+            self.last_read.clear().await;
+        }
+        result
+    }
+
+    /// Apply a unified diff to one file.
+    #[tool(
+        description = "Apply a unified diff (`@@ -a,b +c,d @@` hunks; `---`/`+++` headers optional) to one file. Hunks are located even if lines moved, and may match ignoring whitespace or with up to 2 context lines dropped. Reports each hunk's result. Nothing is written unless every hunk applies, or `partial` is set."
+    )]
+    async fn apply_unified_diff(
+        &self,
+        Parameters(ApplyUnifiedDiffArgs {
+            path,
+            diff,
+            partial,
+        }): Parameters<ApplyUnifiedDiffArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let hunks = unified_diff::parse(&diff)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        let base_content =
+            read_edit_base(&self.client, &self.session_id, &path, &self.staged_edits).await?;
+        let applied = unified_diff::apply(&base_content, &hunks);
+        let any_applied = applied.hunks.iter().any(|hunk| hunk.applied);
+        let write = applied.all_applied() || (partial && any_applied);
+
+        let mut meta_obj = Meta::new();
+        meta_obj.insert("codex_fs_hunks".to_string(), json!(applied.hunks));
+        let report = RawContent::Text(RawTextContent {
+            text: unified_diff::format_outcomes(&path, &applied, write),
+            meta: Some(meta_obj),
+        })
+        .no_annotation();
+        if !write {
+            return Ok(CallToolResult::error(vec![report]));
+        }
+        if applied.content == base_content {
+            return Ok(CallToolResult::success(vec![
+                report,
+                Content::text(format!("No changes detected for {path}.")),
+            ]));
+        }
+
+        let result = write_edited(
+            &self.client,
+            &self.session_id,
+            &path,
+            &base_content,
+            applied.content,
+            &self.staged_edits,
+        )
+        .await;
+        self.last_read.clear().await;
+        let mut contents = result?;
+        contents.push(report);
+        Ok(CallToolResult::success(contents))
+    }
+
+    /// Delete a workspace file.
+    #[tool(
+        description = "Delete a file (not a directory). Refused in read-only mode; protected paths need the user's approval."
+    )]
+    async fn delete_file(
+        &self,
+        Parameters(DeleteFileArgs { path }): Parameters<DeleteFileArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = perform_bridge_op(
+            &self.client,
+            &self.session_id,
+            bridge::BridgeOp::Delete,
+            &path,
+            serde_json::Value::Null,
+        )
+        .await;
+        self.last_read.clear().await;
+        self.staged_edits.discard(&path).await;
+        let message = result.map_err(|e| {
+            McpError::internal_error(
+                "bridge delete failed",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+        Ok(CallToolResult::success(vec![Content::text(message)]))
+    }
+
+    /// Run a read-only command, reusing its output while the workspace is unchanged.
+    #[tool(
+        description = "Run a read-only command without a shell: rg, grep, ls, cat, head, tail, wc, stat, file, pwd, tree, find without actions, or git status/diff/log/show/blame/ls-files/rev-parse. `command` is the program followed by its arguments; `cwd` defaults to the workspace root. Running the same command in the same directory again returns the earlier output at once while no files have changed. Prefer it over the shell for searches and listings you may repeat."
+    )]
+    async fn run_cached_command(
+        &self,
+        Parameters(RunCachedCommandArgs { command, cwd }): Parameters<RunCachedCommandArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        if command.is_empty() {
+            return Err(McpError::invalid_params("command must not be empty", None));
+        }
+        let response = perform_bridge_op(
+            &self.client,
+            &self.session_id,
+            bridge::BridgeOp::RunCommand,
+            cwd.as_deref().unwrap_or(""),
+            json!({ "command": command }),
+        )
+        .await
+        .map_err(|e| {
+            McpError::internal_error(
+                "bridge run_cached_command failed",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+        let result: CommandOutput = serde_json::from_str(&response).map_err(|e| {
+            McpError::internal_error(
+                "malformed run_cached_command response",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+
+        let mut meta_obj = Meta::new();
+        meta_obj.insert("codex_fs_command".to_string(), json!(result));
+        let content = RawContent::Text(RawTextContent {
+            text: command_cache::format_output(&command, &result),
+            meta: Some(meta_obj),
+        })
+        .no_annotation();
+        Ok(CallToolResult::success(vec![content]))
+    }
+
+    /// Create a workspace directory.
+    #[tool(
+        description = "Create a directory and any missing parents, e.g. before writing files into a new module folder. Succeeds if it already exists. Refused in read-only mode."
+    )]
+    async fn create_directory(
+        &self,
+        Parameters(CreateDirectoryArgs { path }): Parameters<CreateDirectoryArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let message = perform_bridge_op(
+            &self.client,
+            &self.session_id,
+            bridge::BridgeOp::CreateDirectory,
+            &path,
+            serde_json::Value::Null,
+        )
+        .await
+        .map_err(|e| {
+            McpError::internal_error(
+                "bridge create_directory failed",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+        Ok(CallToolResult::success(vec![Content::text(message)]))
+    }
+
+    /// Move a workspace file to another path.
+    #[tool(
+        description = "Move a file to another path, creating missing parent directories. Fails if destination exists unless overwrite is set. Refused in read-only mode."
+    )]
+    async fn move_file(
+        &self,
+        Parameters(MoveFileArgs {
+            path,
+            destination,
+            overwrite,
+        }): Parameters<MoveFileArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        self.move_via_bridge(&path, &destination, overwrite).await
+    }
+
+    /// Rename a workspace file within its directory.
+    #[tool(
+        description = "Rename a file within its directory; new_name is a file name, not a path. Fails if a file with that name exists unless overwrite is set. Refused in read-only mode."
+    )]
+    async fn rename_file(
+        &self,
+        Parameters(RenameFileArgs {
+            path,
+            new_name,
+            overwrite,
+        }): Parameters<RenameFileArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let destination =
+            renamed_path(&path, &new_name).map_err(|e| McpError::invalid_params(e, None))?;
+        self.move_via_bridge(&path, &destination, overwrite).await
+    }
+
+    /// Ask the user a clarifying question and wait for their choice or free-text answer.
+    #[tool(
+        description = "Ask the user a clarifying multiple-choice question mid-task and wait for their answer. Provide 2-6 short options; omit options for a yes/no question. The user may also answer in their own words instead of picking an option. Prefer this over ending your turn with a question."
+    )]
+    async fn ask_user(
+        &self,
+        Parameters(AskUserArgs { question, options }): Parameters<AskUserArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        if question.trim().is_empty() {
+            return Err(McpError::invalid_params("question must not be empty", None));
+        }
+        let response = perform_bridge_op(
+            &self.client,
+            &self.session_id,
+            bridge::BridgeOp::AskUser,
+            "",
+            json!({ "question": question, "options": options }),
+        )
+        .await
+        .map_err(|e| {
+            McpError::internal_error("ask_user failed", Some(json!({"reason": e.to_string()})))
+        })?;
+
+        let answer: serde_json::Value = serde_json::from_str(&response).unwrap_or(json!({}));
+        let other = answer.get("other").and_then(|o| o.as_bool()) == Some(true);
+        let text = match answer.get("answer").and_then(|a| a.as_str()) {
+            Some(choice) => format!("The user answered: {choice}"),
+            None if other => "The user wants to answer in their own words. End your turn with the question so they can reply.".to_string(),
+            None => "The user dismissed the question without answering.".to_string(),
+        };
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    /// List editor diagnostics reported by the client.
+    #[tool(
+        description = "List editor diagnostics (errors, warnings) reported by the client, optionally for a single file."
+    )]
+    async fn get_diagnostics(
+        &self,
+        Parameters(GetDiagnosticsArgs { path }): Parameters<GetDiagnosticsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        let response = perform_bridge_request(
+            &self.client,
+            &self.session_id,
+            bridge::BridgeOp::Diagnostics,
+            path.as_deref().unwrap_or_default(),
+            None,
+            None,
+            None,
         )
         .await
+        .map_err(|e| {
+            McpError::internal_error(
+                "bridge diagnostics failed",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+
+        let files: serde_json::Value = serde_json::from_str(&response).unwrap_or(json!([]));
+        Ok(CallToolResult::success(vec![Content::text(
+            format_diagnostics(&files),
+        )]))
     }
 
-    /// Apply multiple sequential replacements in a file and persist the result.
+    /// List commands executed so far in this session.
     #[tool(
-        description = "Apply multiple sequential replacements in a file and persist the result."
+        description = "List the commands executed so far in this session with exit codes, durations, and the tail of their output, most recent last. Check it before re-running a command, and set failed_only to see what already failed."
     )]
-    async fn multi_edit_text_file(
+    async fn command_history(
         &self,
-        Parameters(MultiEditTextFileArgs { path, edits }): Parameters<MultiEditTextFileArgs>,
+        Parameters(CommandHistoryArgs { limit, failed_only }): Parameters<CommandHistoryArgs>,
     ) -> Result<CallToolResult, McpError> {
-        if edits.is_empty() {
-            return Err(McpError::invalid_params(
-                "edits array must not be empty",
-                None,
-            ));
-        }
-        let instructions = edits
-            .into_iter()
-            .map(|edit| EditInstruction {
-                old_text: edit.old_string,
-                new_text: edit.new_string,
-                replace_all: edit.replace_all,
-            })
-            .collect::<Vec<_>>();
-
-        stage_edits(
-            &self.bridge_addr,
+        let response = perform_bridge_op(
+            &self.client,
             &self.session_id,
-            &path,
-            instructions,
-            &self.staged_edits,
+            bridge::BridgeOp::CommandHistory,
+            "",
+            json!({ "limit": limit, "failed_only": failed_only }),
         )
         .await
+        .map_err(|e| {
+            McpError::internal_error(
+                "command_history failed",
+                Some(json!({"reason": e.to_string()})),
+            )
+        })?;
+        Ok(CallToolResult::success(vec![Content::text(response)]))
     }
 }
 
@@ -326,31 +1107,181 @@ struct EditEntry {
 }
 
 #[derive(Deserialize, Serialize, JsonSchema)]
-struct ReadTextFileArgs {
+pub(crate) struct ReadTextFileArgs {
     path: String,
     #[serde(default)]
     line: Option<u32>,
     #[serde(default)]
     limit: Option<u32>,
+    /// Read the last `limit` lines; `line` is ignored.
+    #[serde(default)]
+    from_end: bool,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct ListDirectoryArgs {
+    /// Directory to list; defaults to the workspace root.
+    #[serde(default)]
+    path: Option<String>,
+    /// Levels to descend (default 1, max 10).
+    #[serde(default)]
+    depth: Option<usize>,
+    /// Entries to skip, from `next_offset` of the previous page.
+    #[serde(default)]
+    offset: Option<usize>,
+    /// Entries per page (default 200, max 1000).
+    #[serde(default)]
+    limit: Option<usize>,
+    /// Also list `.gitignore`d paths.
+    #[serde(default)]
+    include_ignored: bool,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct SearchTextArgs {
+    /// Text to find; a regular expression when `regex` is set.
+    query: String,
+    /// Directory or file to search; defaults to the workspace root.
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    regex: bool,
+    #[serde(default)]
+    case_sensitive: bool,
+    /// Only search files matching this glob, relative to `path`.
+    #[serde(default)]
+    glob: Option<String>,
+    /// Matches to return at most (default 100, max 500).
+    #[serde(default)]
+    max_results: Option<usize>,
+    /// Lines of context before and after each match (max 5).
+    #[serde(default)]
+    context_lines: Option<usize>,
+    /// Also search `.gitignore`d paths.
+    #[serde(default)]
+    include_ignored: bool,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct GlobFilesArgs {
+    /// Glob such as `src/**/*.rs`, relative to `path`.
+    pattern: String,
+    /// Directory to match in; defaults to the workspace root.
+    #[serde(default)]
+    path: Option<String>,
+    /// Paths to return at most (default 200, max 1000).
+    #[serde(default)]
+    limit: Option<usize>,
+    /// Also match `.gitignore`d paths.
+    #[serde(default)]
+    include_ignored: bool,
 }
 
 #[derive(Deserialize, Serialize, JsonSchema)]
-struct WriteTextFileArgs {
+pub(crate) struct WriteTextFileArgs {
     path: String,
     content: String,
 }
 
 #[derive(Deserialize, Serialize, JsonSchema)]
-struct EditTextFileArgs {
+pub(crate) struct EditTextFileArgs {
     path: String,
     old_string: String,
     new_string: String,
+    /// Return the diff without writing the file.
+    #[serde(default)]
+    preview: bool,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct ApplyUnifiedDiffArgs {
+    path: String,
+    /// Unified diff for this one file.
+    diff: String,
+    /// Write the hunks that apply even if others fail.
+    #[serde(default)]
+    partial: bool,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct FileInfoArgs {
+    path: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct ReadBinaryFileArgs {
+    path: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct DeleteFileArgs {
+    path: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct CreateDirectoryArgs {
+    path: String,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct RunCachedCommandArgs {
+    /// Program and arguments, e.g. `["rg", "-n", "TODO", "src"]`.
+    command: Vec<String>,
+    /// Directory to run in, relative to the workspace root.
+    #[serde(default)]
+    cwd: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct MoveFileArgs {
+    path: String,
+    /// New path of the file.
+    destination: String,
+    /// Replace an existing file at `destination`.
+    #[serde(default)]
+    overwrite: bool,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct RenameFileArgs {
+    path: String,
+    /// New file name, in the same directory.
+    new_name: String,
+    /// Replace an existing file with that name.
+    #[serde(default)]
+    overwrite: bool,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct AskUserArgs {
+    question: String,
+    #[serde(default)]
+    options: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct GetDiagnosticsArgs {
+    #[serde(default)]
+    path: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, JsonSchema)]
+struct CommandHistoryArgs {
+    /// Maximum number of commands to list (default 20).
+    #[serde(default)]
+    limit: Option<usize>,
+    /// Only list commands that exited with a non-zero code.
+    #[serde(default)]
+    failed_only: bool,
 }
 
 #[derive(Deserialize, Serialize, JsonSchema)]
 struct MultiEditTextFileArgs {
     path: String,
     edits: Vec<EditEntry>,
+    /// Return the diff without writing the file.
+    #[serde(default)]
+    preview: bool,
 }
 
 struct EditInstruction {
@@ -360,42 +1291,16 @@ struct EditInstruction {
 }
 
 async fn stage_edits(
-    bridge_addr: &str,
+    client: &BridgeClient,
     session_id: &str,
     path: &str,
     instructions: Vec<EditInstruction>,
     staged_edits: &StagedEdits,
+    preview: bool,
 ) -> Result<CallToolResult, McpError> {
-    let base_content = if let Some(entry) = staged_edits.get(path).await {
-        entry.content.clone()
-    } else {
-        match perform_bridge_request(
-            bridge_addr,
-            session_id,
-            bridge::BridgeOp::Read,
-            path,
-            None,
-            None,
-            None,
-        )
-        .await
-        {
-            Ok(content) => content,
-            Err(err) => {
-                let message = err.to_string();
-                if is_missing_file_error(&message) {
-                    String::new()
-                } else {
-                    return Err(McpError::internal_error(
-                        "failed to read current file content",
-                        Some(json!({"reason": err.to_string()})),
-                    ));
-                }
-            }
-        }
-    };
+    let base_content = read_edit_base(client, session_id, path, staged_edits).await?;
 
-    let new_content = apply_edits(&base_content, &instructions)
+    let (new_content, matches) = apply_edits(&base_content, &instructions)
         .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
 
     if new_content == base_content {
@@ -404,30 +1309,174 @@ async fn stage_edits(
         ))]));
     }
 
-    let diff_text = format_diff_for_path(path, &base_content, &new_content);
+    let mut contents = if preview {
+        vec![
+            diff_content(path, &base_content, &new_content),
+            Content::text(format!(
+                "Preview only; nothing was written to {path}. \
+                 Call the tool again without `preview` to apply the edit."
+            )),
+        ]
+    } else {
+        write_edited(
+            client,
+            session_id,
+            path,
+            &base_content,
+            new_content,
+            staged_edits,
+        )
+        .await?
+    };
+    if let Some(note) = fuzzy_match_note(&matches) {
+        contents.push(note);
+    }
+    Ok(CallToolResult::success(contents))
+}
+
+/// Tell the model which edits were located by fallback matching, with the
+/// match kind of every edit in `_meta.codex_fs_match`.
+fn fuzzy_match_note(matches: &[MatchKind]) -> Option<Content> {
+    let inexact: Vec<String> = matches
+        .iter()
+        .enumerate()
+        .filter_map(|(index, kind)| {
+            let how = match kind {
+                MatchKind::Exact => return None,
+                MatchKind::Whitespace => "ignoring whitespace",
+                MatchKind::Fuzzy => "fuzzily",
+            };
+            Some(if matches.len() == 1 {
+                format!("`old_string` was matched {how}")
+            } else {
+                format!("edit {} was matched {how}", index + 1)
+            })
+        })
+        .collect();
+    if inexact.is_empty() {
+        return None;
+    }
+    let mut meta_obj = Meta::new();
+    meta_obj.insert("codex_fs_match".to_string(), json!(matches));
+    Some(
+        RawContent::Text(RawTextContent {
+            text: format!("Note: {}; check the diff above.", inexact.join(", ")),
+            meta: Some(meta_obj),
+        })
+        .no_annotation(),
+    )
+}
 
-    let write_content = new_content.clone();
-    let staged_bytes = write_content.len();
-    let _ = perform_bridge_request(
-        bridge_addr,
+/// The content edits apply to: the file as the bridge reads it (empty when
+/// it does not exist yet). When edits to `path` are staged, the file must
+/// still hold the staged content; otherwise it changed on disk since, the
+/// staged content is dropped, and the edit is refused.
+async fn read_edit_base(
+    client: &BridgeClient,
+    session_id: &str,
+    path: &str,
+    staged_edits: &StagedEdits,
+) -> Result<String, McpError> {
+    let current = match perform_bridge_request(
+        client,
         session_id,
-        bridge::BridgeOp::Write,
+        bridge::BridgeOp::Read,
         path,
         None,
         None,
-        Some(write_content.clone()),
+        None,
     )
     .await
-    .map_err(|e| {
-        McpError::internal_error(
-            "bridge write failed",
-            Some(json!({"reason": e.to_string()})),
-        )
-    })?;
+    {
+        Ok(content) => content,
+        Err(err) => {
+            let message = err.to_string();
+            if is_missing_file_error(&message) {
+                String::new()
+            } else {
+                return Err(McpError::internal_error(
+                    "failed to read current file content",
+                    Some(json!({"reason": err.to_string()})),
+                ));
+            }
+        }
+    };
+    if let Some(entry) = staged_edits.get(path).await
+        && entry.hash != content_hash(&current)
+    {
+        staged_edits.discard(path).await;
+        warn!(file = %path, "staged edits are stale; file changed on disk");
+        return Err(McpError::invalid_request(
+            format!(
+                "{path} changed on disk since it was last edited. \
+                 Re-read the file and apply the edit again. No edits were applied."
+            ),
+            Some(json!({"reason": "file_changed_on_disk", "path": path})),
+        ));
+    }
+    Ok(current)
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Write edited content through the bridge and stage it, returning the diff
+/// (with `codex_fs_diff` metadata) and a completion message.
+async fn write_edited(
+    client: &BridgeClient,
+    session_id: &str,
+    path: &str,
+    base_content: &str,
+    new_content: String,
+    staged_edits: &StagedEdits,
+) -> Result<Vec<Content>, McpError> {
+    let diff_content = diff_content(path, base_content, &new_content);
+
+    let write_content = new_content;
+    let staged_bytes = write_content.len();
+    let merged = match write_via_bridge(client, session_id, path, write_content.clone()).await {
+        Ok(merged) => merged,
+        Err(e) => {
+            // The file may have changed under us; re-read it next time.
+            staged_edits.discard(path).await;
+            return Err(McpError::internal_error(
+                "bridge write failed",
+                Some(json!({"reason": e.to_string()})),
+            ));
+        }
+    };
 
+    // Nothing was written, so the next edit starts from the disk again.
+    if merged.starts_with(bridge::DRY_RUN_PREFIX) {
+        staged_edits.discard(path).await;
+        return Ok(vec![diff_content, Content::text(merged)]);
+    }
+    // A non-empty response means the user's concurrent edit was merged in.
+    let rebased = !merged.is_empty();
+    let write_content = if rebased { merged } else { write_content };
     staged_edits.stage(path.to_string(), write_content).await;
     info!(file = %path, bytes = staged_bytes, "Staged edits committed");
 
+    Ok(vec![
+        diff_content,
+        Content::text(if rebased {
+            format!(
+                "Write completed for {path}, merged with the user's concurrent edit. \
+                 Re-read the file before further edits."
+            )
+        } else {
+            format!("Write completed for {path}.")
+        }),
+    ])
+}
+
+/// The diff from `before` to `after`, with its line ranges in
+/// `_meta.codex_fs_diff`.
+fn diff_content(path: &str, before: &str, after: &str) -> Content {
+    let diff_text = format_diff_for_path(path, before, after);
     let (new_ranges, old_ranges) = parse_diff_line_ranges(&diff_text);
     let diff_meta = json!({
         "path": path,
@@ -437,19 +1486,18 @@ async fn stage_edits(
 
     let mut meta_obj = Meta::new();
     meta_obj.insert("codex_fs_diff".to_string(), diff_meta);
-    let diff_content = RawContent::Text(RawTextContent {
+    RawContent::Text(RawTextContent {
         text: diff_text,
         meta: Some(meta_obj),
     })
-    .no_annotation();
-    Ok(CallToolResult::success(vec![
-        diff_content,
-        Content::text(format!("Write completed for {path}.")),
-    ]))
+    .no_annotation()
 }
 
-fn apply_edits(base: &str, edits: &[EditInstruction]) -> Result<String> {
+/// Apply `edits` in order, returning the result and how each `old_string`
+/// was found. `replace_all` edits only match verbatim.
+fn apply_edits(base: &str, edits: &[EditInstruction]) -> Result<(String, Vec<MatchKind>)> {
     let mut content = base.to_string();
+    let mut matches = Vec::with_capacity(edits.len());
     for edit in edits {
         if edit.old_text.is_empty() {
             return Err(anyhow!(
@@ -465,17 +1513,16 @@ fn apply_edits(base: &str, edits: &[EditInstruction]) -> Result<String> {
                 ));
             }
             content = replaced;
+            matches.push(MatchKind::Exact);
         } else {
-            let Some(index) = content.find(&edit.old_text) else {
-                return Err(anyhow!(
-                    "The provided `old_string` does not appear in the file. No edits were applied."
-                ));
-            };
-            let end = index + edit.old_text.len();
-            content.replace_range(index..end, &edit.new_text);
+            let (replaced, kind) =
+                edit_match::replace_once(&content, &edit.old_text, &edit.new_text)
+                    .map_err(|err| anyhow!(err.to_string()))?;
+            content = replaced;
+            matches.push(kind);
         }
     }
-    Ok(content)
+    Ok((content, matches))
 }
 
 fn format_diff_for_path(path: &str, before: &str, after: &str) -> String {
@@ -489,6 +1536,42 @@ fn format_diff_for_path(path: &str, before: &str, after: &str) -> String {
     }
 }
 
+/// Render diagnostics JSON (`[{path, diagnostics: [...]}]`) as `path:line:col severity: message` lines.
+fn format_diagnostics(files: &serde_json::Value) -> String {
+    let mut lines = Vec::new();
+    for file in files.as_array().into_iter().flatten() {
+        let path = file.get("path").and_then(|p| p.as_str()).unwrap_or("?");
+        for diag in file
+            .get("diagnostics")
+            .and_then(|d| d.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let start = &diag["range"]["start"];
+            let line = start["line"].as_u64().unwrap_or(0) + 1;
+            let column = start["character"].as_u64().unwrap_or(0) + 1;
+            let severity = match diag["severity"].as_u64().unwrap_or(1) {
+                1 => "error",
+                2 => "warning",
+                3 => "info",
+                _ => "hint",
+            };
+            let message = diag["message"].as_str().unwrap_or_default();
+            let mut entry = format!("{path}:{line}:{column} {severity}: {message}");
+            if let Some(source) = diag["source"].as_str() {
+                entry.push_str(&format!(" [{source}]"));
+            }
+            lines.push(entry);
+        }
+    }
+
+    if lines.is_empty() {
+        "No diagnostics reported.".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
 fn is_missing_file_error(message: &str) -> bool {
     let lower = message.to_ascii_lowercase();
     lower.contains("no such file") || lower.contains("not found")
@@ -528,7 +1611,7 @@ fn prepare_read_snippet(
         if bytes_used + segment_bytes > max_bytes {
             let remaining = max_bytes.saturating_sub(bytes_used);
             if remaining > 0 {
-                let cut = truncate_to_char_boundary(segment, remaining);
+                let cut = text::floor_char_boundary(segment, remaining);
                 text.push_str(&segment[..cut]);
                 bytes_used += cut;
             }
@@ -568,17 +1651,6 @@ fn prepare_read_snippet(
     }
 }
 
-fn truncate_to_char_boundary(segment: &str, max_bytes: usize) -> usize {
-    if max_bytes >= segment.len() {
-        return segment.len();
-    }
-    let mut idx = max_bytes;
-    while idx > 0 && !segment.is_char_boundary(idx) {
-        idx -= 1;
-    }
-    idx
-}
-
 fn build_file_read_hint(
     snippet: &ReadSnippet,
     start_line: u32,
@@ -673,7 +1745,7 @@ fn line_ranges_to_json(ranges: &[LineRange]) -> Vec<serde_json::Value> {
 }
 
 async fn perform_bridge_request(
-    bridge_addr: &str,
+    client: &BridgeClient,
     session_id: &str,
     op: bridge::BridgeOp,
     path: &str,
@@ -681,51 +1753,198 @@ async fn perform_bridge_request(
     limit: Option<u32>,
     content: Option<String>,
 ) -> Result<String> {
-    let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
-    let mut stream = TcpStream::connect(bridge_addr)
-        .await
-        .with_context(|| format!("failed to connect to bridge at {bridge_addr}"))?;
-    let (reader_half, mut writer_half) = stream.split();
-    let mut reader = BufReader::new(reader_half).lines();
-
-    let payload = serde_json::to_string(&json!({
-        "id": request_id,
-        "session_id": session_id,
-        "op": match op {
-            bridge::BridgeOp::Read => "read",
-            bridge::BridgeOp::Write => "write",
-        },
-        "path": path,
-        "line": line,
-        "limit": limit,
-        "content": content,
-    }))?;
+    send_bridge_payload(
+        client,
+        op,
+        json!({
+            "session_id": session_id,
+            "op": op,
+            "path": path,
+            "line": line,
+            "limit": limit,
+            "content": content,
+        }),
+    )
+    .await
+}
 
-    writer_half.write_all(payload.as_bytes()).await?;
-    writer_half.write_all(b"\n").await?;
-    writer_half.flush().await?;
+/// Send a bridge request for ops that take structured `args` rather than
+/// the read/write fields.
+async fn perform_bridge_op(
+    client: &BridgeClient,
+    session_id: &str,
+    op: bridge::BridgeOp,
+    path: &str,
+    args: serde_json::Value,
+) -> Result<String> {
+    send_bridge_payload(
+        client,
+        op,
+        json!({
+            "session_id": session_id,
+            "op": op,
+            "path": path,
+            "args": args,
+        }),
+    )
+    .await
+}
 
-    let line = timeout(Duration::from_secs(5), reader.next_line())
-        .await
-        .map_err(|_| anyhow!("bridge request timed out"))??
-        .ok_or_else(|| anyhow!("bridge closed connection"))?;
-
-    let response: serde_json::Value = serde_json::from_str(&line)?;
-    let success = response
-        .get("success")
-        .and_then(|s| s.as_bool())
-        .unwrap_or(false);
-    if success {
-        Ok(response
-            .get("content")
-            .and_then(|c| c.as_str())
-            .unwrap_or_default()
-            .to_string())
-    } else {
-        let message = response
-            .get("error")
-            .and_then(|e| e.as_str())
-            .unwrap_or("bridge error");
-        Err(anyhow!(message.to_string()))
+/// Write `content` to `path`, in chunks when it is too large for one
+/// request line. Returns what a single `write` returns.
+async fn write_via_bridge(
+    client: &BridgeClient,
+    session_id: &str,
+    path: &str,
+    content: String,
+) -> Result<String> {
+    if content.len() <= chunked_write::CHUNKED_WRITE_THRESHOLD {
+        return perform_bridge_request(
+            client,
+            session_id,
+            bridge::BridgeOp::Write,
+            path,
+            None,
+            None,
+            Some(content),
+        )
+        .await;
+    }
+
+    let size = content.len();
+    let upload_id = perform_bridge_op(
+        client,
+        session_id,
+        bridge::BridgeOp::WriteBegin,
+        path,
+        json!(UploadArgs {
+            size,
+            ..Default::default()
+        }),
+    )
+    .await?;
+    let frame = |op: bridge::BridgeOp, offset: usize, chunk: Option<&str>| {
+        json!({
+            "session_id": session_id,
+            "op": op,
+            "path": path,
+            "content": chunk,
+            "args": UploadArgs {
+                upload_id: Some(upload_id.clone()),
+                size,
+                offset,
+            },
+        })
+    };
+
+    let mut offset = 0;
+    let mut result = Ok(String::new());
+    for chunk in chunked_write::split_chunks(&content, chunked_write::CHUNK_BYTES) {
+        result = send_bridge_payload(
+            client,
+            bridge::BridgeOp::WriteAppend,
+            frame(bridge::BridgeOp::WriteAppend, offset, Some(chunk)),
+        )
+        .await;
+        if result.is_err() {
+            break;
+        }
+        offset += chunk.len();
+    }
+    if result.is_ok() {
+        return send_bridge_payload(
+            client,
+            bridge::BridgeOp::WriteCommit,
+            frame(bridge::BridgeOp::WriteCommit, offset, None),
+        )
+        .await;
+    }
+    // Free the partial upload now rather than when it expires.
+    if let Err(err) = send_bridge_payload(
+        client,
+        bridge::BridgeOp::WriteAbort,
+        frame(bridge::BridgeOp::WriteAbort, offset, None),
+    )
+    .await
+    {
+        warn!(error = %err, "failed to abort chunked write");
+    }
+    result
+}
+
+async fn send_bridge_payload(
+    client: &BridgeClient,
+    op: bridge::BridgeOp,
+    payload: serde_json::Value,
+) -> Result<String> {
+    // Bytes carried by the request, or declared for a chunked write's commit.
+    let bytes = payload
+        .get("content")
+        .and_then(serde_json::Value::as_str)
+        .map(str::len)
+        .or_else(|| {
+            payload
+                .pointer("/args/size")
+                .and_then(serde_json::Value::as_u64)
+                .map(|size| size as usize)
+        })
+        .unwrap_or(0);
+    let wait = bridge_timeout(client.timeouts(), op, bytes);
+    match client.send(payload.clone(), wait).await {
+        Err(err) if err.is::<TimedOut>() && is_retryable(op) => {
+            warn!(?op, error = %err, "retrying bridge request");
+            client.send(payload, wait).await
+        }
+        result => result,
+    }
+}
+
+/// How long to wait for a bridge response; ops that wait on the user get
+/// longer, and writes get more time the more content they carry.
+fn bridge_timeout(timeouts: &BridgeTimeouts, op: bridge::BridgeOp, bytes: usize) -> Duration {
+    match op {
+        bridge::BridgeOp::AskUser => ASK_USER_TIMEOUT,
+        bridge::BridgeOp::Write | bridge::BridgeOp::WriteCommit => timeouts.write_timeout(bytes),
+        bridge::BridgeOp::WriteAppend => timeouts.transfer_timeout(bytes),
+        bridge::BridgeOp::ListDirectory
+        | bridge::BridgeOp::SearchText
+        | bridge::BridgeOp::GlobFiles => timeouts.list_timeout(),
+        bridge::BridgeOp::Read
+        | bridge::BridgeOp::Diagnostics
+        | bridge::BridgeOp::CommandHistory
+        | bridge::BridgeOp::WriteBegin
+        | bridge::BridgeOp::WriteAbort
+        | bridge::BridgeOp::FileInfo
+        | bridge::BridgeOp::ReadBinary => timeouts.read,
+        // Deletes, moves and mkdirs may wait on a protected-path prompt.
+        bridge::BridgeOp::Delete | bridge::BridgeOp::Move | bridge::BridgeOp::CreateDirectory => {
+            timeouts.write
+        }
+        bridge::BridgeOp::RunCommand => command_cache::COMMAND_TIMEOUT + timeouts.read,
+    }
+}
+
+/// Whether an op can be sent again after a timeout. A write or chunk that
+/// timed out may still land, and a question may still be on the user's screen.
+fn is_retryable(op: bridge::BridgeOp) -> bool {
+    match op {
+        bridge::BridgeOp::Read
+        | bridge::BridgeOp::Diagnostics
+        | bridge::BridgeOp::CommandHistory
+        | bridge::BridgeOp::ListDirectory
+        | bridge::BridgeOp::SearchText
+        | bridge::BridgeOp::GlobFiles
+        | bridge::BridgeOp::WriteAbort
+        | bridge::BridgeOp::FileInfo
+        | bridge::BridgeOp::ReadBinary
+        | bridge::BridgeOp::RunCommand => true,
+        bridge::BridgeOp::AskUser
+        | bridge::BridgeOp::Write
+        | bridge::BridgeOp::WriteBegin
+        | bridge::BridgeOp::WriteAppend
+        | bridge::BridgeOp::WriteCommit
+        | bridge::BridgeOp::Delete
+        | bridge::BridgeOp::Move
+        | bridge::BridgeOp::CreateDirectory => false,
     }
 }
@@ -1,5 +1,14 @@
 pub mod bridge;
+pub mod bridge_client;
+pub mod client_op;
+pub mod framing;
 pub mod mcp_server;
+pub mod text;
+pub mod tools;
+
+#[cfg(test)]
+mod tests;
 
 pub use bridge::FsBridge;
+pub use client_op::{ClientOp, TerminalRun};
 pub use mcp_server::run as run_mcp_server;
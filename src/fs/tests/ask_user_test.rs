@@ -0,0 +1,150 @@
+#![cfg(test)]
+
+use std::time::Duration;
+
+use agent_client_protocol::{
+    PermissionOptionId, RequestPermissionOutcome, RequestPermissionResponse,
+};
+use serde_json::{Value, json};
+use tokio::task::LocalSet;
+
+use super::support::{SESSION, TestBridge, Workspace};
+use crate::fs::ClientOp;
+
+/// Answer the next permission request with `option`, returning its choices.
+async fn answer_next(
+    client_rx: &mut tokio::sync::mpsc::UnboundedReceiver<ClientOp>,
+    option: Option<&str>,
+) -> (String, Vec<String>) {
+    answer_next_with(client_rx, option, None).await
+}
+
+/// Like `answer_next`, with `meta` on the permission response.
+async fn answer_next_with(
+    client_rx: &mut tokio::sync::mpsc::UnboundedReceiver<ClientOp>,
+    option: Option<&str>,
+    meta: Option<Value>,
+) -> (String, Vec<String>) {
+    let Some(ClientOp::RequestPermission {
+        request,
+        response_tx,
+        ..
+    }) = client_rx.recv().await
+    else {
+        panic!("expected a permission request");
+    };
+    let title = request.tool_call.fields.title.clone().unwrap_or_default();
+    let names = request.options.iter().map(|o| o.name.clone()).collect();
+    let outcome = match option {
+        Some(id) => RequestPermissionOutcome::Selected {
+            option_id: PermissionOptionId(id.into()),
+        },
+        None => RequestPermissionOutcome::Cancelled,
+    };
+    let _ = response_tx.send(Ok(RequestPermissionResponse { outcome, meta }));
+    (title, names)
+}
+
+/// `ask_user` becomes a permission request whose options are the choices;
+/// the picked choice, or `null` when dismissed, is the tool's answer.
+#[tokio::test]
+async fn ask_user_round_trips_through_a_permission_request() {
+    LocalSet::new()
+        .run_until(async {
+            let mut test = TestBridge::start(Workspace::new()).await;
+            let client = test.connect(SESSION);
+            let ask = |options: Value| {
+                client.send(
+                    json!({
+                        "session_id": SESSION,
+                        "op": "ask_user",
+                        "args": { "question": "Which database?", "options": options },
+                    }),
+                    Duration::from_secs(10),
+                )
+            };
+
+            let (answer, asked) = tokio::join!(
+                ask(json!(["Postgres", "SQLite"])),
+                answer_next(&mut test.requests, Some("choice-1"))
+            );
+            assert_eq!(
+                asked,
+                (
+                    "Which database?".to_string(),
+                    vec![
+                        "Postgres".into(),
+                        "SQLite".into(),
+                        "Other".into(),
+                        "Skip".into()
+                    ]
+                )
+            );
+            let answer: Value = serde_json::from_str(&answer.expect("answer")).expect("json");
+            assert_eq!(answer, json!({ "answer": "SQLite" }));
+
+            // Without options the choices are yes and no; skipping answers null.
+            let (answer, asked) = tokio::join!(
+                ask(json!([])),
+                answer_next(&mut test.requests, Some("dismiss"))
+            );
+            assert_eq!(asked.1, vec!["Yes", "No", "Other", "Skip"]);
+            let answer: Value = serde_json::from_str(&answer.expect("answer")).expect("json");
+            assert_eq!(answer, json!({ "answer": null }));
+
+            let (answer, _) =
+                tokio::join!(ask(json!(["A"])), answer_next(&mut test.requests, None));
+            let answer: Value = serde_json::from_str(&answer.expect("answer")).expect("json");
+            assert_eq!(answer, json!({ "answer": null }));
+        })
+        .await;
+}
+
+/// Text the user typed comes back as the answer; `Other` without text says the
+/// user wants to reply in their own words.
+#[tokio::test]
+async fn ask_user_passes_free_text_answers_through() {
+    LocalSet::new()
+        .run_until(async {
+            let mut test = TestBridge::start(Workspace::new()).await;
+            let client = test.connect(SESSION);
+            let ask = || {
+                client.send(
+                    json!({
+                        "session_id": SESSION,
+                        "op": "ask_user",
+                        "args": { "question": "Which database?", "options": ["Postgres"] },
+                    }),
+                    Duration::from_secs(10),
+                )
+            };
+
+            let (answer, _) = tokio::join!(
+                ask(),
+                answer_next_with(
+                    &mut test.requests,
+                    Some("other"),
+                    Some(json!({ "answer": "  MySQL, for now " }))
+                )
+            );
+            let answer: Value = serde_json::from_str(&answer.expect("answer")).expect("json");
+            assert_eq!(answer, json!({ "answer": "MySQL, for now" }));
+
+            let (answer, _) = tokio::join!(ask(), answer_next(&mut test.requests, Some("other")));
+            let answer: Value = serde_json::from_str(&answer.expect("answer")).expect("json");
+            assert_eq!(answer, json!({ "answer": null, "other": true }));
+
+            // Blank text does not stand in for a picked choice.
+            let (answer, _) = tokio::join!(
+                ask(),
+                answer_next_with(
+                    &mut test.requests,
+                    Some("choice-0"),
+                    Some(json!({ "answer": " " }))
+                )
+            );
+            let answer: Value = serde_json::from_str(&answer.expect("answer")).expect("json");
+            assert_eq!(answer, json!({ "answer": "Postgres" }));
+        })
+        .await;
+}
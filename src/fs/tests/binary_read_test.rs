@@ -0,0 +1,56 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+
+use crate::fs::tools::binary_read::{
+    BinaryFile, MAX_BINARY_READ_BYTES, check_size, format_summary, sniff_mime_type,
+};
+
+/// Magic bytes win over the extension, which is the fallback.
+#[test]
+fn sniffs_mime_types() {
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    assert_eq!(sniff_mime_type(Path::new("logo.bin"), png), "image/png");
+    assert_eq!(
+        sniff_mime_type(Path::new("a.webp"), b"RIFF\0\0\0\0WEBPVP8 "),
+        "image/webp"
+    );
+    assert_eq!(
+        sniff_mime_type(Path::new("doc"), b"%PDF-1.7"),
+        "application/pdf"
+    );
+    assert_eq!(
+        sniff_mime_type(Path::new("icon.SVG"), b"<svg"),
+        "image/svg+xml"
+    );
+    assert_eq!(
+        sniff_mime_type(Path::new("blob"), &[0, 1, 2]),
+        "application/octet-stream"
+    );
+}
+
+/// Images are flagged for image content; everything is base64 of the whole file.
+#[test]
+fn encodes_whole_files() {
+    let bytes = b"GIF89a\x01\x00\x01\x00";
+    let file = BinaryFile::encode(Path::new("dot.gif"), bytes);
+    assert!(file.is_image());
+    assert_eq!(file.size, bytes.len() as u64);
+    assert_eq!(STANDARD.decode(&file.data).unwrap(), bytes);
+    assert!(format_summary("dot.gif", &file).contains("image/gif image"));
+
+    let file = BinaryFile::encode(Path::new("data.bin"), &[0xde, 0xad]);
+    assert!(!file.is_image());
+    assert!(format_summary("data.bin", &file).contains("base64"));
+}
+
+/// Files over the cap are refused with their size.
+#[test]
+fn refuses_large_files() {
+    assert!(check_size(Path::new("a"), MAX_BINARY_READ_BYTES).is_ok());
+    let err = check_size(Path::new("big.png"), MAX_BINARY_READ_BYTES + 1).unwrap_err();
+    assert!(err.contains("big.png"));
+    assert!(err.contains(&(MAX_BINARY_READ_BYTES + 1).to_string()));
+}
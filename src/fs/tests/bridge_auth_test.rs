@@ -0,0 +1,43 @@
+#![cfg(test)]
+
+use tokio::task::LocalSet;
+
+use super::support::{TestBridge, Workspace};
+
+#[tokio::test]
+async fn tokens_are_stable_per_session_and_not_shared() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::new()).await;
+            let bridge = &test.bridge;
+            let token = bridge.session_token("fs-a");
+            assert_eq!(bridge.session_token("fs-a"), token);
+            assert!(bridge.is_authorized("fs-a", &token));
+
+            // No token was issued for this session.
+            assert!(!bridge.is_authorized("fs-unknown", &token));
+            // Wrong or empty token.
+            assert!(!bridge.is_authorized("fs-a", "not-the-token"));
+            assert!(!bridge.is_authorized("fs-a", ""));
+            // Another session's token.
+            let other = bridge.session_token("fs-b");
+            assert_ne!(other, token);
+            assert!(!bridge.is_authorized("fs-a", &other));
+            assert!(!bridge.is_authorized("fs-b", &token));
+        })
+        .await;
+}
+
+#[tokio::test]
+async fn removed_sessions_lose_their_token() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::new()).await;
+            let bridge = &test.bridge;
+            let token = bridge.session_token("fs-a");
+            bridge.remove_session("fs-a");
+            assert!(!bridge.is_authorized("fs-a", &token));
+            assert_ne!(bridge.session_token("fs-a"), token);
+        })
+        .await;
+}
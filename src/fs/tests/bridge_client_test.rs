@@ -0,0 +1,76 @@
+#![cfg(test)]
+
+use std::time::Duration;
+
+use agent_client_protocol::{
+    PermissionOptionId, RequestPermissionOutcome, RequestPermissionResponse,
+};
+use serde_json::{Value, json};
+use tokio::task::{self, LocalSet};
+
+use super::support::{SESSION, TestBridge, Workspace};
+use crate::fs::ClientOp;
+
+/// One client keeps a single connection, and a request left waiting on the
+/// user does not hold up the requests sent after it.
+#[tokio::test]
+async fn slow_requests_do_not_block_the_shared_connection() {
+    LocalSet::new()
+        .run_until(async {
+            // Reads fall back to disk; questions wait for the test to answer.
+            let mut test =
+                TestBridge::start(Workspace::with_files(&[("notes.txt", "hello")])).await;
+            let file = test.workspace.path("notes.txt");
+            let client = test.connect(SESSION);
+
+            let asking = client.clone();
+            let ask = task::spawn_local(async move {
+                asking
+                    .send(
+                        json!({
+                            "session_id": SESSION,
+                            "op": "ask_user",
+                            "args": { "question": "Which database?", "options": ["Postgres"] },
+                        }),
+                        Duration::from_secs(10),
+                    )
+                    .await
+            });
+            let Some(ClientOp::RequestPermission {
+                response_tx: answer_tx,
+                ..
+            }) = test.requests.recv().await
+            else {
+                panic!("expected the question");
+            };
+
+            for _ in 0..3 {
+                let content = client
+                    .send(
+                        json!({
+                            "session_id": SESSION,
+                            "op": "read",
+                            "path": file.display().to_string(),
+                        }),
+                        Duration::from_secs(10),
+                    )
+                    .await
+                    .expect("read");
+                assert_eq!(content, "hello");
+            }
+            assert!(!ask.is_finished());
+            assert_eq!(test.bridge.connection_count(), 1);
+
+            let _ = answer_tx.send(Ok(RequestPermissionResponse {
+                outcome: RequestPermissionOutcome::Selected {
+                    option_id: PermissionOptionId("choice-0".into()),
+                },
+                meta: None,
+            }));
+            let answer = ask.await.expect("task").expect("answer");
+            let answer: Value = serde_json::from_str(&answer).expect("json");
+            assert_eq!(answer, json!({ "answer": "Postgres" }));
+            assert_eq!(test.bridge.connection_count(), 1);
+        })
+        .await;
+}
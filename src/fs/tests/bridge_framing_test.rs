@@ -0,0 +1,106 @@
+#![cfg(test)]
+
+use tokio::io::{AsyncWriteExt, BufReader};
+
+use crate::fs::framing::{
+    Framing, MAX_FRAME_BYTES, hello, parse_hello, read_message, write_message,
+};
+
+#[test]
+fn hello_round_trips() {
+    assert_eq!(
+        parse_hello(&hello(Framing::LengthPrefixed)),
+        Some(Framing::LengthPrefixed)
+    );
+    assert_eq!(parse_hello(&hello(Framing::Lines)), Some(Framing::Lines));
+    assert_eq!(
+        parse_hello(r#"{"bridge_hello":{"framing":"carrier_pigeon"}}"#),
+        Some(Framing::Lines)
+    );
+    assert_eq!(
+        parse_hello(r#"{"id":1,"session_id":"s","op":"read","path":"a"}"#),
+        None
+    );
+    assert_eq!(parse_hello("not json"), None);
+}
+
+#[tokio::test]
+async fn length_prefixed_frames_carry_newlines_and_large_payloads() {
+    let (mut client, server) = tokio::io::duplex(1024);
+    let big = format!(
+        "{{\"content\":{}}}",
+        serde_json::json!("x\n".repeat(100_000))
+    );
+    let writer = {
+        let big = big.clone();
+        tokio::spawn(async move {
+            write_message(
+                &mut client,
+                Framing::LengthPrefixed,
+                b"{\"a\":\"line\nbreak\"}",
+            )
+            .await
+            .unwrap();
+            write_message(&mut client, Framing::LengthPrefixed, big.as_bytes())
+                .await
+                .unwrap();
+        })
+    };
+    let mut reader = BufReader::new(server);
+    assert_eq!(
+        read_message(&mut reader, Framing::LengthPrefixed)
+            .await
+            .unwrap()
+            .as_deref(),
+        Some(&b"{\"a\":\"line\nbreak\"}"[..])
+    );
+    assert_eq!(
+        read_message(&mut reader, Framing::LengthPrefixed)
+            .await
+            .unwrap(),
+        Some(big.into_bytes())
+    );
+    writer.await.unwrap();
+    assert_eq!(
+        read_message(&mut reader, Framing::LengthPrefixed)
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn lines_skip_blank_lines_and_strip_line_endings() {
+    let (mut client, server) = tokio::io::duplex(1024);
+    client
+        .write_all(b"\n{\"a\":1}\r\n  \n{\"b\":2}\n")
+        .await
+        .unwrap();
+    drop(client);
+    let mut reader = BufReader::new(server);
+    assert_eq!(
+        read_message(&mut reader, Framing::Lines).await.unwrap(),
+        Some(b"{\"a\":1}".to_vec())
+    );
+    assert_eq!(
+        read_message(&mut reader, Framing::Lines).await.unwrap(),
+        Some(b"{\"b\":2}".to_vec())
+    );
+    assert_eq!(
+        read_message(&mut reader, Framing::Lines).await.unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn oversized_frames_are_rejected() {
+    let (mut client, server) = tokio::io::duplex(64);
+    let len = (MAX_FRAME_BYTES as u32 + 1).to_be_bytes();
+    client.write_all(&len).await.unwrap();
+    let mut reader = BufReader::new(server);
+    assert!(
+        read_message(&mut reader, Framing::LengthPrefixed)
+            .await
+            .is_err()
+    );
+}
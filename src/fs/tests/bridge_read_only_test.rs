@@ -0,0 +1,53 @@
+#![cfg(test)]
+
+use serde_json::json;
+use tokio::task::LocalSet;
+
+use super::support::{SESSION, TestBridge, Workspace, send};
+
+/// No bridge operation changes the disk while the session is read-only, even
+/// when the client cannot write and the bridge would fall back to writing
+/// locally.
+#[tokio::test]
+async fn read_only_sessions_cannot_change_files() {
+    LocalSet::new()
+        .run_until(async {
+            // The client can neither read nor write, so the bridge uses the disk.
+            let test = TestBridge::start(Workspace::with_files(&[("keep.txt", "original")])).await;
+            test.client.read_only.set(true);
+            let client = test.connect(SESSION);
+            let dir = &test.workspace.dir;
+            let existing = test.workspace.path("keep.txt");
+            let send = |op: &str, path: &std::path::Path, request| send(&client, op, path, request);
+
+            let created = dir.join("new.txt");
+            let moved = dir.join("moved.txt").display().to_string();
+            let refused = [
+                send("write", &created, json!({ "content": "new" })).await,
+                send("write", &existing, json!({ "content": "changed" })).await,
+                send("write_begin", &created, json!({ "args": { "size": 3 } })).await,
+                send("delete", &existing, json!({})).await,
+                send("create_directory", &dir.join("sub"), json!({})).await,
+                send(
+                    "move",
+                    &existing,
+                    json!({ "args": { "destination": moved } }),
+                )
+                .await,
+            ];
+            for result in refused {
+                let err = result.expect_err("read-only");
+                assert!(err.to_string().contains("read-only"), "{err}");
+            }
+            assert!(!created.exists());
+            assert!(!dir.join("sub").exists());
+            assert_eq!(std::fs::read_to_string(&existing).unwrap(), "original");
+
+            test.client.read_only.set(false);
+            send("write", &created, json!({ "content": "new" }))
+                .await
+                .expect("write");
+            assert_eq!(std::fs::read_to_string(&created).unwrap(), "new");
+        })
+        .await;
+}
@@ -0,0 +1,84 @@
+#![cfg(test)]
+
+use std::time::Duration;
+
+use crate::fs::tools::bridge_timeouts::{
+    ASK_USER_TIMEOUT, BridgeTimeouts, READ_TIMEOUT_ENV, TIMEOUT_PER_MIB_ENV, WRITE_TIMEOUT_ENV,
+    parse_secs,
+};
+
+#[test]
+fn parses_positive_whole_seconds() {
+    assert_eq!(parse_secs(" 30 "), Some(Duration::from_secs(30)));
+    assert_eq!(parse_secs("0"), None);
+    assert_eq!(parse_secs("-5"), None);
+    assert_eq!(parse_secs("1.5"), None);
+    assert_eq!(parse_secs("soon"), None);
+}
+
+#[test]
+fn write_timeout_grows_per_started_mib() {
+    let timeouts = BridgeTimeouts {
+        read: Duration::from_secs(10),
+        write: Duration::from_secs(60),
+        per_mib: Duration::from_secs(5),
+    };
+    assert_eq!(timeouts.write_timeout(0), Duration::from_secs(60));
+    assert_eq!(timeouts.write_timeout(1), Duration::from_secs(65));
+    assert_eq!(timeouts.write_timeout(1024 * 1024), Duration::from_secs(65));
+    assert_eq!(
+        timeouts.write_timeout(3 * 1024 * 1024 + 1),
+        Duration::from_secs(80)
+    );
+}
+
+#[test]
+fn write_timeout_is_capped() {
+    let timeouts = BridgeTimeouts::default();
+    assert_eq!(timeouts.write_timeout(usize::MAX), timeouts.longest());
+    assert!(timeouts.longest() >= timeouts.write);
+
+    let slow = BridgeTimeouts {
+        write: Duration::from_secs(7200),
+        ..timeouts
+    };
+    assert_eq!(
+        slow.write_timeout(100 * 1024 * 1024),
+        Duration::from_secs(7200)
+    );
+}
+
+#[test]
+fn forwards_limits_as_environment() {
+    let timeouts = BridgeTimeouts {
+        read: Duration::from_secs(15),
+        write: Duration::from_secs(120),
+        per_mib: Duration::from_secs(2),
+    };
+    assert_eq!(
+        timeouts.to_env(),
+        vec![
+            (READ_TIMEOUT_ENV.to_string(), "15".to_string()),
+            (WRITE_TIMEOUT_ENV.to_string(), "120".to_string()),
+            (TIMEOUT_PER_MIB_ENV.to_string(), "2".to_string()),
+        ]
+    );
+}
+
+/// A tool call outlasts every bridge request, so the helper reports timeouts.
+#[test]
+fn tool_timeout_follows_the_longest_request() {
+    let timeouts = BridgeTimeouts::default();
+    let tool = timeouts.tool_timeout();
+    assert!(tool > timeouts.write_timeout(usize::MAX));
+    assert!(tool > timeouts.list_timeout() * 2);
+    assert!(tool > ASK_USER_TIMEOUT);
+    assert!(tool - timeouts.longest() <= Duration::from_secs(60));
+
+    let slow = BridgeTimeouts {
+        write: Duration::from_secs(7200),
+        ..timeouts
+    };
+    assert!(slow.tool_timeout() > Duration::from_secs(7200));
+    assert!(slow.tool_timeout() < tool + Duration::from_secs(3600));
+}
@@ -0,0 +1,115 @@
+#![cfg(test)]
+
+use std::{path::PathBuf, time::Duration};
+
+use serde_json::json;
+use tokio::task::LocalSet;
+
+use super::support::{TestBridge, Workspace};
+use crate::fs::{
+    bridge::BridgeAddress, bridge_client::BridgeClient, tools::bridge_timeouts::BridgeTimeouts,
+};
+
+/// Addresses round-trip through the form passed in `ACP_FS_BRIDGE_ADDR`.
+#[test]
+fn addresses_parse_and_display() {
+    for address in [
+        "127.0.0.1:4000",
+        "unix:/tmp/codex/fs-a.sock",
+        r"pipe:\\.\pipe\codex-acp-fs-a",
+    ] {
+        let parsed = BridgeAddress::parse(address).expect("address");
+        assert_eq!(parsed.to_string(), address);
+    }
+    assert_eq!(
+        BridgeAddress::parse("unix:/run/a.sock").unwrap(),
+        BridgeAddress::Unix(PathBuf::from("/run/a.sock"))
+    );
+    assert!(BridgeAddress::parse("localhost").is_err());
+}
+
+/// Each session gets its own socket (a named pipe on Windows), which serves
+/// that session and refuses requests for any other.
+#[cfg(any(unix, windows))]
+#[tokio::test]
+async fn session_sockets_serve_only_their_session() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::with_files(&[("notes.txt", "hello")])).await;
+            let bridge = &test.bridge;
+            let file = test.workspace.path("notes.txt");
+
+            let address = bridge.session_address("fs-a");
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+
+                let socket_dir = test.workspace.path("acp/fs-bridge");
+                assert_eq!(address, BridgeAddress::Unix(socket_dir.join("fs-a.sock")));
+                let mode = std::fs::metadata(&socket_dir)
+                    .expect("socket dir")
+                    .permissions()
+                    .mode();
+                assert_eq!(mode & 0o777, 0o700);
+            }
+            #[cfg(windows)]
+            assert_eq!(
+                address,
+                BridgeAddress::Pipe(r"\\.\pipe\codex-acp-fs-fs-a".to_string())
+            );
+
+            let read = |session_id: &str| {
+                let client = BridgeClient::new(
+                    address.clone(),
+                    bridge.session_token(session_id),
+                    BridgeTimeouts::default(),
+                );
+                let request = json!({
+                    "session_id": session_id,
+                    "op": "read",
+                    "path": file.display().to_string(),
+                });
+                async move { client.send(request, Duration::from_secs(10)).await }
+            };
+            assert_eq!(read("fs-a").await.expect("read"), "hello");
+
+            // A valid token for another session does not open this socket.
+            let err = read("fs-b").await.expect_err("other session");
+            assert!(err.to_string().contains("different session"), "{err}");
+        })
+        .await;
+}
+
+/// A session keeps one socket however often its address is asked for, and
+/// removing the session closes it.
+#[cfg(unix)]
+#[tokio::test]
+async fn removed_sessions_close_their_socket() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::with_files(&[("notes.txt", "hello")])).await;
+            let bridge = &test.bridge;
+            let address = bridge.session_address("fs-a");
+            assert_eq!(bridge.session_address("fs-a"), address);
+            let BridgeAddress::Unix(socket) = &address else {
+                panic!("expected a unix socket, got {address}");
+            };
+            let client = test.connect("fs-a");
+            let request = json!({
+                "session_id": "fs-a",
+                "op": "read",
+                "path": test.workspace.path("notes.txt").display().to_string(),
+            });
+            client
+                .send(request, Duration::from_secs(10))
+                .await
+                .expect("read");
+
+            bridge.remove_session("fs-a");
+            assert!(!socket.exists());
+            tokio::net::UnixStream::connect(socket)
+                .await
+                .expect_err("socket closed");
+        })
+        .await;
+}
@@ -0,0 +1,78 @@
+#![cfg(test)]
+
+use std::time::{Duration, Instant};
+
+use crate::fs::tools::chunked_write::{MAX_UPLOAD_BYTES, UPLOAD_TTL, Uploads, split_chunks};
+
+#[test]
+fn chunks_respect_the_size_and_character_boundaries() {
+    assert_eq!(split_chunks("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    assert!(split_chunks("", 4).is_empty());
+
+    let text = "aéé€€😀b".repeat(50);
+    let chunks = split_chunks(&text, 7);
+    assert!(
+        chunks
+            .iter()
+            .all(|chunk| chunk.len() <= 7 && !chunk.is_empty())
+    );
+    assert_eq!(chunks.concat(), text);
+}
+
+#[test]
+fn assembles_chunks_in_order() {
+    let mut uploads = Uploads::default();
+    let now = Instant::now();
+    let id = uploads.begin("s1", "big.txt", 6, now).unwrap();
+    assert_eq!(uploads.append("s1", &id, 0, "abc"), Ok(3));
+    assert_eq!(uploads.append("s1", &id, 3, "def"), Ok(6));
+    assert_eq!(
+        uploads.finish("s1", &id),
+        Ok(("big.txt".to_string(), "abcdef".to_string()))
+    );
+    assert!(uploads.finish("s1", &id).is_err());
+}
+
+#[test]
+fn rejects_out_of_order_overlong_and_incomplete_uploads() {
+    let mut uploads = Uploads::default();
+    let id = uploads.begin("s1", "f", 4, Instant::now()).unwrap();
+    assert!(uploads.append("s1", &id, 2, "ab").is_err());
+    assert!(uploads.append("s1", &id, 0, "abcde").is_err());
+    uploads.append("s1", &id, 0, "ab").unwrap();
+    assert!(uploads.finish("s1", &id).is_err());
+    // A failed commit keeps the upload so it can still be completed.
+    uploads.append("s1", &id, 2, "cd").unwrap();
+    assert!(uploads.finish("s1", &id).is_ok());
+
+    assert!(
+        uploads
+            .begin("s1", "f", MAX_UPLOAD_BYTES + 1, Instant::now())
+            .is_err()
+    );
+}
+
+#[test]
+fn uploads_belong_to_their_session() {
+    let mut uploads = Uploads::default();
+    let id = uploads.begin("s1", "f", 2, Instant::now()).unwrap();
+    assert!(uploads.append("s2", &id, 0, "ab").is_err());
+    assert!(!uploads.abort("s2", &id));
+    assert!(uploads.abort("s1", &id));
+    assert!(uploads.append("s1", &id, 0, "ab").is_err());
+}
+
+#[test]
+fn limits_open_uploads_and_expires_stale_ones() {
+    let mut uploads = Uploads::default();
+    let start = Instant::now();
+    let ids: Vec<String> = (0..4)
+        .map(|_| uploads.begin("s1", "f", 1, start).unwrap())
+        .collect();
+    assert!(uploads.begin("s1", "f", 1, start).is_err());
+    assert!(uploads.begin("s2", "f", 1, start).is_ok());
+
+    let later = start + UPLOAD_TTL + Duration::from_secs(1);
+    assert!(uploads.begin("s1", "f", 1, later).is_ok());
+    assert!(uploads.append("s1", &ids[0], 0, "x").is_err());
+}
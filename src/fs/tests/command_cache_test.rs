@@ -0,0 +1,141 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use tokio::task::LocalSet;
+
+use super::support::{SESSION, TestBridge, Workspace, send};
+use crate::fs::tools::command_cache::{
+    CommandCache, CommandOutput, command_cache_from_meta, format_output, is_cacheable,
+};
+
+fn cmd(words: &[&str]) -> Vec<String> {
+    words.iter().map(|w| w.to_string()).collect()
+}
+
+fn output(exit_code: Option<i32>, text: &str) -> CommandOutput {
+    CommandOutput {
+        exit_code,
+        output: text.to_string(),
+        cached: false,
+    }
+}
+
+#[test]
+fn only_read_only_commands_are_cacheable() {
+    assert!(is_cacheable(&cmd(&["rg", "TODO", "src"])));
+    assert!(is_cacheable(&cmd(&["/bin/ls", "-la"])));
+    assert!(is_cacheable(&cmd(&["git", "diff", "--stat"])));
+    assert!(!is_cacheable(&cmd(&[])));
+    assert!(!is_cacheable(&cmd(&["tail", "-f", "log.txt"])));
+    assert!(!is_cacheable(&cmd(&["find", ".", "-delete"])));
+    assert!(!is_cacheable(&cmd(&["rg", "--pre=./run.sh", "x"])));
+    assert!(!is_cacheable(&cmd(&["git", "diff", "--output=out.patch"])));
+    assert!(!is_cacheable(&cmd(&["tree", "-o", "listing.txt"])));
+    assert!(!is_cacheable(&cmd(&["cargo", "test"])));
+    assert!(!is_cacheable(&cmd(&["git", "commit", "-m", "x"])));
+}
+
+#[test]
+fn reuses_results_until_the_workspace_changes() {
+    let cwd = PathBuf::from("/repo");
+    let rg = cmd(&["rg", "foo"]);
+    let mut cache = CommandCache::default();
+    let generation = cache.generation();
+    cache.store(
+        rg.clone(),
+        cwd.clone(),
+        generation,
+        output(Some(0), "src/lib.rs:1:foo"),
+    );
+
+    let hit = cache.lookup(&rg, &cwd).expect("cached");
+    assert!(hit.cached);
+    assert_eq!(hit.output, "src/lib.rs:1:foo");
+    assert!(format_output(&rg, &hit).starts_with("$ rg foo (exit code 0; reused"));
+    assert!(cache.lookup(&rg, Path::new("/other")).is_none());
+
+    cache.invalidate();
+    assert!(cache.lookup(&rg, &cwd).is_none());
+
+    // Output of a command the workspace changed under, or that was killed, is dropped.
+    cache.store(
+        rg.clone(),
+        cwd.clone(),
+        generation,
+        output(Some(0), "stale"),
+    );
+    cache.store(
+        rg.clone(),
+        cwd.clone(),
+        cache.generation(),
+        output(None, ""),
+    );
+    assert!(cache.lookup(&rg, &cwd).is_none());
+}
+
+#[test]
+fn reads_the_session_switch() {
+    assert_eq!(command_cache_from_meta(None).unwrap(), None);
+    assert_eq!(
+        command_cache_from_meta(Some(&json!({ "commandCache": true }))).unwrap(),
+        Some(true)
+    );
+    assert!(command_cache_from_meta(Some(&json!({ "commandCache": "yes" }))).is_err());
+}
+
+/// The bridge answers a repeated command from the cache until a write.
+#[cfg(unix)]
+#[tokio::test]
+async fn bridge_reuses_command_output_until_a_write() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::with_files(&[("a.txt", "a")])).await;
+            let client = &test.connect(SESSION);
+            let dir = &test.workspace.dir;
+            let ls = || async move {
+                let response = send(
+                    client,
+                    "run_command",
+                    dir,
+                    json!({ "args": { "command": ["ls"] } }),
+                )
+                .await
+                .expect("run_command");
+                serde_json::from_str::<CommandOutput>(&response).expect("output")
+            };
+
+            let first = ls().await;
+            assert_eq!(first.exit_code, Some(0));
+            assert!(!first.cached);
+            assert_eq!(first.output, "a.txt\n");
+            assert!(ls().await.cached);
+
+            send(
+                client,
+                "write",
+                &test.workspace.path("b.txt"),
+                json!({ "content": "b" }),
+            )
+            .await
+            .expect("write");
+            let after = ls().await;
+            assert!(!after.cached);
+            assert_eq!(after.output, "a.txt\nb.txt\n");
+
+            test.bridge.invalidate_commands(SESSION);
+            assert!(!ls().await.cached);
+
+            let refused = send(
+                client,
+                "run_command",
+                dir,
+                json!({ "args": { "command": ["rm", "a.txt"] } }),
+            )
+            .await;
+            assert!(refused.is_err());
+            assert!(test.workspace.path("a.txt").exists());
+        })
+        .await;
+}
@@ -0,0 +1,174 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use agent_client_protocol::{
+    PermissionOptionId, RequestPermissionOutcome, RequestPermissionResponse, SessionId,
+    ToolCallContent,
+};
+use rmcp::handler::server::wrapper::Parameters;
+use serde_json::json;
+use tokio::task::LocalSet;
+
+use super::support::{TestBridge, Workspace};
+use crate::fs::{
+    ClientOp,
+    tools::conflicts::{
+        Resolution, conflict_request, is_conflict, patch_conflict_request, rebase, resolution,
+    },
+};
+
+const BASE: &str = "one\ntwo\nthree\nfour\nfive\n";
+
+/// Only a file the user changed, and not to the agent's content, conflicts.
+#[test]
+fn detects_user_changes() {
+    let incoming = "one\ntwo\nthree\nfour\nFIVE\n";
+    assert!(!is_conflict(BASE, BASE, incoming));
+    assert!(!is_conflict(BASE, incoming, incoming));
+    assert!(is_conflict(BASE, "ONE\ntwo\nthree\nfour\nfive\n", incoming));
+}
+
+/// Rebasing keeps both sides' changes to different lines.
+#[test]
+fn rebase_merges_disjoint_edits() {
+    let user = "ONE\ntwo\nthree\nfour\nfive\n";
+    let agent = "one\ntwo\nthree\nfour\nFIVE\n";
+    assert_eq!(
+        rebase(BASE, user, agent).unwrap(),
+        "ONE\ntwo\nthree\nfour\nFIVE\n"
+    );
+}
+
+/// Overlapping edits cannot be rebased.
+#[test]
+fn rebase_fails_on_overlap() {
+    let user = "one\nTWO\nthree\nfour\nfive\n";
+    let agent = "one\n2\nthree\nfour\nfive\n";
+    assert!(rebase(BASE, user, agent).is_err());
+}
+
+/// The prompt shows both diffs and offers overwrite / rebase / abort.
+#[test]
+fn request_offers_three_resolutions() {
+    let request = conflict_request(
+        &SessionId("s".into()),
+        "call",
+        Path::new("/repo/src/lib.rs"),
+        BASE,
+        "user\n",
+        "agent\n",
+    );
+    let content = request.tool_call.fields.content.unwrap();
+    assert_eq!(content.len(), 2);
+    assert!(matches!(&content[0], ToolCallContent::Diff { diff } if diff.new_text == "user\n"));
+    assert!(matches!(&content[1], ToolCallContent::Diff { diff } if diff.new_text == "agent\n"));
+
+    let pick = |index: usize| RequestPermissionResponse {
+        outcome: RequestPermissionOutcome::Selected {
+            option_id: request.options[index].id.clone(),
+        },
+        meta: None,
+    };
+    assert_eq!(resolution(&pick(0)), Resolution::Overwrite);
+    assert_eq!(resolution(&pick(1)), Resolution::Rebase);
+    assert_eq!(resolution(&pick(2)), Resolution::Abort);
+}
+
+/// Before a Codex patch, only the user's diff is shown, and the patch can
+/// only go on top of it or be aborted.
+#[test]
+fn patch_request_offers_rebase_or_abort() {
+    let request = patch_conflict_request(
+        &SessionId("s".into()),
+        "call",
+        Path::new("/repo/src/lib.rs"),
+        BASE,
+        "user\n",
+    );
+    let content = request.tool_call.fields.content.unwrap();
+    assert_eq!(content.len(), 1);
+    assert!(matches!(&content[0], ToolCallContent::Diff { diff } if diff.new_text == "user\n"));
+
+    let pick = |index: usize| RequestPermissionResponse {
+        outcome: RequestPermissionOutcome::Selected {
+            option_id: request.options[index].id.clone(),
+        },
+        meta: None,
+    };
+    assert_eq!(request.options.len(), 2);
+    assert_eq!(resolution(&pick(0)), Resolution::Rebase);
+    assert_eq!(resolution(&pick(1)), Resolution::Abort);
+}
+
+/// Cancelled or unknown answers abort the write.
+#[test]
+fn unknown_answers_abort() {
+    let cancelled = RequestPermissionResponse {
+        outcome: RequestPermissionOutcome::Cancelled,
+        meta: None,
+    };
+    assert_eq!(resolution(&cancelled), Resolution::Abort);
+    let other = RequestPermissionResponse {
+        outcome: RequestPermissionOutcome::Selected {
+            option_id: PermissionOptionId("allow".into()),
+        },
+        meta: None,
+    };
+    assert_eq!(resolution(&other), Resolution::Abort);
+}
+
+/// A file the agent read with `read_text_file`, which reads a range of
+/// lines, and the user changed since is not overwritten without asking.
+#[tokio::test]
+async fn writes_after_a_read_ask_about_user_changes() {
+    LocalSet::new()
+        .run_until(async {
+            let mut test = TestBridge::start(Workspace::with_files(&[("notes.txt", BASE)])).await;
+            let tools = test.tools();
+            let file = test.workspace.path("notes.txt");
+            let path = file.display().to_string();
+
+            tools
+                .read_text_file(Parameters(
+                    serde_json::from_value(json!({ "path": path })).expect("args"),
+                ))
+                .await
+                .expect("read");
+            std::fs::write(&file, "ONE\ntwo\nthree\nfour\nfive\n").expect("user edit");
+
+            let write = tools.write_text_file(Parameters(
+                serde_json::from_value(json!({ "path": path, "content": "agent\n" }))
+                    .expect("args"),
+            ));
+            let answer = async {
+                let Some(ClientOp::RequestPermission {
+                    request,
+                    response_tx,
+                    ..
+                }) = test.requests.recv().await
+                else {
+                    panic!("expected a conflict request");
+                };
+                assert!(
+                    request
+                        .options
+                        .iter()
+                        .any(|o| o.id.0.as_ref() == "conflict-abort")
+                );
+                let _ = response_tx.send(Ok(RequestPermissionResponse {
+                    outcome: RequestPermissionOutcome::Selected {
+                        option_id: PermissionOptionId("conflict-abort".into()),
+                    },
+                    meta: None,
+                }));
+            };
+            let (result, ()) = tokio::join!(write, answer);
+            result.expect_err("aborted");
+            assert_eq!(
+                std::fs::read_to_string(&file).unwrap(),
+                "ONE\ntwo\nthree\nfour\nfive\n"
+            );
+        })
+        .await;
+}
@@ -0,0 +1,93 @@
+#![cfg(test)]
+
+use rmcp::handler::server::wrapper::Parameters;
+use serde_json::json;
+use tokio::task::{self, LocalSet};
+
+use super::support::{SESSION, TestBridge, Workspace, send};
+use crate::fs::bridge::DRY_RUN_PREFIX;
+
+/// A dry-run write reports success but creates neither the file nor its
+/// missing parent directories, and records nothing for `/undo` or the audit
+/// log.
+#[tokio::test]
+async fn dry_run_writes_leave_the_disk_alone() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::new()).await;
+            test.client.dry_run.set(true);
+            test.client.swallow_writes.set(true);
+            let client = test.connect(SESSION);
+            let nested = test.workspace.path("new/dir/file.txt");
+
+            send(&client, "write", &nested, json!({ "content": "new" }))
+                .await
+                .expect("simulated write");
+            task::yield_now().await;
+            assert!(!test.workspace.path("new").exists());
+            assert!(test.client.undo.borrow().is_empty());
+            assert!(test.client.audit.borrow().is_empty());
+        })
+        .await;
+}
+
+/// A dry-run write answers with the diff it would make, without the
+/// conflict prompt a real write over a changed file gets.
+#[tokio::test]
+async fn dry_run_writes_return_their_diff_without_asking() {
+    LocalSet::new()
+        .run_until(async {
+            let mut test =
+                TestBridge::start(Workspace::with_files(&[("notes.txt", "alpha\n")])).await;
+            let client = test.connect(SESSION);
+            let file = test.workspace.path("notes.txt");
+            send(&client, "read", &file, json!({})).await.expect("read");
+            std::fs::write(&file, "changed\n").expect("concurrent edit");
+            test.client.dry_run.set(true);
+
+            let answer = send(&client, "write", &file, json!({ "content": "beta\n" }))
+                .await
+                .expect("simulated write");
+            assert!(answer.starts_with(DRY_RUN_PREFIX), "{answer}");
+            assert!(answer.contains("-changed\n"), "{answer}");
+            assert!(answer.contains("+beta\n"), "{answer}");
+            assert!(test.requests.try_recv().is_err());
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), "changed\n");
+
+            let dir = test.workspace.path("new/dir");
+            let answer = send(&client, "create_directory", &dir, json!({}))
+                .await
+                .expect("simulated create_directory");
+            assert!(answer.starts_with(DRY_RUN_PREFIX), "{answer}");
+            assert!(!test.workspace.path("new").exists());
+            assert!(test.client.audit.borrow().is_empty());
+        })
+        .await;
+}
+
+/// Consecutive dry-run edits each apply to the unchanged file instead of
+/// failing as if it had changed on disk.
+#[tokio::test]
+async fn dry_run_edits_can_follow_each_other() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::with_files(&[("notes.txt", "alpha\n")])).await;
+            test.client.dry_run.set(true);
+            test.client.swallow_writes.set(true);
+            let tools = test.tools();
+            let file = test.workspace.path("notes.txt");
+            let edit = |new: &str| {
+                let args = json!({
+                    "path": file.display().to_string(),
+                    "old_string": "alpha",
+                    "new_string": new,
+                });
+                tools.edit_text_file(Parameters(serde_json::from_value(args).expect("args")))
+            };
+
+            edit("beta").await.expect("first edit");
+            edit("gamma").await.expect("second edit");
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), "alpha\n");
+        })
+        .await;
+}
@@ -0,0 +1,61 @@
+#![cfg(test)]
+
+use crate::fs::tools::edit_match::{MatchError, MatchKind, replace_once};
+
+#[test]
+fn prefers_verbatim_matches() {
+    let (content, kind) = replace_once("let a = 1;\nlet b = 2;\n", "b = 2", "b = 3").unwrap();
+    assert_eq!(content, "let a = 1;\nlet b = 3;\n");
+    assert_eq!(kind, MatchKind::Exact);
+}
+
+/// Indentation and trailing whitespace differences still match, and the
+/// replacement is re-indented to the file's indentation.
+#[test]
+fn matches_lines_ignoring_whitespace() {
+    let file = "fn main() {\n    if ready {  \n        go();\n    }\n}\n";
+    let old = "if ready {\n    go();\n}\n";
+    let new = "if ready {\n    go_now();\n}\n";
+    let (content, kind) = replace_once(file, old, new).unwrap();
+    assert_eq!(kind, MatchKind::Whitespace);
+    assert_eq!(
+        content,
+        "fn main() {\n    if ready {\n        go_now();\n    }\n}\n"
+    );
+
+    let (content, _) = replace_once("a\r\n  b\r\nc\r\n", "a\nb\n", "x\ny\n").unwrap();
+    assert_eq!(content, "x\r\ny\r\nc\r\n");
+}
+
+#[test]
+fn matches_close_blocks_anchored_on_first_and_last_lines() {
+    let file = "start\none\ntwo\nthree // changed\nfour\nend\nrest\n";
+    let old = "start\none\ntwo\nthree\nfour\nend";
+    let (content, kind) = replace_once(file, old, "replaced").unwrap();
+    assert_eq!(kind, MatchKind::Fuzzy);
+    assert_eq!(content, "replaced\nrest\n");
+
+    // Too different in between.
+    let file = "start\nx\ny\nz\nend\n";
+    assert_eq!(
+        replace_once(file, "start\na\nb\nc\nend", "replaced"),
+        Err(MatchError::NotFound)
+    );
+}
+
+#[test]
+fn refuses_ambiguous_fallback_matches() {
+    let file = "  call();\nother();\n    call();\n";
+    assert_eq!(
+        replace_once(file, "call( );", "x();"),
+        Err(MatchError::NotFound)
+    );
+    assert_eq!(
+        replace_once(file, " call();\n", "x();\n").map(|(_, kind)| kind),
+        Ok(MatchKind::Exact)
+    );
+    assert_eq!(
+        replace_once(file, "\tcall();\n", "x();\n"),
+        Err(MatchError::Ambiguous(2))
+    );
+}
@@ -0,0 +1,111 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use rmcp::{
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+    model::{CallToolResult, RawContent},
+};
+use serde_json::json;
+use tokio::task::LocalSet;
+
+use super::support::{TestBridge, Workspace};
+use crate::fs::mcp_server::FsTools;
+
+async fn edit(
+    tools: &FsTools,
+    path: &Path,
+    old: &str,
+    new: &str,
+    preview: bool,
+) -> Result<CallToolResult, McpError> {
+    let args = json!({
+        "path": path.display().to_string(),
+        "old_string": old,
+        "new_string": new,
+        "preview": preview,
+    });
+    tools
+        .edit_text_file(Parameters(serde_json::from_value(args).expect("args")))
+        .await
+}
+
+fn texts(result: &CallToolResult) -> Vec<String> {
+    result
+        .content
+        .iter()
+        .filter_map(|content| match &content.raw {
+            RawContent::Text(text) => Some(text.text.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// An edit refuses to build on staged content once the file changed on
+/// disk, and applies again after that.
+#[tokio::test]
+async fn edits_refuse_files_changed_since_the_last_edit() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::with_files(&[("notes.txt", "alpha\n")])).await;
+            let tools = test.tools();
+            let file = test.workspace.path("notes.txt");
+
+            edit(&tools, &file, "alpha", "beta", false)
+                .await
+                .expect("edit");
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), "beta\n");
+
+            // The user rewrites the file behind the staged edit.
+            std::fs::write(&file, "gamma\n").expect("user edit");
+            let err = edit(&tools, &file, "beta", "delta", false)
+                .await
+                .expect_err("stale");
+            assert_eq!(
+                err.data,
+                Some(json!({
+                    "reason": "file_changed_on_disk",
+                    "path": file.display().to_string(),
+                }))
+            );
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), "gamma\n");
+
+            // The stale staging is gone, so an edit of what is on disk applies.
+            edit(&tools, &file, "gamma", "delta", false)
+                .await
+                .expect("edit");
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), "delta\n");
+        })
+        .await;
+}
+
+/// A preview returns the diff but neither writes nor stages the edit.
+#[tokio::test]
+async fn previews_write_nothing() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::with_files(&[("notes.txt", "alpha\n")])).await;
+            let tools = test.tools();
+            let file = test.workspace.path("notes.txt");
+
+            let result = edit(&tools, &file, "alpha", "beta", true)
+                .await
+                .expect("preview");
+            let texts = texts(&result);
+            assert!(
+                texts[0].contains("-alpha") && texts[0].contains("+beta"),
+                "{texts:?}"
+            );
+            assert!(texts[1].starts_with("Preview only"), "{texts:?}");
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), "alpha\n");
+
+            // Nothing was staged, so a change on disk after it is no conflict.
+            std::fs::write(&file, "gamma\n").expect("user edit");
+            edit(&tools, &file, "gamma", "delta", false)
+                .await
+                .expect("edit");
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), "delta\n");
+        })
+        .await;
+}
@@ -0,0 +1,81 @@
+#![cfg(test)]
+
+use std::time::{Duration, SystemTime};
+
+use super::support::Workspace;
+use crate::fs::tools::{
+    file_info::{FileInfo, file_info, format_info, looks_binary},
+    listing::EntryKind,
+};
+
+#[test]
+fn binary_sniffing_tolerates_a_cut_off_character() {
+    assert!(!looks_binary(b"plain text\n"));
+    assert!(looks_binary(b"abc\0def"));
+    assert!(looks_binary(&[0xff, 0xfe, b'a']));
+    let euro = "€".as_bytes();
+    assert!(!looks_binary(&[b'a', euro[0], euro[1]]));
+}
+
+#[tokio::test]
+async fn reports_text_files_with_line_counts() {
+    let workspace = Workspace::new();
+    let dir = workspace.dir.clone();
+    std::fs::write(dir.join("a.txt"), "one\ntwo\nthree").unwrap();
+    std::fs::write(dir.join("b.txt"), "one\ntwo\n").unwrap();
+    std::fs::write(dir.join("empty.txt"), "").unwrap();
+
+    let info = file_info(&dir.join("a.txt")).await.unwrap();
+    assert!(info.exists);
+    assert_eq!(info.kind, Some(EntryKind::File));
+    assert_eq!(info.size, Some(13));
+    assert_eq!(info.binary, Some(false));
+    assert_eq!(info.lines, Some(3));
+    assert!(info.modified.is_some());
+
+    assert_eq!(file_info(&dir.join("b.txt")).await.unwrap().lines, Some(2));
+    assert_eq!(
+        file_info(&dir.join("empty.txt")).await.unwrap().lines,
+        Some(0)
+    );
+}
+
+#[tokio::test]
+async fn reports_binaries_directories_and_missing_paths() {
+    let workspace = Workspace::new();
+    let dir = workspace.dir.clone();
+    std::fs::write(dir.join("img.png"), b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+
+    let info = file_info(&dir.join("img.png")).await.unwrap();
+    assert_eq!(info.binary, Some(true));
+    assert_eq!(info.lines, None);
+
+    let info = file_info(&dir).await.unwrap();
+    assert_eq!(info.kind, Some(EntryKind::Directory));
+    assert_eq!(info.size, None);
+    assert_eq!(info.binary, None);
+
+    let info = file_info(&dir.join("missing")).await.unwrap();
+    assert!(!info.exists);
+    assert_eq!(
+        format_info("missing", &info, SystemTime::now()),
+        "missing: does not exist\n"
+    );
+}
+
+#[test]
+fn formats_one_fact_per_line() {
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(10_000);
+    let info = FileInfo {
+        exists: true,
+        kind: Some(EntryKind::File),
+        size: Some(2048),
+        modified: Some(10_000 - 7200),
+        binary: Some(false),
+        lines: Some(40),
+    };
+    assert_eq!(
+        format_info("src/lib.rs", &info, now),
+        "src/lib.rs: file\nsize: 2048 bytes\nlines: 40\nbinary: no\nmodified: 2h ago\n"
+    );
+}
@@ -0,0 +1,150 @@
+#![cfg(test)]
+
+use std::time::{Duration, SystemTime};
+
+use super::support::Workspace;
+use crate::fs::tools::glob_files::{GlobArgs, GlobMatch, GlobResults, format_results, glob_files};
+
+fn temp_tree() -> Workspace {
+    let tree = Workspace::new();
+    let now = SystemTime::now();
+    for (age, file) in [
+        (0, ".gitignore"),
+        (30, "src/old.rs"),
+        (10, "src/nested/new.rs"),
+        (20, "src/nested/mid.rs"),
+        (5, "src/readme.md"),
+        (1, "build.rs"),
+        (1, "target/gen.rs"),
+        (1, ".git/hooks.rs"),
+    ] {
+        let content = if file == ".gitignore" {
+            "target/\n"
+        } else {
+            ""
+        };
+        let path = tree.write(file, content);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(now - Duration::from_secs(age * 60))
+            .unwrap();
+    }
+    tree
+}
+
+fn args(pattern: &str) -> GlobArgs {
+    GlobArgs {
+        pattern: pattern.to_string(),
+        ..Default::default()
+    }
+}
+
+fn paths(results: &GlobResults) -> Vec<&str> {
+    results.matches.iter().map(|m| m.path.as_str()).collect()
+}
+
+#[tokio::test]
+async fn matches_paths_newest_first() {
+    let tree = temp_tree();
+    let root = tree.dir.clone();
+    let results = glob_files(&root, &root, &args("src/**/*.rs"))
+        .await
+        .unwrap();
+    assert_eq!(
+        paths(&results),
+        vec!["src/nested/new.rs", "src/nested/mid.rs", "src/old.rs"]
+    );
+    assert_eq!(results.total, 3);
+    assert!(results.complete);
+    assert!(results.matches[0].modified > results.matches[1].modified);
+}
+
+#[tokio::test]
+async fn bare_names_match_at_any_depth_and_skip_ignored() {
+    let tree = temp_tree();
+    let root = tree.dir.clone();
+    let results = glob_files(&root, &root, &args("*.rs")).await.unwrap();
+    assert_eq!(
+        paths(&results),
+        vec![
+            "build.rs",
+            "src/nested/new.rs",
+            "src/nested/mid.rs",
+            "src/old.rs"
+        ]
+    );
+
+    let results = glob_files(
+        &root,
+        &root,
+        &GlobArgs {
+            include_ignored: true,
+            ..args("*.rs")
+        },
+    )
+    .await
+    .unwrap();
+    assert!(paths(&results).contains(&"target/gen.rs"));
+    assert!(!paths(&results).iter().any(|p| p.starts_with(".git/")));
+}
+
+#[tokio::test]
+async fn patterns_are_relative_to_the_given_directory() {
+    let tree = temp_tree();
+    let root = tree.dir.clone();
+    let results = glob_files(&root, &root.join("src"), &args("nested/m*.rs"))
+        .await
+        .unwrap();
+    assert_eq!(paths(&results), vec!["nested/mid.rs"]);
+}
+
+#[tokio::test]
+async fn limit_keeps_the_newest_and_reports_the_total() {
+    let tree = temp_tree();
+    let root = tree.dir.clone();
+    let results = glob_files(
+        &root,
+        &root,
+        &GlobArgs {
+            limit: Some(2),
+            ..args("**/*.rs")
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(paths(&results), vec!["build.rs", "src/nested/new.rs"]);
+    assert_eq!(results.total, 4);
+    assert!(format_results(&results).contains("most recently modified of 4 matches"));
+
+    assert!(glob_files(&root, &root, &args("  ")).await.is_err());
+}
+
+#[test]
+fn formats_one_path_per_line() {
+    let results = GlobResults {
+        matches: vec![
+            GlobMatch {
+                path: "a.rs".to_string(),
+                modified: Some(2),
+                size: Some(1),
+            },
+            GlobMatch {
+                path: "b/c.rs".to_string(),
+                modified: None,
+                size: None,
+            },
+        ],
+        total: 2,
+        complete: true,
+    };
+    assert_eq!(format_results(&results), "a.rs\nb/c.rs\n");
+
+    let empty = GlobResults {
+        matches: vec![],
+        total: 0,
+        complete: true,
+    };
+    assert_eq!(format_results(&empty), "No files matched.\n");
+}
@@ -0,0 +1,47 @@
+#![cfg(test)]
+
+use std::io::ErrorKind;
+
+use crate::fs::tools::line_range::{MAX_RANGE_BYTES, read_line_range};
+
+async fn range(content: &[u8], start: u32, limit: Option<u32>, max_bytes: usize) -> String {
+    let mut reader = content;
+    read_line_range(&mut reader, start, limit, max_bytes)
+        .await
+        .unwrap()
+}
+
+/// Lines come back without endings, joined with `\n`.
+#[tokio::test]
+async fn reads_the_requested_lines() {
+    let content = b"one\r\ntwo\nthree\nfour";
+    assert_eq!(
+        range(content, 2, Some(2), MAX_RANGE_BYTES).await,
+        "two\nthree"
+    );
+    assert_eq!(
+        range(content, 3, None, MAX_RANGE_BYTES).await,
+        "three\nfour"
+    );
+    assert_eq!(range(content, 1, Some(1), MAX_RANGE_BYTES).await, "one");
+    assert_eq!(range(content, 9, Some(5), MAX_RANGE_BYTES).await, "");
+}
+
+/// Reading stops once the byte cap is passed.
+#[tokio::test]
+async fn stops_at_the_byte_cap() {
+    let content = b"aaaa\nbbbb\ncccc\ndddd\n";
+    assert_eq!(range(content, 1, None, 6).await, "aaaa\nbbbb");
+}
+
+/// Skipped lines are not decoded; kept ones must be UTF-8.
+#[tokio::test]
+async fn only_decodes_kept_lines() {
+    let content = b"\xff\xfe\nok\n";
+    assert_eq!(range(content, 2, Some(1), MAX_RANGE_BYTES).await, "ok");
+    let mut reader = &content[..];
+    let err = read_line_range(&mut reader, 1, Some(1), MAX_RANGE_BYTES)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
@@ -0,0 +1,147 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use super::support::Workspace;
+use crate::fs::tools::listing::{
+    DirEntry, EntryKind, IgnoreRules, ListArgs, Listing, format_listing, list_directory,
+};
+
+fn temp_tree() -> Workspace {
+    let tree = Workspace::new();
+    std::fs::create_dir_all(tree.path("target/debug")).unwrap();
+    for (file, content) in [
+        (".gitignore", "target/\n*.log\n!keep.log\n"),
+        ("src/main.rs", "fn main() {}\n"),
+        ("src/nested/mod.rs", ""),
+        ("src/.gitignore", "nested/\n"),
+        ("debug.log", "x"),
+        ("keep.log", "kept"),
+        ("docs/guide.md", "# Guide\n"),
+        (".git/HEAD", "ref: refs/heads/main\n"),
+    ] {
+        tree.write(file, content);
+    }
+    tree
+}
+
+fn paths(listing: &Listing) -> Vec<&str> {
+    listing.entries.iter().map(|e| e.path.as_str()).collect()
+}
+
+#[test]
+fn gitignore_rules_match_like_git() {
+    let mut rules = IgnoreRules::default();
+    rules.add(
+        Path::new("/repo"),
+        "# comment\ntarget/\n*.log\n!keep.log\n/build\ndocs/*.tmp\n",
+    );
+    assert!(rules.is_ignored(Path::new("/repo/target"), true));
+    assert!(!rules.is_ignored(Path::new("/repo/target"), false));
+    assert!(rules.is_ignored(Path::new("/repo/a/b/x.log"), false));
+    assert!(!rules.is_ignored(Path::new("/repo/keep.log"), false));
+    assert!(rules.is_ignored(Path::new("/repo/build"), false));
+    assert!(!rules.is_ignored(Path::new("/repo/src/build"), false));
+    assert!(rules.is_ignored(Path::new("/repo/docs/a.tmp"), false));
+    assert!(!rules.is_ignored(Path::new("/repo/other/docs/a.tmp"), false));
+    assert!(!rules.is_ignored(Path::new("/elsewhere/x.log"), false));
+}
+
+#[tokio::test]
+async fn lists_one_level_without_ignored_entries() {
+    let tree = temp_tree();
+    let root = tree.dir.clone();
+    let listing = list_directory(&root, &root, &ListArgs::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        paths(&listing),
+        vec![".gitignore", "docs", "keep.log", "src"]
+    );
+    assert_eq!(
+        listing.entries[2],
+        DirEntry {
+            path: "keep.log".to_string(),
+            kind: EntryKind::File,
+            size: Some(4),
+        }
+    );
+    assert!(listing.complete);
+    assert_eq!(listing.next_offset, None);
+}
+
+#[tokio::test]
+async fn descends_to_the_requested_depth() {
+    let tree = temp_tree();
+    let root = tree.dir.clone();
+    let args = ListArgs {
+        depth: Some(3),
+        ..Default::default()
+    };
+    let listing = list_directory(&root, &root, &args).await.unwrap();
+    assert_eq!(
+        paths(&listing),
+        vec![
+            ".gitignore",
+            "docs",
+            "docs/guide.md",
+            "keep.log",
+            "src",
+            "src/.gitignore",
+            "src/main.rs",
+        ]
+    );
+
+    // Ignore files above the listed directory still apply.
+    let listing = list_directory(&root, &root.join("src"), &ListArgs::default())
+        .await
+        .unwrap();
+    assert_eq!(paths(&listing), vec![".gitignore", "main.rs"]);
+
+    let args = ListArgs {
+        include_ignored: true,
+        ..Default::default()
+    };
+    let listing = list_directory(&root, &root, &args).await.unwrap();
+    assert!(paths(&listing).contains(&"target"));
+    assert!(!paths(&listing).contains(&".git"));
+}
+
+#[tokio::test]
+async fn pages_with_offset_and_limit() {
+    let tree = temp_tree();
+    let root = tree.dir.clone();
+    let args = ListArgs {
+        limit: Some(3),
+        ..Default::default()
+    };
+    let first = list_directory(&root, &root, &args).await.unwrap();
+    assert_eq!(paths(&first), vec![".gitignore", "docs", "keep.log"]);
+    assert_eq!(first.total, 4);
+    assert_eq!(first.next_offset, Some(3));
+    assert!(format_listing(&first).contains("offset=3"));
+
+    let args = ListArgs {
+        offset: first.next_offset,
+        limit: Some(3),
+        ..Default::default()
+    };
+    let second = list_directory(&root, &root, &args).await.unwrap();
+    assert_eq!(paths(&second), vec!["src"]);
+    assert_eq!(second.next_offset, None);
+}
+
+#[tokio::test]
+async fn rejects_files_and_formats_entries() {
+    let tree = temp_tree();
+    let root = tree.dir.clone();
+    assert!(
+        list_directory(&root, &root.join("keep.log"), &ListArgs::default())
+            .await
+            .is_err()
+    );
+    let listing = list_directory(&root, &root.join("docs"), &ListArgs::default())
+        .await
+        .unwrap();
+    assert_eq!(format_listing(&listing), "guide.md (8 bytes)\n");
+}
@@ -0,0 +1,27 @@
+mod ask_user_test;
+mod binary_read_test;
+mod bridge_auth_test;
+mod bridge_client_test;
+mod bridge_framing_test;
+mod bridge_read_only_test;
+mod bridge_timeouts_test;
+mod bridge_transport_test;
+mod chunked_write_test;
+mod command_cache_test;
+mod conflicts_test;
+mod dry_run_test;
+mod edit_match_test;
+mod edit_tools_test;
+mod file_info_test;
+mod glob_files_test;
+mod line_range_test;
+mod listing_test;
+mod protected_test;
+mod search_test;
+mod support;
+mod tail_read_test;
+mod text_test;
+mod undo_test;
+mod unified_diff_test;
+mod workspace_scope_test;
+mod write_approval_test;
@@ -0,0 +1,110 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use agent_client_protocol::{
+    PermissionOptionId, RequestPermissionOutcome, RequestPermissionRequest,
+    RequestPermissionResponse, SessionId, ToolCallId, ToolCallUpdate, ToolCallUpdateFields,
+};
+
+use crate::fs::tools::protected::{ProtectedPaths, escalate, glob_matches, is_allowed};
+
+fn protected() -> ProtectedPaths {
+    ProtectedPaths::new([".git/**", "**/*.pem", "infra/prod/**", ".env", "/etc/**"])
+}
+
+/// Globs match workspace-relative paths, including directories by name.
+#[test]
+fn matches_relative_paths() {
+    let root = Path::new("/repo");
+    let paths = protected();
+    assert_eq!(
+        paths.matching(root, Path::new("/repo/.git/config")),
+        Some(".git/**")
+    );
+    assert_eq!(
+        paths.matching(root, Path::new("/repo/certs/dev/server.pem")),
+        Some("**/*.pem")
+    );
+    assert_eq!(
+        paths.matching(root, Path::new("/repo/infra/prod/main.tf")),
+        Some("infra/prod/**")
+    );
+    assert_eq!(
+        paths.matching(root, Path::new("/repo/app/.env")),
+        Some(".env")
+    );
+    assert_eq!(paths.matching(root, Path::new("/repo/src/main.rs")), None);
+    assert_eq!(
+        paths.matching(root, Path::new("/repo/infra/staging/main.tf")),
+        None
+    );
+}
+
+/// Absolute patterns only apply outside the workspace.
+#[test]
+fn matches_absolute_paths() {
+    let root = Path::new("/repo");
+    let paths = protected();
+    assert_eq!(
+        paths.matching(root, Path::new("/etc/hosts")),
+        Some("/etc/**")
+    );
+    assert_eq!(paths.matching(root, Path::new("/repo/etc/hosts")), None);
+}
+
+#[test]
+fn glob_wildcards() {
+    assert!(glob_matches("*.pem", &["a", "key.pem"]));
+    assert!(glob_matches("secrets/", &["secrets", "x"]));
+    assert!(glob_matches("id_?sa", &["id_rsa"]));
+    assert!(!glob_matches("*.pem", &["key.pem.bak"]));
+}
+
+/// Escalated requests only offer allow-once and reject, flagged high risk.
+#[test]
+fn escalates_permission_requests() {
+    let mut request = RequestPermissionRequest {
+        session_id: SessionId("s".into()),
+        tool_call: ToolCallUpdate {
+            id: ToolCallId("c".into()),
+            fields: ToolCallUpdateFields {
+                title: Some("Apply changes".into()),
+                ..Default::default()
+            },
+            meta: None,
+        },
+        options: Vec::new(),
+        meta: None,
+    };
+    escalate(
+        &mut request,
+        &[("/repo/.git/config".into(), ".git/**".into())],
+    );
+    assert_eq!(request.options.len(), 2);
+    let meta = request.tool_call.meta.expect("meta");
+    assert_eq!(meta["risk"]["level"], "high");
+    assert!(
+        request
+            .tool_call
+            .fields
+            .title
+            .unwrap()
+            .contains("/repo/.git/config")
+    );
+
+    let allow = RequestPermissionResponse {
+        outcome: RequestPermissionOutcome::Selected {
+            option_id: request.options[0].id.clone(),
+        },
+        meta: None,
+    };
+    assert!(is_allowed(&allow));
+    let other = RequestPermissionResponse {
+        outcome: RequestPermissionOutcome::Selected {
+            option_id: PermissionOptionId("approved-for-session".into()),
+        },
+        meta: None,
+    };
+    assert!(!is_allowed(&other));
+}
@@ -0,0 +1,212 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use super::support::Workspace;
+use crate::fs::tools::search::{
+    SearchArgs, SearchMatch, SearchResults, build_matcher, format_results, search, search_text_in,
+};
+
+fn temp_tree() -> Workspace {
+    let tree = Workspace::new();
+    for (file, content) in [
+        (".gitignore", "target/\n"),
+        ("src/main.rs", "fn main() {\n    todo!(\"Widget\");\n}\n"),
+        (
+            "src/nested/lib.rs",
+            "// widget helpers\npub fn widget() {}\n",
+        ),
+        ("notes.md", "A widget note.\n"),
+        ("target/out.rs", "widget\n"),
+        (".git/config", "widget\n"),
+    ] {
+        tree.write(file, content);
+    }
+    std::fs::write(tree.path("image.bin"), b"widget\0\x01").unwrap();
+    tree
+}
+
+fn args(query: &str) -> SearchArgs {
+    SearchArgs {
+        query: query.to_string(),
+        ..Default::default()
+    }
+}
+
+fn found(results: &SearchResults) -> Vec<(&str, u32)> {
+    results
+        .matches
+        .iter()
+        .map(|m| (m.path.as_str(), m.line))
+        .collect()
+}
+
+#[test]
+fn literal_queries_are_escaped_and_case_insensitive_by_default() {
+    let matcher = build_matcher(&args("a.b(")).unwrap();
+    assert!(matcher.is_match("x A.B( y"));
+    assert!(!matcher.is_match("axb("));
+
+    let matcher = build_matcher(&SearchArgs {
+        case_sensitive: true,
+        ..args("Widget")
+    })
+    .unwrap();
+    assert!(matcher.is_match("Widget"));
+    assert!(!matcher.is_match("widget"));
+}
+
+#[test]
+fn regex_queries_are_validated() {
+    let matcher = build_matcher(&SearchArgs {
+        regex: true,
+        ..args(r"fn \w+\(")
+    })
+    .unwrap();
+    assert!(matcher.is_match("pub fn widget() {}"));
+
+    let err = build_matcher(&SearchArgs {
+        regex: true,
+        ..args("(unclosed")
+    })
+    .unwrap_err();
+    assert!(err.starts_with("invalid regex"), "{err}");
+    assert!(build_matcher(&args("")).is_err());
+}
+
+#[test]
+fn matches_carry_line_numbers_and_context() {
+    let matcher = build_matcher(&args("two")).unwrap();
+    let text = "one\ntwo\nthree\nfour\r\n";
+    let matches = search_text_in(text, "a.txt", Path::new("/w/a.txt"), &matcher, 2, 10);
+    assert_eq!(
+        matches,
+        vec![SearchMatch {
+            path: "a.txt".to_string(),
+            location: PathBuf::from("/w/a.txt"),
+            line: 2,
+            text: "two".to_string(),
+            before: vec!["one".to_string()],
+            after: vec!["three".to_string(), "four".to_string()],
+        }]
+    );
+
+    let matcher = build_matcher(&args("o")).unwrap();
+    let matches = search_text_in(text, "a.txt", Path::new("/w/a.txt"), &matcher, 0, 2);
+    assert_eq!(matches.len(), 2);
+}
+
+#[tokio::test]
+async fn searches_the_tree_skipping_ignored_and_binary_files() {
+    let tree = temp_tree();
+    let root = tree.dir.clone();
+    let results = search(&root, &root, &args("widget")).await.unwrap();
+    assert_eq!(
+        found(&results),
+        vec![
+            ("notes.md", 1),
+            ("src/main.rs", 2),
+            ("src/nested/lib.rs", 1),
+            ("src/nested/lib.rs", 2),
+        ]
+    );
+    assert!(!results.truncated);
+    assert!(results.complete);
+    assert_eq!(results.matches[0].location, root.join("notes.md"));
+
+    let results = search(
+        &root,
+        &root,
+        &SearchArgs {
+            include_ignored: true,
+            ..args("widget")
+        },
+    )
+    .await
+    .unwrap();
+    assert!(found(&results).contains(&("target/out.rs", 1)));
+    assert!(
+        !found(&results)
+            .iter()
+            .any(|(path, _)| path.starts_with(".git"))
+    );
+}
+
+#[tokio::test]
+async fn glob_path_and_max_results_narrow_the_search() {
+    let tree = temp_tree();
+    let root = tree.dir.clone();
+    let results = search(
+        &root,
+        &root,
+        &SearchArgs {
+            glob: Some("src/**/*.rs".to_string()),
+            ..args("widget")
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(results.files_searched, 2);
+    assert_eq!(found(&results).len(), 3);
+
+    let results = search(&root, &root.join("src/nested"), &args("widget"))
+        .await
+        .unwrap();
+    assert_eq!(
+        found(&results),
+        vec![("src/nested/lib.rs", 1), ("src/nested/lib.rs", 2)]
+    );
+
+    let results = search(
+        &root,
+        &root,
+        &SearchArgs {
+            max_results: Some(2),
+            ..args("widget")
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(results.matches.len(), 2);
+    assert!(results.truncated);
+    assert!(format_results(&results).contains("[Stopped at 2 matches"));
+}
+
+#[test]
+fn formats_results_like_grep() {
+    let results = SearchResults {
+        matches: vec![
+            SearchMatch {
+                path: "a.rs".to_string(),
+                location: PathBuf::from("/w/a.rs"),
+                line: 3,
+                text: "hit".to_string(),
+                before: vec!["before".to_string()],
+                after: vec![],
+            },
+            SearchMatch {
+                path: "b.rs".to_string(),
+                location: PathBuf::from("/w/b.rs"),
+                line: 1,
+                text: "hit again".to_string(),
+                before: vec![],
+                after: vec!["after".to_string()],
+            },
+        ],
+        files_searched: 2,
+        truncated: false,
+        complete: true,
+    };
+    assert_eq!(
+        format_results(&results),
+        "a.rs-2- before\na.rs:3: hit\n--\nb.rs:1: hit again\nb.rs-2- after\n"
+    );
+
+    let empty = SearchResults {
+        matches: vec![],
+        files_searched: 7,
+        truncated: false,
+        complete: true,
+    };
+    assert_eq!(format_results(&empty), "No matches in 7 files.\n");
+}
@@ -0,0 +1,186 @@
+#![cfg(test)]
+
+//! Setup shared by tests of the bridge and the `acp_fs` tools.
+
+use std::{
+    cell::{Cell, RefCell},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use agent_client_protocol::{Error, WriteTextFileResponse};
+use serde_json::{Value, json};
+use tokio::{
+    sync::mpsc::{UnboundedReceiver, UnboundedSender},
+    task,
+};
+
+use crate::fs::{
+    ClientOp, FsBridge,
+    bridge_client::BridgeClient,
+    mcp_server::FsTools,
+    tools::{audit::AuditEntry, bridge_timeouts::BridgeTimeouts, undo::FileSnapshot},
+};
+
+/// The FS session id the tests talk to the bridge as.
+pub const SESSION: &str = "fs-a";
+
+/// A fresh temporary directory, removed when dropped.
+pub struct Workspace {
+    pub dir: PathBuf,
+}
+
+impl Workspace {
+    /// Create the directory. Its name is short so session sockets inside it
+    /// stay within the platform's path limit.
+    pub fn new() -> Self {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let dir = std::env::temp_dir().join(format!("acp-{}", &id[..12]));
+        std::fs::create_dir_all(&dir).expect("workspace");
+        Self { dir }
+    }
+
+    /// Create the directory with `files` (relative path and content) in it.
+    pub fn with_files(files: &[(&str, &str)]) -> Self {
+        let workspace = Self::new();
+        for (path, content) in files {
+            workspace.write(path, content);
+        }
+        workspace
+    }
+
+    /// Absolute path of `path` inside the workspace.
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.dir.join(path)
+    }
+
+    /// Write `content` to `path`, creating missing parents.
+    pub fn write(&self, path: &str, content: &str) -> PathBuf {
+        let path = self.path(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("parent directory");
+        }
+        std::fs::write(&path, content).expect("file");
+        path
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// A client that cannot read or write files, so the bridge uses the disk.
+/// It reports the session mode set here and keeps what the bridge records.
+#[derive(Default)]
+pub struct LocalClient {
+    pub read_only: Cell<bool>,
+    pub dry_run: Cell<bool>,
+    /// When set, client writes succeed without touching the disk, as the
+    /// agent answers them in a dry run.
+    pub swallow_writes: Cell<bool>,
+    pub undo: RefCell<Vec<FileSnapshot>>,
+    pub audit: RefCell<Vec<AuditEntry>>,
+}
+
+impl LocalClient {
+    /// Answer `client_rx` until the bridge goes away. Requests this client
+    /// does not answer, such as permission requests, are passed to `others`.
+    pub fn serve(
+        self: Rc<Self>,
+        mut client_rx: UnboundedReceiver<ClientOp>,
+        others: UnboundedSender<ClientOp>,
+    ) {
+        task::spawn_local(async move {
+            while let Some(op) = client_rx.recv().await {
+                match op {
+                    ClientOp::ReadTextFile { response_tx, .. } => {
+                        let _ = response_tx.send(Err(Error::method_not_found()));
+                    }
+                    ClientOp::WriteTextFile { response_tx, .. } => {
+                        let response = if self.swallow_writes.get() {
+                            Ok(WriteTextFileResponse::default())
+                        } else {
+                            Err(Error::method_not_found())
+                        };
+                        let _ = response_tx.send(response);
+                    }
+                    ClientOp::GetReadOnly { response_tx, .. } => {
+                        let _ = response_tx.send(Ok(self.read_only.get()));
+                    }
+                    ClientOp::GetDryRun { response_tx, .. } => {
+                        let _ = response_tx.send(Ok(self.dry_run.get()));
+                    }
+                    ClientOp::RecordUndo { files, .. } => self.undo.borrow_mut().extend(files),
+                    ClientOp::RecordAudit { entry, .. } => self.audit.borrow_mut().push(entry),
+                    op => {
+                        let _ = others.send(op);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// A bridge on a fresh workspace, served by a [`LocalClient`].
+pub struct TestBridge {
+    pub bridge: Arc<FsBridge>,
+    pub client: Rc<LocalClient>,
+    /// Requests the [`LocalClient`] leaves to the test, e.g. permission requests.
+    pub requests: UnboundedReceiver<ClientOp>,
+    pub workspace: Workspace,
+}
+
+impl TestBridge {
+    /// Start the bridge on `workspace`. Must run inside a `LocalSet`.
+    pub async fn start(workspace: Workspace) -> Self {
+        let (client_tx, client_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (others_tx, requests) = tokio::sync::mpsc::unbounded_channel();
+        let client = Rc::new(LocalClient::default());
+        client.clone().serve(client_rx, others_tx);
+        let bridge = start_bridge(client_tx, &workspace.dir).await;
+        Self {
+            bridge,
+            client,
+            requests,
+            workspace,
+        }
+    }
+
+    /// A helper connection for `session_id`.
+    pub fn connect(&self, session_id: &str) -> BridgeClient {
+        BridgeClient::new(
+            self.bridge.session_address(session_id),
+            self.bridge.session_token(session_id),
+            BridgeTimeouts::default(),
+        )
+    }
+
+    /// The `acp_fs` tools of [`SESSION`].
+    pub fn tools(&self) -> FsTools {
+        FsTools::new(self.connect(SESSION), SESSION.to_string())
+    }
+}
+
+/// Start a bridge on `dir` whose client requests go to `client_tx`.
+pub async fn start_bridge(client_tx: UnboundedSender<ClientOp>, dir: &Path) -> Arc<FsBridge> {
+    FsBridge::start(client_tx, dir.to_path_buf(), dir)
+        .await
+        .expect("bridge")
+}
+
+/// Send `op` on `path` with the fields of `request` as [`SESSION`].
+pub fn send<'a>(
+    client: &'a BridgeClient,
+    op: &str,
+    path: &Path,
+    mut request: Value,
+) -> impl Future<Output = anyhow::Result<String>> + use<'a> {
+    request["session_id"] = json!(SESSION);
+    request["op"] = json!(op);
+    request["path"] = json!(path.display().to_string());
+    client.send(request, Duration::from_secs(10))
+}
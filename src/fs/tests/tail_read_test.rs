@@ -0,0 +1,81 @@
+#![cfg(test)]
+
+use super::support::Workspace;
+use crate::fs::tools::tail_read::{TAIL_BLOCK_BYTES, TailRead, read_tail};
+
+/// The last lines keep their line endings and report where they start.
+#[test]
+fn takes_the_last_lines() {
+    let tail = TailRead::new("a\nb\nc\nd\n", 2);
+    assert_eq!(tail.content, "c\nd\n");
+    assert_eq!(tail.start_line, 3);
+    assert_eq!(tail.total_lines, 4);
+    assert_eq!(tail.end_line(), 4);
+
+    let whole = TailRead::new("a\nb", 10);
+    assert_eq!(whole.content, "a\nb");
+    assert_eq!(whole.start_line, 1);
+    assert_eq!(whole.end_line(), 2);
+
+    let empty = TailRead::new("", 5);
+    assert_eq!(empty.start_line, 1);
+    assert_eq!(empty.end_line(), 0);
+}
+
+/// Leading lines are dropped to fit the byte cap.
+#[test]
+fn fits_the_byte_cap_from_the_front() {
+    let mut tail = TailRead::new("a\nbb\nccc\n", 3);
+    assert!(!tail.clone().fit_bytes(100));
+    assert!(tail.fit_bytes(5));
+    assert_eq!(tail.content, "ccc\n");
+    assert_eq!(tail.start_line, 3);
+    assert_eq!(tail.end_line(), 3);
+}
+
+/// A last line longer than the cap keeps its end.
+#[test]
+fn keeps_the_end_of_an_overlong_line() {
+    let mut tail = TailRead::new("a\nxxxxxxxxxx\n", 2);
+    assert!(tail.fit_bytes(5));
+    assert_eq!(tail.content, "xxxx\n");
+    assert_eq!(tail.start_line, 2);
+
+    let mut wide = TailRead::new("ééé", 1);
+    assert!(wide.fit_bytes(3));
+    assert_eq!(wide.content, "é");
+}
+
+/// Reading backwards in blocks finds the same lines and line numbers as
+/// reading the whole file, wherever the blocks happen to split it.
+#[tokio::test]
+async fn reads_backwards_across_blocks() {
+    let workspace = Workspace::new();
+    for content in ["", "a", "a\nb", "a\nbb\nccc\n", "\n\nx\n\n", "é\nü\n"] {
+        let path = workspace.write("file.txt", content);
+        for block in [1, 2, 3, 7, TAIL_BLOCK_BYTES] {
+            for count in [0, 1, 2, 3, 10] {
+                let tail = read_tail(&path, count, block).await.expect("tail");
+                assert_eq!(
+                    tail,
+                    TailRead::new(content, count),
+                    "{content:?} block={block} count={count}"
+                );
+            }
+        }
+    }
+}
+
+/// A file several blocks long is read from its end.
+#[tokio::test]
+async fn reads_the_end_of_files_larger_than_a_block() {
+    let workspace = Workspace::new();
+    let content: String = (1..=20_000).map(|n| format!("line {n}\n")).collect();
+    assert!(content.len() > 2 * TAIL_BLOCK_BYTES);
+    let path = workspace.write("big.log", &content);
+
+    let tail = read_tail(&path, 3, TAIL_BLOCK_BYTES).await.expect("tail");
+    assert_eq!(tail.content, "line 19998\nline 19999\nline 20000\n");
+    assert_eq!(tail.start_line, 19_998);
+    assert_eq!(tail.total_lines, 20_000);
+}
@@ -0,0 +1,29 @@
+#![cfg(test)]
+
+use crate::fs::text::{
+    ceil_char_boundary, elide_head, elide_tail, floor_char_boundary, truncate_head, truncate_tail,
+};
+
+/// Cuts never land inside a multi-byte character.
+#[test]
+fn cuts_respect_char_boundaries() {
+    let text = "aé€b"; // 1 + 2 + 3 + 1 bytes
+    assert_eq!(floor_char_boundary(text, 2), 1);
+    assert_eq!(ceil_char_boundary(text, 2), 3);
+    assert_eq!(floor_char_boundary(text, 99), text.len());
+
+    assert_eq!(truncate_head(text, 5), ("aé", true));
+    assert_eq!(truncate_head(text, 7), (text, false));
+    assert_eq!(truncate_tail(text, 3), ("b", true));
+    assert_eq!(truncate_tail(text, 4), ("€b", true));
+    assert_eq!(truncate_tail(text, 0), ("", true));
+    assert_eq!(truncate_tail("", 0), ("", false));
+}
+
+#[test]
+fn elided_text_is_marked() {
+    assert_eq!(elide_head("hello world", 5), "…world");
+    assert_eq!(elide_tail("hello world", 5), "hello…");
+    assert_eq!(elide_head("short", 5), "short");
+    assert_eq!(elide_tail("short", 5), "short");
+}
@@ -0,0 +1,172 @@
+#![cfg(test)]
+
+use std::path::PathBuf;
+
+use serde_json::json;
+use tokio::task::{self, LocalSet};
+
+use super::support::{SESSION, TestBridge, Workspace, send};
+use crate::fs::tools::{
+    audit::AuditEvent,
+    undo::{FileSnapshot, MAX_UNDO_CHANGES, UndoHistory},
+};
+
+fn snapshot(path: &str, before: Option<&str>) -> FileSnapshot {
+    FileSnapshot {
+        path: PathBuf::from(path),
+        before: before.map(str::to_string),
+        moved_to: None,
+    }
+}
+
+/// `/undo` takes the newest change; `/undo all` the newest turn's changes.
+#[test]
+fn pops_changes_and_turns() {
+    let mut history = UndoHistory::default();
+    history.begin_turn();
+    history.record(vec![snapshot("a.txt", Some("a1"))]);
+    history.begin_turn();
+    history.record(vec![snapshot("b.txt", None)]);
+    history.record(vec![snapshot("c.txt", Some("c1"))]);
+
+    let last = history.pop_last().expect("last change");
+    assert_eq!(last.files[0].path, PathBuf::from("c.txt"));
+
+    history.record(vec![snapshot("d.txt", Some("d1"))]);
+    let turn = history.pop_turn();
+    let paths: Vec<_> = turn
+        .iter()
+        .map(|change| change.files[0].path.clone())
+        .collect();
+    assert_eq!(paths, vec![PathBuf::from("d.txt"), PathBuf::from("b.txt")]);
+
+    assert_eq!(history.pop_turn().len(), 1);
+    assert!(history.pop_last().is_none());
+}
+
+/// The oldest changes are forgotten once the history is full.
+#[test]
+fn bounds_history() {
+    let mut history = UndoHistory::default();
+    for i in 0..MAX_UNDO_CHANGES + 5 {
+        assert!(history.record(vec![snapshot(&format!("{i}.txt"), Some("x"))]));
+    }
+    let mut count = 0;
+    let mut oldest = None;
+    while let Some(change) = history.pop_last() {
+        count += 1;
+        oldest = Some(change.files[0].path.clone());
+    }
+    assert_eq!(count, MAX_UNDO_CHANGES);
+    assert_eq!(oldest, Some(PathBuf::from("5.txt")));
+    assert!(!history.record(Vec::new()));
+}
+
+/// Bridge deletes record the deleted content for `/undo` and are audited.
+#[tokio::test]
+async fn deletes_are_undoable_and_audited() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::with_files(&[("old.txt", "gone")])).await;
+            let client = test.connect(SESSION);
+            let file = test.workspace.path("old.txt");
+
+            send(&client, "delete", &file, json!({}))
+                .await
+                .expect("delete");
+            task::yield_now().await;
+
+            assert!(!file.exists());
+            assert_eq!(
+                *test.client.undo.borrow(),
+                vec![FileSnapshot {
+                    path: file.clone(),
+                    before: Some("gone".to_string()),
+                    moved_to: None,
+                }]
+            );
+            let audit = test.client.audit.borrow();
+            assert_eq!(audit.len(), 1);
+            assert_eq!(audit[0].event, AuditEvent::Delete);
+            assert_eq!(audit[0].paths, vec![file]);
+            assert_eq!(audit[0].decision.as_deref(), Some("auto"));
+        })
+        .await;
+}
+
+/// Bridge moves record where the file went, and the destination they
+/// replaced, for `/undo`, and are audited with both paths.
+#[tokio::test]
+async fn moves_are_undoable_and_audited() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::with_files(&[
+                ("from.txt", "moved"),
+                ("to.txt", "replaced"),
+            ]))
+            .await;
+            let client = test.connect(SESSION);
+            let (from, to) = (
+                test.workspace.path("from.txt"),
+                test.workspace.path("to.txt"),
+            );
+
+            send(
+                &client,
+                "move",
+                &from,
+                json!({ "args": { "destination": to.display().to_string(), "overwrite": true } }),
+            )
+            .await
+            .expect("move");
+            task::yield_now().await;
+
+            assert_eq!(std::fs::read_to_string(&to).unwrap(), "moved");
+            assert_eq!(
+                *test.client.undo.borrow(),
+                vec![
+                    FileSnapshot {
+                        path: to.clone(),
+                        before: Some("replaced".to_string()),
+                        moved_to: None,
+                    },
+                    FileSnapshot {
+                        path: from.clone(),
+                        before: Some("moved".to_string()),
+                        moved_to: Some(to.clone()),
+                    },
+                ]
+            );
+            let audit = test.client.audit.borrow();
+            assert_eq!(audit.len(), 1);
+            assert_eq!(audit[0].event, AuditEvent::Move);
+            assert_eq!(audit[0].paths, vec![from, to]);
+        })
+        .await;
+}
+
+/// New directories are audited; they are not undone, so nothing is recorded
+/// for `/undo`.
+#[tokio::test]
+async fn new_directories_are_audited() {
+    LocalSet::new()
+        .run_until(async {
+            let test = TestBridge::start(Workspace::new()).await;
+            let client = test.connect(SESSION);
+            let dir = test.workspace.path("new/dir");
+
+            send(&client, "create_directory", &dir, json!({}))
+                .await
+                .expect("create_directory");
+            task::yield_now().await;
+
+            assert!(dir.is_dir());
+            assert!(test.client.undo.borrow().is_empty());
+            let audit = test.client.audit.borrow();
+            assert_eq!(audit.len(), 1);
+            assert_eq!(audit[0].event, AuditEvent::CreateDirectory);
+            assert_eq!(audit[0].paths, vec![dir]);
+            assert_eq!(audit[0].decision.as_deref(), Some("auto"));
+        })
+        .await;
+}
@@ -0,0 +1,77 @@
+#![cfg(test)]
+
+use crate::fs::tools::unified_diff::{apply, format_outcomes, parse};
+
+fn patch(base: &str, diff: &str) -> String {
+    let applied = apply(base, &parse(diff).unwrap());
+    assert!(applied.all_applied(), "{:?}", applied.hunks);
+    applied.content
+}
+
+#[test]
+fn applies_hunks_at_their_lines_or_where_they_moved() {
+    let diff = "--- a/f.txt\n+++ b/f.txt\n@@ -2,3 +2,3 @@\n b\n-c\n+C\n d\n";
+    let applied = apply("a\nb\nc\nd\ne\n", &parse(diff).unwrap());
+    assert_eq!(applied.content, "a\nb\nC\nd\ne\n");
+    assert_eq!(applied.hunks[0].line, Some(2));
+    assert_eq!(applied.hunks[0].offset, 0);
+
+    let applied = apply("x\ny\na\nb\nc\nd\ne\n", &parse(diff).unwrap());
+    assert_eq!(applied.content, "x\ny\na\nb\nC\nd\ne\n");
+    assert_eq!(applied.hunks[0].line, Some(4));
+    assert_eq!(applied.hunks[0].offset, 2);
+}
+
+#[test]
+fn tolerates_whitespace_and_stale_context() {
+    let diff = "@@ -1,3 +1,3 @@\n fn a() {\n-  let x = 1;\n+    let x = 2;\n }\n";
+    let applied = apply("fn a() {\n    let x = 1;\n}\n", &parse(diff).unwrap());
+    assert_eq!(applied.content, "fn a() {\n    let x = 2;\n}\n");
+    assert!(applied.hunks[0].whitespace);
+
+    let diff = "@@ -1,5 +1,5 @@\n A\n b\n-c\n+C\n d\n e\n";
+    let applied = apply("a\nb\nc\nd\ne\n", &parse(diff).unwrap());
+    assert_eq!(applied.content, "a\nb\nC\nd\ne\n");
+    assert_eq!(applied.hunks[0].fuzz, 1);
+    assert_eq!(applied.hunks[0].line, Some(1));
+}
+
+#[test]
+fn reports_failed_hunks_and_keeps_the_rest() {
+    let diff = "@@ -1,2 +1,2 @@\n a\n-b\n+B\n@@ -4,2 +4,2 @@\n q\n-r\n+R\n";
+    let applied = apply("a\nb\nc\nd\n", &parse(diff).unwrap());
+    assert!(!applied.all_applied());
+    assert!(applied.hunks[0].applied);
+    assert!(!applied.hunks[1].applied);
+    assert_eq!(applied.content, "a\nB\nc\nd\n");
+
+    let report = format_outcomes("f.txt", &applied, false);
+    assert!(report.starts_with("f.txt: 1 of 2 hunks applied, nothing written\n"));
+    assert!(report.contains("hunk 2: failed"));
+}
+
+#[test]
+fn keeps_line_endings_and_missing_final_newlines() {
+    let diff = "@@ -1,2 +1,2 @@\n a\n-b\n+B\n";
+    assert_eq!(patch("a\r\nb\r\n", diff), "a\r\nB\r\n");
+
+    let diff =
+        "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+B\n\\ No newline at end of file\n";
+    assert_eq!(patch("a\nb", diff), "a\nB");
+
+    let diff = "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+B\n";
+    assert_eq!(patch("a\nb", diff), "a\nB\n");
+
+    assert_eq!(patch("", "@@ -0,0 +1,2 @@\n+one\n+two\n"), "one\ntwo\n");
+}
+
+#[test]
+fn parses_only_single_file_diffs() {
+    // `--- x` inside a hunk removes the line `-- x`.
+    let diff = "--- f\n+++ f\n@@ -1,3 +1,2 @@\n a\n--- x\n b\n";
+    assert_eq!(patch("a\n-- x\nb\n", diff), "a\nb\n");
+
+    let two_files = "--- a\n+++ a\n@@ -1 +1 @@\n-a\n+b\n--- b\n+++ b\n@@ -1 +1 @@\n-c\n+d\n";
+    assert!(parse(two_files).is_err());
+    assert!(parse("just text\n").is_err());
+}
@@ -0,0 +1,125 @@
+#![cfg(test)]
+
+use std::path::{Path, PathBuf};
+
+use agent_client_protocol::{
+    PermissionOptionId, RequestPermissionOutcome, RequestPermissionResponse, SessionId, ToolKind,
+};
+
+use crate::fs::tools::workspace_scope::{
+    OutsidePolicy, WorkspaceScope, is_allowed, named_root, normalize, outside_request,
+};
+
+#[test]
+fn normalize_applies_dot_components() {
+    assert_eq!(
+        normalize(Path::new("/repo/src/../docs/./a.md")),
+        PathBuf::from("/repo/docs/a.md")
+    );
+    assert_eq!(
+        normalize(Path::new("/repo/../../../etc/passwd")),
+        PathBuf::from("/etc/passwd")
+    );
+}
+
+#[tokio::test]
+async fn scope_covers_workspace_and_extra_roots() {
+    let scope = WorkspaceScope::new(
+        Path::new("/repo"),
+        vec![PathBuf::from("/opt/shared"), PathBuf::from("../sibling")],
+        OutsidePolicy::Reject,
+    );
+    assert!(scope.contains(Path::new("/repo/src/main.rs"), &[]).await);
+    assert!(scope.contains(Path::new("/opt/shared/lib.rs"), &[]).await);
+    assert!(scope.contains(Path::new("/sibling/README.md"), &[]).await);
+    assert!(!scope.contains(Path::new("/etc/hosts"), &[]).await);
+    // A shared prefix is not enough.
+    assert!(!scope.contains(Path::new("/repository/file"), &[]).await);
+    // Session roots count as well.
+    let session_roots = [PathBuf::from("/work/web")];
+    assert!(
+        scope
+            .contains(Path::new("/work/web/app.ts"), &session_roots)
+            .await
+    );
+}
+
+/// A link inside the workspace that points outside it is outside.
+#[cfg(unix)]
+#[tokio::test]
+async fn symlinks_are_resolved_before_the_check() {
+    use super::support::Workspace;
+
+    let workspace = Workspace::new();
+    let base = workspace.dir.clone();
+    let repo = base.join("repo");
+    let outside = base.join("outside");
+    std::fs::create_dir_all(repo.join("src")).expect("repo");
+    std::fs::create_dir_all(&outside).expect("outside");
+    std::os::unix::fs::symlink(&outside, repo.join("escape")).expect("escape link");
+    std::os::unix::fs::symlink(repo.join("src"), repo.join("inner")).expect("inner link");
+    let scope = WorkspaceScope::new(&repo, Vec::new(), OutsidePolicy::Reject);
+
+    assert!(scope.contains(&repo.join("src/main.rs"), &[]).await);
+    assert!(!scope.contains(&repo.join("escape/secret.txt"), &[]).await);
+    // Not yet existing files behind the link are outside too.
+    assert!(!scope.contains(&repo.join("escape/new/file.txt"), &[]).await);
+    assert!(scope.contains(&repo.join("inner/lib.rs"), &[]).await);
+    // Reached through a link to the workspace itself, the path is inside.
+    let alias = base.join("alias");
+    std::os::unix::fs::symlink(&repo, &alias).expect("alias link");
+    let aliased = WorkspaceScope::new(&alias, Vec::new(), OutsidePolicy::Reject);
+    assert!(aliased.contains(&repo.join("src/main.rs"), &[]).await);
+}
+
+#[test]
+fn policy_parses_known_values() {
+    assert_eq!(OutsidePolicy::parse("reject"), Some(OutsidePolicy::Reject));
+    assert_eq!(OutsidePolicy::parse(" ASK "), Some(OutsidePolicy::Ask));
+    assert_eq!(OutsidePolicy::parse("allow"), None);
+    assert_eq!(OutsidePolicy::default(), OutsidePolicy::Reject);
+}
+
+#[test]
+fn only_the_allow_option_allows() {
+    let request = outside_request(
+        SessionId("s".into()),
+        Path::new("/etc/hosts"),
+        ToolKind::Edit,
+    );
+    assert_eq!(
+        request.tool_call.fields.title.as_deref(),
+        Some("⚠️ Write outside the workspace: /etc/hosts")
+    );
+    let pick = |option: &PermissionOptionId| RequestPermissionResponse {
+        outcome: RequestPermissionOutcome::Selected {
+            option_id: option.clone(),
+        },
+        meta: None,
+    };
+    assert!(is_allowed(&pick(&request.options[0].id)));
+    assert!(!is_allowed(&pick(&request.options[1].id)));
+    assert!(!is_allowed(&RequestPermissionResponse {
+        outcome: RequestPermissionOutcome::Cancelled,
+        meta: None,
+    }));
+}
+
+/// Only with several roots does a leading root name pick the root.
+#[test]
+fn named_root_matches_first_component() {
+    let single = vec![PathBuf::from("/work/api")];
+    assert_eq!(named_root(&single, Path::new("api/src/main.rs")), None);
+
+    let multi = vec![PathBuf::from("/work/api"), PathBuf::from("/work/web")];
+    assert_eq!(
+        named_root(&multi, Path::new("web/src/app.ts")),
+        Some((Path::new("/work/web"), Path::new("src/app.ts")))
+    );
+    assert_eq!(
+        named_root(&multi, Path::new("api")),
+        Some((Path::new("/work/api"), Path::new("")))
+    );
+    assert_eq!(named_root(&multi, Path::new("src/lib.rs")), None);
+    assert_eq!(named_root(&multi, Path::new("/work/web/x")), None);
+}
@@ -0,0 +1,82 @@
+#![cfg(test)]
+
+use std::path::Path;
+
+use agent_client_protocol::{
+    PermissionOptionKind, RequestPermissionOutcome, RequestPermissionResponse, SessionId,
+    ToolCallContent,
+};
+
+use crate::fs::tools::write_approval::{WriteDecision, write_permission_request};
+
+fn respond(outcome: RequestPermissionOutcome) -> RequestPermissionResponse {
+    RequestPermissionResponse {
+        outcome,
+        meta: None,
+    }
+}
+
+/// The request shows the write as a diff and offers once, always, reject.
+#[test]
+fn request_carries_the_diff() {
+    let request = write_permission_request(
+        SessionId("s".into()),
+        "fs_write-1".to_string(),
+        Path::new("/work/app/src/lib.rs"),
+        Some("old\n".to_string()),
+        "new\n".to_string(),
+    );
+    let content = request
+        .tool_call
+        .fields
+        .content
+        .as_deref()
+        .unwrap_or_default();
+    assert!(matches!(
+        content,
+        [ToolCallContent::Diff { diff }]
+            if diff.old_text.as_deref() == Some("old\n") && diff.new_text == "new\n"
+    ));
+    let kinds: Vec<_> = request.options.iter().map(|option| &option.kind).collect();
+    assert!(matches!(
+        kinds.as_slice(),
+        [
+            PermissionOptionKind::AllowOnce,
+            PermissionOptionKind::AllowAlways,
+            PermissionOptionKind::RejectOnce
+        ]
+    ));
+}
+
+/// Only the offered allow options allow; anything else rejects.
+#[test]
+fn decisions_follow_the_selected_option() {
+    let request = write_permission_request(
+        SessionId("s".into()),
+        "fs_write-1".to_string(),
+        Path::new("a.txt"),
+        None,
+        String::new(),
+    );
+    let select = |index: usize| {
+        respond(RequestPermissionOutcome::Selected {
+            option_id: request.options[index].id.clone(),
+        })
+    };
+    assert_eq!(
+        WriteDecision::from_response(&select(0)),
+        WriteDecision::AllowOnce
+    );
+    assert_eq!(
+        WriteDecision::from_response(&select(1)),
+        WriteDecision::AllowAlways
+    );
+    assert_eq!(
+        WriteDecision::from_response(&select(2)),
+        WriteDecision::Reject
+    );
+    assert_eq!(
+        WriteDecision::from_response(&respond(RequestPermissionOutcome::Cancelled)),
+        WriteDecision::Reject
+    );
+}
@@ -0,0 +1,56 @@
+//! Cutting text to a byte budget without splitting a character.
+//!
+//! Used by the bridge and the `acp_fs` MCP server, and by the agent for
+//! attachments, replayed tool output and recorded command output.
+
+/// The largest character boundary of `text` at or below `index`.
+pub fn floor_char_boundary(text: &str, index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+    let mut index = index;
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// The smallest character boundary of `text` at or above `index`.
+pub fn ceil_char_boundary(text: &str, index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+    let mut index = index;
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+/// The start of `text` in at most `max_bytes`, and whether anything was cut.
+pub fn truncate_head(text: &str, max_bytes: usize) -> (&str, bool) {
+    let end = floor_char_boundary(text, max_bytes);
+    (&text[..end], end < text.len())
+}
+
+/// The end of `text` in at most `max_bytes`, and whether anything was cut.
+pub fn truncate_tail(text: &str, max_bytes: usize) -> (&str, bool) {
+    let start = ceil_char_boundary(text, text.len().saturating_sub(max_bytes));
+    (&text[start..], start > 0)
+}
+
+/// The end of `text` in at most `max_bytes`, starting with `…` when cut.
+pub fn elide_head(text: &str, max_bytes: usize) -> String {
+    match truncate_tail(text, max_bytes) {
+        (tail, true) => format!("…{tail}"),
+        (tail, false) => tail.to_string(),
+    }
+}
+
+/// The start of `text` in at most `max_bytes`, ending with `…` when cut.
+pub fn elide_tail(text: &str, max_bytes: usize) -> String {
+    match truncate_head(text, max_bytes) {
+        (head, true) => format!("{head}…"),
+        (head, false) => head.to_string(),
+    }
+}
@@ -0,0 +1,256 @@
+//! Tamper-evident audit log of what the agent did.
+//!
+//! With `CODEX_ACP_AUDIT_LOG=1`, command approvals and runs, Codex patches
+//! and `acp_fs` bridge writes, deletes and moves are appended to `$CODEX_HOME/acp/audit.jsonl`,
+//! one JSON object per line, with secrets in commands redacted. Each entry
+//! carries the SHA-256 of the previous one and its own, so deleting or
+//! editing a line breaks the chain from there on; [`verify_chain`] finds the
+//! first broken entry. `codex/audit` returns a session's entries.
+
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// Environment variable enabling the audit log (`1`, `true`, `yes`).
+pub const AUDIT_LOG_ENV: &str = "CODEX_ACP_AUDIT_LOG";
+
+/// `prevHash` of the first entry.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+pub fn enabled_from_env() -> bool {
+    std::env::var(AUDIT_LOG_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// What an entry records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// The user's (or the digest's) answer to a command approval.
+    ExecDecision,
+    /// A command that ran to completion.
+    Exec,
+    /// The user's answer to a patch approval.
+    PatchDecision,
+    /// A Codex patch that was applied, or failed to apply.
+    Patch,
+    /// A write through the `acp_fs` bridge.
+    Write,
+    /// A file deleted through the `acp_fs` bridge.
+    Delete,
+    /// A file moved through the `acp_fs` bridge; `paths` are source and destination.
+    Move,
+    /// A directory created through the `acp_fs` bridge.
+    CreateDirectory,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// Position in the log, from 0.
+    #[serde(default)]
+    pub seq: u64,
+    /// Milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub timestamp_ms: u64,
+    pub session_id: String,
+    pub event: AuditEvent,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    /// e.g. `approved`, `approved_for_session`, `denied`, `auto`, `failed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decision: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub prev_hash: String,
+    #[serde(default)]
+    pub hash: String,
+}
+
+impl AuditEntry {
+    pub fn new(session_id: impl Into<String>, event: AuditEvent) -> Self {
+        Self {
+            seq: 0,
+            timestamp_ms: 0,
+            session_id: session_id.into(),
+            event,
+            command: None,
+            cwd: None,
+            paths: Vec::new(),
+            bytes: None,
+            decision: None,
+            exit_code: None,
+            prev_hash: String::new(),
+            hash: String::new(),
+        }
+    }
+
+    pub fn with_decision(mut self, decision: impl Into<String>) -> Self {
+        self.decision = Some(decision.into());
+        self
+    }
+
+    /// SHA-256 (hex) of the entry with an empty `hash`.
+    pub fn compute_hash(&self) -> String {
+        let unsealed = Self {
+            hash: String::new(),
+            ..self.clone()
+        };
+        let json = serde_json::to_vec(&unsealed).unwrap_or_default();
+        let digest = Sha256::digest(&json);
+        digest.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
+
+/// The `seq` of the first entry whose hash or link to its predecessor does
+/// not match, or `None` when the whole chain is intact.
+pub fn verify_chain(entries: &[AuditEntry]) -> Option<u64> {
+    let mut prev = GENESIS_HASH.to_string();
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.seq != index as u64
+            || entry.prev_hash != prev
+            || entry.hash != entry.compute_hash()
+        {
+            return Some(index as u64);
+        }
+        prev = entry.hash.clone();
+    }
+    None
+}
+
+/// Where the last entry left the chain.
+#[derive(Debug)]
+struct Tail {
+    next_seq: u64,
+    last_hash: String,
+}
+
+/// The append-only log file; cheap to clone and shared by the agent and the
+/// FS bridge so entries from both form one chain.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: Option<PathBuf>,
+    /// Loaded from the file on the first append.
+    tail: Arc<Mutex<Option<Tail>>>,
+}
+
+impl AuditLog {
+    /// A log under `codex_home`, writing nothing unless `enabled`.
+    pub fn new(codex_home: &Path, enabled: bool) -> Self {
+        Self {
+            path: enabled.then(|| codex_home.join("acp").join("audit.jsonl")),
+            tail: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Seal `entry` onto the chain and append it. Failures are logged, not
+    /// returned, so auditing never stops the work being audited.
+    pub fn record(&self, entry: AuditEntry) {
+        if let Err(err) = self.append(entry) {
+            warn!(error = %err, "failed to write audit log entry");
+        }
+    }
+
+    fn append(&self, mut entry: AuditEntry) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut tail = self
+            .tail
+            .lock()
+            .map_err(|_| io::Error::other("audit log state poisoned"))?;
+        // Taken out until the write succeeds, so a failed write reloads it.
+        let mut state = match tail.take() {
+            Some(state) => state,
+            None => load_tail(path)?,
+        };
+        entry.seq = state.next_seq;
+        entry.timestamp_ms = now_millis();
+        entry.prev_hash = state.last_hash.clone();
+        entry.hash = entry.compute_hash();
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(&line)?;
+        state.next_seq += 1;
+        state.last_hash = entry.hash;
+        *tail = Some(state);
+        Ok(())
+    }
+
+    /// Every entry in the log, oldest first.
+    pub async fn entries(&self) -> io::Result<Vec<AuditEntry>> {
+        let Some(path) = &self.path else {
+            return Ok(Vec::new());
+        };
+        match tokio::fs::read_to_string(path).await {
+            Ok(text) => Ok(parse_entries(&text)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.path.is_some()
+    }
+}
+
+/// Entries of a log file's contents. A line that no longer parses is kept as
+/// an empty entry, so [`verify_chain`] reports it as broken.
+pub fn parse_entries(text: &str) -> Vec<AuditEntry> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .unwrap_or_else(|_| AuditEntry::new(String::new(), AuditEvent::Write))
+        })
+        .collect()
+}
+
+fn load_tail(path: &Path) -> io::Result<Tail> {
+    let entries = match std::fs::read_to_string(path) {
+        Ok(text) => parse_entries(&text),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err),
+    };
+    Ok(match entries.last() {
+        Some(last) => Tail {
+            next_seq: entries.len() as u64,
+            last_hash: last.hash.clone(),
+        },
+        None => Tail {
+            next_seq: 0,
+            last_hash: GENESIS_HASH.to_string(),
+        },
+    })
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
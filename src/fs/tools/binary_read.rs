@@ -0,0 +1,97 @@
+//! Small binary files for the `acp_fs` `read_binary_file` tool.
+//!
+//! ACP can only read text, so the bridge reads binary files from local disk
+//! and hands them to the model base64-encoded, or as image content for PNG,
+//! JPEG, GIF and WebP files. Files over [`MAX_BINARY_READ_BYTES`] are refused
+//! outright rather than truncated, since a partial binary is rarely useful.
+
+use std::path::Path;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+
+/// Largest file `read_binary_file` returns.
+pub const MAX_BINARY_READ_BYTES: u64 = 512 * 1024;
+
+/// Image types returned as image content rather than base64 text.
+const IMAGE_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// A binary file as the bridge returns it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinaryFile {
+    pub mime_type: String,
+    pub size: u64,
+    /// Base64 of the whole file.
+    pub data: String,
+}
+
+impl BinaryFile {
+    pub fn encode(path: &Path, bytes: &[u8]) -> Self {
+        Self {
+            mime_type: sniff_mime_type(path, bytes).to_string(),
+            size: bytes.len() as u64,
+            data: STANDARD.encode(bytes),
+        }
+    }
+
+    /// Whether the model gets the file as image content.
+    pub fn is_image(&self) -> bool {
+        IMAGE_MIME_TYPES.contains(&self.mime_type.as_str())
+    }
+}
+
+/// Refuse files larger than [`MAX_BINARY_READ_BYTES`].
+pub fn check_size(path: &Path, size: u64) -> Result<(), String> {
+    if size > MAX_BINARY_READ_BYTES {
+        return Err(format!(
+            "{} is {size} bytes; read_binary_file only returns files up to {MAX_BINARY_READ_BYTES} bytes",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+/// The MIME type from the file's magic bytes, else its extension.
+pub fn sniff_mime_type(path: &Path, bytes: &[u8]) -> &'static str {
+    const MAGIC: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x00asm", "application/wasm"),
+        (b"\x7fELF", "application/x-elf"),
+    ];
+    if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| bytes.starts_with(magic)) {
+        return mime;
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match ext.as_deref() {
+        Some("svg") => "image/svg+xml",
+        Some("ico") => "image/x-icon",
+        Some("ttf") => "font/ttf",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+/// One-line description preceding the file's content.
+pub fn format_summary(path: &str, file: &BinaryFile) -> String {
+    if file.is_image() {
+        format!("{path}: {} image, {} bytes", file.mime_type, file.size)
+    } else {
+        format!(
+            "{path}: {}, {} bytes, base64-encoded below",
+            file.mime_type, file.size
+        )
+    }
+}
@@ -0,0 +1,136 @@
+//! How long the `acp_fs` helper waits for the FS bridge.
+//!
+//! Reads and writes have separate limits. Writes also get extra time for
+//! every MiB of content, since the client has to receive and store all of it.
+//! The agent reads the limits from its environment and forwards them to each
+//! session's helper through the MCP server config's `env`, where the helper
+//! reads the same variables back:
+//! - `CODEX_ACP_BRIDGE_READ_TIMEOUT_SECS` (default 10);
+//! - `CODEX_ACP_BRIDGE_WRITE_TIMEOUT_SECS` (default 600; writes may wait on
+//!   a protected-path or conflict prompt);
+//! - `CODEX_ACP_BRIDGE_TIMEOUT_PER_MIB_SECS` (default 10).
+//!
+//! A request that only reads is retried once when it times out. Codex gives
+//! each `acp_fs` tool call [`BridgeTimeouts::tool_timeout`], a little more
+//! than the longest request may take, so the helper's own timeout answers
+//! first.
+
+use std::time::Duration;
+
+use tracing::warn;
+
+pub const READ_TIMEOUT_ENV: &str = "CODEX_ACP_BRIDGE_READ_TIMEOUT_SECS";
+pub const WRITE_TIMEOUT_ENV: &str = "CODEX_ACP_BRIDGE_WRITE_TIMEOUT_SECS";
+pub const TIMEOUT_PER_MIB_ENV: &str = "CODEX_ACP_BRIDGE_TIMEOUT_PER_MIB_SECS";
+
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_WRITE_TIMEOUT: Duration = Duration::from_secs(600);
+const DEFAULT_TIMEOUT_PER_MIB: Duration = Duration::from_secs(10);
+
+/// Questions wait on a human, so allow far longer than file operations.
+pub const ASK_USER_TIMEOUT: Duration = Duration::from_secs(600);
+/// Deep listings and searches of large trees take a while to walk.
+pub const LIST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Upper bound of a size-scaled timeout.
+const MAX_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+/// Time between the helper giving up on a request and Codex giving up on
+/// the tool call.
+const TOOL_TIMEOUT_MARGIN: Duration = Duration::from_secs(30);
+
+const MIB: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BridgeTimeouts {
+    pub read: Duration,
+    pub write: Duration,
+    /// Added per started MiB of written content.
+    pub per_mib: Duration,
+}
+
+impl Default for BridgeTimeouts {
+    fn default() -> Self {
+        Self {
+            read: DEFAULT_READ_TIMEOUT,
+            write: DEFAULT_WRITE_TIMEOUT,
+            per_mib: DEFAULT_TIMEOUT_PER_MIB,
+        }
+    }
+}
+
+/// Parse whole seconds; `0` is rejected, since it would fail every request.
+pub fn parse_secs(value: &str) -> Option<Duration> {
+    value
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+}
+
+fn secs_from_env(name: &str, default: Duration) -> Duration {
+    match std::env::var(name) {
+        Ok(value) => parse_secs(&value).unwrap_or_else(|| {
+            warn!(%value, "invalid {name}; using {}s", default.as_secs());
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+impl BridgeTimeouts {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            read: secs_from_env(READ_TIMEOUT_ENV, defaults.read),
+            write: secs_from_env(WRITE_TIMEOUT_ENV, defaults.write),
+            per_mib: secs_from_env(TIMEOUT_PER_MIB_ENV, defaults.per_mib),
+        }
+    }
+
+    /// The variables that hand these limits to the helper.
+    pub fn to_env(&self) -> Vec<(String, String)> {
+        [
+            (READ_TIMEOUT_ENV, self.read),
+            (WRITE_TIMEOUT_ENV, self.write),
+            (TIMEOUT_PER_MIB_ENV, self.per_mib),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.as_secs().to_string()))
+        .collect()
+    }
+
+    /// Time allowed for writing `bytes` of content.
+    pub fn write_timeout(&self, bytes: usize) -> Duration {
+        let mib = u32::try_from(bytes.div_ceil(MIB)).unwrap_or(u32::MAX);
+        self.write
+            .saturating_add(self.per_mib.saturating_mul(mib))
+            .min(self.longest())
+    }
+
+    /// Time allowed for sending `bytes` of content without writing it.
+    pub fn transfer_timeout(&self, bytes: usize) -> Duration {
+        let mib = u32::try_from(bytes.div_ceil(MIB)).unwrap_or(u32::MAX);
+        self.read
+            .saturating_add(self.per_mib.saturating_mul(mib))
+            .min(self.longest())
+    }
+
+    /// Time allowed for listing or searching a tree.
+    pub fn list_timeout(&self) -> Duration {
+        LIST_TIMEOUT.max(self.read)
+    }
+
+    /// The longest any single bridge request may take, retry included.
+    pub fn longest(&self) -> Duration {
+        MAX_TIMEOUT
+            .max(self.write)
+            .max(self.list_timeout().saturating_mul(2))
+            .max(ASK_USER_TIMEOUT)
+    }
+
+    /// Time Codex allows one `acp_fs` tool call.
+    pub fn tool_timeout(&self) -> Duration {
+        self.longest().saturating_add(TOOL_TIMEOUT_MARGIN)
+    }
+}
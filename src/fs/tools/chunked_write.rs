@@ -0,0 +1,177 @@
+//! Chunked writes over the FS bridge.
+//!
+//! A bridge request is one JSON line, so writing a multi-MB file would build
+//! and parse one enormous line. Content over [`CHUNKED_WRITE_THRESHOLD`] is
+//! instead sent as a `write_begin` frame declaring the size, `write_append`
+//! frames of at most [`CHUNK_BYTES`] each carrying their byte offset, and a
+//! `write_commit` frame that runs the assembled content through the usual
+//! write path. `write_abort` drops an upload early; uploads left unfinished
+//! expire after [`UPLOAD_TTL`]. Smaller writes keep the single-line `write`.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::fs::text::floor_char_boundary;
+
+/// Content larger than this is written in chunks.
+pub const CHUNKED_WRITE_THRESHOLD: usize = 1024 * 1024;
+
+/// Largest chunk per `write_append` frame.
+pub const CHUNK_BYTES: usize = 256 * 1024;
+
+/// Largest content one upload may declare.
+pub const MAX_UPLOAD_BYTES: usize = 256 * 1024 * 1024;
+
+/// Unfinished uploads are dropped after this long.
+pub const UPLOAD_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Uploads a session may have open at once.
+const MAX_UPLOADS_PER_SESSION: usize = 4;
+
+/// Arguments of the chunked write frames.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadArgs {
+    /// Set on every frame after `write_begin`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_id: Option<String>,
+    /// Total content bytes, declared by `write_begin`.
+    #[serde(default)]
+    pub size: usize,
+    /// Byte offset of a `write_append` chunk.
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// Split `text` into pieces of at most `max` bytes, never inside a character.
+pub fn split_chunks(text: &str, max: usize) -> Vec<&str> {
+    let max = max.max(4);
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = floor_char_boundary(rest, max);
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+#[derive(Debug)]
+struct Upload {
+    session_id: String,
+    path: String,
+    size: usize,
+    content: String,
+    started: Instant,
+}
+
+/// Uploads in progress, by upload id.
+#[derive(Debug, Default)]
+pub struct Uploads {
+    pending: HashMap<String, Upload>,
+}
+
+impl Uploads {
+    /// Start an upload of `size` bytes to `path` and return its id.
+    pub fn begin(
+        &mut self,
+        session_id: &str,
+        path: &str,
+        size: usize,
+        now: Instant,
+    ) -> Result<String, String> {
+        self.pending
+            .retain(|_, upload| now.saturating_duration_since(upload.started) < UPLOAD_TTL);
+        if size > MAX_UPLOAD_BYTES {
+            return Err(format!(
+                "write of {size} bytes exceeds the {MAX_UPLOAD_BYTES}-byte limit"
+            ));
+        }
+        let open = self
+            .pending
+            .values()
+            .filter(|upload| upload.session_id == session_id)
+            .count();
+        if open >= MAX_UPLOADS_PER_SESSION {
+            return Err("too many chunked writes in progress".to_string());
+        }
+        let upload_id = Uuid::new_v4().to_string();
+        self.pending.insert(
+            upload_id.clone(),
+            Upload {
+                session_id: session_id.to_string(),
+                path: path.to_string(),
+                size,
+                content: String::new(),
+                started: now,
+            },
+        );
+        Ok(upload_id)
+    }
+
+    fn get_mut(&mut self, session_id: &str, upload_id: &str) -> Result<&mut Upload, String> {
+        self.pending
+            .get_mut(upload_id)
+            .filter(|upload| upload.session_id == session_id)
+            .ok_or_else(|| format!("unknown upload {upload_id}"))
+    }
+
+    /// Add `chunk` at byte `offset`, returning the bytes received so far.
+    pub fn append(
+        &mut self,
+        session_id: &str,
+        upload_id: &str,
+        offset: usize,
+        chunk: &str,
+    ) -> Result<usize, String> {
+        let upload = self.get_mut(session_id, upload_id)?;
+        if offset != upload.content.len() {
+            return Err(format!(
+                "chunk at offset {offset}, expected {}",
+                upload.content.len()
+            ));
+        }
+        if offset + chunk.len() > upload.size {
+            return Err(format!(
+                "chunk overruns the declared size of {} bytes",
+                upload.size
+            ));
+        }
+        upload.content.push_str(chunk);
+        Ok(upload.content.len())
+    }
+
+    /// Take a complete upload's path and content.
+    pub fn finish(
+        &mut self,
+        session_id: &str,
+        upload_id: &str,
+    ) -> Result<(String, String), String> {
+        let upload = self.get_mut(session_id, upload_id)?;
+        if upload.content.len() != upload.size {
+            return Err(format!(
+                "upload has {} of {} bytes",
+                upload.content.len(),
+                upload.size
+            ));
+        }
+        let upload = self
+            .pending
+            .remove(upload_id)
+            .ok_or_else(|| format!("unknown upload {upload_id}"))?;
+        Ok((upload.path, upload.content))
+    }
+
+    /// Drop an upload, returning whether it existed.
+    pub fn abort(&mut self, session_id: &str, upload_id: &str) -> bool {
+        if self.get_mut(session_id, upload_id).is_err() {
+            return false;
+        }
+        self.pending.remove(upload_id).is_some()
+    }
+}
@@ -0,0 +1,202 @@
+//! Reuse of read-only command results, behind the `acp_fs` `run_cached_command`
+//! tool.
+//!
+//! Exploratory turns often run the same `rg`, `ls`, or `cat` again. With the
+//! cache enabled (`CODEX_ACP_COMMAND_CACHE=1`, or
+//! `NewSessionRequest.meta.commandCache`), the model gets a tool that runs
+//! whitelisted read-only commands on local disk and answers a repeated command
+//! in the same directory with its earlier output. These commands cannot change
+//! the workspace, so, like the ones Codex runs without asking, they are not
+//! put to the user.
+//!
+//! Entries stay valid only while the workspace is unchanged: a generation
+//! counter stands in for a tree hash. It is bumped when a turn starts, by
+//! bridge writes, deletes, moves and new directories, and by the agent for
+//! applied patches and every other command.
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use agent_client_protocol::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::fs::text;
+
+/// Environment variable enabling the command cache (`1`/`true`).
+pub const COMMAND_CACHE_ENV: &str = "CODEX_ACP_COMMAND_CACHE";
+
+/// Name of the `acp_fs` tool that runs cached commands.
+pub const RUN_COMMAND_TOOL: &str = "run_cached_command";
+
+/// How long a command may run before it is killed.
+pub const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Output kept per command; longer output keeps its end.
+pub const MAX_OUTPUT_BYTES: usize = 16 * 1024;
+
+/// Whether new sessions cache command results, from `CODEX_ACP_COMMAND_CACHE`.
+pub fn default_command_cache() -> bool {
+    std::env::var(COMMAND_CACHE_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Read `NewSessionRequest.meta.commandCache`.
+pub fn command_cache_from_meta(meta: Option<&serde_json::Value>) -> Result<Option<bool>, Error> {
+    match meta.and_then(|m| m.get("commandCache")) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => value
+            .as_bool()
+            .map(Some)
+            .ok_or_else(|| Error::invalid_params().with_data("commandCache must be a boolean")),
+    }
+}
+
+/// Whether `command` (program and arguments, no shell) only reads the
+/// workspace, so repeating it gives the same output as long as nothing changed.
+pub fn is_cacheable(command: &[String]) -> bool {
+    let Some(program) = command.first().and_then(|p| p.rsplit('/').next()) else {
+        return false;
+    };
+    let args = &command[1..];
+    let has = |flags: &[&str]| {
+        args.iter().any(|arg| {
+            flags
+                .iter()
+                .any(|flag| arg == flag || arg.starts_with(&format!("{flag}=")))
+        })
+    };
+    match program {
+        "ls" | "cat" | "head" | "wc" | "grep" | "pwd" | "stat" | "file" => true,
+        // `--pre` runs a program on every file searched.
+        "rg" => !has(&["--pre"]),
+        // `tree -o` writes its listing to a file.
+        "tree" => !has(&["-o"]),
+        // `tail -f` never finishes with the same output.
+        "tail" => !has(&["-f", "-F", "--follow"]),
+        "find" => !has(&[
+            "-delete", "-exec", "-execdir", "-ok", "-okdir", "-fprint", "-fprint0", "-fprintf",
+            "-fls",
+        ]),
+        "git" => {
+            matches!(
+                args.first().map(String::as_str),
+                Some("status" | "diff" | "log" | "show" | "blame" | "ls-files" | "rev-parse")
+            ) && !has(&["--output", "--ext-diff"])
+        }
+        _ => false,
+    }
+}
+
+/// What a command printed and how it ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandOutput {
+    /// `None` when the command was killed (a signal or the timeout).
+    pub exit_code: Option<i32>,
+    /// Standard output followed by standard error, cut to its last
+    /// [`MAX_OUTPUT_BYTES`].
+    pub output: String,
+    /// Whether this is an earlier run's output.
+    #[serde(default)]
+    pub cached: bool,
+}
+
+/// Results of cacheable commands in one session.
+#[derive(Debug, Default)]
+pub struct CommandCache {
+    generation: u64,
+    entries: HashMap<(Vec<String>, PathBuf), CommandOutput>,
+}
+
+impl CommandCache {
+    /// The workspace generation; pass it to [`Self::store`] for a command
+    /// started now.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Forget everything; the workspace may have changed.
+    pub fn invalidate(&mut self) {
+        self.generation += 1;
+        self.entries.clear();
+    }
+
+    /// The earlier output of `command` in `cwd`, if any.
+    pub fn lookup(&self, command: &[String], cwd: &Path) -> Option<CommandOutput> {
+        self.entries
+            .get(&(command.to_vec(), cwd.to_path_buf()))
+            .map(|output| CommandOutput {
+                cached: true,
+                ..output.clone()
+            })
+    }
+
+    /// Remember the output of a command started at `generation`. Output of
+    /// a command the workspace changed under, or that was killed, is dropped.
+    pub fn store(
+        &mut self,
+        command: Vec<String>,
+        cwd: PathBuf,
+        generation: u64,
+        output: CommandOutput,
+    ) {
+        if generation == self.generation && output.exit_code.is_some() {
+            self.entries.insert((command, cwd), output);
+        }
+    }
+}
+
+/// Run `command` in `cwd` without a shell.
+pub async fn run(command: &[String], cwd: &Path) -> io::Result<CommandOutput> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .current_dir(cwd)
+        .env("GIT_PAGER", "cat")
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output();
+    let Ok(output) = tokio::time::timeout(COMMAND_TIMEOUT, output).await else {
+        return Ok(CommandOutput {
+            exit_code: None,
+            output: format!("timed out after {}s", COMMAND_TIMEOUT.as_secs()),
+            cached: false,
+        });
+    };
+    let output = output?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok(CommandOutput {
+        exit_code: output.status.code(),
+        output: text::elide_head(&text, MAX_OUTPUT_BYTES),
+        cached: false,
+    })
+}
+
+/// Render a command result for the model.
+pub fn format_output(command: &[String], result: &CommandOutput) -> String {
+    let status = match result.exit_code {
+        Some(code) => format!("exit code {code}"),
+        None => "killed".to_string(),
+    };
+    let origin = if result.cached {
+        "; reused from an earlier run, the workspace has not changed since"
+    } else {
+        ""
+    };
+    format!(
+        "$ {} ({status}{origin})\n{}",
+        command.join(" "),
+        result.output
+    )
+}
@@ -0,0 +1,70 @@
+//! The commands executed in a session, as the `acp_fs` `command_history`
+//! tool shows them to the model. The agent records them.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Commands listed by default by the `command_history` tool.
+pub const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+/// One finished command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandRecord {
+    pub command: String,
+    pub cwd: PathBuf,
+    pub exit_code: i32,
+    pub duration_ms: u64,
+    /// Tail of the command's output.
+    #[serde(default)]
+    pub output: String,
+}
+
+/// Arguments of the `command_history` tool.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CommandHistoryArgs {
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub failed_only: bool,
+}
+
+/// Render the most recent matching commands, oldest first.
+pub fn format_history(records: &[CommandRecord], args: &CommandHistoryArgs) -> String {
+    let matching: Vec<&CommandRecord> = records
+        .iter()
+        .filter(|record| !args.failed_only || record.exit_code != 0)
+        .collect();
+    if matching.is_empty() {
+        return if args.failed_only {
+            "No failed commands in this session.".to_string()
+        } else {
+            "No commands have run in this session yet.".to_string()
+        };
+    }
+    let limit = args.limit.unwrap_or(DEFAULT_HISTORY_LIMIT).max(1);
+    let shown = &matching[matching.len().saturating_sub(limit)..];
+
+    let mut text = format!("{} of {} command(s):\n", shown.len(), matching.len());
+    for record in shown {
+        let status = if record.exit_code == 0 {
+            "ok".to_string()
+        } else {
+            format!("exit {}", record.exit_code)
+        };
+        text.push_str(&format!(
+            "\n$ {}\n  cwd: {} · {status} · {} ms\n",
+            record.command,
+            record.cwd.display(),
+            record.duration_ms
+        ));
+        if !record.output.is_empty() {
+            for line in record.output.lines() {
+                text.push_str("  | ");
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+    }
+    text
+}
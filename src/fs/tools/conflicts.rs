@@ -0,0 +1,168 @@
+//! Arbitration of concurrent edits by the user and the agent.
+//!
+//! The `acp_fs` bridge remembers, per session and file, the content the
+//! agent last read in full or wrote. Before the next write it reads the file
+//! again (the client's buffer when available); if the user changed it in the
+//! meantime, the write pauses on a permission request offering to overwrite
+//! the user's edit, rebase it (three-way merge of both changes), or abort.
+//!
+//! Patches Codex applies itself are checked the same way when they come up
+//! for approval: a patch to a file the user changed since the agent saw it
+//! first asks whether to apply it on top of the user's edit or abort. Codex
+//! applies its hunks to the file as it is, so there is nothing to overwrite
+//! with. Patches Codex applies without asking are not checked.
+
+use std::path::Path;
+
+use agent_client_protocol::{
+    Diff, PermissionOption, PermissionOptionId, PermissionOptionKind, RequestPermissionOutcome,
+    RequestPermissionRequest, RequestPermissionResponse, SessionId, ToolCallContent, ToolCallId,
+    ToolCallLocation, ToolCallStatus, ToolCallUpdate, ToolCallUpdateFields, ToolKind,
+};
+use serde_json::json;
+
+const OVERWRITE_OPTION_ID: &str = "conflict-overwrite";
+const REBASE_OPTION_ID: &str = "conflict-rebase";
+const ABORT_OPTION_ID: &str = "conflict-abort";
+
+/// How to resolve a write that would clobber the user's edit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Overwrite,
+    Rebase,
+    Abort,
+}
+
+/// Whether writing over `current` loses changes made since the agent saw `base`.
+pub fn is_conflict(base: &str, current: &str, incoming: &str) -> bool {
+    current != base && current != incoming
+}
+
+/// Apply the agent's change (`base` → `incoming`) on top of the user's (`base` → `current`).
+///
+/// Fails when both touched the same lines.
+pub fn rebase(base: &str, current: &str, incoming: &str) -> Result<String, String> {
+    diffy::merge(base, current, incoming)
+        .map_err(|_| "both the user and the agent changed the same lines".to_string())
+}
+
+/// Permission request shown when the user edited `path` since the agent last saw it.
+pub fn conflict_request(
+    session_id: &SessionId,
+    call_id: &str,
+    path: &Path,
+    base: &str,
+    current: &str,
+    incoming: &str,
+) -> RequestPermissionRequest {
+    request(
+        session_id,
+        call_id,
+        path,
+        vec![
+            side_diff(path, base, current, "user"),
+            side_diff(path, base, incoming, "agent"),
+        ],
+        vec![
+            option(
+                OVERWRITE_OPTION_ID,
+                "Overwrite my edit",
+                PermissionOptionKind::AllowOnce,
+            ),
+            option(
+                REBASE_OPTION_ID,
+                "Rebase my edit",
+                PermissionOptionKind::AllowOnce,
+            ),
+            option(ABORT_OPTION_ID, "Abort", PermissionOptionKind::RejectOnce),
+        ],
+    )
+}
+
+/// Permission request shown before Codex patches `path`, which the user edited
+/// since the agent last saw it. The patch itself is shown by its own approval.
+pub fn patch_conflict_request(
+    session_id: &SessionId,
+    call_id: &str,
+    path: &Path,
+    base: &str,
+    current: &str,
+) -> RequestPermissionRequest {
+    request(
+        session_id,
+        call_id,
+        path,
+        vec![side_diff(path, base, current, "user")],
+        vec![
+            option(
+                REBASE_OPTION_ID,
+                "Apply on top of my edit",
+                PermissionOptionKind::AllowOnce,
+            ),
+            option(ABORT_OPTION_ID, "Abort", PermissionOptionKind::RejectOnce),
+        ],
+    )
+}
+
+fn request(
+    session_id: &SessionId,
+    call_id: &str,
+    path: &Path,
+    content: Vec<ToolCallContent>,
+    options: Vec<PermissionOption>,
+) -> RequestPermissionRequest {
+    RequestPermissionRequest {
+        session_id: session_id.clone(),
+        tool_call: ToolCallUpdate {
+            id: ToolCallId(call_id.into()),
+            fields: ToolCallUpdateFields {
+                kind: Some(ToolKind::Edit),
+                status: Some(ToolCallStatus::Pending),
+                title: Some(format!(
+                    "Conflict: {} changed since the agent read it",
+                    path.display()
+                )),
+                content: Some(content),
+                locations: Some(vec![ToolCallLocation {
+                    path: path.to_path_buf(),
+                    line: None,
+                    meta: None,
+                }]),
+                ..Default::default()
+            },
+            meta: Some(json!({ "write_conflict": { "path": path } })),
+        },
+        options,
+        meta: None,
+    }
+}
+
+fn side_diff(path: &Path, old: &str, new: &str, side: &str) -> ToolCallContent {
+    ToolCallContent::from(Diff {
+        path: path.to_path_buf(),
+        old_text: Some(old.to_string()),
+        new_text: new.to_string(),
+        meta: Some(json!({ "conflict_side": side })),
+    })
+}
+
+fn option(id: &str, name: &str, kind: PermissionOptionKind) -> PermissionOption {
+    PermissionOption {
+        id: PermissionOptionId(id.into()),
+        name: name.into(),
+        kind,
+        meta: None,
+    }
+}
+
+/// Map the client's answer to a resolution; cancellation aborts.
+pub fn resolution(response: &RequestPermissionResponse) -> Resolution {
+    match &response.outcome {
+        RequestPermissionOutcome::Selected { option_id } => match option_id.0.as_ref() {
+            OVERWRITE_OPTION_ID => Resolution::Overwrite,
+            REBASE_OPTION_ID => Resolution::Rebase,
+            _ => Resolution::Abort,
+        },
+        RequestPermissionOutcome::Cancelled => Resolution::Abort,
+    }
+}
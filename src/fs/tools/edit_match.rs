@@ -0,0 +1,245 @@
+//! Locating `old_string` for the `acp_fs` edit tools.
+//!
+//! Models often reproduce a snippet with different indentation or trailing
+//! whitespace, so an exact search fails on text that is plainly there. When
+//! `old_string` is not found verbatim, its lines are compared with the file's
+//! lines ignoring whitespace; failing that, a block of about the same length
+//! whose first and last lines match is accepted when at least
+//! [`FUZZY_THRESHOLD`] of its lines agree. Both fallbacks only apply when they
+//! find exactly one place. The replacement is re-indented to the file's
+//! indentation and line endings.
+
+use serde::Serialize;
+
+/// Share of lines that must agree for an anchored fuzzy match.
+pub const FUZZY_THRESHOLD: f64 = 0.75;
+
+/// Fewest lines `old_string` needs for an anchored fuzzy match.
+const FUZZY_MIN_LINES: usize = 3;
+
+/// How `old_string` was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Exact,
+    /// The lines matched once whitespace was ignored.
+    Whitespace,
+    /// The first and last lines matched, and most lines in between.
+    Fuzzy,
+}
+
+/// Why `old_string` could not be located.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchError {
+    NotFound,
+    /// The fallback matched this many places.
+    Ambiguous(usize),
+}
+
+impl std::fmt::Display for MatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(
+                f,
+                "The provided `old_string` does not appear in the file. No edits were applied."
+            ),
+            Self::Ambiguous(count) => write!(
+                f,
+                "The provided `old_string` does not appear verbatim and matches {count} places \
+                 when whitespace is ignored; include more surrounding lines. No edits were applied."
+            ),
+        }
+    }
+}
+
+/// Replace the first occurrence of `old`, falling back to whitespace and
+/// fuzzy matching when there is none.
+pub fn replace_once(
+    content: &str,
+    old: &str,
+    new: &str,
+) -> Result<(String, MatchKind), MatchError> {
+    if let Some(index) = content.find(old) {
+        let mut replaced = content.to_string();
+        replaced.replace_range(index..index + old.len(), new);
+        return Ok((replaced, MatchKind::Exact));
+    }
+
+    let lines = line_spans(content);
+    let old_lines: Vec<&str> = old.lines().collect();
+    if old_lines.iter().all(|line| line.trim().is_empty()) {
+        return Err(MatchError::NotFound);
+    }
+    let (first, count, kind) = match locate_lines(content, &lines, &old_lines)? {
+        Some((first, count)) => (first, count, MatchKind::Whitespace),
+        None => {
+            let (first, count) =
+                locate_fuzzy(content, &lines, &old_lines)?.ok_or(MatchError::NotFound)?;
+            (first, count, MatchKind::Fuzzy)
+        }
+    };
+
+    let last = &lines[first + count - 1];
+    let end = if old.ends_with('\n') {
+        last.end_with_newline
+    } else {
+        last.end
+    };
+    let range = lines[first].start..end;
+    let replacement = adapt_replacement(&content[range.clone()], old_lines[0], new);
+    let mut replaced = content.to_string();
+    replaced.replace_range(range, &replacement);
+    Ok((replaced, kind))
+}
+
+struct LineSpan {
+    start: usize,
+    /// End of the line's text, before `\r\n` or `\n`.
+    end: usize,
+    end_with_newline: usize,
+}
+
+fn line_spans(content: &str) -> Vec<LineSpan> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for line in content.split_inclusive('\n') {
+        let text = line
+            .strip_suffix('\n')
+            .map(|text| text.strip_suffix('\r').unwrap_or(text))
+            .unwrap_or(line);
+        spans.push(LineSpan {
+            start,
+            end: start + text.len(),
+            end_with_newline: start + line.len(),
+        });
+        start += line.len();
+    }
+    spans
+}
+
+fn normalize(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The unique run of lines equal to `old_lines` ignoring whitespace.
+fn locate_lines(
+    content: &str,
+    lines: &[LineSpan],
+    old_lines: &[&str],
+) -> Result<Option<(usize, usize)>, MatchError> {
+    let count = old_lines.len();
+    if count > lines.len() {
+        return Ok(None);
+    }
+    let wanted: Vec<String> = old_lines.iter().map(|line| normalize(line)).collect();
+    let have: Vec<String> = lines
+        .iter()
+        .map(|span| normalize(&content[span.start..span.end]))
+        .collect();
+    let starts: Vec<usize> = (0..=lines.len() - count)
+        .filter(|start| have[*start..*start + count] == wanted[..])
+        .collect();
+    match starts.as_slice() {
+        [] => Ok(None),
+        [start] => Ok(Some((*start, count))),
+        _ => Err(MatchError::Ambiguous(starts.len())),
+    }
+}
+
+/// The unique best block, of roughly `old_lines.len()` lines, whose first
+/// and last lines match and whose lines agree at least [`FUZZY_THRESHOLD`].
+fn locate_fuzzy(
+    content: &str,
+    lines: &[LineSpan],
+    old_lines: &[&str],
+) -> Result<Option<(usize, usize)>, MatchError> {
+    let count = old_lines.len();
+    let wanted: Vec<String> = old_lines.iter().map(|line| normalize(line)).collect();
+    let (Some(first), Some(last)) = (wanted.first(), wanted.last()) else {
+        return Ok(None);
+    };
+    if count < FUZZY_MIN_LINES || first.is_empty() || last.is_empty() {
+        return Ok(None);
+    }
+    let have: Vec<String> = lines
+        .iter()
+        .map(|span| normalize(&content[span.start..span.end]))
+        .collect();
+    let slack = (count / 4).max(1);
+
+    let mut best: Option<(f64, usize, usize)> = None;
+    let mut ties = 0;
+    for start in (0..have.len()).filter(|start| have[*start] == *first) {
+        let shortest = (count - 1).saturating_sub(slack).max(1);
+        for len in shortest..=count - 1 + slack {
+            let end = start + len;
+            if end >= have.len() || have[end] != *last {
+                continue;
+            }
+            let score = similarity(&wanted, &have[start..=end]);
+            if score < FUZZY_THRESHOLD {
+                continue;
+            }
+            match best {
+                Some((best_score, ..)) if score < best_score => {}
+                Some((best_score, ..)) if score == best_score => ties += 1,
+                _ => {
+                    best = Some((score, start, len + 1));
+                    ties = 1;
+                }
+            }
+        }
+    }
+    match best {
+        None => Ok(None),
+        Some(_) if ties > 1 => Err(MatchError::Ambiguous(ties)),
+        Some((_, start, len)) => Ok(Some((start, len))),
+    }
+}
+
+/// `2 * LCS / (a + b)` over lines.
+fn similarity(a: &[String], b: &[String]) -> f64 {
+    let mut row = vec![0usize; b.len() + 1];
+    for line in a {
+        let mut diagonal = 0;
+        for (j, other) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if line == other {
+                diagonal + 1
+            } else {
+                above.max(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    2.0 * row[b.len()] as f64 / (a.len() + b.len()) as f64
+}
+
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// `new` with the indentation of `old_first` swapped for the matched text's,
+/// and with the matched text's line endings.
+fn adapt_replacement(matched: &str, old_first: &str, new: &str) -> String {
+    let from = indentation(old_first);
+    let to = indentation(matched.lines().next().unwrap_or_default());
+    let reindent = from != to
+        && new
+            .lines()
+            .all(|line| line.trim().is_empty() || line.starts_with(from));
+    let mut replacement = if reindent {
+        new.split_inclusive('\n')
+            .map(|line| match line.strip_prefix(from) {
+                Some(rest) if !line.trim().is_empty() => format!("{to}{rest}"),
+                _ => line.to_string(),
+            })
+            .collect()
+    } else {
+        new.to_string()
+    };
+    if matched.contains("\r\n") && !replacement.contains("\r\n") {
+        replacement = replacement.replace('\n', "\r\n");
+    }
+    replacement
+}
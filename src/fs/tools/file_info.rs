@@ -0,0 +1,175 @@
+//! File facts for the `acp_fs` `file_info` tool.
+//!
+//! Lets the model decide how to read a file (all at once, paged, or not at
+//! all) without reading it through the client. The bridge looks at the local
+//! disk: whether the path exists, its type, size, and modification time, and
+//! for regular files whether the first [`SNIFF_BYTES`] look binary and how
+//! many lines the file has. Lines are counted by streaming the file, and only
+//! for files up to [`MAX_LINE_COUNT_BYTES`].
+
+use std::{
+    io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+
+use super::listing::EntryKind;
+
+/// Bytes inspected to decide whether a file is binary.
+pub const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Larger files are not read to count their lines.
+pub const MAX_LINE_COUNT_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileInfo {
+    pub exists: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<EntryKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Last modification, in seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary: Option<bool>,
+    /// Lines in a text file; a last line without a newline counts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<u64>,
+}
+
+/// Whether `head`, the start of a file, looks binary: it contains a NUL
+/// byte or is not UTF-8 (ignoring a character cut off at the end).
+pub fn looks_binary(head: &[u8]) -> bool {
+    if head.contains(&0) {
+        return true;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => false,
+        Err(err) => err.error_len().is_some(),
+    }
+}
+
+async fn count_lines(path: &Path) -> io::Result<u64> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut lines = 0;
+    let mut last = None;
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        lines += buf[..read].iter().filter(|b| **b == b'\n').count() as u64;
+        last = Some(buf[read - 1]);
+    }
+    Ok(match last {
+        Some(b'\n') | None => lines,
+        Some(_) => lines + 1,
+    })
+}
+
+/// Facts about `path`; a missing path is reported, not an error.
+pub async fn file_info(path: &Path) -> io::Result<FileInfo> {
+    let metadata = match tokio::fs::symlink_metadata(path).await {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Ok(FileInfo {
+                exists: false,
+                kind: None,
+                size: None,
+                modified: None,
+                binary: None,
+                lines: None,
+            });
+        }
+        Err(err) => return Err(err),
+    };
+    let file_type = metadata.file_type();
+    let kind = if file_type.is_symlink() {
+        EntryKind::Symlink
+    } else if file_type.is_dir() {
+        EntryKind::Directory
+    } else if file_type.is_file() {
+        EntryKind::File
+    } else {
+        EntryKind::Other
+    };
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_secs());
+    let mut info = FileInfo {
+        exists: true,
+        kind: Some(kind),
+        size: (kind == EntryKind::File).then_some(metadata.len()),
+        modified,
+        binary: None,
+        lines: None,
+    };
+    if kind != EntryKind::File {
+        return Ok(info);
+    }
+
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    tokio::fs::File::open(path)
+        .await?
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut head)
+        .await?;
+    let binary = looks_binary(&head);
+    info.binary = Some(binary);
+    if !binary && metadata.len() <= MAX_LINE_COUNT_BYTES {
+        info.lines = Some(count_lines(path).await?);
+    }
+    Ok(info)
+}
+
+/// Summary for the model, one fact per line.
+pub fn format_info(path: &str, info: &FileInfo, now: SystemTime) -> String {
+    if !info.exists {
+        return format!("{path}: does not exist\n");
+    }
+    let kind = match info.kind {
+        Some(EntryKind::File) => "file",
+        Some(EntryKind::Directory) => "directory",
+        Some(EntryKind::Symlink) => "symlink",
+        _ => "other",
+    };
+    let mut text = format!("{path}: {kind}\n");
+    if let Some(size) = info.size {
+        text.push_str(&format!("size: {size} bytes\n"));
+    }
+    if let Some(lines) = info.lines {
+        text.push_str(&format!("lines: {lines}\n"));
+    } else if info.binary == Some(false) {
+        text.push_str("lines: not counted (file too large)\n");
+    }
+    if let Some(binary) = info.binary {
+        text.push_str(&format!("binary: {}\n", if binary { "yes" } else { "no" }));
+    }
+    if let Some(modified) = info.modified {
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        text.push_str(&format!(
+            "modified: {} ago\n",
+            format_age(now.saturating_sub(modified))
+        ));
+    }
+    text
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
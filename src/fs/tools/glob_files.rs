@@ -0,0 +1,135 @@
+//! File matching for the `acp_fs` `glob_files` tool.
+//!
+//! Expands a pattern such as `src/**/*.rs` against the files below a
+//! directory (the workspace root by default), walking them like
+//! `list_directory` does, and returns the matches most recently modified
+//! first. Patterns use `**`, `*`, and `?`; one without a `/` matches file
+//! names at any depth.
+
+use std::{
+    io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    listing::{self, EntryKind},
+    protected::glob_matches,
+};
+
+pub const DEFAULT_LIMIT: usize = 200;
+pub const MAX_LIMIT: usize = 1000;
+
+/// Entries walked at most per call.
+const MAX_WALK_ENTRIES: usize = 100_000;
+
+/// Arguments of a `glob_files` request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlobArgs {
+    pub pattern: String,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub include_ignored: bool,
+}
+
+/// One matched file; `path` is relative to the searched directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlobMatch {
+    pub path: String,
+    /// Last modification, in seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlobResults {
+    pub matches: Vec<GlobMatch>,
+    /// Files matched in total, including those past `limit`.
+    pub total: usize,
+    /// False when the walk stopped before covering every file.
+    pub complete: bool,
+}
+
+fn unix_secs(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+/// Files below `dir` matching `args.pattern`, newest first.
+pub async fn glob_files(
+    workspace_root: &Path,
+    dir: &Path,
+    args: &GlobArgs,
+) -> io::Result<GlobResults> {
+    let pattern = args.pattern.trim();
+    if pattern.is_empty() {
+        return Err(io::Error::other("pattern must not be empty"));
+    }
+    let limit = args.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let (entries, complete) = listing::walk(
+        workspace_root,
+        dir,
+        usize::MAX,
+        args.include_ignored,
+        MAX_WALK_ENTRIES,
+    )
+    .await?;
+
+    let mut matches = Vec::new();
+    for (path, entry) in entries {
+        if entry.kind != EntryKind::File {
+            continue;
+        }
+        let segments: Vec<&str> = entry.path.split('/').collect();
+        if !glob_matches(pattern, &segments) {
+            continue;
+        }
+        let modified = tokio::fs::metadata(&path)
+            .await
+            .ok()
+            .and_then(|meta| meta.modified().ok())
+            .and_then(unix_secs);
+        matches.push(GlobMatch {
+            path: entry.path,
+            modified,
+            size: entry.size,
+        });
+    }
+    // Newest first; ties and unknown times keep walk (name) order.
+    matches.sort_by(|a, b| b.modified.cmp(&a.modified));
+    let total = matches.len();
+    matches.truncate(limit);
+    Ok(GlobResults {
+        matches,
+        total,
+        complete,
+    })
+}
+
+/// Results text for the model: one path per line, newest first.
+pub fn format_results(results: &GlobResults) -> String {
+    let mut text = String::new();
+    for m in &results.matches {
+        text.push_str(&m.path);
+        text.push('\n');
+    }
+    if results.total == 0 {
+        text.push_str("No files matched.\n");
+    } else if results.matches.len() < results.total {
+        text.push_str(&format!(
+            "\n[Showing the {} most recently modified of {} matches; narrow the pattern or raise limit.]\n",
+            results.matches.len(),
+            results.total
+        ));
+    }
+    if !results.complete {
+        text.push_str(&format!(
+            "\n[Only the first {MAX_WALK_ENTRIES} entries were walked; use a narrower path.]\n"
+        ));
+    }
+    text
+}
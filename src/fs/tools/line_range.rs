@@ -0,0 +1,51 @@
+//! Reading a range of lines without loading the whole file.
+//!
+//! The bridge's local fallback serves `read_text_file` requests for a few
+//! lines of files that may be huge, such as logs. The file is streamed: lines
+//! before `line` are skipped without decoding, and reading stops after
+//! `limit` lines or once [`MAX_RANGE_BYTES`] have been collected.
+
+use std::io;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// Content collected for one ranged read before it stops early. Well above
+/// what the `acp_fs` server returns per read, so it still sees the cut.
+pub const MAX_RANGE_BYTES: usize = 256 * 1024;
+
+/// Lines `start_line..start_line + limit` (1-based) of `reader`, without
+/// their line endings and joined with `\n`.
+pub async fn read_line_range<R>(
+    reader: &mut R,
+    start_line: u32,
+    limit: Option<u32>,
+    max_bytes: usize,
+) -> io::Result<String>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let skip = start_line.saturating_sub(1);
+    let limit = limit.unwrap_or(u32::MAX);
+    let mut buf = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+    let mut bytes = 0;
+    let mut index: u32 = 0;
+    while (lines.len() as u32) < limit && bytes <= max_bytes {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf).await? == 0 {
+            break;
+        }
+        index += 1;
+        if index <= skip {
+            continue;
+        }
+        let mut line = buf.as_slice();
+        line = line.strip_suffix(b"\n").unwrap_or(line);
+        line = line.strip_suffix(b"\r").unwrap_or(line);
+        let line = String::from_utf8(line.to_vec())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        bytes += line.len() + 1;
+        lines.push(line);
+    }
+    Ok(lines.join("\n"))
+}
@@ -0,0 +1,343 @@
+//! Directory listings for the `acp_fs` `list_directory` tool.
+//!
+//! ACP has no directory listing, so the bridge walks the local disk. Entries
+//! come depth-first in name order with their type and, for files, their size.
+//! `.git` is always skipped, and so is anything ignored by a `.gitignore`
+//! between the workspace root and the listed directory or inside it, unless
+//! `include_ignored` is set. Symlinks are listed but not followed. Results are
+//! paged with `offset`/`limit`, like `read_text_file` pages lines.
+
+use std::{
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::protected::{match_name, match_segments};
+
+/// Levels listed when the caller does not say.
+pub const DEFAULT_DEPTH: usize = 1;
+pub const MAX_DEPTH: usize = 10;
+
+/// Entries returned per page when the caller does not say.
+pub const DEFAULT_LIMIT: usize = 200;
+pub const MAX_LIMIT: usize = 1000;
+
+/// Entries walked at most per call; larger trees are reported incomplete.
+pub const MAX_WALK_ENTRIES: usize = 20_000;
+
+/// Arguments of a `list_directory` request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListArgs {
+    #[serde(default)]
+    pub depth: Option<usize>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub include_ignored: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+    Other,
+}
+
+/// One listed entry; `path` is relative to the listed directory, `/`-separated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirEntry {
+    pub path: String,
+    pub kind: EntryKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+/// One page of a listing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Listing {
+    pub entries: Vec<DirEntry>,
+    pub depth: usize,
+    pub offset: usize,
+    /// Entries found in total, across all pages.
+    pub total: usize,
+    /// False when the walk stopped at [`MAX_WALK_ENTRIES`].
+    pub complete: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IgnoreRule {
+    /// Directory of the `.gitignore` the rule comes from.
+    base: PathBuf,
+    pattern: String,
+    /// Matched against the whole path below `base` rather than the name.
+    anchored: bool,
+    dir_only: bool,
+    negated: bool,
+}
+
+/// `.gitignore` rules collected so far; later rules win.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Add the rules of the `.gitignore` in `base`.
+    pub fn add(&mut self, base: &Path, gitignore: &str) {
+        for line in gitignore.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let anchored = line.contains('/');
+            let pattern = line.trim_start_matches('/');
+            if pattern.is_empty() {
+                continue;
+            }
+            self.rules.push(IgnoreRule {
+                base: base.to_path_buf(),
+                pattern: pattern.to_string(),
+                anchored,
+                dir_only,
+                negated,
+            });
+        }
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(&rule.base) else {
+                continue;
+            };
+            let segments = segments(relative);
+            let Some(name) = segments.last() else {
+                continue;
+            };
+            let matched = if rule.anchored {
+                let parts: Vec<&str> = rule.pattern.split('/').filter(|p| !p.is_empty()).collect();
+                let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+                match_segments(&parts, &segments)
+            } else {
+                match_name(&rule.pattern, name)
+            };
+            if matched {
+                ignored = !rule.negated;
+            }
+        }
+        ignored
+    }
+}
+
+fn segments(path: &Path) -> Vec<String> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+async fn add_gitignore(rules: &mut IgnoreRules, dir: &Path) {
+    if let Ok(text) = tokio::fs::read_to_string(dir.join(".gitignore")).await {
+        rules.add(dir, &text);
+    }
+}
+
+/// Children of `dir` worth listing, sorted by name.
+async fn children(
+    dir: &Path,
+    rules: &IgnoreRules,
+    include_ignored: bool,
+) -> io::Result<Vec<(PathBuf, EntryKind, Option<u64>)>> {
+    let mut found = Vec::new();
+    let mut read_dir = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type().await else {
+            continue;
+        };
+        let kind = if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else if file_type.is_dir() {
+            EntryKind::Directory
+        } else if file_type.is_file() {
+            EntryKind::File
+        } else {
+            EntryKind::Other
+        };
+        let path = entry.path();
+        if !include_ignored && rules.is_ignored(&path, kind == EntryKind::Directory) {
+            continue;
+        }
+        let size = match kind {
+            EntryKind::File => entry.metadata().await.ok().map(|m| m.len()),
+            _ => None,
+        };
+        found.push((path, kind, size));
+    }
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(found)
+}
+
+/// Entries under `dir`, `depth` levels deep, in listing order, each with its
+/// full path, and whether the walk finished before reaching `max_entries`.
+pub async fn walk(
+    workspace_root: &Path,
+    dir: &Path,
+    depth: usize,
+    include_ignored: bool,
+    max_entries: usize,
+) -> io::Result<(Vec<(PathBuf, DirEntry)>, bool)> {
+    if !tokio::fs::metadata(dir).await?.is_dir() {
+        return Err(io::Error::other(format!(
+            "{} is not a directory",
+            dir.display()
+        )));
+    }
+
+    let mut rules = IgnoreRules::default();
+    if !include_ignored && let Ok(relative) = dir.strip_prefix(workspace_root) {
+        let mut ancestor = workspace_root.to_path_buf();
+        for component in relative.components() {
+            add_gitignore(&mut rules, &ancestor).await;
+            ancestor.push(component);
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut complete = true;
+    // Depth-first: a directory's children are pushed in reverse so they pop in order.
+    let mut pending: Vec<(PathBuf, EntryKind, Option<u64>, usize)> = Vec::new();
+    let mut expand = Some((dir.to_path_buf(), 0));
+    loop {
+        if let Some((expand_dir, level)) = expand.take() {
+            if !include_ignored {
+                add_gitignore(&mut rules, &expand_dir).await;
+            }
+            match children(&expand_dir, &rules, include_ignored).await {
+                Ok(found) => pending.extend(
+                    found
+                        .into_iter()
+                        .rev()
+                        .map(|(path, kind, size)| (path, kind, size, level + 1)),
+                ),
+                // Unreadable subdirectories are listed without their contents.
+                Err(err) if level == 0 => return Err(err),
+                Err(_) => {}
+            }
+        }
+        let Some((path, kind, size, level)) = pending.pop() else {
+            break;
+        };
+        if entries.len() >= max_entries {
+            complete = false;
+            break;
+        }
+        let relative = segments(path.strip_prefix(dir).unwrap_or(&path)).join("/");
+        if kind == EntryKind::Directory && level < depth {
+            expand = Some((path.clone(), level));
+        }
+        entries.push((
+            path,
+            DirEntry {
+                path: relative,
+                kind,
+                size,
+            },
+        ));
+    }
+    Ok((entries, complete))
+}
+
+/// List `dir` (inside or outside `workspace_root`) and return one page.
+pub async fn list_directory(
+    workspace_root: &Path,
+    dir: &Path,
+    args: &ListArgs,
+) -> io::Result<Listing> {
+    let depth = args.depth.unwrap_or(DEFAULT_DEPTH).clamp(1, MAX_DEPTH);
+    let offset = args.offset.unwrap_or(0);
+    let limit = args.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let (entries, complete) = walk(
+        workspace_root,
+        dir,
+        depth,
+        args.include_ignored,
+        MAX_WALK_ENTRIES,
+    )
+    .await?;
+
+    let total = entries.len();
+    let start = offset.min(total);
+    let end = offset.saturating_add(limit).min(total);
+    Ok(Listing {
+        entries: entries
+            .into_iter()
+            .skip(start)
+            .take(end - start)
+            .map(|(_, entry)| entry)
+            .collect(),
+        depth,
+        offset,
+        total,
+        complete,
+        next_offset: (end < total).then_some(end),
+    })
+}
+
+/// Listing text for the model: one entry per line, directories ending in `/`.
+pub fn format_listing(listing: &Listing) -> String {
+    let mut text = String::new();
+    for entry in &listing.entries {
+        match (entry.kind, entry.size) {
+            (EntryKind::Directory, _) => text.push_str(&format!("{}/\n", entry.path)),
+            (EntryKind::Symlink, _) => text.push_str(&format!("{} (symlink)\n", entry.path)),
+            (_, Some(size)) => text.push_str(&format!("{} ({size} bytes)\n", entry.path)),
+            (_, None) => text.push_str(&format!("{}\n", entry.path)),
+        }
+    }
+    if listing.total == 0 {
+        text.push_str("(empty directory)\n");
+    } else if listing.entries.is_empty() {
+        text.push_str(&format!(
+            "(no entries at offset {}; the listing has {})\n",
+            listing.offset, listing.total
+        ));
+    }
+    if let Some(next) = listing.next_offset {
+        text.push_str(&format!(
+            "\n[Showing entries {}-{} of {}. Call list_directory with offset={next} for more.]\n",
+            listing.offset + 1,
+            next,
+            listing.total
+        ));
+    }
+    if !listing.complete {
+        text.push_str(&format!(
+            "\n[Stopped after {MAX_WALK_ENTRIES} entries; list a subdirectory or use a smaller depth.]\n"
+        ));
+    }
+    text
+}
@@ -0,0 +1,22 @@
+//! Logic behind the `acp_fs` tools, shared by the bridge, the MCP server and
+//! the agent.
+
+pub mod audit;
+pub mod binary_read;
+pub mod bridge_timeouts;
+pub mod chunked_write;
+pub mod command_cache;
+pub mod command_history;
+pub mod conflicts;
+pub mod edit_match;
+pub mod file_info;
+pub mod glob_files;
+pub mod line_range;
+pub mod listing;
+pub mod protected;
+pub mod search;
+pub mod tail_read;
+pub mod undo;
+pub mod unified_diff;
+pub mod workspace_scope;
+pub mod write_approval;
@@ -0,0 +1,170 @@
+//! Paths that always need explicit approval to change.
+//!
+//! `CODEX_ACP_PROTECTED_PATHS` holds comma-separated globs (e.g.
+//! `.git/**,**/*.pem,infra/prod/**`), matched against workspace-relative
+//! paths (absolute paths outside the workspace are matched as-is). A path is
+//! protected when it or one of its parent directories matches; patterns
+//! without a `/` match a file or directory name at any depth.
+//!
+//! Writes through the `acp_fs` bridge and Codex patch approvals touching a
+//! protected path turn into a high-risk permission request offering only
+//! "allow once" and "reject", whatever the session's approval policy. A write
+//! the user does not explicitly allow, or that cannot be asked about, is
+//! refused.
+
+use std::path::{Component, Path};
+
+use agent_client_protocol::{
+    PermissionOption, PermissionOptionId, PermissionOptionKind, RequestPermissionOutcome,
+    RequestPermissionRequest, RequestPermissionResponse,
+};
+use serde_json::json;
+
+/// Environment variable with comma-separated protected globs.
+pub const PROTECTED_PATHS_ENV: &str = "CODEX_ACP_PROTECTED_PATHS";
+
+const ALLOW_OPTION_ID: &str = "allow-protected";
+const REJECT_OPTION_ID: &str = "reject-protected";
+
+/// Configured protected globs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProtectedPaths {
+    patterns: Vec<String>,
+}
+
+impl ProtectedPaths {
+    pub fn new<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            patterns: patterns
+                .into_iter()
+                .map(Into::into)
+                .map(|p| p.trim().to_string())
+                .filter(|p| !p.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        std::env::var(PROTECTED_PATHS_ENV)
+            .map(|raw| Self::new(raw.split(',')))
+            .unwrap_or_default()
+    }
+
+    /// The first pattern protecting `path`, if any.
+    pub fn matching(&self, root: &Path, path: &Path) -> Option<&str> {
+        if self.patterns.is_empty() {
+            return None;
+        }
+        let relative = path.strip_prefix(root).ok();
+        let segments: Vec<String> = relative
+            .unwrap_or(path)
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+        self.patterns
+            .iter()
+            .find(|pattern| {
+                // Absolute patterns only apply to paths outside the workspace.
+                if pattern.starts_with('/') != relative.is_none() {
+                    return false;
+                }
+                (1..=segments.len()).any(|len| glob_matches(pattern, &segments[..len]))
+            })
+            .map(String::as_str)
+    }
+}
+
+/// Match path segments against a glob supporting `**`, `*`, and `?`.
+pub fn glob_matches(pattern: &str, segments: &[&str]) -> bool {
+    let pattern = pattern.trim_start_matches("./");
+    let pattern = match pattern.strip_suffix('/') {
+        Some(dir) => format!("{dir}/**"),
+        None if !pattern.contains('/') => format!("**/{pattern}"),
+        None => pattern.to_string(),
+    };
+    let parts: Vec<&str> = pattern.split('/').filter(|p| !p.is_empty()).collect();
+    match_segments(&parts, segments)
+}
+
+/// Match path segments against already split glob parts.
+pub fn match_segments(pattern: &[&str], segments: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => segments.is_empty(),
+        Some((&"**", rest)) => {
+            (0..=segments.len()).any(|skip| match_segments(rest, &segments[skip..]))
+        }
+        Some((part, rest)) => segments
+            .split_first()
+            .is_some_and(|(segment, tail)| match_name(part, segment) && match_segments(rest, tail)),
+    }
+}
+
+/// Match one path segment against a glob without `/`.
+pub fn match_name(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    fn go(p: &[char], n: &[char]) -> bool {
+        match p.split_first() {
+            None => n.is_empty(),
+            Some((&'*', rest)) => (0..=n.len()).any(|skip| go(rest, &n[skip..])),
+            Some((&'?', rest)) => !n.is_empty() && go(rest, &n[1..]),
+            Some((c, rest)) => n.first() == Some(c) && go(rest, &n[1..]),
+        }
+    }
+    go(&pattern, &name)
+}
+
+/// Turn a permission request into a high-risk one for the protected `hits`
+/// (`(path, pattern)` pairs), offering only "allow once" and "reject".
+pub fn escalate(request: &mut RequestPermissionRequest, hits: &[(String, String)]) {
+    let reasons: Vec<String> = hits
+        .iter()
+        .map(|(path, pattern)| format!("changes protected path {path} (matches `{pattern}`)"))
+        .collect();
+    let paths: Vec<&str> = hits.iter().map(|(path, _)| path.as_str()).collect();
+
+    let fields = &mut request.tool_call.fields;
+    let title = fields.title.take().unwrap_or_else(|| "Edit".to_string());
+    fields.title = Some(format!("⚠️ Protected: {title} ({})", paths.join(", ")));
+
+    request.options = vec![
+        PermissionOption {
+            id: PermissionOptionId(ALLOW_OPTION_ID.into()),
+            name: "Allow this change".into(),
+            kind: PermissionOptionKind::AllowOnce,
+            meta: None,
+        },
+        PermissionOption {
+            id: PermissionOptionId(REJECT_OPTION_ID.into()),
+            name: "Reject".into(),
+            kind: PermissionOptionKind::RejectOnce,
+            meta: None,
+        },
+    ];
+
+    // Same shape as the agent's command risk assessment.
+    let risk = json!({ "level": "high", "reasons": reasons });
+    let mut meta = request.tool_call.meta.take().unwrap_or_else(|| json!({}));
+    if let Some(map) = meta.as_object_mut() {
+        map.insert("risk".into(), risk);
+        map.insert("protectedPaths".into(), json!(paths));
+    }
+    request.tool_call.meta = Some(meta);
+}
+
+/// Whether the user explicitly allowed an escalated change.
+pub fn is_allowed(response: &RequestPermissionResponse) -> bool {
+    matches!(
+        &response.outcome,
+        RequestPermissionOutcome::Selected { option_id } if option_id.0.as_ref() == ALLOW_OPTION_ID
+    )
+}
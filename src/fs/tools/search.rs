@@ -0,0 +1,270 @@
+//! Text search for the `acp_fs` `search_text` tool.
+//!
+//! Lets the model search the workspace without running `rg` through the
+//! sandbox. Files are walked like `list_directory` does (`.git` and
+//! `.gitignore`d paths skipped unless `include_ignored`), optionally filtered
+//! by a glob, and searched line by line for a literal string or a regex.
+//! Binary files and files over [`MAX_FILE_BYTES`] are skipped. Each match
+//! carries its file, line, and a snippet with optional context lines; the
+//! locations also reach the client as `ToolCallLocation`s.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    listing::{self, EntryKind},
+    protected::glob_matches,
+};
+
+pub const DEFAULT_MAX_RESULTS: usize = 100;
+pub const MAX_RESULTS: usize = 500;
+pub const MAX_CONTEXT_LINES: usize = 5;
+
+/// Files larger than this are not searched.
+pub const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Files walked at most per search.
+const MAX_WALK_ENTRIES: usize = 100_000;
+
+/// Characters kept of a matching or context line.
+const MAX_SNIPPET_CHARS: usize = 300;
+
+/// Arguments of a `search_text` request.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchArgs {
+    pub query: String,
+    /// Treat `query` as a regular expression instead of a literal string.
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Only search files matching this glob (e.g. `*.rs`, `src/**/*.ts`).
+    #[serde(default)]
+    pub glob: Option<String>,
+    #[serde(default)]
+    pub max_results: Option<usize>,
+    #[serde(default)]
+    pub context_lines: Option<usize>,
+    #[serde(default)]
+    pub include_ignored: bool,
+}
+
+/// One matching line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchMatch {
+    /// Workspace-relative when inside the workspace, otherwise absolute.
+    pub path: String,
+    /// Absolute path, for client locations.
+    pub location: PathBuf,
+    /// 1-based line number.
+    pub line: u32,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub matches: Vec<SearchMatch>,
+    pub files_searched: usize,
+    /// True when the search stopped at `max_results`.
+    pub truncated: bool,
+    /// False when the walk stopped before covering every file.
+    pub complete: bool,
+}
+
+/// Build the matcher for `args`.
+pub fn build_matcher(args: &SearchArgs) -> Result<Regex, String> {
+    if args.query.is_empty() {
+        return Err("query must not be empty".to_string());
+    }
+    let pattern = if args.regex {
+        args.query.clone()
+    } else {
+        regex::escape(&args.query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!args.case_sensitive)
+        .build()
+        .map_err(|err| format!("invalid regex: {err}"))
+}
+
+fn snippet(line: &str) -> String {
+    let line = line.trim_end_matches('\r');
+    match line.char_indices().nth(MAX_SNIPPET_CHARS) {
+        Some((idx, _)) => format!("{}…", &line[..idx]),
+        None => line.to_string(),
+    }
+}
+
+/// Matches in the `text` of the file at `location` (shown as `path`), at
+/// most `limit`.
+pub fn search_text_in(
+    text: &str,
+    path: &str,
+    location: &Path,
+    matcher: &Regex,
+    context_lines: usize,
+    limit: usize,
+) -> Vec<SearchMatch> {
+    let lines: Vec<&str> = text.lines().collect();
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| matcher.is_match(line))
+        .take(limit)
+        .map(|(idx, line)| {
+            let before = lines[idx.saturating_sub(context_lines)..idx]
+                .iter()
+                .map(|l| snippet(l))
+                .collect();
+            let after = lines[idx + 1..(idx + 1 + context_lines).min(lines.len())]
+                .iter()
+                .map(|l| snippet(l))
+                .collect();
+            SearchMatch {
+                path: path.to_string(),
+                location: location.to_path_buf(),
+                line: idx as u32 + 1,
+                text: snippet(line),
+                before,
+                after,
+            }
+        })
+        .collect()
+}
+
+/// Search the file or directory `path`.
+pub async fn search(
+    workspace_root: &Path,
+    path: &Path,
+    args: &SearchArgs,
+) -> io::Result<SearchResults> {
+    let matcher = build_matcher(args).map_err(io::Error::other)?;
+    let max_results = args
+        .max_results
+        .unwrap_or(DEFAULT_MAX_RESULTS)
+        .clamp(1, MAX_RESULTS);
+    let context_lines = args.context_lines.unwrap_or(0).min(MAX_CONTEXT_LINES);
+
+    let (files, complete) = if tokio::fs::metadata(path).await?.is_dir() {
+        let (entries, complete) = listing::walk(
+            workspace_root,
+            path,
+            usize::MAX,
+            args.include_ignored,
+            MAX_WALK_ENTRIES,
+        )
+        .await?;
+        let files = entries
+            .into_iter()
+            .filter(|(_, entry)| entry.kind == EntryKind::File)
+            .filter(|(_, entry)| entry.size.is_some_and(|size| size <= MAX_FILE_BYTES))
+            .filter(|(_, entry)| {
+                args.glob.as_deref().is_none_or(|glob| {
+                    let segments: Vec<&str> = entry.path.split('/').collect();
+                    glob_matches(glob, &segments)
+                })
+            })
+            .map(|(path, _)| path)
+            .collect();
+        (files, complete)
+    } else {
+        (vec![path.to_path_buf()], true)
+    };
+
+    let mut results = SearchResults {
+        matches: Vec::new(),
+        files_searched: 0,
+        truncated: false,
+        complete,
+    };
+    for file in files {
+        let Ok(bytes) = tokio::fs::read(&file).await else {
+            continue;
+        };
+        // Skip binaries and non-UTF-8 files.
+        if bytes.contains(&0) {
+            continue;
+        }
+        let Ok(text) = String::from_utf8(bytes) else {
+            continue;
+        };
+        results.files_searched += 1;
+        let display = match file.strip_prefix(workspace_root) {
+            Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+            Err(_) => file.display().to_string(),
+        };
+        let remaining = max_results - results.matches.len();
+        for found in search_text_in(
+            &text,
+            &display,
+            &file,
+            &matcher,
+            context_lines,
+            remaining + 1,
+        ) {
+            if results.matches.len() == max_results {
+                results.truncated = true;
+                break;
+            }
+            results.matches.push(found);
+        }
+        if results.truncated {
+            break;
+        }
+    }
+    Ok(results)
+}
+
+/// Results text for the model, `grep -n` style: `path:line: text` for
+/// matches, `path-line- text` for context, `--` between groups.
+pub fn format_results(results: &SearchResults) -> String {
+    let mut text = String::new();
+    let with_context = results
+        .matches
+        .iter()
+        .any(|m| !m.before.is_empty() || !m.after.is_empty());
+    for (idx, m) in results.matches.iter().enumerate() {
+        if with_context && idx > 0 {
+            text.push_str("--\n");
+        }
+        let first = m.line as usize - m.before.len();
+        for (offset, line) in m.before.iter().enumerate() {
+            text.push_str(&format!("{}-{}- {line}\n", m.path, first + offset));
+        }
+        text.push_str(&format!("{}:{}: {}\n", m.path, m.line, m.text));
+        for (offset, line) in m.after.iter().enumerate() {
+            text.push_str(&format!(
+                "{}-{}- {line}\n",
+                m.path,
+                m.line as usize + 1 + offset
+            ));
+        }
+    }
+    if results.matches.is_empty() {
+        text.push_str(&format!(
+            "No matches in {} files.\n",
+            results.files_searched
+        ));
+    }
+    if results.truncated {
+        text.push_str(&format!(
+            "\n[Stopped at {} matches; narrow the query, path, or glob, or raise max_results.]\n",
+            results.matches.len()
+        ));
+    }
+    if !results.complete {
+        text.push_str(&format!(
+            "\n[Only the first {MAX_WALK_ENTRIES} entries were searched; search a subdirectory.]\n"
+        ));
+    }
+    text
+}
@@ -0,0 +1,144 @@
+//! Reading the end of a file for `read_text_file` with `from_end`.
+//!
+//! Logs and long outputs are usually interesting at the bottom. With
+//! `from_end`, the bridge reads the file (through the client, or from local
+//! disk as usual) and returns only its last `limit` lines together with the
+//! line number they start at, so the model can page backwards from there.
+//! Large files are read backwards from the end of the file on disk, see
+//! [`read_tail`], instead of being loaded whole.
+
+use std::{io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
+
+use crate::fs::text;
+
+/// Bytes read at a time when reading a file backwards.
+pub const TAIL_BLOCK_BYTES: usize = 64 * 1024;
+
+/// The last lines of a file, as the bridge returns them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TailRead {
+    /// 1-based line number of the first line in `content`.
+    pub start_line: u32,
+    pub total_lines: u32,
+    pub content: String,
+}
+
+impl TailRead {
+    /// The last `count` lines of `content`.
+    pub fn new(content: &str, count: u32) -> Self {
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let start = lines.len().saturating_sub(count as usize);
+        Self {
+            start_line: start as u32 + 1,
+            total_lines: lines.len() as u32,
+            content: lines[start..].concat(),
+        }
+    }
+
+    /// Drop leading lines until `content` fits in `max_bytes`, returning
+    /// whether anything was dropped. A last line longer than the cap keeps
+    /// its end.
+    pub fn fit_bytes(&mut self, max_bytes: usize) -> bool {
+        if self.content.len() <= max_bytes {
+            return false;
+        }
+        let mut cut = text::ceil_char_boundary(&self.content, self.content.len() - max_bytes);
+        match self.content[cut..].find('\n') {
+            Some(newline) if cut + newline + 1 < self.content.len() => {
+                let dropped = &self.content[..cut + newline + 1];
+                self.start_line += dropped.matches('\n').count() as u32;
+                cut += newline + 1;
+            }
+            _ => {
+                self.start_line += self.content[..cut].matches('\n').count() as u32;
+            }
+        }
+        self.content.drain(..cut);
+        true
+    }
+
+    /// 1-based number of the last line in `content`.
+    pub fn end_line(&self) -> u32 {
+        if self.content.is_empty() {
+            self.start_line.saturating_sub(1)
+        } else {
+            self.total_lines
+        }
+    }
+}
+
+/// The last `count` lines of the file at `path`, read backwards from its end
+/// `block` bytes at a time until enough line breaks are found.
+///
+/// Only the tail is kept and decoded; the line breaks before it are counted
+/// block by block for the line numbers.
+pub async fn read_tail(path: &Path, count: u32, block: usize) -> io::Result<TailRead> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let len = file.metadata().await?.len();
+    let block = block.max(1) as u64;
+    let mut chunks: Vec<Vec<u8>> = Vec::new();
+    let (mut pos, mut start) = (len, None);
+    // Without a line to return, the last one still tells the line count.
+    let wanted = count.max(1);
+    let mut found = 0;
+    while pos > 0 && start.is_none() {
+        let size = block.min(pos);
+        pos -= size;
+        let mut chunk = vec![0; size as usize];
+        file.seek(SeekFrom::Start(pos)).await?;
+        file.read_exact(&mut chunk).await?;
+        for (i, byte) in chunk.iter().enumerate().rev() {
+            let at = pos + i as u64;
+            // A line break ending the file does not start another line.
+            if *byte != b'\n' || at + 1 == len {
+                continue;
+            }
+            found += 1;
+            if found == wanted {
+                start = Some(at + 1);
+                break;
+            }
+        }
+        chunks.push(chunk);
+    }
+    let start = start.unwrap_or(0);
+
+    chunks.reverse();
+    let scanned = chunks.concat();
+    let tail = scanned[(start - pos) as usize..].to_vec();
+    let before = scanned[..(start - pos) as usize]
+        .iter()
+        .filter(|&&byte| byte == b'\n')
+        .count() as u64
+        + count_line_breaks(&mut file, pos, block).await?;
+    let content =
+        String::from_utf8(tail).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let start_line = before as u32 + 1;
+    let mut tail = TailRead {
+        start_line,
+        total_lines: start_line - 1 + content.split_inclusive('\n').count() as u32,
+        content,
+    };
+    if count == 0 {
+        tail.start_line = tail.total_lines + 1;
+        tail.content.clear();
+    }
+    Ok(tail)
+}
+
+/// Line breaks in the first `end` bytes of `file`.
+async fn count_line_breaks(file: &mut tokio::fs::File, end: u64, block: u64) -> io::Result<u64> {
+    file.seek(SeekFrom::Start(0)).await?;
+    let mut buf = vec![0; block.min(end) as usize];
+    let (mut pos, mut breaks) = (0, 0);
+    while pos < end {
+        let size = block.min(end - pos) as usize;
+        file.read_exact(&mut buf[..size]).await?;
+        breaks += buf[..size].iter().filter(|&&byte| byte == b'\n').count() as u64;
+        pos += size as u64;
+    }
+    Ok(breaks)
+}
@@ -0,0 +1,93 @@
+//! History of file changes for `/undo`.
+//!
+//! Bridge writes and the patches Codex applies record the files they touched
+//! as they were before. History is bounded by [`MAX_UNDO_CHANGES`] and
+//! [`MAX_UNDO_BYTES`]; the oldest changes are forgotten first.
+
+use std::path::PathBuf;
+
+/// Changes remembered per session.
+pub const MAX_UNDO_CHANGES: usize = 100;
+
+/// Bytes of old file content remembered per session.
+pub const MAX_UNDO_BYTES: usize = 8 * 1024 * 1024;
+
+/// One file as it was before a change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSnapshot {
+    pub path: PathBuf,
+    /// `None` when the change created the file.
+    pub before: Option<String>,
+    /// Where the change moved the file, if it did.
+    pub moved_to: Option<PathBuf>,
+}
+
+impl FileSnapshot {
+    fn size(&self) -> usize {
+        self.before.as_ref().map_or(0, String::len)
+    }
+}
+
+/// The files one write or patch touched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// The prompt turn that made the change.
+    pub turn: u64,
+    pub files: Vec<FileSnapshot>,
+}
+
+/// The undoable changes of a session, oldest first.
+#[derive(Debug, Default)]
+pub struct UndoHistory {
+    turn: u64,
+    changes: Vec<Change>,
+    bytes: usize,
+}
+
+impl UndoHistory {
+    /// Start a new prompt turn; `/undo all` restores one turn at a time.
+    pub fn begin_turn(&mut self) {
+        self.turn += 1;
+    }
+
+    /// Remember a change. One larger than [`MAX_UNDO_BYTES`] is not kept.
+    pub fn record(&mut self, files: Vec<FileSnapshot>) -> bool {
+        let size: usize = files.iter().map(FileSnapshot::size).sum();
+        if files.is_empty() || size > MAX_UNDO_BYTES {
+            return false;
+        }
+        self.changes.push(Change {
+            turn: self.turn,
+            files,
+        });
+        self.bytes += size;
+        while self.changes.len() > MAX_UNDO_CHANGES || self.bytes > MAX_UNDO_BYTES {
+            let oldest = self.changes.remove(0);
+            self.bytes -= oldest.files.iter().map(FileSnapshot::size).sum::<usize>();
+        }
+        true
+    }
+
+    /// Take the newest change.
+    pub fn pop_last(&mut self) -> Option<Change> {
+        let change = self.changes.pop()?;
+        self.bytes -= change.files.iter().map(FileSnapshot::size).sum::<usize>();
+        Some(change)
+    }
+
+    /// Take every change of the newest change's turn, newest first.
+    pub fn pop_turn(&mut self) -> Vec<Change> {
+        let Some(turn) = self.changes.last().map(|change| change.turn) else {
+            return Vec::new();
+        };
+        let mut taken = Vec::new();
+        while self
+            .changes
+            .last()
+            .is_some_and(|change| change.turn == turn)
+        {
+            taken.extend(self.pop_last());
+        }
+        taken
+    }
+}
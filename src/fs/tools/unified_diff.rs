@@ -0,0 +1,399 @@
+//! Unified diffs for the `acp_fs` `apply_unified_diff` tool.
+//!
+//! Large refactors are easier to express as a diff than as `old_string` /
+//! `new_string` pairs. A diff for one file is parsed into hunks, and each hunk
+//! is applied in order against the current content. A hunk whose lines have
+//! moved is found by searching outward from its expected line. A hunk that
+//! no longer matches exactly is retried ignoring whitespace, then with up to
+//! [`MAX_FUZZ`] context lines dropped from either end, as `patch` does. CRLF
+//! files are matched and rewritten with CRLF line endings.
+
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+
+/// Most context lines dropped from each end of a hunk that does not match.
+pub const MAX_FUZZ: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// 1-based first line in the original file, from the `@@` header.
+    pub old_start: usize,
+    old_count: usize,
+    new_count: usize,
+    lines: Vec<HunkLine>,
+    old_no_newline: bool,
+    new_no_newline: bool,
+}
+
+impl Hunk {
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Remove(text) => Some(text.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Add(text) => Some(text.as_str()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+
+    /// Whether the lines counted in the `@@` header have all been read.
+    fn is_complete(&self) -> bool {
+        self.old_lines().len() >= self.old_count && self.new_lines().len() >= self.new_count
+    }
+
+    fn leading_context(&self) -> usize {
+        self.lines
+            .iter()
+            .take_while(|line| matches!(line, HunkLine::Context(_)))
+            .count()
+    }
+
+    fn trailing_context(&self) -> usize {
+        self.lines
+            .iter()
+            .rev()
+            .take_while(|line| matches!(line, HunkLine::Context(_)))
+            .count()
+    }
+}
+
+/// Parse a unified diff for a single file. `---`/`+++` headers and `diff`
+/// or `index` lines are optional; a diff touching several files is refused.
+pub fn parse(diff: &str) -> Result<Vec<Hunk>> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut files = 0;
+    let mut in_hunk = false;
+    let mut last_kind = None;
+    for line in diff.lines() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if let Some(header) = line.strip_prefix("@@") {
+            let (old_start, old_count, new_count) = parse_hunk_header(header)
+                .ok_or_else(|| anyhow!("malformed hunk header: {line}"))?;
+            hunks.push(Hunk {
+                old_start,
+                old_count,
+                new_count,
+                lines: Vec::new(),
+                old_no_newline: false,
+                new_no_newline: false,
+            });
+            in_hunk = true;
+            last_kind = None;
+            continue;
+        }
+        // Inside a hunk that still expects lines, `--- x` removes `-- x`.
+        let in_body = in_hunk && hunks.last().is_some_and(|hunk| !hunk.is_complete());
+        if line.starts_with("--- ") && !in_body {
+            files += 1;
+            if files > 1 {
+                bail!("the diff touches several files; apply one file per call");
+            }
+            in_hunk = false;
+            continue;
+        }
+        if line.starts_with("+++ ") && !in_body {
+            continue;
+        }
+        let Some(hunk) = hunks.last_mut().filter(|_| in_hunk) else {
+            continue;
+        };
+        if line.starts_with('\\') {
+            match last_kind {
+                Some('-') => hunk.old_no_newline = true,
+                Some('+') => hunk.new_no_newline = true,
+                _ => {
+                    hunk.old_no_newline = true;
+                    hunk.new_no_newline = true;
+                }
+            }
+            continue;
+        }
+        let (kind, text) = match line.chars().next() {
+            Some(kind @ (' ' | '-' | '+')) => (kind, &line[1..]),
+            // Some tools strip the space from empty context lines; a blank
+            // line after a complete hunk is just the end of the diff.
+            None if in_body => (' ', ""),
+            None => continue,
+            Some(_) => {
+                if line.starts_with("diff ") {
+                    in_hunk = false;
+                    continue;
+                }
+                bail!("unexpected line in hunk: {line}");
+            }
+        };
+        hunk.lines.push(match kind {
+            '-' => HunkLine::Remove(text.to_string()),
+            '+' => HunkLine::Add(text.to_string()),
+            _ => HunkLine::Context(text.to_string()),
+        });
+        last_kind = Some(kind);
+    }
+    if hunks.is_empty() {
+        bail!("the diff contains no hunks");
+    }
+    Ok(hunks)
+}
+
+/// Start and line counts from `-a,b +c,d @@`; an omitted count is 1.
+fn parse_hunk_header(header: &str) -> Option<(usize, usize, usize)> {
+    let mut ranges = header.split_whitespace();
+    let range = |token: &str| -> Option<(usize, usize)> {
+        let mut parts = token.splitn(2, ',');
+        let start = parts.next()?.parse().ok()?;
+        let count = parts.next().map_or(Some(1), |count| count.parse().ok())?;
+        Some((start, count))
+    };
+    let (old_start, old_count) = range(ranges.next()?.strip_prefix('-')?)?;
+    let (_, new_count) = range(ranges.next()?.strip_prefix('+')?)?;
+    Some((old_start, old_count, new_count))
+}
+
+/// What happened to one hunk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HunkOutcome {
+    /// 1-based position of the hunk in the diff.
+    pub hunk: usize,
+    pub applied: bool,
+    /// 1-based line in the original file where the hunk matched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Lines between where the header placed the hunk and where it matched.
+    pub offset: isize,
+    /// Context lines dropped from each end to make the hunk match.
+    pub fuzz: usize,
+    /// Whether the match ignored whitespace.
+    pub whitespace: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The patched content and the outcome of every hunk. Hunks that fail are
+/// skipped; the others are still applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Applied {
+    pub content: String,
+    pub hunks: Vec<HunkOutcome>,
+}
+
+impl Applied {
+    pub fn all_applied(&self) -> bool {
+        self.hunks.iter().all(|hunk| hunk.applied)
+    }
+}
+
+/// Apply `hunks` to `base`.
+pub fn apply(base: &str, hunks: &[Hunk]) -> Applied {
+    let crlf = base.contains("\r\n");
+    let normalized = if crlf {
+        base.replace("\r\n", "\n")
+    } else {
+        base.to_string()
+    };
+    let mut trailing_newline = normalized.is_empty() || normalized.ends_with('\n');
+    let mut lines: Vec<String> = normalized.lines().map(str::to_string).collect();
+
+    // Lines added minus lines removed by the hunks applied so far.
+    let mut delta: isize = 0;
+    // Hunks apply in order, so a hunk may not match above the previous one.
+    let mut floor = 0;
+    let mut outcomes = Vec::with_capacity(hunks.len());
+    for (index, hunk) in hunks.iter().enumerate() {
+        let expected = (hunk.old_start.saturating_sub(1) as isize + delta).max(0) as usize;
+        let Some(found) = locate(&lines, hunk, expected, floor) else {
+            outcomes.push(HunkOutcome {
+                hunk: index + 1,
+                applied: false,
+                line: None,
+                offset: 0,
+                fuzz: 0,
+                whitespace: false,
+                error: Some("the hunk's lines were not found in the file".to_string()),
+            });
+            continue;
+        };
+        let old = hunk.old_lines();
+        let new = hunk.new_lines();
+        let (old, new) = (
+            &old[found.fuzz_front..old.len() - found.fuzz_back],
+            &new[found.fuzz_front..new.len() - found.fuzz_back],
+        );
+        let reaches_end = found.start + old.len() == lines.len();
+        lines.splice(
+            found.start..found.start + old.len(),
+            new.iter().map(|line| line.to_string()),
+        );
+        if reaches_end && found.fuzz_back == 0 {
+            if hunk.new_no_newline {
+                trailing_newline = false;
+            } else if hunk.old_no_newline {
+                trailing_newline = true;
+            }
+        }
+        // Where the hunk, including any context dropped as fuzz, starts.
+        let hunk_start = found.start as isize - found.fuzz_front as isize;
+        let original_line = (hunk_start - delta).max(0) as usize + 1;
+        let offset = hunk_start - expected as isize;
+        delta += new.len() as isize - old.len() as isize;
+        floor = found.start + new.len();
+        outcomes.push(HunkOutcome {
+            hunk: index + 1,
+            applied: true,
+            line: Some(original_line),
+            offset,
+            fuzz: found.fuzz_front.max(found.fuzz_back),
+            whitespace: found.whitespace,
+            error: None,
+        });
+    }
+
+    let mut content = lines.join("\n");
+    if trailing_newline && !lines.is_empty() {
+        content.push('\n');
+    }
+    if crlf {
+        content = content.replace('\n', "\r\n");
+    }
+    Applied {
+        content,
+        hunks: outcomes,
+    }
+}
+
+struct Found {
+    start: usize,
+    fuzz_front: usize,
+    fuzz_back: usize,
+    whitespace: bool,
+}
+
+fn locate(lines: &[String], hunk: &Hunk, expected: usize, floor: usize) -> Option<Found> {
+    let old = hunk.old_lines();
+    let max_front = hunk.leading_context().min(MAX_FUZZ);
+    let max_back = hunk.trailing_context().min(MAX_FUZZ);
+    for fuzz in 0..=max_front.max(max_back) {
+        let (front, back) = (fuzz.min(max_front), fuzz.min(max_back));
+        if fuzz > 0 && front + back == 0 {
+            break;
+        }
+        // Keep at least one line to match, unless the hunk is a pure insertion.
+        if front + back >= old.len() && !old.is_empty() {
+            break;
+        }
+        let needle = &old[front..old.len() - back];
+        for whitespace in [false, true] {
+            let expected = expected + front;
+            if let Some(start) = search(lines, needle, expected, floor, whitespace) {
+                return Some(Found {
+                    start,
+                    fuzz_front: front,
+                    fuzz_back: back,
+                    whitespace,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// First position at or after `floor` where `needle` matches, trying
+/// positions in order of distance from `expected`.
+fn search(
+    lines: &[String],
+    needle: &[&str],
+    expected: usize,
+    floor: usize,
+    whitespace: bool,
+) -> Option<usize> {
+    if needle.len() > lines.len() {
+        return None;
+    }
+    let last = lines.len() - needle.len();
+    if floor > last {
+        return None;
+    }
+    let expected = expected.clamp(floor, last);
+    let matches = |start: usize| {
+        needle.iter().enumerate().all(|(i, want)| {
+            let have = lines[start + i].as_str();
+            if whitespace {
+                normalize_whitespace(have) == normalize_whitespace(want)
+            } else {
+                have == *want
+            }
+        })
+    };
+    for distance in 0..=last - floor {
+        if let Some(start) = expected.checked_add(distance).filter(|s| *s <= last)
+            && matches(start)
+        {
+            return Some(start);
+        }
+        if distance > 0
+            && let Some(start) = expected.checked_sub(distance).filter(|s| *s >= floor)
+            && matches(start)
+        {
+            return Some(start);
+        }
+    }
+    None
+}
+
+fn normalize_whitespace(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// One line per hunk for the model.
+pub fn format_outcomes(path: &str, applied: &Applied, written: bool) -> String {
+    let ok = applied.hunks.iter().filter(|hunk| hunk.applied).count();
+    let mut text = format!(
+        "{path}: {ok} of {} hunks applied{}\n",
+        applied.hunks.len(),
+        if written { "" } else { ", nothing written" }
+    );
+    for hunk in &applied.hunks {
+        match (&hunk.error, hunk.line) {
+            (Some(error), _) => text.push_str(&format!("hunk {}: failed: {error}\n", hunk.hunk)),
+            (None, Some(line)) => {
+                let mut notes = Vec::new();
+                if hunk.offset != 0 {
+                    notes.push(format!("offset {:+}", hunk.offset));
+                }
+                if hunk.fuzz > 0 {
+                    notes.push(format!("fuzz {}", hunk.fuzz));
+                }
+                if hunk.whitespace {
+                    notes.push("ignoring whitespace".to_string());
+                }
+                let notes = if notes.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", notes.join(", "))
+                };
+                text.push_str(&format!(
+                    "hunk {}: applied at line {line}{notes}\n",
+                    hunk.hunk
+                ));
+            }
+            (None, None) => {}
+        }
+    }
+    text
+}
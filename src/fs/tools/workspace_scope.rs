@@ -0,0 +1,212 @@
+//! Which paths the `acp_fs` tools may touch.
+//!
+//! Paths given to the bridge are resolved against the workspace root and
+//! normalized (`.` and `..` are applied without touching the disk). For the
+//! check, symlinks are then resolved in the longest part of the path that
+//! exists, and in the roots, so a link inside a root that points elsewhere
+//! counts as outside. Paths that end up outside the workspace and outside
+//! every root listed in `CODEX_ACP_EXTRA_ROOTS` are handled by
+//! `CODEX_ACP_OUTSIDE_WORKSPACE`: `reject` (the default) refuses them, `ask`
+//! turns each access into a permission request.
+
+use std::path::{Component, Path, PathBuf};
+
+use agent_client_protocol::{
+    PermissionOption, PermissionOptionId, PermissionOptionKind, RequestPermissionOutcome,
+    RequestPermissionRequest, RequestPermissionResponse, SessionId, ToolCallId, ToolCallLocation,
+    ToolCallStatus, ToolCallUpdate, ToolCallUpdateFields, ToolKind,
+};
+use serde_json::json;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Environment variable choosing the policy for paths outside the workspace.
+pub const OUTSIDE_WORKSPACE_ENV: &str = "CODEX_ACP_OUTSIDE_WORKSPACE";
+
+/// Environment variable listing extra allowed roots, separated like `PATH`.
+pub const EXTRA_ROOTS_ENV: &str = "CODEX_ACP_EXTRA_ROOTS";
+
+const ALLOW_OPTION_ID: &str = "allow-outside";
+const REJECT_OPTION_ID: &str = "reject-outside";
+
+/// What to do with a path outside the allowed roots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutsidePolicy {
+    #[default]
+    Reject,
+    Ask,
+}
+
+impl OutsidePolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "reject" | "deny" => Some(Self::Reject),
+            "ask" | "prompt" => Some(Self::Ask),
+            _ => None,
+        }
+    }
+}
+
+/// The roots the `acp_fs` tools may use freely, and the policy for the rest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceScope {
+    roots: Vec<PathBuf>,
+    policy: OutsidePolicy,
+}
+
+impl WorkspaceScope {
+    /// Relative extra roots are taken relative to `workspace_root`.
+    pub fn new(workspace_root: &Path, extra_roots: Vec<PathBuf>, policy: OutsidePolicy) -> Self {
+        let mut roots = vec![normalize(workspace_root)];
+        roots.extend(
+            extra_roots
+                .into_iter()
+                .filter(|root| !root.as_os_str().is_empty())
+                .map(|root| normalize(&workspace_root.join(root))),
+        );
+        Self { roots, policy }
+    }
+
+    pub fn from_env(workspace_root: &Path) -> Self {
+        let policy = match std::env::var(OUTSIDE_WORKSPACE_ENV) {
+            Ok(value) => OutsidePolicy::parse(&value).unwrap_or_else(|| {
+                warn!(%value, "unknown {OUTSIDE_WORKSPACE_ENV} value; rejecting paths outside the workspace");
+                OutsidePolicy::Reject
+            }),
+            Err(_) => OutsidePolicy::default(),
+        };
+        let extra_roots = std::env::var_os(EXTRA_ROOTS_ENV)
+            .map(|raw| std::env::split_paths(&raw).collect())
+            .unwrap_or_default();
+        Self::new(workspace_root, extra_roots, policy)
+    }
+
+    pub fn policy(&self) -> OutsidePolicy {
+        self.policy
+    }
+
+    /// Whether the normalized `path` lies inside one of the roots or
+    /// `extra_roots`, with symlinks resolved on both sides.
+    pub async fn contains(&self, path: &Path, extra_roots: &[PathBuf]) -> bool {
+        let path = resolve_links(path).await;
+        for root in self.roots.iter().chain(extra_roots) {
+            if path.starts_with(resolve_links(root).await) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// The normalized `path` with symlinks resolved in its longest existing
+/// ancestor; the part that does not exist yet is kept as given.
+pub async fn resolve_links(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(resolved) = tokio::fs::canonicalize(existing).await {
+            return missing
+                .iter()
+                .rev()
+                .fold(resolved, |resolved, name| resolved.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// The root a relative `path` starts with by name, and the rest of the path.
+///
+/// With several roots, paths are displayed as `<root name>/<path>`, so a
+/// relative path in that form lies in the named root (the first one when
+/// names repeat). With a single root every relative path is its own.
+pub fn named_root<'a>(roots: &'a [PathBuf], path: &'a Path) -> Option<(&'a Path, &'a Path)> {
+    if roots.len() < 2 || path.is_absolute() {
+        return None;
+    }
+    let mut components = path.components();
+    let Some(Component::Normal(first)) = components.next() else {
+        return None;
+    };
+    let root = roots.iter().find(|root| root.file_name() == Some(first))?;
+    Some((root.as_path(), components.as_path()))
+}
+
+/// Apply `.` and `..` components lexically; `..` at the root stays there.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if normalized.file_name().is_some() {
+                    normalized.pop();
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Permission request for an `acp_fs` read ([`ToolKind::Read`]), write
+/// ([`ToolKind::Edit`]), delete, or move of a path outside the allowed roots.
+pub fn outside_request(
+    session_id: SessionId,
+    path: &Path,
+    kind: ToolKind,
+) -> RequestPermissionRequest {
+    let display = path.display().to_string();
+    let action = match kind {
+        ToolKind::Read => "Read",
+        ToolKind::Delete => "Delete",
+        ToolKind::Move => "Move",
+        _ => "Write",
+    };
+    RequestPermissionRequest {
+        session_id,
+        tool_call: ToolCallUpdate {
+            id: ToolCallId(format!("outside_workspace-{}", Uuid::new_v4()).into()),
+            fields: ToolCallUpdateFields {
+                kind: Some(kind),
+                status: Some(ToolCallStatus::Pending),
+                title: Some(format!("⚠️ {action} outside the workspace: {display}")),
+                locations: Some(vec![ToolCallLocation {
+                    path: path.to_path_buf(),
+                    line: None,
+                    meta: None,
+                }]),
+                ..Default::default()
+            },
+            meta: Some(json!({ "outsideWorkspace": display })),
+        },
+        options: vec![
+            PermissionOption {
+                id: PermissionOptionId(ALLOW_OPTION_ID.into()),
+                name: "Allow once".into(),
+                kind: PermissionOptionKind::AllowOnce,
+                meta: None,
+            },
+            PermissionOption {
+                id: PermissionOptionId(REJECT_OPTION_ID.into()),
+                name: "Reject".into(),
+                kind: PermissionOptionKind::RejectOnce,
+                meta: None,
+            },
+        ],
+        meta: None,
+    }
+}
+
+/// Whether the user allowed an access outside the workspace.
+pub fn is_allowed(response: &RequestPermissionResponse) -> bool {
+    matches!(
+        &response.outcome,
+        RequestPermissionOutcome::Selected { option_id } if option_id.0.as_ref() == ALLOW_OPTION_ID
+    )
+}
@@ -0,0 +1,102 @@
+//! Asking before `acp_fs` writes.
+//!
+//! Codex patches go through the session's approval policy, but the `acp_fs`
+//! tools write through the bridge without asking. With
+//! `CODEX_ACP_CONFIRM_WRITES=1`, every bridge write first sends a permission
+//! request showing the change as a diff. "Always allow" stops asking for the
+//! rest of the session; a rejected or unanswerable request refuses the write.
+//! Protected paths keep their own stricter prompt instead.
+
+use std::path::Path;
+
+use agent_client_protocol::{
+    Diff, PermissionOption, PermissionOptionId, PermissionOptionKind, RequestPermissionOutcome,
+    RequestPermissionRequest, RequestPermissionResponse, SessionId, ToolCallContent, ToolCallId,
+    ToolCallLocation, ToolCallStatus, ToolCallUpdate, ToolCallUpdateFields, ToolKind,
+};
+
+/// Environment variable enabling write confirmation (`1`, `true`, `yes`).
+pub const CONFIRM_WRITES_ENV: &str = "CODEX_ACP_CONFIRM_WRITES";
+
+const ALLOW_ONCE_ID: &str = "allow-write";
+const ALLOW_ALWAYS_ID: &str = "allow-writes-for-session";
+const REJECT_ID: &str = "reject-write";
+
+pub fn confirm_writes_from_env() -> bool {
+    std::env::var(CONFIRM_WRITES_ENV)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// The user's answer to a write permission request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteDecision {
+    AllowOnce,
+    /// Allow this and every later write in the session.
+    AllowAlways,
+    Reject,
+}
+
+impl WriteDecision {
+    pub fn from_response(response: &RequestPermissionResponse) -> Self {
+        match &response.outcome {
+            RequestPermissionOutcome::Selected { option_id } => match option_id.0.as_ref() {
+                ALLOW_ONCE_ID => Self::AllowOnce,
+                ALLOW_ALWAYS_ID => Self::AllowAlways,
+                _ => Self::Reject,
+            },
+            _ => Self::Reject,
+        }
+    }
+}
+
+/// The permission request for writing `new_text` over `old_text` (`None`
+/// for a new file) at `path`.
+pub fn write_permission_request(
+    session_id: SessionId,
+    call_id: String,
+    path: &Path,
+    old_text: Option<String>,
+    new_text: String,
+) -> RequestPermissionRequest {
+    let option = |id: &str, name: &str, kind| PermissionOption {
+        id: PermissionOptionId(id.into()),
+        name: name.into(),
+        kind,
+        meta: None,
+    };
+    RequestPermissionRequest {
+        session_id,
+        tool_call: ToolCallUpdate {
+            id: ToolCallId(call_id.into()),
+            fields: ToolCallUpdateFields {
+                kind: Some(ToolKind::Edit),
+                status: Some(ToolCallStatus::Pending),
+                title: Some(format!("Write {}", path.display())),
+                content: Some(vec![ToolCallContent::from(Diff {
+                    path: path.to_path_buf(),
+                    old_text,
+                    new_text,
+                    meta: None,
+                })]),
+                locations: Some(vec![ToolCallLocation {
+                    path: path.to_path_buf(),
+                    line: None,
+                    meta: None,
+                }]),
+                ..Default::default()
+            },
+            meta: None,
+        },
+        options: vec![
+            option(ALLOW_ONCE_ID, "Allow", PermissionOptionKind::AllowOnce),
+            option(
+                ALLOW_ALWAYS_ID,
+                "Always allow writes in this session",
+                PermissionOptionKind::AllowAlways,
+            ),
+            option(REJECT_ID, "Reject", PermissionOptionKind::RejectOnce),
+        ],
+        meta: None,
+    }
+}
@@ -15,7 +15,7 @@ pub mod fs;
 pub mod logging;
 
 // Common re-exports for convenience.
-pub use agent::{CodexAgent, SessionModeLookup};
+pub use agent::{CodexAgent, SessionEvent, SessionEvents, SessionModeLookup, TurnResult};
 pub use fs::FsBridge;
 pub use logging::{LoggingGuard, init_from_env};
 
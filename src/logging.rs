@@ -3,13 +3,24 @@
 //! Features:
 //! - RUST_LOG-compatible filtering via `tracing-subscriber`'s `EnvFilter`.
 //! - Dual output: stderr + file (if configured).
-//! - Optional daily log rotation when a log directory is provided.
+//! - Size-based rotation for an explicit log file, daily or hourly rotation
+//!   when a log directory is provided.
 //! - Non-blocking file writes with a guard to flush logs on shutdown.
 //!
+//! Settings can also be kept in the `[acp.logging]` section of
+//! `$CODEX_HOME/config.toml` (`level`, `modules`, `format`, `destinations`,
+//! `file`, `dir`); environment variables take precedence over it, and RUST_LOG
+//! directives are merged over its levels. Changed levels are picked up while
+//! running when `watch_config` is spawned.
+//!
 //! Environment variables (from highest to lowest precedence for file output):
-//! - CODEX_LOG_FILE: absolute or relative file path to append logs (no rotation).
-//! - CODEX_LOG_DIR: directory for daily-rotated logs (file name: "acp.log").
+//! - CODEX_LOG_FILE: absolute or relative file path to append logs, rotated by size.
+//! - CODEX_LOG_MAX_SIZE_MB: size at which CODEX_LOG_FILE is rotated (default 20, 0 disables).
+//! - CODEX_LOG_MAX_FILES: rotated files kept next to CODEX_LOG_FILE (default 5).
+//! - CODEX_LOG_DIR: directory for time-rotated logs (file name: "acp.log").
+//! - CODEX_LOG_ROTATION: "daily" (default) or "hourly" rotation for CODEX_LOG_DIR.
 //! - CODEX_LOG_STDERR: "0" or "false" disables stderr logging; otherwise enabled.
+//! - CODEX_LOG_FORMAT: "full" (default), "compact", or "pretty".
 //! - RUST_LOG: standard logging filter (e.g., "info", "debug", "codex_acp=trace,rmcp=info").
 //!
 //! Usage:
@@ -25,11 +36,35 @@
 //! - ANSI color is disabled for file output to keep logs clean.
 //! - Parent directories for CODEX_LOG_FILE/CODEX_LOG_DIR are created if needed.
 
-use std::{env, fs, fs::OpenOptions, path::Path};
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
 
 use anyhow::Result;
-use tracing_appender::non_blocking::{self, WorkerGuard};
-use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use toml_edit::{DocumentMut, Item};
+use tracing::{info, warn};
+use tracing_appender::{
+    non_blocking::{self, WorkerGuard},
+    rolling::Rotation,
+};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry, fmt,
+    fmt::MakeWriter,
+    layer::{Layered, SubscriberExt},
+    reload,
+    util::SubscriberInitExt,
+};
+
+/// Default size at which `CODEX_LOG_FILE` is rotated.
+const DEFAULT_MAX_SIZE_MB: u64 = 20;
+
+/// Default number of rotated files kept next to `CODEX_LOG_FILE`.
+const DEFAULT_MAX_FILES: usize = 5;
 
 /// A guard that keeps the non-blocking file writer alive until dropped,
 /// ensuring logs are flushed on process shutdown.
@@ -48,16 +83,31 @@ impl LoggingGuard {
     }
 }
 
-/// Initialize global tracing subscriber from environment variables.
-/// - RUST_LOG controls filtering (defaults to "info" if not set or invalid).
-/// - CODEX_LOG_FILE selects an explicit file (no rotation).
-/// - CODEX_LOG_DIR selects daily-rotated logs in the provided directory.
+/// Initialize global tracing subscriber from environment variables and the
+/// `[acp.logging]` section of `$CODEX_HOME/config.toml`.
+/// - RUST_LOG controls filtering, merged over the configured levels
+///   (defaults to "info" if neither is set or valid).
+/// - CODEX_LOG_FORMAT selects "full", "compact", or "pretty" output.
+/// - CODEX_LOG_FILE selects an explicit file, rotated once it reaches
+///   CODEX_LOG_MAX_SIZE_MB, keeping CODEX_LOG_MAX_FILES older files.
+/// - CODEX_LOG_DIR selects daily-rotated logs in the provided directory
+///   (hourly with CODEX_LOG_ROTATION=hourly).
 /// - CODEX_LOG_STDERR disables stderr logging when set to "0" or "false".
 ///
 /// Returns a LoggingGuard that must be kept alive for the duration of the process.
 pub fn init_from_env() -> Result<LoggingGuard> {
-    // Build EnvFilter from RUST_LOG or default to "info".
-    let filter = EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new("info"))?;
+    let config = codex_core::config::find_codex_home()
+        .map(|home| LoggingConfig::load(&home))
+        .unwrap_or_default();
+
+    // Merge RUST_LOG over the configured levels, falling back to "info".
+    let filter = build_filter(&config);
+    let (filter, filter_handle) = reload::Layer::new(filter);
+
+    let format = match env::var("CODEX_LOG_FORMAT") {
+        Ok(value) => LogFormat::parse(&value).unwrap_or(config.format),
+        Err(_) => config.format,
+    };
 
     // Determine stderr logging behavior.
     let stderr_enabled = env::var("CODEX_LOG_STDERR")
@@ -65,52 +115,55 @@ pub fn init_from_env() -> Result<LoggingGuard> {
             let v = v.to_ascii_lowercase();
             !(v == "0" || v == "false" || v == "off" || v == "no")
         })
-        .unwrap_or(true);
+        .unwrap_or_else(|_| config.logs_to(Destination::Stderr));
 
-    // Determine file logging behavior.
-    let file_path = env::var("CODEX_LOG_FILE").ok();
-    let dir_path = env::var("CODEX_LOG_DIR").ok();
+    // Determine file logging behavior; the environment wins over config.toml.
+    let configured_files = config.logs_to(Destination::File);
+    let file_path = env::var_os("CODEX_LOG_FILE")
+        .map(PathBuf::from)
+        .or_else(|| config.file.clone().filter(|_| configured_files));
+    let dir_path = env::var_os("CODEX_LOG_DIR")
+        .map(PathBuf::from)
+        .or_else(|| config.dir.clone().filter(|_| configured_files));
 
-    // Build optional layers and a guard in one pass.
+    // Build layers and a guard in one pass.
     let mut file_guard: Option<WorkerGuard> = None;
+    let mut layers: Vec<BoxedLayer> = Vec::new();
 
-    let stderr_layer = if stderr_enabled {
-        Some(fmt::layer().with_target(true))
-    } else {
-        None
-    };
+    if stderr_enabled {
+        layers.push(format_layer(format, io::stderr, true));
+    }
 
-    // File layer (non-rotating) takes precedence over directory-based rotation.
-    let file_layer = if let Some(file) = file_path {
-        let (nb, guard) = non_blocking_writer_for_file(&file)?;
+    // An explicit file takes precedence over directory-based rotation.
+    if let Some(file) = file_path {
+        let max_bytes = env_number("CODEX_LOG_MAX_SIZE_MB")
+            .unwrap_or(DEFAULT_MAX_SIZE_MB)
+            .saturating_mul(1024 * 1024);
+        let max_files = env_number("CODEX_LOG_MAX_FILES")
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_MAX_FILES);
+        let (nb, guard) = non_blocking_writer_for_file(&file, max_bytes, max_files)?;
         file_guard = Some(guard);
-        Some(
-            fmt::layer()
-                .with_ansi(false)
-                .with_target(true)
-                .with_writer(nb),
-        )
+        layers.push(format_layer(format, nb, false));
     } else if let Some(dir) = dir_path {
-        let (nb, guard) = non_blocking_writer_for_daily(dir, "acp.log")?;
+        let rotation = match env::var("CODEX_LOG_ROTATION")
+            .map(|v| v.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            Ok("hourly") => Rotation::HOURLY,
+            _ => Rotation::DAILY,
+        };
+        let (nb, guard) = non_blocking_writer_for_dir(dir, "acp.log", rotation)?;
         file_guard = Some(guard);
-        Some(
-            fmt::layer()
-                .with_ansi(false)
-                .with_target(true)
-                .with_writer(nb),
-        )
-    } else {
-        None
-    };
+        layers.push(format_layer(format, nb, false));
+    }
 
-    // Chain all layers in a single expression to avoid type-mismatch on reassignment.
-    let subscriber = tracing_subscriber::registry()
-        .with(filter)
-        .with(stderr_layer)
-        .with(file_layer);
+    let subscriber = tracing_subscriber::registry().with(filter).with(layers);
 
     // Try init; ignore error if already initialized elsewhere.
-    let _ = subscriber.try_init();
+    if subscriber.try_init().is_ok() {
+        let _ = FILTER_HANDLE.set(filter_handle);
+    }
 
     Ok(match file_guard {
         Some(guard) => LoggingGuard::with_guard(guard),
@@ -118,31 +171,379 @@ pub fn init_from_env() -> Result<LoggingGuard> {
     })
 }
 
+type Base = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+type BoxedLayer = Box<dyn Layer<Base> + Send + Sync>;
+
+/// Swaps the filter when `config.toml` changes.
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+fn build_filter(config: &LoggingConfig) -> EnvFilter {
+    let rust_log = env::var("RUST_LOG").ok();
+    EnvFilter::try_new(merge_directives(config, rust_log.as_deref()))
+        .or_else(|_| EnvFilter::try_new(merge_directives(config, None)))
+        .unwrap_or_else(|_| EnvFilter::new("info"))
+}
+
+fn format_layer<W>(format: LogFormat, writer: W, ansi: bool) -> BoxedLayer
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = fmt::layer()
+        .with_ansi(ansi)
+        .with_target(true)
+        .with_writer(writer);
+    match format {
+        LogFormat::Full => layer.boxed(),
+        LogFormat::Compact => layer.compact().boxed(),
+        LogFormat::Pretty => layer.pretty().boxed(),
+    }
+}
+
+/// Re-read the levels in `[acp.logging]` and apply them, merged with
+/// RUST_LOG as at startup. Format and destinations need a restart.
+pub fn reload_levels(codex_home: &Path) -> Result<()> {
+    let Some(handle) = FILTER_HANDLE.get() else {
+        return Ok(());
+    };
+    handle.reload(build_filter(&LoggingConfig::load(codex_home)))?;
+    Ok(())
+}
+
+/// How often [`watch_config`] looks at `config.toml`.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reload log levels whenever `$CODEX_HOME/config.toml` changes.
+pub async fn watch_config(codex_home: PathBuf) {
+    let path = codex_home.join("config.toml");
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut last = modified(&path);
+    loop {
+        tokio::time::sleep(CONFIG_POLL_INTERVAL).await;
+        let current = modified(&path);
+        if current == last {
+            continue;
+        }
+        last = current;
+        match reload_levels(&codex_home) {
+            Ok(()) => info!("reloaded log levels from {}", path.display()),
+            Err(err) => warn!(error = %err, "failed to reload log levels"),
+        }
+    }
+}
+
+/// Where logs are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    Stderr,
+    /// `file` or `dir` from the same section.
+    File,
+}
+
+/// Layout of log lines, as in `tracing_subscriber::fmt`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Full,
+    Compact,
+    Pretty,
+}
+
+impl LogFormat {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "full" => Some(Self::Full),
+            "compact" => Some(Self::Compact),
+            "pretty" => Some(Self::Pretty),
+            _ => None,
+        }
+    }
+}
+
+/// The `[acp.logging]` section of `config.toml`:
+///
+/// ```toml
+/// [acp.logging]
+/// level = "info"
+/// format = "compact"
+/// destinations = ["stderr", "file"]
+/// file = "/home/me/.codex/log/acp.log"
+///
+/// [acp.logging.modules]
+/// codex_acp = "debug"
+/// rmcp = "warn"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoggingConfig {
+    /// Level for modules without their own entry.
+    pub level: Option<String>,
+    /// Levels per module (tracing target), in file order.
+    pub modules: Vec<(String, String)>,
+    pub format: LogFormat,
+    /// Outputs to use; `None` keeps the default of stderr plus any file.
+    pub destinations: Option<Vec<Destination>>,
+    pub file: Option<PathBuf>,
+    pub dir: Option<PathBuf>,
+}
+
+impl LoggingConfig {
+    /// Parse `[acp.logging]` out of a whole `config.toml`.
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let doc: DocumentMut = text.parse().map_err(|err| format!("invalid TOML: {err}"))?;
+        let Some(section) = doc
+            .get("acp")
+            .and_then(|acp| acp.get("logging"))
+            .and_then(Item::as_table_like)
+        else {
+            return Ok(Self::default());
+        };
+        let string = |key: &str| -> std::result::Result<Option<String>, String> {
+            match section.get(key) {
+                None => Ok(None),
+                Some(item) => item
+                    .as_str()
+                    .map(|value| Some(value.to_string()))
+                    .ok_or_else(|| format!("acp.logging.{key} must be a string")),
+            }
+        };
+
+        let mut config = Self {
+            level: string("level")?,
+            file: string("file")?.map(PathBuf::from),
+            dir: string("dir")?.map(PathBuf::from),
+            ..Self::default()
+        };
+        if let Some(format) = string("format")? {
+            config.format = LogFormat::parse(&format)
+                .ok_or_else(|| format!("unknown acp.logging.format {format:?}"))?;
+        }
+        if let Some(modules) = section.get("modules") {
+            let modules = modules
+                .as_table_like()
+                .ok_or("acp.logging.modules must be a table")?;
+            for (module, level) in modules.iter() {
+                let level = level
+                    .as_str()
+                    .ok_or_else(|| format!("acp.logging.modules.{module} must be a string"))?;
+                config.modules.push((module.to_string(), level.to_string()));
+            }
+        }
+        if let Some(destinations) = section.get("destinations") {
+            let destinations = destinations
+                .as_array()
+                .ok_or("acp.logging.destinations must be an array")?;
+            config.destinations = Some(
+                destinations
+                    .iter()
+                    .map(|value| match value.as_str() {
+                        Some("stderr") => Ok(Destination::Stderr),
+                        Some("file") => Ok(Destination::File),
+                        _ => Err(format!("unknown acp.logging destination {value}")),
+                    })
+                    .collect::<std::result::Result<_, _>>()?,
+            );
+        }
+        Ok(config)
+    }
+
+    /// Read `$CODEX_HOME/config.toml`. Problems are reported on stderr, since
+    /// logging is not set up yet, and fall back to the defaults.
+    pub fn load(codex_home: &Path) -> Self {
+        let path = codex_home.join("config.toml");
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        Self::parse(&text).unwrap_or_else(|err| {
+            eprintln!("ignoring [acp.logging] in {}: {err}", path.display());
+            Self::default()
+        })
+    }
+
+    /// Whether logs go to `destination`.
+    pub fn logs_to(&self, destination: Destination) -> bool {
+        self.destinations
+            .as_ref()
+            .is_none_or(|destinations| destinations.contains(&destination))
+    }
+}
+
+/// Filter directives from the configured levels with RUST_LOG's on top:
+/// a bare level in RUST_LOG replaces `level`, and a directive for a module
+/// replaces that module's entry.
+pub fn merge_directives(config: &LoggingConfig, rust_log: Option<&str>) -> String {
+    fn is_level(directive: &str) -> bool {
+        ["off", "error", "warn", "info", "debug", "trace"]
+            .contains(&directive.to_ascii_lowercase().as_str())
+    }
+    let overrides: Vec<&str> = rust_log
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .collect();
+    let overridden: Vec<&str> = overrides
+        .iter()
+        .filter(|directive| !is_level(directive))
+        .filter_map(|directive| directive.split(['=', '[']).next())
+        .collect();
+
+    let mut directives = Vec::new();
+    if !overrides.iter().any(|directive| is_level(directive)) {
+        directives.push(config.level.clone().unwrap_or_else(|| "info".to_string()));
+    }
+    for (module, level) in &config.modules {
+        if !overridden.contains(&module.as_str()) {
+            directives.push(format!("{module}={level}"));
+        }
+    }
+    directives.extend(overrides.iter().map(|directive| directive.to_string()));
+    directives.join(",")
+}
+
+/// A non-negative number from an environment variable.
+fn env_number(name: &str) -> Option<u64> {
+    env::var(name).ok()?.trim().parse().ok()
+}
+
 /// Build a non-blocking writer for an explicit file path.
 /// Ensures parent directories exist. Appends to the file if it exists.
 fn non_blocking_writer_for_file<P: AsRef<Path>>(
     path: P,
+    max_bytes: u64,
+    max_files: usize,
 ) -> Result<(non_blocking::NonBlocking, WorkerGuard)> {
-    let path = path.as_ref();
-    if let Some(parent) = path.parent()
-        && !parent.as_os_str().is_empty()
-    {
-        fs::create_dir_all(parent)?;
-    }
-    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let file = SizeRotatingFile::open(path.as_ref(), max_bytes, max_files)?;
     Ok(tracing_appender::non_blocking(file))
 }
 
-/// Build a non-blocking writer with daily rotation in a directory.
+/// Build a non-blocking writer with time-based rotation in a directory.
 /// Ensures directory exists. Uses `file_name` for the rotated files.
-fn non_blocking_writer_for_daily<P: AsRef<Path>>(
+fn non_blocking_writer_for_dir<P: AsRef<Path>>(
     dir: P,
     file_name: &str,
+    rotation: Rotation,
 ) -> Result<(non_blocking::NonBlocking, WorkerGuard)> {
     let dir = dir.as_ref();
     if !dir.as_os_str().is_empty() {
         fs::create_dir_all(dir)?;
     }
-    let file_appender = tracing_appender::rolling::daily(dir, file_name);
+    let file_appender =
+        tracing_appender::rolling::RollingFileAppender::new(rotation, dir, file_name);
     Ok(tracing_appender::non_blocking(file_appender))
 }
+
+/// A log file that is rotated once it reaches a size.
+///
+/// Rotation renames `acp.log.1` to `acp.log.2` and so on, dropping the oldest
+/// beyond `max_files`, then renames `acp.log` to `acp.log.1` and starts a new
+/// file. Every step is a rename, so a crash mid-rotation never loses the
+/// current log, and the size is read back from disk when the file is reopened.
+/// Writes are not split: a write that would cross the limit goes to the next
+/// file, so log lines stay whole.
+pub struct SizeRotatingFile {
+    path: PathBuf,
+    /// Rotate before exceeding this many bytes; `0` never rotates.
+    max_bytes: u64,
+    /// Rotated files kept; `0` truncates the file instead.
+    max_files: usize,
+    file: Option<File>,
+    written: u64,
+}
+
+impl SizeRotatingFile {
+    /// Open `path` for appending, creating it and its parent directories.
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            max_bytes,
+            max_files,
+            file: Some(file),
+            written,
+        })
+    }
+
+    /// Path of the `index`th rotated file, e.g. `acp.log.1`.
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // Close the file first; Windows cannot rename an open file.
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        if self.max_files == 0 {
+            File::create(&self.path)?;
+        } else {
+            ignore_missing(fs::remove_file(self.rotated_path(self.max_files)))?;
+            for index in (1..self.max_files).rev() {
+                ignore_missing(fs::rename(
+                    self.rotated_path(index),
+                    self.rotated_path(index + 1),
+                ))?;
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+
+    fn file(&mut self) -> io::Result<&mut File> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?;
+                self.written = file.metadata()?.len();
+                file
+            }
+        };
+        Ok(self.file.insert(file))
+    }
+}
+
+fn ignore_missing(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+impl Write for SizeRotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rotation_failed = false;
+        if self.max_bytes > 0
+            && self.written > 0
+            && self.written + buf.len() as u64 > self.max_bytes
+            && let Err(err) = self.rotate()
+        {
+            // Keep logging to whatever file is there rather than dropping lines.
+            eprintln!("failed to rotate {}: {err}", self.path.display());
+            rotation_failed = true;
+        }
+        let written = self.file()?.write(buf)?;
+        self.written += written as u64;
+        if rotation_failed {
+            // Try again after another `max_bytes` rather than on every write.
+            self.written = 0;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,16 +1,19 @@
 use codex_acp::{CodexAgent, FsBridge, SessionModeLookup, agent};
 
-use agent_client_protocol::{AgentSideConnection, Client, Error};
+use agent_client_protocol::{
+    AgentSideConnection, Client, Error, ReleaseTerminalRequest, SessionId, TerminalId,
+    TerminalOutputRequest, WaitForTerminalExitRequest, WriteTextFileResponse,
+};
 use anyhow::{Result, bail};
 use codex_core::config::{self, Config, ConfigOverrides};
-use std::env;
+use std::{env, rc::Rc};
 use tokio::{
     io,
     sync::mpsc,
     task::{self, LocalSet},
 };
 use tokio_util::compat::{TokioAsyncReadCompatExt as _, TokioAsyncWriteCompatExt as _};
-use tracing::error;
+use tracing::{error, warn};
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
@@ -42,21 +45,47 @@ async fn main() -> Result<()> {
             vec![],
         ).await?;
         let profiles = config_toml.profiles;
-        let fs_bridge = FsBridge::start(client_tx.clone(), config.cwd.clone()).await?;
+        task::spawn_local(codex_acp::logging::watch_config(config.codex_home.clone()));
+        let fs_bridge =
+            FsBridge::start(client_tx.clone(), config.cwd.clone(), &config.codex_home).await?;
         let agent = CodexAgent::with_config(tx, client_tx, config, profiles, Some(fs_bridge));
         let session_modes = SessionModeLookup::from(&agent);
+        #[cfg(feature = "acp-compat")]
+        let compat = agent::ProtocolCompat::from(&agent);
+        agent.start_update_check();
+        agent.start_instance_registry();
         let (conn, handle_io) = AgentSideConnection::new(agent, outgoing, incoming, |fut| {
             task::spawn_local(fut);
         });
+        let conn = Rc::new(conn);
+        let plain_output = agent::PlainOutput::from_env();
+        let mut update_clock = agent::UpdateClock::from_env();
 
         task::spawn_local(async move {
             loop {
                 tokio::select! {
                     msg = rx.recv() => {
                         match msg {
-                            Some((session_notification, tx)) => {
-                                let result = conn.session_notification(session_notification).await;
-                                if let Err(e) = result { error!(error = ?e, "failed to send session notification"); break; }
+                            Some((mut session_notification, tx)) => {
+                                if let Some(clock) = update_clock.as_mut() {
+                                    clock.stamp(&mut session_notification);
+                                }
+                                #[cfg(feature = "acp-compat")]
+                                if !compat.notification(&mut session_notification) {
+                                    let _ = tx.send(());
+                                    continue;
+                                }
+                                if let Some(plain_output) = plain_output {
+                                    plain_output.notification(&mut session_notification);
+                                }
+                                #[cfg(feature = "strict-schema")]
+                                agent::report_notification(&session_notification);
+                                let result = conn.session_notification(session_notification.clone()).await;
+                                if let Err(e) = result {
+                                    // Keep it for the next session/load instead of losing it.
+                                    warn!(error = ?e, "failed to send session notification; buffering for backfill");
+                                    session_modes.buffer_undelivered(session_notification);
+                                }
                                 let _ = tx.send(());
                             }
                             None => break,
@@ -64,16 +93,42 @@ async fn main() -> Result<()> {
                     }
                     op = client_rx.recv() => {
                         match op {
-                            Some(agent::ClientOp::RequestPermission { session_id: _, request: req, response_tx: tx }) => {
-                                let res = conn.request_permission(req).await;
-                                let _ = tx.send(res);
+                            Some(agent::ClientOp::RequestPermission { session_id: _, request: mut req, response_tx: tx }) => {
+                                // Requests from the FS bridge carry the FS session id.
+                                match session_modes.resolve_acp_session_id(&req.session_id) {
+                                    Some(resolved_id) => {
+                                        req.session_id = resolved_id;
+                                        if let Some(plain_output) = plain_output {
+                                            plain_output.permission_request(&mut req);
+                                        }
+                                        #[cfg(feature = "acp-compat")]
+                                        compat.permission_request(&mut req);
+                                        #[cfg(feature = "strict-schema")]
+                                        agent::report_permission_request(&req);
+                                        // Client calls run on their own tasks so one session's
+                                        // pending request never stalls another session's turn.
+                                        let conn = conn.clone();
+                                        task::spawn_local(async move {
+                                            let res = conn.request_permission(req).await;
+                                            let _ = tx.send(res);
+                                        });
+                                    }
+                                    None => {
+                                        let err = Error::invalid_params()
+                                            .with_data("unknown session for request_permission");
+                                        let _ = tx.send(Err(err));
+                                    }
+                                }
                             }
                             Some(agent::ClientOp::ReadTextFile { session_id: _, request: mut req, response_tx: tx }) => {
                                 match session_modes.resolve_acp_session_id(&req.session_id) {
                                     Some(resolved_id) => {
                                         req.session_id = resolved_id;
-                                        let res = conn.read_text_file(req).await;
-                                        let _ = tx.send(res);
+                                        let conn = conn.clone();
+                                        task::spawn_local(async move {
+                                            let res = conn.read_text_file(req).await;
+                                            let _ = tx.send(res);
+                                        });
                                     }
                                     None => {
                                         let err = Error::invalid_params()
@@ -90,9 +145,15 @@ async fn main() -> Result<()> {
                                             let err = Error::invalid_params()
                                                 .with_data("write_text_file is disabled while session mode is read-only");
                                             let _ = tx.send(Err(err));
+                                        } else if session_modes.is_dry_run(&resolved_id) {
+                                            // Simulated: the tool call still reports its diff.
+                                            let _ = tx.send(Ok(WriteTextFileResponse::default()));
                                         } else {
-                                            let res = conn.write_text_file(req).await;
-                                            let _ = tx.send(res);
+                                            let conn = conn.clone();
+                                            task::spawn_local(async move {
+                                                let res = conn.write_text_file(req).await;
+                                                let _ = tx.send(res);
+                                            });
                                         }
                                     }
                                     None => {
@@ -102,6 +163,64 @@ async fn main() -> Result<()> {
                                     }
                                 }
                             }
+                            Some(agent::ClientOp::CreateTerminal { session_id: _, request: mut req, response_tx: tx }) => {
+                                match session_modes.resolve_acp_session_id(&req.session_id) {
+                                    Some(resolved_id) => {
+                                        req.session_id = resolved_id;
+                                        let conn = conn.clone();
+                                        task::spawn_local(async move {
+                                            let res = conn.create_terminal(req).await;
+                                            let _ = tx.send(res);
+                                        });
+                                    }
+                                    None => {
+                                        let err = Error::invalid_params()
+                                            .with_data("unknown session for create_terminal");
+                                        let _ = tx.send(Err(err));
+                                    }
+                                }
+                            }
+                            Some(agent::ClientOp::WaitForTerminal { session_id, terminal_id, response_tx: tx }) => {
+                                // Interactive commands can run for minutes; don't block the dispatch loop.
+                                let conn = conn.clone();
+                                task::spawn_local(async move {
+                                    let res = wait_for_terminal(&conn, session_id, terminal_id).await;
+                                    let _ = tx.send(res);
+                                });
+                            }
+                            Some(agent::ClientOp::GetDiagnostics { session_id, path, response_tx: tx }) => {
+                                let res = session_modes
+                                    .diagnostics(&session_id, path.as_deref())
+                                    .ok_or_else(|| Error::invalid_params().with_data("unknown session for get_diagnostics"))
+                                    .and_then(|files| serde_json::to_string(&files).map_err(Error::into_internal_error));
+                                let _ = tx.send(res);
+                            }
+                            Some(agent::ClientOp::GetCommandHistory { session_id, response_tx: tx }) => {
+                                let res = session_modes
+                                    .command_history(&session_id)
+                                    .ok_or_else(|| Error::invalid_params().with_data("unknown session for command_history"));
+                                let _ = tx.send(res);
+                            }
+                            Some(agent::ClientOp::GetReadOnly { session_id, response_tx: tx }) => {
+                                let res = session_modes
+                                    .resolve_acp_session_id(&session_id)
+                                    .map(|resolved_id| session_modes.is_read_only(&resolved_id))
+                                    .ok_or_else(|| Error::invalid_params().with_data("unknown session for read_only"));
+                                let _ = tx.send(res);
+                            }
+                            Some(agent::ClientOp::GetDryRun { session_id, response_tx: tx }) => {
+                                let res = session_modes
+                                    .resolve_acp_session_id(&session_id)
+                                    .map(|resolved_id| session_modes.is_dry_run(&resolved_id))
+                                    .ok_or_else(|| Error::invalid_params().with_data("unknown session for dry_run"));
+                                let _ = tx.send(res);
+                            }
+                            Some(agent::ClientOp::RecordAudit { session_id, entry }) => {
+                                session_modes.record_audit(&session_id, entry);
+                            }
+                            Some(agent::ClientOp::RecordUndo { session_id, files }) => {
+                                session_modes.record_undo(&session_id, files);
+                            }
                             None => break,
                         }
                     }
@@ -115,3 +234,41 @@ async fn main() -> Result<()> {
         }
     }).await
 }
+
+/// Wait for a client terminal to exit, read its output, and release it.
+async fn wait_for_terminal(
+    conn: &AgentSideConnection,
+    session_id: SessionId,
+    terminal_id: TerminalId,
+) -> Result<agent::TerminalRun, Error> {
+    let exit = conn
+        .wait_for_terminal_exit(WaitForTerminalExitRequest {
+            session_id: session_id.clone(),
+            terminal_id: terminal_id.clone(),
+            meta: None,
+        })
+        .await?;
+    let output = conn
+        .terminal_output(TerminalOutputRequest {
+            session_id: session_id.clone(),
+            terminal_id: terminal_id.clone(),
+            meta: None,
+        })
+        .await?;
+    if let Err(err) = conn
+        .release_terminal(ReleaseTerminalRequest {
+            session_id,
+            terminal_id,
+            meta: None,
+        })
+        .await
+    {
+        error!(error = ?err, "failed to release terminal");
+    }
+    Ok(agent::TerminalRun {
+        output: output.output,
+        truncated: output.truncated,
+        exit_code: exit.exit_status.exit_code,
+        signal: exit.exit_status.signal,
+    })
+}